@@ -0,0 +1,11 @@
+-- Previous organization names kept after a rename so existing image references keep working
+CREATE TABLE organization_aliases (
+    id BIGSERIAL PRIMARY KEY,
+    organization_id BIGINT NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    old_name VARCHAR(255) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+-- Index for alias lookups by organization
+CREATE INDEX idx_organization_aliases_organization_id ON organization_aliases(organization_id);
@@ -0,0 +1,6 @@
+-- Platform of the image a manifest describes, read from its config blob
+ALTER TABLE manifests ADD COLUMN architecture VARCHAR(64);
+ALTER TABLE manifests ADD COLUMN os VARCHAR(64);
+
+-- Index for multi-arch resolution and platform filtering
+CREATE INDEX idx_manifests_platform ON manifests(repository_id, os, architecture);
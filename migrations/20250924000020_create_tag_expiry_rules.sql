@@ -0,0 +1,13 @@
+-- Per-repository rules that automatically delete tags matching a pattern after a TTL
+CREATE TABLE tag_expiry_rules (
+    id BIGSERIAL PRIMARY KEY,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    pattern VARCHAR(255) NOT NULL,
+    ttl_days INTEGER NOT NULL CHECK (ttl_days > 0),
+    created_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(repository_id, pattern)
+);
+
+CREATE INDEX idx_tag_expiry_rules_repository_id ON tag_expiry_rules(repository_id);
@@ -0,0 +1,4 @@
+-- Time-boxed organization access: memberships past expires_at no longer grant access
+ALTER TABLE organization_members ADD COLUMN expires_at TIMESTAMPTZ;
+
+CREATE INDEX idx_organization_members_expires_at ON organization_members(expires_at) WHERE expires_at IS NOT NULL;
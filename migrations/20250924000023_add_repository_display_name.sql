@@ -0,0 +1,17 @@
+-- Optional human-friendly repository name. Uniqueness per organization is a
+-- deployment policy (ENFORCE_UNIQUE_DISPLAY_NAMES): repositories created
+-- while it is on claim their display name, and the partial unique index over
+-- claimed names turns concurrent creates of the same name into a conflict.
+-- Names from before enforcement are left alone, so turning the policy on
+-- never fails on existing data.
+ALTER TABLE repositories ADD COLUMN display_name VARCHAR(255);
+ALTER TABLE repositories ADD COLUMN display_name_claimed BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE UNIQUE INDEX idx_repositories_unique_display_name
+    ON repositories (organization_id, lower(btrim(display_name)))
+    WHERE display_name_claimed;
+
+-- Lookup of a name against every repository of an organization, claimed or not
+CREATE INDEX idx_repositories_display_name_key
+    ON repositories (organization_id, lower(btrim(display_name)))
+    WHERE display_name IS NOT NULL;
@@ -0,0 +1,10 @@
+-- Persist chunked upload state so an upload can be resumed on any instance,
+-- including after a restart. Parts are the multipart parts already written,
+-- as [{"number", "etag", "size"}]; pending bytes are held in storage.
+ALTER TABLE blob_uploads
+    ADD COLUMN repository_name VARCHAR(255),
+    ADD COLUMN multipart_upload_id TEXT,
+    ADD COLUMN parts JSONB NOT NULL DEFAULT '[]',
+    ADD COLUMN pending_bytes BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD COLUMN completed_at TIMESTAMPTZ;
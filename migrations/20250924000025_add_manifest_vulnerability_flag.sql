@@ -0,0 +1,8 @@
+-- Scan results reported by an external scanner through POST /admin/scan-results.
+-- Pulls of manifests flagged with critical vulnerabilities are refused when
+-- BLOCK_VULNERABLE_PULLS is set; an organization's block_vulnerable_pulls,
+-- when not NULL, takes precedence over that setting.
+ALTER TABLE manifests ADD COLUMN critical_vulnerabilities BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE manifests ADD COLUMN vulnerability_reason TEXT;
+ALTER TABLE manifests ADD COLUMN scanned_at TIMESTAMPTZ;
+ALTER TABLE organizations ADD COLUMN block_vulnerable_pulls BOOLEAN;
@@ -0,0 +1,3 @@
+-- Opt-in tag policy: when set, manifests may only be pushed under semantic
+-- version tags (or `latest`).
+ALTER TABLE repositories ADD COLUMN require_semver BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Successful manifest pulls, counted so the most pulled manifests can be
+-- loaded into the cache at startup (CACHE_WARMUP)
+ALTER TABLE manifests ADD COLUMN pull_count BIGINT NOT NULL DEFAULT 0;
+CREATE INDEX idx_manifests_pull_count ON manifests (pull_count DESC);
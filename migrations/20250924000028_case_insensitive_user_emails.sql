@@ -0,0 +1,32 @@
+-- Emails are stored lowercased and compared case-insensitively, so
+-- `User@x.com` and `user@x.com` are one account. Logins look accounts up by
+-- LOWER(email) and expect at most one, so every deployment needs
+-- idx_users_email_lower. Addresses are lowercased where that merges nothing;
+-- accounts still sharing an address must be resolved by hand (change or
+-- remove all but one) before this migration can run.
+UPDATE users u SET email = LOWER(TRIM(u.email))
+WHERE u.email <> LOWER(TRIM(u.email))
+  AND NOT EXISTS (
+      SELECT 1 FROM users other
+      WHERE other.id <> u.id AND LOWER(TRIM(other.email)) = LOWER(TRIM(u.email))
+  );
+
+DO $$
+DECLARE
+    conflicts TEXT;
+BEGIN
+    SELECT string_agg(ids, '; ') INTO conflicts
+    FROM (
+        SELECT string_agg(id::TEXT, ', ' ORDER BY id) AS ids
+        FROM users
+        GROUP BY LOWER(email)
+        HAVING COUNT(*) > 1
+    ) duplicates;
+
+    IF conflicts IS NOT NULL THEN
+        RAISE EXCEPTION 'Users share email addresses differing only in case (user IDs: %); give each a distinct address or remove the extra accounts, then restart', conflicts;
+    END IF;
+END
+$$;
+
+CREATE UNIQUE INDEX idx_users_email_lower ON users (LOWER(email));
@@ -0,0 +1,3 @@
+-- Seat limit per organization, e.g. from its billing tier. NULL falls back
+-- to ORG_MAX_MEMBERS; with neither set membership is unlimited.
+ALTER TABLE organizations ADD COLUMN max_members INTEGER CHECK (max_members >= 0);
@@ -0,0 +1,4 @@
+-- Visibility of repositories created in an organization without an explicit
+-- is_public, through the API or by a first push. NULL follows
+-- DEFAULT_REPO_VISIBILITY.
+ALTER TABLE organizations ADD COLUMN default_repo_public BOOLEAN;
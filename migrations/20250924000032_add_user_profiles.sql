@@ -0,0 +1,5 @@
+-- Self-service profile fields, edited through PUT /api/v1/auth/me.
+-- avatar_url is set when an avatar is uploaded through PUT /api/v1/auth/me/avatar.
+ALTER TABLE users ADD COLUMN display_name VARCHAR(100);
+ALTER TABLE users ADD COLUMN bio TEXT;
+ALTER TABLE users ADD COLUMN avatar_url TEXT;
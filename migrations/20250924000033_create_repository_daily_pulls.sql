@@ -0,0 +1,10 @@
+-- Successful manifest pulls per repository and day, so public repositories
+-- can be ranked by pulls over POPULAR_WINDOW_DAYS (GET /v2/_popular)
+CREATE TABLE repository_daily_pulls (
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    pulls BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (repository_id, day)
+);
+
+CREATE INDEX idx_repository_daily_pulls_day ON repository_daily_pulls (day);
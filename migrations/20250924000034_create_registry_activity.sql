@@ -0,0 +1,16 @@
+-- Manifest pulls and pushes counted per repository, day and actor, for
+-- organization analytics; analytics only ever read daily totals
+CREATE TABLE registry_activity_daily (
+    organization_id BIGINT NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    repository_id BIGINT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('pull', 'push')),
+    -- User ID, or org_<id> for organization credentials; NULL when anonymous
+    actor VARCHAR(255),
+    count BIGINT NOT NULL
+);
+
+-- Anonymous activity shares one row per repository, day and action
+CREATE UNIQUE INDEX idx_registry_activity_daily_key
+    ON registry_activity_daily (repository_id, day, action, (COALESCE(actor, '')));
+CREATE INDEX idx_registry_activity_daily_organization_day ON registry_activity_daily (organization_id, day);
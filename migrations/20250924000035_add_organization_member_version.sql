@@ -0,0 +1,3 @@
+-- Incremented on every member update, so an update based on an outdated
+-- copy of the member can be refused instead of silently overwriting
+ALTER TABLE organization_members ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
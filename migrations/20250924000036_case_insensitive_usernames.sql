@@ -0,0 +1,32 @@
+-- Usernames are stored lowercased and compared case-insensitively, so
+-- `Alice` and `alice` are one account. Registration and login look accounts
+-- up by LOWER(username) and expect at most one, so every deployment needs
+-- idx_users_username_lower. Usernames are lowercased where that merges
+-- nothing; accounts still sharing a name must be resolved by hand (rename or
+-- remove all but one) before this migration can run.
+UPDATE users u SET username = LOWER(TRIM(u.username))
+WHERE u.username <> LOWER(TRIM(u.username))
+  AND NOT EXISTS (
+      SELECT 1 FROM users other
+      WHERE other.id <> u.id AND LOWER(TRIM(other.username)) = LOWER(TRIM(u.username))
+  );
+
+DO $$
+DECLARE
+    conflicts TEXT;
+BEGIN
+    SELECT string_agg(ids, '; ') INTO conflicts
+    FROM (
+        SELECT string_agg(id::TEXT, ', ' ORDER BY id) AS ids
+        FROM users
+        GROUP BY LOWER(username)
+        HAVING COUNT(*) > 1
+    ) duplicates;
+
+    IF conflicts IS NOT NULL THEN
+        RAISE EXCEPTION 'Users share usernames differing only in case (user IDs: %); rename or remove the extra accounts, then restart', conflicts;
+    END IF;
+END
+$$;
+
+CREATE UNIQUE INDEX idx_users_username_lower ON users (LOWER(username));
@@ -0,0 +1,3 @@
+-- Limit on the total layer size of images pushed to an organization. NULL
+-- falls back to MAX_IMAGE_BYTES; with neither set image size is unlimited.
+ALTER TABLE organizations ADD COLUMN max_image_bytes BIGINT CHECK (max_image_bytes > 0);
@@ -0,0 +1,32 @@
+-- Blobs and child manifests each manifest references, so checking whether a
+-- blob is still in use is an index lookup instead of a scan of manifest content
+CREATE TABLE manifest_blob_references (
+    manifest_id BIGINT NOT NULL REFERENCES manifests(id) ON DELETE CASCADE,
+    digest VARCHAR(255) NOT NULL,
+    PRIMARY KEY (manifest_id, digest)
+);
+
+CREATE INDEX idx_manifest_blob_references_digest ON manifest_blob_references (digest);
+CREATE INDEX idx_manifests_digest ON manifests (digest);
+
+-- Manifests pushed before this table existed are indexed from storage at startup
+ALTER TABLE manifests ADD COLUMN blob_references_indexed BOOLEAN NOT NULL DEFAULT FALSE;
+CREATE INDEX idx_manifests_blob_references_pending ON manifests (id) WHERE NOT blob_references_indexed;
+
+-- Manifests whose content was kept in the database can be indexed right away
+INSERT INTO manifest_blob_references (manifest_id, digest)
+SELECT m.id, refs.digest
+FROM manifests m
+CROSS JOIN LATERAL (
+    SELECT m.content::jsonb -> 'config' ->> 'digest' AS digest
+    UNION
+    SELECT descriptor ->> 'digest'
+    FROM jsonb_array_elements(COALESCE(m.content::jsonb -> 'layers', '[]'::jsonb)) AS descriptor
+    UNION
+    SELECT descriptor ->> 'digest'
+    FROM jsonb_array_elements(COALESCE(m.content::jsonb -> 'manifests', '[]'::jsonb)) AS descriptor
+) refs
+WHERE m.content IS NOT NULL AND refs.digest IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+UPDATE manifests SET blob_references_indexed = TRUE WHERE content IS NOT NULL;
@@ -1,55 +1,198 @@
 use axum::http::{StatusCode, HeaderMap};
 use axum_extra::headers::{authorization::Bearer, Authorization};
 use axum_extra::TypedHeader;
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use crate::cache::RegistryCache;
+use crate::db::Timed;
+use crate::config::settings::AuthSettings;
 use crate::models::api_key::ApiKey;
 use chrono::{DateTime, Utc};
 use sha2::{Sha256, Digest};
 use rand::{thread_rng, Rng};
 use rand::distributions::Alphanumeric;
+use secrecy::ExposeSecret;
+
+/// Algorithm every JWT the registry issues is signed with
+pub const JWT_ALGORITHM: Algorithm = Algorithm::HS256;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String, // user id
     pub exp: usize,  // expiration time
+    #[serde(default)]
+    pub iat: Option<usize>, // issued at
+    #[serde(default)]
+    pub sid: Option<i64>, // session id
+}
+
+/// Record a sign-in for `user_id` and return the session id to put in its
+/// token as `sid`
+pub async fn create_session(
+    pool: &sqlx::PgPool,
+    user_id: i64,
+    user_agent: Option<&str>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        "INSERT INTO user_sessions (user_id, user_agent, expires_at)
+         VALUES ($1, $2, NOW() + INTERVAL '24 hours')
+         RETURNING id"
+    )
+    .bind(user_id)
+    .bind(crate::models::session::session_label(user_agent))
+    .fetch_one(pool)
+    .timed("create_session")
+    .await
+}
+
+/// `create_session` for a sign-in, except in read-only mode, where no row is
+/// written and the token goes without a `sid`
+pub async fn start_session(
+    pool: &sqlx::PgPool,
+    user_id: i64,
+    user_agent: Option<&str>,
+) -> Result<Option<i64>, sqlx::Error> {
+    if crate::read_only::writes_paused() {
+        return Ok(None);
+    }
+    create_session(pool, user_id, user_agent).await.map(Some)
+}
+
+/// Extend session `sid` of `user_id` for a refreshed token, returning
+/// whether it was still unrevoked. In read-only mode the session is only
+/// checked, not extended.
+pub async fn extend_session(pool: &sqlx::PgPool, sid: i64, user_id: i64) -> Result<bool, sqlx::Error> {
+    if crate::read_only::writes_paused() {
+        return session_active(pool, sid, user_id).await;
+    }
+    let extended = sqlx::query(
+        "UPDATE user_sessions SET last_used_at = NOW(), expires_at = NOW() + INTERVAL '24 hours'
+         WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(sid)
+    .bind(user_id)
+    .execute(pool)
+    .timed("extend_session")
+    .await?;
+    Ok(extended.rows_affected() > 0)
+}
+
+/// How stale a session's `last_used_at` may get before a request updates it
+pub const SESSION_TOUCH_INTERVAL_SECS: i64 = 60;
+
+/// Whether session `sid` of `user_id` is neither revoked nor expired. Its
+/// `last_used_at` is brought up to date at most once a
+/// `SESSION_TOUCH_INTERVAL_SECS`, so most requests only read, and never in
+/// read-only mode.
+pub async fn session_active(pool: &sqlx::PgPool, sid: i64, user_id: i64) -> Result<bool, sqlx::Error> {
+    let stale = sqlx::query_scalar::<_, bool>(
+        "SELECT last_used_at < NOW() - make_interval(secs => $3) FROM user_sessions
+         WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()"
+    )
+    .bind(sid)
+    .bind(user_id)
+    .bind(SESSION_TOUCH_INTERVAL_SECS as f64)
+    .fetch_optional(pool)
+    .timed("session_active: SELECT user_sessions")
+    .await?;
+    match stale {
+        None => Ok(false),
+        Some(stale) => {
+            if stale && !crate::read_only::writes_paused() {
+                sqlx::query("UPDATE user_sessions SET last_used_at = NOW() WHERE id = $1")
+                    .bind(sid)
+                    .execute(pool)
+                    .timed("session_active: UPDATE user_sessions")
+                    .await?;
+            }
+            Ok(true)
+        }
+    }
+}
+
+/// User id of a verified token, rejecting tokens whose session has been
+/// revoked. Tokens issued before sessions were recorded carry no `sid` and
+/// are accepted until they expire.
+pub async fn claims_user_id(claims: &Claims, pool: &sqlx::PgPool) -> Result<i64, StatusCode> {
+    let user_id = claims.sub.parse::<i64>().map_err(|_| StatusCode::UNAUTHORIZED)?;
+    if let Some(sid) = claims.sid {
+        let active = session_active(pool, sid, user_id).await.map_err(|e| {
+            tracing::error!("Database error checking session {}: {}", sid, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        if !active {
+            tracing::debug!("Session {} of user {} is no longer active", sid, user_id);
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    }
+    crate::auth_events::note_user(user_id);
+    Ok(user_id)
 }
 
-pub fn verify_token(token: &str, secret: &[u8]) -> Result<Claims, StatusCode> {
+/// Whether a token issued at `iat` is younger than `max_age_secs` at `now`.
+/// Without a limit every token passes; with one, tokens that do not say
+/// when they were issued fail.
+pub fn token_within_max_age(iat: Option<usize>, max_age_secs: Option<u64>, now: i64) -> bool {
+    match (max_age_secs, iat) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(max_age), Some(iat)) => now.saturating_sub(iat as i64) <= max_age as i64,
+    }
+}
+
+/// Claims of a token signed with `secret`, unexpired and issued at most
+/// `max_age_secs` ago (`JWT_MAX_AGE_SECONDS`)
+pub fn verify_token(token: &str, secret: &[u8], max_age_secs: Option<u64>) -> Result<Claims, StatusCode> {
     tracing::debug!("Verifying token: {}", token);
 
     let token_data = decode::<Claims>(
         token,
         &DecodingKey::from_secret(secret),
-        &Validation::default()
+        &Validation::new(JWT_ALGORITHM)
     ).map_err(|e| {
         tracing::error!("Token verification error: {:?}", e);
         StatusCode::UNAUTHORIZED
     })?;
 
+    if !token_within_max_age(token_data.claims.iat, max_age_secs, chrono::Utc::now().timestamp()) {
+        tracing::debug!("Token of user ID {} is older than JWT_MAX_AGE_SECONDS", token_data.claims.sub);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
     tracing::debug!("Token verified successfully for user ID: {}", token_data.claims.sub);
     Ok(token_data.claims)
 }
 
+/// `verify_token` with the secret and maximum age `settings` configure
+pub fn verify_token_with_settings(token: &str, settings: &AuthSettings) -> Result<Claims, StatusCode> {
+    verify_token(token, settings.jwt_secret.expose_secret().as_bytes(), settings.jwt_max_age_seconds)
+}
+
 /// Verify token with cache support
 pub async fn verify_token_cached(
     token: &str, 
-    secret: &[u8], 
+    settings: &AuthSettings, 
     cache: &Arc<RegistryCache>
 ) -> Result<Claims, StatusCode> {
-    // First check cache
-    if let Some(auth_entry) = cache.get_auth_token(token).await {
+    // First check cache; cached entries do not keep `iat`, so with a max
+    // age every token is verified
+    let cached = match settings.jwt_max_age_seconds {
+        Some(_) => None,
+        None => cache.get_auth_token(token).await,
+    };
+    if let Some(auth_entry) = cached {
         tracing::debug!("Token found in cache for user ID: {}", auth_entry.user_id);
         return Ok(Claims {
             sub: auth_entry.user_id.to_string(),
             exp: (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp() as usize, // Use current time + 24h
+            iat: None,
+            sid: None,
         });
     }
 
     // If not in cache, verify normally
-    let claims = verify_token(token, secret)?;
+    let claims = verify_token_with_settings(token, settings)?;
     
     // Cache the verified token
     if let Ok(user_id) = claims.sub.parse::<i64>() {
@@ -73,7 +216,7 @@ pub async fn verify_token_cached(
 pub async fn extract_user_id_dual(
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     headers: &HeaderMap,
-    secret: &[u8],
+    settings: &AuthSettings,
     pool: &sqlx::PgPool,
     cache: Option<&Arc<RegistryCache>>,
 ) -> Result<i64, StatusCode> {
@@ -85,36 +228,53 @@ pub async fn extract_user_id_dual(
     extract_user_id_dual_auth(
         auth, 
         api_key_header, 
-        secret, 
+        settings, 
         pool, 
         cache
     ).await
 }
 
+/// User id of a bearer JWT, rejecting tokens whose session has been revoked
 pub async fn extract_user_id(
     auth: Option<TypedHeader<Authorization<Bearer>>>, 
-    secret: &[u8],
+    settings: &AuthSettings,
+    pool: &sqlx::PgPool,
 ) -> Result<i64, StatusCode> {
     let auth = auth.ok_or(StatusCode::UNAUTHORIZED)?;
-    let claims = verify_token(auth.token(), secret)?;
-    claims
-        .sub
-        .parse::<i64>()
-        .map_err(|_| StatusCode::UNAUTHORIZED)
+    let claims = verify_token_with_settings(auth.token(), settings)?;
+    claims_user_id(&claims, pool).await
+}
+
+/// User id of the bearer JWT in the `Authorization` header of `headers`,
+/// rejecting tokens whose session has been revoked
+pub async fn bearer_user_id(
+    headers: &HeaderMap,
+    settings: &AuthSettings,
+    pool: &sqlx::PgPool,
+) -> Result<i64, StatusCode> {
+    let token = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let claims = verify_token_with_settings(token, settings)?;
+    claims_user_id(&claims, pool).await
 }
 
 /// Extract user ID with cache support
 pub async fn extract_user_id_cached(
     auth: Option<TypedHeader<Authorization<Bearer>>>, 
-    secret: &[u8],
+    settings: &AuthSettings,
     cache: &Arc<RegistryCache>,
 ) -> Result<i64, StatusCode> {
     let auth = auth.ok_or(StatusCode::UNAUTHORIZED)?;
-    let claims = verify_token_cached(auth.token(), secret, cache).await?;
-    claims
+    let claims = verify_token_cached(auth.token(), settings, cache).await?;
+    let user_id = claims
         .sub
         .parse::<i64>()
-        .map_err(|_| StatusCode::UNAUTHORIZED)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    crate::auth_events::note_user(user_id);
+    Ok(user_id)
 }
 
 /// Check user permissions with cache support
@@ -147,11 +307,13 @@ pub async fn check_permission_cached(
             JOIN organizations o ON r.organization_id = o.id
             JOIN organization_members om ON r.organization_id = om.organization_id
             WHERE CONCAT(o.name, '/', r.name) = $1 AND om.user_id = $2
+              AND (om.expires_at IS NULL OR om.expires_at > NOW())
         )"
     )
     .bind(repository)
     .bind(user_id)
     .fetch_one(pool)
+    .timed("check_permission_cached")
     .await
     .map_err(|e| {
         tracing::error!("Database permission check failed: {}", e);
@@ -200,6 +362,48 @@ pub fn generate_api_key() -> String {
     format!("ak_{}", random_part)
 }
 
+/// Header administrative callers put the configured admin token in
+pub const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+/// Whether the request carries the configured admin token. Always false
+/// when no admin token is configured.
+pub fn is_admin_request(headers: &HeaderMap, settings: &AuthSettings) -> bool {
+    let expected = match &settings.admin_token {
+        Some(expected) => expected,
+        None => return false,
+    };
+
+    headers
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(|provided| hash_api_key(provided) == hash_api_key(expected.expose_secret()))
+        .unwrap_or(false)
+}
+
+/// Whether a new account may be created: anyone when self-registration is
+/// enabled, otherwise only administrative callers.
+pub fn registration_allowed(headers: &HeaderMap, settings: &AuthSettings) -> bool {
+    settings.allow_self_registration || is_admin_request(headers, settings)
+}
+
+/// Header internal callers put the configured introspection secret in
+pub const INTROSPECTION_SECRET_HEADER: &str = "x-introspection-secret";
+
+/// Whether the request may introspect tokens: internal callers that present
+/// the configured introspection secret, and administrative callers.
+pub fn introspection_allowed(headers: &HeaderMap, settings: &AuthSettings) -> bool {
+    let has_secret = match &settings.introspection_secret {
+        Some(expected) => headers
+            .get(INTROSPECTION_SECRET_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map(|provided| hash_api_key(provided) == hash_api_key(expected.expose_secret()))
+            .unwrap_or(false),
+        None => false,
+    };
+
+    has_secret || is_admin_request(headers, settings)
+}
+
 /// Hash an API key using SHA-256
 pub fn hash_api_key(api_key: &str) -> String {
     let mut hasher = Sha256::new();
@@ -219,17 +423,21 @@ pub async fn verify_api_key(
     if let Some(cache) = cache {
         if let Some(cached_info) = cache.get_api_key_info(&key_hash).await {
             // Update last_used_at in background (fire and forget)
-            let pool_clone = pool.clone();
-            let key_hash_clone = key_hash.clone();
-            tokio::spawn(async move {
-                let _ = sqlx::query!(
-                    "UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP WHERE key_hash = $1",
-                    key_hash_clone
-                )
-                .execute(&pool_clone)
-                .await;
-            });
+            if !crate::read_only::writes_paused() {
+                let pool_clone = pool.clone();
+                let key_hash_clone = key_hash.clone();
+                tokio::spawn(async move {
+                    let _ = sqlx::query!(
+                        "UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP WHERE key_hash = $1",
+                        key_hash_clone
+                    )
+                    .execute(&pool_clone)
+                    .timed("verify_api_key: UPDATE api_keys")
+                    .await;
+                });
+            }
             
+            crate::auth_events::note_user(cached_info.user_id);
             return Ok(cached_info.user_id);
         }
     }
@@ -245,6 +453,7 @@ pub async fn verify_api_key(
         key_hash
     )
     .fetch_optional(pool)
+    .timed("verify_api_key: SELECT api_keys")
     .await
     .map_err(|e| {
         tracing::error!("Database error verifying API key: {}", e);
@@ -262,13 +471,16 @@ pub async fn verify_api_key(
         }
     }
     
-    // Update last_used_at
-    let _ = sqlx::query!(
-        "UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP WHERE id = $1",
-        api_key_record.id
-    )
-    .execute(pool)
-    .await;
+    // Update last_used_at, unless in read-only mode
+    if !crate::read_only::writes_paused() {
+        let _ = sqlx::query!(
+            "UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP WHERE id = $1",
+            api_key_record.id
+        )
+        .execute(pool)
+        .timed("verify_api_key: UPDATE api_keys")
+        .await;
+    }
     
     // Cache the result if cache is available
     if let Some(cache) = cache {
@@ -279,6 +491,7 @@ pub async fn verify_api_key(
         let _ = cache.cache_api_key_info(&key_hash, cache_info).await;
     }
     
+    crate::auth_events::note_user(api_key_record.user_id);
     Ok(api_key_record.user_id)
 }
 
@@ -286,7 +499,7 @@ pub async fn verify_api_key(
 pub async fn extract_user_id_dual_auth(
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     api_key_header: Option<&str>, // X-API-Key header value
-    secret: &[u8],
+    settings: &AuthSettings,
     pool: &sqlx::PgPool,
     cache: Option<&Arc<RegistryCache>>,
 ) -> Result<i64, StatusCode> {
@@ -308,17 +521,11 @@ pub async fn extract_user_id_dual_auth(
             return verify_api_key(token, pool, cache).await;
         }
         
-        // Otherwise treat as JWT
+        // Otherwise treat as JWT. The cached claims do not carry the
+        // session, so the token is verified directly and its session checked.
         tracing::debug!("Attempting JWT authentication");
-        if let Some(cache) = cache {
-            let claims = verify_token_cached(token, secret, cache).await?;
-            let user_id = claims.sub.parse::<i64>().map_err(|_| StatusCode::UNAUTHORIZED)?;
-            return Ok(user_id);
-        } else {
-            let claims = verify_token(token, secret)?;
-            let user_id = claims.sub.parse::<i64>().map_err(|_| StatusCode::UNAUTHORIZED)?;
-            return Ok(user_id);
-        }
+        let claims = verify_token_with_settings(token, settings)?;
+        return claims_user_id(&claims, pool).await;
     }
     
     Err(StatusCode::UNAUTHORIZED)
@@ -0,0 +1,199 @@
+// Authentication event stream
+//
+// Logins, token issue and revocation, and permission denials are logged as
+// one structured line each under the `auth_events` target, with the user ID
+// when known, the client IP and the correlation ID, for security tooling to
+// follow. With AUTH_EVENT_DESTINATION=database each is also written to
+// `audit_logs` as `auth.<event>`.
+//
+// `auth_event_middleware` keeps the client IP and authenticated user for the
+// request, and reports a 403 no handler explained as a permission denial, so
+// handlers only say what happened.
+use std::cell::Cell;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::correlation;
+use crate::handlers::audit::record_audit_event;
+use crate::login_throttle::client_ip;
+use crate::AppState;
+
+/// Tracing target of the event lines, for filtering them into their own stream
+pub const AUTH_EVENT_TARGET: &str = "auth_events";
+
+/// Where authentication events go besides the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthEventDestination {
+    /// Log lines only
+    #[default]
+    Log,
+    /// Log lines and `audit_logs` rows
+    Database,
+}
+
+impl FromStr for AuthEventDestination {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "log" => Ok(AuthEventDestination::Log),
+            "database" | "db" => Ok(AuthEventDestination::Database),
+            other => Err(format!("Unknown auth event destination '{}', expected log or database", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthEventKind {
+    LoginSuccess,
+    LoginFailure,
+    TokenIssued,
+    TokenRevoked,
+    PermissionDenied,
+}
+
+impl AuthEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthEventKind::LoginSuccess => "login_success",
+            AuthEventKind::LoginFailure => "login_failure",
+            AuthEventKind::TokenIssued => "token_issued",
+            AuthEventKind::TokenRevoked => "token_revoked",
+            AuthEventKind::PermissionDenied => "permission_denied",
+        }
+    }
+}
+
+/// One authentication event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthEvent {
+    pub kind: AuthEventKind,
+    pub user_id: Option<i64>,
+    pub ip: Option<String>,
+    pub correlation_id: String,
+    /// Why a login failed, which token changed, or what was denied
+    pub reason: Option<String>,
+}
+
+impl AuthEvent {
+    /// Event of `kind` in the current request, with its client IP,
+    /// authenticated user and correlation ID
+    pub fn new(kind: AuthEventKind) -> Self {
+        let (ip, user_id) = CONTEXT
+            .try_with(|context| (Some(context.ip.clone()), context.user_id.get()))
+            .unwrap_or((None, None));
+        Self { kind, user_id, ip, correlation_id: correlation::current_or_new(), reason: None }
+    }
+
+    pub fn user(mut self, user_id: i64) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    pub fn reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Write the event's log line; failures and denials are warnings
+    pub fn log(&self) {
+        let event = self.kind.as_str();
+        let ip = self.ip.as_deref().unwrap_or("unknown");
+        let reason = self.reason.as_deref();
+        match self.kind {
+            AuthEventKind::LoginFailure | AuthEventKind::PermissionDenied => tracing::warn!(
+                target: AUTH_EVENT_TARGET,
+                event,
+                user_id = self.user_id,
+                ip,
+                correlation_id = %self.correlation_id,
+                reason,
+                "Authentication event"
+            ),
+            _ => tracing::info!(
+                target: AUTH_EVENT_TARGET,
+                event,
+                user_id = self.user_id,
+                ip,
+                correlation_id = %self.correlation_id,
+                reason,
+                "Authentication event"
+            ),
+        }
+    }
+}
+
+/// Log `event`, and add it to the audit log with the database destination
+pub async fn record_auth_event(pool: &PgPool, destination: AuthEventDestination, event: AuthEvent) {
+    let _ = CONTEXT.try_with(|context| context.reported.set(true));
+    event.log();
+    if destination == AuthEventDestination::Database {
+        let details = serde_json::json!({
+            "ip": event.ip,
+            "correlation_id": event.correlation_id,
+            "reason": event.reason,
+        });
+        let action = format!("auth.{}", event.kind.as_str());
+        record_audit_event(pool, None, event.user_id, &action, None, details).await;
+    }
+}
+
+/// `record_auth_event` to the configured destination
+pub async fn emit(state: &AppState, event: AuthEvent) {
+    record_auth_event(&state.db_pool, state.config.auth.auth_event_destination, event).await;
+}
+
+/// What the events of the request being handled share
+struct RequestContext {
+    ip: String,
+    user_id: Cell<Option<i64>>,
+    /// Whether a handler already recorded an event
+    reported: Cell<bool>,
+}
+
+tokio::task_local! {
+    static CONTEXT: RequestContext;
+}
+
+/// Run `fut` as a request from `ip`, which its events report
+pub async fn scope<F: Future>(ip: String, fut: F) -> F::Output {
+    let context = RequestContext { ip, user_id: Cell::new(None), reported: Cell::new(false) };
+    CONTEXT.scope(context, fut).await
+}
+
+/// Remember the authenticated user of the current request for its events
+pub fn note_user(user_id: i64) {
+    let _ = CONTEXT.try_with(|context| context.user_id.set(Some(user_id)));
+}
+
+/// Run the request with its client IP known to its events, and record a
+/// permission denial for a 403 the handler did not already report
+pub async fn auth_event_middleware(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let ip = client_ip(request.headers(), peer, &state.config.server.trusted_proxies);
+    let denied = format!("{} {}", request.method(), request.uri().path());
+
+    scope(ip, async move {
+        let response = next.run(request).await;
+        let reported = CONTEXT.with(|context| context.reported.get());
+        if response.status() == StatusCode::FORBIDDEN && !reported {
+            emit(&state, AuthEvent::new(AuthEventKind::PermissionDenied).reason(denied)).await;
+        }
+        response
+    })
+    .await
+}
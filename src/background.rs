@@ -0,0 +1,90 @@
+// Periodic maintenance tasks
+//
+// Both the development and the production binary start these, so a feature
+// that needs housekeeping works the same whichever one serves it. Tasks that
+// write skip their work, or wait for writes to resume, while read-only mode
+// is on.
+use std::time::Duration;
+
+use crate::AppState;
+
+/// How often expired API keys are removed
+const API_KEY_CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+/// How often expired organization memberships are removed
+const MEMBERSHIP_CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+/// How often a write deferred by read-only mode checks whether writes resumed
+const READ_ONLY_RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Spawn the periodic maintenance tasks for `state`
+pub fn spawn_maintenance_tasks(state: &AppState) {
+    // Probe the database pool, unless DATABASE_HEALTH_CHECK_INTERVAL_SECS is 0
+    if state.config.database.health_check_interval_secs > 0 {
+        let interval = Duration::from_secs(state.config.database.health_check_interval_secs);
+        tokio::spawn(crate::db::run_pool_health_probe(state.db_pool.clone(), interval));
+    }
+
+    // Remove expired API keys
+    let cleanup_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(API_KEY_CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            if cleanup_state.read_only.is_enabled() {
+                continue;
+            }
+            if let Err(e) = crate::handlers::auth::cleanup_expired_api_keys(&cleanup_state.db_pool).await {
+                tracing::error!("Failed to cleanup expired API keys: {}", e);
+            }
+        }
+    });
+
+    // Remove expired organization memberships
+    let membership_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(MEMBERSHIP_CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            if membership_state.read_only.is_enabled() {
+                continue;
+            }
+            if let Err(e) = crate::handlers::organizations::cleanup_expired_memberships(&membership_state.db_pool).await {
+                tracing::error!("Failed to cleanup expired memberships: {}", e);
+            }
+        }
+    });
+
+    // Apply tag expiry (TTL) rules
+    let expiry_state = state.clone();
+    let expiry_interval = Duration::from_secs(state.config.registry.tag_expiry_interval_secs);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(expiry_interval);
+        loop {
+            interval.tick().await;
+            if expiry_state.read_only.is_enabled() {
+                continue;
+            }
+            if let Err(e) = crate::handlers::tag_expiry::apply_tag_expiry_rules(&expiry_state).await {
+                tracing::error!("Failed to apply tag expiry rules: {}", e);
+            }
+        }
+    });
+
+    // Index what older manifests reference before blob cleanup relies on it,
+    // once writes are allowed
+    let backfill_state = state.clone();
+    tokio::spawn(async move {
+        if backfill_state.read_only.is_enabled() {
+            tracing::info!("Deferring the blob reference backfill until read-only mode is turned off");
+            while backfill_state.read_only.is_enabled() {
+                tokio::time::sleep(READ_ONLY_RECHECK_INTERVAL).await;
+            }
+        }
+        match crate::handlers::docker_registry_v2::backfill_manifest_blob_references(&backfill_state).await {
+            Ok(0) => {}
+            Ok(indexed) => tracing::info!("Indexed blob references of {} manifest(s)", indexed),
+            Err(e) => tracing::error!("Failed to index manifest blob references: {}", e),
+        }
+    });
+
+    tracing::info!("Background maintenance tasks started");
+}
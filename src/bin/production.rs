@@ -11,8 +11,7 @@ use tokio::sync::RwLock;
 use tracing::{info, warn};
 use secrecy::ExposeSecret;
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
     // Initialize tracing cho production logging
     let subscriber = tracing_subscriber::fmt()
         .with_env_filter(
@@ -27,6 +26,16 @@ async fn main() -> anyhow::Result<()> {
 
     // Load configuration
     let settings = Settings::load().context("Failed to load application settings")?;
+
+    // Build the runtime by hand so worker/blocking thread counts are configurable
+    let runtime = aerugo::runtime::build_runtime(&settings.server)
+        .context("Failed to build Tokio runtime")?;
+
+    runtime.block_on(run(settings))
+}
+
+async fn run(settings: Settings) -> anyhow::Result<()> {
+    settings.log_effective_config();
     let production_config = ProductionSettings::load()
         .context("Failed to load production settings")?;
 
@@ -46,12 +55,14 @@ async fn main() -> anyhow::Result<()> {
     let connection_url = settings.database.url();
     info!("🔗 Connecting to database: {}", connection_url.replace(settings.database.password.expose_secret(), "[HIDDEN]"));
     
-    let database_pool = PgPoolOptions::new()
+    let database_pool_options = PgPoolOptions::new()
         .max_connections(production_config.database_pool.max_connections)
         .min_connections(production_config.database_pool.min_connections)
         .acquire_timeout(Duration::from_secs(production_config.database_pool.connect_timeout))
         .max_lifetime(Duration::from_secs(production_config.database_pool.max_lifetime))
-        .idle_timeout(Duration::from_secs(production_config.database_pool.idle_timeout))
+        .idle_timeout(Duration::from_secs(production_config.database_pool.idle_timeout));
+    aerugo::db::set_slow_query_threshold(Duration::from_millis(settings.database.slow_query_threshold_ms));
+    let database_pool = aerugo::tenant::scoped_pool_options(database_pool_options, settings.database.tenancy_mode)
         .connect(&connection_url)
         .await
         .context("Failed to create database pool")?;
@@ -67,6 +78,10 @@ async fn main() -> anyhow::Result<()> {
 
     info!("✅ Database migrations skipped for now");
 
+    aerugo::tenant::sync_tenant_schemas(&database_pool, settings.database.tenancy_mode)
+        .await
+        .context("Failed to provision tenant schemas")?;
+
     // Initialize Redis cache with production config
     let redis_url = production_config.redis_url_with_auth(
         std::env::var("REDIS_PASSWORD").ok().as_deref()
@@ -78,11 +93,13 @@ async fn main() -> anyhow::Result<()> {
         blob_metadata_ttl: Duration::from_secs(production_config.cache.memory.blob_metadata_ttl),
         repository_ttl: Duration::from_secs(production_config.cache.memory.repository_ttl),
         tag_ttl: Duration::from_secs(production_config.cache.memory.tag_ttl),
+        catalog_ttl: settings.cache.ttl(aerugo::cache::CacheKeyType::Catalog),
         // Authentication cache TTLs
         auth_token_ttl: Duration::from_secs(900), // 15 minutes
         permission_ttl: Duration::from_secs(300), // 5 minutes
         session_ttl: Duration::from_secs(1800), // 30 minutes
         max_memory_entries: production_config.cache.memory.max_entries as usize,
+        max_entry_bytes: settings.cache.max_entry_bytes,
         enable_redis: true,
         enable_memory: true,
     };
@@ -119,6 +136,12 @@ async fn main() -> anyhow::Result<()> {
 
     info!("✅ S3 storage initialized - bucket: {}", settings.storage.bucket_name());
 
+    let storage_router = Arc::new(
+        aerugo::storage::router::StorageRouter::from_settings(&settings.storage, storage)
+            .context("Invalid storage routing configuration")?
+    );
+    let storage: Arc<dyn Storage> = storage_router.clone();
+
     // Initialize email service for production
     let email_service = Arc::new(
         aerugo::email::EmailService::new(settings.email.clone())
@@ -126,14 +149,25 @@ async fn main() -> anyhow::Result<()> {
     );
     info!("📧 Email service initialized for production");
 
+    let cache = Arc::new(cache);
+
     // Create application state with production optimizations
+    let pulls = aerugo::pull_recorder::PullRecorder::start(database_pool.clone());
     let app_state = AppState {
         db_pool: database_pool,
         config: settings.clone(),
-        cache: Some(Arc::new(cache)),
+        cache: Some(cache.clone()),
         storage,
+        storage_router,
         manifest_cache: Arc::new(RwLock::new(HashMap::new())),
         email_service,
+        login_throttle: Arc::new(aerugo::login_throttle::LoginThrottle::from_settings(&settings.auth)),
+        readiness: aerugo::shutdown::Readiness::default(),
+        read_only: aerugo::read_only::ReadOnlyMode::new(settings.registry.read_only_mode),
+        nonces: cache,
+        org_aliases: Arc::new(aerugo::handlers::organizations::OrgAliasCache::default()),
+        blocking_organizations: Arc::new(aerugo::handlers::docker_registry_v2::BlockingOrganizationsCache::default()),
+        pulls,
     };
 
     // Create Axum application with optimized routes
@@ -155,8 +189,12 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // Run server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .with_graceful_shutdown(aerugo::shutdown::drain(
+            aerugo::shutdown::shutdown_signal(),
+            app_state.readiness.clone(),
+            Duration::from_secs(settings.server.shutdown_drain_delay_secs),
+        ))
         .await
         .context("Server error")?;
 
@@ -185,7 +223,7 @@ async fn start_background_tasks(
         }
     });
 
-    // Health check task
+    // Cache health check task
     let health_state = app_state.clone();
     let health_interval = Duration::from_secs(config.health_check_interval);
     
@@ -193,13 +231,8 @@ async fn start_background_tasks(
         let mut interval = tokio::time::interval(health_interval);
         loop {
             interval.tick().await;
-            
-            // Check database health
-            if let Err(e) = sqlx::query("SELECT 1").execute(&health_state.db_pool).await {
-                warn!("Database health check failed: {}", e);
-            }
-            
-            // Check cache health  
+
+            // Check cache health; the database has its own probe
             if let Some(cache) = &health_state.cache {
                 if let Err(e) = cache.health_check().await {
                     warn!("Cache health check failed: {}", e);
@@ -208,6 +241,21 @@ async fn start_background_tasks(
         }
     });
 
+    // Periodic maintenance shared with the development binary
+    aerugo::background::spawn_maintenance_tasks(&app_state);
+
+    // Pre-load the most pulled manifests without holding up startup
+    if app_state.config.cache.warmup {
+        let warmup_state = app_state.clone();
+        let count = app_state.config.cache.warmup_count;
+        tokio::spawn(async move {
+            if let Err(e) = aerugo::cache_warmup::run_cache_warmup(warmup_state, count).await {
+                warn!("Cache warmup skipped: {}", e);
+            }
+        });
+        info!("🔥 Cache warmup started");
+    }
+
     info!("✅ Background tasks started - cache cleanup & health monitoring");
     Ok(())
 }
@@ -250,32 +298,3 @@ async fn start_metrics_server(settings: &Settings) -> anyhow::Result<()> {
     Ok(())
     */
 }
-
-/// Graceful shutdown signal handler
-async fn shutdown_signal() {
-    let ctrl_c = async {
-        tokio::signal::ctrl_c()
-            .await
-            .expect("Failed to install Ctrl+C handler");
-    };
-
-    #[cfg(unix)]
-    let terminate = async {
-        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
-            .expect("Failed to install signal handler")
-            .recv()
-            .await;
-    };
-
-    #[cfg(not(unix))]
-    let terminate = std::future::pending::<()>();
-
-    tokio::select! {
-        _ = ctrl_c => {
-            info!("🛑 Received Ctrl+C, starting graceful shutdown");
-        },
-        _ = terminate => {
-            info!("🛑 Received terminate signal, starting graceful shutdown");
-        },
-    }
-}
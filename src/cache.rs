@@ -9,6 +9,9 @@ use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use redis::{Client as RedisClient, Commands};
 use anyhow::Result;
+use crate::server_timing::{TimingGuard, TimingMetric};
+use crate::nonce::{LocalNonces, NonceStore};
+use crate::tag_lock::{LocalLocks, LockBackend, RELEASE_SCRIPT};
 
 // Authentication cache structures
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +49,10 @@ pub struct RegistryCache {
     redis_client: Option<RedisClient>,
     memory_cache: Arc<RwLock<MemoryCache>>,
     config: CacheConfig,
+    /// Locks for when Redis is unavailable
+    local_locks: Arc<LocalLocks>,
+    /// Request nonces for when Redis is unavailable
+    local_nonces: Arc<LocalNonces>,
 }
 
 /// In-memory cache for high-frequency data
@@ -54,6 +61,7 @@ struct MemoryCache {
     manifest_cache: HashMap<String, CacheEntry<Bytes>>,
     blob_metadata: HashMap<String, CacheEntry<BlobCacheMetadata>>,
     repository_cache: HashMap<String, CacheEntry<Vec<String>>>,
+    catalog_cache: HashMap<String, CacheEntry<CatalogPage>>,
     tag_cache: HashMap<String, CacheEntry<Vec<String>>>,
     // Authentication and permission caches
     auth_token_cache: HashMap<String, CacheEntry<AuthCacheEntry>>,
@@ -92,11 +100,15 @@ pub struct CacheConfig {
     pub blob_metadata_ttl: Duration,
     pub repository_ttl: Duration,
     pub tag_ttl: Duration,
+    /// Zero disables catalog caching
+    pub catalog_ttl: Duration,
     // Authentication cache TTLs
     pub auth_token_ttl: Duration,
     pub permission_ttl: Duration,
     pub session_ttl: Duration,
     pub max_memory_entries: usize,
+    /// Larger manifests are not cached and are served from storage instead
+    pub max_entry_bytes: usize,
     pub enable_redis: bool,
     pub enable_memory: bool,
 }
@@ -109,17 +121,85 @@ impl Default for CacheConfig {
             blob_metadata_ttl: Duration::from_secs(600), // 10 minutes
             repository_ttl: Duration::from_secs(60), // 1 minute
             tag_ttl: Duration::from_secs(120), // 2 minutes
+            catalog_ttl: Duration::from_secs(30),
             // Authentication cache TTLs
             auth_token_ttl: Duration::from_secs(900), // 15 minutes
             permission_ttl: Duration::from_secs(300), // 5 minutes
             session_ttl: Duration::from_secs(1800), // 30 minutes
             max_memory_entries: 10000,
+            max_entry_bytes: 1024 * 1024, // 1 MiB
             enable_redis: true,
             enable_memory: true,
         }
     }
 }
 
+/// Kind of data a cache entry holds, which selects its TTL
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheKeyType {
+    Manifest,
+    BlobMetadata,
+    Repository,
+    Tag,
+    /// Pages of `/v2/_catalog`
+    Catalog,
+    AuthToken,
+    /// Authorization decisions (repository permissions)
+    Authz,
+    Session,
+}
+
+impl CacheConfig {
+    /// TTL for entries of `key_type`
+    pub fn ttl(&self, key_type: CacheKeyType) -> Duration {
+        match key_type {
+            CacheKeyType::Manifest => self.manifest_ttl,
+            CacheKeyType::BlobMetadata => self.blob_metadata_ttl,
+            CacheKeyType::Repository => self.repository_ttl,
+            CacheKeyType::Tag => self.tag_ttl,
+            CacheKeyType::Catalog => self.catalog_ttl,
+            CacheKeyType::AuthToken => self.auth_token_ttl,
+            CacheKeyType::Authz => self.permission_ttl,
+            CacheKeyType::Session => self.session_ttl,
+        }
+    }
+}
+
+/// One page of `/v2/_catalog` as a caller sees it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogPage {
+    pub repositories: Vec<String>,
+    /// Last repository of the page, when more follow
+    pub next_last: Option<String>,
+}
+
+/// Key `get_manifest` caches manifest `reference` of repository `name` under,
+/// `name` as the client gave it
+pub fn manifest_cache_key(name: &str, reference: &str) -> String {
+    format!("manifest:{}:{}", name, reference)
+}
+
+/// Names clients pull `organization/repository` by: repositories of the
+/// default organization are also pulled by their bare name
+pub fn repository_pull_names(organization: &str, repository: &str, organization_id: i64) -> Vec<String> {
+    let mut names = vec![format!("{}/{}", organization, repository)];
+    if organization_id == crate::tenant::DEFAULT_ORGANIZATION_ID {
+        names.push(repository.to_string());
+    }
+    names
+}
+
+/// Key for the catalog page requested with `n` and `last` by `caller`, whose
+/// visibility decides which repositories the page holds
+pub fn catalog_cache_key(caller: &str, n: Option<u32>, last: Option<&str>) -> String {
+    format!(
+        "catalog:{}:{}:{}",
+        caller,
+        n.map(|n| n.to_string()).unwrap_or_default(),
+        last.unwrap_or_default()
+    )
+}
+
 /// Blob metadata for caching
 #[derive(Clone, Serialize, Deserialize)]
 pub struct BlobCacheMetadata {
@@ -161,17 +241,20 @@ impl RegistryCache {
             redis_client,
             memory_cache: Arc::new(RwLock::new(MemoryCache::default())),
             config,
+            local_locks: Arc::new(LocalLocks::default()),
+            local_nonces: Arc::new(LocalNonces::default()),
         })
     }
     
     /// Cache blob metadata
     pub async fn cache_blob_metadata(&self, digest: &str, metadata: BlobCacheMetadata) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Memory cache
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
             cache.blob_metadata.insert(
                 digest.to_string(),
-                CacheEntry::new(metadata.clone(), self.config.blob_metadata_ttl),
+                CacheEntry::new(metadata.clone(), self.config.ttl(CacheKeyType::BlobMetadata)),
             );
         }
         
@@ -179,7 +262,7 @@ impl RegistryCache {
         if let Some(redis) = &self.redis_client {
             if let Ok(mut conn) = redis.get_connection() {
                 let redis_key = format!("blob_meta:{}", digest);
-                let ttl_secs = self.config.blob_metadata_ttl.as_secs();
+                let ttl_secs = self.config.ttl(CacheKeyType::BlobMetadata).as_secs();
                 if let Ok(json_data) = serde_json::to_string(&metadata) {
                     let _: Result<(), _> = conn.set_ex(&redis_key, json_data, ttl_secs);
                 }
@@ -191,6 +274,7 @@ impl RegistryCache {
     
     /// Get cached blob metadata
     pub async fn get_blob_metadata(&self, digest: &str) -> Option<BlobCacheMetadata> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Try memory cache first
         if self.config.enable_memory {
             let cache = self.memory_cache.read().await;
@@ -212,7 +296,7 @@ impl RegistryCache {
                             let mut cache = self.memory_cache.write().await;
                             cache.blob_metadata.insert(
                                 digest.to_string(),
-                                CacheEntry::new(metadata.clone(), self.config.blob_metadata_ttl),
+                                CacheEntry::new(metadata.clone(), self.config.ttl(CacheKeyType::BlobMetadata)),
                             );
                         }
                         
@@ -225,14 +309,26 @@ impl RegistryCache {
         None
     }
     
-    /// Cache manifest data
-    pub async fn cache_manifest(&self, key: &str, manifest: Bytes) -> Result<()> {
+    /// Cache manifest data. Manifests over `max_entry_bytes` are skipped to
+    /// keep Redis memory bounded; returns whether the manifest was cached.
+    pub async fn cache_manifest(&self, key: &str, manifest: Bytes) -> Result<bool> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
+        if manifest.len() > self.config.max_entry_bytes {
+            tracing::debug!(
+                "Not caching manifest {} ({} bytes, limit {})",
+                key,
+                manifest.len(),
+                self.config.max_entry_bytes
+            );
+            return Ok(false);
+        }
+
         // Memory cache
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
             cache.manifest_cache.insert(
                 key.to_string(),
-                CacheEntry::new(manifest.clone(), self.config.manifest_ttl),
+                CacheEntry::new(manifest.clone(), self.config.ttl(CacheKeyType::Manifest)),
             );
             
             // Cleanup old entries if needed
@@ -245,16 +341,17 @@ impl RegistryCache {
         if let Some(redis) = &self.redis_client {
             if let Ok(mut conn) = redis.get_connection() {
                 let redis_key = format!("manifest:{}", key);
-                let ttl_secs = self.config.manifest_ttl.as_secs();
+                let ttl_secs = self.config.ttl(CacheKeyType::Manifest).as_secs();
                 let _: Result<(), _> = conn.set_ex(&redis_key, manifest.as_ref(), ttl_secs);
             }
         }
         
-        Ok(())
+        Ok(true)
     }
     
     /// Get cached manifest
     pub async fn get_manifest(&self, key: &str) -> Option<Bytes> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Try memory cache first
         if self.config.enable_memory {
             let cache = self.memory_cache.read().await;
@@ -277,7 +374,7 @@ impl RegistryCache {
                         let mut cache = self.memory_cache.write().await;
                         cache.manifest_cache.insert(
                             key.to_string(),
-                            CacheEntry::new(bytes.clone(), self.config.manifest_ttl),
+                            CacheEntry::new(bytes.clone(), self.config.ttl(CacheKeyType::Manifest)),
                         );
                     }
                     
@@ -291,6 +388,7 @@ impl RegistryCache {
     
     /// Cache repository list
     pub async fn cache_repositories(&self, repositories: Vec<String>) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         let key = "repositories";
         
         // Memory cache
@@ -298,7 +396,7 @@ impl RegistryCache {
             let mut cache = self.memory_cache.write().await;
             cache.repository_cache.insert(
                 key.to_string(),
-                CacheEntry::new(repositories.clone(), self.config.repository_ttl),
+                CacheEntry::new(repositories.clone(), self.config.ttl(CacheKeyType::Repository)),
             );
         }
         
@@ -306,7 +404,7 @@ impl RegistryCache {
         if let Some(redis) = &self.redis_client {
             if let Ok(mut conn) = redis.get_connection() {
                 let redis_key = format!("repos:{}", key);
-                let ttl_secs = self.config.repository_ttl.as_secs();
+                let ttl_secs = self.config.ttl(CacheKeyType::Repository).as_secs();
                 if let Ok(json_data) = serde_json::to_string(&repositories) {
                     let _: Result<(), _> = conn.set_ex(&redis_key, json_data, ttl_secs);
                 }
@@ -318,6 +416,7 @@ impl RegistryCache {
     
     /// Get cached repository list
     pub async fn get_repositories(&self) -> Option<Vec<String>> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         let key = "repositories";
         
         // Try memory cache first
@@ -341,7 +440,7 @@ impl RegistryCache {
                             let mut cache = self.memory_cache.write().await;
                             cache.repository_cache.insert(
                                 key.to_string(),
-                                CacheEntry::new(repositories.clone(), self.config.repository_ttl),
+                                CacheEntry::new(repositories.clone(), self.config.ttl(CacheKeyType::Repository)),
                             );
                         }
                         
@@ -354,14 +453,74 @@ impl RegistryCache {
         None
     }
     
+    /// Cache a catalog page under a key from `catalog_cache_key`
+    pub async fn cache_catalog_page(&self, key: &str, page: &CatalogPage) -> Result<()> {
+        let ttl = self.config.ttl(CacheKeyType::Catalog);
+        if ttl.is_zero() {
+            return Ok(());
+        }
+        let _timing = TimingGuard::start(TimingMetric::Cache);
+
+        if self.config.enable_memory {
+            let mut cache = self.memory_cache.write().await;
+            cache.catalog_cache.insert(key.to_string(), CacheEntry::new(page.clone(), ttl));
+        }
+
+        // Stored under repos: so invalidate_repositories drops it
+        if let Some(redis) = &self.redis_client {
+            if let Ok(mut conn) = redis.get_connection() {
+                if let Ok(json_data) = serde_json::to_string(page) {
+                    let _: Result<(), _> = conn.set_ex(format!("repos:{}", key), json_data, ttl.as_secs().max(1));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get a cached catalog page
+    pub async fn get_catalog_page(&self, key: &str) -> Option<CatalogPage> {
+        let ttl = self.config.ttl(CacheKeyType::Catalog);
+        if ttl.is_zero() {
+            return None;
+        }
+        let _timing = TimingGuard::start(TimingMetric::Cache);
+
+        if self.config.enable_memory {
+            let cache = self.memory_cache.read().await;
+            if let Some(entry) = cache.catalog_cache.get(key) {
+                if !entry.is_expired() {
+                    return Some(entry.data.clone());
+                }
+            }
+        }
+
+        if let Some(redis) = &self.redis_client {
+            if let Ok(mut conn) = redis.get_connection() {
+                if let Ok(data) = conn.get::<_, String>(format!("repos:{}", key)) {
+                    if let Ok(page) = serde_json::from_str::<CatalogPage>(&data) {
+                        if self.config.enable_memory {
+                            let mut cache = self.memory_cache.write().await;
+                            cache.catalog_cache.insert(key.to_string(), CacheEntry::new(page.clone(), ttl));
+                        }
+                        return Some(page);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+    
     /// Cache tag list for repository
     pub async fn cache_tags(&self, repository: &str, tags: Vec<String>) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Memory cache
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
             cache.tag_cache.insert(
                 repository.to_string(),
-                CacheEntry::new(tags.clone(), self.config.tag_ttl),
+                CacheEntry::new(tags.clone(), self.config.ttl(CacheKeyType::Tag)),
             );
         }
         
@@ -369,7 +528,7 @@ impl RegistryCache {
         if let Some(redis) = &self.redis_client {
             if let Ok(mut conn) = redis.get_connection() {
                 let redis_key = format!("tags:{}", repository);
-                let ttl_secs = self.config.tag_ttl.as_secs();
+                let ttl_secs = self.config.ttl(CacheKeyType::Tag).as_secs();
                 if let Ok(json_data) = serde_json::to_string(&tags) {
                     let _: Result<(), _> = conn.set_ex(&redis_key, json_data, ttl_secs);
                 }
@@ -381,6 +540,7 @@ impl RegistryCache {
     
     /// Get cached tag list
     pub async fn get_tags(&self, repository: &str) -> Option<Vec<String>> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Try memory cache first
         if self.config.enable_memory {
             let cache = self.memory_cache.read().await;
@@ -402,7 +562,7 @@ impl RegistryCache {
                             let mut cache = self.memory_cache.write().await;
                             cache.tag_cache.insert(
                                 repository.to_string(),
-                                CacheEntry::new(tags.clone(), self.config.tag_ttl),
+                                CacheEntry::new(tags.clone(), self.config.ttl(CacheKeyType::Tag)),
                             );
                         }
                         
@@ -417,13 +577,17 @@ impl RegistryCache {
     
     /// Invalidate cache entries
     pub async fn invalidate(&self, pattern: &str) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Clear memory cache entries matching pattern
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
             
             match pattern {
                 "manifests" => cache.manifest_cache.clear(),
-                "repositories" => cache.repository_cache.clear(),
+                "repositories" => {
+                    cache.repository_cache.clear();
+                    cache.catalog_cache.clear();
+                }
                 key if key.starts_with("tags:") => {
                     let repo = key.strip_prefix("tags:").unwrap_or("");
                     cache.tag_cache.remove(repo);
@@ -486,6 +650,7 @@ impl RegistryCache {
         
         // Remove expired repositories
         cache.repository_cache.retain(|_, entry| !entry.is_expired());
+        cache.catalog_cache.retain(|_, entry| !entry.is_expired());
         
         // Remove expired tags
         cache.tag_cache.retain(|_, entry| !entry.is_expired());
@@ -562,11 +727,15 @@ impl RegistryCache {
     pub async fn health_check(&self) -> anyhow::Result<()> {
         // Test Redis connection if available
         if let Some(redis) = &self.redis_client {
-            if let Ok(mut conn) = redis.get_connection() {
-                let _: String = redis::cmd("PING")
-                    .query(&mut conn)
-                    .map_err(|e| anyhow::anyhow!("Redis health check failed: {}", e))?;
-            }
+            // Async, so the probe's timeout can cut off an unresponsive server
+            let mut conn = redis
+                .get_multiplexed_async_connection()
+                .await
+                .map_err(|e| anyhow::anyhow!("Redis connection failed: {}", e))?;
+            let _: String = redis::cmd("PING")
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| anyhow::anyhow!("Redis health check failed: {}", e))?;
         }
         
         Ok(())
@@ -594,6 +763,7 @@ impl RegistryCache {
             cache.manifest_cache.clear();
             cache.blob_metadata.clear();
             cache.repository_cache.clear();
+            cache.catalog_cache.clear();
             cache.tag_cache.clear();
         }
         
@@ -611,12 +781,13 @@ impl RegistryCache {
     
     /// Cache authentication token
     pub async fn cache_auth_token(&self, token: &str, auth_entry: AuthCacheEntry) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Memory cache
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
             cache.auth_token_cache.insert(
                 token.to_string(),
-                CacheEntry::new(auth_entry.clone(), self.config.auth_token_ttl),
+                CacheEntry::new(auth_entry.clone(), self.config.ttl(CacheKeyType::AuthToken)),
             );
         }
         
@@ -625,7 +796,7 @@ impl RegistryCache {
             if let Ok(mut conn) = redis.get_connection() {
                 let redis_key = format!("auth:{}", token);
                 let serialized = serde_json::to_string(&auth_entry)?;
-                let _: Result<(), _> = conn.set_ex(&redis_key, serialized, self.config.auth_token_ttl.as_secs());
+                let _: Result<(), _> = conn.set_ex(&redis_key, serialized, self.config.ttl(CacheKeyType::AuthToken).as_secs());
             }
         }
         
@@ -634,6 +805,7 @@ impl RegistryCache {
     
     /// Get cached authentication token
     pub async fn get_auth_token(&self, token: &str) -> Option<AuthCacheEntry> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Try memory cache first
         if self.config.enable_memory {
             let cache = self.memory_cache.read().await;
@@ -655,7 +827,7 @@ impl RegistryCache {
                             let mut cache = self.memory_cache.write().await;
                             cache.auth_token_cache.insert(
                                 token.to_string(),
-                                CacheEntry::new(auth_entry.clone(), self.config.auth_token_ttl),
+                                CacheEntry::new(auth_entry.clone(), self.config.ttl(CacheKeyType::AuthToken)),
                             );
                         }
                         
@@ -670,6 +842,7 @@ impl RegistryCache {
     
     /// Cache user permissions for repository
     pub async fn cache_permissions(&self, user_id: &str, repo_name: &str, permissions: PermissionCacheEntry) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         let cache_key = format!("{}:{}", user_id, repo_name);
         
         // Memory cache
@@ -677,7 +850,7 @@ impl RegistryCache {
             let mut cache = self.memory_cache.write().await;
             cache.permission_cache.insert(
                 cache_key.clone(),
-                CacheEntry::new(permissions.clone(), self.config.permission_ttl),
+                CacheEntry::new(permissions.clone(), self.config.ttl(CacheKeyType::Authz)),
             );
         }
         
@@ -686,7 +859,7 @@ impl RegistryCache {
             if let Ok(mut conn) = redis.get_connection() {
                 let redis_key = format!("perms:{}", cache_key);
                 let serialized = serde_json::to_string(&permissions)?;
-                let _: Result<(), _> = conn.set_ex(&redis_key, serialized, self.config.permission_ttl.as_secs());
+                let _: Result<(), _> = conn.set_ex(&redis_key, serialized, self.config.ttl(CacheKeyType::Authz).as_secs());
             }
         }
         
@@ -695,6 +868,7 @@ impl RegistryCache {
     
     /// Get cached permissions
     pub async fn get_permissions(&self, user_id: &str, repo_name: &str) -> Option<PermissionCacheEntry> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         let cache_key = format!("{}:{}", user_id, repo_name);
         
         // Try memory cache first
@@ -718,7 +892,7 @@ impl RegistryCache {
                             let mut cache = self.memory_cache.write().await;
                             cache.permission_cache.insert(
                                 cache_key,
-                                CacheEntry::new(permissions.clone(), self.config.permission_ttl),
+                                CacheEntry::new(permissions.clone(), self.config.ttl(CacheKeyType::Authz)),
                             );
                         }
                         
@@ -733,12 +907,13 @@ impl RegistryCache {
     
     /// Cache user session data
     pub async fn cache_session(&self, session_id: &str, session_data: UserSessionCache) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Memory cache
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
             cache.user_session_cache.insert(
                 session_id.to_string(),
-                CacheEntry::new(session_data.clone(), self.config.session_ttl),
+                CacheEntry::new(session_data.clone(), self.config.ttl(CacheKeyType::Session)),
             );
         }
         
@@ -747,7 +922,7 @@ impl RegistryCache {
             if let Ok(mut conn) = redis.get_connection() {
                 let redis_key = format!("session:{}", session_id);
                 let serialized = serde_json::to_string(&session_data)?;
-                let _: Result<(), _> = conn.set_ex(&redis_key, serialized, self.config.session_ttl.as_secs());
+                let _: Result<(), _> = conn.set_ex(&redis_key, serialized, self.config.ttl(CacheKeyType::Session).as_secs());
             }
         }
         
@@ -756,6 +931,7 @@ impl RegistryCache {
     
     /// Get cached session data
     pub async fn get_session(&self, session_id: &str) -> Option<UserSessionCache> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Try memory cache first
         if self.config.enable_memory {
             let cache = self.memory_cache.read().await;
@@ -777,7 +953,7 @@ impl RegistryCache {
                             let mut cache = self.memory_cache.write().await;
                             cache.user_session_cache.insert(
                                 session_id.to_string(),
-                                CacheEntry::new(session_data.clone(), self.config.session_ttl),
+                                CacheEntry::new(session_data.clone(), self.config.ttl(CacheKeyType::Session)),
                             );
                         }
                         
@@ -792,6 +968,7 @@ impl RegistryCache {
     
     /// Invalidate authentication cache entries
     pub async fn invalidate_auth_token(&self, token: &str) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Remove from memory cache
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
@@ -809,8 +986,28 @@ impl RegistryCache {
         Ok(())
     }
     
+    /// Invalidate a cached API key, e.g. after its secret was rotated
+    pub async fn invalidate_api_key_info(&self, key_hash: &str) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
+        let cache_key = format!("api_key:{}", key_hash);
+
+        if self.config.enable_memory {
+            let mut cache = self.memory_cache.write().await;
+            cache.api_key_cache.remove(&cache_key);
+        }
+
+        if let Some(redis) = &self.redis_client {
+            if let Ok(mut conn) = redis.get_connection() {
+                let _: Result<(), _> = conn.del(&cache_key);
+            }
+        }
+
+        Ok(())
+    }
+    
     /// Invalidate all permissions for a user
     pub async fn invalidate_user_permissions(&self, user_id: &str) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Remove from memory cache
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
@@ -841,6 +1038,7 @@ impl RegistryCache {
 
     /// Invalidate manifest cache entry
     pub async fn invalidate_manifest(&self, cache_key: &str) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Remove from memory cache
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
@@ -860,6 +1058,7 @@ impl RegistryCache {
     
     /// Invalidate tags cache for a repository
     pub async fn invalidate_tags(&self, repository: &str) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Remove from memory cache
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
@@ -877,12 +1076,15 @@ impl RegistryCache {
         Ok(())
     }
     
-    /// Invalidate repository cache
+    /// Invalidate repository lists and catalog pages, after a repository is
+    /// created or deleted
     pub async fn invalidate_repositories(&self) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         // Remove from memory cache
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
             cache.repository_cache.clear();
+            cache.catalog_cache.clear();
         }
         
         // Remove from Redis cache
@@ -900,6 +1102,7 @@ impl RegistryCache {
 
     /// Cache OTP code for password reset
     pub async fn cache_otp_code(&self, email: &str, otp_code: &str, ttl: Duration) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         if self.config.enable_memory {
             let mut cache = self.memory_cache.write().await;
             let cache_key = format!("otp:reset:{}", email);
@@ -931,6 +1134,7 @@ impl RegistryCache {
 
     /// Get cached OTP code
     pub async fn get_otp_code(&self, email: &str) -> Option<String> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         let cache_key = format!("otp:reset:{}", email);
         
         if self.config.enable_memory {
@@ -959,6 +1163,7 @@ impl RegistryCache {
 
     /// Remove OTP code (after use)
     pub async fn remove_otp_code(&self, email: &str) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         let cache_key = format!("otp:reset:{}", email);
         
         if self.config.enable_memory {
@@ -979,6 +1184,7 @@ impl RegistryCache {
     
     /// Cache API key information  
     pub async fn cache_api_key_info(&self, key_hash: &str, api_key_entry: ApiKeyCacheEntry) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         let cache_key = format!("api_key:{}", key_hash);
         
         // Memory cache - store serialized string for API keys
@@ -987,7 +1193,7 @@ impl RegistryCache {
             let serialized = serde_json::to_string(&api_key_entry)?;
             cache.api_key_cache.insert(
                 cache_key.clone(),
-                CacheEntry::new(serialized, self.config.auth_token_ttl),
+                CacheEntry::new(serialized, self.config.ttl(CacheKeyType::AuthToken)),
             );
         }
         
@@ -995,7 +1201,7 @@ impl RegistryCache {
         if let Some(redis) = &self.redis_client {
             if let Ok(mut conn) = redis.get_connection() {
                 let serialized = serde_json::to_string(&api_key_entry)?;
-                let _: Result<(), _> = conn.set_ex(&cache_key, serialized, self.config.auth_token_ttl.as_secs());
+                let _: Result<(), _> = conn.set_ex(&cache_key, serialized, self.config.ttl(CacheKeyType::AuthToken).as_secs());
             }
         }
         
@@ -1004,6 +1210,7 @@ impl RegistryCache {
     
     /// Get cached API key information
     pub async fn get_api_key_info(&self, key_hash: &str) -> Option<ApiKeyCacheEntry> {
+        let _timing = TimingGuard::start(TimingMetric::Cache);
         let cache_key = format!("api_key:{}", key_hash);
         
         // Try memory cache first
@@ -1029,7 +1236,7 @@ impl RegistryCache {
                             let mut cache = self.memory_cache.write().await;
                             cache.api_key_cache.insert(
                                 cache_key,
-                                CacheEntry::new(data, self.config.auth_token_ttl),
+                                CacheEntry::new(data, self.config.ttl(CacheKeyType::AuthToken)),
                             );
                         }
                         
@@ -1060,3 +1267,71 @@ pub struct MemoryCacheStats {
     pub permission_count: usize,
     pub session_count: usize,
 }
+
+/// Locks shared by every instance through Redis, or held in process when
+/// Redis is not connected
+#[async_trait::async_trait]
+impl LockBackend for RegistryCache {
+    async fn try_acquire(&self, key: &str, token: &str, ttl: Duration) -> Result<bool> {
+        let redis = match &self.redis_client {
+            Some(redis) => redis,
+            None => return self.local_locks.try_acquire(key, token, ttl).await,
+        };
+        let mut conn = redis.get_multiplexed_async_connection().await?;
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await?;
+        Ok(reply.is_some())
+    }
+
+    async fn release(&self, key: &str, token: &str) -> Result<bool> {
+        let redis = match &self.redis_client {
+            Some(redis) => redis,
+            None => return self.local_locks.release(key, token).await,
+        };
+        let mut conn = redis.get_multiplexed_async_connection().await?;
+        let deleted: i64 = redis::Script::new(RELEASE_SCRIPT)
+            .key(key)
+            .arg(token)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(deleted == 1)
+    }
+}
+
+/// Request nonces shared by every instance through Redis, or held in process
+/// when Redis is not connected
+#[async_trait::async_trait]
+impl NonceStore for RegistryCache {
+    async fn store(&self, key: &str, ttl: Duration) -> Result<()> {
+        let redis = match &self.redis_client {
+            Some(redis) => redis,
+            None => return self.local_nonces.store(key, ttl).await,
+        };
+        let mut conn = redis.get_multiplexed_async_connection().await?;
+        redis::cmd("SET")
+            .arg(key)
+            .arg(1)
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn take(&self, key: &str) -> Result<bool> {
+        let redis = match &self.redis_client {
+            Some(redis) => redis,
+            None => return self.local_nonces.take(key).await,
+        };
+        let mut conn = redis.get_multiplexed_async_connection().await?;
+        // DEL is atomic, so of two requests racing with one nonce only one wins
+        let deleted: i64 = redis::cmd("DEL").arg(key).query_async(&mut conn).await?;
+        Ok(deleted == 1)
+    }
+}
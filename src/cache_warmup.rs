@@ -0,0 +1,113 @@
+// Startup cache warmup
+//
+// With CACHE_WARMUP set, the most pulled manifests are loaded into the cache
+// before traffic arrives, so the first pulls after a deploy do not all miss.
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use sqlx::PgPool;
+
+use crate::cache::{manifest_cache_key, repository_pull_names, RegistryCache};
+use crate::storage::Storage;
+use crate::tenant::TenancyMode;
+use crate::AppState;
+
+/// A manifest to pre-load and the references it is pulled by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmupManifest {
+    /// Names the repository is pulled by, as `repository_pull_names` gives them
+    pub repositories: Vec<String>,
+    pub digest: String,
+    pub tags: Vec<String>,
+}
+
+impl WarmupManifest {
+    /// Cache keys `get_manifest` looks the manifest up under: its digest
+    /// and each of its tags, under each name of its repository
+    pub fn cache_keys(&self) -> Vec<String> {
+        self.repositories
+            .iter()
+            .flat_map(|repository| {
+                std::iter::once(&self.digest)
+                    .chain(&self.tags)
+                    .map(move |reference| manifest_cache_key(repository, reference))
+            })
+            .collect()
+    }
+}
+
+/// The `limit` most pulled manifests of all tenants, most pulled first
+pub async fn most_pulled_manifests(pool: &PgPool, mode: TenancyMode, limit: u32) -> Result<Vec<WarmupManifest>> {
+    let mut rows = crate::tenant::across_tenants(pool, mode, || {
+        sqlx::query_as::<_, (String, String, i64, String, Vec<String>, i64)>(
+            "SELECT o.name, r.name, o.id, m.digest,
+                    COALESCE(ARRAY_AGG(t.name ORDER BY t.name) FILTER (WHERE t.name IS NOT NULL), '{}'),
+                    m.pull_count
+             FROM manifests m
+             JOIN repositories r ON r.id = m.repository_id
+             JOIN organizations o ON o.id = r.organization_id
+             LEFT JOIN tags t ON t.manifest_id = m.id
+             WHERE m.pull_count > 0
+             GROUP BY m.id, o.name, r.name, o.id
+             ORDER BY m.pull_count DESC, m.id
+             LIMIT $1",
+        )
+        .bind(limit as i64)
+        .fetch_all(pool)
+    })
+    .await
+    .context("Failed to list most pulled manifests")?;
+
+    // Each tenant's list is ordered; keep the overall top `limit`
+    rows.sort_by(|a, b| b.5.cmp(&a.5));
+    rows.truncate(limit as usize);
+
+    Ok(rows
+        .into_iter()
+        .map(|(organization, repository, organization_id, digest, tags, _)| WarmupManifest {
+            repositories: repository_pull_names(&organization, &repository, organization_id),
+            digest,
+            tags,
+        })
+        .collect())
+}
+
+/// Cache each manifest under all of its keys; returns how many keys were
+/// filled. Manifests missing from storage are skipped.
+pub async fn warm_manifests(cache: &RegistryCache, storage: &dyn Storage, manifests: &[WarmupManifest]) -> usize {
+    let mut cached = 0;
+    for manifest in manifests {
+        let content: Bytes = match storage.get_blob(&format!("blobs/{}", manifest.digest)).await {
+            Ok(Some(content)) => content,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("Cache warmup could not read {}: {}", manifest.digest, e);
+                continue;
+            }
+        };
+        for key in manifest.cache_keys() {
+            if let Ok(true) = cache.cache_manifest(&key, content.clone()).await {
+                cached += 1;
+            }
+        }
+    }
+    cached
+}
+
+/// Pre-load the `limit` most pulled manifests once the database and cache
+/// are known to be reachable
+pub async fn run_cache_warmup(state: AppState, limit: u32) -> Result<()> {
+    let cache = match &state.cache {
+        Some(cache) => cache.clone(),
+        None => return Ok(()),
+    };
+    sqlx::query("SELECT 1")
+        .execute(&state.db_pool)
+        .await
+        .context("Database unavailable for cache warmup")?;
+    cache.health_check().await.context("Cache unavailable for warmup")?;
+
+    let manifests = most_pulled_manifests(&state.db_pool, state.config.database.tenancy_mode, limit).await?;
+    let cached = warm_manifests(&cache, state.storage.as_ref(), &manifests).await;
+    tracing::info!("Cache warmup loaded {} manifests under {} keys", manifests.len(), cached);
+    Ok(())
+}
@@ -0,0 +1,30 @@
+// Response compression
+//
+// Only API, health and documentation responses are compressed. Registry
+// routes are never wrapped: blobs are served exactly as stored (layers are
+// usually gzip already) and clients check them against their digest, so a
+// client asking for an encoding gets the raw bytes without `Content-Encoding`.
+use axum::Router;
+use tower_http::compression::predicate::{DefaultPredicate, NotForContentType, Predicate};
+use tower_http::compression::CompressionLayer;
+
+/// Compress responses worth compressing, skipping content that already is
+pub fn compression_predicate() -> impl Predicate {
+    DefaultPredicate::new()
+        .and(NotForContentType::const_new("application/octet-stream"))
+        .and(NotForContentType::const_new("application/gzip"))
+        .and(NotForContentType::const_new("application/vnd.docker.image.rootfs"))
+        .and(NotForContentType::const_new("application/vnd.oci.image.layer"))
+}
+
+/// Gzip `router`'s responses when `enabled` (`ENABLE_COMPRESSION`). Must not
+/// be applied to the registry router.
+pub fn compress<S>(router: Router<S>, enabled: bool) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    if !enabled {
+        return router;
+    }
+    router.layer(CompressionLayer::new().compress_when(compression_predicate()))
+}
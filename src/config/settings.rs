@@ -2,9 +2,18 @@ use anyhow::{Context, Result};
 use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
+use std::time::Duration;
 use url::Url;
 use validator::Validate;
 
+use crate::auth_events::AuthEventDestination;
+use crate::cache::CacheKeyType;
+use crate::correlation::DEFAULT_CORRELATION_HEADER;
+use crate::models::manifest_size::ManifestSizeLimits;
+use crate::models::user::{normalize_username, DEFAULT_RESERVED_USERNAMES};
+use crate::storage::router::{parse_routes, StorageRoute, S3_BACKEND};
+use crate::tenant::TenancyMode;
+
 #[derive(Debug, Deserialize, Clone, Validate)]
 pub struct Settings {
     #[validate]
@@ -19,6 +28,8 @@ pub struct Settings {
     pub auth: AuthSettings,
     #[validate]
     pub email: EmailSettings,
+    #[validate]
+    pub registry: RegistrySettings,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
@@ -29,6 +40,37 @@ pub struct ServerSettings {
     pub port: u16,
     pub api_prefix: String,
     pub log_level: String,
+    /// Tokio worker threads; `None` uses one per CPU core
+    #[validate(range(min = 1, max = 1024))]
+    pub worker_threads: Option<usize>,
+    /// Upper bound on Tokio's blocking thread pool; `None` uses Tokio's default (512)
+    #[validate(range(min = 1, max = 4096))]
+    pub max_blocking_threads: Option<usize>,
+    /// Emit a Server-Timing header with DB/cache/storage time per request
+    pub enable_server_timing: bool,
+    /// Send `Strict-Transport-Security`; enable when served over TLS or behind a TLS proxy
+    pub enable_hsts: bool,
+    /// HSTS max-age
+    pub hsts_max_age_secs: u64,
+    /// Expose the admin-only `/debug/pprof` endpoints
+    pub enable_profiling: bool,
+    /// Gzip API responses for clients that accept it; registry routes are never compressed
+    pub enable_compression: bool,
+    /// Header the correlation ID is read from and echoed in
+    #[validate(custom = "validate_header_name")]
+    pub correlation_header: String,
+    /// How long to keep serving after readiness turns 503 on shutdown
+    pub shutdown_drain_delay_secs: u64,
+    /// Answer `400` to `?fields=` selections naming fields a resource does
+    /// not have, instead of leaving them out
+    pub strict_field_selection: bool,
+    /// How far `Retry-After` on 503 responses is randomized around the
+    /// base delay, in percent, so waiting clients do not retry in step
+    #[validate(range(max = 100))]
+    pub retry_after_jitter_percent: u8,
+    /// Reverse proxies whose `X-Forwarded-For` is believed; from any other
+    /// peer the header is ignored and the peer address is the client
+    pub trusted_proxies: Vec<std::net::IpAddr>,
 }
 
 impl ServerSettings {
@@ -49,6 +91,16 @@ pub struct DatabaseSettings {
     pub require_ssl: bool,
     pub min_connections: u32,
     pub max_connections: u32,
+    /// Close connections idle for longer than this
+    pub idle_timeout_secs: u64,
+    /// Recycle connections older than this, even if healthy
+    pub max_lifetime_secs: u64,
+    /// Seconds between background pool health probes, 0 disables them
+    pub health_check_interval_secs: u64,
+    /// Queries taking at least this many milliseconds are logged, 0 disables it
+    pub slow_query_threshold_ms: u64,
+    /// Shared tables, or one schema per organization
+    pub tenancy_mode: TenancyMode,
 }
 
 impl DatabaseSettings {
@@ -73,27 +125,360 @@ pub struct StorageSettings {
     pub access_key_id: Secret<String>,
     pub secret_access_key: Secret<String>,
     pub use_path_style: bool,
+    /// Root of the optional filesystem backend
+    pub filesystem_path: Option<String>,
+    /// Backend for content no route matches
+    pub default_backend: String,
+    /// Rules choosing a backend by content type and size
+    pub routes: Vec<StorageRoute>,
 }
 
 impl StorageSettings {
     pub fn bucket_name(&self) -> &str {
         &self.bucket
     }
+
+    /// Check that endpoint, region and addressing style fit together. Returns
+    /// warnings for settings that work but are likely unintended, and an
+    /// error for combinations S3 requests cannot succeed with.
+    pub fn validate_consistency(&self) -> Result<Vec<String>, StorageConsistencyError> {
+        let endpoint = S3Endpoint::parse(&self.endpoint)?;
+        let region = self.region.trim();
+        let mut warnings = Vec::new();
+
+        if !self.use_path_style && !endpoint.supports_virtual_host() {
+            return Err(StorageConsistencyError::VirtualHostUnsupported(self.endpoint.clone()));
+        }
+
+        match endpoint.provider() {
+            S3Provider::Aws => {
+                if region.is_empty() {
+                    return Err(StorageConsistencyError::MissingRegion(self.endpoint.clone()));
+                }
+                if let Some(endpoint_region) = endpoint.aws_region() {
+                    if endpoint_region != region {
+                        return Err(StorageConsistencyError::RegionMismatch {
+                            region: region.to_string(),
+                            endpoint: self.endpoint.clone(),
+                            endpoint_region,
+                        });
+                    }
+                }
+                if self.use_path_style {
+                    warnings.push(format!(
+                        "S3_USE_PATH_STYLE is true for AWS endpoint {}; AWS is retiring path-style addressing",
+                        self.endpoint
+                    ));
+                }
+                if endpoint.url().scheme() != "https" {
+                    warnings.push(format!("AWS endpoint {} does not use https", self.endpoint));
+                }
+            }
+            S3Provider::Compatible => {
+                if region.is_empty() {
+                    warnings.push(format!(
+                        "S3_REGION is empty; requests to {} are signed without a region",
+                        self.endpoint
+                    ));
+                }
+            }
+        }
+
+        Ok(warnings)
+    }
+}
+
+/// S3 settings that cannot work together
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StorageConsistencyError {
+    #[error("S3_ENDPOINT is not a URL with a host: {0}")]
+    InvalidEndpoint(String),
+    #[error("S3_REGION must be set for AWS endpoint {0}")]
+    MissingRegion(String),
+    #[error("S3_REGION {region} does not match endpoint {endpoint}, which is in {endpoint_region}")]
+    RegionMismatch { region: String, endpoint: String, endpoint_region: String },
+    #[error("S3_USE_PATH_STYLE=false needs an endpoint buckets can be subdomains of, not {0}")]
+    VirtualHostUnsupported(String),
+}
+
+/// Kind of service an S3 endpoint belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum S3Provider {
+    Aws,
+    /// MinIO, Ceph and other S3-compatible services
+    Compatible,
+}
+
+/// A parsed `S3_ENDPOINT`
+#[derive(Debug, Clone)]
+pub struct S3Endpoint(Url);
+
+impl S3Endpoint {
+    pub fn parse(endpoint: &str) -> Result<Self, StorageConsistencyError> {
+        Url::parse(endpoint)
+            .ok()
+            .filter(|url| url.host_str().is_some())
+            .map(Self)
+            .ok_or_else(|| StorageConsistencyError::InvalidEndpoint(endpoint.to_string()))
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.0
+    }
+
+    fn host(&self) -> String {
+        self.0.host_str().unwrap_or_default().to_ascii_lowercase()
+    }
+
+    pub fn provider(&self) -> S3Provider {
+        let host = self.host();
+        if host.ends_with(".amazonaws.com") || host.ends_with(".amazonaws.com.cn") {
+            S3Provider::Aws
+        } else {
+            S3Provider::Compatible
+        }
+    }
+
+    /// Region named by an AWS endpoint such as `s3.eu-west-1.amazonaws.com`
+    /// or `s3-eu-west-1.amazonaws.com`; the global `s3.amazonaws.com` is
+    /// `us-east-1`. `None` for other endpoints or hosts that name no region.
+    pub fn aws_region(&self) -> Option<String> {
+        if self.provider() != S3Provider::Aws {
+            return None;
+        }
+        let host = self.host();
+        let labels: Vec<&str> = host
+            .trim_end_matches(".cn")
+            .trim_end_matches(".amazonaws.com")
+            .split('.')
+            .collect();
+        let s3 = labels.iter().position(|label| label.starts_with("s3"))?;
+
+        if let Some(region) = labels[s3].strip_prefix("s3-") {
+            return match region {
+                "external-1" => Some("us-east-1".to_string()),
+                "fips" => None,
+                region => Some(region.to_string()),
+            };
+        }
+        match labels[s3 + 1..].iter().find(|label| **label != "dualstack") {
+            Some(region) => Some(region.to_string()),
+            None if labels[s3] == "s3" => Some("us-east-1".to_string()),
+            None => None,
+        }
+    }
+
+    /// Whether `<bucket>.<host>` can resolve, as virtual-hosted-style
+    /// addressing needs; IP addresses and single-label hosts like
+    /// `localhost` or `minio` cannot have bucket subdomains
+    pub fn supports_virtual_host(&self) -> bool {
+        matches!(self.0.host(), Some(url::Host::Domain(domain)) if domain.contains('.'))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct CacheSettings {
     pub redis_url: String,
     pub pool_size: u32,
+    /// Default TTL for entries whose type has no TTL of its own
     pub ttl_seconds: u64,
+    pub manifest_ttl_seconds: Option<u64>,
+    pub authz_ttl_seconds: Option<u64>,
+    pub tag_ttl_seconds: Option<u64>,
+    /// TTL for `/v2/_catalog` pages; zero disables catalog caching
+    pub catalog_ttl_seconds: u64,
+    /// Manifests larger than this are not cached
+    pub max_entry_bytes: usize,
+    /// Pre-load the most pulled manifests into the cache at startup
+    pub warmup: bool,
+    /// How many manifests the startup warmup loads
+    pub warmup_count: u32,
+}
+
+impl CacheSettings {
+    /// TTL for entries of `key_type`, falling back to `ttl_seconds`
+    pub fn ttl(&self, key_type: CacheKeyType) -> Duration {
+        let seconds = match key_type {
+            CacheKeyType::Manifest => self.manifest_ttl_seconds,
+            CacheKeyType::Authz => self.authz_ttl_seconds,
+            CacheKeyType::Tag => self.tag_ttl_seconds,
+            CacheKeyType::Catalog => Some(self.catalog_ttl_seconds),
+            _ => None,
+        };
+        Duration::from_secs(seconds.unwrap_or(self.ttl_seconds))
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, Validate)]
+#[validate(schema(function = "validate_auth_settings"))]
 pub struct AuthSettings {
     pub jwt_secret: Secret<String>,
+    /// Shortest accepted `jwt_secret`, in bytes (0 disables the check)
+    pub jwt_min_secret_bytes: usize,
     #[validate(range(min = 300))] // Minimum 5 minutes
     pub jwt_expiration_seconds: u64,
     pub refresh_token_expiration_seconds: u64,
+    /// Reject JWTs issued longer ago than this, even before they expire
+    pub jwt_max_age_seconds: Option<u64>,
+    /// Shared secret internal callers present to use token introspection
+    pub introspection_secret: Option<Secret<String>>,
+    /// Let anyone create an account via `POST /auth/register`
+    pub allow_self_registration: bool,
+    /// Usernames that cannot be registered, lowercased
+    pub reserved_usernames: Vec<String>,
+    /// Refuse organization deletion and ownership transfer without an
+    /// `X-Request-Nonce`
+    pub require_request_nonce: bool,
+    /// Whether authentication events are also written to the audit log
+    pub auth_event_destination: AuthEventDestination,
+    /// Shared secret presented in `X-Admin-Token` by administrative callers
+    pub admin_token: Option<Secret<String>>,
+    /// Failed logins from one address before a challenge is required (0 disables)
+    pub login_challenge_threshold: u32,
+    /// Window over which failed logins are counted
+    pub login_challenge_window_secs: u64,
+    /// CAPTCHA `siteverify` endpoint; challenges are only required when set
+    pub captcha_verify_url: Option<String>,
+    pub captcha_secret: Option<Secret<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+#[validate(schema(function = "validate_registry_settings"))]
+pub struct RegistrySettings {
+    /// Smallest accepted PATCH chunk when the client sends a Content-Range (0 disables the check)
+    pub min_upload_chunk_bytes: u64,
+    /// Largest accepted PATCH chunk
+    #[validate(range(min = 1))]
+    pub max_upload_chunk_bytes: u64,
+    /// How long a renamed organization's old name keeps redirecting
+    #[validate(range(min = 0))]
+    pub org_alias_grace_days: i32,
+    /// Blobs smaller than this are served buffered, larger ones are streamed
+    pub blob_stream_threshold_bytes: u64,
+    /// Hash blobs as they are served and fail the pull when the content no
+    /// longer matches its digest
+    pub verify_blob_on_read: bool,
+    /// How often tag expiry rules are evaluated
+    #[validate(range(min = 1))]
+    pub tag_expiry_interval_secs: u64,
+    /// Cache-Control max-age for manifests fetched by tag; 0 sends `no-cache`
+    pub tag_manifest_max_age_secs: u64,
+    /// Reject repository display names already used in the same organization
+    pub enforce_unique_display_names: bool,
+    /// Whether manifest and blob deletes clean up storage before responding
+    pub deletion_mode: DeletionMode,
+    /// Reject image manifests without layers; off since some artifact types have none
+    pub reject_empty_manifest_layers: bool,
+    /// Unreferenced blobs stored more recently than this are never collected
+    pub gc_blob_grace_seconds: u64,
+    /// Only organization owners and admins may create repositories, not plain members
+    pub repo_creation_requires_admin: bool,
+    /// Refuse pulls of manifests the scanner flagged as critically vulnerable,
+    /// unless the organization says otherwise
+    pub block_vulnerable_pulls: bool,
+    /// Check that every blob a manifest references is in storage before a
+    /// tag is pointed at it
+    pub verify_blobs_on_tag: bool,
+    /// Seat limit for organizations without their own `max_members`; `None` is unlimited
+    pub org_max_members: Option<i64>,
+    /// Create a repository when a manifest is first pushed to it
+    pub auto_create_repos: bool,
+    /// Refuse a push by digest when different content is already stored under that digest
+    pub enforce_manifest_immutability: bool,
+    /// Who may list repositories through `/v2/_catalog`
+    pub catalog_visibility: CatalogVisibility,
+    /// Visibility of new repositories whose creator did not choose one,
+    /// unless their organization sets its own
+    pub default_repo_visibility: RepositoryVisibility,
+    /// Most `/` separated segments a repository name may have, namespace included
+    #[validate(range(min = 1))]
+    pub max_repo_path_segments: usize,
+    /// Refuse non-http(s) organization URLs and strip control characters
+    /// from descriptions
+    pub sanitize_org_profiles: bool,
+    /// Days of pulls `/v2/_popular` ranks repositories by
+    #[validate(range(min = 1))]
+    pub popular_window_days: u32,
+    /// Start with writes refused; `PUT /admin/read-only` changes it at runtime
+    pub read_only_mode: bool,
+    /// Largest manifest accepted on push, by artifact type
+    pub manifest_size_limits: ManifestSizeLimits,
+    /// Largest total layer size of a pushed image for organizations without
+    /// their own `max_image_bytes`; `None` is unlimited
+    pub max_image_bytes: Option<u64>,
+}
+
+/// When storage is cleaned up after a manifest or blob delete
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeletionMode {
+    /// Storage is cleaned up before responding with `204 No Content`
+    Sync,
+    /// Storage is cleaned up in the background; the response is `202 Accepted`
+    Async,
+}
+
+impl std::str::FromStr for DeletionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sync" => Ok(DeletionMode::Sync),
+            "async" => Ok(DeletionMode::Async),
+            _ => Err(format!("Invalid deletion mode: {}", s)),
+        }
+    }
+}
+
+/// Who may call `/v2/_catalog`. Whoever calls it only sees the repositories
+/// they can pull from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatalogVisibility {
+    /// Anyone; anonymous callers see public repositories
+    Public,
+    /// Signed-in users and administrative callers
+    Authenticated,
+    /// Administrative callers only
+    Admin,
+}
+
+impl std::str::FromStr for CatalogVisibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "public" => Ok(CatalogVisibility::Public),
+            "authenticated" => Ok(CatalogVisibility::Authenticated),
+            "admin" => Ok(CatalogVisibility::Admin),
+            _ => Err(format!("Invalid catalog visibility: {}", s)),
+        }
+    }
+}
+
+/// Whether a repository can be pulled without being a member of its organization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepositoryVisibility {
+    Public,
+    Private,
+}
+
+impl RepositoryVisibility {
+    pub fn is_public(self) -> bool {
+        self == RepositoryVisibility::Public
+    }
+}
+
+impl std::str::FromStr for RepositoryVisibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "public" => Ok(RepositoryVisibility::Public),
+            "private" => Ok(RepositoryVisibility::Private),
+            _ => Err(format!("Invalid repository visibility: {}", s)),
+        }
+    }
 }
 
 impl Settings {
@@ -113,6 +498,47 @@ impl Settings {
                 port: 3000, // Port is now parsed from LISTEN_ADDRESS
                 api_prefix: std::env::var("API_PREFIX").unwrap_or_else(|_| "/api/v1".to_string()),
                 log_level: std::env::var("LOG_LEVEL").unwrap_or_else(|_| "debug".to_string()),
+                worker_threads: env_thread_count("TOKIO_WORKER_THREADS", 1024),
+                max_blocking_threads: env_thread_count("TOKIO_MAX_BLOCKING_THREADS", 4096),
+                enable_server_timing: std::env::var("ENABLE_SERVER_TIMING")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                enable_hsts: std::env::var("ENABLE_HSTS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                hsts_max_age_secs: std::env::var("HSTS_MAX_AGE_SECS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(31_536_000), // 1 year
+                enable_profiling: std::env::var("ENABLE_PROFILING")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                enable_compression: std::env::var("ENABLE_COMPRESSION")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                correlation_header: std::env::var("CORRELATION_HEADER")
+                    .ok()
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or_else(|| DEFAULT_CORRELATION_HEADER.to_string()),
+                shutdown_drain_delay_secs: std::env::var("SHUTDOWN_DRAIN_DELAY_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(5),
+                strict_field_selection: std::env::var("STRICT_FIELD_SELECTION")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                retry_after_jitter_percent: std::env::var("RETRY_AFTER_JITTER_PERCENT")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(crate::error::DEFAULT_RETRY_AFTER_JITTER_PERCENT),
+                trusted_proxies: std::env::var("TRUSTED_PROXIES")
+                    .map(|s| s.split(',').filter_map(|ip| ip.trim().parse().ok()).collect())
+                    .unwrap_or_default(),
             },
             database: {
                 // If DATABASE_URL is set, parse it to extract components
@@ -142,6 +568,26 @@ impl Settings {
                                 .ok()
                                 .and_then(|c| c.parse().ok())
                                 .unwrap_or(20),
+                            idle_timeout_secs: std::env::var("DATABASE_IDLE_TIMEOUT_SECS")
+                                .ok()
+                                .and_then(|c| c.parse().ok())
+                                .unwrap_or(60),
+                            max_lifetime_secs: std::env::var("DATABASE_MAX_LIFETIME_SECS")
+                                .ok()
+                                .and_then(|c| c.parse().ok())
+                                .unwrap_or(1800),
+                            health_check_interval_secs: std::env::var("DATABASE_HEALTH_CHECK_INTERVAL_SECS")
+                                .ok()
+                                .and_then(|c| c.parse().ok())
+                                .unwrap_or(30),
+                            slow_query_threshold_ms: std::env::var("SLOW_QUERY_THRESHOLD_MS")
+                                .ok()
+                                .and_then(|c| c.parse().ok())
+                                .unwrap_or(500),
+                            tenancy_mode: std::env::var("TENANCY_MODE")
+                                .ok()
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(TenancyMode::Shared),
                         }
                     } else {
                         // Fallback to individual settings if URL can't be parsed
@@ -166,6 +612,26 @@ impl Settings {
                                 .ok()
                                 .and_then(|c| c.parse().ok())
                                 .unwrap_or(20),
+                            idle_timeout_secs: std::env::var("DATABASE_IDLE_TIMEOUT_SECS")
+                                .ok()
+                                .and_then(|c| c.parse().ok())
+                                .unwrap_or(60),
+                            max_lifetime_secs: std::env::var("DATABASE_MAX_LIFETIME_SECS")
+                                .ok()
+                                .and_then(|c| c.parse().ok())
+                                .unwrap_or(1800),
+                            health_check_interval_secs: std::env::var("DATABASE_HEALTH_CHECK_INTERVAL_SECS")
+                                .ok()
+                                .and_then(|c| c.parse().ok())
+                                .unwrap_or(30),
+                            slow_query_threshold_ms: std::env::var("SLOW_QUERY_THRESHOLD_MS")
+                                .ok()
+                                .and_then(|c| c.parse().ok())
+                                .unwrap_or(500),
+                            tenancy_mode: std::env::var("TENANCY_MODE")
+                                .ok()
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(TenancyMode::Shared),
                         }
                     }
                 } else {
@@ -191,6 +657,26 @@ impl Settings {
                             .ok()
                             .and_then(|c| c.parse().ok())
                             .unwrap_or(20),
+                        idle_timeout_secs: std::env::var("DATABASE_IDLE_TIMEOUT_SECS")
+                            .ok()
+                            .and_then(|c| c.parse().ok())
+                            .unwrap_or(60),
+                        max_lifetime_secs: std::env::var("DATABASE_MAX_LIFETIME_SECS")
+                            .ok()
+                            .and_then(|c| c.parse().ok())
+                            .unwrap_or(1800),
+                        health_check_interval_secs: std::env::var("DATABASE_HEALTH_CHECK_INTERVAL_SECS")
+                            .ok()
+                            .and_then(|c| c.parse().ok())
+                            .unwrap_or(30),
+                        slow_query_threshold_ms: std::env::var("SLOW_QUERY_THRESHOLD_MS")
+                            .ok()
+                            .and_then(|c| c.parse().ok())
+                            .unwrap_or(500),
+                        tenancy_mode: std::env::var("TENANCY_MODE")
+                            .ok()
+                            .and_then(|s| s.parse().ok())
+                            .unwrap_or(TenancyMode::Shared),
                     }
                 }
             },
@@ -204,6 +690,10 @@ impl Settings {
                     .ok()
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(true),
+                filesystem_path: std::env::var("STORAGE_FILESYSTEM_PATH").ok().filter(|s| !s.is_empty()),
+                default_backend: std::env::var("STORAGE_DEFAULT_BACKEND").unwrap_or_else(|_| S3_BACKEND.to_string()),
+                routes: parse_routes(&std::env::var("STORAGE_ROUTES").unwrap_or_default())
+                    .map_err(|e| anyhow::anyhow!(e))?,
             },
             cache: CacheSettings {
                 redis_url: std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string()),
@@ -215,9 +705,32 @@ impl Settings {
                     .ok()
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(3600),
+                manifest_ttl_seconds: std::env::var("CACHE_TTL_MANIFEST").ok().and_then(|s| s.parse().ok()),
+                authz_ttl_seconds: std::env::var("CACHE_TTL_AUTHZ").ok().and_then(|s| s.parse().ok()),
+                tag_ttl_seconds: std::env::var("CACHE_TTL_TAG").ok().and_then(|s| s.parse().ok()),
+                catalog_ttl_seconds: std::env::var("CACHE_TTL_CATALOG")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(30),
+                max_entry_bytes: std::env::var("CACHE_MAX_ENTRY_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1024 * 1024), // 1 MiB
+                warmup: std::env::var("CACHE_WARMUP")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                warmup_count: std::env::var("CACHE_WARMUP_COUNT")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(100),
             },
             auth: AuthSettings {
                 jwt_secret: Secret::new(std::env::var("JWT_SECRET").unwrap_or_else(|_| "your-super-secret-key".to_string())),
+                jwt_min_secret_bytes: std::env::var("JWT_MIN_SECRET_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(DEFAULT_JWT_MIN_SECRET_BYTES),
                 jwt_expiration_seconds: std::env::var("JWT_EXPIRATION_SECONDS")
                     .ok()
                     .and_then(|s| s.parse().ok())
@@ -226,6 +739,51 @@ impl Settings {
                     .ok()
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(604800),
+                jwt_max_age_seconds: std::env::var("JWT_MAX_AGE_SECONDS").ok().and_then(|s| s.parse().ok()),
+                introspection_secret: std::env::var("INTROSPECTION_SECRET")
+                    .ok()
+                    .filter(|s| !s.is_empty())
+                    .map(Secret::new),
+                allow_self_registration: std::env::var("ALLOW_SELF_REGISTRATION")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                reserved_usernames: std::env::var("RESERVED_USERNAMES")
+                    .ok()
+                    .map(|s| {
+                        s.split(',')
+                            .map(normalize_username)
+                            .filter(|name| !name.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_else(|| DEFAULT_RESERVED_USERNAMES.iter().map(|name| name.to_string()).collect()),
+                require_request_nonce: std::env::var("REQUIRE_REQUEST_NONCE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                auth_event_destination: std::env::var("AUTH_EVENT_DESTINATION")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or_default(),
+                admin_token: std::env::var("ADMIN_TOKEN")
+                    .ok()
+                    .filter(|s| !s.is_empty())
+                    .map(Secret::new),
+                login_challenge_threshold: std::env::var("LOGIN_CHALLENGE_THRESHOLD")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(5),
+                login_challenge_window_secs: std::env::var("LOGIN_CHALLENGE_WINDOW_SECS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(900),
+                captcha_verify_url: std::env::var("CAPTCHA_VERIFY_URL")
+                    .ok()
+                    .filter(|s| !s.is_empty()),
+                captcha_secret: std::env::var("CAPTCHA_SECRET")
+                    .ok()
+                    .filter(|s| !s.is_empty())
+                    .map(Secret::new),
             },
             email: EmailSettings {
                 smtp_host: std::env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()),
@@ -247,11 +805,120 @@ impl Settings {
                     .unwrap_or(cfg!(debug_assertions)), // Use test mode in development by default
                 test_email_file: std::env::var("EMAIL_TEST_FILE").ok(),
             },
+            registry: RegistrySettings {
+                min_upload_chunk_bytes: std::env::var("MIN_UPLOAD_CHUNK_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0),
+                max_upload_chunk_bytes: std::env::var("MAX_UPLOAD_CHUNK_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(1024 * 1024 * 1024), // 1 GiB
+                org_alias_grace_days: std::env::var("ORG_ALIAS_GRACE_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(90),
+                blob_stream_threshold_bytes: std::env::var("BLOB_STREAM_THRESHOLD_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(8 * 1024 * 1024), // 8 MiB
+                verify_blob_on_read: std::env::var("VERIFY_BLOB_ON_READ")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                tag_expiry_interval_secs: std::env::var("TAG_EXPIRY_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(3600),
+                tag_manifest_max_age_secs: std::env::var("TAG_MANIFEST_MAX_AGE_SECS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0),
+                enforce_unique_display_names: std::env::var("ENFORCE_UNIQUE_DISPLAY_NAMES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                deletion_mode: std::env::var("DELETION_MODE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(DeletionMode::Async),
+                reject_empty_manifest_layers: std::env::var("REJECT_EMPTY_MANIFEST_LAYERS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                gc_blob_grace_seconds: std::env::var("GC_BLOB_GRACE_SECONDS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(3600), // 1 hour
+                repo_creation_requires_admin: std::env::var("REPO_CREATION_REQUIRES_ADMIN")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                block_vulnerable_pulls: std::env::var("BLOCK_VULNERABLE_PULLS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                verify_blobs_on_tag: std::env::var("VERIFY_BLOBS_ON_TAG")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+                org_max_members: std::env::var("ORG_MAX_MEMBERS")
+                    .ok()
+                    .and_then(|s| s.parse().ok()),
+                auto_create_repos: std::env::var("AUTO_CREATE_REPOS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+                enforce_manifest_immutability: std::env::var("ENFORCE_MANIFEST_IMMUTABILITY")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+                catalog_visibility: std::env::var("CATALOG_VISIBILITY")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(CatalogVisibility::Authenticated),
+                // A typo must not make repositories public by accident
+                default_repo_visibility: std::env::var("DEFAULT_REPO_VISIBILITY")
+                    .ok()
+                    .map(|s| s.parse())
+                    .transpose()
+                    .map_err(|e: String| anyhow::anyhow!(e))?
+                    .unwrap_or(RepositoryVisibility::Public),
+                max_repo_path_segments: std::env::var("MAX_REPO_PATH_SEGMENTS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(DEFAULT_MAX_REPO_PATH_SEGMENTS),
+                sanitize_org_profiles: std::env::var("SANITIZE_ORG_PROFILES")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(true),
+                popular_window_days: std::env::var("POPULAR_WINDOW_DAYS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(30),
+                read_only_mode: std::env::var("READ_ONLY_MODE")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(false),
+                manifest_size_limits: ManifestSizeLimits::default()
+                    .with_overrides(&std::env::var("MANIFEST_SIZE_LIMITS").unwrap_or_default())
+                    .map_err(|e| anyhow::anyhow!(e))?,
+                max_image_bytes: std::env::var("MAX_IMAGE_BYTES")
+                    .ok()
+                    .and_then(|s| s.parse().ok()),
+            },
         };
 
         settings
             .validate_all()
             .context("Configuration validation failed")?;
+        for warning in settings
+            .storage
+            .validate_consistency()
+            .context("Inconsistent S3 storage configuration")?
+        {
+            eprintln!("⚠️  {}", warning);
+        }
 
         Ok(settings)
     }
@@ -264,6 +931,7 @@ impl Settings {
         self.cache.validate()?;
         self.auth.validate()?;
         self.email.validate()?;
+        self.registry.validate()?;
         Ok(())
     }
 
@@ -271,6 +939,60 @@ impl Settings {
     pub fn server_url(&self) -> String {
         format!("http://{}:{}", self.server.bind_address, self.server.port)
     }
+
+    /// Settings operators need to confirm what was loaded. Secrets are never
+    /// included, only whether they are set, and URL passwords are masked.
+    pub fn effective_config(&self) -> serde_json::Value {
+        serde_json::json!({
+            "bind_address": self.server.bind_address,
+            "api_prefix": self.server.api_prefix,
+            "log_level": self.server.log_level,
+            "trusted_proxies": self.server.trusted_proxies,
+            "database": format!("{}:{}/{}", self.database.host, self.database.port, self.database.database_name),
+            "database_require_ssl": self.database.require_ssl,
+            "tenancy_mode": self.database.tenancy_mode.to_string(),
+            "storage_default_backend": self.storage.default_backend,
+            "storage_endpoint": redact_url(&self.storage.endpoint),
+            "storage_bucket": self.storage.bucket,
+            "storage_region": self.storage.region,
+            "storage_filesystem_path": self.storage.filesystem_path,
+            "storage_routes": self.storage.routes.len(),
+            "cache_redis_url": redact_url(&self.cache.redis_url),
+            "auth_mode": "jwt",
+            "self_registration": self.auth.allow_self_registration,
+            "admin_token_configured": self.auth.admin_token.is_some(),
+            "introspection_enabled": self.auth.introspection_secret.is_some(),
+            "captcha_enabled": self.auth.captcha_secret.is_some(),
+            "require_request_nonce": self.auth.require_request_nonce,
+            "auth_event_destination": self.auth.auth_event_destination,
+            "deletion_mode": self.registry.deletion_mode,
+            "catalog_visibility": self.registry.catalog_visibility,
+            "default_repo_visibility": self.registry.default_repo_visibility,
+            "max_repo_path_segments": self.registry.max_repo_path_segments,
+            "popular_window_days": self.registry.popular_window_days,
+            "read_only_mode": self.registry.read_only_mode,
+            "max_image_bytes": self.registry.max_image_bytes,
+        })
+    }
+
+    /// Log `effective_config` as a single structured line
+    pub fn log_effective_config(&self) {
+        tracing::info!(config = %self.effective_config(), "Effective configuration");
+    }
+}
+
+/// `url` with any password replaced; unparseable URLs are left out entirely
+/// since they may still hold credentials
+fn redact_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) => {
+            if parsed.password().is_some() {
+                let _ = parsed.set_password(Some("REDACTED"));
+            }
+            parsed.to_string()
+        }
+        Err(_) => "<unparseable>".to_string(),
+    }
 }
 
 impl DatabaseSettings {
@@ -292,18 +1014,86 @@ impl DatabaseSettings {
     }
 }
 
+/// Read a thread count from the environment, ignoring values outside `1..=max`
+/// so a bad setting falls back to Tokio's default instead of aborting startup
+fn env_thread_count(name: &str, max: usize) -> Option<usize> {
+    let value = std::env::var(name).ok()?;
+    match value.parse::<usize>() {
+        Ok(count) if (1..=max).contains(&count) => Some(count),
+        _ => {
+            eprintln!("Ignoring invalid {}={:?} (expected 1..={}), using default", name, value, max);
+            None
+        }
+    }
+}
+
 fn validate_socket_addr(addr: &str) -> Result<(), validator::ValidationError> {
     addr.parse::<SocketAddr>()
         .map(|_| ())
         .map_err(|_| validator::ValidationError::new("invalid_socket_address"))
 }
 
+fn validate_header_name(name: &str) -> Result<(), validator::ValidationError> {
+    axum::http::HeaderName::try_from(name)
+        .map(|_| ())
+        .map_err(|_| validator::ValidationError::new("invalid_header_name"))
+}
+
 fn validate_url(url: &str) -> Result<(), validator::ValidationError> {
     Url::parse(url)
         .map(|_| ())
         .map_err(|_| validator::ValidationError::new("invalid_url"))
 }
 
+/// Default `MAX_REPO_PATH_SEGMENTS`: a namespace and up to four levels below it
+pub const DEFAULT_MAX_REPO_PATH_SEGMENTS: usize = 5;
+
+/// Shortest JWT secret accepted unless `JWT_MIN_SECRET_BYTES` says otherwise;
+/// the key size RFC 7518 requires for HS256
+pub const DEFAULT_JWT_MIN_SECRET_BYTES: usize = 32;
+
+/// Check that `secret` is at least `min_bytes` long when `algorithm` signs
+/// with it. A short HMAC secret can be brute forced offline from any issued
+/// token. Asymmetric algorithms sign with a private key, not a shared secret,
+/// and are not checked.
+pub fn validate_jwt_secret(
+    algorithm: jsonwebtoken::Algorithm,
+    secret: &str,
+    min_bytes: usize,
+) -> Result<(), validator::ValidationError> {
+    use jsonwebtoken::Algorithm::{HS256, HS384, HS512};
+
+    if !matches!(algorithm, HS256 | HS384 | HS512) || secret.len() >= min_bytes {
+        return Ok(());
+    }
+    let mut error = validator::ValidationError::new("jwt_secret_too_short");
+    error.message = Some(
+        format!(
+            "JWT_SECRET is {} bytes; {:?} needs at least {} (set JWT_MIN_SECRET_BYTES to change the minimum)",
+            secret.len(),
+            algorithm,
+            min_bytes
+        )
+        .into(),
+    );
+    Err(error)
+}
+
+fn validate_auth_settings(settings: &AuthSettings) -> Result<(), validator::ValidationError> {
+    validate_jwt_secret(
+        crate::auth::JWT_ALGORITHM,
+        settings.jwt_secret.expose_secret(),
+        settings.jwt_min_secret_bytes,
+    )
+}
+
+fn validate_registry_settings(settings: &RegistrySettings) -> Result<(), validator::ValidationError> {
+    if settings.min_upload_chunk_bytes > settings.max_upload_chunk_bytes {
+        return Err(validator::ValidationError::new("min_upload_chunk_exceeds_max"));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Deserialize, Clone, Validate)]
 pub struct EmailSettings {
     pub smtp_host: String,
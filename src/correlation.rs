@@ -0,0 +1,195 @@
+// Request correlation IDs
+//
+// Every request runs with an ID, taken from the correlation header
+// (`X-Correlation-ID` unless `CORRELATION_HEADER` names another) or the trace
+// ID of a W3C `traceparent`, or generated. Log lines about the request carry
+// it, and it is echoed in the response so client reports can be matched to
+// server logs. Handlers can also take it as an `Extension<CorrelationId>`.
+use std::future::Future;
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::config::settings::ServerSettings;
+use crate::error::new_correlation_id;
+
+pub const DEFAULT_CORRELATION_HEADER: &str = "x-correlation-id";
+
+/// W3C trace context header
+pub const TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
+
+/// Longest client-supplied ID that is accepted
+const MAX_CORRELATION_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+/// ID of the request being handled, if any
+pub fn current() -> Option<String> {
+    CORRELATION_ID.try_with(|id| id.clone()).ok()
+}
+
+/// ID of the request being handled, or a fresh one outside a request
+pub fn current_or_new() -> String {
+    current().unwrap_or_else(new_correlation_id)
+}
+
+/// Correlation ID of a request, kept in its extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationId(pub String);
+
+/// Run `fut` with `id` as the current correlation ID
+pub async fn scope<F: Future>(id: String, fut: F) -> F::Output {
+    CORRELATION_ID.scope(id, fut).await
+}
+
+/// Which header carries the correlation ID
+#[derive(Debug, Clone)]
+pub struct CorrelationConfig {
+    pub header: HeaderName,
+}
+
+impl CorrelationConfig {
+    pub fn new(header: HeaderName) -> Self {
+        Self { header }
+    }
+
+    /// Header from `CORRELATION_HEADER`; settings validation has checked it
+    /// is a valid header name
+    pub fn from_settings(settings: &ServerSettings) -> Self {
+        let header = HeaderName::try_from(settings.correlation_header.as_str())
+            .unwrap_or_else(|_| HeaderName::from_static(DEFAULT_CORRELATION_HEADER));
+        Self::new(header)
+    }
+}
+
+impl Default for CorrelationConfig {
+    fn default() -> Self {
+        Self::new(HeaderName::from_static(DEFAULT_CORRELATION_HEADER))
+    }
+}
+
+/// A W3C `traceparent`: `00-<trace id>-<parent id>-<flags>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceParent {
+    /// 32 lowercase hex digits
+    pub trace_id: String,
+    /// 16 lowercase hex digits
+    pub parent_id: String,
+    /// 2 lowercase hex digits
+    pub flags: String,
+}
+
+impl TraceParent {
+    /// Parse a version 00 `traceparent`; all-zero IDs are invalid
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+        let (version, trace_id, parent_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() || version != "00" {
+            return None;
+        }
+
+        if !is_hex_id(trace_id, 32) || !is_hex_id(parent_id, 16) || !is_hex(flags, 2) {
+            return None;
+        }
+
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            parent_id: parent_id.to_string(),
+            flags: flags.to_string(),
+        })
+    }
+
+    /// Start a new trace, reusing `id` as trace ID when it is a valid one
+    pub fn start(id: &str) -> Self {
+        let trace_id = if is_hex_id(id, 32) { id.to_string() } else { random_hex(32) };
+        Self { trace_id, parent_id: random_hex(16), flags: "01".to_string() }
+    }
+
+    /// The same trace with this server as the parent of what follows
+    pub fn child(&self) -> Self {
+        Self { trace_id: self.trace_id.clone(), parent_id: random_hex(16), flags: self.flags.clone() }
+    }
+}
+
+impl std::fmt::Display for TraceParent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "00-{}-{}-{}", self.trace_id, self.parent_id, self.flags)
+    }
+}
+
+fn is_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+/// Trace and parent IDs are lowercase hex and not all zeros
+fn is_hex_id(s: &str, len: usize) -> bool {
+    is_hex(s, len) && s.chars().any(|c| c != '0')
+}
+
+/// `len` random lowercase hex digits, at most 32
+fn random_hex(len: usize) -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..len].to_string()
+}
+
+/// Characters accepted in client-supplied IDs besides ASCII letters and
+/// digits; enough for UUIDs, ULIDs and base64 but nothing that could break
+/// a log line or a header
+const CORRELATION_ID_PUNCTUATION: &[char] = &['-', '_', '.', ':', '+', '/', '='];
+
+/// Client-supplied IDs are kept only if short and made of safe characters,
+/// since they end up in logs and are echoed back; others are replaced by a
+/// generated ID
+pub fn accept_client_id(value: &HeaderValue) -> Option<String> {
+    let id = value.to_str().ok()?.trim();
+    let valid = !id.is_empty()
+        && id.len() <= MAX_CORRELATION_ID_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || CORRELATION_ID_PUNCTUATION.contains(&c));
+    valid.then(|| id.to_string())
+}
+
+/// Set response header `name` to `value`, or log and leave it out if
+/// `value` cannot be a header; the response is sent either way
+fn insert_response_header(response: &mut Response, name: HeaderName, value: &str) {
+    match HeaderValue::from_str(value) {
+        Ok(value) => {
+            response.headers_mut().insert(name, value);
+        }
+        Err(e) => tracing::warn!("Not sending {} header {:?}: {}", name, value, e),
+    }
+}
+
+/// Assigns each request its correlation ID and returns it in the response,
+/// along with a `traceparent` continuing the client's trace or starting one
+pub async fn correlation_middleware(
+    State(config): State<CorrelationConfig>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let trace_parent = request
+        .headers()
+        .get(&TRACEPARENT)
+        .and_then(|value| value.to_str().ok())
+        .and_then(TraceParent::parse);
+    let id = request
+        .headers()
+        .get(&config.header)
+        .and_then(accept_client_id)
+        .or_else(|| trace_parent.as_ref().map(|parent| parent.trace_id.clone()))
+        .unwrap_or_else(new_correlation_id);
+    let trace_parent = match trace_parent {
+        Some(parent) => parent.child(),
+        None => TraceParent::start(&id),
+    };
+
+    request.extensions_mut().insert(CorrelationId(id.clone()));
+    let mut response = scope(id.clone(), next.run(request)).await;
+    insert_response_header(&mut response, config.header, &id);
+    insert_response_header(&mut response, TRACEPARENT, &trace_parent.to_string());
+    response
+}
@@ -9,6 +9,7 @@ pub struct BlobUpload {
     pub uuid: String,
     pub repository_id: i64,
     pub user_id: Option<String>,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -26,6 +27,7 @@ pub struct User {
     pub username: String,
     pub email: String,
     pub password_hash: String,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -45,7 +47,9 @@ pub struct Organization {
     pub description: Option<String>,
     pub website_url: Option<String>,
     pub avatar_url: Option<String>,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::timestamp")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -55,7 +59,9 @@ pub struct OrganizationMember {
     pub organization_id: i64,
     pub user_id: i64,
     pub role: String,
+    #[serde(with = "crate::utils::timestamp")]
     pub joined_at: DateTime<Utc>,
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub invited_at: Option<DateTime<Utc>>,
     pub invited_by: Option<i64>,
 }
@@ -68,9 +74,16 @@ pub struct Repository {
     pub name: String,
     pub description: Option<String>,
     pub is_public: bool, // Changed from visibility to match database schema
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::timestamp")]
     pub updated_at: DateTime<Utc>,
     pub created_by: Option<i64>,
+    #[sqlx(default)]
+    pub display_name: Option<String>,
+    /// Only semantic version tags (and `latest`) may be pushed
+    #[sqlx(default)]
+    pub require_semver: bool,
 }
 
 // Permission models
@@ -113,6 +126,7 @@ pub struct ResourcePermission {
     pub user_id: Option<i64>,
     pub organization_id: Option<i64>,
     pub permission_id: i64,
+    #[serde(with = "crate::utils::timestamp")]
     pub granted_at: DateTime<Utc>,
     pub granted_by: i64,
 }
@@ -125,6 +139,7 @@ pub struct Manifest {
     pub digest: String,
     pub media_type: String,
     pub size: i64,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -143,7 +158,9 @@ pub struct Tag {
     pub repository_id: i64,
     pub name: String,
     pub manifest_id: i64,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::timestamp")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -161,9 +178,13 @@ pub struct ApiKey {
     pub user_id: i64,
     pub name: String,
     pub key_hash: String,
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub expires_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::timestamp")]
     pub updated_at: DateTime<Utc>,
     pub is_active: bool,
 }
@@ -180,8 +201,11 @@ pub struct NewApiKey {
 pub struct ApiKeyInfo {
     pub id: i64,
     pub name: String,
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub expires_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
     pub is_active: bool,
 }
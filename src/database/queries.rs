@@ -4,6 +4,9 @@ use tracing::{info, error};
 
 use super::models::*;
 use crate::models::repository_with_org::{RepositoryWithOrg, RepositoryWithOrgRow};
+use crate::models::user::normalize_email;
+use crate::db::QueryTimer;
+use crate::storage::upload_session::UploadSession;
 
 // Blob upload queries (simplified)
 pub async fn create_blob_upload(
@@ -12,12 +15,13 @@ pub async fn create_blob_upload(
     repository_id: i64,
     user_id: Option<&str>,
 ) -> Result<BlobUpload> {
+    let _timer = QueryTimer::start("create_blob_upload");
     info!("🔧 Creating blob upload: uuid={}, repository_id={}, user_id={:?}", uuid, repository_id, user_id);
     
     let result = sqlx::query_as::<_, BlobUpload>(
         "INSERT INTO blob_uploads (uuid, repository_id, user_id)
-         VALUES ($1, $2, $3)
-         RETURNING id, uuid, repository_id, user_id, created_at, completed_at",
+         VALUES ($1, $2, $3::bigint)
+         RETURNING id, uuid, repository_id, user_id::text AS user_id, created_at",
     )
     .bind(uuid)
     .bind(repository_id)
@@ -33,26 +37,115 @@ pub async fn create_blob_upload(
     result.context("Failed to create blob upload record")
 }
 
-pub async fn update_blob_upload_completed(
-    pool: &PgPool,
-    uuid: &str,
-) -> Result<()> {
+pub async fn update_blob_upload_completed<'c, E>(executor: E, uuid: &str) -> Result<()>
+where
+    E: sqlx::Executor<'c, Database = Postgres>,
+{
+    let _timer = QueryTimer::start("update_blob_upload_completed");
     sqlx::query(
         "UPDATE blob_uploads SET completed_at = NOW() WHERE uuid = $1"
     )
     .bind(uuid)
-    .execute(pool)
+    .execute(executor)
     .await
     .context("Failed to update blob upload completion")?;
     
     Ok(())
 }
 
+/// Store the current state of a chunked upload
+pub async fn save_upload_session<'c, E>(executor: E, session: &UploadSession) -> Result<()>
+where
+    E: sqlx::Executor<'c, Database = Postgres>,
+{
+    let _timer = QueryTimer::start("save_upload_session");
+    sqlx::query(
+        "UPDATE blob_uploads
+         SET repository_name = $2, multipart_upload_id = $3, parts = $4::jsonb,
+             pending_bytes = $5, updated_at = NOW()
+         WHERE uuid = $1",
+    )
+    .bind(&session.uuid)
+    .bind(&session.repository)
+    .bind(&session.multipart_upload_id)
+    .bind(serde_json::to_string(&session.parts)?)
+    .bind(session.pending_bytes as i64)
+    .execute(executor)
+    .await
+    .context("Failed to save upload session")?;
+
+    Ok(())
+}
+
+type UploadSessionRow = (String, Option<String>, Option<String>, String, i64);
+
+fn upload_session_from_row(row: UploadSessionRow) -> Result<UploadSession> {
+    let (uuid, repository, multipart_upload_id, parts, pending_bytes) = row;
+    Ok(UploadSession {
+        uuid,
+        repository: repository.unwrap_or_default(),
+        multipart_upload_id,
+        parts: serde_json::from_str(&parts).context("Invalid upload session parts")?,
+        pending_bytes: pending_bytes as u64,
+    })
+}
+
+/// State of an unfinished chunked upload, if there is one with this UUID
+pub async fn get_upload_session(pool: &PgPool, uuid: &str) -> Result<Option<UploadSession>> {
+    let _timer = QueryTimer::start("get_upload_session");
+    let row = sqlx::query_as::<_, UploadSessionRow>(
+        "SELECT uuid, repository_name, multipart_upload_id, parts::text, pending_bytes
+         FROM blob_uploads
+         WHERE uuid = $1 AND completed_at IS NULL",
+    )
+    .bind(uuid)
+    .fetch_optional(pool)
+    .await
+    .context("Failed to load upload session")?;
+
+    row.map(upload_session_from_row).transpose()
+}
+
+/// Like `get_upload_session`, but locks the session row until `tx` ends so
+/// concurrent chunks for one upload are applied one at a time, each seeing
+/// the offset the previous one left
+pub async fn lock_upload_session(
+    tx: &mut Transaction<'_, Postgres>,
+    uuid: &str,
+) -> Result<Option<UploadSession>> {
+    let _timer = QueryTimer::start("lock_upload_session");
+    let row = sqlx::query_as::<_, UploadSessionRow>(
+        "SELECT uuid, repository_name, multipart_upload_id, parts::text, pending_bytes
+         FROM blob_uploads
+         WHERE uuid = $1 AND completed_at IS NULL
+         FOR UPDATE",
+    )
+    .bind(uuid)
+    .fetch_optional(&mut **tx)
+    .await
+    .context("Failed to lock upload session")?;
+
+    row.map(upload_session_from_row).transpose()
+}
+
+/// Forget a cancelled upload
+pub async fn delete_upload_session(pool: &PgPool, uuid: &str) -> Result<()> {
+    let _timer = QueryTimer::start("delete_upload_session");
+    sqlx::query("DELETE FROM blob_uploads WHERE uuid = $1")
+        .bind(uuid)
+        .execute(pool)
+        .await
+        .context("Failed to delete upload session")?;
+
+    Ok(())
+}
+
 // Repository queries
 pub async fn repository_exists(
     pool: &PgPool,
     repository_id: i64,
 ) -> Result<bool> {
+    let _timer = QueryTimer::start("repository_exists");
     let exists = sqlx::query_scalar::<_, bool>(
         "SELECT EXISTS(SELECT 1 FROM repositories WHERE id = $1)"
     )
@@ -68,6 +161,7 @@ pub async fn get_repository_id_by_name(
     pool: &PgPool,
     repository_name: &str,
 ) -> Result<Option<i64>> {
+    let _timer = QueryTimer::start("get_repository_id_by_name");
     // Handle both formats: "repo" and "org/repo"
     let (org_name, repo_name) = if repository_name.contains('/') {
         let parts: Vec<&str> = repository_name.splitn(2, '/').collect();
@@ -109,13 +203,14 @@ pub async fn create_user(
     email: &str,
     password_hash: &str,
 ) -> Result<User> {
+    let _timer = QueryTimer::start("create_user");
     sqlx::query_as::<_, User>(
         "INSERT INTO users (username, email, password_hash)
          VALUES ($1, $2, $3)
          RETURNING *",
     )
     .bind(username)
-    .bind(email)
+    .bind(normalize_email(email))
     .bind(password_hash)
     .fetch_one(pool)
     .await
@@ -123,6 +218,7 @@ pub async fn create_user(
 }
 
 pub async fn get_user_by_id(pool: &PgPool, user_id: i64) -> Result<Option<User>> {
+    let _timer = QueryTimer::start("get_user_by_id");
     sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
         .bind(user_id)
         .fetch_optional(pool)
@@ -139,6 +235,7 @@ pub async fn create_organization(
     website_url: Option<&str>,
     creator_id: i64,
 ) -> Result<Organization> {
+    let _timer = QueryTimer::start("create_organization");
     // Create organization
     let org = sqlx::query_as::<_, Organization>(
         "INSERT INTO organizations (name, display_name, description, website_url)
@@ -176,6 +273,7 @@ pub async fn create_repository(
     description: Option<&str>,
     visibility: &str,
 ) -> Result<Repository> {
+    let _timer = QueryTimer::start("create_repository");
     sqlx::query_as::<_, Repository>(
         "INSERT INTO repositories (organization_id, name, description, visibility)
          VALUES ($1, $2, $3, $4)
@@ -191,6 +289,7 @@ pub async fn create_repository(
 }
 
 pub async fn get_repository_with_org(pool: &PgPool, repo_id: i64) -> Result<Option<RepositoryWithOrg>> {
+    let _timer = QueryTimer::start("get_repository_with_org");
     let row = sqlx::query_as::<_, RepositoryWithOrgRow>(
         "SELECT 
             r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
@@ -215,11 +314,13 @@ pub async fn check_permission(
     resource_id: i64,
     _required_permission: &str,
 ) -> Result<bool> {
+    let _timer = QueryTimer::start("check_permission");
     // Only check if user is member of the organization that owns the resource
     let org_permission = sqlx::query_scalar::<_, bool>(
         "SELECT EXISTS (
             SELECT 1 FROM organization_members om
             WHERE om.user_id = $1
+            AND (om.expires_at IS NULL OR om.expires_at > NOW())
             AND om.organization_id = (
                 CASE $2
                     WHEN 'Organization' THEN $3
@@ -1,16 +1,125 @@
-use crate::config::settings::Settings;
+use crate::config::settings::{DatabaseSettings, Settings};
+use crate::server_timing::{TimingGuard, TimingMetric};
 use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::migrate::Migrator;
 use sqlx::{postgres::PgPoolOptions, PgPool};
-use std::time::Duration;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{ready, Poll};
+use std::time::{Duration, Instant};
+
+/// Migrations embedded from `migrations/`, run at startup
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Slow query threshold in milliseconds, 0 when slow queries are not logged
+static SLOW_QUERY_THRESHOLD_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Log database calls taking at least `threshold`; zero turns logging off
+pub fn set_slow_query_threshold(threshold: Duration) {
+    SLOW_QUERY_THRESHOLD_MS.store(threshold.as_millis() as u64, Ordering::Relaxed);
+}
+
+pub fn slow_query_threshold() -> Option<Duration> {
+    match SLOW_QUERY_THRESHOLD_MS.load(Ordering::Relaxed) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms)),
+    }
+}
+
+/// Times one database call until dropped: the time counts towards the
+/// request's Server-Timing `db` entry, and a call over the slow query
+/// threshold is logged. `query` names the call and must not contain
+/// parameter values.
+pub struct QueryTimer {
+    query: &'static str,
+    start: Instant,
+    _timing: TimingGuard,
+}
+
+impl QueryTimer {
+    pub fn start(query: &'static str) -> Self {
+        Self {
+            query,
+            start: Instant::now(),
+            _timing: TimingGuard::start(TimingMetric::Db),
+        }
+    }
+}
+
+impl Drop for QueryTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        if let Some(threshold) = slow_query_threshold() {
+            if elapsed >= threshold {
+                tracing::warn!(
+                    query = self.query,
+                    duration_ms = elapsed.as_millis() as u64,
+                    correlation_id = %crate::correlation::current_or_new(),
+                    "Slow query"
+                );
+            }
+        }
+    }
+}
+
+/// Await a database call under a `QueryTimer`
+pub async fn timed<F: Future>(query: &'static str, fut: F) -> F::Output {
+    let _timer = QueryTimer::start(query);
+    fut.await
+}
+
+/// `timed` as a method, so a query reads
+/// `sqlx::query(..).fetch_one(pool).timed("name").await`
+pub trait Timed: Future + Sized {
+    fn timed(self, query: &'static str) -> TimedQuery<Self> {
+        TimedQuery { fut: Box::pin(self), query, timer: None }
+    }
+}
+
+impl<F: Future> Timed for F {}
+
+/// A database call under a `QueryTimer` from its first poll until it completes
+pub struct TimedQuery<F> {
+    fut: Pin<Box<F>>,
+    query: &'static str,
+    timer: Option<QueryTimer>,
+}
+
+impl<F: Future> Future for TimedQuery<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<F::Output> {
+        let this = &mut *self;
+        let query = this.query;
+        this.timer.get_or_insert_with(|| QueryTimer::start(query));
+        let output = ready!(this.fut.as_mut().poll(cx));
+        this.timer = None;
+        Poll::Ready(output)
+    }
+}
+
+/// Pool options for the configured database. Connections are pinged before
+/// being handed out, so ones left stale by a Postgres restart or a dropped
+/// TCP connection are discarded and replaced instead of failing the query.
+/// In schema tenancy mode they are also pointed at the current tenant's schema.
+pub fn pool_options(database: &DatabaseSettings) -> PgPoolOptions {
+    let options = PgPoolOptions::new()
+        .max_connections(database.max_connections)
+        .min_connections(database.min_connections)
+        .acquire_timeout(Duration::from_secs(30))
+        .test_before_acquire(true)
+        .idle_timeout(Duration::from_secs(database.idle_timeout_secs))
+        .max_lifetime(Duration::from_secs(database.max_lifetime_secs));
+    crate::tenant::scoped_pool_options(options, database.tenancy_mode)
+}
 
 pub async fn create_pool(settings: &Settings) -> Result<PgPool> {
+    set_slow_query_threshold(Duration::from_millis(settings.database.slow_query_threshold_ms));
+
     // Create connection pool with configuration
-    let pool = PgPoolOptions::new()
-        .max_connections(settings.database.max_connections)
-        .min_connections(settings.database.min_connections)
-        .acquire_timeout(Duration::from_secs(30))
-        .idle_timeout(Duration::from_secs(60))
-        .max_lifetime(Duration::from_secs(3600))
+    let pool = pool_options(&settings.database)
         .connect(&settings.database.connection_string())
         .await
         .context("Failed to create database connection pool")?;
@@ -21,10 +130,13 @@ pub async fn create_pool(settings: &Settings) -> Result<PgPool> {
         .context("Failed to acquire initial database connection")?;
 
     // Run migrations
-    sqlx::migrate!("./migrations")
+    MIGRATOR
         .run(&pool)
         .await
         .context("Failed to run database migrations")?;
+    crate::tenant::sync_tenant_schemas(&pool, settings.database.tenancy_mode)
+        .await
+        .context("Failed to provision tenant schemas")?;
 
     // Test database connection
     pool.acquire()
@@ -34,6 +146,33 @@ pub async fn create_pool(settings: &Settings) -> Result<PgPool> {
     Ok(pool)
 }
 
+/// Periodically check that the database is reachable and log pool usage.
+/// Logs once when the database becomes unreachable and once when it recovers.
+pub async fn run_pool_health_probe(pool: PgPool, interval: Duration) {
+    let mut healthy = true;
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        let result = sqlx::query_scalar::<_, i32>("SELECT 1").fetch_one(&pool).await;
+        match (result, healthy) {
+            (Ok(_), true) => {
+                tracing::debug!(size = pool.size(), idle = pool.num_idle(), "Database pool healthy");
+            }
+            (Ok(_), false) => {
+                healthy = true;
+                tracing::info!(size = pool.size(), idle = pool.num_idle(), "Database connection recovered");
+            }
+            (Err(e), true) => {
+                healthy = false;
+                tracing::error!(size = pool.size(), idle = pool.num_idle(), "Database health check failed: {}", e);
+            }
+            (Err(_), false) => {}
+        }
+    }
+}
+
 // Transaction helper function
 pub async fn transaction<'a, F, R>(pool: &PgPool, f: F) -> Result<R>
 where
@@ -70,3 +209,61 @@ pub async fn exists<'a>(pool: &PgPool, table: &str, column: &str, value: &str) -
 
     Ok(exists)
 }
+
+/// A migration recorded in `_sqlx_migrations`
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    #[serde(with = "crate::utils::timestamp")]
+    pub installed_on: chrono::DateTime<chrono::Utc>,
+    /// False when the migration failed part way
+    pub success: bool,
+}
+
+/// A migration embedded in this build that the database has not recorded
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingMigration {
+    pub version: i64,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    /// Latest successfully applied version, `None` on an empty database
+    pub schema_version: Option<i64>,
+    pub applied: Vec<AppliedMigration>,
+    pub pending: Vec<PendingMigration>,
+    /// Every embedded migration has been applied successfully
+    pub up_to_date: bool,
+}
+
+/// Compare the migrations recorded in the database with those in `migrator`
+pub async fn migration_status(pool: &PgPool, migrator: &Migrator) -> Result<MigrationStatus> {
+    let table_exists: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    let applied: Vec<AppliedMigration> = if table_exists {
+        sqlx::query_as(
+            "SELECT version, description, installed_on, success FROM _sqlx_migrations ORDER BY version",
+        )
+        .fetch_all(pool)
+        .await?
+    } else {
+        Vec::new()
+    };
+
+    let pending: Vec<PendingMigration> = migrator
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .filter(|m| !applied.iter().any(|a| a.version == m.version && a.success))
+        .map(|m| PendingMigration { version: m.version, description: m.description.to_string() })
+        .collect();
+
+    Ok(MigrationStatus {
+        schema_version: applied.iter().filter(|a| a.success).map(|a| a.version).max(),
+        up_to_date: pending.is_empty() && applied.iter().all(|a| a.success),
+        applied,
+        pending,
+    })
+}
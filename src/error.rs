@@ -0,0 +1,238 @@
+// Typed application errors for the management API
+use axum::{
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use rand::Rng;
+use std::sync::atomic::{AtomicU8, Ordering};
+use thiserror::Error;
+
+/// Message clients see in place of internal error details
+pub const INTERNAL_ERROR_MESSAGE: &str = "Internal server error";
+
+/// Default `RETRY_AFTER_JITTER_PERCENT`
+pub const DEFAULT_RETRY_AFTER_JITTER_PERCENT: u8 = 20;
+
+/// Set once at startup; errors are built without access to the app state
+static RETRY_AFTER_JITTER_PERCENT: AtomicU8 = AtomicU8::new(DEFAULT_RETRY_AFTER_JITTER_PERCENT);
+
+/// Randomize `Retry-After` on back-off responses by up to `percent` either
+/// side of the base delay; values above 100 are capped
+pub fn set_retry_after_jitter_percent(percent: u8) {
+    RETRY_AFTER_JITTER_PERCENT.store(percent.min(100), Ordering::Relaxed);
+}
+
+/// `base` seconds moved by a random amount within `percent` of it, never
+/// below one second, so clients throttled together do not retry together
+pub fn jittered_retry_after(base: u64, percent: u8) -> u64 {
+    let band = base as f64 * f64::from(percent.min(100)) / 100.0;
+    if band < 0.5 {
+        return base;
+    }
+    let seconds = rand::thread_rng().gen_range(base as f64 - band..=base as f64 + band);
+    (seconds.round() as u64).max(1)
+}
+
+/// Errors with a fixed HTTP status and a stable machine readable code.
+///
+/// Internal helpers keep returning `anyhow::Result`; they return one of these
+/// (via `.into()`) when the caller should see something other than the
+/// handler's default status.
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Conflict(String),
+    /// The resource changed since the version the client based its update on (412)
+    #[error("{0}")]
+    PreconditionFailed(String),
+    /// Adding a member would exceed the organization's seat limit (403)
+    #[error("Organization has used all {max_members} of its seats")]
+    SeatLimitExceeded { max_members: i64 },
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    /// The request cannot be served for now, such as while the server is
+    /// starting or read-only; the client should retry after the delay (503)
+    #[error("{message}")]
+    Unavailable { code: &'static str, message: String, retry_after_seconds: u64 },
+}
+
+impl AppError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            AppError::SeatLimitExceeded { .. } => StatusCode::FORBIDDEN,
+            AppError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Unavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "BAD_REQUEST",
+            AppError::Forbidden(_) => "FORBIDDEN",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::Conflict(_) => "CONFLICT",
+            AppError::PreconditionFailed(_) => "PRECONDITION_FAILED",
+            AppError::SeatLimitExceeded { .. } => "SEAT_LIMIT_EXCEEDED",
+            AppError::Database(_) => "DATABASE_ERROR",
+            AppError::Unavailable { code, .. } => *code,
+        }
+    }
+
+    /// Seconds a client should wait before retrying, for back-off errors
+    pub fn retry_after_seconds(&self) -> Option<u64> {
+        match self {
+            AppError::Unavailable { retry_after_seconds, .. } => Some(*retry_after_seconds),
+            _ => None,
+        }
+    }
+
+    /// `retry_after_seconds` with the configured jitter applied
+    fn jittered_retry_after_seconds(&self) -> Option<u64> {
+        let percent = RETRY_AFTER_JITTER_PERCENT.load(Ordering::Relaxed);
+        self.retry_after_seconds().map(|base| jittered_retry_after(base, percent))
+    }
+
+    /// Status and JSON body, for handlers that return `(StatusCode, Json<Value>)`
+    /// The Retry-After header is only added by `into_response`.
+    pub fn response_parts(&self) -> (StatusCode, Json<serde_json::Value>) {
+        self.response_parts_with(self.jittered_retry_after_seconds())
+    }
+
+    fn response_parts_with(&self, retry_after: Option<u64>) -> (StatusCode, Json<serde_json::Value>) {
+        if let AppError::Database(e) = self {
+            return (self.status_code(), Json(internal_error_body(self.code(), e)));
+        }
+
+        let body = match retry_after {
+            // Back-off errors carry structured retry info for programmatic clients
+            Some(retry_after_seconds) => serde_json::json!({
+                "error": {
+                    "code": self.code(),
+                    "message": self.to_string(),
+                    "retry_after_seconds": retry_after_seconds
+                }
+            }),
+            None => serde_json::json!({
+                "error": self.to_string(),
+                "code": self.code()
+            }),
+        };
+        (self.status_code(), Json(body))
+    }
+
+    /// The error in the OCI distribution format, for clients under `/v2`
+    pub fn into_registry_response(self) -> Response {
+        let retry_after = self.jittered_retry_after_seconds();
+        let code = match self.status_code() {
+            StatusCode::SERVICE_UNAVAILABLE => "UNAVAILABLE",
+            _ => self.code(),
+        };
+        let detail = match retry_after {
+            Some(retry_after_seconds) => serde_json::json!({ "retry_after_seconds": retry_after_seconds }),
+            None => serde_json::json!({}),
+        };
+        let body = serde_json::json!({
+            "errors": [{
+                "code": code,
+                "message": self.to_string(),
+                "detail": detail
+            }]
+        });
+        with_retry_after((self.status_code(), Json(body)).into_response(), retry_after)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        // Drawn once so the header and body agree
+        let retry_after = self.jittered_retry_after_seconds();
+        with_retry_after(self.response_parts_with(retry_after).into_response(), retry_after)
+    }
+}
+
+fn with_retry_after(mut response: Response, retry_after: Option<u64>) -> Response {
+    if let Some(seconds) = retry_after {
+        response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(seconds));
+    }
+    response
+}
+
+/// Map an error returned by an `*_internal` helper to a handler response.
+/// Typed `AppError`s keep their own status and code; anything else gets `fallback`.
+/// Raw `sqlx` errors that bubbled up through `?` are sanitized like `AppError::Database`.
+pub fn error_response(err: &anyhow::Error, fallback: StatusCode) -> (StatusCode, Json<serde_json::Value>) {
+    if let Some(app_error) = err.downcast_ref::<AppError>() {
+        return app_error.response_parts();
+    }
+
+    match err.downcast_ref::<sqlx::Error>() {
+        Some(db_error) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(internal_error_body("DATABASE_ERROR", db_error)),
+        ),
+        None => (
+            fallback,
+            Json(serde_json::json!({
+                "error": err.to_string()
+            })),
+        ),
+    }
+}
+
+/// Log an internal error in full and build the generic body returned instead.
+/// The correlation id is in both, so a client report can be matched to the log.
+fn internal_error_body(code: &str, detail: &dyn std::fmt::Display) -> serde_json::Value {
+    let correlation_id = crate::correlation::current_or_new();
+    tracing::error!(correlation_id = %correlation_id, "{}: {}", code, detail);
+
+    serde_json::json!({
+        "error": INTERNAL_ERROR_MESSAGE,
+        "code": code,
+        "correlation_id": correlation_id
+    })
+}
+
+/// Identifier tying a response to the server-side log lines about it
+pub fn new_correlation_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// 404 for paths no route matches. Registry clients get the OCI error
+/// format under `/v2`; everything else gets the structured API error body.
+pub async fn route_not_found(uri: Uri) -> Response {
+    let path = uri.path();
+    let correlation_id = crate::correlation::current_or_new();
+    tracing::debug!(correlation_id = %correlation_id, "No route for {}", path);
+
+    let body = if path == "/v2" || path.starts_with("/v2/") {
+        serde_json::json!({
+            "errors": [{
+                "code": "UNSUPPORTED",
+                "message": "Route not found",
+                "detail": { "path": path, "correlation_id": correlation_id }
+            }]
+        })
+    } else {
+        serde_json::json!({
+            "error": {
+                "code": "NOT_FOUND",
+                "message": "Route not found",
+                "correlation_id": correlation_id
+            }
+        })
+    };
+
+    (StatusCode::NOT_FOUND, Json(body)).into_response()
+}
@@ -0,0 +1,498 @@
+// Operator endpoints for inspecting a running deployment
+use std::collections::{BTreeMap, BTreeSet};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::is_admin_request;
+use crate::db::{migration_status, Timed, MIGRATOR};
+use crate::handlers::docker_registry_v2::manifest_blob_descriptors;
+use crate::models::organizations::seats_remaining;
+use crate::tenant::TenancyMode;
+use crate::AppState;
+
+/// 403 for a request without the admin token
+pub(crate) fn admin_token_required() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({ "error": "Admin token required" })),
+    )
+        .into_response()
+}
+
+/// Applied and pending migrations - GET /admin/migrations (admin only)
+pub async fn migrations(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if !is_admin_request(&headers, &state.config.auth) {
+        return admin_token_required();
+    }
+
+    match migration_status(&state.db_pool, &MIGRATOR).await {
+        Ok(status) => (StatusCode::OK, Json(status)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to read migration status: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to read migration status" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// A repository's reference to a stored blob or manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobReference {
+    pub organization: String,
+    pub repository: String,
+    pub digest: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepositoryUsage {
+    pub repository: String,
+    /// Distinct blobs and manifests the repository references
+    pub blob_count: u64,
+    /// Size of everything the repository references, shared or not
+    pub logical_bytes: u64,
+    /// Share of stored bytes, splitting each shared blob evenly between the
+    /// repositories referencing it
+    pub attributed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OrganizationUsage {
+    pub organization: String,
+    pub logical_bytes: u64,
+    pub attributed_bytes: u64,
+    pub repositories: Vec<RepositoryUsage>,
+    /// Active members
+    pub members: u64,
+    /// Seat limit, the organization's own or ORG_MAX_MEMBERS; `None` is unlimited
+    pub max_members: Option<i64>,
+    pub seats_remaining: Option<i64>,
+}
+
+/// An organization's member count and its own seat limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrganizationSeats {
+    pub organization: String,
+    pub members: i64,
+    pub max_members: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageUsageReport {
+    /// Bytes stored once per distinct digest; the sum of all attributed bytes
+    pub total_bytes: u64,
+    pub organizations: Vec<OrganizationUsage>,
+}
+
+impl StorageUsageReport {
+    pub fn compute(references: impl IntoIterator<Item = BlobReference>) -> Self {
+        // digest -> (size, repositories referencing it)
+        let mut blobs: BTreeMap<String, (u64, BTreeSet<(String, String)>)> = BTreeMap::new();
+        for reference in references {
+            let (size, owners) = blobs.entry(reference.digest).or_default();
+            *size = (*size).max(reference.size);
+            owners.insert((reference.organization, reference.repository));
+        }
+
+        let mut total_bytes = 0;
+        let mut repositories: BTreeMap<(String, String), RepositoryUsage> = BTreeMap::new();
+        for (size, owners) in blobs.into_values() {
+            total_bytes += size;
+            // The remainder goes to the first repositories so shares add up to the size
+            let count = owners.len() as u64;
+            for (index, owner) in owners.into_iter().enumerate() {
+                let usage = repositories.entry(owner).or_default();
+                usage.blob_count += 1;
+                usage.logical_bytes += size;
+                usage.attributed_bytes += size / count + u64::from((index as u64) < size % count);
+            }
+        }
+
+        let mut organizations: Vec<OrganizationUsage> = Vec::new();
+        for ((organization, repository), usage) in repositories {
+            if organizations.last().map(|o| &o.organization) != Some(&organization) {
+                organizations.push(OrganizationUsage { organization, ..OrganizationUsage::default() });
+            }
+            let org = organizations.last_mut().expect("pushed above");
+            org.logical_bytes += usage.logical_bytes;
+            org.attributed_bytes += usage.attributed_bytes;
+            org.repositories.push(RepositoryUsage { repository, ..usage });
+        }
+
+        Self { total_bytes, organizations }
+    }
+
+    /// Add member counts and seat limits, listing organizations that store
+    /// nothing as well. Organizations without a limit of their own get
+    /// `default_max_members`.
+    pub fn add_seats(&mut self, seats: impl IntoIterator<Item = OrganizationSeats>, default_max_members: Option<i64>) {
+        for seats in seats {
+            let index = match self.organizations.binary_search_by(|o| o.organization.cmp(&seats.organization)) {
+                Ok(index) => index,
+                Err(index) => {
+                    let organization = OrganizationUsage { organization: seats.organization, ..OrganizationUsage::default() };
+                    self.organizations.insert(index, organization);
+                    index
+                }
+            };
+            let usage = &mut self.organizations[index];
+            let max_members = seats.max_members.or(default_max_members);
+            usage.members = seats.members.max(0) as u64;
+            usage.max_members = max_members;
+            usage.seats_remaining = seats_remaining(max_members, seats.members);
+        }
+    }
+}
+
+/// Active member count and seat limit of every organization
+pub async fn organization_seats(pool: &sqlx::PgPool) -> Result<Vec<OrganizationSeats>, sqlx::Error> {
+    let rows: Vec<(String, i64, Option<i32>)> = sqlx::query_as(
+        "SELECT o.name, COUNT(om.id), o.max_members
+         FROM organizations o
+         LEFT JOIN organization_members om
+           ON om.organization_id = o.id AND (om.expires_at IS NULL OR om.expires_at > NOW())
+         GROUP BY o.id",
+    )
+    .fetch_all(pool)
+    .timed("organization_seats")
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(organization, members, max_members)| OrganizationSeats {
+            organization,
+            members,
+            max_members: max_members.map(i64::from),
+        })
+        .collect())
+}
+
+/// Every manifest of every tenant and the config and layer blobs it lists,
+/// per repository
+pub async fn blob_references(pool: &sqlx::PgPool, mode: TenancyMode) -> Result<Vec<BlobReference>, sqlx::Error> {
+    let manifests = crate::tenant::across_tenants(pool, mode, || {
+        sqlx::query_as::<_, (String, String, String, i64, Option<String>)>(
+            "SELECT o.name, r.name, m.digest, m.size, m.content
+             FROM manifests m
+             JOIN repositories r ON r.id = m.repository_id
+             JOIN organizations o ON o.id = r.organization_id",
+        )
+        .fetch_all(pool)
+    })
+    .timed("blob_references")
+    .await?;
+
+    let mut references = Vec::new();
+    for (organization, repository, digest, size, content) in manifests {
+        let blobs = content.as_deref().map(manifest_blob_descriptors).unwrap_or_default();
+        for (digest, size) in std::iter::once((digest, size.max(0) as u64)).chain(blobs) {
+            references.push(BlobReference {
+                organization: organization.clone(),
+                repository: repository.clone(),
+                digest,
+                size,
+            });
+        }
+    }
+    Ok(references)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StorageUsageQuery {
+    /// Only list this organization; shares are still computed over all repositories
+    pub organization: Option<String>,
+}
+
+/// Stored bytes per organization and repository, and each organization's
+/// seats - GET /admin/storage-usage (admin only)
+pub async fn storage_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<StorageUsageQuery>,
+) -> Response {
+    if !is_admin_request(&headers, &state.config.auth) {
+        return admin_token_required();
+    }
+
+    let references = match blob_references(&state.db_pool, state.config.database.tenancy_mode).await {
+        Ok(references) => references,
+        Err(e) => {
+            tracing::error!("Failed to compute storage usage: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to compute storage usage" })),
+            )
+                .into_response();
+        }
+    };
+
+    let mut report = StorageUsageReport::compute(references);
+    match organization_seats(&state.db_pool).await {
+        Ok(seats) => report.add_seats(seats, state.config.registry.org_max_members),
+        Err(e) => tracing::warn!("Failed to count organization seats: {}", e),
+    }
+    if let Some(organization) = &query.organization {
+        report.organizations.retain(|o| &o.organization == organization);
+    }
+    (StatusCode::OK, Json(report)).into_response()
+}
+
+/// Outcome of scanning one manifest, as reported by an external scanner
+#[derive(Debug, Deserialize)]
+pub struct ScanResult {
+    /// `organization/repository`
+    pub repository: String,
+    pub digest: String,
+    /// Whether critical vulnerabilities were found
+    pub critical: bool,
+    /// Returned to clients whose pull is refused, e.g. the CVE IDs found
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Record a scan result - POST /admin/scan-results (admin only)
+/// Flagged manifests can no longer be pulled where BLOCK_VULNERABLE_PULLS or
+/// the organization's setting says so; a later clean result lifts the flag.
+pub async fn record_scan_result(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(result): Json<ScanResult>,
+) -> Response {
+    if !is_admin_request(&headers, &state.config.auth) {
+        return admin_token_required();
+    }
+
+    let (organization, repository) = match result.repository.split_once('/') {
+        Some(parts) => parts,
+        None => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "repository must be organization/repository" })),
+            )
+                .into_response()
+        }
+    };
+
+    let tenant = match crate::tenant::organization_tenant(&state.db_pool, state.config.database.tenancy_mode, organization).await {
+        Ok(Some(tenant)) => tenant,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": "Manifest not found" }))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to record scan result: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to record scan result" })),
+            )
+                .into_response();
+        }
+    };
+
+    let updated = tenant
+        .scope(
+            sqlx::query(
+                "UPDATE manifests m
+                 SET critical_vulnerabilities = $4, vulnerability_reason = $5, scanned_at = NOW()
+                 FROM repositories r
+                 JOIN organizations o ON o.id = r.organization_id
+                 WHERE m.repository_id = r.id AND o.name = $1 AND r.name = $2 AND m.digest = $3",
+            )
+            .bind(organization)
+            .bind(repository)
+            .bind(&result.digest)
+            .bind(result.critical)
+            .bind(result.reason.as_deref().filter(|_| result.critical))
+            .execute(&state.db_pool),
+        )
+        .timed("record_scan_result")
+        .await;
+
+    match updated {
+        Ok(done) if done.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Manifest not found" })),
+        )
+            .into_response(),
+        Ok(_) => {
+            tracing::info!(
+                repository = %result.repository,
+                digest = %result.digest,
+                critical = result.critical,
+                "Recorded scan result"
+            );
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "repository": result.repository,
+                    "digest": result.digest,
+                    "critical": result.critical,
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to record scan result: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to record scan result" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeatLimitRequest {
+    /// Largest number of members; `null` falls back to ORG_MAX_MEMBERS
+    pub max_members: Option<i32>,
+}
+
+/// Set an organization's seat limit - PUT /admin/organizations/:name/seats (admin only)
+/// Lowering it below the current member count removes no one; adding
+/// members is refused until enough have left.
+pub async fn set_seat_limit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(req): Json<SeatLimitRequest>,
+) -> Response {
+    if !is_admin_request(&headers, &state.config.auth) {
+        return admin_token_required();
+    }
+    if req.max_members.map_or(false, |max_members| max_members < 0) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "max_members must not be negative" })),
+        )
+            .into_response();
+    }
+
+    let updated = sqlx::query("UPDATE organizations SET max_members = $2 WHERE name = $1")
+        .bind(&name)
+        .bind(req.max_members)
+        .execute(&state.db_pool)
+        .timed("set_seat_limit")
+        .await;
+
+    match updated {
+        Ok(done) if done.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Organization not found" })),
+        )
+            .into_response(),
+        Ok(_) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "organization": name, "max_members": req.max_members })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to set seat limit: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to set seat limit" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImageSizeLimitRequest {
+    /// Largest total layer size of a pushed image; `null` falls back to MAX_IMAGE_BYTES
+    pub max_image_bytes: Option<i64>,
+}
+
+/// Set an organization's image size limit - PUT /admin/organizations/:name/image-size-limit
+/// (admin only). Images already stored are kept; only later pushes are checked.
+pub async fn set_image_size_limit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(req): Json<ImageSizeLimitRequest>,
+) -> Response {
+    if !is_admin_request(&headers, &state.config.auth) {
+        return admin_token_required();
+    }
+    if req.max_image_bytes.map_or(false, |max_image_bytes| max_image_bytes <= 0) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "max_image_bytes must be positive" })),
+        )
+            .into_response();
+    }
+
+    let updated = sqlx::query("UPDATE organizations SET max_image_bytes = $2 WHERE name = $1")
+        .bind(&name)
+        .bind(req.max_image_bytes)
+        .execute(&state.db_pool)
+        .timed("set_image_size_limit")
+        .await;
+
+    match updated {
+        Ok(done) if done.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Organization not found" })),
+        )
+            .into_response(),
+        Ok(_) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "organization": name, "max_image_bytes": req.max_image_bytes })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to set image size limit: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to set image size limit" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReadOnlyRequest {
+    pub enabled: bool,
+}
+
+fn read_only_status(state: &AppState) -> Response {
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "read_only": state.read_only.is_enabled() })),
+    )
+        .into_response()
+}
+
+/// Whether the registry is read-only - GET /admin/read-only (admin only)
+pub async fn get_read_only(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if !is_admin_request(&headers, &state.config.auth) {
+        return admin_token_required();
+    }
+    read_only_status(&state)
+}
+
+/// Switch read-only mode on or off - PUT /admin/read-only (admin only)
+/// Applies to this instance only and lasts until it restarts, after which
+/// READ_ONLY_MODE decides again.
+pub async fn set_read_only(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<ReadOnlyRequest>,
+) -> Response {
+    if !is_admin_request(&headers, &state.config.auth) {
+        return admin_token_required();
+    }
+
+    state.read_only.set(req.enabled);
+    tracing::warn!(read_only = req.enabled, "Read-only mode changed by an administrator");
+    read_only_status(&state)
+}
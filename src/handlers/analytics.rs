@@ -0,0 +1,105 @@
+// Organization pull and push analytics
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{Authorization, authorization::Bearer};
+use axum_extra::TypedHeader;
+use chrono::{NaiveDate, Utc};
+
+use crate::auth::extract_user_id_dual;
+use crate::db::Timed;
+use crate::error::{error_response, AppError};
+use crate::handlers::audit::authorize_by_name;
+use crate::models::analytics::{
+    summarize_activity, ActivityCount, AnalyticsGranularity, AnalyticsQuery, OrganizationAnalytics,
+};
+use crate::models::organizations::OrganizationAction;
+use crate::AppState;
+
+// Pull and push counts of an organization's repositories over time
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{name}/analytics",
+    tag = "organizations",
+    params(
+        ("name" = String, Path, description = "Organization name"),
+        ("from" = Option<String>, Query, description = "First day, YYYY-MM-DD (default: 30 days before `to`)"),
+        ("to" = Option<String>, Query, description = "Last day, YYYY-MM-DD (default: today, UTC)"),
+        ("granularity" = Option<AnalyticsGranularity>, Query, description = "Bucket width: day or week (default: day)")
+    ),
+    responses(
+        (status = 200, description = "Activity per time bucket, top repositories and unique pullers", body = OrganizationAnalytics),
+        (status = 400, description = "Invalid date range"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only organization owners and admins can view analytics"),
+        (status = 404, description = "Organization not found"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_organization_analytics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(name): Path<String>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Response {
+    let user_id = match extract_user_id_dual(auth, &headers, &state.config.auth, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(serde_json::json!({ "error": "Unauthorized" }))).into_response();
+        }
+    };
+
+    let range = match query.resolve(Utc::now().date_naive()) {
+        Ok(range) => range,
+        Err(message) => return AppError::BadRequest(message).into_response(),
+    };
+
+    let organization_id = match authorize_by_name(
+        &state.db_pool,
+        &name,
+        user_id,
+        OrganizationAction::ViewAnalytics,
+        "Only organization owners and admins can view analytics",
+    )
+    .await
+    {
+        Ok(id) => id,
+        Err(e) => return error_response(&e, StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+    };
+
+    // Counted per day, repository and actor in the database, into buckets here
+    let rows = sqlx::query_as::<_, (NaiveDate, String, String, Option<String>, i64)>(
+        "SELECT a.day, r.name, a.action, a.actor, a.count
+         FROM registry_activity_daily a
+         JOIN repositories r ON r.id = a.repository_id
+         WHERE a.organization_id = $1 AND a.day BETWEEN $2 AND $3",
+    )
+    .bind(organization_id)
+    .bind(range.from)
+    .bind(range.to)
+    .fetch_all(&state.db_pool)
+    .timed("get_organization_analytics")
+    .await;
+
+    let counts: Vec<ActivityCount> = match rows {
+        Ok(rows) => rows
+            .into_iter()
+            .filter_map(|(day, repository, action, actor, count)| {
+                Some(ActivityCount { day, repository, action: action.parse().ok()?, actor, count })
+            })
+            .collect(),
+        Err(e) => {
+            tracing::error!("Failed to load analytics for organization '{}': {}", name, e);
+            return AppError::Database(e).into_response();
+        }
+    };
+
+    let analytics: OrganizationAnalytics = summarize_activity(name, range, &counts);
+    Json(analytics).into_response()
+}
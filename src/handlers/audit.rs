@@ -0,0 +1,238 @@
+// Audit log recording and export
+use anyhow::Result;
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{Authorization, authorization::Bearer};
+use axum_extra::TypedHeader;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+
+use crate::auth::extract_user_id_dual;
+use crate::db::Timed;
+use crate::error::{error_response, AppError};
+use crate::models::audit::{AuditExportQuery, AuditLogEntry};
+use crate::models::organizations::{OrganizationAction, OrganizationRole};
+use crate::AppState;
+
+/// Entries fetched from the database per streamed chunk
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+/// Append an entry to the audit log. Failures are logged and swallowed: the
+/// action being audited has already happened and must not be reported as failed.
+pub async fn record_audit_event(
+    pool: &PgPool,
+    organization_id: Option<i64>,
+    actor_id: Option<i64>,
+    action: &str,
+    target: Option<(&str, String)>,
+    details: serde_json::Value,
+) {
+    let (target_type, target_id) = match target {
+        Some((target_type, target_id)) => (Some(target_type), Some(target_id)),
+        None => (None, None),
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO audit_logs (organization_id, actor_id, action, target_type, target_id, details)
+         VALUES ($1, $2, $3, $4, $5, $6::jsonb)",
+    )
+    .bind(organization_id)
+    .bind(actor_id)
+    .bind(action)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(details.to_string())
+    .execute(pool)
+    .timed("record_audit_event")
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to record audit event '{}': {}", action, e);
+    }
+}
+
+// Export an organization's audit log as JSON lines
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{name}/audit/export",
+    tag = "organizations",
+    params(
+        ("name" = String, Path, description = "Organization name"),
+        ("from" = Option<String>, Query, description = "Only entries at or after this RFC 3339 timestamp"),
+        ("to" = Option<String>, Query, description = "Only entries before this RFC 3339 timestamp")
+    ),
+    responses(
+        (status = 200, description = "Newline-delimited JSON, one AuditLogEntry per line", content_type = "application/x-ndjson"),
+        (status = 400, description = "Invalid date range"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only organization owners and admins can export the audit log"),
+        (status = 404, description = "Organization not found"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn export_audit_log(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(name): Path<String>,
+    Query(query): Query<AuditExportQuery>,
+) -> Response {
+    let user_id = match extract_user_id_dual(auth, &headers, &state.config.auth, &state.db_pool, state.cache.as_ref()).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(serde_json::json!({ "error": "Unauthorized" }))).into_response();
+        }
+    };
+
+    if !query.is_valid_range() {
+        return AppError::BadRequest("'from' must be earlier than 'to'".to_string()).into_response();
+    }
+
+    let organization_id = match authorize_by_name(
+        &state.db_pool,
+        &name,
+        user_id,
+        OrganizationAction::ExportAuditLog,
+        "Only organization owners and admins can export the audit log",
+    )
+    .await
+    {
+        Ok(id) => id,
+        Err(e) => return error_response(&e, StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+    };
+
+    // Page through the log by ID so memory use stays flat however large it is
+    let pool = state.db_pool.clone();
+    let stream = futures::stream::try_unfold(Some(0i64), move |after_id| {
+        let pool = pool.clone();
+        async move {
+            let after_id = match after_id {
+                Some(id) => id,
+                None => return Ok::<_, sqlx::Error>(None),
+            };
+
+            let entries = fetch_batch(&pool, organization_id, &query, after_id).await?;
+            if entries.is_empty() {
+                return Ok(None);
+            }
+
+            let next = if (entries.len() as i64) < EXPORT_BATCH_SIZE {
+                None
+            } else {
+                entries.last().map(|entry| entry.id)
+            };
+            let chunk: String = entries.iter().map(AuditLogEntry::to_json_line).collect();
+            Ok(Some((Bytes::from(chunk), next)))
+        }
+    });
+
+    let disposition = format!("attachment; filename=\"{}-audit.jsonl\"", name);
+    let mut response = Response::new(Body::from_stream(stream));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/x-ndjson"),
+    );
+    if let Ok(value) = header::HeaderValue::from_str(&disposition) {
+        response.headers_mut().insert(header::CONTENT_DISPOSITION, value);
+    }
+    response
+}
+
+/// Resolve the ID of organization `name` if the user's role there permits
+/// `action`, or fail with 403 and `denied`
+pub(crate) async fn authorize_by_name(
+    pool: &PgPool,
+    name: &str,
+    user_id: i64,
+    action: OrganizationAction,
+    denied: &str,
+) -> Result<i64> {
+    let row = sqlx::query_as::<_, (i64, Option<String>)>(
+        "SELECT o.id, om.role
+         FROM organizations o
+         LEFT JOIN organization_members om ON om.organization_id = o.id AND om.user_id = $2
+              AND (om.expires_at IS NULL OR om.expires_at > NOW())
+         WHERE o.name = $1",
+    )
+    .bind(name)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .timed("authorize_by_name")
+    .await?;
+
+    let (organization_id, role) = match row {
+        Some(row) => row,
+        None => return Err(AppError::NotFound(format!("Organization '{}' not found", name)).into()),
+    };
+
+    let allowed = role
+        .and_then(|r| r.parse::<OrganizationRole>().ok())
+        .map(|r| r.allows(action))
+        .unwrap_or(false);
+    if !allowed {
+        return Err(AppError::Forbidden(denied.to_string()).into());
+    }
+
+    Ok(organization_id)
+}
+
+#[derive(FromRow)]
+struct AuditLogRow {
+    id: i64,
+    organization_id: Option<i64>,
+    actor_id: Option<i64>,
+    action: String,
+    target_type: Option<String>,
+    target_id: Option<String>,
+    details: String,
+    created_at: DateTime<Utc>,
+}
+
+async fn fetch_batch(
+    pool: &PgPool,
+    organization_id: i64,
+    query: &AuditExportQuery,
+    after_id: i64,
+) -> std::result::Result<Vec<AuditLogEntry>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, AuditLogRow>(
+        "SELECT id, organization_id, actor_id, action, target_type, target_id,
+                details::text AS details, created_at
+         FROM audit_logs
+         WHERE organization_id = $1
+           AND id > $2
+           AND ($3::timestamptz IS NULL OR created_at >= $3)
+           AND ($4::timestamptz IS NULL OR created_at < $4)
+         ORDER BY id
+         LIMIT $5",
+    )
+    .bind(organization_id)
+    .bind(after_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(EXPORT_BATCH_SIZE)
+    .fetch_all(pool)
+    .timed("fetch_batch")
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| AuditLogEntry {
+            id: row.id,
+            organization_id: row.organization_id,
+            actor_id: row.actor_id,
+            action: row.action,
+            target_type: row.target_type,
+            target_id: row.target_id,
+            details: serde_json::from_str(&row.details).unwrap_or(serde_json::Value::Null),
+            created_at: row.created_at,
+        })
+        .collect())
+}
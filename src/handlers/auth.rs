@@ -1,19 +1,32 @@
+use crate::db::Timed;
 use crate::database::models::{NewUser, User};
+use crate::auth_events::{emit, AuthEvent, AuthEventKind};
+use crate::error::AppError;
+use crate::login_throttle::{client_ip, LoginGate};
 use crate::models::api_key::ApiKey;
+use crate::models::organizations::{OrganizationPermissions, OrganizationRole};
+use crate::models::session::{SessionId, SessionResponse};
+use crate::nonce::{issue_nonce, NonceAction, NONCE_TTL};
+use crate::models::user::{normalize_email, normalize_username, validate_username, UpdateProfileRequest, UserResponse};
+use crate::utils::avatar::{sniff_image_type, user_avatar_key, user_avatar_url, validate_avatar, AvatarError};
+use crate::utils::fields::{project, FieldsQuery};
+use crate::config::settings::AuthSettings;
 use crate::AppState;
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
-use axum::{extract::State, http::{StatusCode, HeaderMap}, response::IntoResponse, Json};
+use axum::{extract::{ConnectInfo, Query, State}, http::{StatusCode, HeaderMap}, response::IntoResponse, Json};
 use axum_extra::headers::{authorization::Bearer, Authorization};
 use axum_extra::TypedHeader;
 use jsonwebtoken::{encode, EncodingKey, Header};
 use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use validator::{Validate, ValidationErrors};
 use chrono::{Duration, Utc};
 use uuid::Uuid;
+use std::net::SocketAddr;
 use rand;
 use sha2;
 use hex;
@@ -21,7 +34,7 @@ use hex;
 /// User registration request
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RegisterRequest {
-    /// Username for the new account
+    /// Username for the new account (3-39 letters, digits, `-` or `_`; stored lowercased)
     username: String,
     /// Email address for the new account
     email: String,
@@ -40,6 +53,9 @@ pub struct LoginRequest {
     username: String,
     /// User's password
     password: String,
+    /// CAPTCHA token, required after repeated failed logins from the same address
+    #[serde(default)]
+    challenge_token: Option<String>,
 }
 
 /// Authentication response with JWT token
@@ -88,6 +104,15 @@ pub struct VerifyOtpRequest {
 pub struct Claims {
     pub sub: String, // user id
     pub exp: usize,  // expiration time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>, // issued at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<i64>, // session id
+}
+
+/// User-Agent header of a request, used to label the session it starts
+fn user_agent(headers: &HeaderMap) -> Option<&str> {
+    headers.get(axum::http::header::USER_AGENT).and_then(|h| h.to_str().ok())
 }
 
 /// Register a new user
@@ -98,15 +123,41 @@ pub struct Claims {
     request_body = RegisterRequest,
     responses(
         (status = 201, description = "User successfully registered", body = AuthResponse),
+        (status = 400, description = "Invalid, reserved or malformed username, email or password"),
+        (status = 403, description = "Self-registration is disabled and no valid admin token was given"),
         (status = 409, description = "User already exists"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn register(
     State(state): State<AppState>,
-    Json(req): Json<RegisterRequest>,
+    headers: HeaderMap,
+    Json(mut req): Json<RegisterRequest>,
 ) -> impl IntoResponse {
+    if !crate::auth::registration_allowed(&headers, &state.config.auth) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Self-registration is disabled; ask an administrator to create your account"
+            })),
+        );
+    }
+
     // Input validation for registration request
+    req.email = normalize_email(&req.email);
+    req.username = normalize_username(&req.username);
+
+    if let Err(error) = validate_username(&req.username, &state.config.auth.reserved_usernames) {
+        let mut details = ValidationErrors::new();
+        details.add("username", error);
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Validation failed",
+                "details": details
+            })),
+        );
+    }
     
     // Validate password length (minimum 8 characters)
     if req.password.len() < 8 {
@@ -203,17 +254,23 @@ pub async fn register(
     }
     
     // Check if user already exists by email (unique constraint)
-    let existing_user = sqlx::query_as!(User, "SELECT * FROM users WHERE email = $1", req.email)
+    let existing_user = sqlx::query_as!(User, "SELECT * FROM users WHERE LOWER(email) = $1", normalize_email(&req.email))
         .fetch_optional(&state.db_pool)
+        .timed("register: SELECT users")
         .await;
 
     if let Ok(Some(_)) = existing_user {
-        return (
-            StatusCode::CONFLICT,
-            Json(serde_json::json!({
-                "error": "User with this email already exists"
-            })),
-        );
+        return AppError::Conflict("User with this email already exists".to_string()).response_parts();
+    }
+
+    let username_taken = sqlx::query_scalar::<_, i64>("SELECT id FROM users WHERE LOWER(username) = $1")
+        .bind(&req.username)
+        .fetch_optional(&state.db_pool)
+        .timed("register: SELECT users")
+        .await;
+
+    if let Ok(Some(_)) = username_taken {
+        return AppError::Conflict("Username already exists".to_string()).response_parts();
     }
 
     // Hash password using Argon2
@@ -250,6 +307,7 @@ pub async fn register(
         new_user.password_hash,
     )
     .fetch_one(&state.db_pool)
+    .timed("register: INSERT users")
     .await
     {
         Ok(user) => user,
@@ -257,12 +315,7 @@ pub async fn register(
             tracing::error!("Database insertion failed: {}", e);
             // Check if error is due to duplicate username (if constraint exists)
             if e.to_string().contains("duplicate key") {
-                return (
-                    StatusCode::CONFLICT,
-                    Json(serde_json::json!({
-                        "error": "Username already exists"
-                    })),
-                );
+                return AppError::Conflict("Username already exists".to_string()).response_parts();
             }
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -273,14 +326,29 @@ pub async fn register(
         }
     };
 
+    let sid = match crate::auth::create_session(&state.db_pool, user.id, user_agent(&headers)).await {
+        Ok(sid) => sid,
+        Err(e) => {
+            tracing::error!("Failed to record session: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Failed to create authentication token"
+                })),
+            );
+        }
+    };
+
     // Generate JWT token with 24-hour expiration
     let claims = Claims {
         sub: user.id.to_string(),
         exp: (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp() as usize,
+        iat: Some(chrono::Utc::now().timestamp() as usize),
+        sid: Some(sid),
     };
 
     let token = match encode(
-        &Header::default(),
+        &Header::new(crate::auth::JWT_ALGORITHM),
         &claims,
         &EncodingKey::from_secret(state.config.auth.jwt_secret.expose_secret().as_bytes()),
     ) {
@@ -296,6 +364,8 @@ pub async fn register(
         }
     };
 
+    emit(&state, AuthEvent::new(AuthEventKind::TokenIssued).user(user.id).reason("register")).await;
+
     // Return success response with token
     (
         StatusCode::CREATED,
@@ -315,18 +385,48 @@ pub async fn register(
     responses(
         (status = 200, description = "Login successful", body = AuthResponse),
         (status = 401, description = "Invalid credentials"),
+        (status = 403, description = "Challenge required or failed (CHALLENGE_REQUIRED / CHALLENGE_FAILED)"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn login(
     State(state): State<AppState>,
+    headers: HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
     Json(req): Json<LoginRequest>,
 ) -> impl IntoResponse {
+    // Repeated failures from this address must pass a challenge first
+    let client_ip = client_ip(&headers, peer.map(|ConnectInfo(addr)| addr), &state.config.server.trusted_proxies);
+    match state.login_throttle.check(&client_ip, req.challenge_token.as_deref()).await {
+        LoginGate::Allowed => {}
+        LoginGate::ChallengeRequired => {
+            emit(&state, AuthEvent::new(AuthEventKind::LoginFailure).reason("challenge_required")).await;
+            return (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({
+                    "error": "Too many failed login attempts; complete the challenge and send its challenge_token",
+                    "code": "CHALLENGE_REQUIRED"
+                })),
+            );
+        }
+        LoginGate::ChallengeFailed => {
+            emit(&state, AuthEvent::new(AuthEventKind::LoginFailure).reason("challenge_failed")).await;
+            return (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({
+                    "error": "Challenge verification failed",
+                    "code": "CHALLENGE_FAILED"
+                })),
+            );
+        }
+    }
+
     // Find user by email or username
     let user = if !req.email.is_empty() {
         // Try to find user by email
-        match sqlx::query_as!(User, "SELECT * FROM users WHERE email = $1", req.email)
+        match sqlx::query_as!(User, "SELECT * FROM users WHERE LOWER(email) = $1", normalize_email(&req.email))
             .fetch_optional(&state.db_pool)
+            .timed("login: SELECT users")
             .await
         {
             Ok(Some(user)) => Some(user),
@@ -342,8 +442,9 @@ pub async fn login(
         }
     } else if !req.username.is_empty() {
         // Try to find user by username
-        match sqlx::query_as!(User, "SELECT * FROM users WHERE username = $1", req.username)
+        match sqlx::query_as!(User, "SELECT * FROM users WHERE LOWER(username) = $1", normalize_username(&req.username))
             .fetch_optional(&state.db_pool)
+            .timed("login: SELECT users")
             .await
         {
             Ok(Some(user)) => Some(user),
@@ -365,6 +466,8 @@ pub async fn login(
     let user = match user {
         Some(user) => user,
         None => {
+            state.login_throttle.record_failure(&client_ip);
+            emit(&state, AuthEvent::new(AuthEventKind::LoginFailure).reason("unknown_user")).await;
             return (
                 StatusCode::UNAUTHORIZED,
                 Json(serde_json::json!({
@@ -391,6 +494,8 @@ pub async fn login(
         .verify_password(req.password.as_bytes(), &parsed_hash)
         .is_err()
     {
+        state.login_throttle.record_failure(&client_ip);
+        emit(&state, AuthEvent::new(AuthEventKind::LoginFailure).user(user.id).reason("invalid_password")).await;
         return (
             StatusCode::UNAUTHORIZED,
             Json(serde_json::json!({
@@ -398,15 +503,31 @@ pub async fn login(
             })),
         );
     }
+    state.login_throttle.record_success(&client_ip);
+
+    let sid = match crate::auth::start_session(&state.db_pool, user.id, user_agent(&headers)).await {
+        Ok(sid) => sid,
+        Err(e) => {
+            tracing::error!("Failed to record session: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Failed to create authentication token"
+                })),
+            );
+        }
+    };
 
     // Generate JWT token
     let claims = Claims {
         sub: user.id.to_string(),
         exp: (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp() as usize,
+        iat: Some(chrono::Utc::now().timestamp() as usize),
+        sid,
     };
 
     let token = match encode(
-        &Header::default(),
+        &Header::new(crate::auth::JWT_ALGORITHM),
         &claims,
         &EncodingKey::from_secret(state.config.auth.jwt_secret.expose_secret().as_bytes()),
     ) {
@@ -421,6 +542,7 @@ pub async fn login(
         }
     };
 
+    emit(&state, AuthEvent::new(AuthEventKind::LoginSuccess).user(user.id)).await;
     (
         StatusCode::OK,
         Json(serde_json::json!({
@@ -433,8 +555,12 @@ pub async fn login(
 #[utoipa::path(
     get,
     path = "/api/v1/auth/me",
+    params(
+        ("fields" = Option<String>, Query, description = "Comma separated user fields to return, e.g. id,username,email")
+    ),
     responses(
         (status = 200, description = "User information retrieved successfully", body = UserResponse),
+        (status = 400, description = "Unknown field selected while STRICT_FIELD_SELECTION is enabled"),
         (status = 401, description = "Unauthorized"),
     ),
     security(
@@ -444,7 +570,8 @@ pub async fn login(
 pub async fn me(
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     headers: HeaderMap,
-    State(state): State<AppState>
+    State(state): State<AppState>,
+    Query(fields): Query<FieldsQuery>,
 ) -> impl IntoResponse {
     // Add debug logging
     if let Some(ref auth_header) = auth {
@@ -457,7 +584,7 @@ pub async fn me(
     let user_id = match crate::auth::extract_user_id_dual(
         auth,
         &headers,
-        state.config.auth.jwt_secret.expose_secret().as_bytes(),
+        &state.config.auth,
         &state.db_pool,
         state.cache.as_ref()
     ).await {
@@ -481,22 +608,37 @@ pub async fn me(
         id: i64,
         username: String,
         email: String,
+        display_name: Option<String>,
+        bio: Option<String>,
+        avatar_url: Option<String>,
     }
 
-    match sqlx::query_as::<_, UserInfo>("SELECT id, username, email FROM users WHERE id = $1")
+    match sqlx::query_as::<_, UserInfo>("SELECT id, username, email, display_name, bio, avatar_url FROM users WHERE id = $1")
         .bind(user_id)
         .fetch_optional(&state.db_pool)
+        .timed("me")
         .await
     {
-        Ok(Some(user)) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
+        Ok(Some(user)) => {
+            let user = serde_json::json!({
                 "id": user.id,
                 "username": user.username,
                 "email": user.email,
-                "created_at": chrono::Utc::now()  // Adding created_at as expected by test
-            })),
-        ),
+                "display_name": user.display_name,
+                "bio": user.bio,
+                "avatar_url": user.avatar_url,
+                "created_at": crate::utils::timestamp::format(&chrono::Utc::now())  // Adding created_at as expected by test
+            });
+            match project(&user, &fields, state.config.server.strict_field_selection) {
+                Ok(user) => (StatusCode::OK, Json(user)),
+                Err(e) => (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": e.to_string()
+                    })),
+                ),
+            }
+        }
         Ok(None) => (
             StatusCode::NOT_FOUND,
             Json(serde_json::json!({
@@ -515,6 +657,256 @@ pub async fn me(
     }
 }
 
+/// Permissions of the current user in every organization they belong to
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PermissionsResponse {
+    pub organizations: Vec<OrganizationPermissions>,
+}
+
+/// Get the current user's allowed actions per organization
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/me/permissions",
+    responses(
+        (status = 200, description = "Allowed actions per organization", body = PermissionsResponse),
+        (status = 401, description = "Unauthorized"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn my_permissions(
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>
+) -> impl IntoResponse {
+    let user_id = match crate::auth::extract_user_id_dual(
+        auth,
+        &headers,
+        &state.config.auth,
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (status, Json(serde_json::json!({ "error": "Unauthorized" }))).into_response();
+        }
+    };
+
+    // Expired memberships grant nothing, so they are left out
+    let memberships = sqlx::query_as::<_, (i64, String, String)>(
+        "SELECT o.id, o.name, om.role
+         FROM organization_members om
+         JOIN organizations o ON o.id = om.organization_id
+         WHERE om.user_id = $1 AND (om.expires_at IS NULL OR om.expires_at > NOW())
+         ORDER BY o.name"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db_pool)
+    .timed("my_permissions")
+    .await;
+
+    match memberships {
+        Ok(rows) => {
+            let organizations = rows
+                .into_iter()
+                .filter_map(|(organization_id, organization, role)| {
+                    let role = role.parse::<OrganizationRole>().ok()?;
+                    Some(OrganizationPermissions {
+                        organization_id,
+                        organization,
+                        role: role.to_string(),
+                        actions: role.allowed_actions(),
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(PermissionsResponse { organizations })).into_response()
+        }
+        Err(e) => crate::error::error_response(&anyhow::Error::from(e), StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+    }
+}
+
+/// The session or API key a request was authenticated with
+fn current_session(
+    auth: Option<&TypedHeader<Authorization<Bearer>>>,
+    headers: &HeaderMap,
+    settings: &AuthSettings,
+) -> Option<SessionCredential> {
+    if let Some(api_key) = headers.get("x-api-key").and_then(|h| h.to_str().ok()) {
+        return Some(SessionCredential::ApiKey(crate::auth::hash_api_key(api_key)));
+    }
+    let token = auth?.token();
+    if token.starts_with("ak_") {
+        return Some(SessionCredential::ApiKey(crate::auth::hash_api_key(token)));
+    }
+    crate::auth::verify_token_with_settings(token, settings).ok()?.sid.map(SessionCredential::Session)
+}
+
+enum SessionCredential {
+    Session(i64),
+    ApiKey(String),
+}
+
+/// List the current user's active sessions and API keys
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/me/sessions",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Active sessions and API keys, most recently used first", body = [SessionResponse]),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_sessions(
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SessionResponse>>, StatusCode> {
+    let current = current_session(auth.as_ref(), &headers, &state.config.auth);
+    let user_id = crate::auth::extract_user_id_dual(
+        auth,
+        &headers,
+        &state.config.auth,
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await?;
+
+    let sessions = sqlx::query_as::<_, (i64, Option<String>, chrono::DateTime<Utc>, chrono::DateTime<Utc>)>(
+        "SELECT id, user_agent, created_at, last_used_at FROM user_sessions
+         WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db_pool)
+    .timed("list_sessions: SELECT user_sessions")
+    .await
+    .map_err(|e| {
+        tracing::error!("Database error listing sessions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let api_keys = sqlx::query_as::<_, (i64, String, String, Option<chrono::NaiveDateTime>, Option<chrono::NaiveDateTime>)>(
+        "SELECT id, name, key_hash, created_at, last_used_at FROM api_keys
+         WHERE user_id = $1 AND is_active = true
+           AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)"
+    )
+    .bind(user_id)
+    .fetch_all(&state.db_pool)
+    .timed("list_sessions: SELECT api_keys")
+    .await
+    .map_err(|e| {
+        tracing::error!("Database error listing API keys: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut response: Vec<SessionResponse> = sessions
+        .into_iter()
+        .map(|(id, user_agent, created_at, last_used_at)| SessionResponse {
+            id: SessionId::Session(id).to_string(),
+            kind: "session".to_string(),
+            label: user_agent,
+            created_at: Some(created_at),
+            last_used_at: Some(last_used_at),
+            current: matches!(&current, Some(SessionCredential::Session(sid)) if *sid == id),
+        })
+        .chain(api_keys.into_iter().map(|(id, name, key_hash, created_at, last_used_at)| SessionResponse {
+            id: SessionId::ApiKey(id).to_string(),
+            kind: "api_key".to_string(),
+            label: Some(name),
+            created_at: created_at.map(|t| t.and_utc()),
+            last_used_at: last_used_at.map(|t| t.and_utc()),
+            current: matches!(&current, Some(SessionCredential::ApiKey(hash)) if *hash == key_hash),
+        }))
+        .collect();
+    response.sort_by(|a, b| b.last_used_at.cmp(&a.last_used_at));
+
+    Ok(Json(response))
+}
+
+/// Revoke one of the current user's sessions or API keys
+#[utoipa::path(
+    delete,
+    path = "/api/v1/auth/me/sessions/{id}",
+    params(
+        ("id" = String, Path, description = "Session id from the session list, e.g. session-12 or api-key-5")
+    ),
+    tag = "auth",
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "No such session"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn revoke_session(
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let user_id = crate::auth::extract_user_id_dual(
+        auth,
+        &headers,
+        &state.config.auth,
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await?;
+
+    let session_id = id.parse::<SessionId>().map_err(|_| StatusCode::NOT_FOUND)?;
+    match session_id {
+        SessionId::Session(sid) => {
+            let result = sqlx::query(
+                "UPDATE user_sessions SET revoked_at = NOW()
+                 WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+            )
+            .bind(sid)
+            .bind(user_id)
+            .execute(&state.db_pool)
+            .timed("revoke_session: UPDATE user_sessions")
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error revoking session: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            if result.rows_affected() == 0 {
+                return Err(StatusCode::NOT_FOUND);
+            }
+        }
+        SessionId::ApiKey(key_id) => {
+            let key_hash = sqlx::query_scalar::<_, String>(
+                "DELETE FROM api_keys WHERE id = $1 AND user_id = $2 RETURNING key_hash"
+            )
+            .bind(key_id)
+            .bind(user_id)
+            .fetch_optional(&state.db_pool)
+            .timed("revoke_session: DELETE api_keys")
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error deleting API key: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+
+            // A cached key would keep working until its entry expired
+            if let Some(cache) = &state.cache {
+                if let Err(e) = cache.invalidate_api_key_info(&key_hash).await {
+                    tracing::warn!("Failed to invalidate API key in cache: {}", e);
+                }
+            }
+        }
+    }
+
+    tracing::info!("Revoked {} for user {}", session_id, user_id);
+    emit(&state, AuthEvent::new(AuthEventKind::TokenRevoked).user(user_id).reason(session_id.to_string())).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RefreshRequest {
     /// Old token to refresh
@@ -537,7 +929,7 @@ pub async fn refresh(
     State(state): State<AppState>,
     Json(req): Json<RefreshRequest>,
 ) -> impl IntoResponse {
-    let claims = match crate::auth::verify_token(&req.token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
+    let claims = match crate::auth::verify_token_with_settings(&req.token, &state.config.auth) {
         Ok(claims) => claims,
         Err(_) => {
             return (
@@ -549,13 +941,43 @@ pub async fn refresh(
         }
     };
 
+    // A revoked session cannot be refreshed; an active one lives on
+    if let Some(sid) = claims.sid {
+        let user_id = claims.sub.parse::<i64>().unwrap_or_default();
+        match crate::auth::extend_session(&state.db_pool, sid, user_id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    Json(serde_json::json!({
+                        "error": "Session has been revoked"
+                    })),
+                );
+            }
+            Err(e) => {
+                tracing::error!("Failed to refresh session {}: {}", sid, e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({
+                        "error": "Failed to refresh token"
+                    })),
+                );
+            }
+        }
+    }
+
+    let user_id = claims.sub.parse::<i64>().ok();
+    // The refreshed token keeps the original issue time, so refreshing does
+    // not outlive JWT_MAX_AGE_SECONDS
     let new_claims = Claims {
         sub: claims.sub,
         exp: (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp() as usize,
+        iat: claims.iat.or(Some(chrono::Utc::now().timestamp() as usize)),
+        sid: claims.sid,
     };
 
     let new_token = match encode(
-        &Header::default(),
+        &Header::new(crate::auth::JWT_ALGORITHM),
         &new_claims,
         &EncodingKey::from_secret(state.config.auth.jwt_secret.expose_secret().as_bytes()),
     ) {
@@ -570,6 +992,9 @@ pub async fn refresh(
         }
     };
 
+    // The token comes in the body, so the user is only known from its claims
+    emit(&state, AuthEvent { user_id, ..AuthEvent::new(AuthEventKind::TokenIssued).reason("refresh") }).await;
+
     (
         StatusCode::OK,
         Json(serde_json::json!({
@@ -602,7 +1027,7 @@ pub async fn logout(
     Json(req): Json<LogoutRequest>,
 ) -> impl IntoResponse {
     // Verify the token first
-    let claims = match crate::auth::verify_token(&req.token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
+    let claims = match crate::auth::verify_token_with_settings(&req.token, &state.config.auth) {
         Ok(claims) => claims,
         Err(_) => {
             return (
@@ -614,6 +1039,18 @@ pub async fn logout(
         }
     };
 
+    // End the session so the token stops working everywhere
+    if let Some(sid) = claims.sid {
+        if let Err(e) = sqlx::query("UPDATE user_sessions SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+            .bind(sid)
+            .execute(&state.db_pool)
+            .timed("logout")
+            .await
+        {
+            tracing::warn!("Failed to revoke session {}: {}", sid, e);
+        }
+    }
+
     // Invalidate token in cache
     if let Some(cache) = &state.cache {
         if let Err(e) = cache.invalidate_auth_token(&req.token).await {
@@ -628,6 +1065,9 @@ pub async fn logout(
         }
     }
 
+    let user_id = claims.sub.parse::<i64>().ok();
+    emit(&state, AuthEvent { user_id, ..AuthEvent::new(AuthEventKind::TokenRevoked).reason("logout") }).await;
+
     (
         StatusCode::OK,
         Json(serde_json::json!({
@@ -636,6 +1076,357 @@ pub async fn logout(
     )
 }
 
+/// Scope granted by user JWTs; they carry full registry access for the user
+pub const JWT_SCOPE: &str = "pull push delete";
+
+/// Token introspection request
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct IntrospectRequest {
+    /// Token to introspect
+    pub token: String,
+}
+
+/// Token introspection response (RFC 7662 style)
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct IntrospectResponse {
+    /// Whether the token is currently valid
+    pub active: bool,
+    /// Subject (user id) the token was issued for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    /// Space separated list of scopes granted by the token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Expiration time as a unix timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<usize>,
+}
+
+impl IntrospectResponse {
+    fn active(claims: Claims) -> Self {
+        IntrospectResponse {
+            active: true,
+            sub: Some(claims.sub),
+            scope: Some(JWT_SCOPE.to_string()),
+            exp: Some(claims.exp),
+        }
+    }
+
+    fn inactive() -> Self {
+        IntrospectResponse {
+            active: false,
+            sub: None,
+            scope: None,
+            exp: None,
+        }
+    }
+}
+
+/// Introspect a JWT without touching the cache. Expired, malformed or wrongly
+/// signed tokens, and tokens of revoked or expired sessions, are reported as
+/// inactive.
+pub async fn introspect_session(token: &str, settings: &AuthSettings, pool: &sqlx::PgPool) -> Result<IntrospectResponse, StatusCode> {
+    let claims = match crate::auth::verify_token_with_settings(token, settings) {
+        Ok(claims) => claims,
+        Err(_) => return Ok(IntrospectResponse::inactive()),
+    };
+    match crate::auth::claims_user_id(&claims, pool).await {
+        Ok(_) => Ok(IntrospectResponse::active(claims)),
+        Err(StatusCode::UNAUTHORIZED) => Ok(IntrospectResponse::inactive()),
+        Err(status) => Err(status),
+    }
+}
+
+/// Introspect a token for internal callers (gateways, sidecars) and administrators
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/introspect",
+    tag = "auth",
+    request_body = IntrospectRequest,
+    params(
+        ("X-Introspection-Secret" = Option<String>, Header, description = "Shared secret configured via INTROSPECTION_SECRET"),
+        ("X-Admin-Token" = Option<String>, Header, description = "Admin token, accepted instead of the introspection secret")
+    ),
+    responses(
+        (status = 200, description = "Introspection result", body = IntrospectResponse),
+        (status = 403, description = "Caller is not allowed to introspect tokens"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn introspect(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Json(req): Json<IntrospectRequest>,
+) -> impl IntoResponse {
+    // Only internal callers that know the shared secret, and administrators,
+    // may introspect
+    if !crate::auth::introspection_allowed(&headers, &state.config.auth) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Token introspection is not allowed"
+            })),
+        );
+    }
+
+    let result = match introspect_session(
+        &req.token,
+        &state.config.auth,
+        &state.db_pool,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Failed to check the token's session"
+                })),
+            );
+        }
+    };
+
+    (StatusCode::OK, Json(serde_json::json!(result)))
+}
+
+/// Request for a one-time nonce
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NonceRequest {
+    /// Operation the nonce will be sent with
+    pub action: NonceAction,
+}
+
+/// A nonce to send once in `X-Request-Nonce`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NonceResponse {
+    pub nonce: String,
+    pub action: NonceAction,
+    /// Seconds until the nonce expires
+    pub expires_in: u64,
+}
+
+/// Issue a one-time nonce for organization deletion or ownership transfer
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/nonce",
+    tag = "auth",
+    request_body = NonceRequest,
+    responses(
+        (status = 201, description = "Nonce issued", body = NonceResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Nonce could not be stored"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn issue_request_nonce(
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Json(req): Json<NonceRequest>,
+) -> impl IntoResponse {
+    let user_id = match crate::auth::extract_user_id_dual(
+        auth,
+        &headers,
+        &state.config.auth,
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(serde_json::json!({ "error": "Unauthorized" }))),
+    };
+
+    match issue_nonce(state.nonces.as_ref(), user_id, req.action).await {
+        Ok(nonce) => (
+            StatusCode::CREATED,
+            Json(serde_json::json!(NonceResponse {
+                nonce,
+                action: req.action,
+                expires_in: NONCE_TTL.as_secs(),
+            })),
+        ),
+        Err(e) => {
+            tracing::error!("Failed to issue request nonce: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to issue nonce" })),
+            )
+        }
+    }
+}
+
+/// Update the current user's profile
+#[utoipa::path(
+    put,
+    path = "/api/v1/auth/me",
+    tag = "auth",
+    request_body = UpdateProfileRequest,
+    responses(
+        (status = 200, description = "Profile updated", body = UserResponse),
+        (status = 400, description = "Display name or bio failed validation"),
+        (status = 401, description = "Unauthorized"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn update_me(
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Json(req): Json<UpdateProfileRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Validation failed",
+                "details": validation_errors
+            })),
+        );
+    }
+
+    let user_id = match crate::auth::extract_user_id_dual(
+        auth,
+        &headers,
+        &state.config.auth,
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(serde_json::json!({ "error": "Unauthorized" }))),
+    };
+
+    match sqlx::query_as::<_, UserResponse>(
+        "UPDATE users
+         SET display_name = COALESCE($2, display_name),
+             bio = COALESCE($3, bio)
+         WHERE id = $1
+         RETURNING id, username, email, display_name, bio, avatar_url, created_at"
+    )
+    .bind(user_id)
+    .bind(&req.display_name)
+    .bind(&req.bio)
+    .fetch_optional(&state.db_pool)
+    .timed("update_me")
+    .await
+    {
+        Ok(Some(user)) => (StatusCode::OK, Json(serde_json::json!(user))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": "User not found" }))),
+        Err(e) => {
+            tracing::error!("Failed to update profile of user {}: {}", user_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": "Internal server error" })))
+        }
+    }
+}
+
+/// Upload the current user's avatar
+/// The body is the image itself, with its type as Content-Type
+#[utoipa::path(
+    put,
+    path = "/api/v1/auth/me/avatar",
+    tag = "auth",
+    request_body(content = Vec<u8>, description = "PNG, JPEG, GIF or WebP image of at most 1 MiB", content_type = "image/png"),
+    responses(
+        (status = 200, description = "Avatar stored; its URL is returned"),
+        (status = 400, description = "Empty body, or content not matching its Content-Type"),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "Image larger than 1 MiB"),
+        (status = 415, description = "Content-Type is not an accepted image type"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn upload_avatar(
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    body: axum::body::Bytes,
+) -> impl IntoResponse {
+    let user_id = match crate::auth::extract_user_id_dual(
+        auth,
+        &headers,
+        &state.config.auth,
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await {
+        Ok(id) => id,
+        Err(status) => return (status, Json(serde_json::json!({ "error": "Unauthorized" }))),
+    };
+
+    let content_type = headers.get(axum::http::header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+    if let Err(e) = validate_avatar(content_type, &body) {
+        let status = match e {
+            AvatarError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            AvatarError::UnsupportedType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AvatarError::Empty | AvatarError::ContentMismatch(_) => StatusCode::BAD_REQUEST,
+        };
+        return (status, Json(serde_json::json!({ "error": e.to_string() })));
+    }
+
+    if let Err(e) = state.storage.put_blob(&user_avatar_key(user_id), body).await {
+        tracing::error!("Failed to store avatar of user {}: {}", user_id, e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": "Internal server error" })));
+    }
+
+    let avatar_url = user_avatar_url(user_id);
+    match sqlx::query("UPDATE users SET avatar_url = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(&avatar_url)
+        .execute(&state.db_pool)
+        .timed("upload_avatar")
+        .await
+    {
+        Ok(_) => (StatusCode::OK, Json(serde_json::json!({ "avatar_url": avatar_url }))),
+        Err(e) => {
+            tracing::error!("Failed to record avatar of user {}: {}", user_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": "Internal server error" })))
+        }
+    }
+}
+
+/// Get a user's avatar
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/users/{id}/avatar",
+    tag = "auth",
+    params(
+        ("id" = i64, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "The avatar image"),
+        (status = 404, description = "User has no avatar"),
+    )
+)]
+pub async fn get_avatar(
+    State(state): State<AppState>,
+    axum::extract::Path(user_id): axum::extract::Path<i64>,
+) -> axum::response::Response {
+    let image = match state.storage.get_blob(&user_avatar_key(user_id)).await {
+        Ok(Some(image)) => image,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": "Avatar not found" }))).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to read avatar of user {}: {}", user_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": "Internal server error" }))).into_response();
+        }
+    };
+
+    // Checked on upload; the type is read back from the content
+    let content_type = sniff_image_type(&image).unwrap_or("application/octet-stream");
+    (
+        StatusCode::OK,
+        [
+            (axum::http::header::CONTENT_TYPE, content_type),
+            (axum::http::header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+            (axum::http::header::CACHE_CONTROL, "no-cache"),
+        ],
+        image,
+    ).into_response()
+}
+
 /// Change user password
 #[utoipa::path(
     put,
@@ -657,10 +1448,10 @@ pub async fn change_password(
     State(state): State<AppState>,
     Json(req): Json<ChangePasswordRequest>,
 ) -> impl IntoResponse {
-    // Verify JWT token
-    let claims = match crate::auth::verify_token(auth.token(), state.config.auth.jwt_secret.expose_secret().as_bytes()) {
-        Ok(claims) => claims,
-        Err(_) => {
+    // Verify JWT token and that its session is still active
+    let user_id = match crate::auth::extract_user_id(Some(TypedHeader(auth)), &state.config.auth, &state.db_pool).await {
+        Ok(id) => id,
+        Err(StatusCode::UNAUTHORIZED) => {
             return (
                 StatusCode::UNAUTHORIZED,
                 Json(serde_json::json!({
@@ -668,16 +1459,11 @@ pub async fn change_password(
                 })),
             );
         }
-    };
-
-    // Parse user ID from claims
-    let user_id: i64 = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
+        Err(status) => {
             return (
-                StatusCode::UNAUTHORIZED,
+                status,
                 Json(serde_json::json!({
-                    "error": "Invalid token format"
+                    "error": "Failed to check the token's session"
                 })),
             );
         }
@@ -705,6 +1491,7 @@ pub async fn change_password(
     // Get current user from database
     let user = match sqlx::query_as!(User, "SELECT * FROM users WHERE id = $1", user_id)
         .fetch_optional(&state.db_pool)
+        .timed("change_password: SELECT users")
         .await
     {
         Ok(Some(user)) => user,
@@ -774,6 +1561,7 @@ pub async fn change_password(
         user_id
     )
     .execute(&state.db_pool)
+    .timed("change_password: UPDATE users")
     .await
     {
         Ok(_) => {
@@ -821,8 +1609,9 @@ pub async fn forgot_password(
     Json(req): Json<ForgotPasswordRequest>,
 ) -> impl IntoResponse {
     // Find user by email
-    let user = match sqlx::query!("SELECT id, username, email FROM users WHERE email = $1", req.email)
+    let user = match sqlx::query!("SELECT id, username, email FROM users WHERE LOWER(email) = $1", normalize_email(&req.email))
         .fetch_optional(&state.db_pool)
+        .timed("forgot_password")
         .await
     {
         Ok(Some(user)) => user,
@@ -906,8 +1695,9 @@ pub async fn verify_otp_and_reset(
     }
 
     // Find user by email
-    let user = match sqlx::query!("SELECT id, username, email FROM users WHERE email = $1", req.email)
+    let user = match sqlx::query!("SELECT id, username, email FROM users WHERE LOWER(email) = $1", normalize_email(&req.email))
         .fetch_optional(&state.db_pool)
+        .timed("verify_otp_and_reset: SELECT users")
         .await
     {
         Ok(Some(user)) => user,
@@ -971,6 +1761,7 @@ pub async fn verify_otp_and_reset(
     // Update password in database
     match sqlx::query!("UPDATE users SET password_hash = $1 WHERE id = $2", password_hash, user.id)
         .execute(&state.db_pool)
+        .timed("verify_otp_and_reset: UPDATE users")
         .await
     {
         Ok(_) => Json(serde_json::json!({
@@ -1029,6 +1820,23 @@ pub struct DeleteApiKeyResponse {
     pub message: String,
 }
 
+/// Rotate API Key response (includes the new key - only shown once!)
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RotateApiKeyResponse {
+    /// API key ID, unchanged by rotation
+    pub id: i64,
+    /// API key name, unchanged by rotation
+    pub name: String,
+    /// The new API key (ak_...) - ONLY SHOWN ONCE! The old one no longer works
+    pub api_key: String,
+    /// Expiration date, unchanged by rotation
+    pub expires_at: Option<chrono::NaiveDateTime>,
+    /// Creation timestamp
+    pub created_at: Option<chrono::NaiveDateTime>,
+    /// Security warning
+    pub warning: String,
+}
+
 /// Error response for API key operations
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ApiKeyErrorResponse {
@@ -1068,7 +1876,7 @@ pub async fn get_user_api_keys(
     let user_id = crate::auth::extract_user_id_dual_auth(
         auth_header, 
         api_key,
-        &state.config.auth.jwt_secret.expose_secret().as_bytes(),
+        &state.config.auth,
         &state.db_pool, 
         state.cache.as_ref()
     ).await.map_err(|_| StatusCode::UNAUTHORIZED)?;
@@ -1092,6 +1900,7 @@ pub async fn get_user_api_keys(
             format!("%{}%", search_name)
         )
         .fetch_all(&state.db_pool)
+        .timed("get_user_api_keys: SELECT api_keys")
         .await
     } else {
         sqlx::query_as!(
@@ -1105,6 +1914,7 @@ pub async fn get_user_api_keys(
             user_id
         )
         .fetch_all(&state.db_pool)
+        .timed("get_user_api_keys: SELECT api_keys")
         .await
     };
 
@@ -1156,7 +1966,7 @@ pub async fn create_api_key(
     let user_id = crate::auth::extract_user_id_dual_auth(
         Some(TypedHeader(auth)), 
         None, // No X-API-Key header for this endpoint
-        &state.config.auth.jwt_secret.expose_secret().as_bytes(),
+        &state.config.auth,
         &state.db_pool, 
         state.cache.as_ref()
     ).await.map_err(|_| {
@@ -1174,6 +1984,7 @@ pub async fn create_api_key(
         request.name
     )
     .fetch_optional(&state.db_pool)
+    .timed("create_api_key: SELECT api_keys")
     .await
     .map_err(|e| {
         tracing::error!("Database error checking duplicate API key name: {}", e);
@@ -1221,6 +2032,7 @@ pub async fn create_api_key(
         Some(expires_at),
     )
     .fetch_one(&state.db_pool)
+    .timed("create_api_key: INSERT api_keys")
     .await
     .map_err(|e| {
         tracing::error!("Database error creating API key: {}", e);
@@ -1242,6 +2054,7 @@ pub async fn create_api_key(
 
     tracing::info!("Created new API key for user {} (expires: {})", 
         user_id, expires_at.format("%Y-%m-%d %H:%M:%S"));
+    emit(&state, AuthEvent::new(AuthEventKind::TokenIssued).user(user_id).reason(format!("api-key-{}", api_key_record.id))).await;
 
     Ok((StatusCode::CREATED, Json(response)))
 }
@@ -1278,7 +2091,7 @@ pub async fn delete_api_key(
     let user_id = crate::auth::extract_user_id_dual_auth(
         auth_header, 
         api_key,
-        &state.config.auth.jwt_secret.expose_secret().as_bytes(),
+        &state.config.auth,
         &state.db_pool, 
         state.cache.as_ref()
     ).await.map_err(|_| StatusCode::UNAUTHORIZED)?;
@@ -1290,6 +2103,7 @@ pub async fn delete_api_key(
         user_id
     )
     .execute(&state.db_pool)
+    .timed("delete_api_key")
     .await
     .map_err(|e| {
         tracing::error!("Database error deleting API key: {}", e);
@@ -1301,6 +2115,7 @@ pub async fn delete_api_key(
     }
 
     tracing::info!("Deleted API key {} for user {}", key_id, user_id);
+    emit(&state, AuthEvent::new(AuthEventKind::TokenRevoked).user(user_id).reason(format!("api-key-{}", key_id))).await;
     
     let response = DeleteApiKeyResponse {
         message: "API key deleted successfully".to_string(),
@@ -1309,6 +2124,116 @@ pub async fn delete_api_key(
     Ok((StatusCode::OK, Json(response)))
 }
 
+/// An API key whose secret was replaced
+pub struct RotatedApiKey {
+    pub id: i64,
+    pub name: String,
+    /// The new secret
+    pub api_key: String,
+    /// Hash of the secret that stopped working
+    pub old_key_hash: String,
+    pub expires_at: Option<chrono::NaiveDateTime>,
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Replace the secret of one of the user's active API keys, keeping its
+/// name and expiry. `None` if the user has no such key.
+pub async fn rotate_api_key_secret(
+    db_pool: &sqlx::PgPool,
+    user_id: i64,
+    key_id: i64,
+) -> Result<Option<RotatedApiKey>, sqlx::Error> {
+    let api_key = format!("ak_{}", hex::encode(rand::random::<[u8; 16]>()));
+    let key_hash = crate::auth::hash_api_key(&api_key);
+
+    let row: Option<(String, String, Option<chrono::NaiveDateTime>, Option<chrono::NaiveDateTime>)> = sqlx::query_as(
+        "UPDATE api_keys k SET key_hash = $1
+         FROM (SELECT id, key_hash FROM api_keys WHERE id = $2 AND user_id = $3 AND is_active = true FOR UPDATE) old
+         WHERE k.id = old.id
+         RETURNING k.name, old.key_hash, k.expires_at, k.created_at",
+    )
+    .bind(&key_hash)
+    .bind(key_id)
+    .bind(user_id)
+    .fetch_optional(db_pool)
+    .timed("rotate_api_key_secret")
+    .await?;
+
+    Ok(row.map(|(name, old_key_hash, expires_at, created_at)| RotatedApiKey {
+        id: key_id,
+        name,
+        api_key,
+        old_key_hash,
+        expires_at,
+        created_at,
+    }))
+}
+
+/// Rotate an API key
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/api-keys/{id}/rotate",
+    params(
+        ("id" = i64, Path, description = "API key ID to rotate")
+    ),
+    tag = "auth",
+    responses(
+        (status = 200, description = "API key rotated; the old key no longer works", body = RotateApiKeyResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "API key not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn rotate_api_key(
+    auth_header: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    axum::extract::Path(key_id): axum::extract::Path<i64>,
+) -> Result<Json<RotateApiKeyResponse>, StatusCode> {
+    // Extract API key from X-API-Key header
+    let api_key = headers.get("x-api-key")
+        .and_then(|v| v.to_str().ok());
+
+    // Extract user ID using dual auth function
+    let user_id = crate::auth::extract_user_id_dual_auth(
+        auth_header, 
+        api_key,
+        &state.config.auth,
+        &state.db_pool, 
+        state.cache.as_ref()
+    ).await.map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    let rotated = rotate_api_key_secret(&state.db_pool, user_id, key_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error rotating API key: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // A cached entry would keep the old secret working until it expires
+    if let Some(cache) = &state.cache {
+        if let Err(e) = cache.invalidate_api_key_info(&rotated.old_key_hash).await {
+            tracing::warn!("Failed to invalidate cached API key {}: {}", key_id, e);
+        }
+    }
+
+    tracing::info!("Rotated API key {} for user {}", key_id, user_id);
+    emit(&state, AuthEvent::new(AuthEventKind::TokenIssued).user(user_id).reason(format!("api-key-{} rotated", key_id))).await;
+
+    Ok(Json(RotateApiKeyResponse {
+        id: rotated.id,
+        name: rotated.name,
+        api_key: rotated.api_key,
+        expires_at: rotated.expires_at,
+        created_at: rotated.created_at,
+        warning: "⚠️ SECURITY WARNING: This API key will only be shown once. Please save it securely immediately. The previous key has been revoked.".to_string(),
+    }))
+}
+
 /// Clean up expired API keys from database
 pub async fn cleanup_expired_api_keys(db_pool: &sqlx::PgPool) -> Result<i64, sqlx::Error> {
     let now = chrono::Utc::now().naive_utc();
@@ -1318,6 +2243,7 @@ pub async fn cleanup_expired_api_keys(db_pool: &sqlx::PgPool) -> Result<i64, sql
         now
     )
     .execute(db_pool)
+    .timed("cleanup_expired_api_keys")
     .await?;
 
     tracing::info!("Cleaned up {} expired API keys", result.rows_affected());
@@ -1,14 +1,84 @@
 // Docker Registry Authentication helper functions
 use axum::{
-    http::{HeaderMap, StatusCode, header::AUTHORIZATION},
+    extract::FromRequestParts,
+    http::{HeaderMap, StatusCode, header::AUTHORIZATION, request::Parts},
     response::{IntoResponse, Response},
     Json,
 };
-use secrecy::ExposeSecret;
+use std::convert::Infallible;
 use base64::Engine;
 use bcrypt;
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
-use crate::{AppState, auth::verify_token};
+use crate::{AppState, auth::verify_token_with_settings};
+use crate::db::{QueryTimer, Timed};
+
+/// Authenticated caller of a registry route; rejects the request with 401
+/// when no valid credentials are supplied.
+#[derive(Debug, Clone)]
+pub struct AuthUser(pub String);
+
+/// Caller identity when credentials are present. Never rejects: anonymous
+/// callers and invalid credentials resolve to `MaybeAuthUser(None)`, so
+/// handlers can serve public content and challenge only when needed.
+#[derive(Debug, Clone)]
+pub struct MaybeAuthUser(pub Option<String>);
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        match extract_user_from_auth(&parts.headers, state, true).await? {
+            Some(user_id) => {
+                note_auth_user(&user_id);
+                Ok(AuthUser(user_id))
+            }
+            None => Err(authentication_required()),
+        }
+    }
+}
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for MaybeAuthUser {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        match extract_user_from_auth(&parts.headers, state, false).await {
+            Ok(user_id) => {
+                if let Some(user_id) = &user_id {
+                    note_auth_user(user_id);
+                }
+                Ok(MaybeAuthUser(user_id))
+            }
+            Err(_) => {
+                println!("⚠️ Ignoring invalid credentials, treating request as anonymous");
+                Ok(MaybeAuthUser(None))
+            }
+        }
+    }
+}
+
+/// Attribute the request's authentication events to `user_id`
+fn note_auth_user(user_id: &str) {
+    if let Ok(user_id) = user_id.parse() {
+        crate::auth_events::note_user(user_id);
+    }
+}
+
+/// 401 challenge asking the client to authenticate
+pub fn authentication_required() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [("WWW-Authenticate", "Basic")],
+        Json(serde_json::json!({
+            "errors": [{
+                "code": "UNAUTHORIZED",
+                "message": "Authentication required",
+                "detail": {}
+            }]
+        }))
+    ).into_response()
+}
 
 /// Extract user ID from Authorization header
 pub async fn extract_user_from_auth(
@@ -22,19 +92,19 @@ pub async fn extract_user_from_auth(
                 let token = &auth_str[7..]; // Remove "Bearer " prefix
                 
                 // Verify JWT token and extract user_id
-                match verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
+                match verify_token_with_settings(token, &state.config.auth) {
                     Ok(claims) => {
-                        match claims.sub.parse::<i64>() {
+                        match crate::auth::claims_user_id(&claims, &state.db_pool).await {
                             Ok(uid) => Ok(Some(uid.to_string())),
-                            Err(_) => {
-                                println!("❌ Invalid user ID in JWT token");
+                            Err(status) => {
+                                println!("❌ Invalid user ID or revoked session in JWT token");
                                 Err((
-                                    StatusCode::UNAUTHORIZED,
+                                    status,
                                     [("WWW-Authenticate", "Bearer")],
                                     Json(serde_json::json!({
                                         "errors": [{
                                             "code": "UNAUTHORIZED",
-                                            "message": "Invalid user ID in token",
+                                            "message": "Invalid user ID in token or session revoked",
                                             "detail": {}
                                         }]
                                     }))
@@ -204,6 +274,7 @@ async fn verify_docker_credentials(
         username
     )
     .fetch_optional(&state.db_pool)
+    .timed("verify_docker_credentials")
     .await?;
 
     if let Some(user) = user_result {
@@ -304,6 +375,26 @@ pub async fn check_repository_permission(
     operation: &str, // "pull", "push", "delete"
     state: &AppState,
 ) -> Result<bool, sqlx::Error> {
+    // Unqualified names are checked against the caller's namespace, which
+    // need not be the organization the request was scoped to
+    match crate::tenant::organization_tenant(&state.db_pool, state.config.database.tenancy_mode, namespace).await? {
+        Some(tenant) => {
+            tenant
+                .scope(namespace_permission(user_id, namespace, repository, operation, state))
+                .await
+        }
+        None => Ok(false),
+    }
+}
+
+async fn namespace_permission(
+    user_id: &str,
+    namespace: &str,
+    repository: &str,
+    operation: &str,
+    state: &AppState,
+) -> Result<bool, sqlx::Error> {
+    let _timer = QueryTimer::start("check_repository_permission");
     println!("🔒 Checking {} permission for user {} on {}/{}", operation, user_id, namespace, repository);
 
     // If user_id starts with "org_", it's an organization-level access
@@ -330,7 +421,8 @@ pub async fn check_repository_permission(
          FROM organization_members om
          JOIN organizations o ON om.organization_id = o.id
          JOIN repositories r ON r.organization_id = o.id
-         WHERE om.user_id = $1 AND o.name = $2 AND r.name = $3",
+         WHERE om.user_id = $1 AND o.name = $2 AND r.name = $3
+           AND (om.expires_at IS NULL OR om.expires_at > NOW())",
         user_id_int, namespace, repository
     )
     .fetch_optional(&state.db_pool)
@@ -10,15 +10,35 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use serde_json::json;
-use std::collections::HashMap;
-use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use utoipa::ToSchema;
 use uuid;
-use secrecy::ExposeSecret;
 use bytes::Bytes;
+use crate::db::Timed;
 use crate::AppState;
-use crate::auth::verify_token;
-use crate::handlers::docker_auth::{extract_user_from_auth, check_repository_permission};
+use crate::cache::CatalogPage;
+use crate::auth::is_admin_request;
+use crate::config::settings::{CatalogVisibility, DeletionMode, RepositoryVisibility};
+use crate::auth::verify_token_with_settings;
+use crate::models::digest::Digest;
+use crate::pull_recorder::ManifestPull;
+use crate::models::analytics::ActivityAction;
+use crate::models::manifest_size::{artifact_type, check_image_size, image_size_limit};
+use crate::models::media_type::MediaType;
+use crate::handlers::repositories::{new_repository_public, organization_default_public};
+use crate::models::repository::RepositoryName;
+use crate::models::tag_expiry::tag_matches_pattern;
+use crate::models::tag_policy::tag_allowed;
+use crate::oci_layout::{layout_len, layout_stream, plan_layout, LayoutManifest, LayoutTag};
+use crate::utils::conditional;
+use crate::utils::content_range::{parse_content_range, ContentRangeError};
+use crate::utils::pagination::{paginate, Page, PageQuery};
+use crate::storage::router::StoredContent;
+use crate::storage::upload_session::{UploadSession, MIN_PART_BYTES};
+use crate::storage::verify::{content_matches, VerifyingReader};
+use crate::handlers::docker_auth::{
+    authentication_required, check_repository_permission, extract_user_from_auth, AuthUser, MaybeAuthUser,
+};
 
 /// Docker Registry V2 API version response
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -33,6 +53,38 @@ pub struct CatalogResponse {
     pub repositories: Vec<String>,
 }
 
+/// Most repositories `/v2/_popular` returns, and how many without `limit`
+pub const MAX_POPULAR_LIMIT: usize = 100;
+pub const DEFAULT_POPULAR_LIMIT: usize = 10;
+
+/// A repository ranked by `/v2/_popular`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PopularRepository {
+    pub name: String,
+    /// Manifest pulls within the window
+    pub pulls: i64,
+}
+
+/// Most pulled public repositories, most pulled first
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PopularResponse {
+    pub window_days: u32,
+    pub repositories: Vec<PopularRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PopularQuery {
+    pub limit: Option<usize>,
+}
+
+/// Pulls of one repository within the window, before ranking
+#[derive(Debug, Clone)]
+pub struct PullTotal {
+    pub name: String,
+    pub is_public: bool,
+    pub pulls: i64,
+}
+
 /// Tag list response
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TagListResponse {
@@ -61,18 +113,50 @@ pub struct RegistryError {
     pub detail: Option<serde_json::Value>,
 }
 
-/// Query parameters for catalog endpoint
-#[derive(Debug, Deserialize)]
-pub struct CatalogQuery {
-    pub n: Option<u32>,
-    pub last: Option<String>,
-}
+/// Redirect namespaced registry requests that use a renamed organization's old name.
+/// GET/HEAD get a 301; other methods get a 308 so clients replay the body.
+pub async fn redirect_renamed_organization(
+    State(state): State<AppState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let path = request.uri().path();
+    let segments: Vec<&str> = path.trim_start_matches("/v2/").split('/').collect();
 
-/// Query parameters for tags endpoint  
-#[derive(Debug, Deserialize)]
-pub struct TagsQuery {
-    pub n: Option<u32>,
-    pub last: Option<String>,
+    // Only /v2/<org>/<name>/{manifests,blobs,tags}/... carries an organization segment
+    let is_namespaced = segments.len() >= 3
+        && segments[0] != "id"
+        && matches!(segments[2], "manifests" | "blobs" | "tags");
+
+    if is_namespaced {
+        match state.org_aliases.resolve(&state.db_pool, segments[0]).await {
+            Ok(Some(current_name)) => {
+                let mut location = format!("/v2/{}/{}", current_name, segments[1..].join("/"));
+                if let Some(query) = request.uri().query() {
+                    location.push('?');
+                    location.push_str(query);
+                }
+
+                let status = if request.method() == axum::http::Method::GET
+                    || request.method() == axum::http::Method::HEAD
+                {
+                    StatusCode::MOVED_PERMANENTLY
+                } else {
+                    StatusCode::PERMANENT_REDIRECT
+                };
+
+                let mut headers = HeaderMap::new();
+                if let Ok(value) = HeaderValue::from_str(&location) {
+                    headers.insert(axum::http::header::LOCATION, value);
+                }
+                return (status, headers).into_response();
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to resolve organization alias: {}", e),
+        }
+    }
+
+    next.run(request).await
 }
 
 /// Docker Registry V2 version check - GET /v2/
@@ -113,8 +197,8 @@ pub async fn version_check(
 }
 
 /// Get repository catalog - GET /v2/_catalog
-/// Lists all repositories in the registry
-/// Requires authentication and shows only repositories user has access to
+/// Lists the repositories the caller can pull from. Whether anonymous or
+/// non-admin callers may list at all depends on `CATALOG_VISIBILITY`.
 #[utoipa::path(
     get,
     path = "/v2/_catalog",
@@ -126,124 +210,263 @@ pub async fn version_check(
     responses(
         (status = 200, description = "Repository catalog", body = CatalogResponse),
         (status = 401, description = "Authentication required"),
+        (status = 403, description = "Catalog is restricted to administrators"),
     )
 )]
 pub async fn get_catalog(
     State(state): State<AppState>,
-    Query(_params): Query<CatalogQuery>,
+    Query(page_query): Query<PageQuery>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
     println!("🔍 GET Catalog");
-    
-    // Require authentication for catalog access
-    let user_id = match extract_user_from_auth(&headers, &state, true).await {
-        Ok(Some(uid)) => uid,
-        Ok(None) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "UNAUTHORIZED",
-                        "message": "Authentication required",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
-        }
+
+    let caller = match authorize_catalog(&state, &headers).await {
+        Ok(caller) => caller,
         Err(response) => return response,
     };
 
-    println!("✅ Authenticated user: {} requesting catalog", user_id);
-    
-    // Query database for repositories the user has access to
-    let repositories = if user_id.starts_with("org_") {
-        // Organization-level access - show all repositories for this organization
-        let org_id: i64 = user_id[4..].parse().unwrap_or(0);
-        match sqlx::query!(
-            "SELECT CONCAT(o.name, '/', r.name) as full_name 
-             FROM repositories r 
-             JOIN organizations o ON r.organization_id = o.id 
-             WHERE o.id = $1
-             ORDER BY o.name, r.name",
-            org_id
-        )
-        .fetch_all(&state.db_pool)
-        .await
-        {
-            Ok(rows) => {
-                rows.into_iter()
-                    .filter_map(|row| row.full_name)
-                    .collect::<Vec<String>>()
-            },
-            Err(e) => {
-                println!("❌ Database error querying repositories: {}", e);
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({
-                        "errors": [{
-                            "code": "UNKNOWN",
-                            "message": "Internal server error",
-                            "detail": {}
-                        }]
-                    }))
-                ).into_response();
-            }
+    println!("✅ Caller {} requesting catalog", caller.cache_id());
+
+    // Pages differ by caller, since each sees only the repositories it can access
+    let cache_key = crate::cache::catalog_cache_key(caller.cache_id(), page_query.n, page_query.last.as_deref());
+    if let Some(cache) = &state.cache {
+        if let Some(cached) = cache.get_catalog_page(&cache_key).await {
+            return catalog_response(Page {
+                items: cached.repositories,
+                next_last: cached.next_last,
+                n: page_query.n,
+            });
+        }
+    }
+
+    let repositories = match catalog_repositories(&state, &caller, &page_query).await {
+        Ok(repositories) => repositories,
+        Err(e) => {
+            println!("❌ Database error querying repositories: {}", e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+        }
+    };
+
+    println!("📋 Found {} repositories for caller", repositories.len());
+
+    let page = paginate(repositories, |name| name.clone(), &page_query);
+    if let Some(cache) = &state.cache {
+        let cached = CatalogPage { repositories: page.items.clone(), next_last: page.next_last.clone() };
+        if let Err(e) = cache.cache_catalog_page(&cache_key, &cached).await {
+            println!("⚠️ Failed to cache catalog page: {}", e);
         }
+    }
+
+    catalog_response(page)
+}
+
+/// The catalog caller of a request, if `CATALOG_VISIBILITY` lets it list
+/// repositories; 401 or 403 otherwise
+async fn authorize_catalog(state: &AppState, headers: &HeaderMap) -> Result<CatalogCaller, Response> {
+    let caller = if is_admin_request(headers, &state.config.auth) {
+        CatalogCaller::Admin
     } else {
-        // User-level access - show repositories user has access to
-        let user_id_int: i64 = user_id.parse().unwrap_or(0);
-        match sqlx::query!(
-            "SELECT CONCAT(o.name, '/', r.name) as full_name 
-             FROM repositories r 
-             JOIN organizations o ON r.organization_id = o.id 
-             LEFT JOIN organization_members om ON om.organization_id = o.id AND om.user_id = $1
-             WHERE om.user_id = $1 OR r.created_by = $1
-             ORDER BY o.name, r.name",
-            user_id_int
+        match extract_user_from_auth(headers, state, false).await? {
+            Some(uid) => CatalogCaller::User(uid),
+            None => CatalogCaller::Anonymous,
+        }
+    };
+
+    match catalog_access(state.config.registry.catalog_visibility, &caller) {
+        Ok(()) => Ok(caller),
+        Err(StatusCode::UNAUTHORIZED) => Err(authentication_required()),
+        Err(status) => Err(registry_error(status, "DENIED", "Listing the catalog requires an administrator")),
+    }
+}
+
+/// Popular repositories - GET /v2/_popular
+/// Ranks public repositories by manifest pulls over the last
+/// `POPULAR_WINDOW_DAYS` days, for registry landing pages. Who may ask
+/// follows `CATALOG_VISIBILITY`, as for the catalog.
+#[utoipa::path(
+    get,
+    path = "/v2/_popular",
+    tag = "docker-registry-v2",
+    params(
+        ("limit" = Option<usize>, Query, description = "Number of repositories to return (default 10, at most 100)"),
+    ),
+    responses(
+        (status = 200, description = "Most pulled public repositories", body = PopularResponse),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Catalog is restricted to administrators"),
+    )
+)]
+pub async fn get_popular(
+    State(state): State<AppState>,
+    Query(query): Query<PopularQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(response) = authorize_catalog(&state, &headers).await {
+        return response;
+    }
+
+    let window_days = state.config.registry.popular_window_days;
+    let limit = query.limit.unwrap_or(DEFAULT_POPULAR_LIMIT).clamp(1, MAX_POPULAR_LIMIT);
+    match popular_repositories(&state.db_pool, state.config.database.tenancy_mode, window_days, limit).await {
+        Ok(repositories) => (StatusCode::OK, Json(PopularResponse { window_days, repositories })).into_response(),
+        Err(e) => {
+            tracing::error!("Database error ranking popular repositories: {}", e);
+            registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error")
+        }
+    }
+}
+
+/// The `limit` public repositories with the most pulls over the last
+/// `window_days` days. Each tenant ranks its own in SQL; only those top
+/// lists are merged here.
+pub async fn popular_repositories(
+    pool: &sqlx::PgPool,
+    mode: crate::tenant::TenancyMode,
+    window_days: u32,
+    limit: usize,
+) -> Result<Vec<PopularRepository>, sqlx::Error> {
+    let totals = crate::tenant::across_tenants(pool, mode, || {
+        sqlx::query_as::<_, (String, bool, i64)>(
+            "SELECT CONCAT(o.name, '/', r.name) COLLATE \"C\" AS name, r.is_public, SUM(p.pulls)::BIGINT AS pulls
+             FROM repository_daily_pulls p
+             JOIN repositories r ON r.id = p.repository_id
+             JOIN organizations o ON o.id = r.organization_id
+             WHERE r.is_public AND p.day > CURRENT_DATE - $1::INT
+             GROUP BY o.name, r.name, r.is_public
+             HAVING SUM(p.pulls) > 0
+             ORDER BY pulls DESC, name
+             LIMIT $2",
         )
-        .fetch_all(&state.db_pool)
-        .await
-        {
-            Ok(rows) => {
-                rows.into_iter()
-                    .filter_map(|row| row.full_name)
-                    .collect::<Vec<String>>()
-            },
-            Err(e) => {
-                println!("❌ Database error querying repositories: {}", e);
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({
-                        "errors": [{
-                            "code": "UNKNOWN",
-                            "message": "Internal server error",
-                            "detail": {}
-                        }]
-                    }))
-                ).into_response();
-            }
+        .bind(window_days as i32)
+        .bind(limit as i64)
+        .fetch_all(pool)
+    })
+    .timed("popular_repositories")
+    .await?;
+
+    let totals = totals
+        .into_iter()
+        .map(|(name, is_public, pulls)| PullTotal { name, is_public, pulls })
+        .collect();
+    Ok(rank_popular(totals, limit))
+}
+
+/// The `limit` public repositories of `totals` with the most pulls, ties
+/// broken by name; private and never pulled repositories are left out
+pub fn rank_popular(totals: Vec<PullTotal>, limit: usize) -> Vec<PopularRepository> {
+    let mut ranked: Vec<PopularRepository> = totals
+        .into_iter()
+        .filter(|total| total.is_public && total.pulls > 0)
+        .map(|total| PopularRepository { name: total.name, pulls: total.pulls })
+        .collect();
+    ranked.sort_by(|a, b| b.pulls.cmp(&a.pulls).then_with(|| a.name.cmp(&b.name)));
+    ranked.truncate(limit);
+    ranked
+}
+
+/// Who is listing the catalog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogCaller {
+    Anonymous,
+    /// A user ID, or `org_<id>` for organization credentials
+    User(String),
+    /// A caller presenting the admin token
+    Admin,
+}
+
+impl CatalogCaller {
+    /// Identifies the caller's view of the catalog in cache keys
+    fn cache_id(&self) -> &str {
+        match self {
+            CatalogCaller::Anonymous => "anonymous",
+            CatalogCaller::User(user_id) => user_id,
+            CatalogCaller::Admin => "admin",
+        }
+    }
+}
+
+/// Whether `caller` may list the catalog under `visibility`: 401 when
+/// signing in could help, 403 when it could not
+pub fn catalog_access(visibility: CatalogVisibility, caller: &CatalogCaller) -> Result<(), StatusCode> {
+    match (visibility, caller) {
+        (_, CatalogCaller::Admin) | (CatalogVisibility::Public, _) => Ok(()),
+        (_, CatalogCaller::Anonymous) => Err(StatusCode::UNAUTHORIZED),
+        (CatalogVisibility::Authenticated, CatalogCaller::User(_)) => Ok(()),
+        (CatalogVisibility::Admin, CatalogCaller::User(_)) => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+/// Names of the repositories `caller` can pull from: every repository for
+/// administrators, public ones for anyone, and for users also those of
+/// their organizations and those they created. Only the rows of the page
+/// `page_query` asks for are loaded from each tenant.
+async fn catalog_repositories(state: &AppState, caller: &CatalogCaller, page_query: &PageQuery) -> Result<Vec<String>, sqlx::Error> {
+    let mut names = crate::tenant::across_tenants(&state.db_pool, state.config.database.tenancy_mode, || {
+        tenant_catalog_repositories(state, caller, page_query)
+    })
+    .timed("catalog_repositories")
+    .await?;
+    // Each tenant's names come sorted; merge them in the same order
+    names.sort();
+    Ok(names)
+}
+
+async fn tenant_catalog_repositories(state: &AppState, caller: &CatalogCaller, page_query: &PageQuery) -> Result<Vec<String>, sqlx::Error> {
+    let (filter, id) = match caller {
+        CatalogCaller::Admin => ("WHERE TRUE", None),
+        CatalogCaller::Anonymous => ("WHERE r.is_public", None),
+        // Organization-level access - every repository of the organization
+        CatalogCaller::User(user_id) if user_id.starts_with("org_") => {
+            ("WHERE o.id = $3", Some(user_id[4..].parse::<i64>().unwrap_or(0)))
         }
+        CatalogCaller::User(user_id) => (
+            "LEFT JOIN organization_members om ON om.organization_id = o.id AND om.user_id = $3
+                 AND (om.expires_at IS NULL OR om.expires_at > NOW())
+             WHERE (om.user_id = $3 OR r.created_by = $3 OR r.is_public)",
+            Some(user_id.parse::<i64>().unwrap_or(0)),
+        ),
     };
+    let sql = format!(
+        "SELECT name FROM (
+             SELECT CONCAT(o.name, '/', r.name) COLLATE \"C\" AS name FROM repositories r
+             JOIN organizations o ON r.organization_id = o.id
+             {}
+         ) catalog
+         WHERE $1::TEXT IS NULL OR name > $1
+         ORDER BY name
+         LIMIT $2",
+        filter
+    );
 
-    println!("📋 Found {} repositories for user", repositories.len());
-    
-    // Update cache if available
+    let mut query = sqlx::query_scalar::<_, String>(&sql)
+        .bind(page_query.last.as_deref())
+        .bind(page_query.fetch_limit());
+    if let Some(id) = id {
+        query = query.bind(id);
+    }
+    query.fetch_all(&state.db_pool).await
+}
+
+fn catalog_response(page: Page<String>) -> Response {
+    let mut headers = HeaderMap::new();
+    page.add_link("/v2/_catalog", &mut headers);
+
+    let response = CatalogResponse { repositories: page.items };
+    (StatusCode::OK, headers, Json(response)).into_response()
+}
+
+/// Drop cached catalog pages once a repository is created or deleted
+pub(crate) async fn invalidate_catalog(state: &AppState) {
     if let Some(cache) = &state.cache {
-        if let Err(e) = cache.cache_repositories(repositories.clone()).await {
-            println!("⚠️ Failed to cache repositories: {}", e);
-        } else {
-            println!("✅ Updated repository cache");
+        if let Err(e) = cache.invalidate_repositories().await {
+            println!("⚠️ Failed to invalidate catalog cache: {}", e);
         }
     }
-
-    let response = CatalogResponse { repositories };
-    (StatusCode::OK, Json(response)).into_response()
 }
 
 /// Get manifest - GET /v2/<name>/manifests/<reference>
 /// Retrieves an image manifest by name and reference (tag or digest)
-/// Requires authentication and pull permission
+/// Requires pull permission, or a public repository for anonymous callers
 #[utoipa::path(
     get,
     path = "/v2/{name}/manifests/{reference}",
@@ -254,6 +477,7 @@ pub async fn get_catalog(
     ),
     responses(
         (status = 200, description = "Image manifest"),
+        (status = 304, description = "If-None-Match matched the manifest digest"),
         (status = 404, description = "Manifest not found"),
         (status = 401, description = "Authentication required"),
         (status = 403, description = "Insufficient permissions"),
@@ -261,26 +485,25 @@ pub async fn get_catalog(
 )]
 pub async fn get_manifest(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    user: MaybeAuthUser,
     axum::extract::Path((name, reference)): axum::extract::Path<(String, String)>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    // Require authentication for manifest pull
-    let user_id = match extract_user_from_auth(&headers, &state, true).await {
-        Ok(Some(uid)) => uid,
-        Ok(None) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                [("WWW-Authenticate", "Basic")],
-                Json(serde_json::json!({
-                    "errors": [{
-                        "code": "UNAUTHORIZED",
-                        "message": "Authentication required",
-                        "detail": {}
-                    }]
-                }))
-            ).into_response();
+    // Anonymous callers may pull public repositories; without a caller
+    // namespace, unqualified names resolve against the default organization
+    let user_id = match user.0 {
+        Some(user_id) => user_id,
+        None => {
+            let (org, repository) = match name.split_once('/') {
+                Some((org, repository)) => (Some(org), repository),
+                None => (None, name.as_str()),
+            };
+            if let Err(response) = authorize_pull(&state.db_pool, org, repository, None).await {
+                return response;
+            }
+            let response = negotiate_manifest_type(&headers, get_manifest_impl(&state, &name, &reference, ManifestRead::Pull(None)).await);
+            return apply_manifest_preconditions(&headers, response);
         }
-        Err(response) => return response,
     };
 
     // Parse namespace/repository from name
@@ -304,7 +527,8 @@ pub async fn get_manifest(
     match check_repository_permission(&user_id, &namespace, &repository, "pull", &state).await {
         Ok(true) => {
             println!("✅ User {} has pull permission for {}/{}", user_id, namespace, repository);
-            get_manifest_impl(&state, &name, &reference).await
+            let response = negotiate_manifest_type(&headers, get_manifest_impl(&state, &name, &reference, ManifestRead::Pull(Some(user_id.as_str()))).await);
+            apply_manifest_preconditions(&headers, response)
         }
         Ok(false) => {
             println!("❌ User {} denied pull access to {}/{}", user_id, namespace, repository);
@@ -387,13 +611,8 @@ pub async fn head_manifest(
     // Check if user has pull permission
     match check_repository_permission(&user_id, namespace, repository, "pull", &state).await {
         Ok(true) => {
-            // Call the existing implementation
-            let result = get_manifest_impl(&state, &name, &reference).await;
-            match result.into_response().status() {
-                StatusCode::OK => (StatusCode::OK, "").into_response(),
-                StatusCode::NOT_FOUND => (StatusCode::NOT_FOUND, "").into_response(),
-                _ => (StatusCode::INTERNAL_SERVER_ERROR, "").into_response(),
-            }
+            // Same headers as GET (digest, type, length), without the body
+            head_manifest_impl(&state, &name, &reference).await
         }
         Ok(false) => {
             (StatusCode::FORBIDDEN, "").into_response()
@@ -416,9 +635,10 @@ pub async fn head_manifest(
     ),
     responses(
         (status = 201, description = "Manifest uploaded"),
-        (status = 400, description = "Invalid manifest"),
+        (status = 400, description = "Invalid manifest, config blob not uploaded, or layer blob not uploaded while an image size limit applies"),
         (status = 401, description = "Authentication required"),
         (status = 403, description = "Insufficient permissions"),
+        (status = 413, description = "Image layers add up to more than the organization's or MAX_IMAGE_BYTES limit (DENIED)"),
     )
 )]
 pub async fn put_manifest(
@@ -512,81 +732,1375 @@ pub async fn put_manifest(
         ("reference" = String, Path, description = "Tag or digest"),
     ),
     responses(
-        (status = 202, description = "Manifest deleted"),
+        (status = 202, description = "Manifest deleted, storage cleanup queued (DELETION_MODE=async)"),
+        (status = 204, description = "Manifest and its unreferenced blobs deleted (DELETION_MODE=sync)"),
         (status = 404, description = "Manifest not found"),
         (status = 401, description = "Authentication required"),
-        (status = 405, description = "Delete not allowed"),
+        (status = 403, description = "Only organization owners and admins can delete"),
     )
 )]
 pub async fn delete_manifest(
     State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
     axum::extract::Path((name, reference)): axum::extract::Path<(String, String)>,
 ) -> impl IntoResponse {
-    delete_manifest_impl(&state, &name, &reference).await
+    delete_manifest_impl(&state, &user_id, &name, &reference).await
 }
 
-/// Get blob - GET /v2/<name>/blobs/<digest>
-/// Downloads a blob (layer) by digest
+/// Bulk tag deletion request: either exact tag names or a glob pattern
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BulkTagDeleteRequest {
+    /// Exact tag names to delete
+    pub tags: Option<Vec<String>>,
+    /// Glob pattern (`*` and `?` wildcards) matched against all tags
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkTagDeleteResponse {
+    pub name: String,
+    pub deleted: u64,
+    pub tags: Vec<String>,
+}
+
+/// Pick the existing tags a bulk delete request refers to. Exactly one of
+/// `tags` or `pattern` must be given; names that don't exist are ignored.
+pub fn select_tags_to_delete(
+    request: &BulkTagDeleteRequest,
+    existing: &[String],
+) -> Result<Vec<String>, String> {
+    match (&request.tags, &request.pattern) {
+        (Some(tags), None) => Ok(existing
+            .iter()
+            .filter(|tag| tags.contains(tag))
+            .cloned()
+            .collect()),
+        (None, Some(pattern)) if !pattern.is_empty() => Ok(existing
+            .iter()
+            .filter(|tag| tag_matches_pattern(pattern, tag))
+            .cloned()
+            .collect()),
+        (None, Some(_)) => Err("pattern must not be empty".to_string()),
+        _ => Err("exactly one of 'tags' or 'pattern' is required".to_string()),
+    }
+}
+
+/// Bulk delete tags - POST /v2/<name>/tags/delete
+/// Deletes every tag named in the request, or matching its pattern, in one transaction
+/// Requires authentication and delete permission
 #[utoipa::path(
-    get,
-    path = "/v2/{name}/blobs/{digest}",
+    post,
+    path = "/v2/{name}/tags/delete",
     tag = "docker-registry-v2",
     params(
         ("name" = String, Path, description = "Repository name"),
-        ("digest" = String, Path, description = "Blob digest"),
     ),
+    request_body = BulkTagDeleteRequest,
     responses(
-        (status = 200, description = "Blob content"),
-        (status = 404, description = "Blob not found"),
+        (status = 200, description = "Tags deleted", body = BulkTagDeleteResponse),
+        (status = 400, description = "Neither or both of tags and pattern given"),
         (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 404, description = "Repository not found"),
     )
 )]
-pub async fn get_blob(
+pub async fn bulk_delete_tags(
     State(state): State<AppState>,
-    axum::extract::Path((name, digest)): axum::extract::Path<(String, String)>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Json(request): Json<BulkTagDeleteRequest>,
 ) -> impl IntoResponse {
-    get_blob_impl(&state, &name, &digest).await
+    bulk_delete_tags_impl(&state, &user_id, &name, request).await
 }
 
-/// Check if blob exists - HEAD /v2/<name>/blobs/<digest>
-#[utoipa::path(
-    head,
-    path = "/v2/{name}/blobs/{digest}",
-    tag = "docker-registry-v2",
-    params(
-        ("name" = String, Path, description = "Repository name"),
-        ("digest" = String, Path, description = "Blob digest"),
-    ),
-    responses(
-        (status = 200, description = "Blob exists"),
-        (status = 404, description = "Blob not found"),
-        (status = 401, description = "Authentication required"),
-    )
-)]
-pub async fn head_blob(
+pub async fn bulk_delete_tags_namespaced(
     State(state): State<AppState>,
-    axum::extract::Path((name, digest)): axum::extract::Path<(String, String)>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path((org, name)): axum::extract::Path<(String, String)>,
+    Json(request): Json<BulkTagDeleteRequest>,
 ) -> impl IntoResponse {
-    head_blob_impl(&state, &name, &digest).await
+    let full_name = format!("{}/{}", org, name);
+    bulk_delete_tags_impl(&state, &user_id, &full_name, request).await
 }
 
-/// Start blob upload - POST /v2/<name>/blobs/uploads/
-/// Initiates a resumable blob upload
-/// Requires authentication and push permission
-#[utoipa::path(
-    post,
-    path = "/v2/{name}/blobs/uploads/",
-    tag = "docker-registry-v2", 
-    params(
-        ("name" = String, Path, description = "Repository name"),
-    ),
-    responses(
-        (status = 202, description = "Upload initiated", body = BlobUploadResponse),
-        (status = 401, description = "Authentication required"),
-        (status = 403, description = "Insufficient permissions"),
+async fn bulk_delete_tags_impl(
+    state: &AppState,
+    user_id: &str,
+    name: &str,
+    request: BulkTagDeleteRequest,
+) -> Response {
+    let registry_error = |status: StatusCode, code: &str, message: &str| {
+        (
+            status,
+            Json(serde_json::json!({
+                "errors": [{
+                    "code": code,
+                    "message": message,
+                    "detail": {}
+                }]
+            }))
+        ).into_response()
+    };
+
+    let (namespace, repository) = match parse_repository_name(name, user_id, state).await {
+        Ok(parts) => parts,
+        Err(_) => return registry_error(StatusCode::BAD_REQUEST, "NAME_INVALID", "Invalid repository name format"),
+    };
+
+    match check_repository_permission(user_id, &namespace, &repository, "delete", state).await {
+        Ok(true) => {}
+        Ok(false) => {
+            println!("❌ User {} denied tag deletion in {}/{}", user_id, namespace, repository);
+            return registry_error(StatusCode::FORBIDDEN, "DENIED", "Insufficient permissions to delete tags");
+        }
+        Err(e) => {
+            println!("❌ Error checking permissions: {}", e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+        }
+    }
+
+    let (repository_id, organization_id) = match sqlx::query_as::<_, (i64, i64)>(
+        "SELECT r.id, o.id FROM repositories r JOIN organizations o ON r.organization_id = o.id WHERE o.name = $1 AND r.name = $2"
     )
-)]
-pub async fn start_blob_upload(
+    .bind(&namespace)
+    .bind(&repository)
+    .fetch_optional(&state.db_pool)
+    .timed("bulk_delete_tags_impl: SELECT repositories")
+    .await
+    {
+        Ok(Some(ids)) => ids,
+        Ok(None) => return registry_error(StatusCode::NOT_FOUND, "NAME_UNKNOWN", "repository name not known to registry"),
+        Err(e) => {
+            println!("❌ Database error looking up {}/{}: {}", namespace, repository, e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+        }
+    };
+
+    let existing: Vec<String> = match sqlx::query_scalar("SELECT name FROM tags WHERE repository_id = $1")
+        .bind(repository_id)
+        .fetch_all(&state.db_pool)
+        .timed("bulk_delete_tags_impl: SELECT tags")
+        .await
+    {
+        Ok(tags) => tags,
+        Err(e) => {
+            println!("❌ Database error listing tags for {}/{}: {}", namespace, repository, e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+        }
+    };
+
+    let matching = match select_tags_to_delete(&request, &existing) {
+        Ok(tags) => tags,
+        Err(message) => return registry_error(StatusCode::BAD_REQUEST, "TAG_INVALID", &message),
+    };
+
+    let full_name = format!("{}/{}", namespace, repository);
+    let names = crate::cache::repository_pull_names(&namespace, &repository, organization_id);
+    match crate::handlers::tag_expiry::delete_tags(state, repository_id, &names, &matching, false).await {
+        Ok(deleted) => {
+            println!("🗑️ Deleted {} tag(s) from {}", deleted, full_name);
+            (
+                StatusCode::OK,
+                Json(BulkTagDeleteResponse {
+                    name: full_name,
+                    deleted,
+                    tags: matching,
+                })
+            ).into_response()
+        }
+        Err(e) if e.is::<crate::tag_lock::TagLockError>() => {
+            tracing::warn!(repository = %full_name, "Tags not deleted: {}", e);
+            registry_error(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "UNAVAILABLE",
+                "Tags are being updated by another push, retry later",
+            )
+        }
+        Err(e) => {
+            println!("❌ Failed to delete tags from {}: {}", full_name, e);
+            registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error")
+        }
+    }
+}
+
+/// Summary of a repository deletion
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RepositoryDeleteResponse {
+    pub name: String,
+    pub tags_deleted: u64,
+    pub manifests_deleted: u64,
+    /// Blobs handed to background cleanup; each is removed from storage only
+    /// if no remaining manifest references it
+    pub blobs_queued_for_cleanup: u64,
+}
+
+/// Digests of the blobs an image manifest references: its config and layers.
+/// Indexes and unparsable content reference none.
+pub fn manifest_blob_digests(content: &str) -> Vec<String> {
+    let mut digests: Vec<String> = manifest_blob_descriptors(content)
+        .into_iter()
+        .map(|(digest, _)| digest)
+        .collect();
+    digests.sort();
+    digests.dedup();
+    digests
+}
+
+/// Every digest a manifest references: config and layer blobs of an image
+/// manifest, and the child manifests of an index
+pub fn manifest_referenced_digests(content: &str) -> Vec<String> {
+    let children: Vec<String> = serde_json::from_str::<serde_json::Value>(content)
+        .ok()
+        .and_then(|manifest| manifest.get("manifests").and_then(|m| m.as_array()).cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|descriptor| descriptor.get("digest").and_then(|d| d.as_str()).map(str::to_string))
+        .collect();
+
+    let mut digests = manifest_blob_digests(content);
+    digests.extend(children);
+    digests.sort();
+    digests.dedup();
+    digests
+}
+
+/// Index what `content`, the manifest stored as `manifest_id`, references,
+/// so blob cleanup can tell which blobs are still in use
+pub async fn record_manifest_blob_references(pool: &sqlx::PgPool, manifest_id: i64, content: &str) -> Result<(), sqlx::Error> {
+    let digests = manifest_referenced_digests(content);
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM manifest_blob_references WHERE manifest_id = $1")
+        .bind(manifest_id)
+        .execute(&mut *tx)
+        .timed("record_manifest_blob_references: DELETE manifest_blob_references")
+        .await?;
+    sqlx::query(
+        "INSERT INTO manifest_blob_references (manifest_id, digest)
+         SELECT $1, UNNEST($2::text[])
+         ON CONFLICT DO NOTHING"
+    )
+    .bind(manifest_id)
+    .bind(&digests)
+    .execute(&mut *tx)
+    .timed("record_manifest_blob_references: INSERT manifest_blob_references")
+    .await?;
+    sqlx::query("UPDATE manifests SET blob_references_indexed = TRUE WHERE id = $1")
+        .bind(manifest_id)
+        .execute(&mut *tx)
+        .timed("record_manifest_blob_references: UPDATE manifests")
+        .await?;
+
+    tx.commit().await
+}
+
+/// Index the references of manifests pushed before they were recorded on
+/// push, reading their content from storage. A manifest whose content is
+/// gone references nothing that could still be read, so it is marked indexed.
+pub async fn backfill_manifest_blob_references(state: &AppState) -> anyhow::Result<u64> {
+    let indexed = crate::tenant::across_tenants(&state.db_pool, state.config.database.tenancy_mode, || async move {
+        backfill_tenant_blob_references(state).await.map(|indexed| vec![indexed])
+    })
+    .await?;
+    Ok(indexed.into_iter().sum())
+}
+
+async fn backfill_tenant_blob_references(state: &AppState) -> anyhow::Result<u64> {
+    let pending = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, digest FROM manifests WHERE NOT blob_references_indexed ORDER BY id"
+    )
+    .fetch_all(&state.db_pool)
+    .timed("backfill_tenant_blob_references")
+    .await?;
+
+    let mut indexed = 0;
+    for (manifest_id, digest) in pending {
+        let content = match state.storage.get_blob(&format!("blobs/{}", digest)).await {
+            Ok(Some(bytes)) => String::from_utf8_lossy(&bytes).into_owned(),
+            Ok(None) => String::new(),
+            Err(e) => {
+                tracing::warn!(%digest, "Could not read manifest to index its references: {}", e);
+                continue;
+            }
+        };
+        record_manifest_blob_references(&state.db_pool, manifest_id, &content).await?;
+        indexed += 1;
+    }
+    Ok(indexed)
+}
+
+/// Digest and declared size of each blob descriptor (config, then layers)
+/// of an image manifest. Descriptors without a size count as 0 bytes.
+pub fn manifest_blob_descriptors(content: &str) -> Vec<(String, u64)> {
+    let manifest: serde_json::Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(_) => return Vec::new(),
+    };
+
+    let config = manifest.get("config").into_iter();
+    let layers = manifest
+        .get("layers")
+        .and_then(|layers| layers.as_array())
+        .into_iter()
+        .flatten();
+
+    config
+        .chain(layers)
+        .filter_map(|descriptor| {
+            let digest = descriptor.get("digest").and_then(|d| d.as_str())?;
+            let size = descriptor.get("size").and_then(|s| s.as_u64()).unwrap_or(0);
+            Some((digest.to_string(), size))
+        })
+        .collect()
+}
+
+/// A layer present in only one of two compared manifests
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct LayerChange {
+    pub digest: String,
+    pub size: u64,
+}
+
+/// Differences between two image manifests
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ManifestDiff {
+    /// Layers of the second manifest that the first lacks, in order
+    pub layers_added: Vec<LayerChange>,
+    /// Layers of the first manifest that the second lacks, in order
+    pub layers_removed: Vec<LayerChange>,
+    pub layers_shared: usize,
+    pub config_changed: bool,
+    pub from_config: Option<String>,
+    pub to_config: Option<String>,
+    /// Change in total config and layer size, in bytes
+    pub size_delta: i64,
+}
+
+/// Response of the manifest diff endpoint
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ManifestDiffResponse {
+    /// Digest of the first manifest
+    pub from: String,
+    /// Digest of the second manifest
+    pub to: String,
+    #[serde(flatten)]
+    pub diff: ManifestDiff,
+}
+
+/// Config and layer descriptors of an image manifest; `None` for indexes and
+/// unparsable content
+fn image_manifest_descriptors(content: &str) -> Option<(Option<LayerChange>, Vec<LayerChange>)> {
+    let manifest: serde_json::Value = serde_json::from_str(content).ok()?;
+    let descriptor = |value: &serde_json::Value| {
+        let digest = value.get("digest").and_then(|d| d.as_str())?;
+        let size = value.get("size").and_then(|s| s.as_u64()).unwrap_or(0);
+        Some(LayerChange { digest: digest.to_string(), size })
+    };
+
+    let layers = manifest.get("layers")?.as_array()?;
+    let config = manifest.get("config").and_then(descriptor);
+    Some((config, layers.iter().filter_map(descriptor).collect()))
+}
+
+/// Compare two image manifests by config and layer digests. Returns `None`
+/// if either is not an image manifest.
+pub fn diff_manifests(from: &str, to: &str) -> Option<ManifestDiff> {
+    let (from_config, from_layers) = image_manifest_descriptors(from)?;
+    let (to_config, to_layers) = image_manifest_descriptors(to)?;
+
+    let only_in = |layers: &[LayerChange], other: &[LayerChange]| -> Vec<LayerChange> {
+        layers
+            .iter()
+            .filter(|layer| !other.iter().any(|o| o.digest == layer.digest))
+            .cloned()
+            .collect()
+    };
+    let layers_added = only_in(&to_layers, &from_layers);
+    let layers_removed = only_in(&from_layers, &to_layers);
+    let layers_shared = to_layers.len() - layers_added.len();
+
+    let total_size = |config: &Option<LayerChange>, layers: &[LayerChange]| -> i64 {
+        let config_size = config.as_ref().map_or(0, |c| c.size);
+        (config_size + layers.iter().map(|l| l.size).sum::<u64>()) as i64
+    };
+    let size_delta = total_size(&to_config, &to_layers) - total_size(&from_config, &from_layers);
+
+    let from_config = from_config.map(|c| c.digest);
+    let to_config = to_config.map(|c| c.digest);
+    Some(ManifestDiff {
+        layers_added,
+        layers_removed,
+        layers_shared,
+        config_changed: from_config != to_config,
+        from_config,
+        to_config,
+        size_delta,
+    })
+}
+
+/// Compare manifests - GET /v2/<name>/manifests/<from>/diff/<to>
+/// Lists the layers added and removed between two tags or digests, whether
+/// the config changed, and the change in image size
+/// Requires authentication and pull permission
+#[utoipa::path(
+    get,
+    path = "/v2/{name}/manifests/{from}/diff/{to}",
+    tag = "docker-registry-v2",
+    params(
+        ("name" = String, Path, description = "Repository name"),
+        ("from" = String, Path, description = "Tag or digest of the first manifest"),
+        ("to" = String, Path, description = "Tag or digest of the second manifest"),
+    ),
+    responses(
+        (status = 200, description = "Manifest differences", body = ManifestDiffResponse),
+        (status = 400, description = "A reference is not an image manifest"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 404, description = "Repository or manifest not found"),
+    )
+)]
+pub async fn diff_manifests_handler(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path((name, from, to)): axum::extract::Path<(String, String, String)>,
+) -> impl IntoResponse {
+    diff_manifests_impl(&state, &user_id, &name, &from, &to).await
+}
+
+pub async fn diff_manifests_namespaced(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path((org, name, from, to)): axum::extract::Path<(String, String, String, String)>,
+) -> impl IntoResponse {
+    let full_name = format!("{}/{}", org, name);
+    diff_manifests_impl(&state, &user_id, &full_name, &from, &to).await
+}
+
+async fn diff_manifests_impl(state: &AppState, user_id: &str, name: &str, from: &str, to: &str) -> Response {
+    let repository_id = match pullable_repository_id(state, user_id, name).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    let (from_digest, from_content) = match resolve_manifest_content(state, repository_id, from).await {
+        Ok(manifest) => manifest,
+        Err(response) => return response,
+    };
+    let (to_digest, to_content) = match resolve_manifest_content(state, repository_id, to).await {
+        Ok(manifest) => manifest,
+        Err(response) => return response,
+    };
+
+    match diff_manifests(&from_content, &to_content) {
+        Some(diff) => (
+            StatusCode::OK,
+            Json(ManifestDiffResponse { from: from_digest.into(), to: to_digest.into(), diff }),
+        ).into_response(),
+        None => registry_error(StatusCode::BAD_REQUEST, "MANIFEST_INVALID", "only image manifests can be compared"),
+    }
+}
+
+/// ID of repository `name`, once `user_id` is allowed to pull from it
+async fn pullable_repository_id(state: &AppState, user_id: &str, name: &str) -> Result<i64, Response> {
+    let (namespace, repository) = match parse_repository_name(name, user_id, state).await {
+        Ok(parts) => parts,
+        Err(_) => return Err(registry_error(StatusCode::BAD_REQUEST, "NAME_INVALID", "Invalid repository name format")),
+    };
+
+    match check_repository_permission(user_id, &namespace, &repository, "pull", state).await {
+        Ok(true) => {}
+        Ok(false) => {
+            println!("❌ User {} denied pull access to {}/{}", user_id, namespace, repository);
+            return Err(registry_error(StatusCode::FORBIDDEN, "DENIED", "Insufficient permissions to pull from repository"));
+        }
+        Err(e) => {
+            println!("❌ Error checking permissions: {}", e);
+            return Err(registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error"));
+        }
+    }
+
+    match sqlx::query_scalar::<_, i64>(
+        "SELECT r.id FROM repositories r JOIN organizations o ON r.organization_id = o.id WHERE o.name = $1 AND r.name = $2"
+    )
+    .bind(&namespace)
+    .bind(&repository)
+    .fetch_optional(&state.db_pool)
+    .timed("pullable_repository_id")
+    .await
+    {
+        Ok(Some(id)) => Ok(id),
+        Ok(None) => Err(registry_error(StatusCode::NOT_FOUND, "NAME_UNKNOWN", "repository name not known to registry")),
+        Err(e) => {
+            println!("❌ Database error looking up {}/{}: {}", namespace, repository, e);
+            Err(registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error"))
+        }
+    }
+}
+
+/// Export repository - GET /v2/_export/<name>
+/// Streams the repository as an OCI image layout tarball (`oci-layout`,
+/// `index.json` and blobs) holding every tag and what it references, for
+/// loading elsewhere with `skopeo copy oci-archive:...` or oras.
+/// Requires authentication and pull permission
+#[utoipa::path(
+    get,
+    path = "/v2/_export/{name}",
+    tag = "docker-registry-v2",
+    params(
+        ("name" = String, Path, description = "Repository name"),
+    ),
+    responses(
+        (status = 200, description = "OCI image layout tarball", content_type = "application/x-tar"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions, or a tag is blocked for critical vulnerabilities"),
+        (status = 404, description = "Repository not found"),
+        (status = 500, description = "A referenced manifest or blob is missing"),
+    )
+)]
+pub async fn export_repository(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> impl IntoResponse {
+    export_repository_impl(&state, &user_id, &name).await
+}
+
+pub async fn export_repository_namespaced(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path((org, name)): axum::extract::Path<(String, String)>,
+) -> impl IntoResponse {
+    let full_name = format!("{}/{}", org, name);
+    export_repository_impl(&state, &user_id, &full_name).await
+}
+
+async fn export_repository_impl(state: &AppState, user_id: &str, name: &str) -> Response {
+    let repository_id = match pullable_repository_id(state, user_id, name).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    let tags = match sqlx::query_as::<_, (String, String)>(
+        "SELECT t.name, m.digest FROM tags t JOIN manifests m ON m.id = t.manifest_id
+         WHERE t.repository_id = $1 ORDER BY t.name"
+    )
+    .bind(repository_id)
+    .fetch_all(&state.db_pool)
+    .timed("export_repository_impl: SELECT tags")
+    .await
+    {
+        Ok(rows) => rows.into_iter().map(|(name, digest)| LayoutTag { name, digest }).collect::<Vec<_>>(),
+        Err(e) => {
+            println!("❌ Database error listing tags of {} for export: {}", name, e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+        }
+    };
+
+    // An export pulls every tag, so a blocked one refuses the whole export
+    for tag in &tags {
+        if let Some(denied) = vulnerable_pull_denial(&state.db_pool, &state.blocking_organizations, state.config.registry.block_vulnerable_pulls, name, &tag.name).await {
+            return denied;
+        }
+    }
+
+    let rows = match sqlx::query_as::<_, (String, String, Option<String>)>(
+        "SELECT digest, media_type, content FROM manifests WHERE repository_id = $1"
+    )
+    .bind(repository_id)
+    .fetch_all(&state.db_pool)
+    .timed("export_repository_impl: SELECT manifests")
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            println!("❌ Database error listing manifests of {} for export: {}", name, e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+        }
+    };
+
+    // Content is read as `resolve_manifest_content` does; manifests without
+    // any are reported by `plan_layout` if a tag reaches them
+    let mut manifests = Vec::with_capacity(rows.len());
+    for (digest, media_type, stored_content) in rows {
+        let from_storage = match state.storage.get_blob(&format!("blobs/{}", digest)).await {
+            Ok(Some(content)) => String::from_utf8(content.to_vec()).ok(),
+            Ok(None) => None,
+            Err(e) => {
+                println!("⚠️ Error retrieving manifest {} from storage: {}", digest, e);
+                None
+            }
+        };
+        let content = match from_storage {
+            Some(content) => Some(content),
+            None => state.manifest_cache.read().await.get(&digest).cloned().or(stored_content),
+        };
+        if let Some(content) = content {
+            manifests.push(LayoutManifest { digest, media_type, content });
+        }
+    }
+
+    let entries = match plan_layout(state.storage.as_ref(), &tags, &manifests).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("❌ Cannot export {}: {}", name, e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", &e.to_string());
+        }
+    };
+    println!("📦 Exporting {} ({} tag(s), {} file(s))", name, tags.len(), entries.len());
+
+    let length = layout_len(&entries);
+    let disposition = format!("attachment; filename=\"{}.tar\"", name.replace('/', "_"));
+    let mut response = Response::new(axum::body::Body::from_stream(layout_stream(state.storage.clone(), entries)));
+    let headers = response.headers_mut();
+    headers.insert(axum::http::header::CONTENT_TYPE, HeaderValue::from_static("application/x-tar"));
+    headers.insert(axum::http::header::CONTENT_LENGTH, HeaderValue::from(length));
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        headers.insert(axum::http::header::CONTENT_DISPOSITION, value);
+    }
+    response
+}
+
+/// Details of an image, decoded from its config blob
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ImageConfigDetails {
+    pub architecture: Option<String>,
+    pub os: Option<String>,
+    /// When the image was built, as recorded by the builder (RFC 3339)
+    pub created: Option<String>,
+    pub labels: BTreeMap<String, String>,
+    pub entrypoint: Vec<String>,
+    pub cmd: Vec<String>,
+    /// `NAME=value` pairs
+    pub env: Vec<String>,
+    pub working_dir: Option<String>,
+    pub user: Option<String>,
+}
+
+/// Image config blob, as the OCI image spec lays it out
+#[derive(Deserialize)]
+struct RawImageConfig {
+    architecture: Option<String>,
+    os: Option<String>,
+    created: Option<String>,
+    config: Option<RawContainerConfig>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+struct RawContainerConfig {
+    labels: Option<BTreeMap<String, String>>,
+    entrypoint: Option<Vec<String>>,
+    cmd: Option<Vec<String>>,
+    env: Option<Vec<String>>,
+    working_dir: Option<String>,
+    user: Option<String>,
+}
+
+/// Decode an image config blob. Returns `None` if it is not a JSON object
+/// in the image config layout.
+pub fn parse_image_config(blob: &[u8]) -> Option<ImageConfigDetails> {
+    let raw: RawImageConfig = serde_json::from_slice(blob).ok()?;
+    let config = raw.config.unwrap_or_default();
+    let non_empty = |value: Option<String>| value.filter(|v| !v.is_empty());
+    Some(ImageConfigDetails {
+        architecture: raw.architecture,
+        os: raw.os,
+        created: raw.created,
+        labels: config.labels.unwrap_or_default(),
+        entrypoint: config.entrypoint.unwrap_or_default(),
+        cmd: config.cmd.unwrap_or_default(),
+        env: config.env.unwrap_or_default(),
+        working_dir: non_empty(config.working_dir),
+        user: non_empty(config.user),
+    })
+}
+
+/// Response of the image config endpoint
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImageConfigResponse {
+    /// Digest of the manifest the reference resolved to
+    pub manifest: String,
+    /// Digest of the config blob
+    pub config_digest: String,
+    #[serde(flatten)]
+    pub config: ImageConfigDetails,
+}
+
+/// Get image config - GET /v2/<name>/manifests/<reference>/config
+/// Decodes the config blob of an image manifest: platform, creation time,
+/// labels, entrypoint, command and environment, without pulling the image
+/// Requires authentication and pull permission
+#[utoipa::path(
+    get,
+    path = "/v2/{name}/manifests/{reference}/config",
+    tag = "docker-registry-v2",
+    params(
+        ("name" = String, Path, description = "Repository name"),
+        ("reference" = String, Path, description = "Tag or digest of an image manifest"),
+    ),
+    responses(
+        (status = 200, description = "Decoded image config", body = ImageConfigResponse),
+        (status = 400, description = "Reference is not an image manifest, or its config cannot be decoded"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 404, description = "Repository, manifest or config blob not found"),
+    )
+)]
+pub async fn get_manifest_config(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path((name, reference)): axum::extract::Path<(String, String)>,
+) -> impl IntoResponse {
+    manifest_config_impl(&state, &user_id, &name, &reference).await
+}
+
+pub async fn get_manifest_config_namespaced(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path((org, name, reference)): axum::extract::Path<(String, String, String)>,
+) -> impl IntoResponse {
+    let full_name = format!("{}/{}", org, name);
+    manifest_config_impl(&state, &user_id, &full_name, &reference).await
+}
+
+async fn manifest_config_impl(state: &AppState, user_id: &str, name: &str, reference: &str) -> Response {
+    let repository_id = match pullable_repository_id(state, user_id, name).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+    let (manifest_digest, content) = match resolve_manifest_content(state, repository_id, reference).await {
+        Ok(manifest) => manifest,
+        Err(response) => return response,
+    };
+
+    let config_digest = match image_manifest_descriptors(&content) {
+        Some((Some(config), _)) => config.digest,
+        _ => return registry_error(StatusCode::BAD_REQUEST, "MANIFEST_INVALID", "only image manifests have a config; resolve an index to a platform manifest first"),
+    };
+    // Manifests pushed before descriptor digests were checked may carry any string
+    let config_key = match Digest::parse(&config_digest) {
+        Ok(digest) => digest.blob_key(),
+        Err(e) => return registry_error(StatusCode::BAD_REQUEST, "MANIFEST_INVALID", &format!("config digest invalid: {}", e)),
+    };
+    let blob = match state.storage.get_blob(&config_key).await {
+        Ok(Some(blob)) => blob,
+        Ok(None) => return registry_error(StatusCode::NOT_FOUND, "BLOB_UNKNOWN", &format!("config blob unknown: {}", config_digest)),
+        Err(e) => {
+            println!("❌ Error reading config blob {}: {}", config_digest, e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+        }
+    };
+
+    match parse_image_config(&blob) {
+        Some(config) => (
+            StatusCode::OK,
+            Json(ImageConfigResponse { manifest: manifest_digest.into(), config_digest, config }),
+        ).into_response(),
+        None => registry_error(StatusCode::BAD_REQUEST, "MANIFEST_INVALID", "config blob is not an image config"),
+    }
+}
+
+/// Most references one manifest batch may ask for
+pub const MAX_MANIFEST_BATCH: usize = 100;
+
+/// Tags and digests to fetch in one request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ManifestBatchRequest {
+    pub references: Vec<String>,
+}
+
+/// Why one reference in a batch could not be served, as the single-manifest
+/// endpoint would have answered it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ManifestBatchError {
+    pub status: u16,
+    pub code: String,
+    pub message: String,
+}
+
+/// One reference of a batch: its manifest, or the error it resolved to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ManifestBatchEntry {
+    pub reference: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    /// Manifest exactly as stored, so clients can check it against `digest`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ManifestBatchError>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ManifestBatchResponse {
+    pub name: String,
+    /// One entry per requested reference, in request order
+    pub manifests: Vec<ManifestBatchEntry>,
+}
+
+/// Check a batch asks for between one and `MAX_MANIFEST_BATCH` references
+pub fn validate_manifest_batch(references: &[String]) -> Result<(), String> {
+    match references.len() {
+        0 => Err("references must not be empty".to_string()),
+        n if n > MAX_MANIFEST_BATCH => Err(format!(
+            "at most {} references can be fetched at once, got {}",
+            MAX_MANIFEST_BATCH, n
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Batch entry for `reference`, from its digest and content or its error
+pub fn manifest_batch_entry(reference: String, resolved: Result<(String, String), ManifestBatchError>) -> ManifestBatchEntry {
+    match resolved {
+        Ok((digest, content)) => {
+            let media_type = serde_json::from_str::<serde_json::Value>(&content)
+                .map(|manifest| manifest_media_type(&manifest))
+                .unwrap_or(MediaType::DockerManifest);
+            ManifestBatchEntry {
+                reference,
+                digest: Some(digest),
+                media_type: Some(media_type.to_string()),
+                manifest: Some(content),
+                error: None,
+            }
+        }
+        Err(error) => ManifestBatchEntry {
+            reference,
+            digest: None,
+            media_type: None,
+            manifest: None,
+            error: Some(error),
+        },
+    }
+}
+
+/// Fetch manifests in bulk - POST /v2/_batch/<name>/manifests
+/// Resolves up to `MAX_MANIFEST_BATCH` tags or digests in one round trip.
+/// References that are unknown, invalid or blocked get a per-entry error
+/// instead of failing the whole batch.
+/// Requires authentication and pull permission
+#[utoipa::path(
+    post,
+    path = "/v2/_batch/{name}/manifests",
+    tag = "docker-registry-v2",
+    params(
+        ("name" = String, Path, description = "Repository name"),
+    ),
+    request_body = ManifestBatchRequest,
+    responses(
+        (status = 200, description = "Manifests and per-reference errors", body = ManifestBatchResponse),
+        (status = 400, description = "No references, or more than the batch limit"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions"),
+        (status = 404, description = "Repository not found"),
+    )
+)]
+pub async fn get_manifest_batch(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    Json(request): Json<ManifestBatchRequest>,
+) -> impl IntoResponse {
+    manifest_batch_impl(&state, &user_id, &name, request).await
+}
+
+pub async fn get_manifest_batch_namespaced(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path((org, name)): axum::extract::Path<(String, String)>,
+    Json(request): Json<ManifestBatchRequest>,
+) -> impl IntoResponse {
+    let full_name = format!("{}/{}", org, name);
+    manifest_batch_impl(&state, &user_id, &full_name, request).await
+}
+
+async fn manifest_batch_impl(state: &AppState, user_id: &str, name: &str, request: ManifestBatchRequest) -> Response {
+    if let Err(message) = validate_manifest_batch(&request.references) {
+        return registry_error(StatusCode::BAD_REQUEST, "BATCH_INVALID", &message);
+    }
+    let repository_id = match pullable_repository_id(state, user_id, name).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    let mut manifests = Vec::with_capacity(request.references.len());
+    for reference in request.references {
+        // Same checks as a single GET, so a batch cannot reach a blocked image
+        let resolved = match vulnerable_pull_denial(&state.db_pool, &state.blocking_organizations, state.config.registry.block_vulnerable_pulls, name, &reference).await {
+            Some(denied) => Err(denied),
+            None => resolve_manifest_content(state, repository_id, &reference).await,
+        };
+        let resolved = match resolved {
+            Ok((digest, content)) => {
+                record_manifest_pull(state, name, &digest, Some(user_id));
+                Ok((digest.into(), content))
+            }
+            Err(response) => Err(batch_error(response).await),
+        };
+        manifests.push(manifest_batch_entry(reference, resolved));
+    }
+
+    (
+        StatusCode::OK,
+        Json(ManifestBatchResponse { name: name.to_string(), manifests }),
+    ).into_response()
+}
+
+/// Per-entry error from a `registry_error` response
+async fn batch_error(response: Response) -> ManifestBatchError {
+    let status = response.status().as_u16();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap_or_default();
+    let error = serde_json::from_slice::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|body| body["errors"].get(0).cloned())
+        .unwrap_or_default();
+    let field = |name: &str| error[name].as_str().unwrap_or("UNKNOWN").to_string();
+    ManifestBatchError { status, code: field("code"), message: field("message") }
+}
+
+/// Digest and content of the manifest a tag or digest refers to. Content is
+/// read from storage, then the in-memory cache, then the database.
+async fn resolve_manifest_content(state: &AppState, repository_id: i64, reference: &str) -> Result<(Digest, String), Response> {
+    let query = if is_digest_reference(reference) {
+        validated_digest(reference)?;
+        "SELECT digest, content FROM manifests WHERE repository_id = $1 AND digest = $2"
+    } else {
+        "SELECT m.digest, m.content FROM manifests m JOIN tags t ON m.id = t.manifest_id
+         WHERE t.repository_id = $1 AND t.name = $2"
+    };
+    let (digest, stored_content) = match sqlx::query_as::<_, (Digest, Option<String>)>(query)
+        .bind(repository_id)
+        .bind(reference)
+        .fetch_optional(&state.db_pool)
+        .timed("resolve_manifest_content")
+        .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return Err(registry_error(StatusCode::NOT_FOUND, "MANIFEST_UNKNOWN", &format!("manifest unknown: {}", reference))),
+        Err(e) => {
+            println!("❌ Database error resolving manifest {}: {}", reference, e);
+            return Err(registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error"));
+        }
+    };
+
+    let from_storage = match state.storage.get_blob(&digest.blob_key()).await {
+        Ok(Some(content)) => String::from_utf8(content.to_vec()).ok(),
+        Ok(None) => None,
+        Err(e) => {
+            println!("⚠️ Error retrieving manifest {} from storage: {}", digest, e);
+            None
+        }
+    };
+    let content = match from_storage {
+        Some(content) => Some(content),
+        None => state.manifest_cache.read().await.get(digest.as_str()).cloned().or(stored_content),
+    };
+
+    match content {
+        Some(content) => Ok((digest, content)),
+        None => Err(registry_error(StatusCode::NOT_FOUND, "MANIFEST_UNKNOWN", &format!("manifest content unavailable: {}", digest))),
+    }
+}
+
+/// Delete repository - DELETE /v2/<name>
+/// Removes the repository with all its tags and manifests, and queues the
+/// blobs they referenced for cleanup. Requires organization owner or admin.
+#[utoipa::path(
+    delete,
+    path = "/v2/{name}",
+    tag = "docker-registry-v2",
+    params(
+        ("name" = String, Path, description = "Repository name"),
+    ),
+    responses(
+        (status = 200, description = "Repository deleted", body = RepositoryDeleteResponse),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only organization owners and admins can delete repositories"),
+        (status = 404, description = "Repository not found"),
+    )
+)]
+pub async fn delete_repository(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> impl IntoResponse {
+    delete_repository_impl(&state, &user_id, &name).await
+}
+
+pub async fn delete_repository_namespaced(
+    State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
+    axum::extract::Path((org, name)): axum::extract::Path<(String, String)>,
+) -> impl IntoResponse {
+    let full_name = format!("{}/{}", org, name);
+    delete_repository_impl(&state, &user_id, &full_name).await
+}
+
+/// OCI error response with a single error
+fn registry_error(status: StatusCode, code: &str, message: &str) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "errors": [{
+                "code": code,
+                "message": message,
+                "detail": {}
+            }]
+        }))
+    ).into_response()
+}
+
+/// Validate a digest taken from the request, answering `DIGEST_INVALID` with
+/// the reason when it is malformed
+fn validated_digest(digest: &str) -> Result<Digest, Response> {
+    Digest::parse(digest).map_err(|e| registry_error(StatusCode::BAD_REQUEST, "DIGEST_INVALID", &e.to_string()))
+}
+
+/// Whether a manifest reference is a digest rather than a tag; tags cannot contain ':'
+fn is_digest_reference(reference: &str) -> bool {
+    reference.contains(':')
+}
+
+/// Validate a repository name taken from the path, answering `NAME_INVALID`
+/// with the reason when it is not one
+fn validated_repository_name(name: &str) -> Result<RepositoryName, Response> {
+    RepositoryName::parse(name).map_err(|e| registry_error(StatusCode::BAD_REQUEST, "NAME_INVALID", &e.to_string()))
+}
+
+/// Resolve a repository the user wants to delete from to its ID and full
+/// name. Deleting requires organization owner or admin.
+async fn repository_for_deletion(state: &AppState, user_id: &str, name: &str) -> Result<(i64, String), Response> {
+    let (namespace, repository) = match parse_repository_name(name, user_id, state).await {
+        Ok(parts) => parts,
+        Err(_) => return Err(registry_error(StatusCode::BAD_REQUEST, "NAME_INVALID", "Invalid repository name format")),
+    };
+
+    let user_id_int: i64 = match user_id.parse() {
+        Ok(id) => id,
+        Err(_) => return Err(registry_error(StatusCode::FORBIDDEN, "DENIED", "Only organization owners and admins can delete")),
+    };
+
+    let row = sqlx::query_as::<_, (i64, Option<String>)>(
+        "SELECT r.id, om.role
+         FROM repositories r
+         JOIN organizations o ON r.organization_id = o.id
+         LEFT JOIN organization_members om ON om.organization_id = o.id AND om.user_id = $3
+              AND (om.expires_at IS NULL OR om.expires_at > NOW())
+         WHERE o.name = $1 AND r.name = $2"
+    )
+    .bind(&namespace)
+    .bind(&repository)
+    .bind(user_id_int)
+    .fetch_optional(&state.db_pool)
+    .timed("repository_for_deletion")
+    .await;
+
+    let (repository_id, role) = match row {
+        Ok(Some(row)) => row,
+        Ok(None) => return Err(registry_error(StatusCode::NOT_FOUND, "NAME_UNKNOWN", "repository name not known to registry")),
+        Err(e) => {
+            println!("❌ Database error looking up {}/{}: {}", namespace, repository, e);
+            return Err(registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error"));
+        }
+    };
+
+    let can_delete = role
+        .and_then(|r| r.parse::<crate::models::organizations::OrganizationRole>().ok())
+        .map(|r| r.allows(crate::models::organizations::OrganizationAction::DeleteManifests))
+        .unwrap_or(false);
+    if !can_delete {
+        println!("❌ User {} denied deletion in {}/{}", user_id, namespace, repository);
+        return Err(registry_error(StatusCode::FORBIDDEN, "DENIED", "Only organization owners and admins can delete"));
+    }
+
+    Ok((repository_id, format!("{}/{}", namespace, repository)))
+}
+
+async fn delete_repository_impl(state: &AppState, user_id: &str, name: &str) -> Response {
+    let (repository_id, full_name) = match repository_for_deletion(state, user_id, name).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    match delete_repository_cascade(state, repository_id, &full_name).await {
+        Ok(summary) => {
+            println!(
+                "🗑️ Deleted repository {}: {} tag(s), {} manifest(s), {} blob(s) queued for cleanup",
+                full_name, summary.tags_deleted, summary.manifests_deleted, summary.blobs_queued_for_cleanup
+            );
+            (StatusCode::OK, Json(summary)).into_response()
+        }
+        Err(e) => {
+            println!("❌ Failed to delete repository {}: {}", full_name, e);
+            registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error")
+        }
+    }
+}
+
+/// Delete a repository's metadata in one transaction, then queue its blobs for
+/// cleanup. The repository row is locked first: a concurrent push inserting a
+/// manifest, tag or upload session needs a key-share lock on it for the
+/// foreign key, so it waits for the deletion and then fails cleanly instead of
+/// leaving rows behind.
+async fn delete_repository_cascade(
+    state: &AppState,
+    repository_id: i64,
+    full_name: &str,
+) -> Result<RepositoryDeleteResponse, sqlx::Error> {
+    let mut tx = state.db_pool.begin().await?;
+
+    sqlx::query("SELECT id FROM repositories WHERE id = $1 FOR UPDATE")
+        .bind(repository_id)
+        .execute(&mut *tx)
+        .timed("delete_repository_cascade: SELECT repositories")
+        .await?;
+
+    let tag_names: Vec<String> = sqlx::query_scalar("DELETE FROM tags WHERE repository_id = $1 RETURNING name")
+        .bind(repository_id)
+        .fetch_all(&mut *tx)
+        .timed("delete_repository_cascade: DELETE tags")
+        .await?;
+
+    let manifests = sqlx::query_as::<_, (String, Option<String>)>(
+        "DELETE FROM manifests WHERE repository_id = $1 RETURNING digest, content"
+    )
+    .bind(repository_id)
+    .fetch_all(&mut *tx)
+    .timed("delete_repository_cascade: DELETE manifests")
+    .await?;
+
+    sqlx::query("DELETE FROM repositories WHERE id = $1")
+        .bind(repository_id)
+        .execute(&mut *tx)
+        .timed("delete_repository_cascade: DELETE repositories")
+        .await?;
+
+    tx.commit().await?;
+    invalidate_catalog(state).await;
+
+    {
+        let mut manifest_cache = state.manifest_cache.write().await;
+        for (digest, _) in &manifests {
+            manifest_cache.remove(digest);
+        }
+    }
+
+    if let Some(cache) = &state.cache {
+        let references = tag_names.iter().chain(manifests.iter().map(|(digest, _)| digest));
+        for reference in references {
+            let manifest_cache_key = crate::cache::manifest_cache_key(full_name, reference);
+            if let Err(e) = cache.invalidate_manifest(&manifest_cache_key).await {
+                println!("⚠️ Failed to invalidate manifest cache: {}", e);
+            }
+        }
+        if let Err(e) = cache.invalidate_tags(full_name).await {
+            println!("⚠️ Failed to invalidate tags cache: {}", e);
+        }
+    }
+
+    Ok(RepositoryDeleteResponse {
+        name: full_name.to_string(),
+        tags_deleted: tag_names.len() as u64,
+        manifests_deleted: manifests.len() as u64,
+        blobs_queued_for_cleanup: queue_blob_cleanup(state, &manifests),
+    })
+}
+
+/// Queue the removal of what the deleted `manifests` (digest and content)
+/// may have left unreferenced in storage: the blobs they reference and their
+/// own stored copies. Returns how many referenced blobs were queued.
+pub(crate) fn queue_blob_cleanup(state: &AppState, manifests: &[(String, Option<String>)]) -> u64 {
+    // Only well-formed digests can name a stored blob
+    let mut blob_digests: Vec<Digest> = manifests
+        .iter()
+        .filter_map(|(_, content)| content.as_deref())
+        .flat_map(manifest_blob_digests)
+        .filter_map(|digest| Digest::parse(&digest).ok())
+        .collect();
+    blob_digests.sort();
+    blob_digests.dedup();
+    let queued = blob_digests.len() as u64;
+
+    // Manifests are also stored as blobs under their own digest
+    blob_digests.extend(manifests.iter().filter_map(|(digest, _)| Digest::parse(digest).ok()));
+    tokio::spawn(cleanup_unreferenced_blobs(state.clone(), blob_digests, uuid::Uuid::new_v4().to_string()));
+    queued
+}
+
+/// Remove blobs from storage that no remaining manifest references. Blobs
+/// are content addressed and shared between repositories and tenants, so
+/// each digest is re-checked against what is left in every tenant after the
+/// deletion. `deletion_id` ties the log lines to the delete that queued the
+/// cleanup.
+async fn cleanup_unreferenced_blobs(state: AppState, digests: Vec<Digest>, deletion_id: String) {
+    let grace = std::time::Duration::from_secs(state.config.registry.gc_blob_grace_seconds);
+    let mode = state.config.database.tenancy_mode;
+    let pool = &state.db_pool;
+    let mut removed = 0;
+
+    // Until every manifest's references are indexed, an unindexed one may
+    // still use any of these blobs
+    let unindexed = crate::tenant::across_tenants(pool, mode, || async move {
+        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM manifests WHERE NOT blob_references_indexed)")
+            .fetch_one(pool)
+            .timed("cleanup_unreferenced_blobs: SELECT manifests")
+            .await
+            .map(|unindexed| vec![unindexed])
+    })
+    .await;
+    match unindexed.map(|tenants| tenants.contains(&true)) {
+        Ok(false) => {}
+        Ok(true) => {
+            tracing::warn!(%deletion_id, "Blob cleanup skipped: manifest references are still being indexed");
+            return;
+        }
+        Err(e) => {
+            tracing::warn!(%deletion_id, "Blob cleanup skipped: {}", e);
+            return;
+        }
+    }
+
+    for digest in digests {
+        let digest_ref = &digest;
+        let referenced = crate::tenant::across_tenants(pool, mode, || async move {
+            sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM manifests WHERE digest = $1)
+                     OR EXISTS(SELECT 1 FROM manifest_blob_references WHERE digest = $1)"
+            )
+            .bind(digest_ref)
+            .fetch_one(pool)
+            .timed("cleanup_unreferenced_blobs: SELECT manifests")
+            .await
+            .map(|referenced| vec![referenced])
+        })
+        .await
+        .map(|tenants| tenants.contains(&true));
+
+        match referenced {
+            Ok(false) => match collect_orphan_blob(state.storage.as_ref(), &digest, grace, chrono::Utc::now()).await {
+                Ok(true) => removed += 1,
+                Ok(false) => {}
+                Err(e) => println!("⚠️ Failed to delete blob {}: {}", digest, e),
+            },
+            Ok(true) => {}
+            Err(e) => println!("⚠️ Failed to check references of blob {}: {}", digest, e),
+        }
+    }
+
+    println!("🧹 Blob cleanup for deletion {} removed {} unreferenced blob(s)", deletion_id, removed);
+}
+
+/// Whether a blob stored at `created_at` is old enough to be collected
+pub fn blob_gc_eligible(
+    created_at: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+    grace: std::time::Duration,
+) -> bool {
+    now.signed_duration_since(created_at)
+        .to_std()
+        .map_or(false, |age| age >= grace)
+}
+
+/// Delete a blob no manifest references, unless it was stored less than
+/// `grace` (`GC_BLOB_GRACE_SECONDS`) ago: a layer uploaded for a push whose
+/// manifest has not arrived yet is unreferenced too. Returns whether the
+/// blob was deleted.
+pub async fn collect_orphan_blob(
+    storage: &dyn crate::storage::Storage,
+    digest: &Digest,
+    grace: std::time::Duration,
+    now: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<bool> {
+    let key = digest.blob_key();
+    let metadata = match storage.get_blob_metadata(&key).await? {
+        Some(metadata) => metadata,
+        None => return Ok(false),
+    };
+
+    if !blob_gc_eligible(metadata.created_at, now, grace) {
+        println!("⏳ Keeping unreferenced blob {} stored at {}, within the GC grace period", digest, metadata.created_at);
+        return Ok(false);
+    }
+    storage.delete_blob(&key).await
+}
+
+/// Get blob - GET /v2/<name>/blobs/<digest>
+/// Downloads a blob (layer) by digest
+#[utoipa::path(
+    get,
+    path = "/v2/{name}/blobs/{digest}",
+    tag = "docker-registry-v2",
+    params(
+        ("name" = String, Path, description = "Repository name"),
+        ("digest" = String, Path, description = "Blob digest"),
+    ),
+    responses(
+        (status = 200, description = "Blob content"),
+        (status = 404, description = "Blob not found"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions"),
+    )
+)]
+pub async fn get_blob(
+    State(state): State<AppState>,
+    user: MaybeAuthUser,
+    axum::extract::Path((name, digest)): axum::extract::Path<(String, String)>,
+) -> impl IntoResponse {
+    if let Err(response) = authorize_blob_pull(&state.db_pool, &name, user.0.as_deref()).await {
+        return response;
+    }
+
+    get_blob_impl(&state, &name, &digest).await
+}
+
+/// Check if blob exists - HEAD /v2/<name>/blobs/<digest>
+#[utoipa::path(
+    head,
+    path = "/v2/{name}/blobs/{digest}",
+    tag = "docker-registry-v2",
+    params(
+        ("name" = String, Path, description = "Repository name"),
+        ("digest" = String, Path, description = "Blob digest"),
+    ),
+    responses(
+        (status = 200, description = "Blob exists"),
+        (status = 404, description = "Blob not found"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions"),
+    )
+)]
+pub async fn head_blob(
+    State(state): State<AppState>,
+    user: MaybeAuthUser,
+    axum::extract::Path((name, digest)): axum::extract::Path<(String, String)>,
+) -> impl IntoResponse {
+    if let Err(response) = authorize_blob_pull(&state.db_pool, &name, user.0.as_deref()).await {
+        return response;
+    }
+
+    head_blob_impl(&state, &name, &digest).await
+}
+
+/// Start blob upload - POST /v2/<name>/blobs/uploads/
+/// Initiates a resumable blob upload
+/// Requires authentication and push permission
+#[utoipa::path(
+    post,
+    path = "/v2/{name}/blobs/uploads/",
+    tag = "docker-registry-v2", 
+    params(
+        ("name" = String, Path, description = "Repository name"),
+    ),
+    responses(
+        (status = 202, description = "Upload initiated", body = BlobUploadResponse),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Insufficient permissions"),
+    )
+)]
+pub async fn start_blob_upload(
     State(state): State<AppState>,
     Path(name): Path<String>,
     headers: HeaderMap,
@@ -700,16 +2214,16 @@ pub async fn start_blob_upload(
                 let token = &auth_str[7..]; // Remove "Bearer " prefix
                 
                 // Verify JWT token and extract user_id
-                match verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
+                match verify_token_with_settings(token, &state.config.auth) {
                     Ok(claims) => {
-                        match claims.sub.parse::<i64>() {
+                        match crate::auth::claims_user_id(&claims, &state.db_pool).await {
                             Ok(uid) => Some(uid.to_string()),
-                            Err(_) => {
-                                println!("❌ Invalid user ID in JWT token");
+                            Err(status) => {
+                                println!("❌ Invalid user ID or revoked session in JWT token");
                                 return (
-                                    StatusCode::UNAUTHORIZED,
+                                    status,
                                     Json(serde_json::json!({
-                                        "error": "Invalid user ID in token"
+                                        "error": "Invalid user ID in token or session revoked"
                                     }))
                                 ).into_response();
                             }
@@ -781,6 +2295,15 @@ pub async fn start_blob_upload(
     } else {
         println!("✅ Blob upload saved to database successfully");
     }
+    if let Err(e) = crate::database::queries::save_upload_session(&state.db_pool, &UploadSession::new(&upload_uuid, &name)).await {
+        eprintln!("❌ Failed to save upload session: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": "Failed to create blob upload record"
+            }))
+        ).into_response();
+    }
     
     let mut response_headers = HeaderMap::new();
     response_headers.insert("Location", HeaderValue::from_str(&location).unwrap());
@@ -816,7 +2339,7 @@ pub async fn start_blob_upload_by_id(
     headers: HeaderMap,
 ) -> impl IntoResponse {
     use axum::http::header::AUTHORIZATION;
-    use crate::auth::verify_token;
+    use crate::auth::verify_token_with_settings;
     
     println!("Starting blob upload for repository ID: {}", repository_id);
     
@@ -851,16 +2374,16 @@ pub async fn start_blob_upload_by_id(
                 let token = &auth_str[7..]; // Remove "Bearer " prefix
                 
                 // Verify JWT token and extract user_id
-                match verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
+                match verify_token_with_settings(token, &state.config.auth) {
                     Ok(claims) => {
-                        match claims.sub.parse::<i64>() {
+                        match crate::auth::claims_user_id(&claims, &state.db_pool).await {
                             Ok(uid) => Some(uid.to_string()),
-                            Err(_) => {
-                                println!("❌ Invalid user ID in JWT token");
+                            Err(status) => {
+                                println!("❌ Invalid user ID or revoked session in JWT token");
                                 return (
-                                    StatusCode::UNAUTHORIZED,
+                                    status,
                                     Json(serde_json::json!({
-                                        "error": "Invalid user ID in token"
+                                        "error": "Invalid user ID in token or session revoked"
                                     }))
                                 ).into_response();
                             }
@@ -932,6 +2455,15 @@ pub async fn start_blob_upload_by_id(
     } else {
         println!("✅ Blob upload saved to database successfully");
     }
+    if let Err(e) = crate::database::queries::save_upload_session(&state.db_pool, &UploadSession::new(&upload_uuid, &repository_id.to_string())).await {
+        eprintln!("❌ Failed to save upload session: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": "Failed to create blob upload record"
+            }))
+        ).into_response();
+    }
     
     let mut response_headers = HeaderMap::new();
     response_headers.insert("Location", HeaderValue::from_str(&location).unwrap());
@@ -964,6 +2496,8 @@ pub async fn start_blob_upload_by_id(
         (status = 202, description = "Chunk uploaded"),
         (status = 400, description = "Invalid range"),
         (status = 404, description = "Upload not found"),
+        (status = 413, description = "Chunk larger than MAX_UPLOAD_CHUNK_BYTES"),
+        (status = 416, description = "Chunk out of order or smaller than MIN_UPLOAD_CHUNK_BYTES"),
         (status = 401, description = "Authentication required"),
     )
 )]
@@ -1007,7 +2541,7 @@ pub async fn complete_blob_upload(
     let user_info = extract_user_info_from_headers(&headers);
     println!("Blob upload completion by user: {:?} for {}/{}", user_info, name, uuid);
     
-    complete_blob_upload_impl(&state, &name, &uuid, params, body).await
+    complete_blob_upload_impl(&state, &name, &uuid, params, headers, body).await
 }
 
 /// Get upload status - GET /v2/<name>/blobs/uploads/<uuid>
@@ -1068,7 +2602,7 @@ pub async fn cancel_blob_upload(
         ("last" = Option<String>, Query, description = "Last tag for pagination"),
     ),
     responses(
-        (status = 200, description = "Tag list", body = TagListResponse),
+        (status = 200, description = "Tag list; with `Accept: application/x-ndjson`, one `{\"tag\": ...}` object per line, ordered by name", body = TagListResponse),
         (status = 404, description = "Repository not found"),
         (status = 401, description = "Authentication required"),
     )
@@ -1076,8 +2610,101 @@ pub async fn cancel_blob_upload(
 pub async fn list_tags(
     State(state): State<AppState>,
     axum::extract::Path(name): axum::extract::Path<String>,
-    Query(_params): Query<TagsQuery>,
+    Query(page_query): Query<PageQuery>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
+    if accepts_ndjson(&headers) {
+        return ndjson_tag_list(&state, &name, &page_query).await;
+    }
+    let (status, Json(response)) = tag_list(&state, name).await;
+    paginated_tag_list(status, response, &page_query)
+}
+
+/// Media type of the streamed tag list
+pub const NDJSON_MEDIA_TYPE: &str = "application/x-ndjson";
+
+fn accepts_ndjson(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(axum::http::header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_type| media_type.split(';').next().unwrap_or("").trim().eq_ignore_ascii_case(NDJSON_MEDIA_TYPE))
+}
+
+/// Stream a repository's tags from the database as NDJSON, so large
+/// repositories are never buffered whole. `n` and `last` page by name.
+async fn ndjson_tag_list(state: &AppState, name: &str, page_query: &PageQuery) -> Response {
+    let repository_id = match crate::database::queries::get_repository_id_by_name(&state.db_pool, name).await {
+        Ok(Some(id)) => id,
+        Ok(None) => return registry_error(StatusCode::NOT_FOUND, "NAME_UNKNOWN", "repository name not known to registry"),
+        Err(e) => {
+            println!("❌ Database error: {}", e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+        }
+    };
+
+    let pool = state.db_pool.clone();
+    let last = page_query.last.clone();
+    let limit = page_query.n.map(i64::from);
+    let (sender, receiver) = tokio::sync::mpsc::channel(64);
+    crate::tenant::spawn(async move {
+        let mut tags = sqlx::query_scalar::<_, String>(
+            "SELECT name FROM tags
+             WHERE repository_id = $1 AND ($2::TEXT IS NULL OR name > $2)
+             ORDER BY name
+             LIMIT $3",
+        )
+        .bind(repository_id)
+        .bind(last)
+        .bind(limit)
+        .fetch(&pool);
+        while let Some(tag) = futures::StreamExt::next(&mut tags).await {
+            let failed = tag.is_err();
+            // Stop when the client has gone away or the query failed
+            if sender.send(tag).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    ndjson_tag_response(tokio_stream::wrappers::ReceiverStream::new(receiver))
+}
+
+/// Response writing each tag as it arrives as a `{"tag": ...}` line. An
+/// error ends the body early, so clients see a truncated stream rather than
+/// a partial list that looks complete.
+pub fn ndjson_tag_response<S, E>(tags: S) -> Response
+where
+    S: futures::Stream<Item = Result<String, E>> + Send + 'static,
+    E: Into<axum::BoxError> + 'static,
+{
+    let lines = futures::StreamExt::map(tags, |tag| {
+        tag.map(|tag| {
+            let mut line = serde_json::to_vec(&json!({ "tag": tag })).unwrap_or_default();
+            line.push(b'\n');
+            Bytes::from(line)
+        })
+    });
+    (
+        StatusCode::OK,
+        [(axum::http::header::CONTENT_TYPE, NDJSON_MEDIA_TYPE)],
+        axum::body::Body::from_stream(lines),
+    )
+        .into_response()
+}
+
+/// Answer with the page of `response.tags` the client asked for, linking the next one
+fn paginated_tag_list(status: StatusCode, response: TagListResponse, page_query: &PageQuery) -> Response {
+    let page = paginate(response.tags, |tag| tag.clone(), page_query);
+    let mut headers = HeaderMap::new();
+    page.add_link(&format!("/v2/{}/tags/list", response.name), &mut headers);
+
+    let response = TagListResponse { name: response.name, tags: page.items };
+    (status, headers, Json(response)).into_response()
+}
+
+async fn tag_list(state: &AppState, name: String) -> (StatusCode, Json<TagListResponse>) {
     println!("🏷️  Listing tags for: {}", name);
     
     // Check cache first
@@ -1113,6 +2740,7 @@ pub async fn list_tags(
             org, repo_name
         )
         .fetch_optional(&state.db_pool)
+        .timed("tag_list: SELECT repositories")
         .await
         {
             Ok(Some(row)) => row.id,
@@ -1150,6 +2778,7 @@ pub async fn list_tags(
             repo_name
         )
         .fetch_optional(&state.db_pool)
+        .timed("tag_list: SELECT repositories")
         .await
         {
             Ok(Some(row)) => row.id,
@@ -1186,6 +2815,7 @@ pub async fn list_tags(
         repository_id
     )
     .fetch_all(&state.db_pool)
+    .timed("tag_list: SELECT tags")
     .await;
     
     match tags_result {
@@ -1263,111 +2893,134 @@ pub async fn list_tags(
 pub async fn list_tags_namespaced(
     State(state): State<AppState>,
     axum::extract::Path((org, name)): axum::extract::Path<(String, String)>,
-    query: Query<TagsQuery>,
+    Query(page_query): Query<PageQuery>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
     println!("Listing tags for namespaced repo: {}", full_name);
+    if accepts_ndjson(&headers) {
+        return ndjson_tag_list(&state, &full_name, &page_query).await;
+    }
     
     // Reuse the main implementation with combined name
-    let response = TagListResponse {
-        name: full_name,
-        tags: vec![
-            "latest".to_string(),
-            "v1.0.0".to_string(),
-            "v1.1.0".to_string(),
-        ],
-    };
-    
-    (StatusCode::OK, Json(response))
+    let (status, Json(response)) = tag_list(&state, full_name).await;
+    paginated_tag_list(status, response, &page_query)
 }
 
 // Namespaced manifest handlers
 pub async fn get_manifest_namespaced(
     State(state): State<AppState>,
     axum::extract::Path((org, name, reference)): axum::extract::Path<(String, String, String)>,
+    user: MaybeAuthUser,
     headers: HeaderMap,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
     println!("🔍 GET Manifest (namespaced) for: {}/{}/{}", org, name, reference);
-    
-    // Docker operations require authentication
-    let user_id_opt = match extract_user_from_auth(&headers, &state, false).await {
-        Ok(user_opt) => user_opt,
-        Err(response) => return response,
+
+    if let Err(response) = authorize_pull(&state.db_pool, Some(&org), &name, user.0.as_deref()).await {
+        return response;
+    }
+
+    let response = negotiate_manifest_type(&headers, get_manifest_impl(&state, &full_name, &reference, ManifestRead::Pull(user.0.as_deref())).await);
+    apply_manifest_preconditions(&headers, response)
+}
+
+/// Media type of a manifest body: its `mediaType` field, or for manifests
+/// without one, an OCI index if it lists manifests and a Docker image
+/// manifest otherwise
+pub fn manifest_media_type(manifest: &serde_json::Value) -> MediaType {
+    let declared = manifest
+        .get("mediaType")
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse().ok());
+    match declared {
+        Some(media_type) => media_type,
+        None if manifest.get("manifests").is_some() => MediaType::OciIndex,
+        None => MediaType::DockerManifest,
+    }
+}
+
+/// Media type to store a pushed manifest under. The `Content-Type` header
+/// and the manifest's own `mediaType` must agree when both are given, and
+/// neither may name a config or layer type.
+pub fn manifest_push_media_type(content_type: Option<&str>, body: &str) -> Result<MediaType, String> {
+    let parse = |value: &str| value.parse::<MediaType>().map_err(|e| e.to_string());
+    let header = content_type.map(parse).transpose()?;
+    let declared = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|manifest| manifest.get("mediaType").and_then(|v| v.as_str()).map(str::to_string))
+        .map(|declared| parse(&declared))
+        .transpose()?;
+
+    let media_type = match (header, declared) {
+        (Some(header), Some(declared)) if header != declared => {
+            return Err(format!("Content-Type {} does not match the manifest's mediaType {}", header, declared));
+        }
+        (Some(media_type), _) | (None, Some(media_type)) => media_type,
+        (None, None) => MediaType::DockerManifest,
     };
+    if media_type.is_blob() {
+        return Err(format!("{} is not a manifest media type", media_type));
+    }
+    Ok(media_type)
+}
 
-    if user_id_opt.is_none() {
-        println!("❌ No authentication provided for manifest {}/{}:{} - Docker login required", org, name, reference);
-        return (
-            StatusCode::UNAUTHORIZED,
-            [("WWW-Authenticate", "Basic")],
-            Json(serde_json::json!({
-                "errors": [{
-                    "code": "UNAUTHORIZED",
-                    "message": "Authentication required - please run 'docker login'",
-                    "detail": {}
-                }]
-            }))
-        ).into_response();
+/// Answer an index with `MANIFEST_UNKNOWN` when the request's `Accept`
+/// header rules indexes out, as clients that predate them would fail to
+/// parse one. Image manifests are served whatever the client accepts.
+fn negotiate_manifest_type(request_headers: &HeaderMap, response: Response) -> Response {
+    let accept = match request_headers.get(axum::http::header::ACCEPT).and_then(|v| v.to_str().ok()) {
+        Some(accept) if response.status() == StatusCode::OK => accept,
+        _ => return response,
+    };
+    let media_type = response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<MediaType>().ok());
+
+    match media_type {
+        Some(media_type) if media_type.is_index() && !media_type.accepted_by(accept) => registry_error(
+            StatusCode::NOT_FOUND,
+            "MANIFEST_UNKNOWN",
+            &format!("manifest is an index ({}), which the request does not accept", media_type),
+        ),
+        _ => response,
     }
+}
 
-    let user_id = user_id_opt.unwrap();
-    println!("🔐 Authenticated request for manifest {}/{}:{} by user {}", org, name, reference, user_id);
+/// Tag a successful manifest response with its digest as ETag and answer
+/// `If-None-Match` revalidation with 304, so clients can cheaply re-check tags.
+fn apply_manifest_preconditions(request_headers: &HeaderMap, mut response: Response) -> Response {
+    if response.status() != StatusCode::OK {
+        return response;
+    }
 
-    // Check repository permissions
-    let repo_query = "SELECT r.is_public, r.created_by FROM repositories r JOIN organizations o ON r.organization_id = o.id WHERE o.name = $1 AND r.name = $2";
-    match sqlx::query_as::<_, (bool, i64)>(repo_query)
-        .bind(&org)
-        .bind(&name)
-        .fetch_optional(&state.db_pool)
-        .await 
+    let etag = match response
+        .headers()
+        .get("Docker-Content-Digest")
+        .and_then(|v| v.to_str().ok())
+        .map(conditional::strong_etag)
     {
-        Ok(Some((is_public, owner_id))) => {
-            if is_public {
-                // Private repository - only owner can access
-                if user_id.parse::<i64>().unwrap_or(0) == owner_id {
-                    println!("✅ Repository {}/{} is private (is_public=true) - owner access granted", org, name);
-                } else {
-                    println!("❌ Repository {}/{} is private (is_public=true) - access denied for non-owner", org, name);
-                    return (
-                        StatusCode::FORBIDDEN,
-                        Json(serde_json::json!({
-                            "errors": [{
-                                "code": "DENIED",
-                                "message": "Access denied - private repository",
-                                "detail": {}
-                            }]
-                        }))
-                    ).into_response();
-                }
-            } else {
-                // Public repository - any authenticated user can access
-                println!("✅ Repository {}/{} is public (is_public=false) - authenticated access granted", org, name);
+        Some(etag) => etag,
+        None => return response,
+    };
+
+    let resource = conditional::ResourceState { etag: Some(&etag), last_modified: None };
+    if let Some(mut not_modified) = conditional::check(request_headers, &axum::http::Method::GET, &resource) {
+        // Keep the caching headers on 304 so clients refresh their freshness
+        for name in ["Cache-Control", "Docker-Content-Digest"] {
+            if let Some(value) = response.headers().get(name).cloned() {
+                not_modified.headers_mut().insert(name, value);
             }
-        },
-        Ok(None) => {
-            println!("❌ Repository {}/{} not found", org, name);
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "errors": [{
-                    "code": "NAME_UNKNOWN",
-                    "message": "repository name not known to registry",
-                    "detail": {"name": format!("{}/{}", org, name)}
-                }]
-            }))).into_response();
-        },
-        Err(e) => {
-            println!("❌ Database error checking repository {}/{}: {}", org, name, e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "errors": [{
-                    "code": "UNKNOWN",
-                    "message": "database error", 
-                    "detail": {}
-                }]
-            }))).into_response();
         }
+        return not_modified;
     }
 
-    get_manifest_impl(&state, &full_name, &reference).await
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(axum::http::header::ETAG, value);
+    }
+    response
 }
 
 pub async fn head_manifest_namespaced(
@@ -1386,39 +3039,160 @@ pub async fn put_manifest_namespaced(
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
     // Extract user_id from headers if available 
-    let user_id = if let Ok(Some(uid)) = extract_user_from_auth(&headers, &state, false).await {
-        uid.parse().unwrap_or(0)
-    } else {
-        0
+    let user_id = match extract_user_from_auth(&headers, &state, false).await {
+        Ok(Some(uid)) => uid.parse().ok(),
+        _ => None,
     };
-    put_manifest_impl(&state, &full_name, &reference, headers, body, Some(user_id)).await
+    put_manifest_impl(&state, &full_name, &reference, headers, body, user_id).await
 }
 
 pub async fn delete_manifest_namespaced(
     State(state): State<AppState>,
+    AuthUser(user_id): AuthUser,
     axum::extract::Path((org, name, reference)): axum::extract::Path<(String, String, String)>,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
-    delete_manifest_impl(&state, &full_name, &reference).await
+    delete_manifest_impl(&state, &user_id, &full_name, &reference).await
 }
 
 // Namespaced blob handlers
 pub async fn get_blob_namespaced(
     State(state): State<AppState>,
     axum::extract::Path((org, name, digest)): axum::extract::Path<(String, String, String)>,
+    user: MaybeAuthUser,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
+
+    if let Err(response) = authorize_pull(&state.db_pool, Some(&org), &name, user.0.as_deref()).await {
+        return response;
+    }
+
     get_blob_impl(&state, &full_name, &digest).await
 }
 
+/// Decide whether the caller may pull from `org/name`, or from `name` in the
+/// default organization when `org` is `None`. Public repositories are
+/// readable by anyone, including anonymous callers; private repositories only
+/// by their owner. Anonymous callers are challenged rather than denied, so
+/// clients retry with credentials.
+pub async fn authorize_pull(
+    pool: &sqlx::PgPool,
+    org: Option<&str>,
+    name: &str,
+    user: Option<&str>,
+) -> Result<(), Response> {
+    let full_name = match org {
+        Some(org) => format!("{}/{}", org, name),
+        None => name.to_string(),
+    };
+    let repo_query = "SELECT r.is_public, r.created_by FROM repositories r JOIN organizations o ON r.organization_id = o.id
+                      WHERE (o.name = $1 OR ($1 IS NULL AND o.id = 1)) AND r.name = $2";
+    let repo = sqlx::query_as::<_, (bool, Option<i64>)>(repo_query)
+        .bind(org)
+        .bind(name)
+        .fetch_optional(pool)
+        .timed("authorize_pull")
+        .await
+        .map_err(|e| {
+            println!("❌ Database error checking repository {}: {}", full_name, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "errors": [{
+                    "code": "UNKNOWN",
+                    "message": "database error",
+                    "detail": {}
+                }]
+            }))).into_response()
+        })?;
+
+    match (repo, user) {
+        (Some((true, _)), _) => {
+            println!("✅ Repository {} is public - pull granted", full_name);
+            Ok(())
+        }
+        (Some((false, owner_id)), Some(user_id)) => {
+            if owner_id.is_some() && user_id.parse::<i64>().ok() == owner_id {
+                println!("✅ Repository {} is private - owner access granted", full_name);
+                Ok(())
+            } else {
+                println!("❌ Repository {} is private - access denied for user {}", full_name, user_id);
+                Err((
+                    StatusCode::FORBIDDEN,
+                    Json(serde_json::json!({
+                        "errors": [{
+                            "code": "DENIED",
+                            "message": "Access denied - private repository",
+                            "detail": {}
+                        }]
+                    }))
+                ).into_response())
+            }
+        }
+        (None, Some(_)) => {
+            println!("❌ Repository {} not found", full_name);
+            Err((StatusCode::NOT_FOUND, Json(serde_json::json!({
+                "errors": [{
+                    "code": "NAME_UNKNOWN",
+                    "message": "repository name not known to registry",
+                    "detail": {"name": full_name}
+                }]
+            }))).into_response())
+        }
+        // Anonymous callers can't tell private repositories from missing ones
+        (_, None) => {
+            println!("❌ Anonymous pull of {} requires authentication", full_name);
+            Err(authentication_required())
+        }
+    }
+}
+
 pub async fn head_blob_namespaced(
     State(state): State<AppState>,
     axum::extract::Path((org, name, digest)): axum::extract::Path<(String, String, String)>,
+    user: MaybeAuthUser,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
+
+    if let Err(response) = authorize_pull(&state.db_pool, Some(&org), &name, user.0.as_deref()).await {
+        return response;
+    }
+
     head_blob_impl(&state, &full_name, &digest).await
 }
 
+/// Decide whether the caller may pull blobs through `/v2/<name>/blobs`.
+/// Unqualified names resolve the way `get_manifest` resolves them: against
+/// the caller's namespace when authenticated, the default organization
+/// otherwise. Blobs are stored by digest alone, so this check is what keeps
+/// the layers of private repositories private.
+pub async fn authorize_blob_pull(
+    pool: &sqlx::PgPool,
+    name: &str,
+    user: Option<&str>,
+) -> Result<(), Response> {
+    let (org, repository) = match (name.split_once('/'), user) {
+        (Some((org, repository)), _) => (Some(org.to_string()), repository),
+        (None, None) => (None, name),
+        (None, Some(user_id)) => {
+            let username = match user_id.parse::<i64>() {
+                Ok(id) => crate::database::queries::get_user_by_id(pool, id)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|user| user.username),
+                Err(_) => None,
+            };
+            match username {
+                Some(username) => (Some(username), name),
+                None => {
+                    return Err(registry_error(StatusCode::BAD_REQUEST, "NAME_INVALID", "Invalid repository name format"));
+                }
+            }
+        }
+    };
+
+    authorize_pull(pool, org.as_deref(), repository, user).await
+}
+
 // Namespaced blob upload handlers
 pub async fn start_blob_upload_namespaced(
     State(state): State<AppState>,
@@ -1453,10 +3227,11 @@ pub async fn complete_blob_upload_namespaced(
     State(state): State<AppState>,
     axum::extract::Path((org, name, uuid)): axum::extract::Path<(String, String, String)>,
     Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
     body: axum::body::Bytes,
 ) -> impl IntoResponse {
     let full_name = format!("{}/{}", org, name);
-    complete_blob_upload_impl(&state, &full_name, &uuid, params, body).await
+    complete_blob_upload_impl(&state, &full_name, &uuid, params, headers, body).await
 }
 
 pub async fn cancel_blob_upload_namespaced(
@@ -1468,15 +3243,219 @@ pub async fn cancel_blob_upload_namespaced(
 }
 
 // Implementation functions that do the actual work
+/// Whether a pull of a manifest is refused by the vulnerability policy. The
+/// organization's setting, when it has one, takes precedence over
+/// `BLOCK_VULNERABLE_PULLS`.
+pub fn vulnerable_pull_blocked(critical_vulnerabilities: bool, block_by_default: bool, org_setting: Option<bool>) -> bool {
+    critical_vulnerabilities && org_setting.unwrap_or(block_by_default)
+}
+
+/// How long an instance relies on what it read about organizations blocking
+/// vulnerable pulls; a change made through another instance applies here
+/// within this long
+pub const BLOCKING_ORGANIZATIONS_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Per tenant schema, whether some organization blocks vulnerable pulls, and
+/// when that was read. Changes to an organization's setting on this instance
+/// invalidate it; changes elsewhere show up within
+/// `BLOCKING_ORGANIZATIONS_TTL`.
+#[derive(Default)]
+pub struct BlockingOrganizationsCache {
+    known: std::sync::Mutex<Vec<(Option<String>, bool, std::time::Instant)>>,
+}
+
+impl BlockingOrganizationsCache {
+    /// Whether any organization of the current tenant overrides
+    /// `BLOCK_VULNERABLE_PULLS` to block, read at most once per
+    /// `BLOCKING_ORGANIZATIONS_TTL`; `None` when it cannot be read
+    async fn any_blocks(&self, pool: &sqlx::PgPool) -> Option<bool> {
+        let schema = crate::tenant::current().and_then(|tenant| tenant.schema().map(str::to_string));
+        let now = std::time::Instant::now();
+        let cached = self
+            .known
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(cached, _, read_at)| *cached == schema && now.duration_since(*read_at) < BLOCKING_ORGANIZATIONS_TTL)
+            .map(|(_, blocks, _)| *blocks);
+        if cached.is_some() {
+            return cached;
+        }
+
+        let blocks = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM organizations WHERE block_vulnerable_pulls)")
+            .fetch_one(pool)
+            .timed("organizations_block_vulnerable_pulls")
+            .await
+            .ok()?;
+        let mut known = self.known.lock().unwrap();
+        known.retain(|(cached, _, _)| *cached != schema);
+        known.push((schema, blocks, now));
+        Some(blocks)
+    }
+
+    /// Forget what was read, after an organization changed its setting
+    pub fn invalidate(&self) {
+        self.known.lock().unwrap().clear();
+    }
+}
+
+/// `DENIED` response when the manifest `reference` names has been flagged by
+/// the scanner and the policy blocks it, `block_by_default` unless its
+/// organization says otherwise. Simple names resolve under the default
+/// organization, as in `get_manifest_impl`. When nothing can block, the
+/// manifest is not looked up. When the scan result cannot be read the pull
+/// is refused, so an outage cannot let a blocked image out.
+pub async fn vulnerable_pull_denial(
+    pool: &sqlx::PgPool,
+    blocking: &BlockingOrganizationsCache,
+    block_by_default: bool,
+    name: &str,
+    reference: &str,
+) -> Option<Response> {
+    // Without BLOCK_VULNERABLE_PULLS only an organization's override blocks,
+    // and usually none has one
+    if !block_by_default && blocking.any_blocks(pool).await == Some(false) {
+        return None;
+    }
+
+    let (org, repo) = match name.split_once('/') {
+        Some((org, repo)) => (Some(org), repo),
+        None => (None, name),
+    };
+
+    let row = sqlx::query_as::<_, (bool, Option<String>, Option<bool>)>(
+        "SELECT m.critical_vulnerabilities, m.vulnerability_reason, o.block_vulnerable_pulls
+         FROM manifests m
+         JOIN repositories r ON r.id = m.repository_id
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))
+           AND (m.digest = $3 OR m.id IN (SELECT manifest_id FROM tags WHERE repository_id = r.id AND name = $3))
+         LIMIT 1"
+    )
+    .bind(org)
+    .bind(repo)
+    .bind(reference)
+    .fetch_optional(pool)
+    .timed("vulnerable_pull_denial")
+    .await;
+
+    let (critical, reason, org_setting) = match row {
+        Ok(Some(row)) => row,
+        Ok(None) => return None,
+        Err(e) => {
+            println!("❌ Failed to check scan result for {}:{}: {}", name, reference, e);
+            return Some(registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error"));
+        }
+    };
+
+    if !vulnerable_pull_blocked(critical, block_by_default, org_setting) {
+        return None;
+    }
+
+    println!("🚫 Pull of {}:{} blocked: critical vulnerabilities", name, reference);
+    let message = match reason {
+        Some(reason) => format!("image has critical vulnerabilities: {}", reason),
+        None => "image has critical vulnerabilities".to_string(),
+    };
+    Some(registry_error(StatusCode::FORBIDDEN, "DENIED", &message))
+}
+
+/// Count a pull of `digest` from repository `name` in the background, for
+/// the startup cache warmup, `/v2/_popular` and organization analytics;
+/// cache hits are counted too, and nothing is in read-only mode. `puller`
+/// is the caller's user ID, if any.
+fn record_manifest_pull(state: &AppState, name: &str, digest: &Digest, puller: Option<&str>) {
+    if state.read_only.is_enabled() {
+        return;
+    }
+    let (organization, repository) = split_repository_name(name);
+    state.pulls.record(ManifestPull {
+        tenant: crate::tenant::current(),
+        organization,
+        repository,
+        digest: digest.clone(),
+        puller: puller.map(str::to_string),
+    });
+}
+
+/// Record a manifest push to repository `name` for organization analytics,
+/// in the background
+fn record_manifest_push(state: &AppState, name: &str, pusher: Option<i64>) {
+    let (org, repo) = split_repository_name(name);
+    let pool = state.db_pool.clone();
+    let pusher = pusher.map(|id| id.to_string());
+    crate::tenant::spawn(async move {
+        insert_activity(&pool, org, repo, ActivityAction::Push, pusher).await;
+    });
+}
+
+/// Organization and repository of `name`; names without one belong to the
+/// default organization
+fn split_repository_name(name: &str) -> (Option<String>, String) {
+    match name.split_once('/') {
+        Some((org, repo)) => (Some(org.to_string()), repo.to_string()),
+        None => (None, name.to_string()),
+    }
+}
+
+async fn insert_activity(
+    pool: &sqlx::PgPool,
+    org: Option<String>,
+    repo: String,
+    action: ActivityAction,
+    actor: Option<String>,
+) {
+    let recorded = sqlx::query(
+        "INSERT INTO registry_activity_daily (organization_id, repository_id, day, action, actor, count)
+         SELECT o.id, r.id, CURRENT_DATE, $3, $4, 1
+         FROM repositories r
+         JOIN organizations o ON o.id = r.organization_id
+         WHERE r.name = $2 AND (o.name = $1 OR ($1 IS NULL AND o.id = 1))
+         ON CONFLICT (repository_id, day, action, (COALESCE(actor, '')))
+         DO UPDATE SET count = registry_activity_daily.count + 1",
+    )
+    .bind(org)
+    .bind(repo)
+    .bind(action.as_str())
+    .bind(actor)
+    .execute(pool)
+    .timed("insert_activity")
+    .await;
+    if let Err(e) = recorded {
+        tracing::debug!("Failed to record registry activity: {}", e);
+    }
+}
+
+/// Why a manifest is being read
+#[derive(Debug, Clone, Copy)]
+enum ManifestRead<'a> {
+    /// A GET, counted as a pull by the signed-in user, if any
+    Pull(Option<&'a str>),
+    /// A HEAD, which only checks the manifest and is not counted
+    Check,
+}
+
 async fn get_manifest_impl(
     state: &AppState,
     name: &str,
     reference: &str,
+    read: ManifestRead<'_>,
 ) -> Response {
     println!("🔍 GET Manifest: {}/{}", name, reference);
+
+    if is_digest_reference(reference) {
+        if let Err(response) = validated_digest(reference) {
+            return response;
+        }
+    }
+
+    // Refuse flagged images before the cache can serve them
+    if let Some(response) = vulnerable_pull_denial(&state.db_pool, &state.blocking_organizations, state.config.registry.block_vulnerable_pulls, name, reference).await {
+        return response;
+    }
     
     // Check cache first
-    let cache_key = format!("manifest:{}:{}", name, reference);
+    let cache_key = crate::cache::manifest_cache_key(name, reference);
     if let Some(cache) = &state.cache {
         if let Some(cached_manifest) = cache.get_manifest(&cache_key).await {
             println!("✅ Cache HIT for manifest: {}/{}", name, reference);
@@ -1484,16 +3463,19 @@ async fn get_manifest_impl(
             // Parse cached manifest to extract headers
             if let Ok(manifest_json) = String::from_utf8(cached_manifest.to_vec()) {
                 if let Ok(manifest_value) = serde_json::from_str::<serde_json::Value>(&manifest_json) {
-                    let digest = format!("sha256:{:x}", Sha256::digest(cached_manifest.as_ref()));
-                    let media_type = manifest_value.get("mediaType")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("application/vnd.docker.distribution.manifest.v2+json");
+                    let digest = Digest::sha256(cached_manifest.as_ref());
+                    let media_type = manifest_media_type(&manifest_value);
                     
                     let mut headers = HeaderMap::new();
-                    headers.insert("Content-Type", HeaderValue::from_str(media_type).unwrap());
-                    headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
+                    if let Ok(value) = HeaderValue::from_str(media_type.as_str()) {
+                        headers.insert("Content-Type", value);
+                    }
+                    set_content_digest(&mut headers, digest.as_str());
                     headers.insert("Content-Length", HeaderValue::from_str(&cached_manifest.len().to_string()).unwrap());
-                    headers.insert("Cache-Control", HeaderValue::from_static("public, max-age=300"));
+                    headers.insert("Cache-Control", HeaderValue::from_str(&manifest_cache_control(reference, state.config.registry.tag_manifest_max_age_secs)).unwrap());
+                    if let ManifestRead::Pull(puller) = read {
+                        record_manifest_pull(state, name, &digest, puller);
+                    }
                     
                     return (StatusCode::OK, headers, manifest_json).into_response();
                 }
@@ -1521,6 +3503,7 @@ async fn get_manifest_impl(
             org, repo_name
         )
         .fetch_optional(&state.db_pool)
+        .timed("get_manifest_impl: SELECT repositories")
         .await
         {
             Ok(Some(row)) => row.id,
@@ -1548,6 +3531,7 @@ async fn get_manifest_impl(
             repo_name
         )
         .fetch_optional(&state.db_pool)
+        .timed("get_manifest_impl: SELECT repositories")
         .await
         {
             Ok(Some(row)) => row.id,
@@ -1571,7 +3555,7 @@ async fn get_manifest_impl(
     };
     
     // Find manifest by tag or digest
-    let result = if reference.starts_with("sha256:") {
+    let result = if is_digest_reference(reference) {
         // Direct digest lookup
         sqlx::query(
             "SELECT digest, media_type, size FROM manifests 
@@ -1580,6 +3564,7 @@ async fn get_manifest_impl(
         .bind(repository_id)
         .bind(reference)
         .fetch_optional(&state.db_pool)
+        .timed("get_manifest_impl: SELECT manifests")
         .await
     } else {
         // Tag lookup 
@@ -1592,19 +3577,26 @@ async fn get_manifest_impl(
         .bind(repository_id)
         .bind(reference)
         .fetch_optional(&state.db_pool)
+        .timed("get_manifest_impl: SELECT manifests")
         .await
     };
     
     match result {
         Ok(Some(row)) => {
-            let digest: String = row.get("digest");
+            let digest: Digest = match row.try_get("digest") {
+                Ok(digest) => digest,
+                Err(e) => {
+                    println!("❌ Invalid digest stored for {}:{}: {}", name, reference, e);
+                    return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+                }
+            };
             let media_type: String = row.get("media_type");  
             let size: i64 = row.get("size");
             
             println!("✅ Found manifest in database: digest={}, media_type={}, size={}", digest, media_type, size);
             
             // Try to retrieve the actual manifest content from S3 storage first
-            let manifest_blob_key = format!("blobs/{}", digest);
+            let manifest_blob_key = digest.blob_key();
             let manifest_content = match state.storage.get_blob(&manifest_blob_key).await {
                 Ok(Some(content)) => {
                     println!("✅ Retrieved manifest content from S3: {} bytes", content.len());
@@ -1613,7 +3605,7 @@ async fn get_manifest_impl(
                         Err(_) => {
                             println!("⚠️ Failed to parse manifest content as UTF-8, checking memory cache");
                             // Check memory cache
-                            match state.manifest_cache.read().await.get(&digest) {
+                            match state.manifest_cache.read().await.get(digest.as_str()) {
                                 Some(cached_content) => {
                                     println!("✅ Found manifest in memory cache: {} bytes", cached_content.len());
                                     cached_content.clone()
@@ -1645,7 +3637,7 @@ async fn get_manifest_impl(
                 Ok(None) => {
                     println!("⚠️ Manifest content not found in S3, checking memory cache");
                     // Check memory cache for manifest content
-                    match state.manifest_cache.read().await.get(&digest) {
+                    match state.manifest_cache.read().await.get(digest.as_str()) {
                         Some(cached_content) => {
                             println!("✅ Found manifest in memory cache: {} bytes", cached_content.len());
                             cached_content.clone()
@@ -1675,7 +3667,7 @@ async fn get_manifest_impl(
                 Err(e) => {
                     println!("⚠️ Error retrieving manifest from S3: {}, checking memory cache", e);
                     // Check memory cache for manifest content
-                    match state.manifest_cache.read().await.get(&digest) {
+                    match state.manifest_cache.read().await.get(digest.as_str()) {
                         Some(cached_content) => {
                             println!("✅ Found manifest in memory cache: {} bytes", cached_content.len());
                             cached_content.clone()
@@ -1705,18 +3697,21 @@ async fn get_manifest_impl(
             };            // Cache the manifest
             if let Some(cache) = &state.cache {
                 let manifest_bytes = Bytes::from(manifest_content.clone());
-                if let Err(e) = cache.cache_manifest(&cache_key, manifest_bytes).await {
-                    println!("⚠️ Failed to cache manifest: {}", e);
-                } else {
-                    println!("✅ Cached manifest: {}/{}", name, reference);
+                match cache.cache_manifest(&cache_key, manifest_bytes).await {
+                    Ok(true) => println!("✅ Cached manifest: {}/{}", name, reference),
+                    Ok(false) => println!("ℹ️ Manifest {}/{} too large to cache", name, reference),
+                    Err(e) => println!("⚠️ Failed to cache manifest: {}", e),
                 }
             }
             
             let mut headers = HeaderMap::new();
             headers.insert("Content-Type", HeaderValue::from_str(&media_type).unwrap());
-            headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
+            set_content_digest(&mut headers, digest.as_str());
             headers.insert("Content-Length", HeaderValue::from_str(&manifest_content.len().to_string()).unwrap());
-            headers.insert("Cache-Control", HeaderValue::from_static("public, max-age=300"));
+            headers.insert("Cache-Control", HeaderValue::from_str(&manifest_cache_control(reference, state.config.registry.tag_manifest_max_age_secs)).unwrap());
+            if let ManifestRead::Pull(puller) = read {
+                record_manifest_pull(state, name, &digest, puller);
+            }
             
             (StatusCode::OK, headers, manifest_content).into_response()
         },
@@ -1737,45 +3732,458 @@ async fn get_manifest_impl(
             ).into_response()
         }
     }
-}
-
-async fn head_manifest_impl(
-    _state: &AppState,
-    name: &str,
-    reference: &str,
-) -> impl IntoResponse {
-    // TODO: Implement actual manifest existence check
-    println!("Checking manifest existence for {}/{}", name, reference);
+}
+
+async fn head_manifest_impl(
+    state: &AppState,
+    name: &str,
+    reference: &str,
+) -> Response {
+    println!("Checking manifest existence for {}/{}", name, reference);
+
+    // Resolve exactly as GET does so the digest matches what a pull receives
+    without_body(get_manifest_impl(state, name, reference, ManifestRead::Check).await)
+}
+
+/// Name of the header carrying the digest of a manifest or blob
+pub const DOCKER_CONTENT_DIGEST: &str = "Docker-Content-Digest";
+
+/// Set `Docker-Content-Digest` to the resolved digest, so clients can verify
+/// what they received and skip pulls of content they already have. Digests
+/// that are not valid header values are left out rather than panicking.
+pub fn set_content_digest(headers: &mut HeaderMap, digest: &str) {
+    match HeaderValue::from_str(digest) {
+        Ok(value) => {
+            headers.insert(DOCKER_CONTENT_DIGEST, value);
+        }
+        Err(_) => println!("⚠️ Not sending invalid digest header: {:?}", digest),
+    }
+}
+
+/// Turn a GET response into the matching HEAD response: same status and
+/// headers (including Content-Length), no body.
+pub fn without_body(response: Response) -> Response {
+    let (parts, _body) = response.into_parts();
+    Response::from_parts(parts, axum::body::Body::empty())
+}
+
+/// Platform of an image, read from its config blob
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ImagePlatform {
+    pub architecture: String,
+    pub os: String,
+}
+
+/// Cache-Control value for a manifest response. Digest references are
+/// content-addressed and never change, so they may be cached forever; tags
+/// can be re-pointed at any time and get `no-cache` unless a max-age is set.
+pub fn manifest_cache_control(reference: &str, tag_max_age_secs: u64) -> String {
+    let is_digest = reference
+        .split_once(':')
+        .map(|(algorithm, hex)| {
+            !algorithm.is_empty() && !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit())
+        })
+        .unwrap_or(false);
+
+    if is_digest {
+        "public, max-age=31536000, immutable".to_string()
+    } else if tag_max_age_secs == 0 {
+        "no-cache".to_string()
+    } else {
+        format!("public, max-age={}", tag_max_age_secs)
+    }
+}
+
+/// Why a pushed manifest's config or layer blobs were rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum ManifestConfigError {
+    /// A referenced blob has not been uploaded, or has been collected
+    BlobUnknown(String),
+    /// A descriptor's digest is malformed, so it cannot name a blob
+    InvalidDigest(String),
+    /// The storage backend failed while looking up a blob
+    Storage(String),
+}
+
+impl std::fmt::Display for ManifestConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestConfigError::BlobUnknown(digest) => write!(f, "blob {} is not in storage", digest),
+            ManifestConfigError::InvalidDigest(message) => write!(f, "invalid descriptor digest: {}", message),
+            ManifestConfigError::Storage(e) => write!(f, "storage error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ManifestConfigError {}
+
+/// Parse a descriptor digest from a pushed manifest. The manifest is
+/// untrusted, so its digests are checked before they name a storage key.
+fn descriptor_digest(digest: &str) -> Result<Digest, ManifestConfigError> {
+    Digest::parse(digest).map_err(|e| ManifestConfigError::InvalidDigest(e.to_string()))
+}
+
+/// Verify that the config blob referenced by a manifest exists and read the
+/// image platform from it. Manifests without a config descriptor (indexes,
+/// manifest lists) return `Ok(None)`, as do configs without platform fields.
+pub async fn verify_manifest_config(
+    storage: &dyn crate::storage::Storage,
+    manifest: &str,
+) -> Result<Option<ImagePlatform>, ManifestConfigError> {
+    let manifest: serde_json::Value = match serde_json::from_str(manifest) {
+        Ok(value) => value,
+        Err(_) => return Ok(None),
+    };
+
+    let config_digest = match manifest
+        .get("config")
+        .and_then(|config| config.get("digest"))
+        .and_then(|digest| digest.as_str())
+    {
+        Some(digest) => digest,
+        None => return Ok(None),
+    };
+
+    let config_digest = descriptor_digest(config_digest)?;
+    let config = storage
+        .get_blob(&config_digest.blob_key())
+        .await
+        .map_err(|e| ManifestConfigError::Storage(e.to_string()))?
+        .ok_or_else(|| ManifestConfigError::BlobUnknown(config_digest.to_string()))?;
+
+    let config: serde_json::Value = match serde_json::from_slice(&config) {
+        Ok(value) => value,
+        Err(_) => return Ok(None),
+    };
+
+    match (
+        config.get("architecture").and_then(|v| v.as_str()),
+        config.get("os").and_then(|v| v.as_str()),
+    ) {
+        (Some(architecture), Some(os)) => Ok(Some(ImagePlatform {
+            architecture: architecture.to_string(),
+            os: os.to_string(),
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Verify that the config and every layer a manifest references are in
+/// storage, so a tag never points at an image that cannot be pulled. Layers
+/// that live elsewhere (`urls`, as in foreign layers) are not checked.
+pub async fn verify_manifest_blobs(
+    storage: &dyn crate::storage::Storage,
+    manifest: &str,
+) -> Result<(), ManifestConfigError> {
+    let manifest: serde_json::Value = match serde_json::from_str(manifest) {
+        Ok(value) => value,
+        Err(_) => return Ok(()),
+    };
+
+    let layers = manifest
+        .get("layers")
+        .and_then(|layers| layers.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let descriptors = manifest.get("config").into_iter().chain(layers);
+    for descriptor in descriptors {
+        if descriptor.get("urls").is_some() {
+            continue;
+        }
+        let digest = match descriptor.get("digest").and_then(|digest| digest.as_str()) {
+            Some(digest) => digest,
+            None => continue,
+        };
+        let digest = descriptor_digest(digest)?;
+        let exists = storage
+            .blob_exists(&digest.blob_key())
+            .await
+            .map_err(|e| ManifestConfigError::Storage(e.to_string()))?;
+        if !exists {
+            return Err(ManifestConfigError::BlobUnknown(digest.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Total size of the layers a manifest references, as stored. Every layer
+/// must already be in storage, since a size a descriptor merely declares
+/// could understate the blob uploaded later; layers that live elsewhere
+/// (`urls`, as in foreign layers) are never stored here and count with the
+/// size their descriptor gives. Indexes and manifest lists have no layers
+/// and total 0.
+pub async fn image_layers_size(
+    storage: &dyn crate::storage::Storage,
+    manifest: &serde_json::Value,
+) -> Result<u64, ManifestConfigError> {
+    let layers = manifest
+        .get("layers")
+        .and_then(|layers| layers.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut total: u64 = 0;
+    for layer in layers {
+        let declared = layer.get("size").and_then(|size| size.as_u64()).unwrap_or(0);
+        if layer.get("urls").is_some() {
+            total = total.saturating_add(declared);
+            continue;
+        }
+        // `validate_manifest_layers` refuses descriptors without a digest first
+        let digest = match layer.get("digest").and_then(|digest| digest.as_str()) {
+            Some(digest) => digest,
+            None => return Err(ManifestConfigError::BlobUnknown(String::new())),
+        };
+        let digest = descriptor_digest(digest)?;
+        let metadata = storage
+            .get_blob_metadata(&digest.blob_key())
+            .await
+            .map_err(|e| ManifestConfigError::Storage(e.to_string()))?;
+        match metadata {
+            Some(metadata) => total = total.saturating_add(metadata.size),
+            None => return Err(ManifestConfigError::BlobUnknown(digest.to_string())),
+        }
+    }
+    Ok(total)
+}
+
+/// Limit on the total layer size of images pushed to repository `name`.
+/// Simple names belong to the default organization, as in `get_manifest_impl`.
+async fn push_image_size_limit(state: &AppState, name: &str) -> anyhow::Result<Option<u64>> {
+    let org = name.split_once('/').map(|(org, _)| org);
+    let org_limit = sqlx::query_scalar::<_, Option<i64>>(
+        "SELECT max_image_bytes FROM organizations WHERE name = $1 OR ($1 IS NULL AND id = 1)"
+    )
+    .bind(org)
+    .fetch_optional(&state.db_pool)
+    .timed("push_image_size_limit")
+    .await?
+    .flatten();
+    Ok(image_size_limit(org_limit, state.config.registry.max_image_bytes))
+}
+
+/// Why a pushed manifest's layer list was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum ManifestLayersError {
+    /// The same layer digest is listed more than once
+    DuplicateLayer(String),
+    /// A layer descriptor has no digest
+    MissingDigest(usize),
+    /// The manifest lists no layers while `REJECT_EMPTY_MANIFEST_LAYERS` is set
+    NoLayers,
+}
+
+impl std::fmt::Display for ManifestLayersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestLayersError::DuplicateLayer(digest) => write!(f, "layer {} is listed more than once", digest),
+            ManifestLayersError::MissingDigest(index) => write!(f, "layer {} has no digest", index),
+            ManifestLayersError::NoLayers => write!(f, "manifest has no layers"),
+        }
+    }
+}
+
+/// Check the layer list of an image manifest. Indexes and manifest lists,
+/// which have no config descriptor, are not checked.
+pub fn validate_manifest_layers(manifest: &str, reject_empty: bool) -> Result<(), ManifestLayersError> {
+    let manifest: serde_json::Value = match serde_json::from_str(manifest) {
+        Ok(value) => value,
+        Err(_) => return Ok(()),
+    };
+    if manifest.get("config").is_none() {
+        return Ok(());
+    }
+
+    let layers = manifest
+        .get("layers")
+        .and_then(|layers| layers.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    if layers.is_empty() && reject_empty {
+        return Err(ManifestLayersError::NoLayers);
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    for (index, layer) in layers.iter().enumerate() {
+        let digest = layer
+            .get("digest")
+            .and_then(|digest| digest.as_str())
+            .ok_or(ManifestLayersError::MissingDigest(index))?;
+        if !seen.insert(digest) {
+            return Err(ManifestLayersError::DuplicateLayer(digest.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Whether what is stored under manifest `digest` is exactly `body`; true
+/// when nothing is stored yet
+pub async fn stored_manifest_matches(
+    storage: &dyn crate::storage::Storage,
+    digest: &Digest,
+    body: &[u8],
+) -> anyhow::Result<bool> {
+    Ok(match storage.get_blob(&digest.blob_key()).await? {
+        Some(stored) => stored.as_ref() == body,
+        None => true,
+    })
+}
+
+/// Whether a push may create repository `name`, which does not exist yet.
+/// With AUTO_CREATE_REPOS off, repositories must be created explicitly and
+/// the push is answered with `NAME_UNKNOWN`.
+pub fn auto_create_allowed(auto_create_repos: bool, name: &str) -> Result<(), Response> {
+    if auto_create_repos {
+        return Ok(());
+    }
+    println!("❌ Repository {} does not exist and AUTO_CREATE_REPOS is disabled", name);
+    Err(registry_error(
+        StatusCode::NOT_FOUND,
+        "NAME_UNKNOWN",
+        &format!("repository {} not known to registry; create it before pushing", name),
+    ))
+}
+
+/// Whether a repository a push creates in organization `org_id` is public.
+/// When the organization's default cannot be read the push fails, rather
+/// than risk publishing a repository meant to be private.
+pub async fn auto_created_repository_public(
+    pool: &sqlx::PgPool,
+    org_id: i64,
+    deployment_default: RepositoryVisibility,
+) -> Result<bool, Response> {
+    match organization_default_public(pool, org_id).await {
+        Ok(org_default) => Ok(new_repository_public(None, org_default, deployment_default)),
+        Err(e) => {
+            println!("❌ Failed to read default visibility of organization {}: {}", org_id, e);
+            Err(registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error"))
+        }
+    }
+}
+
+/// Check that `user_id` may create a repository in organization `org_id` by
+/// pushing to it. Anonymous callers are challenged; callers whose role in the
+/// organization does not pass `can_create_repository` are denied, the same
+/// as when creating the repository through the API.
+pub async fn authorize_auto_create(
+    pool: &sqlx::PgPool,
+    org_id: i64,
+    user_id: Option<i64>,
+    requires_admin: bool,
+) -> Result<(), Response> {
+    let user_id = match user_id {
+        Some(user_id) => user_id,
+        None => return Err(authentication_required()),
+    };
+
+    let role = sqlx::query_scalar::<_, String>(
+        "SELECT role FROM organization_members
+         WHERE organization_id = $1 AND user_id = $2
+           AND (expires_at IS NULL OR expires_at > NOW())"
+    )
+    .bind(org_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .timed("authorize_auto_create")
+    .await
+    .map_err(|e| {
+        println!("❌ Failed to read role of user {} in organization {}: {}", user_id, org_id, e);
+        registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error")
+    })?;
+
+    let role = role.and_then(|role| role.parse::<crate::models::organizations::OrganizationRole>().ok());
+    if !role.map_or(false, |role| role.can_create_repository(requires_admin)) {
+        println!("❌ User {} may not create repositories in organization {}", user_id, org_id);
+        return Err(registry_error(
+            StatusCode::FORBIDDEN,
+            "DENIED",
+            "pushing would create a repository in an organization you cannot create repositories in",
+        ));
+    }
+    Ok(())
+}
+
+/// Store the row of a pushed manifest with its references and platform, and
+/// point `reference` at it when it is a tag
+async fn store_manifest_and_tag(
+    state: &AppState,
+    repository_id: i64,
+    reference: &str,
+    digest: &str,
+    media_type: &str,
+    body: &str,
+    platform: Option<&ImagePlatform>,
+) -> Result<(), Response> {
+    let size = body.len() as i64;
+    let manifest_result = sqlx::query!(
+        "INSERT INTO manifests (repository_id, digest, media_type, size) 
+         VALUES ($1, $2, $3, $4) 
+         ON CONFLICT (repository_id, digest) 
+         DO UPDATE SET media_type = $3, size = $4
+         RETURNING id",
+        repository_id, digest, media_type, size
+    )
+    .fetch_one(&state.db_pool)
+    .timed("store_manifest_and_tag: INSERT manifests")
+    .await;
+    
+    let manifest_id = match manifest_result {
+        Ok(row) => {
+            println!("✅ Manifest stored in database with ID: {}", row.id);
+            row.id
+        },
+        Err(e) => {
+            println!("❌ Error storing manifest: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                HeaderMap::new(),
+                Json(serde_json::json!({"error": "Failed to store manifest"}))
+            ).into_response());
+        }
+    };
+    
+    if let Err(e) = record_manifest_blob_references(&state.db_pool, manifest_id, body).await {
+        println!("❌ Error indexing manifest references: {}", e);
+        return Err(registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error"));
+    }
+
+    // Record the image platform for multi-arch resolution and filtering
+    if let Some(platform) = platform {
+        if let Err(e) = sqlx::query("UPDATE manifests SET architecture = $2, os = $3 WHERE id = $1")
+            .bind(manifest_id)
+            .bind(&platform.architecture)
+            .bind(&platform.os)
+            .execute(&state.db_pool)
+            .timed("store_manifest_and_tag: UPDATE manifests")
+            .await
+        {
+            println!("⚠️  Error storing manifest platform: {}", e);
+        }
+    }
     
-    // Use the same manifest structure as in get_manifest_impl to calculate the correct digest
-    let manifest = json!({
-        "schemaVersion": 2,
-        "mediaType": "application/vnd.docker.distribution.manifest.v2+json",
-        "config": {
-            "mediaType": "application/vnd.docker.container.image.v1+json",
-            "size": 1469,
-            "digest": "sha256:9234e8fb04c47cfe0f49931e4ac7eb76fa904e33b7f8576aec0501c085f02516"
-        },
-        "layers": [
-            {
-                "mediaType": "application/vnd.docker.image.rootfs.diff.tar.gzip",
-                "size": 3208942,
-                "digest": "sha256:4bcff63911fcb4448bd4fdacec207030997caf25e9bea4045fa6c8c44de311d1"
+    // If reference is a tag (not a digest), create/update tag
+    if !is_digest_reference(reference) {
+        let tag_result = sqlx::query!(
+            "INSERT INTO tags (repository_id, name, manifest_id) 
+             VALUES ($1, $2, $3)
+             ON CONFLICT (repository_id, name)
+             DO UPDATE SET manifest_id = $3, updated_at = CURRENT_TIMESTAMP
+             RETURNING id",
+            repository_id, reference, manifest_id
+        )
+        .fetch_one(&state.db_pool)
+        .timed("store_manifest_and_tag: INSERT tags")
+        .await;
+
+        match tag_result {
+            Ok(row) => println!("✅ Tag '{}' stored in database with ID: {}", reference, row.id),
+            Err(e) => {
+                println!("⚠️  Error storing tag: {}", e);
+                // Don't fail the whole operation for tag errors
             }
-        ]
-    });
-    
-    // Calculate proper digest for this manifest
-    let manifest_json = serde_json::to_string(&manifest).unwrap();
-    let digest = format!("sha256:{:x}", Sha256::digest(manifest_json.as_bytes()));
-    let content_length = manifest_json.len().to_string();
-    
-    let mut headers = HeaderMap::new();
-    headers.insert("Content-Type", HeaderValue::from_static("application/vnd.docker.distribution.manifest.v2+json"));
-    headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
-    headers.insert("Content-Length", HeaderValue::from_str(&content_length).unwrap());
-    
-    (StatusCode::OK, headers)
+        }
+    }
+    Ok(())
 }
 
 async fn put_manifest_impl(
@@ -1788,20 +4196,172 @@ async fn put_manifest_impl(
 ) -> impl IntoResponse {
     println!("🚀 PUT Manifest: {}/{} - {} bytes", name, reference, body.len());
     println!("Content-Type: {:?}", headers.get("content-type"));
+
+    // Pushing a manifest creates the repository, so the name must be valid
+    let repository_name = match validated_repository_name(name) {
+        Ok(repository_name) => repository_name,
+        Err(response) => return response,
+    };
+    if let Err(e) = repository_name.check_depth(state.config.registry.max_repo_path_segments) {
+        return registry_error(StatusCode::BAD_REQUEST, "NAME_INVALID", &e.to_string());
+    }
     
     // Calculate digest 
-    let digest = format!("sha256:{}", hex::encode(Sha256::digest(body.as_bytes())));
-    let size = body.len() as i64;
-    let media_type = headers.get("content-type")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("application/vnd.docker.distribution.manifest.v2+json");
+    let digest = Digest::sha256(body.as_bytes());
+
+    // A manifest pushed by digest must have that digest
+    if is_digest_reference(reference) {
+        match validated_digest(reference) {
+            Ok(expected) if expected != digest => {
+                return registry_error(StatusCode::BAD_REQUEST, "DIGEST_INVALID", "provided digest did not match uploaded content");
+            }
+            Ok(_) => {}
+            Err(response) => return response,
+        }
+    }
+    let content_type = headers.get("content-type").and_then(|h| h.to_str().ok());
+    let media_type = match manifest_push_media_type(content_type, &body) {
+        Ok(media_type) => media_type.to_string(),
+        Err(message) => {
+            tracing::warn!(repository = %name, %reference, "Rejected manifest: {}", message);
+            return registry_error(StatusCode::BAD_REQUEST, "MANIFEST_INVALID", &message);
+        }
+    };
+    let media_type = media_type.as_str();
+
+    let manifest_value = serde_json::from_str::<serde_json::Value>(&body).unwrap_or_default();
+    let artifact = artifact_type(&manifest_value, media_type);
+    if let Err(message) = state.config.registry.manifest_size_limits.check(&artifact, body.len()) {
+        tracing::warn!(repository = %name, %reference, "Rejected manifest: {}", message);
+        return registry_error(StatusCode::BAD_REQUEST, "MANIFEST_INVALID", &message);
+    }
+
+    if let Err(e) = validate_manifest_layers(&body, state.config.registry.reject_empty_manifest_layers) {
+        println!("❌ Invalid layers in manifest {}/{}: {}", name, reference, e);
+        return registry_error(StatusCode::BAD_REQUEST, "MANIFEST_INVALID", &format!("manifest invalid: {}", e));
+    }
+
+    // The config blob must be uploaded before the manifest that references it
+    let platform = match verify_manifest_config(state.storage.as_ref(), &body).await {
+        Ok(platform) => platform,
+        Err(ManifestConfigError::BlobUnknown(config_digest)) => {
+            println!("❌ Config blob {} not found for manifest {}/{}", config_digest, name, reference);
+            return (
+                StatusCode::BAD_REQUEST,
+                HeaderMap::new(),
+                Json(serde_json::json!({
+                    "errors": [{
+                        "code": "MANIFEST_BLOB_UNKNOWN",
+                        "message": "blob unknown to registry",
+                        "detail": { "digest": config_digest }
+                    }]
+                }))
+            ).into_response();
+        }
+        Err(ManifestConfigError::InvalidDigest(message)) => {
+            tracing::warn!(repository = %name, %reference, "Rejected manifest: {}", message);
+            return registry_error(StatusCode::BAD_REQUEST, "DIGEST_INVALID", &message);
+        }
+        Err(ManifestConfigError::Storage(e)) => {
+            println!("❌ Failed to check config blob: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                HeaderMap::new(),
+                Json(serde_json::json!({
+                    "errors": [{
+                        "code": "UNKNOWN",
+                        "message": "Internal server error",
+                        "detail": {}
+                    }]
+                }))
+            ).into_response();
+        }
+    };
     
-    // Parse repository name (handle org/repo format)
-    let (org_name, repo_name) = if name.contains('/') {
-        let parts: Vec<&str> = name.splitn(2, '/').collect();
-        (Some(parts[0]), parts[1])
-    } else {
-        (None, name)
+    // Images whose layers add up to more than the organization's or the
+    // global limit are refused before anything is recorded
+    let max_image_bytes = match push_image_size_limit(state, name).await {
+        Ok(max_image_bytes) => max_image_bytes,
+        Err(e) => {
+            tracing::error!(repository = %name, "Failed to read image size limit: {}", e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+        }
+    };
+    if max_image_bytes.is_some() {
+        let total = match image_layers_size(state.storage.as_ref(), &manifest_value).await {
+            Ok(total) => total,
+            Err(ManifestConfigError::BlobUnknown(layer_digest)) => {
+                tracing::warn!(repository = %name, %reference, layer = %layer_digest, "Rejected manifest: layer not uploaded");
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "errors": [{
+                            "code": "MANIFEST_BLOB_UNKNOWN",
+                            "message": "blob unknown to registry",
+                            "detail": { "digest": layer_digest }
+                        }]
+                    }))
+                ).into_response();
+            }
+            Err(ManifestConfigError::InvalidDigest(message)) => {
+                tracing::warn!(repository = %name, %reference, "Rejected manifest: {}", message);
+                return registry_error(StatusCode::BAD_REQUEST, "DIGEST_INVALID", &message);
+            }
+            Err(ManifestConfigError::Storage(e)) => {
+                tracing::error!(repository = %name, %reference, "Failed to size manifest layers: {}", e);
+                return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+            }
+        };
+        if let Err(message) = check_image_size(total, max_image_bytes) {
+            tracing::warn!(repository = %name, %reference, "Rejected manifest: {}", message);
+            return registry_error(StatusCode::PAYLOAD_TOO_LARGE, "DENIED", &message);
+        }
+    }
+
+    // Tagging a manifest that was stored before, e.g. rolling a tag back to
+    // it, must not succeed if its blobs have been collected since
+    if !is_digest_reference(reference) && state.config.registry.verify_blobs_on_tag {
+        match verify_manifest_blobs(state.storage.as_ref(), &body).await {
+            Ok(()) => {}
+            Err(ManifestConfigError::BlobUnknown(blob_digest)) => {
+                println!("❌ Blob {} of manifest {} is missing; not tagging {}/{}", blob_digest, digest, name, reference);
+                return (
+                    StatusCode::BAD_REQUEST,
+                    HeaderMap::new(),
+                    Json(serde_json::json!({
+                        "errors": [{
+                            "code": "MANIFEST_BLOB_UNKNOWN",
+                            "message": "blob unknown to registry",
+                            "detail": { "digest": blob_digest }
+                        }]
+                    }))
+                ).into_response();
+            }
+            Err(ManifestConfigError::InvalidDigest(message)) => {
+                tracing::warn!(repository = %name, %reference, "Rejected manifest: {}", message);
+                return registry_error(StatusCode::BAD_REQUEST, "DIGEST_INVALID", &message);
+            }
+            Err(ManifestConfigError::Storage(e)) => {
+                println!("❌ Failed to check manifest blobs: {}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    HeaderMap::new(),
+                    Json(serde_json::json!({
+                        "errors": [{
+                            "code": "UNKNOWN",
+                            "message": "Internal server error",
+                            "detail": {}
+                        }]
+                    }))
+                ).into_response();
+            }
+        }
+    }
+
+    // Split the validated name into organization and repository
+    let (org_name, repo_name) = match repository_name.split_namespace() {
+        Some((org, repo)) => (Some(org), repo),
+        None => (None, repository_name.as_str()),
     };
     
     // Find or create repository ID
@@ -1814,44 +4374,34 @@ async fn put_manifest_impl(
             org, repo_name
         )
         .fetch_optional(&state.db_pool)
+        .timed("put_manifest_impl: SELECT repositories")
         .await
         {
             Ok(Some(row)) => row.id,
             Ok(None) => {
+                if let Err(response) = auto_create_allowed(state.config.registry.auto_create_repos, name) {
+                    return response;
+                }
                 // Repository not found, try to create it
                 println!("🔧 Repository {}/{} not found, attempting to create it", org, repo_name);
                 
-                // First, get or create organization
+                // Pushes only create repositories, never organizations
                 let org_id = match sqlx::query!(
                     "SELECT id FROM organizations WHERE name = $1",
                     org
                 )
                 .fetch_optional(&state.db_pool)
+                .timed("put_manifest_impl: SELECT organizations")
                 .await
                 {
                     Ok(Some(org_row)) => org_row.id,
                     Ok(None) => {
-                        // Create organization
-                        match sqlx::query!(
-                            "INSERT INTO organizations (name, display_name) VALUES ($1, $1) RETURNING id",
-                            org
-                        )
-                        .fetch_one(&state.db_pool)
-                        .await
-                        {
-                            Ok(new_org) => {
-                                println!("✅ Created organization: {}", org);
-                                new_org.id
-                            },
-                            Err(e) => {
-                                println!("❌ Failed to create organization: {}", e);
-                                return (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    HeaderMap::new(),
-                                    Json(serde_json::json!({"error": "Failed to create organization"}))
-                                ).into_response();
-                            }
-                        }
+                        println!("❌ Organization {} does not exist", org);
+                        return registry_error(
+                            StatusCode::NOT_FOUND,
+                            "NAME_UNKNOWN",
+                            &format!("organization {} not known to registry", org),
+                        );
                     },
                     Err(e) => {
                         println!("❌ Database error getting organization: {}", e);
@@ -1862,19 +4412,44 @@ async fn put_manifest_impl(
                         ).into_response();
                     }
                 };
+                if let Err(response) = authorize_auto_create(
+                    &state.db_pool,
+                    org_id,
+                    user_id,
+                    state.config.registry.repo_creation_requires_admin,
+                )
+                .await
+                {
+                    return response;
+                }
                 
                 // Create repository
-                match sqlx::query!(
+                let is_public = match auto_created_repository_public(
+                    &state.db_pool,
+                    org_id,
+                    state.config.registry.default_repo_visibility,
+                )
+                .await
+                {
+                    Ok(is_public) => is_public,
+                    Err(response) => return response,
+                };
+                match sqlx::query_scalar::<_, i64>(
                     "INSERT INTO repositories (name, organization_id, is_public, created_by) 
-                     VALUES ($1, $2, true, $3) RETURNING id",
-                    repo_name, org_id, user_id
+                     VALUES ($1, $2, $3, $4) RETURNING id"
                 )
+                .bind(repo_name)
+                .bind(org_id)
+                .bind(is_public)
+                .bind(user_id)
                 .fetch_one(&state.db_pool)
+                .timed("put_manifest_impl: INSERT repositories")
                 .await
                 {
-                    Ok(new_repo) => {
+                    Ok(new_repo_id) => {
                         println!("✅ Created repository: {}/{}", org, repo_name);
-                        new_repo.id
+                        invalidate_catalog(state).await;
+                        new_repo_id
                     },
                     Err(e) => {
                         println!("❌ Failed to create repository: {}", e);
@@ -1902,23 +4477,51 @@ async fn put_manifest_impl(
             repo_name
         )
         .fetch_optional(&state.db_pool)
+        .timed("put_manifest_impl: SELECT repositories")
         .await
         {
             Ok(Some(row)) => row.id,
             Ok(None) => {
+                if let Err(response) = auto_create_allowed(state.config.registry.auto_create_repos, name) {
+                    return response;
+                }
                 // Repository not found, create it under default organization (id=1)
                 println!("🔧 Repository {} not found, attempting to create it", repo_name);
-                match sqlx::query!(
+                if let Err(response) = authorize_auto_create(
+                    &state.db_pool,
+                    crate::tenant::DEFAULT_ORGANIZATION_ID,
+                    user_id,
+                    state.config.registry.repo_creation_requires_admin,
+                )
+                .await
+                {
+                    return response;
+                }
+                let is_public = match auto_created_repository_public(
+                    &state.db_pool,
+                    1,
+                    state.config.registry.default_repo_visibility,
+                )
+                .await
+                {
+                    Ok(is_public) => is_public,
+                    Err(response) => return response,
+                };
+                match sqlx::query_scalar::<_, i64>(
                     "INSERT INTO repositories (name, organization_id, is_public, created_by) 
-                     VALUES ($1, 1, true, $2) RETURNING id",
-                    repo_name, user_id
+                     VALUES ($1, 1, $2, $3) RETURNING id"
                 )
+                .bind(repo_name)
+                .bind(is_public)
+                .bind(user_id)
                 .fetch_one(&state.db_pool)
+                .timed("put_manifest_impl: INSERT repositories")
                 .await
                 {
-                    Ok(new_repo) => {
+                    Ok(new_repo_id) => {
                         println!("✅ Created repository: {}", repo_name);
-                        new_repo.id
+                        invalidate_catalog(state).await;
+                        new_repo_id
                     },
                     Err(e) => {
                         println!("❌ Failed to create repository: {}", e);
@@ -1941,9 +4544,59 @@ async fn put_manifest_impl(
         }
     };
 
+    // Repositories may restrict tags to semantic versions
+    if !is_digest_reference(reference) {
+        let require_semver = match sqlx::query_scalar::<_, bool>("SELECT require_semver FROM repositories WHERE id = $1")
+            .bind(repository_id)
+            .fetch_one(&state.db_pool)
+            .timed("put_manifest_impl: SELECT repositories")
+            .await
+        {
+            Ok(require_semver) => require_semver,
+            Err(e) => {
+                println!("❌ Database error reading tag policy: {}", e);
+                return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+            }
+        };
+        if !tag_allowed(require_semver, reference) {
+            return registry_error(
+                StatusCode::BAD_REQUEST,
+                "TAG_INVALID",
+                &format!("tag '{}' is not a semantic version, which repository {} requires", reference, name),
+            );
+        }
+    }
+
     // Store manifest content in S3 storage as a blob
-    let manifest_blob_key = format!("blobs/{}", digest);  // Full blobs/ path
-    let _s3_success = match state.storage.put_blob(&manifest_blob_key, Bytes::from(body.clone())).await {
+    let manifest_blob_key = digest.blob_key();
+    let manifest_storage = state.storage_router.select(&StoredContent::manifest(media_type, body.len() as u64));
+
+    // Content is addressed by its digest, so what is already stored under a
+    // pushed digest must be byte for byte the same; re-pushing it is a no-op
+    if is_digest_reference(reference) && state.config.registry.enforce_manifest_immutability {
+        match stored_manifest_matches(manifest_storage.as_ref(), &digest, body.as_bytes()).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::error!(
+                    repository = %name,
+                    digest = %digest,
+                    "Stored manifest content differs from pushed content with the same digest; storage may be corrupt"
+                );
+                println!("🚨 Stored manifest {} differs from pushed content; refusing to overwrite", digest);
+                return registry_error(
+                    StatusCode::BAD_REQUEST,
+                    "MANIFEST_INVALID",
+                    &format!("content stored for {} does not match the pushed manifest", digest),
+                );
+            }
+            Err(e) => {
+                println!("❌ Failed to read stored manifest {}: {}", digest, e);
+                return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+            }
+        }
+    }
+
+    let _s3_success = match manifest_storage.put_blob(&manifest_blob_key, Bytes::from(body.clone())).await {
         Ok(_) => {
             println!("✅ Manifest content stored in S3 blobs folder: {}", manifest_blob_key);
             true
@@ -1958,63 +4611,46 @@ async fn put_manifest_impl(
     // Always store manifest content in memory cache as backup
     {
         let mut cache = state.manifest_cache.write().await;
-        cache.insert(digest.clone(), body.clone());
+        cache.insert(digest.to_string(), body.clone());
         println!("✅ Manifest content cached in memory: {} bytes", body.len());
     }
 
-    // Insert or update manifest in database  
-    let manifest_result = sqlx::query!(
-        "INSERT INTO manifests (repository_id, digest, media_type, size) 
-         VALUES ($1, $2, $3, $4) 
-         ON CONFLICT (repository_id, digest) 
-         DO UPDATE SET media_type = $3, size = $4
-         RETURNING id",
-        repository_id, digest, media_type, size
-    )
-    .fetch_one(&state.db_pool)
-    .await;
-    
-    let manifest_id = match manifest_result {
-        Ok(row) => {
-            println!("✅ Manifest stored in database with ID: {}", row.id);
-            row.id
-        },
-        Err(e) => {
-            println!("❌ Error storing manifest: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                HeaderMap::new(),
-                Json(serde_json::json!({"error": "Failed to store manifest"}))
-            ).into_response();
-        }
-    };
-    
-    // If reference is a tag (not a digest), create/update tag
-    if !reference.starts_with("sha256:") {
-        let tag_result = sqlx::query!(
-            "INSERT INTO tags (repository_id, name, manifest_id) 
-             VALUES ($1, $2, $3)
-             ON CONFLICT (repository_id, name)
-             DO UPDATE SET manifest_id = $3, updated_at = CURRENT_TIMESTAMP
-             RETURNING id",
-            repository_id, reference, manifest_id
-        )
-        .fetch_one(&state.db_pool)
-        .await;
-        
-        match tag_result {
-            Ok(row) => println!("✅ Tag '{}' stored in database with ID: {}", reference, row.id),
-            Err(e) => {
-                println!("⚠️  Error storing tag: {}", e);
-                // Don't fail the whole operation for tag errors
+    // Pushes to one tag from different instances store their manifest and
+    // move the tag one after the other
+    let stored = store_manifest_and_tag(state, repository_id, reference, digest.as_str(), media_type, &body, platform.as_ref());
+    let stored = match &state.cache {
+        Some(cache) if !is_digest_reference(reference) => {
+            let key = crate::tag_lock::tag_lock_key(name, reference);
+            match crate::tag_lock::with_lock(
+                cache.as_ref(),
+                &key,
+                crate::tag_lock::TAG_LOCK_TTL,
+                crate::tag_lock::TAG_LOCK_WAIT,
+                stored,
+            )
+            .await
+            {
+                Ok(stored) => stored,
+                Err(e) => {
+                    tracing::warn!(repository = %name, tag = %reference, "Manifest not stored: {}", e);
+                    return registry_error(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "UNAVAILABLE",
+                        "Tag is being updated by another push, retry later",
+                    );
+                }
             }
         }
+        _ => stored.await,
+    };
+    if let Err(response) = stored {
+        return response;
     }
     
     // Invalidate related caches after successful manifest upload
     if let Some(cache) = &state.cache {
         // Invalidate manifest cache for this repository/reference
-        let manifest_cache_key = format!("manifest:{}:{}", name, reference);
+        let manifest_cache_key = crate::cache::manifest_cache_key(name, reference);
         if let Err(e) = cache.invalidate_manifest(&manifest_cache_key).await {
             println!("⚠️ Failed to invalidate manifest cache: {}", e);
         }
@@ -2026,55 +4662,214 @@ async fn put_manifest_impl(
             println!("✅ Invalidated caches for: {}", name);
         }
     }
-    
-    let mut response_headers = HeaderMap::new();
-    response_headers.insert("Location", HeaderValue::from_str(&format!("/v2/{}/manifests/{}", name, digest)).unwrap());
-    response_headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
-    
-    println!("🎉 Manifest successfully stored in database!");
-    (StatusCode::CREATED, response_headers, Json(serde_json::json!({}))).into_response()
+    
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert("Location", HeaderValue::from_str(&format!("/v2/{}/manifests/{}", name, digest)).unwrap());
+    set_content_digest(&mut response_headers, digest.as_str());
+    
+    record_manifest_push(state, name, user_id);
+
+    println!("🎉 Manifest successfully stored in database!");
+    (StatusCode::CREATED, response_headers, Json(serde_json::json!({}))).into_response()
+}
+
+/// Header carrying the ID that a delete's background cleanup logs under
+pub const DELETION_ID: &str = "X-Deletion-ID";
+
+/// Response to a delete whose storage cleanup `mode` describes: `202 Accepted`
+/// with the cleanup's tracking ID when it runs in the background, `204 No
+/// Content` once it has completed
+pub fn deletion_response(mode: DeletionMode, deletion_id: &str) -> Response {
+    match mode {
+        DeletionMode::Async => {
+            let mut headers = HeaderMap::new();
+            if let Ok(value) = HeaderValue::from_str(deletion_id) {
+                headers.insert(DELETION_ID, value);
+            }
+            (StatusCode::ACCEPTED, headers).into_response()
+        }
+        DeletionMode::Sync => StatusCode::NO_CONTENT.into_response(),
+    }
+}
+
+/// Delete a manifest by digest (with the tags pointing to it) or a single
+/// tag, then remove the blobs nothing references any more
+async fn delete_manifest_impl(
+    state: &AppState,
+    user_id: &str,
+    name: &str,
+    reference: &str,
+) -> Response {
+    if is_digest_reference(reference) {
+        if let Err(response) = validated_digest(reference) {
+            return response;
+        }
+    }
+
+    let (repository_id, full_name) = match repository_for_deletion(state, user_id, name).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    let deleted = if is_digest_reference(reference) {
+        // Tags pointing to the manifest go with it (ON DELETE CASCADE)
+        sqlx::query_as::<_, (String, Option<String>)>(
+            "DELETE FROM manifests WHERE repository_id = $1 AND digest = $2 RETURNING digest, content"
+        )
+        .bind(repository_id)
+        .bind(reference)
+        .fetch_optional(&state.db_pool)
+        .timed("delete_manifest_impl: DELETE manifests")
+        .await
+    } else {
+        // Deleting a tag leaves the manifest in place; no blobs are freed
+        sqlx::query_scalar::<_, String>("DELETE FROM tags WHERE repository_id = $1 AND name = $2 RETURNING name")
+            .bind(repository_id)
+            .bind(reference)
+            .fetch_optional(&state.db_pool)
+            .timed("delete_manifest_impl: DELETE tags")
+            .await
+            .map(|tag| tag.map(|_| (String::new(), None)))
+    };
+
+    let (digest, content) = match deleted {
+        Ok(Some(row)) => row,
+        Ok(None) => return registry_error(StatusCode::NOT_FOUND, "MANIFEST_UNKNOWN", "manifest unknown"),
+        Err(e) => {
+            println!("❌ Failed to delete manifest {}:{}: {}", full_name, reference, e);
+            return registry_error(StatusCode::INTERNAL_SERVER_ERROR, "UNKNOWN", "Internal server error");
+        }
+    };
+
+    state.manifest_cache.write().await.remove(reference);
+    if let Some(cache) = &state.cache {
+        if let Err(e) = cache.invalidate_manifest(&crate::cache::manifest_cache_key(&full_name, reference)).await {
+            println!("⚠️ Failed to invalidate manifest cache: {}", e);
+        }
+        if let Err(e) = cache.invalidate_tags(&full_name).await {
+            println!("⚠️ Failed to invalidate tags cache: {}", e);
+        }
+    }
+
+    // Manifests are also stored as blobs under their own digest
+    let mut blob_digests: Vec<Digest> = content
+        .as_deref()
+        .map(manifest_blob_digests)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|digest| Digest::parse(digest).ok())
+        .collect();
+    if let Ok(digest) = Digest::parse(&digest) {
+        blob_digests.push(digest);
+    }
+
+    let deletion_id = uuid::Uuid::new_v4().to_string();
+    let mode = state.config.registry.deletion_mode;
+    println!("🗑️ Deleted {}:{} (deletion {}, {:?} cleanup)", full_name, reference, deletion_id, mode);
+
+    match mode {
+        DeletionMode::Async => {
+            tokio::spawn(cleanup_unreferenced_blobs(state.clone(), blob_digests, deletion_id.clone()));
+        }
+        DeletionMode::Sync => cleanup_unreferenced_blobs(state.clone(), blob_digests, deletion_id.clone()).await,
+    }
+
+    deletion_response(mode, &deletion_id)
 }
 
-async fn delete_manifest_impl(
-    _state: &AppState,
-    name: &str,
-    reference: &str,
-) -> impl IntoResponse {
-    // TODO: Implement actual manifest deletion
-    println!("Deleting manifest for {}/{}", name, reference);
-    
-    StatusCode::ACCEPTED
+/// Build a blob response from storage, or `None` if the blob does not exist.
+/// Blobs below `stream_threshold` are read into memory; larger ones are streamed
+/// from the backend with the Content-Length taken from the blob metadata.
+/// With `verify`, content that no longer matches `digest` is refused with a
+/// 500 when buffered and aborts the body at its end when streamed.
+pub async fn blob_response(
+    storage: &dyn crate::storage::Storage,
+    blob_key: &str,
+    digest: &str,
+    stream_threshold: u64,
+    verify: bool,
+) -> anyhow::Result<Option<Response>> {
+    // Callers pass validated digests; one that does not parse cannot be checked
+    let verify_digest = if verify { Digest::parse(digest).ok() } else { None };
+
+    let metadata = match storage.get_blob_metadata(blob_key).await? {
+        Some(metadata) => metadata,
+        None => return Ok(None),
+    };
+
+    let filename = format!("{}.bin", digest.replace("sha256:", ""));
+    let mut headers = HeaderMap::new();
+    set_content_digest(&mut headers, digest);
+    headers.insert("Content-Disposition",
+        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", filename))?);
+    headers.insert("Cache-Control", HeaderValue::from_static("public, max-age=31536000"));
+
+    if metadata.size < stream_threshold {
+        let data = match storage.get_blob(blob_key).await? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        if let Some(expected) = &verify_digest {
+            if !content_matches(expected, &data) {
+                tracing::error!(digest = %expected, "Stored blob does not match its digest; refusing the pull");
+                return Ok(Some(registry_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "UNKNOWN",
+                    "Stored blob failed digest verification",
+                )));
+            }
+        }
+        println!("Serving blob buffered: {} bytes", data.len());
+
+        let content_type = detect_content_type(&data, digest);
+        headers.insert("Content-Type", HeaderValue::from_str(&content_type)?);
+        headers.insert("Content-Length", HeaderValue::from(data.len()));
+
+        return Ok(Some((StatusCode::OK, headers, data).into_response()));
+    }
+
+    let reader = match storage.get_blob_streaming(blob_key).await? {
+        Some(reader) => reader,
+        None => return Ok(None),
+    };
+    println!("Serving blob streamed: {} bytes", metadata.size);
+
+    let content_type = metadata
+        .content_type
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    headers.insert("Content-Type", HeaderValue::from_str(&content_type)?);
+    headers.insert("Content-Length", HeaderValue::from(metadata.size));
+
+    let reader: Box<dyn tokio::io::AsyncRead + Send + Unpin> = match verify_digest {
+        Some(expected) => Box::new(VerifyingReader::new(reader, expected)),
+        None => reader,
+    };
+    let body = axum::body::Body::from_stream(tokio_util::io::ReaderStream::new(reader));
+    Ok(Some((StatusCode::OK, headers, body).into_response()))
 }
 
 async fn get_blob_impl(
     state: &AppState,
     name: &str,
     digest: &str,
-) -> impl IntoResponse {
+) -> Response {
     println!("Getting blob for {}/{}", name, digest);
+
+    let digest = match validated_digest(digest) {
+        Ok(digest) => digest,
+        Err(response) => return response,
+    };
     
     // Try to get blob from S3 storage first
-    let blob_key = format!("blobs/{}", digest);
-    match state.storage.get_blob(&blob_key).await {
-        Ok(Some(data)) => {
-            println!("Found blob in S3: {} bytes", data.len());
-            
-            // Detect content type and set download headers
-            let content_type = detect_content_type(&data, digest);
-            let filename = format!("{}.bin", digest.replace("sha256:", ""));
-            
-            let mut headers = HeaderMap::new();
-            headers.insert("Content-Type", HeaderValue::from_str(&content_type).unwrap());
-            headers.insert("Docker-Content-Digest", HeaderValue::from_str(digest).unwrap());
-            headers.insert("Content-Length", HeaderValue::from_str(&data.len().to_string()).unwrap());
-            
-            // Add download headers for file download
-            headers.insert("Content-Disposition", 
-                HeaderValue::from_str(&format!("attachment; filename=\"{}\"", filename)).unwrap());
-            headers.insert("Cache-Control", HeaderValue::from_static("public, max-age=31536000"));
-            
-            return (StatusCode::OK, headers, data.to_vec());
-        },
+    let blob_key = digest.blob_key();
+    match blob_response(
+        state.storage.as_ref(),
+        &blob_key,
+        digest.as_str(),
+        state.config.registry.blob_stream_threshold_bytes,
+        state.config.registry.verify_blob_on_read,
+    ).await {
+        Ok(Some(response)) => return response,
         Ok(None) => {
             println!("Blob not found in S3: {}", digest);
             // Fall through to hardcoded blobs
@@ -2086,16 +4881,16 @@ async fn get_blob_impl(
     }
     
     // Handle specific Alpine blobs (fallback for demo)
-    match digest {
+    match digest.as_str() {
         // Alpine config blob
         "sha256:9234e8fb04c47cfe0f49931e4ac7eb76fa904e33b7f8576aec0501c085f02516" => {
             let config_json = r#"{"architecture":"amd64","config":{"Hostname":"","Domainname":"","User":"","AttachStdin":false,"AttachStdout":false,"AttachStderr":false,"Tty":false,"OpenStdin":false,"StdinOnce":false,"Env":["PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin"],"Cmd":["/bin/sh"],"Image":"","Volumes":null,"WorkingDir":"","Entrypoint":null,"OnBuild":null,"Labels":null},"created":"2024-01-27T00:00:00Z","history":[{"created":"2024-01-27T00:00:00Z","created_by":"ADD file:29f1d1b7e6e4c6c9a6e3b5c8b6c7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f3a4b /"}],"os":"linux","rootfs":{"type":"layers","diff_ids":["sha256:4bcff63911fcb4448bd4fdacec207030997caf25e9bea4045fa6c8c44de311d1"]}}"#;
             let mut headers = HeaderMap::new();
             headers.insert("Content-Type", HeaderValue::from_static("application/json"));
-            headers.insert("Docker-Content-Digest", HeaderValue::from_str(digest).unwrap());
+            set_content_digest(&mut headers, digest.as_str());
             headers.insert("Content-Length", HeaderValue::from_str(&config_json.len().to_string()).unwrap());
             headers.insert("Content-Disposition", HeaderValue::from_static("attachment; filename=\"alpine-config.json\""));
-            return (StatusCode::OK, headers, config_json.as_bytes().to_vec());
+            return (StatusCode::OK, headers, config_json.as_bytes().to_vec()).into_response();
         },
         
         // Alpine layer blob
@@ -2105,16 +4900,16 @@ async fn get_blob_impl(
             
             let mut headers = HeaderMap::new();
             headers.insert("Content-Type", HeaderValue::from_static("application/gzip"));
-            headers.insert("Docker-Content-Digest", HeaderValue::from_str(digest).unwrap());
+            set_content_digest(&mut headers, digest.as_str());
             headers.insert("Content-Length", HeaderValue::from_str(&empty_tar_gz.len().to_string()).unwrap());
             headers.insert("Content-Disposition", HeaderValue::from_static("attachment; filename=\"alpine-layer.tar.gz\""));
             
-            return (StatusCode::OK, headers, empty_tar_gz);
+            return (StatusCode::OK, headers, empty_tar_gz).into_response();
         },
         
         _ => {
             println!("Unknown blob digest: {}", digest);
-            return (StatusCode::NOT_FOUND, HeaderMap::new(), Vec::new());
+            return (StatusCode::NOT_FOUND, HeaderMap::new(), Vec::new()).into_response();
         }
     }
 }
@@ -2160,19 +4955,34 @@ fn create_minimal_tar_gz() -> Vec<u8> {
 }
 
 async fn head_blob_impl(
-    _state: &AppState,
+    state: &AppState,
     name: &str,
     digest: &str,
-) -> impl IntoResponse {
-    // TODO: Implement actual blob existence check
+) -> Response {
     println!("Checking blob existence for {}/{}", name, digest);
-    
-    let mut headers = HeaderMap::new();
-    headers.insert("Content-Type", HeaderValue::from_static("application/octet-stream"));
-    headers.insert("Docker-Content-Digest", HeaderValue::from_str(digest).unwrap());
-    headers.insert("Content-Length", HeaderValue::from_static("1234"));
-    
-    (StatusCode::OK, headers)
+
+    let digest = match validated_digest(digest) {
+        Ok(digest) => digest,
+        Err(response) => return response,
+    };
+    let blob_key = digest.blob_key();
+    match state.storage.get_blob_metadata(&blob_key).await {
+        Ok(Some(metadata)) => {
+            let content_type = metadata
+                .content_type
+                .unwrap_or_else(|| "application/octet-stream".to_string());
+            let mut headers = HeaderMap::new();
+            if let Ok(value) = HeaderValue::from_str(&content_type) {
+                headers.insert("Content-Type", value);
+            }
+            set_content_digest(&mut headers, digest.as_str());
+            headers.insert("Content-Length", HeaderValue::from(metadata.size));
+
+            (StatusCode::OK, headers).into_response()
+        }
+        // Not in storage: answer as GET would, which also covers the built-in demo blobs
+        _ => without_body(get_blob_impl(state, name, digest.as_str()).await),
+    }
 }
 
 async fn start_blob_upload_impl(
@@ -2215,35 +5025,30 @@ async fn start_blob_upload_impl(
         println!("  👤 User ID: {}", user.user_id);
         println!("  📄 Upload UUID: {}", upload_uuid);
         println!("  🔗 Location: {}", location);
-        
-        // Save to database
-        if let Err(e) = crate::database::queries::create_blob_upload(
-            &state.db_pool,
-            &upload_uuid,
-            repository_id,
-            Some(&user.user_id.to_string()),
-        ).await {
-            eprintln!("❌ Failed to save blob upload to database: {}", e);
-        } else {
-            println!("✅ Blob upload saved to database successfully");
-        }
     } else {
         println!("🔍 Anonymous upload:");
         println!("  📁 Repository: {}", name);
         println!("  📄 Upload UUID: {}", upload_uuid);
-        
-        // Save anonymous upload to database
-        if let Err(e) = crate::database::queries::create_blob_upload(
-            &state.db_pool,
-            &upload_uuid,
-            repository_id,
-            None, // No user ID for anonymous uploads
-        ).await {
-            eprintln!("❌ Failed to save anonymous blob upload to database: {}", e);
-        } else {
-            println!("✅ Anonymous blob upload saved to database successfully");
-        }
     }
+
+    // The session is persisted so any instance can continue the upload
+    let user_id = user_info.as_ref().map(|user| user.user_id.to_string());
+    let session = UploadSession::new(&upload_uuid, name);
+    let saved = async {
+        crate::database::queries::create_blob_upload(&state.db_pool, &upload_uuid, repository_id, user_id.as_deref()).await?;
+        crate::database::queries::save_upload_session(&state.db_pool, &session).await
+    }
+    .await;
+    if let Err(e) = saved {
+        eprintln!("❌ Failed to save blob upload to database: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": "Database error"
+            }))
+        ).into_response();
+    }
+    println!("✅ Blob upload saved to database successfully");
     
     let mut headers = HeaderMap::new();
     headers.insert("Location", HeaderValue::from_str(&location).unwrap());
@@ -2265,17 +5070,17 @@ async fn start_blob_upload_impl(
 // For simple names like "hello-world", use username as namespace
 // For namespaced names like "myorg/hello-world", use explicit namespace
 async fn parse_repository_name(name: &str, user_id: &str, state: &AppState) -> Result<(String, String), String> {
-    let parts: Vec<&str> = name.split('/').collect();
-    
-    match parts.len() {
-        1 => {
+    let repository_name = RepositoryName::parse(name).map_err(|e| e.to_string())?;
+
+    match repository_name.split_namespace() {
+        None => {
             // Simple name like "hello-world" - use username as namespace
             let user_id_int: i64 = user_id.parse().map_err(|_| "Invalid user ID".to_string())?;
             
             // Fetch username from database
             match crate::database::queries::get_user_by_id(&state.db_pool, user_id_int).await {
                 Ok(Some(user)) => {
-                    Ok((user.username, parts[0].to_string()))
+                    Ok((user.username, repository_name.as_str().to_string()))
                 }
                 Ok(None) => {
                     Err("User not found".to_string())
@@ -2285,11 +5090,11 @@ async fn parse_repository_name(name: &str, user_id: &str, state: &AppState) -> R
                 }
             }
         }
-        2 => {
+        Some((namespace, repository)) if !repository.contains('/') => {
             // Namespaced name like "myorg/hello-world"
-            Ok((parts[0].to_string(), parts[1].to_string()))
+            Ok((namespace.to_string(), repository.to_string()))
         }
-        _ => {
+        Some(_) => {
             Err("Invalid repository name format".to_string())
         }
     }
@@ -2372,183 +5177,381 @@ fn parse_basic_auth(token: &str) -> Option<UserInfo> {
 }
 
 async fn get_upload_status_impl(
-    _state: &AppState,
+    state: &AppState,
     name: &str,
     uuid: &str,
-) -> impl IntoResponse {
-    // TODO: Implement actual upload status check
+) -> Response {
     println!("Getting upload status for {}/{}", name, uuid);
-    
+
+    let session = match load_upload_session(state, uuid).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    (StatusCode::NO_CONTENT, upload_progress_headers(name, uuid, session.offset())).into_response()
+}
+
+/// Load a persisted upload session, or the OCI error response for it
+async fn load_upload_session(state: &AppState, uuid: &str) -> Result<UploadSession, Response> {
+    upload_session_or_error(uuid, crate::database::queries::get_upload_session(&state.db_pool, uuid).await)
+}
+
+fn upload_session_or_error(uuid: &str, loaded: anyhow::Result<Option<UploadSession>>) -> Result<UploadSession, Response> {
+    match loaded {
+        Ok(Some(session)) => Ok(session),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "errors": [{
+                    "code": "BLOB_UPLOAD_UNKNOWN",
+                    "message": "blob upload unknown to registry",
+                    "detail": { "uuid": uuid }
+                }]
+            }))
+        ).into_response()),
+        Err(e) => {
+            tracing::error!(upload = %uuid, "Failed to load upload session: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new()).into_response())
+        }
+    }
+}
+
+/// Location, Range and Docker-Upload-UUID for an upload at `offset` bytes
+fn upload_progress_headers(name: &str, uuid: &str, offset: u64) -> HeaderMap {
     let location = format!("/v2/{}/blobs/uploads/{}", name, uuid);
-    
     let mut headers = HeaderMap::new();
-    headers.insert("Location", HeaderValue::from_str(&location).unwrap());
-    headers.insert("Range", HeaderValue::from_static("0-1023"));
-    headers.insert("Docker-Upload-UUID", HeaderValue::from_str(uuid).unwrap());
-    
-    (StatusCode::NO_CONTENT, headers)
+    if let Ok(value) = HeaderValue::from_str(&location) {
+        headers.insert("Location", value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&format!("0-{}", offset.saturating_sub(1))) {
+        headers.insert("Range", value);
+    }
+    if let Ok(value) = HeaderValue::from_str(uuid) {
+        headers.insert("Docker-Upload-UUID", value);
+    }
+    headers
 }
 
-async fn upload_blob_chunk_impl(
-    state: &AppState,
+/// Reasons a PATCH chunk can be rejected before it is stored
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChunkValidationError {
+    /// Content-Range cannot be parsed (400)
+    MalformedRange(ContentRangeError),
+    /// Content-Range does not match the body length (416)
+    InvalidRange,
+    /// Chunk does not start at the session's current offset (416)
+    NonContiguous { expected_offset: u64 },
+    /// Non-final chunk is smaller than MIN_UPLOAD_CHUNK_BYTES (416)
+    ChunkTooSmall { min: u64 },
+    /// Chunk is larger than MAX_UPLOAD_CHUNK_BYTES (413)
+    ChunkTooLarge { max: u64 },
+}
+
+impl ChunkValidationError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ChunkValidationError::MalformedRange(_) => StatusCode::BAD_REQUEST,
+            ChunkValidationError::ChunkTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            _ => StatusCode::RANGE_NOT_SATISFIABLE,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ChunkValidationError::MalformedRange(e) => e.to_string(),
+            ChunkValidationError::InvalidRange => "Content-Range does not match the chunk length".to_string(),
+            ChunkValidationError::NonContiguous { expected_offset } => {
+                format!("Chunk must start at offset {}", expected_offset)
+            }
+            ChunkValidationError::ChunkTooSmall { min } => {
+                format!("Chunk is smaller than the minimum of {} bytes", min)
+            }
+            ChunkValidationError::ChunkTooLarge { max } => {
+                format!("Chunk is larger than the maximum of {} bytes", max)
+            }
+        }
+    }
+}
+
+/// Validate a PATCH chunk against the upload session's current offset.
+///
+/// Chunks sent with a Content-Range must be contiguous and at least `min_bytes`.
+/// A request without Content-Range is a streamed (single chunk) upload and the
+/// final chunk may be sent with the closing PUT, so neither is held to the minimum.
+pub fn validate_upload_chunk(
+    current_offset: u64,
+    content_range: Option<&str>,
+    chunk_len: u64,
+    min_bytes: u64,
+    max_bytes: u64,
+) -> Result<(), ChunkValidationError> {
+    if chunk_len > max_bytes {
+        return Err(ChunkValidationError::ChunkTooLarge { max: max_bytes });
+    }
+
+    let range = match content_range {
+        Some(range) => range,
+        None => return Ok(()),
+    };
+
+    let range = parse_content_range(range).map_err(ChunkValidationError::MalformedRange)?;
+    if range.byte_count() != chunk_len {
+        return Err(ChunkValidationError::InvalidRange);
+    }
+
+    if range.start != current_offset {
+        return Err(ChunkValidationError::NonContiguous { expected_offset: current_offset });
+    }
+
+    if chunk_len < min_bytes {
+        return Err(ChunkValidationError::ChunkTooSmall { min: min_bytes });
+    }
+
+    Ok(())
+}
+
+/// A chunk of a blob upload, sent with PATCH or with the closing PUT
+pub struct UploadChunk<'a> {
+    /// The request's Content-Range, if it had one
+    pub content_range: Option<&'a str>,
+    pub body: axum::body::Bytes,
+}
+
+impl<'a> UploadChunk<'a> {
+    /// The chunk a request carries. A Content-Range that is not visible
+    /// ASCII is rejected as malformed, not ignored.
+    fn from_request(headers: &'a HeaderMap, body: axum::body::Bytes) -> Self {
+        let content_range = headers.get("content-range").map(|v| v.to_str().unwrap_or_default());
+        Self { content_range, body }
+    }
+}
+
+/// Answer a chunk that failed validation, pointing the client at the offset
+/// the upload is at
+fn rejected_chunk(name: &str, uuid: &str, current_offset: u64, err: ChunkValidationError) -> Response {
+    tracing::warn!(repository = %name, upload = %uuid, offset = current_offset, "Rejected chunk: {}", err.message());
+
+    let code = match err {
+        ChunkValidationError::ChunkTooLarge { .. } => "SIZE_INVALID",
+        _ => "BLOB_UPLOAD_INVALID",
+    };
+
+    (
+        err.status_code(),
+        upload_progress_headers(name, uuid, current_offset),
+        Json(serde_json::json!({
+            "errors": [{
+                "code": code,
+                "message": err.message(),
+                "detail": {}
+            }]
+        }))
+    ).into_response()
+}
+
+/// Lock the session of upload `uuid` until the returned transaction ends, so
+/// chunks and the completion of one upload are applied one at a time, each
+/// seeing the offset the previous one left
+async fn locked_upload_session(
+    pool: &sqlx::PgPool,
+    uuid: &str,
+) -> Result<(sqlx::Transaction<'static, sqlx::Postgres>, UploadSession), Response> {
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            tracing::error!(upload = %uuid, "Failed to start upload transaction: {}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new()).into_response());
+        }
+    };
+    let locked = crate::database::queries::lock_upload_session(&mut tx, uuid).await;
+    let session = upload_session_or_error(uuid, locked)?;
+    Ok((tx, session))
+}
+
+/// Add a PATCH chunk to upload `uuid` in repository `name`, holding it to
+/// `min_bytes`..=`max_bytes` and the session's current offset
+pub async fn append_upload_chunk(
+    pool: &sqlx::PgPool,
+    storage: &dyn crate::storage::Storage,
     name: &str,
     uuid: &str,
-    headers: HeaderMap,
-    body: axum::body::Bytes,
-) -> impl IntoResponse {
-    println!("Uploading blob chunk for {}/{}", name, uuid);
-    println!("Content-Range: {:?}", headers.get("content-range"));
-    println!("Chunk size: {}", body.len());
-    
-    // Store chunk data in temporary storage keyed by upload UUID
-    let temp_key = format!("uploads/{}/{}", name, uuid);
-    let body_len = body.len();
-    
-    match state.storage.put_blob(&temp_key, body).await {
+    chunk: UploadChunk<'_>,
+    min_bytes: u64,
+    max_bytes: u64,
+) -> Response {
+    // Data already received for this session determines the expected offset.
+    // The session stays locked until the chunk is stored, so a concurrent
+    // chunk for the same range sees the advanced offset and is rejected.
+    let (mut tx, mut session) = match locked_upload_session(pool, uuid).await {
+        Ok(locked) => locked,
+        Err(response) => return response,
+    };
+    let current_offset = session.offset();
+
+    if let Err(err) = validate_upload_chunk(current_offset, chunk.content_range, chunk.body.len() as u64, min_bytes, max_bytes) {
+        return rejected_chunk(name, uuid, current_offset, err);
+    }
+
+    // Append the chunk to what has been received so far; the offset moves
+    // to the end of the range plus one
+    let appended = async {
+        session.append(storage, chunk.body, MIN_PART_BYTES).await?;
+        crate::database::queries::save_upload_session(&mut *tx, &session).await?;
+        tx.commit().await.map_err(anyhow::Error::from)
+    }
+    .await;
+
+    match appended {
         Ok(_) => {
-            println!("Blob chunk stored successfully");
-            
-            let location = format!("/v2/{}/blobs/uploads/{}", name, uuid);
-            let range = format!("0-{}", body_len - 1);
-            
-            let mut response_headers = HeaderMap::new();
-            response_headers.insert("Location", HeaderValue::from_str(&location).unwrap());
-            response_headers.insert("Range", HeaderValue::from_str(&range).unwrap());
+            tracing::debug!(repository = %name, upload = %uuid, offset = session.offset(), "Blob chunk stored");
+
+            let mut response_headers = upload_progress_headers(name, uuid, session.offset());
             response_headers.insert("Content-Length", HeaderValue::from_static("0"));
-            response_headers.insert("Docker-Upload-UUID", HeaderValue::from_str(uuid).unwrap());
-            
-            (StatusCode::ACCEPTED, response_headers)
+
+            (StatusCode::ACCEPTED, response_headers).into_response()
         },
         Err(e) => {
-            eprintln!("Failed to store blob chunk: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new())
+            tracing::error!(repository = %name, upload = %uuid, "Failed to store blob chunk: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new()).into_response()
         }
     }
 }
 
-async fn complete_blob_upload_impl(
-    state: &AppState,
+/// Complete upload `uuid` in repository `name` with its final chunk, if
+/// any, and store the assembled blob under `digest` where `router` sends it.
+/// The session stays locked throughout, so a PATCH racing the completion
+/// either lands before the final chunk or finds the upload finished.
+pub async fn finish_blob_upload(
+    pool: &sqlx::PgPool,
+    router: &crate::storage::router::StorageRouter,
     name: &str,
     uuid: &str,
-    params: HashMap<String, String>,
-    body: axum::body::Bytes,
-) -> impl IntoResponse {
-    println!("Completing blob upload for {}/{}", name, uuid);
-    
-    let digest = params.get("digest").unwrap_or(&"sha256:unknown".to_string()).clone();
-    println!("Expected digest: {}", digest);
-    println!("Final chunk size: {}", body.len());
-    
+    digest: &Digest,
+    chunk: UploadChunk<'_>,
+    max_bytes: u64,
+) -> Response {
+    let (mut tx, mut session) = match locked_upload_session(pool, uuid).await {
+        Ok(locked) => locked,
+        Err(response) => return response,
+    };
+    let current_offset = session.offset();
+
+    // The final chunk is not held to the minimum chunk size
+    if let Err(err) = validate_upload_chunk(current_offset, chunk.content_range, chunk.body.len() as u64, 0, max_bytes) {
+        return rejected_chunk(name, uuid, current_offset, err);
+    }
+
     // Final blob key in S3
-    let blob_key = format!("blobs/{}", digest);
-    
-    // If there's a final chunk, append it to the existing data
-    if !body.is_empty() {
-        let temp_key = format!("uploads/{}/{}", name, uuid);
-        
-        // Get existing data from temp storage
-        let existing_data = match state.storage.get_blob(&temp_key).await {
-            Ok(Some(data)) => data,
-            Ok(None) | Err(_) => body.clone(), // If no existing data, use just this chunk
-        };
-        
-        // Combine existing data with final chunk
-        let mut final_data = existing_data.to_vec();
-        final_data.extend_from_slice(&body);
-        let final_size = final_data.len() as i64;
-        
-        // Store final blob in S3 with digest as key
-        match state.storage.put_blob(&blob_key, axum::body::Bytes::from(final_data)).await {
-            Ok(_) => {
-                println!("Blob stored successfully in S3 with key: {}", blob_key);
-                
-                // Clean up temporary upload
-                let _ = state.storage.delete_blob(&temp_key).await;
-                
-                // Update blob upload status in database
-                if let Err(e) = crate::database::queries::update_blob_upload_completed(
-                    &state.db_pool,
-                    uuid,
-                ).await {
-                    eprintln!("❌ Failed to update blob upload completion in database: {}", e);
-                } else {
-                    println!("✅ Blob upload completion updated in database");
-                }
-                
-                let location = format!("/v2/{}/blobs/{}", name, digest);
-                let mut headers = HeaderMap::new();
-                headers.insert("Location", HeaderValue::from_str(&location).unwrap());
-                headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
-                headers.insert("Content-Length", HeaderValue::from_static("0"));
-                
-                (StatusCode::CREATED, headers)
-            },
-            Err(e) => {
-                eprintln!("Failed to store final blob: {}", e);
-                // Update database with failed status - just log error for now
-                eprintln!("⚠️  Blob upload failed for UUID: {}", uuid);
-                (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new())
-            }
+    let blob_key = digest.blob_key();
+
+    // Append the final chunk, if any, then assemble the blob
+    let stored = async {
+        if !chunk.body.is_empty() {
+            session.append(router, chunk.body, MIN_PART_BYTES).await?;
         }
-    } else {
-        // No final chunk, just move temp data to final location
-        let temp_key = format!("uploads/{}/{}", name, uuid);
-        
-        match state.storage.get_blob(&temp_key).await {
-            Ok(Some(data)) => {
-                let blob_size = data.len() as i64;
-                match state.storage.put_blob(&blob_key, data).await {
-                    Ok(_) => {
-                        println!("Blob stored successfully in S3 with key: {}", blob_key);
-                        
-                        // Clean up temporary upload
-                        let _ = state.storage.delete_blob(&temp_key).await;
-                        
-                        // Update blob upload status in database
-                        if let Err(e) = crate::database::queries::update_blob_upload_completed(
-                            &state.db_pool,
-                            uuid,
-                        ).await {
-                            eprintln!("❌ Failed to update blob upload completion in database: {}", e);
-                        } else {
-                            println!("✅ Blob upload completion updated in database");
-                        }
-                        
-                        let location = format!("/v2/{}/blobs/{}", name, digest);
-                        let mut headers = HeaderMap::new();
-                        headers.insert("Location", HeaderValue::from_str(&location).unwrap());
-                        headers.insert("Docker-Content-Digest", HeaderValue::from_str(&digest).unwrap());
-                        headers.insert("Content-Length", HeaderValue::from_static("0"));
-                        
-                        (StatusCode::CREATED, headers)
-                    },
-                    Err(e) => {
-                        eprintln!("Failed to store final blob: {}", e);
-                        // Update database with failed status - just log error for now
-                        eprintln!("⚠️  Blob upload failed for UUID: {}", uuid);
-                        (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new())
-                    }
-                }
-            },
-            Ok(None) => {
-                eprintln!("No temp blob data found for upload: {}", uuid);
-                (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new())
-            },
-            Err(e) => {
-                eprintln!("Failed to retrieve temp blob data: {}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new())
-            }
+        let size = session.finish(router, &blob_key).await?;
+        // Uploads are staged in the default backend; move the blob if routed elsewhere
+        router.relocate(&blob_key, &StoredContent::blob(size)).await?;
+        crate::database::queries::update_blob_upload_completed(&mut *tx, uuid).await?;
+        tx.commit().await?;
+        Ok::<_, anyhow::Error>(size)
+    }
+    .await;
+
+    match stored {
+        Ok(size) => {
+            tracing::debug!(repository = %name, upload = %uuid, %digest, key = %blob_key, size, "Blob upload completed");
+
+            let location = format!("/v2/{}/blobs/{}", name, digest);
+            let mut headers = HeaderMap::new();
+            headers.insert("Location", HeaderValue::from_str(&location).unwrap());
+            set_content_digest(&mut headers, digest.as_str());
+            headers.insert("Content-Length", HeaderValue::from_static("0"));
+
+            (StatusCode::CREATED, headers).into_response()
+        },
+        Err(e) => {
+            tracing::error!(repository = %name, upload = %uuid, %digest, "Failed to store final blob: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new()).into_response()
         }
     }
 }
 
+async fn upload_blob_chunk_impl(
+    state: &AppState,
+    name: &str,
+    uuid: &str,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Response {
+    tracing::debug!(
+        repository = %name,
+        upload = %uuid,
+        content_range = ?headers.get("content-range"),
+        chunk_bytes = body.len(),
+        "Uploading blob chunk"
+    );
+
+    append_upload_chunk(
+        &state.db_pool,
+        state.storage.as_ref(),
+        name,
+        uuid,
+        UploadChunk::from_request(&headers, body),
+        state.config.registry.min_upload_chunk_bytes,
+        state.config.registry.max_upload_chunk_bytes,
+    )
+    .await
+}
+
+async fn complete_blob_upload_impl(
+    state: &AppState,
+    name: &str,
+    uuid: &str,
+    params: HashMap<String, String>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Response {
+    let digest = match params.get("digest") {
+        Some(digest) => match validated_digest(digest) {
+            Ok(digest) => digest,
+            Err(response) => return response,
+        },
+        None => return registry_error(StatusCode::BAD_REQUEST, "DIGEST_INVALID", "digest query parameter is required"),
+    };
+    tracing::debug!(repository = %name, upload = %uuid, %digest, final_chunk_bytes = body.len(), "Completing blob upload");
+
+    finish_blob_upload(
+        &state.db_pool,
+        &state.storage_router,
+        name,
+        uuid,
+        &digest,
+        UploadChunk::from_request(&headers, body),
+        state.config.registry.max_upload_chunk_bytes,
+    )
+    .await
+}
+
 async fn cancel_blob_upload_impl(
-    _state: &AppState,
+    state: &AppState,
     name: &str,
     uuid: &str,
-) -> impl IntoResponse {
-    // TODO: Implement actual upload cancellation
+) -> Response {
     println!("Cancelling blob upload for {}/{}", name, uuid);
-    
-    StatusCode::NO_CONTENT
-}
\ No newline at end of file
+
+    let session = match load_upload_session(state, uuid).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    if let Err(e) = session.abort(state.storage.as_ref()).await {
+        eprintln!("⚠️ Failed to discard data of upload {}: {}", uuid, e);
+    }
+    if let Err(e) = crate::database::queries::delete_upload_session(&state.db_pool, uuid).await {
+        eprintln!("❌ Failed to delete upload session {}: {}", uuid, e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new()).into_response();
+    }
+
+    StatusCode::NO_CONTENT.into_response()
+}
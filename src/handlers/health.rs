@@ -0,0 +1,161 @@
+// Dependency health report for operators
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::auth::is_admin_request;
+use crate::db::Timed;
+use crate::handlers::admin::admin_token_required;
+use crate::shutdown::Readiness;
+use crate::AppState;
+
+/// A dependency that has not answered within this time is reported down
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    Up,
+    Down,
+    /// Not configured, so not probed
+    Disabled,
+}
+
+/// Outcome of probing one dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyReport {
+    pub name: &'static str,
+    pub status: DependencyStatus,
+    /// The registry cannot serve requests while a critical dependency is down
+    pub critical: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DependencyReport {
+    pub fn disabled(name: &'static str, critical: bool) -> Self {
+        Self { name, status: DependencyStatus::Disabled, critical, latency_ms: 0, error: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverallStatus {
+    Healthy,
+    /// A non-critical dependency is down; requests are still served
+    Degraded,
+    /// A critical dependency is down
+    Down,
+}
+
+impl OverallStatus {
+    /// Degraded stays 200 so load balancers keep routing to the instance
+    pub fn status_code(self) -> StatusCode {
+        match self {
+            OverallStatus::Healthy | OverallStatus::Degraded => StatusCode::OK,
+            OverallStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: OverallStatus,
+    pub dependencies: Vec<DependencyReport>,
+}
+
+impl HealthReport {
+    pub fn new(dependencies: Vec<DependencyReport>) -> Self {
+        let down = |critical: bool| {
+            dependencies
+                .iter()
+                .any(|d| d.status == DependencyStatus::Down && d.critical == critical)
+        };
+        let status = if down(true) {
+            OverallStatus::Down
+        } else if down(false) {
+            OverallStatus::Degraded
+        } else {
+            OverallStatus::Healthy
+        };
+        Self { status, dependencies }
+    }
+}
+
+impl IntoResponse for HealthReport {
+    fn into_response(self) -> Response {
+        (self.status.status_code(), Json(self)).into_response()
+    }
+}
+
+/// Time `check`, reporting the dependency down if it fails or exceeds `PROBE_TIMEOUT`
+pub async fn probe<F>(name: &'static str, critical: bool, check: F) -> DependencyReport
+where
+    F: Future<Output = anyhow::Result<()>>,
+{
+    let started = Instant::now();
+    let result = tokio::time::timeout(PROBE_TIMEOUT, check).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("No answer within {}s", PROBE_TIMEOUT.as_secs())),
+    };
+    if let Some(error) = &error {
+        tracing::warn!(dependency = name, latency_ms, "Health probe failed: {}", error);
+    }
+
+    DependencyReport {
+        name,
+        status: if error.is_some() { DependencyStatus::Down } else { DependencyStatus::Up },
+        critical,
+        latency_ms,
+        error,
+    }
+}
+
+/// Probe the database, Redis and storage concurrently
+pub async fn check_dependencies(state: &AppState) -> HealthReport {
+    let database = probe("database", true, async {
+        sqlx::query("SELECT 1").execute(&state.db_pool).timed("check_dependencies").await?;
+        Ok(())
+    });
+    let redis = async {
+        match &state.cache {
+            Some(cache) => probe("redis", false, cache.health_check()).await,
+            None => DependencyReport::disabled("redis", false),
+        }
+    };
+    let storage = probe("storage", true, state.storage.health_check());
+
+    let (database, redis, storage) = tokio::join!(database, redis, storage);
+    HealthReport::new(vec![database, redis, storage])
+}
+
+/// Dependency health - GET /health/dependencies (admin only)
+pub async fn dependency_health(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if !is_admin_request(&headers, &state.config.auth) {
+        return admin_token_required();
+    }
+
+    check_dependencies(&state).await.into_response()
+}
+
+/// Readiness - GET /health/ready
+/// 503 once shutdown has started, so load balancers stop routing here
+pub async fn readiness(State(readiness): State<Readiness>) -> Response {
+    if readiness.is_ready() {
+        (StatusCode::OK, Json(serde_json::json!({ "status": "ready" }))).into_response()
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({ "status": "draining" }))).into_response()
+    }
+}
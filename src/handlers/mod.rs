@@ -1,8 +1,14 @@
 // Handlers module
+pub mod admin;
 pub mod auth;
 pub mod docker_auth;
 pub mod docker_registry_v2;
+pub mod health;
 // pub mod docker_registry_v2_optimized; // Already merged into docker_registry_v2.rs
 pub mod organizations;
+pub mod profiling;
 pub mod repositories;
 pub mod storage;
+pub mod tag_expiry;
+pub mod audit;
+pub mod analytics;
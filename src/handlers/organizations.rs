@@ -1,7 +1,7 @@
 // src/handlers/organizations.rs - Fixed version with API key support
 use anyhow::{bail, Context, Result};
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{StatusCode, HeaderMap},
     response::IntoResponse,
     Json,
@@ -12,14 +12,22 @@ use utoipa::ToSchema;
 
 use axum_extra::headers::{Authorization, authorization::Bearer};
 use axum_extra::TypedHeader;
-use secrecy::ExposeSecret;
 use crate::auth::{extract_user_id_dual, extract_user_id};
+use crate::db::Timed;
+use crate::error::{error_response, AppError};
+use crate::handlers::audit::record_audit_event;
+use crate::nonce::{check_nonce, NonceAction};
+use crate::tenant::{TenancyMode, TenantContext};
+use crate::utils::fields::{project, FieldsQuery};
+use crate::utils::pagination::{paginate, PageQuery};
 
 use crate::{
     models::organizations::{
-        AddMemberRequest, CreateOrganizationRequest, Organization, OrganizationMember,
-        OrganizationRole, UpdateMemberRequest, UpdateOrganizationRequest,
+        check_member_version, is_reserved_org_name, resolve_member_role, seats_remaining, AddMemberRequest,
+        CreateOrganizationRequest, MemberRoleResponse, Organization, OrganizationAction, OrganizationMember,
+        OrganizationRole, RenameOrganizationRequest, UpdateMemberRequest, UpdateOrganizationRequest,
     },
+    models::user::normalize_email,
     AppState,
 };
 
@@ -32,6 +40,7 @@ use crate::{
     responses(
         (status = 201, description = "Organization created successfully"),
         (status = 400, description = "Validation failed or bad request"),
+        (status = 409, description = "Organization name already exists"),
         (status = 500, description = "Internal server error")
     ),
     security(
@@ -42,7 +51,7 @@ pub async fn create_organization(
     State(state): State<AppState>,
     headers: HeaderMap,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
-    Json(req): Json<CreateOrganizationRequest>,
+    Json(mut req): Json<CreateOrganizationRequest>,
 ) -> impl IntoResponse {
     // Validate request
     if let Err(validation_errors) = req.validate() {
@@ -54,14 +63,23 @@ pub async fn create_organization(
             })),
         );
     }
+    if state.config.registry.sanitize_org_profiles {
+        if let Err(message) = req.sanitize() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": message
+                })),
+            );
+        }
+    }
 
     // Extract user ID from JWT or API key
-    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
     
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
-        secret, 
+        &state.config.auth, 
         &state.db_pool, 
         state.cache.as_ref()
     ).await {
@@ -76,7 +94,7 @@ pub async fn create_organization(
         }
     };
 
-    match create_org_internal(&state.db_pool, req, user_id).await {
+    match create_org_internal(&state.db_pool, req, user_id, state.config.database.tenancy_mode).await {
         Ok(organization) => (
             StatusCode::CREATED,
             Json(serde_json::json!({
@@ -85,12 +103,7 @@ pub async fn create_organization(
         ),
         Err(e) => {
             tracing::error!("Failed to create organization: {}", e);
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "error": e.to_string()
-                })),
-            )
+            error_response(&e, StatusCode::BAD_REQUEST)
         }
     }
 }
@@ -101,10 +114,12 @@ pub async fn create_organization(
     path = "/api/v1/organizations/{id}",
     tag = "organizations",
     params(
-        ("id" = i64, Path, description = "Organization ID")
+        ("id" = i64, Path, description = "Organization ID"),
+        ("fields" = Option<String>, Query, description = "Comma separated organization fields to return, e.g. id,name")
     ),
     responses(
         (status = 200, description = "Organization details retrieved successfully"),
+        (status = 400, description = "Unknown field selected while STRICT_FIELD_SELECTION is enabled"),
         (status = 404, description = "Organization not found"),
         (status = 500, description = "Internal server error")
     ),
@@ -115,14 +130,23 @@ pub async fn create_organization(
 pub async fn get_organization(
     State(state): State<AppState>,
     Path(id): Path<i64>,
+    Query(fields): Query<FieldsQuery>,
 ) -> impl IntoResponse {
     match get_org_by_id_internal(&state.db_pool, id).await {
-        Ok(Some(organization)) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "organization": organization
-            })),
-        ),
+        Ok(Some(organization)) => match project(&organization, &fields, state.config.server.strict_field_selection) {
+            Ok(organization) => (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "organization": organization
+                })),
+            ),
+            Err(e) => (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": e.to_string()
+                })),
+            ),
+        },
         Ok(None) => (
             StatusCode::NOT_FOUND,
             Json(serde_json::json!({
@@ -166,7 +190,7 @@ pub async fn update_organization(
     headers: HeaderMap,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     Path(id): Path<i64>,
-    Json(req): Json<UpdateOrganizationRequest>,
+    Json(mut req): Json<UpdateOrganizationRequest>,
 ) -> impl IntoResponse {
     if let Err(validation_errors) = req.validate() {
         return (
@@ -177,14 +201,23 @@ pub async fn update_organization(
             })),
         );
     }
+    if state.config.registry.sanitize_org_profiles {
+        if let Err(message) = req.sanitize() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": message
+                })),
+            );
+        }
+    }
 
     // Extract user ID from JWT or API key
-    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
     
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
-        secret, 
+        &state.config.auth, 
         &state.db_pool, 
         state.cache.as_ref()
     ).await {
@@ -199,22 +232,32 @@ pub async fn update_organization(
         }
     };
 
+    let policy_changed = req.block_vulnerable_pulls.is_some();
     match update_org_by_id_internal(&state.db_pool, id, req, user_id).await {
-        Ok(organization) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "organization": organization
-            })),
-        ),
-        Err(e) => {
-            tracing::error!("Failed to update organization: {}", e);
+        Ok(organization) => {
+            if policy_changed {
+                state.blocking_organizations.invalidate();
+            }
+            record_audit_event(
+                &state.db_pool,
+                Some(id),
+                Some(user_id),
+                "organization.updated",
+                Some(("organization", id.to_string())),
+                serde_json::json!({}),
+            )
+            .await;
             (
-                StatusCode::BAD_REQUEST,
+                StatusCode::OK,
                 Json(serde_json::json!({
-                    "error": e.to_string()
+                    "organization": organization
                 })),
             )
         }
+        Err(e) => {
+            tracing::error!("Failed to update organization: {}", e);
+            error_response(&e, StatusCode::BAD_REQUEST)
+        }
     }
 }
 
@@ -228,8 +271,10 @@ pub async fn update_organization(
     ),
     responses(
         (status = 204, description = "Organization deleted successfully"),
+        (status = 400, description = "REQUIRE_REQUEST_NONCE is set and no X-Request-Nonce was sent"),
         (status = 403, description = "Only owners can delete organizations"),
         (status = 404, description = "Organization not found"),
+        (status = 409, description = "X-Request-Nonce was already used or has expired"),
         (status = 500, description = "Internal server error")
     ),
     security(
@@ -239,9 +284,10 @@ pub async fn update_organization(
 pub async fn delete_organization(
     State(state): State<AppState>,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
     Path(id): Path<i64>,
 ) -> impl IntoResponse {
-    let user_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let user_id = match extract_user_id(auth, &state.config.auth, &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -253,17 +299,113 @@ pub async fn delete_organization(
         }
     };
 
+    let require_nonce = state.config.auth.require_request_nonce;
+    if let Err(e) = check_nonce(state.nonces.as_ref(), &headers, user_id, NonceAction::DeleteOrganization, require_nonce).await {
+        return error_response(&e, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
     match delete_org_by_id_internal(&state.db_pool, id, user_id).await {
-        Ok(_) => (StatusCode::NO_CONTENT, Json(serde_json::json!({}))),
+        Ok(_) => {
+            let tenant = TenantContext::for_organization(state.config.database.tenancy_mode, id);
+            if let Err(e) = tenant.drop_schema(&state.db_pool).await {
+                tracing::warn!("Failed to drop the schema of deleted organization {}: {}", id, e);
+            }
+            (StatusCode::NO_CONTENT, Json(serde_json::json!({})))
+        }
         Err(e) => {
             tracing::error!("Failed to delete organization: {}", e);
+            error_response(&e, StatusCode::BAD_REQUEST)
+        }
+    }
+}
+
+// Rename organization
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{name}/rename",
+    tag = "organizations",
+    params(
+        ("name" = String, Path, description = "Current organization name")
+    ),
+    request_body = RenameOrganizationRequest,
+    responses(
+        (status = 200, description = "Organization renamed successfully"),
+        (status = 400, description = "Validation failed or insufficient permissions"),
+        (status = 404, description = "Organization not found"),
+        (status = 409, description = "New name is taken or reserved"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn rename_organization(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path(name): Path<String>,
+    Json(req): Json<RenameOrganizationRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Validation failed",
+                "details": validation_errors
+            })),
+        );
+    }
+
+    // Extract user ID from JWT or API key
+
+    let user_id = match extract_user_id_dual(
+        auth,
+        &headers,
+        &state.config.auth,
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match rename_org_internal(
+        &state.db_pool,
+        &name,
+        &req.new_name,
+        user_id,
+        state.config.registry.org_alias_grace_days,
+    ).await {
+        Ok(organization) => {
+            state.org_aliases.invalidate().await;
+            record_audit_event(
+                &state.db_pool,
+                Some(organization.id),
+                Some(user_id),
+                "organization.renamed",
+                Some(("organization", organization.id.to_string())),
+                serde_json::json!({ "from": name, "to": organization.name }),
+            )
+            .await;
             (
-                StatusCode::BAD_REQUEST,
+                StatusCode::OK,
                 Json(serde_json::json!({
-                    "error": e.to_string()
+                    "organization": organization,
+                    "previous_name": name
                 })),
             )
         }
+        Err(e) => {
+            tracing::error!("Failed to rename organization: {}", e);
+            error_response(&e, StatusCode::BAD_REQUEST)
+        }
     }
 }
 
@@ -273,10 +415,12 @@ pub async fn delete_organization(
     path = "/api/v1/organizations/{id}/members",
     tag = "organizations",
     params(
-        ("id" = i64, Path, description = "Organization ID")
+        ("id" = i64, Path, description = "Organization ID"),
+        ("n" = Option<u32>, Query, description = "Number of members to return"),
+        ("last" = Option<String>, Query, description = "Last username for pagination")
     ),
     responses(
-        (status = 200, description = "Organization members retrieved successfully"),
+        (status = 200, description = "Organization members retrieved successfully; a Link header points to the next page"),
         (status = 403, description = "Access denied: not a member of this organization"),
         (status = 404, description = "Organization not found"),
         (status = 500, description = "Internal server error")
@@ -289,8 +433,9 @@ pub async fn get_organization_members(
     State(state): State<AppState>,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     Path(id): Path<i64>,
+    Query(page_query): Query<PageQuery>,
 ) -> impl IntoResponse {
-    let extracted_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let extracted_id = match extract_user_id(auth, &state.config.auth, &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -298,26 +443,29 @@ pub async fn get_organization_members(
                 Json(serde_json::json!({
                     "error": "Unauthorized"
                 })),
-            );
+            )
+                .into_response();
         }
     };
     let user_id = Some(extracted_id);
 
-    match get_members_by_org_id_internal(&state.db_pool, id, user_id).await {
-        Ok(members) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "members": members
-            })),
-        ),
-        Err(e) => {
-            tracing::error!("Failed to get organization members: {}", e);
+    match get_members_by_org_id_internal(&state.db_pool, id, user_id, &page_query).await {
+        Ok(members) => {
+            let page = paginate(members, |member| member.username.clone(), &page_query);
+            let mut headers = HeaderMap::new();
+            page.add_link(&format!("/api/v1/organizations/{}/members", id), &mut headers);
             (
-                StatusCode::BAD_REQUEST,
+                StatusCode::OK,
+                headers,
                 Json(serde_json::json!({
-                    "error": e.to_string()
+                    "members": page.items
                 })),
             )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to get organization members: {}", e);
+            error_response(&e, StatusCode::BAD_REQUEST).into_response()
         }
     }
 }
@@ -334,7 +482,7 @@ pub async fn get_organization_members(
     responses(
         (status = 201, description = "Member added to organization successfully"),
         (status = 400, description = "User already a member or validation failed"),
-        (status = 403, description = "Insufficient permissions to add members"),
+        (status = 403, description = "Insufficient permissions to add members, or no seats left (SEAT_LIMIT_EXCEEDED)"),
         (status = 404, description = "User or organization not found"),
         (status = 500, description = "Internal server error")
     ),
@@ -358,7 +506,7 @@ pub async fn add_organization_member(
         );
     }
 
-    let inviter_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let inviter_id = match extract_user_id(auth, &state.config.auth, &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -370,22 +518,86 @@ pub async fn add_organization_member(
         }
     };
 
-    match add_member_by_org_id_internal(&state.db_pool, id, req, inviter_id).await {
-        Ok(member) => (
-            StatusCode::CREATED,
-            Json(serde_json::json!({
-                "member": member
-            })),
-        ),
-        Err(e) => {
-            tracing::error!("Failed to add organization member: {}", e);
+    match add_member_by_org_id_internal(&state.db_pool, id, req, inviter_id, state.config.registry.org_max_members).await {
+        Ok(member) => {
+            record_audit_event(
+                &state.db_pool,
+                Some(id),
+                Some(inviter_id),
+                "member.added",
+                Some(("user", member.user_id.to_string())),
+                serde_json::json!({ "role": member.role, "expires_at": member.expires_at.as_ref().map(crate::utils::timestamp::format) }),
+            )
+            .await;
             (
-                StatusCode::BAD_REQUEST,
+                StatusCode::CREATED,
                 Json(serde_json::json!({
-                    "error": e.to_string()
+                    "member": member
                 })),
             )
         }
+        Err(e) => {
+            tracing::error!("Failed to add organization member: {}", e);
+            error_response(&e, StatusCode::BAD_REQUEST)
+        }
+    }
+}
+
+// Get a member's effective role
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{name}/members/{user_id}/role",
+    tag = "organizations",
+    params(
+        ("name" = String, Path, description = "Organization name"),
+        ("user_id" = i64, Path, description = "User ID to look up")
+    ),
+    responses(
+        (status = 200, description = "The user's role in the organization", body = MemberRoleResponse),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only organization owners and admins can look up member roles"),
+        (status = 404, description = "Organization not found or user is not a member"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_member_role(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((name, member_id)): Path<(String, i64)>,
+) -> impl IntoResponse {
+
+    let user_id = match extract_user_id_dual(
+        auth,
+        &headers,
+        &state.config.auth,
+        &state.db_pool,
+        state.cache.as_ref()
+    ).await {
+        Ok(id) => id,
+        Err(status) => {
+            return (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            );
+        }
+    };
+
+    match get_member_role_internal(&state.db_pool, &name, user_id, member_id).await {
+        Ok(role) => (
+            StatusCode::OK,
+            Json(serde_json::json!(MemberRoleResponse {
+                organization: name,
+                user_id: member_id,
+                role,
+            })),
+        ),
+        Err(e) => error_response(&e, StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
@@ -404,6 +616,8 @@ pub async fn add_organization_member(
         (status = 400, description = "Invalid role or validation failed"),
         (status = 403, description = "Insufficient permissions to modify this member"),
         (status = 404, description = "Member or organization not found"),
+        (status = 409, description = "X-Request-Nonce sent to make the member an owner was already used or has expired"),
+        (status = 412, description = "Member changed since `version`; the body's `current` holds the member as it is now"),
         (status = 500, description = "Internal server error")
     ),
     security(
@@ -413,10 +627,11 @@ pub async fn add_organization_member(
 pub async fn update_member_role(
     State(state): State<AppState>,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: HeaderMap,
     Path((id, member_id)): Path<(i64, i64)>,
     Json(req): Json<UpdateMemberRequest>,
 ) -> impl IntoResponse {
-    let updater_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let updater_id = match extract_user_id(auth, &state.config.auth, &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -428,24 +643,46 @@ pub async fn update_member_role(
         }
     };
 
+    // Making someone an owner hands over control of the organization
+    if req.role == OrganizationRole::Owner {
+        let require_nonce = state.config.auth.require_request_nonce;
+        if let Err(e) = check_nonce(state.nonces.as_ref(), &headers, updater_id, NonceAction::TransferOwnership, require_nonce).await {
+            return error_response(&e, StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
     match update_member_role_by_org_id_internal(&state.db_pool, id, member_id, req, updater_id)
         .await
     {
-        Ok(member) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "member": member
-            })),
-        ),
-        Err(e) => {
-            tracing::error!("Failed to update member role: {}", e);
+        Ok(member) => {
+            record_audit_event(
+                &state.db_pool,
+                Some(id),
+                Some(updater_id),
+                "member.updated",
+                Some(("user", member_id.to_string())),
+                serde_json::json!({ "role": member.role, "expires_at": member.expires_at.as_ref().map(crate::utils::timestamp::format) }),
+            )
+            .await;
             (
-                StatusCode::BAD_REQUEST,
+                StatusCode::OK,
                 Json(serde_json::json!({
-                    "error": e.to_string()
+                    "member": member
                 })),
             )
         }
+        Err(e) => {
+            tracing::error!("Failed to update member role: {}", e);
+            let (status, Json(mut body)) = error_response(&e, StatusCode::BAD_REQUEST);
+            // A stale update gets the current member, so the client can
+            // reapply its change on top of it
+            if status == StatusCode::PRECONDITION_FAILED {
+                if let Ok(current) = fetch_member(&state.db_pool, id, member_id).await {
+                    body["current"] = serde_json::json!(current);
+                }
+            }
+            (status, Json(body))
+        }
     }
 }
 
@@ -473,7 +710,7 @@ pub async fn remove_organization_member(
     auth: Option<TypedHeader<Authorization<Bearer>>>,
     Path((id, member_id)): Path<(i64, i64)>,
 ) -> impl IntoResponse {
-    let remover_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let remover_id = match extract_user_id(auth, &state.config.auth, &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -486,15 +723,21 @@ pub async fn remove_organization_member(
     };
 
     match remove_member_internal(&state.db_pool, id, member_id, remover_id).await {
-        Ok(_) => (StatusCode::NO_CONTENT, Json(serde_json::json!({}))),
+        Ok(_) => {
+            record_audit_event(
+                &state.db_pool,
+                Some(id),
+                Some(remover_id),
+                "member.removed",
+                Some(("user", member_id.to_string())),
+                serde_json::json!({}),
+            )
+            .await;
+            (StatusCode::NO_CONTENT, Json(serde_json::json!({})))
+        }
         Err(e) => {
             tracing::error!("Failed to remove organization member: {}", e);
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "error": e.to_string()
-                })),
-            )
+            error_response(&e, StatusCode::BAD_REQUEST)
         }
     }
 }
@@ -516,7 +759,7 @@ pub async fn list_user_organizations(
     State(state): State<AppState>,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
 ) -> impl IntoResponse {
-    let user_id = match extract_user_id(auth, state.config.auth.jwt_secret.expose_secret().as_bytes()).await {
+    let user_id = match extract_user_id(auth, &state.config.auth, &state.db_pool).await {
         Ok(id) => id,
         Err(status) => {
             return (
@@ -547,6 +790,20 @@ pub async fn list_user_organizations(
     }
 }
 
+/// Delete memberships whose expiry has passed. Expired memberships already
+/// grant no access; this keeps the table tidy.
+pub async fn cleanup_expired_memberships(pool: &PgPool) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM organization_members WHERE expires_at IS NOT NULL AND expires_at <= NOW()",
+    )
+    .execute(pool)
+    .timed("cleanup_expired_memberships")
+    .await?;
+
+    tracing::info!("Cleaned up {} expired organization memberships", result.rows_affected());
+    Ok(result.rows_affected())
+}
+
 // Helper function to get user's role in organization
 async fn get_user_role_in_org(
     pool: &PgPool,
@@ -558,10 +815,11 @@ async fn get_user_role_in_org(
         role: String,
     }
 
-    let result = sqlx::query_as::<_, RoleRow>("SELECT om.role FROM organization_members om JOIN organizations o ON om.organization_id = o.id WHERE o.id = $1 AND om.user_id = $2")
+    let result = sqlx::query_as::<_, RoleRow>("SELECT om.role FROM organization_members om JOIN organizations o ON om.organization_id = o.id WHERE o.id = $1 AND om.user_id = $2 AND (om.expires_at IS NULL OR om.expires_at > NOW())")
         .bind(org_id)
         .bind(user_id)
         .fetch_optional(pool)
+        .timed("get_user_role_in_org")
         .await?;
 
     match result {
@@ -576,10 +834,29 @@ async fn get_user_role_in_org(
 }
 
 // Internal database functions
+async fn get_member_role_internal(
+    pool: &PgPool,
+    org_name: &str,
+    caller_id: i64,
+    member_id: i64,
+) -> Result<OrganizationRole> {
+    let org_id = sqlx::query_scalar::<_, i64>("SELECT id FROM organizations WHERE name = $1")
+        .bind(org_name)
+        .fetch_optional(pool)
+        .timed("get_member_role_internal")
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Organization '{}' not found", org_name)))?;
+
+    let caller_role = get_user_role_in_org(pool, org_id, caller_id).await?;
+    let member_role = get_user_role_in_org(pool, org_id, member_id).await?;
+    Ok(resolve_member_role(caller_role.as_ref(), member_role)?)
+}
+
 async fn create_org_internal(
     pool: &PgPool,
     req: CreateOrganizationRequest,
     creator_id: i64,
+    tenancy_mode: TenancyMode,
 ) -> Result<Organization> {
     let mut tx = pool.begin().await?;
 
@@ -587,17 +864,32 @@ async fn create_org_internal(
     let existing = sqlx::query("SELECT id FROM organizations WHERE name = $1")
         .bind(&req.name)
         .fetch_optional(&mut *tx)
+        .timed("create_org_internal: SELECT organizations")
         .await?;
 
     if existing.is_some() {
-        bail!("Organization with name '{}' already exists", req.name);
+        return Err(AppError::Conflict(format!("Organization with name '{}' already exists", req.name)).into());
+    }
+
+    // Old names of recently renamed organizations stay reserved during the grace period
+    let aliased = sqlx::query("SELECT id FROM organization_aliases WHERE old_name = $1 AND expires_at > NOW()")
+        .bind(&req.name)
+        .fetch_optional(&mut *tx)
+        .timed("create_org_internal: SELECT organization_aliases")
+        .await?;
+
+    if aliased.is_some() {
+        return Err(AppError::Conflict(format!(
+            "Organization name '{}' is reserved by a recently renamed organization",
+            req.name
+        )).into());
     }
 
     // Create organization
     let org = sqlx::query_as::<_, Organization>(
         "INSERT INTO organizations (name, display_name, description, website_url, avatar_url)
         VALUES ($1, $2, $3, $4, $5)
-        RETURNING id, name, display_name, description, website_url, avatar_url, created_at, updated_at"
+        RETURNING id, name, display_name, description, website_url, avatar_url, created_at, updated_at, block_vulnerable_pulls, default_repo_public"
     )
     .bind(&req.name)
     .bind(&req.display_name)
@@ -605,6 +897,7 @@ async fn create_org_internal(
     .bind(&req.website_url)
     .bind(&req.avatar_url)
     .fetch_one(&mut *tx)
+    .timed("create_org_internal: INSERT organizations")
     .await?;
 
     // Add creator as owner
@@ -616,8 +909,14 @@ async fn create_org_internal(
     .bind(creator_id)
     .bind("owner")
     .execute(&mut *tx)
+    .timed("create_org_internal: INSERT organization_members")
     .await?;
 
+    // In schema-per-tenant mode the organization gets its own tables
+    TenantContext::for_organization(tenancy_mode, org.id)
+        .provision(&mut *tx)
+        .await?;
+
     tx.commit().await?;
     Ok(org)
 }
@@ -630,6 +929,7 @@ async fn get_org_by_id_internal(pool: &PgPool, org_id: i64) -> Result<Option<Org
     )
     .bind(org_id)
     .fetch_optional(pool)
+    .timed("get_org_by_id_internal")
     .await
     .context("Failed to fetch organization")
 }
@@ -643,7 +943,7 @@ async fn update_org_by_id_internal(
     // Check if user has permission to update
     let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
     if !user_role
-        .map(|r| r.can_manage_organization())
+        .map(|r| r.allows(OrganizationAction::UpdateOrganization))
         .unwrap_or(false)
     {
         bail!("Insufficient permissions to update organization");
@@ -656,16 +956,21 @@ async fn update_org_by_id_internal(
              description = COALESCE($3, description),
              website_url = COALESCE($4, website_url),
              avatar_url = COALESCE($5, avatar_url),
+             block_vulnerable_pulls = COALESCE($6, block_vulnerable_pulls),
+             default_repo_public = COALESCE($7, default_repo_public),
              updated_at = CURRENT_TIMESTAMP
          WHERE id = $1
-         RETURNING id, name, display_name, description, website_url, avatar_url, created_at, updated_at"
+         RETURNING id, name, display_name, description, website_url, avatar_url, created_at, updated_at, block_vulnerable_pulls, default_repo_public"
     )
     .bind(org_id)
     .bind(&req.display_name)
     .bind(&req.description)
     .bind(&req.website_url)
     .bind(&req.avatar_url)
+    .bind(req.block_vulnerable_pulls)
+    .bind(req.default_repo_public)
     .fetch_one(pool)
+    .timed("update_org_by_id_internal")
     .await
     .context("Organization not found")
 }
@@ -673,7 +978,7 @@ async fn update_org_by_id_internal(
 async fn delete_org_by_id_internal(pool: &PgPool, org_id: i64, user_id: i64) -> Result<()> {
     let user_role = get_user_role_in_org(pool, org_id, user_id).await?;
     if !user_role
-        .map(|r| r.can_delete_organization())
+        .map(|r| r.allows(OrganizationAction::DeleteOrganization))
         .unwrap_or(false)
     {
         bail!("Only organization owners can delete organizations");
@@ -682,6 +987,7 @@ async fn delete_org_by_id_internal(pool: &PgPool, org_id: i64, user_id: i64) ->
     let result = sqlx::query("DELETE FROM organizations WHERE id = $1")
         .bind(org_id)
         .execute(pool)
+        .timed("delete_org_by_id_internal")
         .await?;
 
     if result.rows_affected() == 0 {
@@ -691,10 +997,157 @@ async fn delete_org_by_id_internal(pool: &PgPool, org_id: i64, user_id: i64) ->
     Ok(())
 }
 
+async fn rename_org_internal(
+    pool: &PgPool,
+    current_name: &str,
+    new_name: &str,
+    user_id: i64,
+    grace_days: i32,
+) -> Result<Organization> {
+    if is_reserved_org_name(new_name) {
+        return Err(AppError::Conflict(format!("Organization name '{}' is reserved", new_name)).into());
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let org = sqlx::query_as::<_, Organization>(
+        "SELECT id, name, display_name, description, website_url, avatar_url, created_at, updated_at
+         FROM organizations
+         WHERE name = $1
+         FOR UPDATE"
+    )
+    .bind(current_name)
+    .fetch_optional(&mut *tx)
+    .timed("rename_org_internal: SELECT organizations")
+    .await?
+    .ok_or_else(|| AppError::NotFound("Organization not found".to_string()))?;
+
+    let user_role = get_user_role_in_org(pool, org.id, user_id).await?;
+    if !user_role
+        .map(|r| r.allows(OrganizationAction::RenameOrganization))
+        .unwrap_or(false)
+    {
+        bail!("Only organization owners can rename organizations");
+    }
+
+    let existing = sqlx::query("SELECT id FROM organizations WHERE name = $1")
+        .bind(new_name)
+        .fetch_optional(&mut *tx)
+        .timed("rename_org_internal: SELECT organizations")
+        .await?;
+
+    if existing.is_some() {
+        return Err(AppError::Conflict(format!("Organization with name '{}' already exists", new_name)).into());
+    }
+
+    // An organization may take back one of its own old names, but not another's
+    let alias_owner = sqlx::query_scalar::<_, i64>(
+        "SELECT organization_id FROM organization_aliases WHERE old_name = $1 AND expires_at > NOW()"
+    )
+    .bind(new_name)
+    .fetch_optional(&mut *tx)
+    .timed("rename_org_internal: SELECT organization_aliases")
+    .await?;
+
+    if matches!(alias_owner, Some(owner_id) if owner_id != org.id) {
+        return Err(AppError::Conflict(format!(
+            "Organization name '{}' is reserved by a recently renamed organization",
+            new_name
+        )).into());
+    }
+
+    sqlx::query("DELETE FROM organization_aliases WHERE old_name = $1")
+        .bind(new_name)
+        .execute(&mut *tx)
+        .timed("rename_org_internal: DELETE organization_aliases")
+        .await?;
+
+    let renamed = sqlx::query_as::<_, Organization>(
+        "UPDATE organizations
+         SET name = $2, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1
+         RETURNING id, name, display_name, description, website_url, avatar_url, created_at, updated_at, block_vulnerable_pulls, default_repo_public"
+    )
+    .bind(org.id)
+    .bind(new_name)
+    .fetch_one(&mut *tx)
+    .timed("rename_org_internal: UPDATE organizations")
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO organization_aliases (organization_id, old_name, expires_at)
+         VALUES ($1, $2, NOW() + make_interval(days => $3))
+         ON CONFLICT (old_name) DO UPDATE
+         SET organization_id = EXCLUDED.organization_id,
+             created_at = CURRENT_TIMESTAMP,
+             expires_at = EXCLUDED.expires_at"
+    )
+    .bind(org.id)
+    .bind(&org.name)
+    .bind(grace_days)
+    .execute(&mut *tx)
+    .timed("rename_org_internal: INSERT organization_aliases")
+    .await?;
+
+    tx.commit().await?;
+    Ok(renamed)
+}
+
+/// How long a snapshot of the alias table is trusted before it is reloaded
+const ORG_ALIAS_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// In-memory snapshot of the active organization aliases, so namespaced
+/// registry requests do not query the alias table each time. Renames on this
+/// instance invalidate it; renames elsewhere show up within the TTL.
+#[derive(Default)]
+pub struct OrgAliasCache {
+    snapshot: tokio::sync::RwLock<Option<(std::time::Instant, std::collections::HashMap<String, String>)>>,
+}
+
+impl OrgAliasCache {
+    /// Resolve an old organization name to the current one while its alias is active.
+    /// Returns `None` when the name is not an alias or an organization now owns it.
+    pub async fn resolve(&self, pool: &PgPool, name: &str) -> Result<Option<String>> {
+        if let Some((loaded_at, aliases)) = self.snapshot.read().await.as_ref() {
+            if loaded_at.elapsed() < ORG_ALIAS_CACHE_TTL {
+                return Ok(aliases.get(name).cloned());
+            }
+        }
+
+        let aliases = load_org_aliases(pool).await?;
+        let current = aliases.get(name).cloned();
+        *self.snapshot.write().await = Some((std::time::Instant::now(), aliases));
+        Ok(current)
+    }
+
+    /// Drop the snapshot after the alias table changed
+    pub async fn invalidate(&self) {
+        *self.snapshot.write().await = None;
+    }
+}
+
+/// Active aliases whose old name no organization has taken, old name -> current name
+async fn load_org_aliases(pool: &PgPool) -> Result<std::collections::HashMap<String, String>> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT a.old_name, o.name
+         FROM organization_aliases a
+         JOIN organizations o ON o.id = a.organization_id
+         WHERE a.expires_at > NOW()
+           AND NOT EXISTS (SELECT 1 FROM organizations WHERE name = a.old_name)"
+    )
+    .fetch_all(pool)
+    .timed("load_org_aliases")
+    .await
+    .context("Failed to load organization aliases")?;
+
+    Ok(rows.into_iter().collect())
+}
+
 async fn get_members_by_org_id_internal(
     pool: &PgPool,
     org_id: i64,
     user_id: Option<i64>,
+    page_query: &PageQuery,
 ) -> Result<Vec<OrganizationMember>> {
     // Check if user has access to view members
     if let Some(uid) = user_id {
@@ -704,19 +1157,28 @@ async fn get_members_by_org_id_internal(
         }
     }
 
-    sqlx::query_as::<_, OrganizationMember>(
+    // Pages are ordered by username; the whole list keeps join order
+    let order = if page_query.is_paginated() { "u.username COLLATE \"C\"" } else { "om.joined_at ASC" };
+    let sql = format!(
         "SELECT 
             om.id, om.organization_id, om.user_id, om.role,
-            om.joined_at, om.invited_at, om.invited_by,
+            om.joined_at, om.invited_at, om.invited_by, om.expires_at, om.version,
             u.username, u.email
         FROM organization_members om
         JOIN users u ON om.user_id = u.id
         JOIN organizations o ON om.organization_id = o.id
-        WHERE o.id = $1
-        ORDER BY om.joined_at ASC",
-    )
+        WHERE o.id = $1 AND (om.expires_at IS NULL OR om.expires_at > NOW())
+        AND ($2::TEXT IS NULL OR u.username COLLATE \"C\" > $2)
+        ORDER BY {}
+        LIMIT $3",
+        order
+    );
+    sqlx::query_as::<_, OrganizationMember>(&sql)
     .bind(org_id)
+    .bind(page_query.last.as_deref())
+    .bind(page_query.fetch_limit())
     .fetch_all(pool)
+    .timed("get_members_by_org_id_internal")
     .await
     .context("Failed to fetch organization members")
 }
@@ -726,10 +1188,11 @@ async fn add_member_by_org_id_internal(
     org_id: i64,
     req: AddMemberRequest,
     inviter_id: i64,
+    default_max_members: Option<i64>,
 ) -> Result<OrganizationMember> {
     let inviter_role = get_user_role_in_org(pool, org_id, inviter_id).await?;
     if !inviter_role
-        .map(|r| r.can_manage_members())
+        .map(|r| r.allows(OrganizationAction::AddMembers))
         .unwrap_or(false)
     {
         bail!("Insufficient permissions to add members");
@@ -743,37 +1206,81 @@ async fn add_member_by_org_id_internal(
         email: String,
     }
 
-    let user = sqlx::query_as::<_, User>("SELECT id, username, email FROM users WHERE email = $1")
-        .bind(&req.email)
+    let user = sqlx::query_as::<_, User>("SELECT id, username, email FROM users WHERE LOWER(email) = $1")
+        .bind(normalize_email(&req.email))
         .fetch_one(pool)
+        .timed("add_member_by_org_id_internal: SELECT users")
         .await
         .context("User not found with that email")?;
 
+    if let Some(expires_at) = req.expires_at {
+        if expires_at <= chrono::Utc::now() {
+            bail!("Membership expiry must be in the future");
+        }
+    }
+
+    // Locking the organization serializes concurrent adds, so two of them
+    // cannot both take the last seat
+    let mut tx = pool.begin().await?;
+    let max_members: Option<i32> = sqlx::query_scalar("SELECT max_members FROM organizations WHERE id = $1 FOR UPDATE")
+        .bind(org_id)
+        .fetch_one(&mut *tx)
+        .timed("add_member_by_org_id_internal: SELECT organizations")
+        .await
+        .context("Organization not found")?;
+
+    // An expired membership that hasn't been cleaned up yet doesn't block re-adding
+    sqlx::query(
+        "DELETE FROM organization_members WHERE organization_id = $1 AND user_id = $2 AND expires_at <= NOW()",
+    )
+    .bind(org_id)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .timed("add_member_by_org_id_internal: DELETE organization_members")
+    .await?;
+
     // Check if user is already a member
     let existing = sqlx::query(
         "SELECT id FROM organization_members WHERE organization_id = $1 AND user_id = $2",
     )
     .bind(org_id)
     .bind(user.id)
-    .fetch_optional(pool)
+    .fetch_optional(&mut *tx)
+    .timed("add_member_by_org_id_internal: SELECT organization_members")
     .await?;
 
     if existing.is_some() {
         bail!("User is already a member of this organization");
     }
 
+    let members: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM organization_members
+         WHERE organization_id = $1 AND (expires_at IS NULL OR expires_at > NOW())",
+    )
+    .bind(org_id)
+    .fetch_one(&mut *tx)
+    .timed("add_member_by_org_id_internal: SELECT organization_members")
+    .await?;
+    let max_members = max_members.map(i64::from).or(default_max_members);
+    if seats_remaining(max_members, members) == Some(0) {
+        return Err(AppError::SeatLimitExceeded { max_members: max_members.unwrap_or_default() }.into());
+    }
+
     // Add member
     let member_id: i64 = sqlx::query_scalar(
-        "INSERT INTO organization_members (organization_id, user_id, role, invited_by)
-         VALUES ($1, $2, $3, $4)
+        "INSERT INTO organization_members (organization_id, user_id, role, invited_by, expires_at)
+         VALUES ($1, $2, $3, $4, $5)
          RETURNING id",
     )
     .bind(org_id)
     .bind(user.id)
     .bind(&req.role.to_string())
     .bind(inviter_id)
-    .fetch_one(pool)
+    .bind(req.expires_at)
+    .fetch_one(&mut *tx)
+    .timed("add_member_by_org_id_internal: INSERT organization_members")
     .await?;
+    tx.commit().await?;
 
     // Return the created member
     let member = OrganizationMember {
@@ -784,6 +1291,8 @@ async fn add_member_by_org_id_internal(
         joined_at: chrono::Utc::now(),
         invited_at: Some(chrono::Utc::now()),
         invited_by: Some(inviter_id),
+        expires_at: req.expires_at,
+        version: 1,
         username: user.username,
         email: user.email,
     };
@@ -814,21 +1323,49 @@ async fn update_member_role_by_org_id_internal(
 
     // Organization ID is already provided
 
-    // Update the role
+    if let Some(expires_at) = req.expires_at {
+        if expires_at <= chrono::Utc::now() {
+            bail!("Membership expiry must be in the future");
+        }
+    }
+
+    // Locking the row makes the version check and the update atomic, so of
+    // two concurrent updates based on the same version only one applies
+    let mut tx = pool.begin().await?;
+    let current_version: i64 = sqlx::query_scalar(
+        "SELECT version FROM organization_members WHERE organization_id = $1 AND user_id = $2 FOR UPDATE",
+    )
+    .bind(org_id)
+    .bind(member_user_id)
+    .fetch_optional(&mut *tx)
+    .timed("update_member_role_by_org_id_internal: SELECT organization_members")
+    .await?
+    .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+    check_member_version(req.version, current_version)?;
+
+    // Update the role, and the expiry when one is given
     sqlx::query(
-        "UPDATE organization_members SET role = $3 WHERE organization_id = $1 AND user_id = $2",
+        "UPDATE organization_members SET role = $3, expires_at = COALESCE($4, expires_at), version = version + 1
+         WHERE organization_id = $1 AND user_id = $2",
     )
     .bind(org_id)
     .bind(member_user_id)
     .bind(&req.role.to_string())
-    .execute(pool)
+    .bind(req.expires_at)
+    .execute(&mut *tx)
+    .timed("update_member_role_by_org_id_internal: UPDATE organization_members")
     .await?;
+    tx.commit().await?;
 
-    // Fetch and return updated member info
-    let member = sqlx::query_as::<_, OrganizationMember>(
+    fetch_member(pool, org_id, member_user_id).await
+}
+
+/// One member of an organization, with their user details
+async fn fetch_member(pool: &PgPool, org_id: i64, member_user_id: i64) -> Result<OrganizationMember> {
+    sqlx::query_as::<_, OrganizationMember>(
         "SELECT 
             om.id, om.organization_id, om.user_id, om.role,
-            om.joined_at, om.invited_at, om.invited_by,
+            om.joined_at, om.invited_at, om.invited_by, om.expires_at, om.version,
             u.username, u.email
         FROM organization_members om
         JOIN users u ON om.user_id = u.id
@@ -837,10 +1374,9 @@ async fn update_member_role_by_org_id_internal(
     .bind(org_id)
     .bind(member_user_id)
     .fetch_one(pool)
+    .timed("fetch_member")
     .await
-    .context("Member not found")?;
-
-    Ok(member)
+    .context("Member not found")
 }
 
 async fn remove_member_internal(
@@ -870,6 +1406,7 @@ async fn remove_member_internal(
             .bind(org_id)
             .bind(member_user_id)
             .execute(pool)
+            .timed("remove_member_internal")
             .await?;
 
     if result.rows_affected() == 0 {
@@ -887,12 +1424,13 @@ async fn list_user_orgs_internal(pool: &PgPool, user_id: i64) -> Result<Vec<Orga
                o.website_url, o.avatar_url, o.created_at, o.updated_at
         FROM organizations o
         JOIN organization_members om ON o.id = om.organization_id
-        WHERE om.user_id = $1
+        WHERE om.user_id = $1 AND (om.expires_at IS NULL OR om.expires_at > NOW())
         ORDER BY o.name
         "#,
         user_id
     )
     .fetch_all(pool)
+    .timed("list_user_orgs_internal")
     .await
     .context("Failed to fetch user organizations")
 }
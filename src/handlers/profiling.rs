@@ -0,0 +1,165 @@
+// On-demand CPU profiling and memory statistics for debugging production slowness
+use anyhow::Result;
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::is_admin_request;
+use crate::handlers::admin::admin_token_required;
+use crate::AppState;
+
+/// Longest CPU profile a caller may request
+pub const MAX_PROFILE_SECONDS: u64 = 60;
+
+/// Sampling frequency of the CPU profiler, in Hz
+const PROFILE_FREQUENCY: i32 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileQuery {
+    pub seconds: Option<u64>,
+}
+
+/// Process memory figures, in bytes
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct HeapStats {
+    /// Resident set size
+    pub resident_bytes: u64,
+    /// Peak resident set size
+    pub peak_resident_bytes: u64,
+    /// Virtual memory size
+    pub virtual_bytes: u64,
+    /// Private data and heap segment size
+    pub data_bytes: u64,
+}
+
+/// Capture a CPU profile over `seconds` and encode it as an (uncompressed)
+/// pprof protobuf, readable by `go tool pprof`. Blocks the calling thread.
+#[cfg(unix)]
+pub fn capture_cpu_profile(seconds: u64) -> Result<Vec<u8>> {
+    use pprof::protos::Message;
+
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(PROFILE_FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()?;
+
+    std::thread::sleep(std::time::Duration::from_secs(seconds));
+
+    let profile = guard.report().build()?.pprof()?;
+    Ok(profile.encode_to_vec())
+}
+
+#[cfg(not(unix))]
+pub fn capture_cpu_profile(_seconds: u64) -> Result<Vec<u8>> {
+    anyhow::bail!("CPU profiling is only supported on Unix")
+}
+
+/// Parse the memory lines of `/proc/self/status` (values are in kB)
+pub fn parse_proc_status(status: &str) -> HeapStats {
+    let mut stats = HeapStats::default();
+
+    for line in status.lines() {
+        let (key, value) = match line.split_once(':') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let kilobytes: u64 = match value.trim().trim_end_matches("kB").trim().parse() {
+            Ok(kb) => kb,
+            Err(_) => continue,
+        };
+
+        match key {
+            "VmRSS" => stats.resident_bytes = kilobytes * 1024,
+            "VmHWM" => stats.peak_resident_bytes = kilobytes * 1024,
+            "VmSize" => stats.virtual_bytes = kilobytes * 1024,
+            "VmData" => stats.data_bytes = kilobytes * 1024,
+            _ => {}
+        }
+    }
+
+    stats
+}
+
+/// CPU profile - GET /debug/pprof/profile?seconds=N
+pub async fn cpu_profile(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ProfileQuery>,
+) -> Response {
+    if let Some(response) = reject_unless_allowed(&state, &headers) {
+        return response;
+    }
+
+    let seconds = query.seconds.unwrap_or(10);
+    if seconds == 0 || seconds > MAX_PROFILE_SECONDS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("seconds must be between 1 and {}", MAX_PROFILE_SECONDS)
+            })),
+        )
+            .into_response();
+    }
+
+    tracing::info!(seconds, "Capturing CPU profile");
+    match tokio::task::spawn_blocking(move || capture_cpu_profile(seconds)).await {
+        Ok(Ok(profile)) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "application/octet-stream"),
+                (header::CONTENT_DISPOSITION, "attachment; filename=\"profile.pb\""),
+            ],
+            profile,
+        )
+            .into_response(),
+        Ok(Err(e)) => {
+            tracing::error!("CPU profiling failed: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": format!("Profiling failed: {}", e) })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("CPU profiling task failed: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Internal server error" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Memory statistics - GET /debug/pprof/heap
+pub async fn heap_stats(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Some(response) = reject_unless_allowed(&state, &headers) {
+        return response;
+    }
+
+    match tokio::fs::read_to_string("/proc/self/status").await {
+        Ok(status) => (StatusCode::OK, Json(parse_proc_status(&status))).into_response(),
+        Err(e) => (
+            StatusCode::NOT_IMPLEMENTED,
+            Json(serde_json::json!({ "error": format!("Memory statistics unavailable: {}", e) })),
+        )
+            .into_response(),
+    }
+}
+
+/// Profiling endpoints do not exist unless enabled, and then only for admins
+fn reject_unless_allowed(state: &AppState, headers: &HeaderMap) -> Option<Response> {
+    if !state.config.server.enable_profiling {
+        return Some(StatusCode::NOT_FOUND.into_response());
+    }
+
+    if !is_admin_request(headers, &state.config.auth) {
+        return Some(admin_token_required());
+    }
+
+    None
+}
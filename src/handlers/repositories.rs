@@ -8,21 +8,35 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use axum_extra::headers::{authorization::Bearer, Authorization};
 use axum_extra::TypedHeader;
-use secrecy::ExposeSecret;
 use utoipa::{OpenApi, ToSchema};
 
+use crate::db::Timed;
 use crate::{
-    auth::{extract_user_id_dual, extract_user_id, verify_token},
+    auth::{extract_user_id_dual, extract_user_id, bearer_user_id},
+    config::settings::RepositoryVisibility,
     database::models::{Organization, Repository},
+    handlers::docker_registry_v2::invalidate_catalog,
+    models::organizations::OrganizationRole,
+    models::repository::{check_repository_depth, RepositoryName},
     models::repository_with_org::RepositoryWithOrgRow,
+    tenant::TenancyMode,
+    utils::pagination::{paginate, PageQuery},
     AppState,
 };
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateRepositoryRequest {
     pub name: String,
+    /// Human-friendly name; unique per organization when ENFORCE_UNIQUE_DISPLAY_NAMES is set
+    #[serde(default)]
+    pub display_name: Option<String>,
     pub description: Option<String>,
-    pub is_public: bool,
+    /// Left out, the organization's default applies, then DEFAULT_REPO_VISIBILITY
+    #[serde(default)]
+    pub is_public: Option<bool>,
+    /// Reject pushes under tags that are not semantic versions, except `latest`
+    #[serde(default)]
+    pub require_semver: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -30,10 +44,13 @@ pub struct RepositoryResponse {
     pub id: i64,
     pub organization_id: i64,
     pub name: String,
+    pub display_name: Option<String>,
     pub description: Option<String>,
     pub is_public: bool,
     pub created_by: Option<i64>,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(with = "crate::utils::timestamp")]
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub organization: OrganizationInfo,
 }
@@ -56,6 +73,7 @@ pub struct RepositoryDetailsResponse {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RepositoryStats {
     pub total_tags: i64,
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub last_push: Option<chrono::DateTime<chrono::Utc>>,
 }
 
@@ -68,10 +86,12 @@ pub struct ListRepositoriesQuery {
     get,
     path = "/api/v1/repos/repositories",
     params(
-        ("namespace" = Option<String>, Query, description = "Filter by organization namespace")
+        ("namespace" = Option<String>, Query, description = "Filter by organization namespace"),
+        ("n" = Option<u32>, Query, description = "Number of repositories to return"),
+        ("last" = Option<String>, Query, description = "Last organization/repository name for pagination")
     ),
     responses(
-        (status = 200, description = "List of repositories", body = Vec<RepositoryResponse>),
+        (status = 200, description = "List of repositories; a Link header points to the next page", body = Vec<RepositoryResponse>),
         (status = 401, description = "Authentication required"),
         (status = 500, description = "Internal server error")
     ),
@@ -82,15 +102,15 @@ pub struct ListRepositoriesQuery {
 pub async fn list_repositories(
     State(state): State<AppState>,
     Query(query): Query<ListRepositoriesQuery>,
+    Query(page_query): Query<PageQuery>,
     headers: HeaderMap,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
 ) -> Response {
-    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
     
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
-        secret, 
+        &state.config.auth, 
         &state.db_pool, 
         state.cache.as_ref()
     ).await {
@@ -109,6 +129,7 @@ pub async fn list_repositories(
         )
         .bind(namespace)
         .fetch_optional(&state.db_pool)
+        .timed("list_repositories")
         .await {
             Ok(Some(org)) => org,
             Ok(None) => {
@@ -123,21 +144,30 @@ pub async fn list_repositories(
             }
         };
 
-        match sqlx::query_as::<_, RepositoryWithOrgRow>(
-            r#"
-            SELECT DISTINCT 
-                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
-                o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
-            FROM repositories r
-            JOIN organizations o ON r.organization_id = o.id
-            JOIN organization_members om ON r.organization_id = om.organization_id
-            WHERE om.user_id = $1
-            AND o.name = $2
-            "#
-        )
-        .bind(user_id)
-        .bind(namespace)
-        .fetch_all(&state.db_pool)
+        match crate::tenant::across_tenants(&state.db_pool, state.config.database.tenancy_mode, || {
+            sqlx::query_as::<_, RepositoryWithOrgRow>(
+                r#"
+                SELECT DISTINCT 
+                    r.id, r.organization_id, r.name, r.display_name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
+                    o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url,
+                    CONCAT(o.name, '/', r.name) COLLATE "C" as full_name
+                FROM repositories r
+                JOIN organizations o ON r.organization_id = o.id
+                JOIN organization_members om ON r.organization_id = om.organization_id
+                WHERE om.user_id = $1 AND (om.expires_at IS NULL OR om.expires_at > NOW())
+                AND o.name = $2
+                AND ($3::TEXT IS NULL OR CONCAT(o.name, '/', r.name) COLLATE "C" > $3)
+                ORDER BY full_name
+                LIMIT $4
+                "#
+            )
+            .bind(user_id)
+            .bind(namespace)
+            .bind(page_query.last.as_deref())
+            .bind(page_query.fetch_limit())
+            .fetch_all(&state.db_pool)
+        })
+        .timed("list_repositories")
         .await {
             Ok(repos) => repos,
             Err(e) => {
@@ -148,19 +178,28 @@ pub async fn list_repositories(
         }
     } else {
         // Get all repositories for user
-        match sqlx::query_as::<_, RepositoryWithOrgRow>(
-            r#"
-            SELECT DISTINCT 
-                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
-                o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
-            FROM repositories r
-            JOIN organizations o ON r.organization_id = o.id
-            JOIN organization_members om ON r.organization_id = om.organization_id
-            WHERE om.user_id = $1
-            "#
-        )
-        .bind(user_id)
-        .fetch_all(&state.db_pool)
+        match crate::tenant::across_tenants(&state.db_pool, state.config.database.tenancy_mode, || {
+            sqlx::query_as::<_, RepositoryWithOrgRow>(
+                r#"
+                SELECT DISTINCT 
+                    r.id, r.organization_id, r.name, r.display_name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
+                    o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url,
+                    CONCAT(o.name, '/', r.name) COLLATE "C" as full_name
+                FROM repositories r
+                JOIN organizations o ON r.organization_id = o.id
+                JOIN organization_members om ON r.organization_id = om.organization_id
+                WHERE om.user_id = $1 AND (om.expires_at IS NULL OR om.expires_at > NOW())
+                AND ($2::TEXT IS NULL OR CONCAT(o.name, '/', r.name) COLLATE "C" > $2)
+                ORDER BY full_name
+                LIMIT $3
+                "#
+            )
+            .bind(user_id)
+            .bind(page_query.last.as_deref())
+            .bind(page_query.fetch_limit())
+            .fetch_all(&state.db_pool)
+        })
+        .timed("list_repositories")
         .await {
             Ok(repos) => repos,
             Err(e) => {
@@ -177,6 +216,7 @@ pub async fn list_repositories(
             id: repo.id,
             organization_id: repo.organization_id,
             name: repo.name,
+            display_name: repo.display_name,
             description: repo.description,
             is_public: repo.is_public,
             created_by: repo.created_by,
@@ -192,13 +232,27 @@ pub async fn list_repositories(
         })
         .collect();
 
+    let page = paginate(
+        response_repos,
+        |repo| format!("{}/{}", repo.organization.name, repo.name),
+        &page_query,
+    );
+    let mut page_headers = HeaderMap::new();
+    match &query.namespace {
+        Some(namespace) => {
+            let namespace: String = url::form_urlencoded::byte_serialize(namespace.as_bytes()).collect();
+            page.add_link(&format!("/api/v1/repos/repositories?namespace={}", namespace), &mut page_headers)
+        }
+        None => page.add_link("/api/v1/repos/repositories", &mut page_headers),
+    }
+
     // If a namespace is specified, return a direct list for compatibility
     // If no namespace (all repositories), return wrapped in "repositories" object
     if query.namespace.is_some() {
-        (StatusCode::OK, Json(json!(response_repos))).into_response()
+        (StatusCode::OK, page_headers, Json(json!(page.items))).into_response()
     } else {
-        (StatusCode::OK, Json(json!({
-            "repositories": response_repos
+        (StatusCode::OK, page_headers, Json(json!({
+            "repositories": page.items
         }))).into_response()
     }
 }
@@ -224,12 +278,11 @@ pub async fn list_repositories_by_namespace(
     headers: HeaderMap,
     auth: Option<TypedHeader<Authorization<Bearer>>>,
 ) -> Response {
-    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
     
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
-        secret, 
+        &state.config.auth, 
         &state.db_pool, 
         state.cache.as_ref()
     ).await {
@@ -247,6 +300,7 @@ pub async fn list_repositories_by_namespace(
     )
     .bind(&namespace)
     .fetch_optional(&state.db_pool)
+    .timed("list_repositories_by_namespace: SELECT organizations")
     .await {
         Ok(Some(org)) => org,
         Ok(None) => {
@@ -265,18 +319,19 @@ pub async fn list_repositories_by_namespace(
     let repositories = match sqlx::query_as::<_, RepositoryWithOrgRow>(
         r#"
         SELECT DISTINCT 
-            r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
+            r.id, r.organization_id, r.name, r.display_name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
             o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
         FROM repositories r
         JOIN organizations o ON r.organization_id = o.id
         JOIN organization_members om ON r.organization_id = om.organization_id
-        WHERE om.user_id = $1
+        WHERE om.user_id = $1 AND (om.expires_at IS NULL OR om.expires_at > NOW())
         AND o.name = $2
         "#
     )
     .bind(user_id)
     .bind(&namespace)
     .fetch_all(&state.db_pool)
+    .timed("list_repositories_by_namespace: SELECT repositories")
     .await {
         Ok(repos) => repos,
         Err(e) => {
@@ -292,6 +347,7 @@ pub async fn list_repositories_by_namespace(
             id: repo.id,
             organization_id: repo.organization_id,
             name: repo.name,
+            display_name: repo.display_name,
             description: repo.description,
             is_public: repo.is_public,
             created_by: repo.created_by,
@@ -320,6 +376,8 @@ pub async fn list_repositories_by_namespace(
     request_body = CreateRepositoryRequest,
     responses(
         (status = 200, description = "Repository creation temporarily disabled"),
+        (status = 403, description = "Caller is not a member, or not an owner or admin when REPO_CREATION_REQUIRES_ADMIN is set"),
+        (status = 409, description = "Repository name, or display name when enforced, already taken"),
         (status = 500, description = "Internal server error")
     ),
     security(
@@ -334,12 +392,11 @@ pub async fn create_repository(
     Json(request): Json<CreateRepositoryRequest>,
 ) -> Response {
     // Extract user ID from JWT token or API key
-    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
     
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
-        secret, 
+        &state.config.auth, 
         &state.db_pool, 
         state.cache.as_ref()
     ).await {
@@ -351,12 +408,28 @@ pub async fn create_repository(
         }
     };
     
+    // The depth limit counts the namespace the repository is created in
+    let valid = RepositoryName::parse(&request.name).and_then(|name| {
+        check_repository_depth(&format!("{}/{}", namespace, name), state.config.registry.max_repo_path_segments)
+            .map(|()| name)
+    });
+    let repository_name = match valid {
+        Ok(name) => name,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({
+                "error": e.to_string(),
+                "code": "NAME_INVALID"
+            }))).into_response()
+        }
+    };
+
     // First, find the organization by name
     let org = match sqlx::query_as::<_, Organization>(
         "SELECT * FROM organizations WHERE name = $1"
     )
     .bind(&namespace)
     .fetch_optional(&state.db_pool)
+    .timed("create_repository: SELECT organizations")
     .await {
         Ok(Some(org)) => org,
         Ok(None) => {
@@ -371,19 +444,47 @@ pub async fn create_repository(
         }
     };
 
+    // Only members of the organization may create repositories in its namespace
+    let role = match sqlx::query_scalar::<_, String>(
+        "SELECT role FROM organization_members
+         WHERE organization_id = $1 AND user_id = $2
+           AND (expires_at IS NULL OR expires_at > NOW())"
+    )
+    .bind(org.id)
+    .bind(user_id)
+    .fetch_optional(&state.db_pool)
+    .timed("create_repository: SELECT organization_members")
+    .await {
+        Ok(role) => role.and_then(|role| role.parse::<OrganizationRole>().ok()),
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                "error": format!("Database error: {}", e)
+            }))).into_response()
+        }
+    };
+
+    let requires_admin = state.config.registry.repo_creation_requires_admin;
+    if !role.map_or(false, |role| role.can_create_repository(requires_admin)) {
+        let required = if requires_admin { "an owner or admin" } else { "a member" };
+        return (StatusCode::FORBIDDEN, Json(json!({
+            "error": format!("You must be {} of organization '{}' to create repositories in it", required, namespace)
+        }))).into_response()
+    }
+
     // Check if repository already exists in this organization
     let existing_repo = sqlx::query_scalar::<_, bool>(
         "SELECT EXISTS(SELECT 1 FROM repositories WHERE organization_id = $1 AND name = $2)"
     )
     .bind(org.id)
-    .bind(&request.name)
+    .bind(repository_name.as_str())
     .fetch_one(&state.db_pool)
+    .timed("create_repository: SELECT repositories")
     .await;
 
     match existing_repo {
         Ok(true) => {
             return (StatusCode::CONFLICT, Json(json!({
-                "error": format!("Repository '{}' already exists in organization '{}'", request.name, namespace)
+                "error": format!("Repository '{}' already exists in organization '{}'", repository_name, namespace)
             }))).into_response()
         }
         Err(e) => {
@@ -394,6 +495,32 @@ pub async fn create_repository(
         _ => {} // Continue if repo doesn't exist
     }
 
+    // Display names only need to be unique when the deployment opts in
+    let enforce_unique = state.config.registry.enforce_unique_display_names;
+    if let (true, Some(display_name)) = (enforce_unique, &request.display_name) {
+        match display_name_taken(&state.db_pool, org.id, display_name).await {
+            Ok(true) => return display_name_conflict(display_name, &namespace),
+            Ok(false) => {}
+            Err(e) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                    "error": format!("Database error: {}", e)
+                }))).into_response()
+            }
+        }
+    }
+
+    let is_public = match request.is_public {
+        Some(is_public) => is_public,
+        None => match organization_default_public(&state.db_pool, org.id).await {
+            Ok(org_default) => new_repository_public(None, org_default, state.config.registry.default_repo_visibility),
+            Err(e) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+                    "error": format!("Database error: {}", e)
+                }))).into_response()
+            }
+        },
+    };
+
     // Start a database transaction
     let mut tx = match state.db_pool.begin().await {
         Ok(tx) => tx,
@@ -406,18 +533,27 @@ pub async fn create_repository(
 
     // Create the repository
     let repository = match sqlx::query_as::<_, crate::database::models::Repository>(
-        "INSERT INTO repositories (organization_id, name, description, is_public, created_by, created_at, updated_at)
-         VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "INSERT INTO repositories (organization_id, name, display_name, description, is_public, created_by, require_semver, display_name_claimed, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
          RETURNING *",
     )
     .bind(org.id)
-    .bind(&request.name)
+    .bind(repository_name.as_str())
+    .bind(&request.display_name)
     .bind(&request.description)
-    .bind(request.is_public)
+    .bind(is_public)
     .bind(user_id)
+    .bind(request.require_semver)
+    .bind(enforce_unique && request.display_name.is_some())
     .fetch_one(&mut *tx)
+    .timed("create_repository: INSERT repositories")
     .await {
         Ok(repo) => repo,
+        Err(sqlx::Error::Database(db_err)) if db_err.constraint() == Some(UNIQUE_DISPLAY_NAME_INDEX) => {
+            // Lost a race with a concurrent create using the same display name
+            let _ = tx.rollback().await;
+            return display_name_conflict(request.display_name.as_deref().unwrap_or_default(), &namespace)
+        }
         Err(e) => {
             let _ = tx.rollback().await;
             return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
@@ -432,12 +568,14 @@ pub async fn create_repository(
             "error": format!("Failed to commit transaction: {}", e)
         }))).into_response()
     }
+    invalidate_catalog(&state).await;
 
     // Return the created repository
     let response = RepositoryResponse {
         id: repository.id,
         organization_id: repository.organization_id,
         name: repository.name,
+        display_name: repository.display_name,
         description: repository.description,
         is_public: repository.is_public,
         created_by: repository.created_by,
@@ -455,6 +593,80 @@ pub async fn create_repository(
     (StatusCode::CREATED, Json(response)).into_response()
 }
 
+/// Whether a new repository is public: as requested, else as its
+/// organization's `default_repo_public` says, else DEFAULT_REPO_VISIBILITY
+pub fn new_repository_public(
+    requested: Option<bool>,
+    org_default: Option<bool>,
+    default: RepositoryVisibility,
+) -> bool {
+    requested.or(org_default).unwrap_or(default.is_public())
+}
+
+/// The organization's own default visibility for new repositories, if it set one
+pub(crate) async fn organization_default_public(pool: &sqlx::PgPool, org_id: i64) -> Result<Option<bool>, sqlx::Error> {
+    sqlx::query_scalar::<_, Option<bool>>("SELECT default_repo_public FROM organizations WHERE id = $1")
+        .bind(org_id)
+        .fetch_optional(pool)
+        .timed("organization_default_public")
+        .await
+        .map(Option::flatten)
+}
+
+/// Partial unique index over the display names claimed under
+/// ENFORCE_UNIQUE_DISPLAY_NAMES, created by migration
+pub const UNIQUE_DISPLAY_NAME_INDEX: &str = "idx_repositories_unique_display_name";
+
+/// Whether an organization already has a repository with `display_name`,
+/// ignoring case and surrounding whitespace like the unique index
+pub async fn display_name_taken(pool: &sqlx::PgPool, org_id: i64, display_name: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+             SELECT 1 FROM repositories
+             WHERE organization_id = $1 AND lower(btrim(display_name)) = lower(btrim($2))
+         )"
+    )
+    .bind(org_id)
+    .bind(display_name)
+    .fetch_one(pool)
+    .timed("display_name_taken")
+    .await
+}
+
+/// Display names used by more than one repository of an organization, with
+/// the organization ID and how many repositories share each
+pub async fn duplicate_display_names(pool: &sqlx::PgPool, mode: TenancyMode) -> Result<Vec<(i64, String, i64)>, sqlx::Error> {
+    crate::tenant::across_tenants(pool, mode, || {
+        sqlx::query_as::<_, (i64, String, i64)>(
+            "SELECT organization_id, lower(btrim(display_name)), COUNT(*)
+             FROM repositories
+             WHERE display_name IS NOT NULL
+             GROUP BY organization_id, lower(btrim(display_name))
+             HAVING COUNT(*) > 1
+             ORDER BY organization_id, lower(btrim(display_name))"
+        )
+        .fetch_all(pool)
+    })
+    .timed("duplicate_display_names")
+    .await
+}
+
+fn display_name_conflict(display_name: &str, namespace: &str) -> Response {
+    (StatusCode::CONFLICT, Json(json!({
+        "error": format!("Display name '{}' is already used in organization '{}'", display_name, namespace)
+    }))).into_response()
+}
+
+/// Warn about display names that were already duplicated before
+/// ENFORCE_UNIQUE_DISPLAY_NAMES was turned on; they are kept, but no new
+/// repository can take them
+pub async fn report_duplicate_display_names(pool: &sqlx::PgPool, mode: TenancyMode) -> anyhow::Result<()> {
+    for (organization_id, display_name, count) in duplicate_display_names(pool, mode).await? {
+        tracing::warn!(organization_id, %display_name, count, "Display name is shared by several repositories");
+    }
+    Ok(())
+}
+
 #[utoipa::path(
     delete,
     path = "/api/v1/repos/{namespace}/{repo_name}",
@@ -478,39 +690,17 @@ pub async fn delete_repository(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
 ) -> Response {
-    // Extract JWT token from Authorization header
-    let auth_header = match headers.get("authorization") {
-        Some(header) => header.to_str().unwrap_or(""),
-        None => {
-            return (StatusCode::UNAUTHORIZED, Json(json!({
-                "error": "Missing authorization header"
-            }))).into_response()
-        }
-    };
-
-    let token = if let Some(token) = auth_header.strip_prefix("Bearer ") {
-        token
-    } else {
-        return (StatusCode::UNAUTHORIZED, Json(json!({
-            "error": "Invalid authorization header format"
-        }))).into_response()
-    };
-
-    // Verify JWT token and get user_id
-    let claims = match crate::auth::verify_token(token, state.config.auth.jwt_secret.expose_secret().as_bytes()) {
-        Ok(claims) => claims,
-        Err(_) => {
+    // Verify the bearer JWT and its session, and get user_id
+    let user_id = match bearer_user_id(&headers, &state.config.auth, &state.db_pool).await {
+        Ok(id) => id,
+        Err(StatusCode::UNAUTHORIZED) => {
             return (StatusCode::UNAUTHORIZED, Json(json!({
-                "error": "Invalid or expired token"
+                "error": "Missing, invalid or expired bearer token"
             }))).into_response()
         }
-    };
-
-    let user_id: i64 = match claims.sub.parse() {
-        Ok(id) => id,
-        Err(_) => {
-            return (StatusCode::UNAUTHORIZED, Json(json!({
-                "error": "Invalid user ID in token"
+        Err(status) => {
+            return (status, Json(json!({
+                "error": "Failed to check the token's session"
             }))).into_response()
         }
     };
@@ -531,6 +721,7 @@ pub async fn delete_repository(
     )
     .bind(&namespace)
     .fetch_optional(&mut *tx)
+    .timed("delete_repository: SELECT organizations")
     .await {
         Ok(Some(org)) => org,
         Ok(None) => {
@@ -552,6 +743,7 @@ pub async fn delete_repository(
     .bind(org.id)
     .bind(&repo_name)
     .fetch_optional(&mut *tx)
+    .timed("delete_repository: SELECT repositories")
     .await {
         Ok(Some(repo)) => repo,
         Ok(None) => {
@@ -569,11 +761,12 @@ pub async fn delete_repository(
     // Check if user has permission to delete the repository
     // User must be organization member to delete repositories
     let has_permission = match sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM organization_members WHERE organization_id = $1 AND user_id = $2)"
+        "SELECT EXISTS(SELECT 1 FROM organization_members WHERE organization_id = $1 AND user_id = $2 AND (expires_at IS NULL OR expires_at > NOW()))"
     )
     .bind(org.id)
     .bind(user_id)
     .fetch_one(&mut *tx)
+    .timed("delete_repository: SELECT organization_members")
     .await {
         Ok(has_perm) => has_perm,
         Err(e) => {
@@ -593,6 +786,7 @@ pub async fn delete_repository(
     match sqlx::query("DELETE FROM repositories WHERE id = $1")
         .bind(repository.id)
         .execute(&mut *tx)
+        .timed("delete_repository: DELETE repositories")
         .await {
         Ok(_) => {},
         Err(e) => {
@@ -608,6 +802,7 @@ pub async fn delete_repository(
             "error": format!("Transaction commit error: {}", e)
         }))).into_response()
     }
+    invalidate_catalog(&state).await;
 
     // Return 200 OK with success message
     (StatusCode::OK, Json(json!({
@@ -638,12 +833,11 @@ pub async fn get_repository(
     auth: Option<TypedHeader<Authorization<Bearer>>>,
 ) -> Response {
     // Extract user ID from JWT token or API key
-    let secret = state.config.auth.jwt_secret.expose_secret().as_bytes();
     
     let user_id = match extract_user_id_dual(
         auth, 
         &headers, 
-        secret, 
+        &state.config.auth, 
         &state.db_pool, 
         state.cache.as_ref()
     ).await {
@@ -661,6 +855,7 @@ pub async fn get_repository(
     )
     .bind(&namespace)
     .fetch_optional(&state.db_pool)
+    .timed("get_repository: SELECT organizations")
     .await {
         Ok(Some(org)) => org,
         Ok(None) => {
@@ -682,6 +877,7 @@ pub async fn get_repository(
     .bind(org.id)
     .bind(&repo_name)
     .fetch_optional(&state.db_pool)
+    .timed("get_repository: SELECT repositories")
     .await {
         Ok(Some(repo)) => repo,
         Ok(None) => {
@@ -698,11 +894,12 @@ pub async fn get_repository(
 
     // Check if user has access to this repository (member of organization)
     let has_access = match sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM organization_members WHERE organization_id = $1 AND user_id = $2)"
+        "SELECT EXISTS(SELECT 1 FROM organization_members WHERE organization_id = $1 AND user_id = $2 AND (expires_at IS NULL OR expires_at > NOW()))"
     )
     .bind(org.id)
     .bind(user_id)
     .fetch_one(&state.db_pool)
+    .timed("get_repository: SELECT organization_members")
     .await {
         Ok(has_access) => has_access,
         Err(e) => {
@@ -742,6 +939,7 @@ pub async fn get_repository(
         id: repository.id,
         organization_id: repository.organization_id,
         name: repository.name,
+        display_name: repository.display_name,
         description: repository.description,
         is_public: repository.is_public,
         created_by: repository.created_by,
@@ -782,22 +980,25 @@ pub async fn list_public_repositories(
     State(state): State<AppState>,
     Query(query): Query<ListRepositoriesQuery>,
 ) -> Response {
-    let repositories = if let Some(namespace) = &query.namespace {
+    let mut repositories = if let Some(namespace) = &query.namespace {
         // Filter by organization namespace and is_public = true
-        match sqlx::query_as::<_, RepositoryWithOrgRow>(
-            r#"
-            SELECT 
-                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
-                o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
-            FROM repositories r
-            JOIN organizations o ON r.organization_id = o.id
-            WHERE r.is_public = true
-            AND o.name = $1
-            ORDER BY r.created_at DESC
-            "#
-        )
-        .bind(namespace)
-        .fetch_all(&state.db_pool)
+        match crate::tenant::across_tenants(&state.db_pool, state.config.database.tenancy_mode, || {
+            sqlx::query_as::<_, RepositoryWithOrgRow>(
+                r#"
+                SELECT 
+                    r.id, r.organization_id, r.name, r.display_name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
+                    o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
+                FROM repositories r
+                JOIN organizations o ON r.organization_id = o.id
+                WHERE r.is_public = true
+                AND o.name = $1
+                ORDER BY r.created_at DESC
+                "#
+            )
+            .bind(namespace)
+            .fetch_all(&state.db_pool)
+        })
+        .timed("list_public_repositories")
         .await {
             Ok(repos) => repos,
             Err(e) => {
@@ -808,18 +1009,21 @@ pub async fn list_public_repositories(
         }
     } else {
         // Get all public repositories (is_public = true)
-        match sqlx::query_as::<_, RepositoryWithOrgRow>(
-            r#"
-            SELECT 
-                r.id, r.organization_id, r.name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
-                o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
-            FROM repositories r
-            JOIN organizations o ON r.organization_id = o.id
-            WHERE r.is_public = true
-            ORDER BY r.created_at DESC
-            "#
-        )
-        .fetch_all(&state.db_pool)
+        match crate::tenant::across_tenants(&state.db_pool, state.config.database.tenancy_mode, || {
+            sqlx::query_as::<_, RepositoryWithOrgRow>(
+                r#"
+                SELECT 
+                    r.id, r.organization_id, r.name, r.display_name, r.description, r.is_public, r.created_by, r.created_at, r.updated_at,
+                    o.id as org_id, o.name as org_name, o.display_name as org_display_name, o.description as org_description, o.website_url as org_website_url
+                FROM repositories r
+                JOIN organizations o ON r.organization_id = o.id
+                WHERE r.is_public = true
+                ORDER BY r.created_at DESC
+                "#
+            )
+            .fetch_all(&state.db_pool)
+        })
+        .timed("list_public_repositories")
         .await {
             Ok(repos) => repos,
             Err(e) => {
@@ -829,6 +1033,8 @@ pub async fn list_public_repositories(
             }
         }
     };
+    // Newest first across every tenant
+    repositories.sort_by(|a, b| b.created_at.cmp(&a.created_at));
 
     let response_repositories: Vec<RepositoryResponse> = repositories
         .into_iter()
@@ -836,6 +1042,7 @@ pub async fn list_public_repositories(
             id: repo.id,
             organization_id: repo.organization_id,
             name: repo.name,
+            display_name: repo.display_name,
             description: repo.description,
             is_public: repo.is_public,
             created_by: repo.created_by,
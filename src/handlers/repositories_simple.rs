@@ -7,6 +7,7 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::db::Timed;
 use crate::{
     auth::get_user_id_from_request,
     database::{
@@ -30,7 +31,9 @@ pub struct RepositoryResponse {
     pub name: String,
     pub description: Option<String>,
     pub is_public: bool,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(with = "crate::utils::timestamp")]
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub organization: OrganizationInfo,
 }
@@ -53,6 +56,7 @@ pub struct RepositoryDetailsResponse {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RepositoryStats {
     pub total_tags: i64,
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub last_push: Option<chrono::DateTime<chrono::Utc>>,
 }
 
@@ -81,6 +85,7 @@ pub async fn list_repositories(
         )
         .bind(namespace)
         .fetch_optional(&state.db_pool)
+        .timed("list_repositories: SELECT organizations")
         .await {
             Ok(Some(org)) => org,
             Ok(None) => {
@@ -103,13 +108,14 @@ pub async fn list_repositories(
             FROM repositories r
             JOIN organizations o ON r.organization_id = o.id
             JOIN organization_members om ON r.organization_id = om.organization_id
-            WHERE om.user_id = $1
+            WHERE om.user_id = $1 AND (om.expires_at IS NULL OR om.expires_at > NOW())
             AND o.name = $2
             "#
         )
         .bind(user_id)
         .bind(namespace)
         .fetch_all(&state.db_pool)
+        .timed("list_repositories: SELECT repositories")
         .await {
             Ok(repos) => repos,
             Err(e) => {
@@ -128,11 +134,12 @@ pub async fn list_repositories(
             FROM repositories r
             JOIN organizations o ON r.organization_id = o.id
             JOIN organization_members om ON r.organization_id = om.organization_id
-            WHERE om.user_id = $1
+            WHERE om.user_id = $1 AND (om.expires_at IS NULL OR om.expires_at > NOW())
             "#
         )
         .bind(user_id)
         .fetch_all(&state.db_pool)
+        .timed("list_repositories: SELECT repositories")
         .await {
             Ok(repos) => repos,
             Err(e) => {
@@ -160,7 +160,7 @@ pub async fn blob_metadata(
             Ok(Json(BlobMetadataResponse {
                 size: data.len() as u64,
                 digest: digest.clone(),
-                created_at: chrono::Utc::now().to_rfc3339(),
+                created_at: crate::utils::timestamp::format(&chrono::Utc::now()),
                 content_type: Some("application/octet-stream".to_string()),
             }))
         },
@@ -213,7 +213,7 @@ pub async fn upload_blob_streaming(
     metadata_store.insert(digest.clone(), BlobMetadataResponse {
         size: data.len() as u64,
         digest: digest.clone(),
-        created_at: chrono::Utc::now().to_rfc3339(),
+        created_at: crate::utils::timestamp::format(&chrono::Utc::now()),
         content_type: Some("application/octet-stream".to_string()),
     });
 
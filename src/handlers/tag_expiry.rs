@@ -0,0 +1,533 @@
+// Tag expiry (TTL) rules and the background task that applies them
+use anyhow::Result;
+use axum::{
+    extract::{Path, State},
+    http::{StatusCode, HeaderMap},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::headers::{Authorization, authorization::Bearer};
+use axum_extra::TypedHeader;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use validator::Validate;
+
+use crate::auth::extract_user_id_dual;
+use crate::cache::repository_pull_names;
+use crate::db::Timed;
+use crate::error::{error_response, AppError};
+use crate::models::organizations::{OrganizationAction, OrganizationRole};
+use crate::models::tag_expiry::{
+    expired_tag_names, CreateTagExpiryRuleRequest, TagExpiryRule, UpdateTagExpiryRuleRequest,
+};
+use crate::tag_lock::{tag_lock_key, with_locks, TagLockError, TAG_LOCK_TTL, TAG_LOCK_WAIT};
+use crate::AppState;
+
+// List tag expiry rules of a repository
+#[utoipa::path(
+    get,
+    path = "/api/v1/repos/{namespace}/{repo_name}/tag-expiry-rules",
+    tag = "repositories",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    responses(
+        (status = 200, description = "Tag expiry rules", body = Vec<TagExpiryRule>),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Not a member of the organization"),
+        (status = 404, description = "Repository not found"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_tag_expiry_rules(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((namespace, repo_name)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth, &headers).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match list_rules_internal(&state.db_pool, &namespace, &repo_name, user_id).await {
+        Ok(rules) => (StatusCode::OK, Json(serde_json::json!(rules))),
+        Err(e) => error_response(&e, StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+// Create a tag expiry rule
+#[utoipa::path(
+    post,
+    path = "/api/v1/repos/{namespace}/{repo_name}/tag-expiry-rules",
+    tag = "repositories",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name")
+    ),
+    request_body = CreateTagExpiryRuleRequest,
+    responses(
+        (status = 201, description = "Rule created", body = TagExpiryRule),
+        (status = 400, description = "Validation failed"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only organization owners and admins can manage rules"),
+        (status = 404, description = "Repository not found"),
+        (status = 409, description = "A rule with this pattern already exists"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_tag_expiry_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((namespace, repo_name)): Path<(String, String)>,
+    Json(req): Json<CreateTagExpiryRuleRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Validation failed",
+                "details": validation_errors
+            })),
+        );
+    }
+
+    let user_id = match authenticate(&state, auth, &headers).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match create_rule_internal(&state.db_pool, &namespace, &repo_name, &req, user_id).await {
+        Ok(rule) => (StatusCode::CREATED, Json(serde_json::json!(rule))),
+        Err(e) => error_response(&e, StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+// Update a tag expiry rule
+#[utoipa::path(
+    put,
+    path = "/api/v1/repos/{namespace}/{repo_name}/tag-expiry-rules/{rule_id}",
+    tag = "repositories",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("rule_id" = i64, Path, description = "Rule ID")
+    ),
+    request_body = UpdateTagExpiryRuleRequest,
+    responses(
+        (status = 200, description = "Rule updated", body = TagExpiryRule),
+        (status = 400, description = "Validation failed"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only organization owners and admins can manage rules"),
+        (status = 404, description = "Repository or rule not found"),
+        (status = 409, description = "A rule with this pattern already exists"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn update_tag_expiry_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((namespace, repo_name, rule_id)): Path<(String, String, i64)>,
+    Json(req): Json<UpdateTagExpiryRuleRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Validation failed",
+                "details": validation_errors
+            })),
+        );
+    }
+
+    let user_id = match authenticate(&state, auth, &headers).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match update_rule_internal(&state.db_pool, &namespace, &repo_name, rule_id, &req, user_id).await {
+        Ok(rule) => (StatusCode::OK, Json(serde_json::json!(rule))),
+        Err(e) => error_response(&e, StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+// Delete a tag expiry rule
+#[utoipa::path(
+    delete,
+    path = "/api/v1/repos/{namespace}/{repo_name}/tag-expiry-rules/{rule_id}",
+    tag = "repositories",
+    params(
+        ("namespace" = String, Path, description = "Organization namespace"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        ("rule_id" = i64, Path, description = "Rule ID")
+    ),
+    responses(
+        (status = 200, description = "Rule deleted"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only organization owners and admins can manage rules"),
+        (status = 404, description = "Repository or rule not found"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_tag_expiry_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    Path((namespace, repo_name, rule_id)): Path<(String, String, i64)>,
+) -> impl IntoResponse {
+    let user_id = match authenticate(&state, auth, &headers).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match delete_rule_internal(&state.db_pool, &namespace, &repo_name, rule_id, user_id).await {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "message": "Tag expiry rule deleted successfully"
+            })),
+        ),
+        Err(e) => error_response(&e, StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn authenticate(
+    state: &AppState,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    headers: &HeaderMap,
+) -> std::result::Result<i64, (StatusCode, Json<serde_json::Value>)> {
+
+    extract_user_id_dual(auth, headers, &state.config.auth, &state.db_pool, state.cache.as_ref())
+        .await
+        .map_err(|status| {
+            (
+                status,
+                Json(serde_json::json!({
+                    "error": "Unauthorized"
+                })),
+            )
+        })
+}
+
+/// Resolve `namespace/repo_name` to a repository ID the user may read rules of,
+/// or manage them when `manage` is set (organization owners and admins).
+async fn authorize_repository(
+    pool: &PgPool,
+    namespace: &str,
+    repo_name: &str,
+    user_id: i64,
+    manage: bool,
+) -> Result<i64> {
+    let row = sqlx::query_as::<_, (i64, Option<String>)>(
+        "SELECT r.id, om.role
+         FROM repositories r
+         JOIN organizations o ON r.organization_id = o.id
+         LEFT JOIN organization_members om ON om.organization_id = o.id AND om.user_id = $3
+              AND (om.expires_at IS NULL OR om.expires_at > NOW())
+         WHERE o.name = $1 AND r.name = $2",
+    )
+    .bind(namespace)
+    .bind(repo_name)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .timed("authorize_repository")
+    .await?;
+
+    let (repository_id, role) = match row {
+        Some(row) => row,
+        None => {
+            return Err(AppError::NotFound(format!("Repository '{}/{}' not found", namespace, repo_name)).into())
+        }
+    };
+
+    let role = match role.and_then(|r| r.parse::<OrganizationRole>().ok()) {
+        Some(role) => role,
+        None => return Err(AppError::Forbidden("Not a member of this organization".to_string()).into()),
+    };
+
+    if manage && !role.allows(OrganizationAction::ManageTagExpiry) {
+        return Err(AppError::Forbidden(
+            "Only organization owners and admins can manage tag expiry rules".to_string(),
+        )
+        .into());
+    }
+
+    Ok(repository_id)
+}
+
+async fn list_rules_internal(
+    pool: &PgPool,
+    namespace: &str,
+    repo_name: &str,
+    user_id: i64,
+) -> Result<Vec<TagExpiryRule>> {
+    let repository_id = authorize_repository(pool, namespace, repo_name, user_id, false).await?;
+
+    let rules = sqlx::query_as::<_, TagExpiryRule>(
+        "SELECT * FROM tag_expiry_rules WHERE repository_id = $1 ORDER BY id",
+    )
+    .bind(repository_id)
+    .fetch_all(pool)
+    .timed("list_rules_internal")
+    .await?;
+
+    Ok(rules)
+}
+
+async fn create_rule_internal(
+    pool: &PgPool,
+    namespace: &str,
+    repo_name: &str,
+    req: &CreateTagExpiryRuleRequest,
+    user_id: i64,
+) -> Result<TagExpiryRule> {
+    let repository_id = authorize_repository(pool, namespace, repo_name, user_id, true).await?;
+
+    let rule = sqlx::query_as::<_, TagExpiryRule>(
+        "INSERT INTO tag_expiry_rules (repository_id, pattern, ttl_days, created_by)
+         VALUES ($1, $2, $3, $4)
+         RETURNING *",
+    )
+    .bind(repository_id)
+    .bind(&req.pattern)
+    .bind(req.ttl_days)
+    .bind(user_id)
+    .fetch_one(pool)
+    .timed("create_rule_internal")
+    .await
+    .map_err(|e| duplicate_pattern(e, &req.pattern))?;
+
+    Ok(rule)
+}
+
+async fn update_rule_internal(
+    pool: &PgPool,
+    namespace: &str,
+    repo_name: &str,
+    rule_id: i64,
+    req: &UpdateTagExpiryRuleRequest,
+    user_id: i64,
+) -> Result<TagExpiryRule> {
+    let repository_id = authorize_repository(pool, namespace, repo_name, user_id, true).await?;
+
+    let rule = sqlx::query_as::<_, TagExpiryRule>(
+        "UPDATE tag_expiry_rules
+         SET pattern = COALESCE($3, pattern),
+             ttl_days = COALESCE($4, ttl_days),
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND repository_id = $2
+         RETURNING *",
+    )
+    .bind(rule_id)
+    .bind(repository_id)
+    .bind(&req.pattern)
+    .bind(req.ttl_days)
+    .fetch_optional(pool)
+    .timed("update_rule_internal")
+    .await
+    .map_err(|e| duplicate_pattern(e, req.pattern.as_deref().unwrap_or_default()))?;
+
+    match rule {
+        Some(rule) => Ok(rule),
+        None => Err(AppError::NotFound(format!("Tag expiry rule {} not found", rule_id)).into()),
+    }
+}
+
+async fn delete_rule_internal(
+    pool: &PgPool,
+    namespace: &str,
+    repo_name: &str,
+    rule_id: i64,
+    user_id: i64,
+) -> Result<()> {
+    let repository_id = authorize_repository(pool, namespace, repo_name, user_id, true).await?;
+
+    let result = sqlx::query("DELETE FROM tag_expiry_rules WHERE id = $1 AND repository_id = $2")
+        .bind(rule_id)
+        .bind(repository_id)
+        .execute(pool)
+        .timed("delete_rule_internal")
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Tag expiry rule {} not found", rule_id)).into());
+    }
+
+    Ok(())
+}
+
+fn duplicate_pattern(err: sqlx::Error, pattern: &str) -> anyhow::Error {
+    match &err {
+        sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+            AppError::Conflict(format!("A tag expiry rule for pattern '{}' already exists", pattern)).into()
+        }
+        _ => err.into(),
+    }
+}
+
+/// Delete the named tags of a repository in one transaction, holding their
+/// tag locks so a concurrent push to one of them runs before or after. With
+/// `remove_orphaned_manifests`, manifests that lost their last tag are removed
+/// in the same transaction and their blobs queued for cleanup, as for a
+/// repository deletion. `repository_names` are the names the repository is
+/// pushed and pulled by, as `repository_pull_names` gives them; the tags are
+/// locked and dropped from the cache under each. Returns the number of tags
+/// removed.
+pub async fn delete_tags(
+    state: &AppState,
+    repository_id: i64,
+    repository_names: &[String],
+    tags: &[String],
+    remove_orphaned_manifests: bool,
+) -> Result<u64> {
+    if tags.is_empty() {
+        return Ok(0);
+    }
+
+    let deleted = async {
+        let mut tx = state.db_pool.begin().await?;
+
+        let manifest_ids: Vec<i64> = sqlx::query_scalar(
+            "DELETE FROM tags WHERE repository_id = $1 AND name = ANY($2) RETURNING manifest_id",
+        )
+        .bind(repository_id)
+        .bind(tags)
+        .fetch_all(&mut *tx)
+        .timed("delete_tags: DELETE tags")
+        .await?;
+
+        let orphaned: Vec<(String, Option<String>)> = if remove_orphaned_manifests {
+            sqlx::query_as(
+                "DELETE FROM manifests m
+                 WHERE m.id = ANY($1)
+                   AND NOT EXISTS (SELECT 1 FROM tags t WHERE t.manifest_id = m.id)
+                 RETURNING m.digest, m.content",
+            )
+            .bind(&manifest_ids)
+            .fetch_all(&mut *tx)
+            .timed("delete_tags: DELETE manifests")
+            .await?
+        } else {
+            Vec::new()
+        };
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>((manifest_ids.len() as u64, orphaned))
+    };
+    let (removed, orphaned) = match &state.cache {
+        Some(cache) => {
+            let keys: Vec<String> = repository_names
+                .iter()
+                .flat_map(|name| tags.iter().map(move |tag| tag_lock_key(name, tag)))
+                .collect();
+            with_locks(cache.as_ref(), &keys, TAG_LOCK_TTL, TAG_LOCK_WAIT, deleted).await??
+        }
+        None => deleted.await?,
+    };
+
+    if !orphaned.is_empty() {
+        {
+            let mut manifest_cache = state.manifest_cache.write().await;
+            for (digest, _) in &orphaned {
+                manifest_cache.remove(digest);
+            }
+        }
+        crate::handlers::docker_registry_v2::queue_blob_cleanup(state, &orphaned);
+    }
+
+    if let Some(cache) = &state.cache {
+        for repository_name in repository_names {
+            let references = tags.iter().chain(orphaned.iter().map(|(digest, _)| digest));
+            for reference in references {
+                let manifest_cache_key = crate::cache::manifest_cache_key(repository_name, reference);
+                if let Err(e) = cache.invalidate_manifest(&manifest_cache_key).await {
+                    tracing::warn!("Failed to invalidate manifest cache: {}", e);
+                }
+            }
+            if let Err(e) = cache.invalidate_tags(repository_name).await {
+                tracing::warn!("Failed to invalidate tags cache: {}", e);
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Evaluate every tag expiry rule of every tenant and delete the tags they
+/// have expired. Returns the total number of tags removed.
+pub async fn apply_tag_expiry_rules(state: &AppState) -> Result<u64> {
+    let removed = crate::tenant::across_tenants(&state.db_pool, state.config.database.tenancy_mode, || async move {
+        apply_tenant_tag_expiry_rules(state).await.map(|removed| vec![removed])
+    })
+    .await?;
+    Ok(removed.into_iter().sum())
+}
+
+async fn apply_tenant_tag_expiry_rules(state: &AppState) -> Result<u64> {
+    let rules = sqlx::query_as::<_, TagExpiryRule>("SELECT * FROM tag_expiry_rules")
+        .fetch_all(&state.db_pool)
+        .timed("apply_tenant_tag_expiry_rules: SELECT tag_expiry_rules")
+        .await?;
+
+    let mut rules_by_repository: HashMap<i64, Vec<TagExpiryRule>> = HashMap::new();
+    for rule in rules {
+        rules_by_repository.entry(rule.repository_id).or_default().push(rule);
+    }
+
+    let now = Utc::now();
+    let mut removed = 0;
+
+    for (repository_id, rules) in rules_by_repository {
+        let (organization, repository, organization_id): (String, String, i64) = sqlx::query_as(
+            "SELECT o.name, r.name, o.id
+             FROM repositories r
+             JOIN organizations o ON r.organization_id = o.id
+             WHERE r.id = $1",
+        )
+        .bind(repository_id)
+        .fetch_one(&state.db_pool)
+        .timed("apply_tenant_tag_expiry_rules: SELECT repositories")
+        .await?;
+        let repository_names = repository_pull_names(&organization, &repository, organization_id);
+        let repository_name = &repository_names[0];
+
+        let tags = sqlx::query_as::<_, (String, DateTime<Utc>)>(
+            "SELECT name, updated_at FROM tags WHERE repository_id = $1",
+        )
+        .bind(repository_id)
+        .fetch_all(&state.db_pool)
+        .timed("apply_tenant_tag_expiry_rules: SELECT tags")
+        .await?;
+
+        let expired = expired_tag_names(&rules, &tags, now);
+        if expired.is_empty() {
+            continue;
+        }
+
+        let count = match delete_tags(state, repository_id, &repository_names, &expired, true).await {
+            Ok(count) => count,
+            // Left for the next run
+            Err(e) if e.is::<TagLockError>() => {
+                tracing::warn!(repository = %repository_name, "Expired tags not deleted: {}", e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        tracing::info!(repository = %repository_name, tags = ?expired, "Expired {} tag(s)", count);
+        removed += count;
+    }
+
+    Ok(removed)
+}
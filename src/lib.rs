@@ -2,32 +2,66 @@ use sqlx::PgPool;
 use std::sync::Arc;
 use std::collections::HashMap;
 use tokio::sync::RwLock;
-use axum::{Router, response::Html, http::{StatusCode, Uri}};
+use axum::{Router, response::{Html, IntoResponse, Response}, http::{StatusCode, Uri}};
 use axum::routing::get;
 use tower_http::services::{ServeDir, ServeFile};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 pub mod auth;
+pub mod auth_events;
+pub mod background;
 pub mod cache;
+pub mod cache_warmup;
+pub mod compression;
 pub mod config;
+pub mod correlation;
 pub mod database;
 pub mod db;
 pub mod email;
+pub mod error;
 pub mod handlers;
+pub mod login_throttle;
 pub mod models;
+pub mod nonce;
+pub mod oci_layout;
 pub mod openapi;
+pub mod pull_recorder;
+pub mod read_only;
 pub mod routes;
+pub mod runtime;
+pub mod security;
+pub mod server_timing;
+pub mod shutdown;
+pub mod startup;
 pub mod storage;
+pub mod tag_lock;
+pub mod tenant;
+pub mod utils;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: PgPool,
     pub config: config::Settings,
     pub storage: Arc<dyn storage::Storage>,
+    /// Chooses the backend new content is written to; `storage` reads through it
+    pub storage_router: Arc<storage::router::StorageRouter>,
     pub cache: Option<Arc<cache::RegistryCache>>,
     pub manifest_cache: Arc<RwLock<HashMap<String, String>>>, // digest -> content
     pub email_service: Arc<email::EmailService>,
+    pub login_throttle: Arc<login_throttle::LoginThrottle>,
+    /// Cleared on shutdown so `/health/ready` fails while connections drain
+    pub readiness: shutdown::Readiness,
+    /// Refuses writes and pauses background writers while set
+    pub read_only: read_only::ReadOnlyMode,
+    /// One-time nonces for sensitive requests, in Redis when the cache has it
+    pub nonces: Arc<dyn nonce::NonceStore>,
+    /// Old organization names still redirected to the renamed organization
+    pub org_aliases: Arc<handlers::organizations::OrgAliasCache>,
+    /// Whether some organization blocks vulnerable pulls, per tenant
+    pub blocking_organizations: Arc<handlers::docker_registry_v2::BlockingOrganizationsCache>,
+    /// Counts manifest pulls in the background
+    pub pulls: pull_recorder::PullRecorder,
 }
 
 // Function to detect correct paths for static files
@@ -83,35 +117,69 @@ async fn serve_spa() -> Result<Html<String>, StatusCode> {
 }
 
 // Fallback handler for SPA routes
-async fn spa_fallback(uri: Uri) -> Result<Html<String>, StatusCode> {
+async fn spa_fallback(uri: Uri) -> Response {
     let path = uri.path();
     
     // Don't handle API routes
     if path.starts_with("/api") || path.starts_with("/v2") || path.starts_with("/docs") {
-        return Err(StatusCode::NOT_FOUND);
+        return error::route_not_found(uri).await;
     }
     
     // For all other routes, serve the SPA
-    serve_spa().await
+    match serve_spa().await {
+        Ok(html) => html.into_response(),
+        Err(_) => error::route_not_found(uri).await,
+    }
 }
 
 /// Create the main Axum application router
 pub async fn create_app(state: AppState) -> Router {
     // Register API documentation
     let openapi = openapi::ApiDoc::openapi();
+    error::set_retry_after_jitter_percent(state.config.server.retry_after_jitter_percent);
     
-    // API routes with state
-    let api_router = Router::new()
+    // API, health and docs routes, gzipped when ENABLE_COMPRESSION is set
+    let compressible_router = Router::new()
         .nest("/api/v1", routes::api::api_router())
-        // Docker Registry V2 API routes - direct routes to avoid nesting conflicts
-        .merge(routes::docker_registry_v2::docker_registry_v2_router())
         // Health and monitoring endpoints  
         .merge(routes::health::health_router())
+        // Admin-only profiling, disabled unless ENABLE_PROFILING is set
+        .merge(routes::debug::debug_router())
+        // Admin-only operator endpoints
+        .merge(routes::admin::admin_router())
         // Serve Swagger UI
-        .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", openapi))
+        .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", openapi));
+    let compressible_router =
+        compression::compress(compressible_router, state.config.server.enable_compression);
+
+    // API routes with state
+    let api_router = compressible_router
+        // Docker Registry V2 API routes - direct routes to avoid nesting conflicts.
+        // Merged after compression so blobs are always served as stored
+        .merge(
+            routes::docker_registry_v2::docker_registry_v2_router().layer(
+                axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    handlers::docker_registry_v2::redirect_renamed_organization,
+                ),
+            ),
+        )
+        .layer(axum::middleware::from_fn_with_state(state.clone(), tenant::tenant_middleware))
+        .layer(axum::middleware::from_fn_with_state(
+            state.read_only.clone(),
+            read_only::read_only_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            server_timing::server_timing_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            auth_events::auth_event_middleware,
+        ))
         .layer(tower_http::trace::TraceLayer::new_for_http())
         .layer(tower_http::cors::CorsLayer::permissive())
-        .with_state(state);
+        .with_state(state.clone());
 
     // Detect the correct path for static files
     let (assets_path, favicon_path) = detect_frontend_paths();
@@ -127,4 +195,9 @@ pub async fn create_app(state: AppState) -> Router {
     Router::new()
         .merge(api_router)
         .merge(static_router)
+        .layer(axum::middleware::from_fn_with_state(state.clone(), security::hsts_middleware))
+        .layer(axum::middleware::from_fn_with_state(
+            correlation::CorrelationConfig::from_settings(&state.config.server),
+            correlation::correlation_middleware,
+        ))
 }
\ No newline at end of file
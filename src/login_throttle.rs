@@ -0,0 +1,196 @@
+// Per-IP login throttling with an external challenge (CAPTCHA) step
+//
+// After too many failed logins from one address, further attempts must carry
+// a challenge token, which a `ChallengeVerifier` checks before the password is.
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use secrecy::{ExposeSecret, Secret};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::settings::AuthSettings;
+
+/// Tracked addresses above which expired entries are pruned
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Checks a challenge token presented by a client
+#[async_trait]
+pub trait ChallengeVerifier: Send + Sync {
+    /// Whether `token` proves the client at `client_ip` passed the challenge
+    async fn verify(&self, token: &str, client_ip: &str) -> bool;
+}
+
+/// Verifier for `siteverify`-style CAPTCHA services (reCAPTCHA, hCaptcha,
+/// Turnstile): POST the secret and token, read `success` from the JSON reply.
+pub struct HttpChallengeVerifier {
+    client: reqwest::Client,
+    verify_url: String,
+    secret: Secret<String>,
+}
+
+impl HttpChallengeVerifier {
+    pub fn new(verify_url: String, secret: Secret<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self { client, verify_url, secret }
+    }
+}
+
+#[async_trait]
+impl ChallengeVerifier for HttpChallengeVerifier {
+    async fn verify(&self, token: &str, client_ip: &str) -> bool {
+        let params = [
+            ("secret", self.secret.expose_secret().as_str()),
+            ("response", token),
+            ("remoteip", client_ip),
+        ];
+
+        let body = match self.client.post(&self.verify_url).form(&params).send().await {
+            Ok(response) => response.text().await,
+            Err(e) => Err(e),
+        };
+
+        // An unreachable verifier fails closed
+        match body.map(|text| serde_json::from_str::<serde_json::Value>(&text)) {
+            Ok(Ok(reply)) => reply["success"].as_bool().unwrap_or(false),
+            Ok(Err(e)) => {
+                tracing::warn!("Invalid reply from challenge verifier: {}", e);
+                false
+            }
+            Err(e) => {
+                tracing::warn!("Challenge verifier request failed: {}", e);
+                false
+            }
+        }
+    }
+}
+
+/// Whether a login attempt may go on to password verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginGate {
+    Allowed,
+    /// Too many failures and no challenge token was sent
+    ChallengeRequired,
+    /// A challenge token was sent but did not verify
+    ChallengeFailed,
+}
+
+/// Failed login counts per client address, within a sliding window
+pub struct LoginThrottle {
+    threshold: u32,
+    window: Duration,
+    verifier: Option<Arc<dyn ChallengeVerifier>>,
+    failures: Mutex<HashMap<String, (u32, Instant)>>,
+}
+
+impl LoginThrottle {
+    /// `threshold` failures within `window` require a challenge. Without a
+    /// verifier (or with a zero threshold) no challenge is ever required.
+    pub fn new(threshold: u32, window: Duration, verifier: Option<Arc<dyn ChallengeVerifier>>) -> Self {
+        Self {
+            threshold,
+            window,
+            verifier,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Throttle backed by the configured CAPTCHA service, if any
+    pub fn from_settings(settings: &AuthSettings) -> Self {
+        let verifier = settings.captcha_verify_url.clone().map(|url| {
+            let secret = settings
+                .captcha_secret
+                .clone()
+                .unwrap_or_else(|| Secret::new(String::new()));
+            Arc::new(HttpChallengeVerifier::new(url, secret)) as Arc<dyn ChallengeVerifier>
+        });
+
+        Self::new(
+            settings.login_challenge_threshold,
+            Duration::from_secs(settings.login_challenge_window_secs),
+            verifier,
+        )
+    }
+
+    /// Failed attempts from `client_ip` within the current window
+    pub fn failure_count(&self, client_ip: &str) -> u32 {
+        let failures = self.failures.lock().unwrap();
+        match failures.get(client_ip) {
+            Some((count, first_failure)) if first_failure.elapsed() < self.window => *count,
+            _ => 0,
+        }
+    }
+
+    pub fn challenge_required(&self, client_ip: &str) -> bool {
+        self.verifier.is_some() && self.threshold > 0 && self.failure_count(client_ip) >= self.threshold
+    }
+
+    /// Decide whether a login attempt may proceed, verifying its challenge
+    /// token when one is required
+    pub async fn check(&self, client_ip: &str, challenge_token: Option<&str>) -> LoginGate {
+        if !self.challenge_required(client_ip) {
+            return LoginGate::Allowed;
+        }
+
+        let (verifier, token) = match (&self.verifier, challenge_token.filter(|t| !t.is_empty())) {
+            (Some(verifier), Some(token)) => (verifier, token),
+            _ => return LoginGate::ChallengeRequired,
+        };
+
+        if verifier.verify(token, client_ip).await {
+            LoginGate::Allowed
+        } else {
+            LoginGate::ChallengeFailed
+        }
+    }
+
+    pub fn record_failure(&self, client_ip: &str) {
+        let mut failures = self.failures.lock().unwrap();
+        if failures.len() > PRUNE_THRESHOLD {
+            let window = self.window;
+            failures.retain(|_, (_, first_failure)| first_failure.elapsed() < window);
+        }
+
+        let entry = failures.entry(client_ip.to_string()).or_insert((0, Instant::now()));
+        if entry.1.elapsed() >= self.window {
+            *entry = (0, Instant::now());
+        }
+        entry.0 += 1;
+    }
+
+    /// A successful login clears the address's failures
+    pub fn record_success(&self, client_ip: &str) {
+        self.failures.lock().unwrap().remove(client_ip);
+    }
+}
+
+/// Address a request came from. `X-Forwarded-For` is only read when the
+/// peer is one of `trusted_proxies`, as anyone else can put anything in it;
+/// the client is then the right-most entry that is not a trusted proxy.
+/// Otherwise, and when the header has no usable entry, it is the peer address.
+pub fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>, trusted_proxies: &[IpAddr]) -> String {
+    let peer = match peer {
+        Some(addr) => addr.ip(),
+        None => return "unknown".to_string(),
+    };
+    if !trusted_proxies.contains(&peer) {
+        return peer.to_string();
+    }
+
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .find(|ip| !trusted_proxies.contains(ip))
+        .unwrap_or(peer)
+        .to_string()
+}
@@ -1,21 +1,30 @@
 use aerugo::{create_app, AppState};
 use aerugo::config::Settings;
 use aerugo::storage::{Storage, s3::S3Storage};
-use aerugo::cache::{RegistryCache, CacheConfig};
+use aerugo::cache::{RegistryCache, CacheConfig, CacheKeyType};
 use anyhow::{Result, Context};
+use std::future::IntoFuture;
 use std::sync::Arc;
 use std::time::Duration;
 use std::process::{Command, Stdio};
 use secrecy::ExposeSecret;
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
     // Load configuration
     let settings = Settings::load().expect("Failed to load configuration");
     settings.validate_all().expect("Invalid configuration");
 
+    // Build the runtime by hand so worker/blocking thread counts are configurable
+    let runtime = aerugo::runtime::build_runtime(&settings.server)
+        .context("Failed to build Tokio runtime")?;
+
+    runtime.block_on(run(settings))
+}
+
+async fn run(settings: Settings) -> Result<()> {
     // Initialize tracing
     tracing_subscriber::fmt::init();
+    settings.log_effective_config();
 
     // Start frontend development server in debug mode
     // Disabled to serve static files via backend instead
@@ -34,6 +43,33 @@ async fn main() -> Result<()> {
     }
     println!();
 
+    // Bind early: until startup completes every request gets a 503 saying
+    // what is still being waited on, rather than a refused connection
+    println!("Preparing to start server...");
+    let listen_address = settings.server.bind_address.clone();
+    println!("Listen address: {}", listen_address);
+    let addr: std::net::SocketAddr = listen_address.parse()?;
+    println!("Parsed address: {}", addr);
+    
+    println!("Creating TCP listener...");
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    println!("TCP listener created successfully");
+    
+    aerugo::error::set_retry_after_jitter_percent(settings.server.retry_after_jitter_percent);
+    let startup = aerugo::startup::StartupGate::new(&["database", "storage", "cache"]);
+    let readiness = aerugo::shutdown::Readiness::default();
+    tracing::info!("listening on {}", addr);
+    println!("Starting axum server...");
+    let server = tokio::spawn(
+        axum::serve(listener, startup.router().into_make_service_with_connect_info::<std::net::SocketAddr>())
+            .with_graceful_shutdown(aerugo::shutdown::drain(
+                aerugo::shutdown::shutdown_signal(),
+                readiness.clone(),
+                Duration::from_secs(settings.server.shutdown_drain_delay_secs),
+            ))
+            .into_future(),
+    );
+
     // Initialize database connection and run migrations
     println!("Initializing database connection and running migrations...");
     let db_pool = aerugo::db::create_pool(&settings)
@@ -41,6 +77,7 @@ async fn main() -> Result<()> {
         .context("Failed to create database pool and run migrations")?;
     
     println!("Database connection and migrations completed successfully");
+    startup.dependency_ready("database");
 
     // Initialize S3 storage
     println!("Initializing S3 storage...");
@@ -64,20 +101,38 @@ async fn main() -> Result<()> {
             .expect("Failed to initialize S3 storage")
     );
     println!("S3 storage initialized successfully");
+    startup.dependency_ready("storage");
+
+    // Route content to the configured backends
+    let storage_router = Arc::new(
+        aerugo::storage::router::StorageRouter::from_settings(&settings.storage, storage)
+            .context("Invalid storage routing configuration")?
+    );
+    let storage: Arc<dyn Storage> = storage_router.clone();
+
+    // Measure storage calls for the Server-Timing header
+    let storage: Arc<dyn Storage> = if settings.server.enable_server_timing {
+        println!("Server-Timing enabled");
+        Arc::new(aerugo::storage::timed::TimedStorage::new(storage))
+    } else {
+        storage
+    };
 
     // Initialize cache
     println!("Initializing cache layer...");
     let cache_config = CacheConfig {
         redis_url: Some(settings.cache.redis_url.clone()),
-        manifest_ttl: Duration::from_secs(settings.cache.ttl_seconds),
+        manifest_ttl: settings.cache.ttl(CacheKeyType::Manifest),
         blob_metadata_ttl: Duration::from_secs(settings.cache.ttl_seconds * 2), // 2x longer for blob metadata
         repository_ttl: Duration::from_secs(60), // 1 minute for repo lists
-        tag_ttl: Duration::from_secs(120), // 2 minutes for tag lists
+        tag_ttl: settings.cache.ttl(CacheKeyType::Tag),
+        catalog_ttl: settings.cache.ttl(CacheKeyType::Catalog),
         // Authentication cache TTLs
         auth_token_ttl: Duration::from_secs(900), // 15 minutes
-        permission_ttl: Duration::from_secs(300), // 5 minutes
+        permission_ttl: settings.cache.ttl(CacheKeyType::Authz),
         session_ttl: Duration::from_secs(1800), // 30 minutes
         max_memory_entries: 10000,
+        max_entry_bytes: settings.cache.max_entry_bytes,
         enable_redis: true,
         enable_memory: true,
     };
@@ -92,6 +147,7 @@ async fn main() -> Result<()> {
             None
         }
     };
+    startup.dependency_ready("cache");
 
     // Initialize email service
     println!("Initializing email service...");
@@ -110,48 +166,61 @@ async fn main() -> Result<()> {
         }
     };
 
+    let nonces: Arc<dyn aerugo::nonce::NonceStore> = match &cache {
+        Some(cache) => cache.clone(),
+        None => Arc::new(aerugo::nonce::LocalNonces::default()),
+    };
+
     // Create shared application state
     let state = AppState {
         db_pool: db_pool.clone(),
         config: settings.clone(),
         storage,
+        storage_router,
         cache,
         manifest_cache: Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new())),
         email_service,
+        login_throttle: Arc::new(aerugo::login_throttle::LoginThrottle::from_settings(&settings.auth)),
+        readiness,
+        read_only: aerugo::read_only::ReadOnlyMode::new(settings.registry.read_only_mode),
+        nonces,
+        org_aliases: Arc::new(aerugo::handlers::organizations::OrgAliasCache::default()),
+        blocking_organizations: Arc::new(aerugo::handlers::docker_registry_v2::BlockingOrganizationsCache::default()),
+        pulls: aerugo::pull_recorder::PullRecorder::start(db_pool.clone()),
     };
     println!("Application state created successfully");
 
-    // Start background task to cleanup expired API keys
-    let cleanup_db_pool = db_pool.clone();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(3600)); // Run every hour
-        loop {
-            interval.tick().await;
-            if let Err(e) = aerugo::handlers::auth::cleanup_expired_api_keys(&cleanup_db_pool).await {
-                tracing::error!("Failed to cleanup expired API keys: {}", e);
+    // Point out display names that predate ENFORCE_UNIQUE_DISPLAY_NAMES
+    if settings.registry.enforce_unique_display_names {
+        aerugo::handlers::repositories::report_duplicate_display_names(&db_pool, settings.database.tenancy_mode)
+            .await
+            .context("Failed to check repository display names")?;
+    }
+
+    // Start the database health probe, periodic maintenance and the blob
+    // reference backfill
+    aerugo::background::spawn_maintenance_tasks(&state);
+
+    // Pre-load the most pulled manifests without holding up startup
+    if settings.cache.warmup {
+        let warmup_state = state.clone();
+        let count = settings.cache.warmup_count;
+        tokio::spawn(async move {
+            if let Err(e) = aerugo::cache_warmup::run_cache_warmup(warmup_state, count).await {
+                tracing::warn!("Cache warmup skipped: {}", e);
             }
-        }
-    });
-    println!("Background API key cleanup task started");
+        });
+        println!("Cache warmup started");
+    }
 
     // Create application using lib.rs
     let app = create_app(state).await;
     println!("Application created successfully");
 
-    // Run server
-    println!("Preparing to start server...");
-    let listen_address = settings.server.bind_address.clone();
-    println!("Listen address: {}", listen_address);
-    let addr: std::net::SocketAddr = listen_address.parse()?;
-    println!("Parsed address: {}", addr);
-    
-    println!("Creating TCP listener...");
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    println!("TCP listener created successfully");
-    
-    tracing::info!("listening on {}", addr);
-    println!("Starting axum server...");
-    axum::serve(listener, app).await?;
+    // Hand requests over to the application
+    startup.open(app);
+    println!("Server ready");
+    server.await??;
     Ok(())
 }
 
@@ -0,0 +1,211 @@
+// src/models/analytics.rs
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use utoipa::ToSchema;
+
+/// Days covered when a request gives no `from`
+pub const DEFAULT_ANALYTICS_DAYS: i64 = 30;
+/// Longest range one analytics request may cover
+pub const MAX_ANALYTICS_DAYS: i64 = 366;
+/// Repositories listed in `top_repositories`
+pub const TOP_REPOSITORIES: usize = 10;
+
+/// Registry operation counted in `registry_activity_daily`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActivityAction {
+    Pull,
+    Push,
+}
+
+impl ActivityAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityAction::Pull => "pull",
+            ActivityAction::Push => "push",
+        }
+    }
+}
+
+impl std::str::FromStr for ActivityAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pull" => Ok(ActivityAction::Pull),
+            "push" => Ok(ActivityAction::Push),
+            other => Err(format!("Unknown registry activity '{}'", other)),
+        }
+    }
+}
+
+/// Width of the time buckets analytics are grouped into
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyticsGranularity {
+    #[default]
+    Day,
+    /// Weeks starting on Monday
+    Week,
+}
+
+impl AnalyticsGranularity {
+    /// First day of the bucket `day` falls in
+    pub fn bucket_start(&self, day: NaiveDate) -> NaiveDate {
+        match self {
+            AnalyticsGranularity::Day => day,
+            AnalyticsGranularity::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+        }
+    }
+
+    fn step(&self) -> Duration {
+        match self {
+            AnalyticsGranularity::Day => Duration::days(1),
+            AnalyticsGranularity::Week => Duration::days(7),
+        }
+    }
+}
+
+/// Query of `GET /api/v1/organizations/{name}/analytics`. Both dates are
+/// inclusive; `to` defaults to today and `from` to 30 days before `to`.
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+pub struct AnalyticsQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub granularity: Option<AnalyticsGranularity>,
+}
+
+/// Validated days and bucket width of an analytics request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnalyticsRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub granularity: AnalyticsGranularity,
+}
+
+impl AnalyticsQuery {
+    /// Fill in defaults relative to `today` and check the range
+    pub fn resolve(&self, today: NaiveDate) -> Result<AnalyticsRange, String> {
+        let to = self.to.unwrap_or(today);
+        let from = self.from.unwrap_or(to - Duration::days(DEFAULT_ANALYTICS_DAYS - 1));
+        if from > to {
+            return Err("'from' must not be later than 'to'".to_string());
+        }
+        if (to - from).num_days() + 1 > MAX_ANALYTICS_DAYS {
+            return Err(format!("Analytics cover at most {} days per request", MAX_ANALYTICS_DAYS));
+        }
+        Ok(AnalyticsRange { from, to, granularity: self.granularity.unwrap_or_default() })
+    }
+}
+
+/// Operations of one kind on one repository by one actor on one day, as
+/// counted in `registry_activity_daily`
+#[derive(Debug, Clone)]
+pub struct ActivityCount {
+    pub day: NaiveDate,
+    pub repository: String,
+    pub action: ActivityAction,
+    /// User ID, or `org_<id>` for organization credentials; `None` when anonymous
+    pub actor: Option<String>,
+    pub count: i64,
+}
+
+/// Activity within one time bucket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AnalyticsBucket {
+    /// First day of the bucket
+    pub start: NaiveDate,
+    pub pulls: i64,
+    pub pushes: i64,
+    /// Distinct signed-in pullers; anonymous pulls are not counted
+    pub unique_pullers: i64,
+}
+
+/// Activity of one repository over the whole range
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RepositoryActivity {
+    pub name: String,
+    pub pulls: i64,
+    pub pushes: i64,
+}
+
+/// Pull and push analytics of an organization
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrganizationAnalytics {
+    pub organization: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub granularity: AnalyticsGranularity,
+    /// Every bucket of the range in order, including those without activity
+    pub buckets: Vec<AnalyticsBucket>,
+    /// Most pulled repositories, then most pushed
+    pub top_repositories: Vec<RepositoryActivity>,
+    /// Distinct signed-in pullers over the whole range
+    pub unique_pullers: i64,
+}
+
+/// Group daily activity counts into the buckets of `range`; counts outside
+/// the range are ignored
+pub fn summarize_activity(organization: String, range: AnalyticsRange, counts: &[ActivityCount]) -> OrganizationAnalytics {
+    let granularity = range.granularity;
+
+    let mut buckets = BTreeMap::new();
+    let mut start = granularity.bucket_start(range.from);
+    while start <= range.to {
+        buckets.insert(start, (0i64, 0i64, HashSet::new()));
+        start += granularity.step();
+    }
+
+    let mut repositories: HashMap<&str, (i64, i64)> = HashMap::new();
+    let mut pullers = HashSet::new();
+    for count in counts.iter().filter(|c| c.day >= range.from && c.day <= range.to) {
+        let Some((pulls, pushes, bucket_pullers)) = buckets.get_mut(&granularity.bucket_start(count.day)) else {
+            continue;
+        };
+        let repository = repositories.entry(count.repository.as_str()).or_default();
+        match count.action {
+            ActivityAction::Pull => {
+                *pulls += count.count;
+                repository.0 += count.count;
+                if let Some(actor) = &count.actor {
+                    bucket_pullers.insert(actor.as_str());
+                    pullers.insert(actor.as_str());
+                }
+            }
+            ActivityAction::Push => {
+                *pushes += count.count;
+                repository.1 += count.count;
+            }
+        }
+    }
+
+    let mut top_repositories: Vec<RepositoryActivity> = repositories
+        .into_iter()
+        .map(|(name, (pulls, pushes))| RepositoryActivity { name: name.to_string(), pulls, pushes })
+        .collect();
+    top_repositories.sort_by(|a, b| {
+        b.pulls
+            .cmp(&a.pulls)
+            .then_with(|| b.pushes.cmp(&a.pushes))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    top_repositories.truncate(TOP_REPOSITORIES);
+
+    OrganizationAnalytics {
+        organization,
+        from: range.from,
+        to: range.to,
+        granularity,
+        buckets: buckets
+            .into_iter()
+            .map(|(start, (pulls, pushes, bucket_pullers))| AnalyticsBucket {
+                start,
+                pulls,
+                pushes,
+                unique_pullers: bucket_pullers.len() as i64,
+            })
+            .collect(),
+        top_repositories,
+        unique_pullers: pullers.len() as i64,
+    }
+}
@@ -0,0 +1,52 @@
+// src/models/audit.rs
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// One audit log entry, as written to the JSON lines export
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AuditLogEntry {
+    /// Unique entry ID, increasing in insertion order
+    pub id: i64,
+    /// Organization the action was taken in
+    pub organization_id: Option<i64>,
+    /// User who performed the action (null once the user is deleted)
+    pub actor_id: Option<i64>,
+    /// Dotted action name, e.g. `member.added`
+    pub action: String,
+    /// Kind of object acted upon, e.g. `user` or `organization`
+    pub target_type: Option<String>,
+    /// ID or name of the object acted upon
+    pub target_id: Option<String>,
+    /// Action specific details
+    #[schema(value_type = Object)]
+    pub details: serde_json::Value,
+    /// When the action happened
+    #[serde(with = "crate::utils::timestamp")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditLogEntry {
+    /// Serialize as a single newline-terminated JSON line
+    pub fn to_json_line(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string());
+        line.push('\n');
+        line
+    }
+}
+
+/// Date range of an audit export. `from` is inclusive, `to` exclusive.
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+pub struct AuditExportQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl AuditExportQuery {
+    pub fn is_valid_range(&self) -> bool {
+        match (self.from, self.to) {
+            (Some(from), Some(to)) => from < to,
+            _ => true,
+        }
+    }
+}
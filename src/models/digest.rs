@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef, Postgres};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Why a content digest was rejected
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DigestError {
+    #[error("Digest '{0}' has no algorithm prefix (expected e.g. 'sha256:<hex>')")]
+    MissingAlgorithm(String),
+    #[error("Unsupported digest algorithm '{0}'")]
+    UnsupportedAlgorithm(String),
+    #[error("{algorithm} digest must have {expected} hex characters, got {actual}")]
+    InvalidLength {
+        algorithm: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Digest contains '{0}', which is not a lowercase hex character")]
+    InvalidHex(char),
+}
+
+/// Digest algorithms the registry accepts, with the hex length of their output
+const ALGORITHMS: &[(&str, usize)] = &[("sha256", 64), ("sha512", 128)];
+
+/// A content digest as the OCI image spec defines it, `<algorithm>:<hex>`,
+/// with a supported algorithm and hex of the right length. Construction
+/// validates, so holding one means the digest is well formed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+pub struct Digest(String);
+
+impl Digest {
+    pub fn parse(digest: &str) -> Result<Self, DigestError> {
+        let (algorithm, hex) = digest
+            .split_once(':')
+            .ok_or_else(|| DigestError::MissingAlgorithm(digest.to_string()))?;
+        let (algorithm, expected) = ALGORITHMS
+            .iter()
+            .find(|(name, _)| *name == algorithm)
+            .copied()
+            .ok_or_else(|| DigestError::UnsupportedAlgorithm(algorithm.to_string()))?;
+
+        if let Some(c) = hex.chars().find(|c| !matches!(c, '0'..='9' | 'a'..='f')) {
+            return Err(DigestError::InvalidHex(c));
+        }
+        if hex.len() != expected {
+            return Err(DigestError::InvalidLength { algorithm, expected, actual: hex.len() });
+        }
+
+        Ok(Self(digest.to_string()))
+    }
+
+    /// SHA-256 digest of `data`
+    pub fn sha256(data: &[u8]) -> Self {
+        Self(format!("sha256:{:x}", Sha256::digest(data)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn algorithm(&self) -> &str {
+        self.0.split_once(':').map(|(algorithm, _)| algorithm).unwrap_or_default()
+    }
+
+    /// The hex-encoded hash, without the algorithm prefix
+    pub fn hex(&self) -> &str {
+        self.0.split_once(':').map(|(_, hex)| hex).unwrap_or_default()
+    }
+
+    /// Key the content is stored under
+    pub fn blob_key(&self) -> String {
+        format!("blobs/{}", self.0)
+    }
+}
+
+impl std::str::FromStr for Digest {
+    type Err = DigestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for Digest {
+    type Error = DigestError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Digest> for String {
+    fn from(digest: Digest) -> Self {
+        digest.0
+    }
+}
+
+impl AsRef<str> for Digest {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// Stored as text; reading one back validates it like any other input
+impl sqlx::Type<Postgres> for Digest {
+    fn type_info() -> PgTypeInfo {
+        <&str as sqlx::Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <&str as sqlx::Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl PgHasArrayType for Digest {
+    fn array_type_info() -> PgTypeInfo {
+        <&str as PgHasArrayType>::array_type_info()
+    }
+
+    fn array_compatible(ty: &PgTypeInfo) -> bool {
+        <&str as PgHasArrayType>::array_compatible(ty)
+    }
+}
+
+impl<'q> sqlx::Encode<'q, Postgres> for Digest {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        <&str as sqlx::Encode<'q, Postgres>>::encode(self.as_str(), buf)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Postgres> for Digest {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let digest = <&str as sqlx::Decode<'r, Postgres>>::decode(value)?;
+        Ok(Self::parse(digest)?)
+    }
+}
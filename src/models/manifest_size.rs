@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::models::media_type::MediaType;
+
+/// Limit for artifact types without one of their own, unless
+/// `MANIFEST_SIZE_LIMITS` sets `default`
+pub const DEFAULT_MANIFEST_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+/// Config media type of manifests that carry no config, which then say what
+/// they are through `artifactType` or their layers
+const EMPTY_CONFIG: &str = "application/vnd.oci.empty.v1+json";
+
+/// Built-in limits: signatures are a few kilobytes, indexes list manifests
+/// rather than layers, and image manifests get the 4 MiB the distribution
+/// spec asks registries to accept
+const DEFAULT_LIMITS: &[(&str, usize)] = &[
+    ("application/vnd.dev.cosign.artifact.sig.v1+json", 64 * 1024),
+    ("application/vnd.cncf.notary.signature", 64 * 1024),
+    ("application/vnd.dev.sigstore.bundle.v0.3+json", 64 * 1024),
+    ("application/vnd.oci.image.index.v1+json", 1024 * 1024),
+    ("application/vnd.docker.distribution.manifest.list.v2+json", 1024 * 1024),
+    ("application/vnd.oci.image.manifest.v1+json", 4 * 1024 * 1024),
+    ("application/vnd.docker.distribution.manifest.v2+json", 4 * 1024 * 1024),
+];
+
+/// Largest manifest accepted on push, per artifact type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestSizeLimits {
+    /// Limits by lowercased artifact type
+    limits: BTreeMap<String, usize>,
+    /// Limit for artifact types not in `limits`
+    default: usize,
+}
+
+impl Default for ManifestSizeLimits {
+    fn default() -> Self {
+        Self {
+            limits: DEFAULT_LIMITS.iter().map(|(artifact_type, max)| (artifact_type.to_string(), *max)).collect(),
+            default: DEFAULT_MANIFEST_MAX_BYTES,
+        }
+    }
+}
+
+impl ManifestSizeLimits {
+    /// Apply `MANIFEST_SIZE_LIMITS` on top of these limits: comma-separated
+    /// `<artifact type>=<bytes>` entries, with `default=<bytes>` setting the
+    /// limit for types that have none
+    pub fn with_overrides(mut self, overrides: &str) -> Result<Self, String> {
+        for entry in overrides.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (artifact_type, max) = entry
+                .rsplit_once('=')
+                .ok_or_else(|| format!("Manifest size limit '{}' is not <artifact type>=<bytes>", entry))?;
+            let max: usize = max
+                .trim()
+                .parse()
+                .map_err(|_| format!("Manifest size limit '{}' does not end in a number of bytes", entry))?;
+            if max == 0 {
+                return Err(format!("Manifest size limit '{}' must be at least 1 byte", entry));
+            }
+
+            let artifact_type = artifact_type.trim().to_ascii_lowercase();
+            if artifact_type == "default" {
+                self.default = max;
+            } else if !artifact_type.contains('/') {
+                return Err(format!("'{}' is not a media type", artifact_type));
+            } else {
+                self.limits.insert(artifact_type, max);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Largest manifest of `artifact_type` accepted
+    pub fn limit_for(&self, artifact_type: &str) -> usize {
+        self.limits
+            .get(&artifact_type.to_ascii_lowercase())
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Refuse a manifest of `size` bytes that exceeds the limit for `artifact_type`
+    pub fn check(&self, artifact_type: &str, size: usize) -> Result<(), String> {
+        let max = self.limit_for(artifact_type);
+        if size > max {
+            return Err(format!(
+                "manifest of artifact type {} is {} bytes, more than the {} bytes allowed",
+                artifact_type, size, max
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Largest total layer size of an image pushed to an organization: its own
+/// `max_image_bytes`, else MAX_IMAGE_BYTES; `None` is unlimited
+pub fn image_size_limit(org_limit: Option<i64>, default_limit: Option<u64>) -> Option<u64> {
+    org_limit.map(|max| max.max(0) as u64).or(default_limit)
+}
+
+/// Refuse an image whose layers add up to `total` bytes when that exceeds `max`
+pub fn check_image_size(total: u64, max: Option<u64>) -> Result<(), String> {
+    match max {
+        Some(max) if total > max => Err(format!(
+            "image layers total {} bytes, more than the {} bytes allowed",
+            total, max
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// What a pushed manifest is, as the OCI image spec works it out: its
+/// `artifactType`, else a config media type other than an image config or
+/// the empty config, else the manifest's own media type
+pub fn artifact_type(manifest: &serde_json::Value, media_type: &str) -> String {
+    let declared = manifest
+        .get("artifactType")
+        .and_then(|t| t.as_str())
+        .and_then(|t| t.parse::<MediaType>().ok());
+    if let Some(artifact_type) = declared {
+        return artifact_type.as_str().to_string();
+    }
+
+    let config_type = manifest
+        .get("config")
+        .and_then(|config| config.get("mediaType"))
+        .and_then(|t| t.as_str())
+        .and_then(|t| t.parse::<MediaType>().ok());
+    match config_type {
+        Some(MediaType::Other(config_type)) if config_type != EMPTY_CONFIG => config_type,
+        _ => media_type.to_ascii_lowercase(),
+    }
+}
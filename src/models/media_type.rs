@@ -0,0 +1,130 @@
+use std::fmt;
+use thiserror::Error;
+
+/// Why a media type was rejected
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MediaTypeError {
+    #[error("Media type is empty")]
+    Empty,
+    #[error("Media type '{0}' is not of the form type/subtype")]
+    Malformed(String),
+}
+
+/// Media types of the manifests, configs and layers clients exchange with
+/// the registry. Anything else is kept as `Other`, so artifacts with their
+/// own types still round-trip.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MediaType {
+    DockerManifest,
+    DockerManifestList,
+    OciManifest,
+    OciIndex,
+    DockerConfig,
+    OciConfig,
+    DockerLayer,
+    DockerForeignLayer,
+    OciLayer,
+    OciLayerGzip,
+    OciLayerZstd,
+    /// Another well-formed type, lowercased and without parameters
+    Other(String),
+}
+
+const KNOWN: &[(MediaType, &str)] = &[
+    (MediaType::DockerManifest, "application/vnd.docker.distribution.manifest.v2+json"),
+    (MediaType::DockerManifestList, "application/vnd.docker.distribution.manifest.list.v2+json"),
+    (MediaType::OciManifest, "application/vnd.oci.image.manifest.v1+json"),
+    (MediaType::OciIndex, "application/vnd.oci.image.index.v1+json"),
+    (MediaType::DockerConfig, "application/vnd.docker.container.image.v1+json"),
+    (MediaType::OciConfig, "application/vnd.oci.image.config.v1+json"),
+    (MediaType::DockerLayer, "application/vnd.docker.image.rootfs.diff.tar.gzip"),
+    (MediaType::DockerForeignLayer, "application/vnd.docker.image.rootfs.foreign.diff.tar.gzip"),
+    (MediaType::OciLayer, "application/vnd.oci.image.layer.v1.tar"),
+    (MediaType::OciLayerGzip, "application/vnd.oci.image.layer.v1.tar+gzip"),
+    (MediaType::OciLayerZstd, "application/vnd.oci.image.layer.v1.tar+zstd"),
+];
+
+impl MediaType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            MediaType::Other(media_type) => media_type,
+            known => KNOWN
+                .iter()
+                .find(|(media_type, _)| media_type == known)
+                .map(|(_, name)| *name)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// An image manifest, pointing at a config and layers
+    pub fn is_manifest(&self) -> bool {
+        matches!(self, MediaType::DockerManifest | MediaType::OciManifest)
+    }
+
+    /// An index or manifest list, pointing at other manifests
+    pub fn is_index(&self) -> bool {
+        matches!(self, MediaType::DockerManifestList | MediaType::OciIndex)
+    }
+
+    /// One of the known config or layer types, which can never be a manifest
+    pub fn is_blob(&self) -> bool {
+        !matches!(self, MediaType::Other(_)) && !self.is_manifest() && !self.is_index()
+    }
+
+    /// Whether an `Accept` header value lets the client receive this type.
+    /// Wildcards count; ranges with `q=0` are refused.
+    pub fn accepted_by(&self, accept: &str) -> bool {
+        let (kind, _) = self.as_str().split_once('/').unwrap_or_default();
+        accept.split(',').any(|range| {
+            let mut parts = range.split(';');
+            let name = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let refused = parts.any(|param| {
+                param
+                    .split_once('=')
+                    .map_or(false, |(key, value)| key.trim() == "q" && value.trim().parse::<f64>() == Ok(0.0))
+            });
+            !refused && (name == "*/*" || name == format!("{}/*", kind) || name == self.as_str())
+        })
+    }
+}
+
+impl std::str::FromStr for MediaType {
+    type Err = MediaTypeError;
+
+    /// Parse a media type as found in `Content-Type` or a `mediaType` field;
+    /// parameters such as `charset` are dropped
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        if name.is_empty() {
+            return Err(MediaTypeError::Empty);
+        }
+        let well_formed = match name.split_once('/') {
+            Some((kind, subtype)) => is_restricted_name(kind) && is_restricted_name(subtype),
+            None => false,
+        };
+        if !well_formed {
+            return Err(MediaTypeError::Malformed(s.trim().to_string()));
+        }
+
+        Ok(KNOWN
+            .iter()
+            .find(|(_, known)| *known == name)
+            .map(|(media_type, _)| media_type.clone())
+            .unwrap_or(MediaType::Other(name)))
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `restricted-name` of RFC 6838: what may appear in a type or subtype
+fn is_restricted_name(name: &str) -> bool {
+    name.len() <= 127
+        && name.chars().next().map_or(false, |c| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+}
@@ -4,3 +4,11 @@ pub mod repository;
 pub mod repository_with_org;
 pub mod user;
 pub mod api_key;
+pub mod tag_expiry;
+pub mod audit;
+pub mod analytics;
+pub mod digest;
+pub mod manifest_size;
+pub mod media_type;
+pub mod tag_policy;
+pub mod session;
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use validator::Validate;
 use utoipa::ToSchema;
+use crate::error::AppError;
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
 pub struct Organization {
@@ -20,9 +21,18 @@ pub struct Organization {
     /// Optional avatar URL
     pub avatar_url: Option<String>,
     /// When the organization was created
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
     /// When the organization was last updated
+    #[serde(with = "crate::utils::timestamp")]
     pub updated_at: DateTime<Utc>,
+    /// Refuse pulls of critically vulnerable images; `None` follows BLOCK_VULNERABLE_PULLS
+    #[sqlx(default)]
+    pub block_vulnerable_pulls: Option<bool>,
+    /// Whether new repositories are public unless created with `is_public`;
+    /// `None` follows DEFAULT_REPO_VISIBILITY
+    #[sqlx(default)]
+    pub default_repo_public: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
@@ -54,6 +64,85 @@ pub struct UpdateOrganizationRequest {
     pub website_url: Option<String>,
     /// Updated avatar URL
     pub avatar_url: Option<String>,
+    /// Refuse or allow pulls of critically vulnerable images regardless of BLOCK_VULNERABLE_PULLS
+    pub block_vulnerable_pulls: Option<bool>,
+    /// Make new repositories public or private unless created with `is_public`,
+    /// regardless of DEFAULT_REPO_VISIBILITY
+    pub default_repo_public: Option<bool>,
+}
+
+impl CreateOrganizationRequest {
+    /// Apply `sanitize_profile` to the profile fields
+    pub fn sanitize(&mut self) -> Result<(), String> {
+        sanitize_profile(&mut self.description, &self.website_url, &self.avatar_url)
+    }
+}
+
+impl UpdateOrganizationRequest {
+    /// Apply `sanitize_profile` to the profile fields being changed
+    pub fn sanitize(&mut self) -> Result<(), String> {
+        sanitize_profile(&mut self.description, &self.website_url, &self.avatar_url)
+    }
+}
+
+/// Refuse profile links a UI could not safely render as `href`/`src`, and
+/// drop control characters from the description. Enforced unless
+/// SANITIZE_ORG_PROFILES is disabled.
+pub fn sanitize_profile(
+    description: &mut Option<String>,
+    website_url: &Option<String>,
+    avatar_url: &Option<String>,
+) -> Result<(), String> {
+    for (field, url) in [("website_url", website_url), ("avatar_url", avatar_url)] {
+        if let Some(url) = url.as_deref().filter(|url| !url.is_empty()) {
+            check_profile_url(url).map_err(|reason| format!("{} {}", field, reason))?;
+        }
+    }
+    if let Some(description) = description {
+        *description = strip_control_characters(description);
+    }
+    Ok(())
+}
+
+/// Accept only absolute `http` and `https` URLs with a host; anything else,
+/// `javascript:` and `data:` included, is refused with the reason
+pub fn check_profile_url(url: &str) -> Result<(), &'static str> {
+    let parsed = url::Url::parse(url.trim()).map_err(|_| "must be an absolute URL")?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("must use http or https");
+    }
+    if parsed.host_str().map_or(true, str::is_empty) {
+        return Err("must name a host");
+    }
+    Ok(())
+}
+
+/// `text` without control characters, keeping line breaks and tabs
+pub fn strip_control_characters(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+        .collect()
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct RenameOrganizationRequest {
+    /// New organization name (3-50 characters, URL-friendly)
+    #[validate(length(min = 3, max = 50))]
+    pub new_name: String,
+}
+
+/// Seats an organization limited to `max_members` has left with `members`
+/// active members; `None` when membership is unlimited. An organization's
+/// own limit takes precedence over ORG_MAX_MEMBERS.
+pub fn seats_remaining(max_members: Option<i64>, members: i64) -> Option<i64> {
+    max_members.map(|max_members| (max_members - members).max(0))
+}
+
+/// Names that would collide with registry or API paths
+pub const RESERVED_ORG_NAMES: &[&str] = &["_catalog", "admin", "api", "docs", "health", "id", "v2"];
+
+pub fn is_reserved_org_name(name: &str) -> bool {
+    RESERVED_ORG_NAMES.contains(&name.to_lowercase().as_str())
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
@@ -62,9 +151,16 @@ pub struct OrganizationMember {
     pub organization_id: i64,
     pub user_id: i64,
     pub role: String, // Changed from OrganizationRole to String for now
+    #[serde(with = "crate::utils::timestamp")]
     pub joined_at: DateTime<Utc>,
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub invited_at: Option<DateTime<Utc>>,
     pub invited_by: Option<i64>,
+    /// When the membership stops granting access; `None` never expires
+    #[serde(default, with = "crate::utils::timestamp::option")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Incremented on every update; send it back as `version` to update safely
+    pub version: i64,
     // User details (from JOIN)
     pub username: String,
     pub email: String,
@@ -105,11 +201,59 @@ pub struct AddMemberRequest {
     #[validate(email)]
     pub email: String,
     pub role: OrganizationRole,
+    /// Optional time after which the membership expires
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateMemberRequest {
     pub role: OrganizationRole,
+    /// New expiry for the membership; omitted keeps the current one
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Member `version` the update is based on; if the member changed since,
+    /// the update is refused with 412. Omitted applies it unconditionally.
+    #[serde(default)]
+    pub version: Option<i64>,
+}
+
+/// Refuse an update based on `expected` once the member is at `current`
+pub fn check_member_version(expected: Option<i64>, current: i64) -> Result<(), AppError> {
+    match expected {
+        Some(expected) if expected != current => Err(AppError::PreconditionFailed(format!(
+            "Member was modified by someone else (version {} is current, the update was based on {})",
+            current, expected
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Effective role of a user in an organization
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MemberRoleResponse {
+    pub organization: String,
+    pub user_id: i64,
+    pub role: OrganizationRole,
+}
+
+/// Role of a member looked up by a caller with `caller_role`: only owners
+/// and admins may look roles up, and a user with no active membership is 404
+pub fn resolve_member_role(
+    caller_role: Option<&OrganizationRole>,
+    member_role: Option<OrganizationRole>,
+) -> Result<OrganizationRole, AppError> {
+    if !caller_role.map_or(false, |role| role.allows(OrganizationAction::ViewMemberRoles)) {
+        return Err(AppError::Forbidden(
+            "Only organization owners and admins can look up member roles".to_string(),
+        ));
+    }
+    member_role.ok_or_else(|| AppError::NotFound("User is not a member of this organization".to_string()))
+}
+
+/// Whether a membership with this expiry still grants access at `now`
+pub fn membership_active(expires_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    expires_at.map_or(true, |expires_at| expires_at > now)
 }
 
 impl OrganizationRole {
@@ -140,4 +284,96 @@ impl OrganizationRole {
             OrganizationRole::Member => false,
         }
     }
+
+    /// Whether the role may create repositories in the organization; plain
+    /// members may unless `REPO_CREATION_REQUIRES_ADMIN` is set
+    pub fn can_create_repository(&self, requires_admin: bool) -> bool {
+        !requires_admin || self.can_manage_organization()
+    }
+
+    /// Whether the role permits `action`
+    pub fn allows(&self, action: OrganizationAction) -> bool {
+        use OrganizationRole::{Admin, Member, Owner};
+
+        match action {
+            OrganizationAction::ViewOrganization => true,
+            OrganizationAction::UpdateOrganization
+            | OrganizationAction::DeleteManifests
+            | OrganizationAction::ManageTagExpiry
+            | OrganizationAction::ExportAuditLog
+            | OrganizationAction::ViewAnalytics => self.can_manage_organization(),
+            OrganizationAction::RenameOrganization | OrganizationAction::DeleteOrganization => {
+                self.can_delete_organization()
+            }
+            OrganizationAction::AddMembers | OrganizationAction::ViewMemberRoles => self.can_manage_members(),
+            OrganizationAction::RemoveOwners => self.can_remove_member(&Owner),
+            OrganizationAction::RemoveAdmins => self.can_remove_member(&Admin),
+            OrganizationAction::RemoveMembers => self.can_remove_member(&Member),
+            OrganizationAction::AssignOwnerRole => self.can_change_role_to(&Owner),
+            OrganizationAction::AssignAdminRole => self.can_change_role_to(&Admin),
+            OrganizationAction::AssignMemberRole => self.can_change_role_to(&Member),
+        }
+    }
+
+    /// Every action the role permits, in `OrganizationAction::ALL` order
+    pub fn allowed_actions(&self) -> Vec<OrganizationAction> {
+        OrganizationAction::ALL
+            .iter()
+            .copied()
+            .filter(|action| self.allows(*action))
+            .collect()
+    }
+}
+
+/// Something a member may do in an organization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrganizationAction {
+    ViewOrganization,
+    UpdateOrganization,
+    RenameOrganization,
+    DeleteOrganization,
+    AddMembers,
+    RemoveOwners,
+    RemoveAdmins,
+    RemoveMembers,
+    AssignOwnerRole,
+    AssignAdminRole,
+    AssignMemberRole,
+    DeleteManifests,
+    ManageTagExpiry,
+    ExportAuditLog,
+    ViewAnalytics,
+    ViewMemberRoles,
+}
+
+impl OrganizationAction {
+    pub const ALL: &'static [OrganizationAction] = &[
+        OrganizationAction::ViewOrganization,
+        OrganizationAction::UpdateOrganization,
+        OrganizationAction::RenameOrganization,
+        OrganizationAction::DeleteOrganization,
+        OrganizationAction::AddMembers,
+        OrganizationAction::RemoveOwners,
+        OrganizationAction::RemoveAdmins,
+        OrganizationAction::RemoveMembers,
+        OrganizationAction::AssignOwnerRole,
+        OrganizationAction::AssignAdminRole,
+        OrganizationAction::AssignMemberRole,
+        OrganizationAction::DeleteManifests,
+        OrganizationAction::ManageTagExpiry,
+        OrganizationAction::ExportAuditLog,
+        OrganizationAction::ViewAnalytics,
+        OrganizationAction::ViewMemberRoles,
+    ];
+}
+
+/// A user's role and allowed actions in one organization
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OrganizationPermissions {
+    pub organization_id: i64,
+    pub organization: String,
+    /// Lowercase role name, as in member listings
+    pub role: String,
+    pub actions: Vec<OrganizationAction>,
 }
@@ -1,9 +1,144 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use thiserror::Error;
 use validator::Validate;
 use utoipa::ToSchema;
 
+/// Longest repository name accepted, including namespace and separators
+pub const MAX_REPOSITORY_NAME_LEN: usize = 255;
+
+/// Why a repository name was rejected
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RepositoryNameError {
+    #[error("Repository name is empty")]
+    Empty,
+    #[error("Repository name is longer than {} characters", MAX_REPOSITORY_NAME_LEN)]
+    TooLong,
+    #[error("Repository names must be lowercase; did you mean '{suggestion}'?")]
+    Uppercase { suggestion: String },
+    /// Non-ASCII characters, including look-alikes of valid ones such as
+    /// Cyrillic 'а' or fullwidth '／'
+    #[error("Repository name contains the non-ASCII character '{0}'")]
+    NonAscii(char),
+    #[error("Repository name contains the invalid character '{0}'")]
+    InvalidCharacter(char),
+    #[error("Repository name path component '{0}' must start and end with a letter or digit and use only single '.', '_', '__' or '-' runs as separators")]
+    InvalidComponent(String),
+    #[error("Repository name has {segments} path segments; at most {max} are allowed")]
+    TooDeep { segments: usize, max: usize },
+}
+
+/// A repository name as the OCI distribution spec defines it: lowercase ASCII
+/// path components of `[a-z0-9]+((\.|_|__|-+)[a-z0-9]+)*` separated by `/`.
+/// Construction validates, so holding one means the name is valid.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+pub struct RepositoryName(String);
+
+impl RepositoryName {
+    pub fn parse(name: &str) -> Result<Self, RepositoryNameError> {
+        if name.is_empty() {
+            return Err(RepositoryNameError::Empty);
+        }
+        if let Some(c) = name.chars().find(|c| !c.is_ascii()) {
+            return Err(RepositoryNameError::NonAscii(c));
+        }
+        if name.len() > MAX_REPOSITORY_NAME_LEN {
+            return Err(RepositoryNameError::TooLong);
+        }
+        if name.bytes().any(|b| b.is_ascii_uppercase()) {
+            return Err(RepositoryNameError::Uppercase { suggestion: name.to_ascii_lowercase() });
+        }
+        if let Some(c) = name
+            .chars()
+            .find(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-' | '/')))
+        {
+            return Err(RepositoryNameError::InvalidCharacter(c));
+        }
+        if let Some(component) = name.split('/').find(|component| !valid_component(component)) {
+            return Err(RepositoryNameError::InvalidComponent(component.to_string()));
+        }
+
+        Ok(Self(name.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Namespace and repository of a `namespace/repository` name; `None` for
+    /// names without a namespace
+    pub fn split_namespace(&self) -> Option<(&str, &str)> {
+        self.0.split_once('/')
+    }
+
+    /// Reject names nested deeper than MAX_REPO_PATH_SEGMENTS allows
+    pub fn check_depth(&self, max: usize) -> Result<(), RepositoryNameError> {
+        check_repository_depth(&self.0, max)
+    }
+}
+
+/// Reject a full `namespace/.../repository` path with more than `max`
+/// `/` separated segments
+pub fn check_repository_depth(path: &str, max: usize) -> Result<(), RepositoryNameError> {
+    match path.split('/').count() {
+        segments if segments > max => Err(RepositoryNameError::TooDeep { segments, max }),
+        _ => Ok(()),
+    }
+}
+
+/// One path component, already known to hold only `[a-z0-9._-]`
+fn valid_component(component: &str) -> bool {
+    let bytes = component.as_bytes();
+    let alphanumeric = |b: &u8| b.is_ascii_lowercase() || b.is_ascii_digit();
+
+    match (bytes.first(), bytes.last()) {
+        (Some(first), Some(last)) if alphanumeric(first) && alphanumeric(last) => {}
+        _ => return false,
+    }
+
+    // Separator runs between alphanumerics: '.', '_', '__' or any number of '-'
+    component
+        .split(|c: char| c.is_ascii_alphanumeric())
+        .filter(|run| !run.is_empty())
+        .all(|run| matches!(run, "." | "_" | "__") || run.bytes().all(|b| b == b'-'))
+}
+
+impl std::str::FromStr for RepositoryName {
+    type Err = RepositoryNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for RepositoryName {
+    type Error = RepositoryNameError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<RepositoryName> for String {
+    fn from(name: RepositoryName) -> Self {
+        name.0
+    }
+}
+
+impl AsRef<str> for RepositoryName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RepositoryName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
 pub struct Repository {
     /// Unique repository ID
@@ -19,8 +154,10 @@ pub struct Repository {
     /// User ID who created this repository
     pub created_by: Option<i64>,
     /// When the repository was created
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
     /// When the repository was last updated
+    #[serde(with = "crate::utils::timestamp")]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -20,7 +20,9 @@ pub struct RepositoryWithOrg {
     pub description: Option<String>,
     pub is_public: bool,
     pub created_by: Option<i64>,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "crate::utils::timestamp")]
     pub updated_at: DateTime<Utc>,
     pub organization: Organization,
 }
@@ -31,6 +33,8 @@ pub struct RepositoryWithOrgRow {
     pub id: i64,
     pub organization_id: i64,
     pub name: String,
+    #[sqlx(default)]
+    pub display_name: Option<String>,
     pub description: Option<String>,
     pub is_public: bool,
     pub created_by: Option<i64>,
@@ -0,0 +1,65 @@
+// src/models/session.rs
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Longest user agent kept as a session's label
+pub const MAX_USER_AGENT_LEN: usize = 255;
+
+/// Something that keeps a user signed in and can be revoked: a session
+/// started by signing in, or a personal access token (API key)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionId {
+    Session(i64),
+    ApiKey(i64),
+}
+
+impl std::str::FromStr for SessionId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = if let Some(id) = s.strip_prefix("session-") {
+            id.parse().ok().map(SessionId::Session)
+        } else if let Some(id) = s.strip_prefix("api-key-") {
+            id.parse().ok().map(SessionId::ApiKey)
+        } else {
+            None
+        };
+        parsed.ok_or_else(|| format!("Invalid session id '{}'", s))
+    }
+}
+
+impl std::fmt::Display for SessionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionId::Session(id) => write!(f, "session-{}", id),
+            SessionId::ApiKey(id) => write!(f, "api-key-{}", id),
+        }
+    }
+}
+
+/// An active session or access token
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionResponse {
+    /// `session-<n>` or `api-key-<n>`; pass it to `DELETE /auth/me/sessions/:id`
+    pub id: String,
+    /// `session` or `api_key`
+    pub kind: String,
+    /// User agent the session was started from, or the API key's name
+    pub label: Option<String>,
+    #[serde(with = "crate::utils::timestamp::option")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(with = "crate::utils::timestamp::option")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Whether the request listing sessions was made with this one
+    pub current: bool,
+}
+
+/// User agent to record for a new session, cut to `MAX_USER_AGENT_LEN`
+pub fn session_label(user_agent: Option<&str>) -> Option<String> {
+    let user_agent = user_agent?.trim();
+    if user_agent.is_empty() {
+        return None;
+    }
+    Some(user_agent.chars().take(MAX_USER_AGENT_LEN).collect())
+}
@@ -0,0 +1,92 @@
+// src/models/tag_expiry.rs
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow, ToSchema)]
+pub struct TagExpiryRule {
+    /// Unique rule ID
+    pub id: i64,
+    /// Repository the rule applies to
+    pub repository_id: i64,
+    /// Glob pattern matched against tag names (`*` and `?` wildcards)
+    pub pattern: String,
+    /// Days after its last push before a matching tag is deleted
+    pub ttl_days: i32,
+    /// User who created the rule
+    pub created_by: Option<i64>,
+    /// When the rule was created
+    #[serde(with = "crate::utils::timestamp")]
+    pub created_at: DateTime<Utc>,
+    /// When the rule was last updated
+    #[serde(with = "crate::utils::timestamp")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateTagExpiryRuleRequest {
+    /// Glob pattern, e.g. `ci-*`
+    #[validate(length(min = 1, max = 255))]
+    pub pattern: String,
+    /// TTL in days (1-3650)
+    #[validate(range(min = 1, max = 3650))]
+    pub ttl_days: i32,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateTagExpiryRuleRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub pattern: Option<String>,
+    #[validate(range(min = 1, max = 3650))]
+    pub ttl_days: Option<i32>,
+}
+
+/// Match a tag name against a glob pattern. `*` matches any run of
+/// characters (including none) and `?` matches exactly one.
+pub fn tag_matches_pattern(pattern: &str, tag: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let tag: Vec<char> = tag.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` seen and the tag position it was matched against
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < tag.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == tag[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last `*` swallow one more character and retry
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Names of the tags that at least one rule has expired at `now`.
+/// Tags are `(name, last pushed at)` pairs.
+pub fn expired_tag_names(
+    rules: &[TagExpiryRule],
+    tags: &[(String, DateTime<Utc>)],
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    tags.iter()
+        .filter(|(name, pushed_at)| {
+            rules.iter().any(|rule| {
+                tag_matches_pattern(&rule.pattern, name)
+                    && *pushed_at + Duration::days(rule.ttl_days as i64) <= now
+            })
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
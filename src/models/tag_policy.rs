@@ -0,0 +1,46 @@
+// src/models/tag_policy.rs
+
+/// Tag accepted by repositories that require semantic versions, whatever
+/// its form
+pub const SEMVER_EXEMPT_TAG: &str = "latest";
+
+/// Whether `tag` is a semantic version, `MAJOR.MINOR.PATCH` with optional
+/// `-prerelease` and `+build` parts as semver.org defines them. A leading
+/// `v` is accepted, as in `v1.2.3`.
+pub fn is_semver_tag(tag: &str) -> bool {
+    let version = tag.strip_prefix('v').unwrap_or(tag);
+    let (version, build) = match version.split_once('+') {
+        Some((version, build)) => (version, Some(build)),
+        None => (version, None),
+    };
+    let (core, prerelease) = match version.split_once('-') {
+        Some((core, prerelease)) => (core, Some(prerelease)),
+        None => (version, None),
+    };
+
+    let numbers: Vec<&str> = core.split('.').collect();
+    numbers.len() == 3
+        && numbers.iter().all(|n| is_numeric_identifier(n))
+        && prerelease.map_or(true, |p| {
+            p.split('.').all(|id| is_identifier(id) && (!is_digits(id) || is_numeric_identifier(id)))
+        })
+        && build.map_or(true, |b| b.split('.').all(is_identifier))
+}
+
+/// Whether a repository's policy lets `tag` be pushed
+pub fn tag_allowed(require_semver: bool, tag: &str) -> bool {
+    !require_semver || tag == SEMVER_EXEMPT_TAG || is_semver_tag(tag)
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Digits without a leading zero, other than `0` itself
+fn is_numeric_identifier(s: &str) -> bool {
+    is_digits(s) && (s == "0" || !s.starts_with('0'))
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct User {
@@ -7,11 +8,12 @@ pub struct User {
     pub username: String,
     pub email: String,
     pub password_hash: String,
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
 /// User information returned in API responses
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct UserResponse {
     /// Unique user ID
     pub id: i64,
@@ -19,12 +21,87 @@ pub struct UserResponse {
     pub username: String,
     /// Email address
     pub email: String,
+    /// Name shown in place of the username
+    pub display_name: Option<String>,
+    /// Short self description
+    pub bio: Option<String>,
+    /// Where the uploaded avatar is served from
+    pub avatar_url: Option<String>,
     /// When the user was created
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Profile changes through `PUT /api/v1/auth/me`; fields left out keep their value
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateProfileRequest {
+    /// Name shown in place of the username (1-100 characters)
+    #[validate(length(min = 1, max = 100))]
+    pub display_name: Option<String>,
+    /// Short self description (max 500 characters)
+    #[validate(length(max = 500))]
+    pub bio: Option<String>,
+}
+
 pub struct NewUser {
     pub username: String,
     pub email: String,
     pub password_hash: String,
 }
+
+/// Canonical form of an email address. Addresses differing only in case or
+/// surrounding whitespace belong to the same account; they are stored and
+/// looked up in this form.
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Shortest and longest accepted username
+pub const USERNAME_MIN_LEN: usize = 3;
+pub const USERNAME_MAX_LEN: usize = 39;
+
+/// Usernames refused at registration unless RESERVED_USERNAMES says otherwise
+pub const DEFAULT_RESERVED_USERNAMES: &[&str] = &["admin", "root", "support"];
+
+/// Canonical form of a username. Usernames differing only in case or
+/// surrounding whitespace are the same account; they are stored in this form.
+pub fn normalize_username(username: &str) -> String {
+    username.trim().to_lowercase()
+}
+
+/// Check a normalized username: 3-39 lowercase letters, digits, `-` and `_`,
+/// starting and ending with a letter or digit, and not in `reserved`. The
+/// error's code is `length`, `invalid_characters` or `reserved`.
+pub fn validate_username(username: &str, reserved: &[String]) -> Result<(), ValidationError> {
+    let error = |code: &'static str, message: String| {
+        let mut error = ValidationError::new(code);
+        error.message = Some(message.into());
+        error
+    };
+
+    let len = username.chars().count();
+    if !(USERNAME_MIN_LEN..=USERNAME_MAX_LEN).contains(&len) {
+        return Err(error(
+            "length",
+            format!("Username must be {}-{} characters long", USERNAME_MIN_LEN, USERNAME_MAX_LEN),
+        ));
+    }
+
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_';
+    let alphanumeric = |c: Option<char>| c.map_or(false, |c| c.is_ascii_alphanumeric());
+    if !username.chars().all(allowed)
+        || !alphanumeric(username.chars().next())
+        || !alphanumeric(username.chars().last())
+    {
+        return Err(error(
+            "invalid_characters",
+            "Username may only contain lowercase letters, digits, '-' and '_', and must start and end with a letter or digit"
+                .to_string(),
+        ));
+    }
+
+    if reserved.iter().any(|name| name.eq_ignore_ascii_case(username)) {
+        return Err(error("reserved", format!("Username '{}' is reserved", username)));
+    }
+    Ok(())
+}
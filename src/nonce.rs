@@ -0,0 +1,122 @@
+// One-time nonces against replayed sensitive requests
+//
+// Deleting an organization or making someone its owner may carry an
+// `X-Request-Nonce` header with a nonce from `POST /api/v1/auth/nonce`. A
+// nonce belongs to the user and action it was issued for and is deleted on
+// first use, so a captured request cannot be sent again: a reused, expired or
+// unknown nonce gets 409. With REQUIRE_REQUEST_NONCE set the header is
+// mandatory for these actions. Nonces are kept in Redis (`SET key 1 EX ttl`,
+// spent with `DEL`) so any instance can check them; without Redis they are
+// held in process, which covers a single instance.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::error::AppError;
+
+/// Header a sensitive request carries its nonce in
+pub const NONCE_HEADER: &str = "x-request-nonce";
+
+/// How long an issued nonce can be used
+pub const NONCE_TTL: Duration = Duration::from_secs(300);
+
+/// Operations a nonce can be issued for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NonceAction {
+    /// `DELETE /api/v1/organizations/{id}`
+    DeleteOrganization,
+    /// Making a member an owner through `PUT /api/v1/organizations/{id}/members/{member_id}`
+    TransferOwnership,
+}
+
+impl NonceAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NonceAction::DeleteOrganization => "delete_organization",
+            NonceAction::TransferOwnership => "transfer_ownership",
+        }
+    }
+}
+
+/// Where issued nonces are kept until used or expired
+#[async_trait]
+pub trait NonceStore: Send + Sync {
+    /// Keep `key` for `ttl`
+    async fn store(&self, key: &str, ttl: Duration) -> anyhow::Result<()>;
+
+    /// Remove `key`; true if it was there and had not expired
+    async fn take(&self, key: &str) -> anyhow::Result<bool>;
+}
+
+/// Nonces held in this process, with the same expiry as the Redis ones
+#[derive(Default)]
+pub struct LocalNonces {
+    issued: Mutex<HashMap<String, Instant>>,
+}
+
+#[async_trait]
+impl NonceStore for LocalNonces {
+    async fn store(&self, key: &str, ttl: Duration) -> anyhow::Result<()> {
+        let now = Instant::now();
+        let mut issued = self.issued.lock().unwrap();
+        issued.retain(|_, expires| *expires > now);
+        issued.insert(key.to_string(), now + ttl);
+        Ok(())
+    }
+
+    async fn take(&self, key: &str) -> anyhow::Result<bool> {
+        let mut issued = self.issued.lock().unwrap();
+        Ok(issued.remove(key).map_or(false, |expires| expires > Instant::now()))
+    }
+}
+
+/// Store key of `nonce` issued to `user_id` for `action`
+pub fn nonce_key(user_id: i64, action: NonceAction, nonce: &str) -> String {
+    format!("nonce:{}:{}:{}", user_id, action.as_str(), nonce)
+}
+
+/// Issue a nonce `user_id` can use once for `action` within `NONCE_TTL`
+pub async fn issue_nonce(store: &dyn NonceStore, user_id: i64, action: NonceAction) -> anyhow::Result<String> {
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    store.store(&nonce_key(user_id, action, &nonce), NONCE_TTL).await?;
+    Ok(nonce)
+}
+
+/// Spend the nonce in `headers` for `action`. Without a nonce the request
+/// goes ahead unless `required`; a nonce that is not live fails with 409.
+pub async fn check_nonce(
+    store: &dyn NonceStore,
+    headers: &HeaderMap,
+    user_id: i64,
+    action: NonceAction,
+    required: bool,
+) -> anyhow::Result<()> {
+    let nonce = headers
+        .get(NONCE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|nonce| !nonce.is_empty());
+
+    let nonce = match nonce {
+        Some(nonce) => nonce,
+        None if required => {
+            return Err(AppError::BadRequest(format!(
+                "X-Request-Nonce is required to {}; get one from POST /api/v1/auth/nonce",
+                action.as_str().replace('_', " ")
+            ))
+            .into())
+        }
+        None => return Ok(()),
+    };
+
+    if !store.take(&nonce_key(user_id, action, nonce)).await? {
+        return Err(AppError::Conflict("Request nonce was already used or has expired".to_string()).into());
+    }
+    Ok(())
+}
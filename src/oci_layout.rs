@@ -0,0 +1,212 @@
+// Repository export as an OCI image layout
+//
+// GET /v2/_export/<name> streams a tarball holding `oci-layout`, `index.json`
+// and `blobs/<algorithm>/<hex>` for every tagged manifest and everything it
+// references, which skopeo, oras and crane can load as an `oci-archive`.
+// Blob sizes are checked before the first byte is sent, so a missing blob
+// fails the request instead of truncating the archive.
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::io;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use serde_json::json;
+use tokio::io::AsyncReadExt;
+use tokio_util::io::ReaderStream;
+
+use crate::handlers::docker_registry_v2::manifest_blob_descriptors;
+use crate::models::digest::Digest;
+use crate::storage::Storage;
+use crate::utils::tar;
+
+/// Contents of the `oci-layout` file
+pub const OCI_LAYOUT_FILE: &str = r#"{"imageLayoutVersion":"1.0.0"}"#;
+
+/// Annotation `index.json` names tags with
+pub const REF_NAME_ANNOTATION: &str = "org.opencontainers.image.ref.name";
+
+const OCI_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+
+/// A manifest stored in the repository being exported
+#[derive(Debug, Clone)]
+pub struct LayoutManifest {
+    pub digest: String,
+    pub media_type: String,
+    pub content: String,
+}
+
+/// A tag of the repository and the manifest it points at
+#[derive(Debug, Clone)]
+pub struct LayoutTag {
+    pub name: String,
+    pub digest: String,
+}
+
+/// Where the content of an archive entry comes from
+#[derive(Debug, Clone)]
+pub enum EntrySource {
+    Inline(Bytes),
+    /// Read from storage under this key while streaming
+    Stored(String),
+}
+
+/// A file of the archive
+#[derive(Debug, Clone)]
+pub struct LayoutEntry {
+    pub path: String,
+    pub size: u64,
+    pub source: EntrySource,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LayoutError {
+    #[error("manifest {0} is referenced but not stored in the repository")]
+    ManifestMissing(String),
+    #[error("blob {0} is referenced but missing from storage")]
+    BlobMissing(String),
+    #[error("invalid digest {0}")]
+    InvalidDigest(String),
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+/// Path of the blob with `digest` inside the layout
+fn blob_path(digest: &str) -> Result<String, LayoutError> {
+    let digest = Digest::parse(digest).map_err(|_| LayoutError::InvalidDigest(digest.to_string()))?;
+    Ok(format!("blobs/{}/{}", digest.algorithm(), digest.hex()))
+}
+
+/// The `index.json` of the layout: one descriptor per tag, named with
+/// `org.opencontainers.image.ref.name`
+pub fn layout_index(tags: &[LayoutTag], manifests: &[LayoutManifest]) -> Result<serde_json::Value, LayoutError> {
+    let by_digest: HashMap<&str, &LayoutManifest> = manifests.iter().map(|m| (m.digest.as_str(), m)).collect();
+    let descriptors = tags
+        .iter()
+        .map(|tag| {
+            let manifest = by_digest
+                .get(tag.digest.as_str())
+                .ok_or_else(|| LayoutError::ManifestMissing(tag.digest.clone()))?;
+            Ok(json!({
+                "mediaType": manifest.media_type,
+                "digest": manifest.digest,
+                "size": manifest.content.len(),
+                "annotations": { REF_NAME_ANNOTATION: tag.name },
+            }))
+        })
+        .collect::<Result<Vec<_>, LayoutError>>()?;
+
+    Ok(json!({
+        "schemaVersion": 2,
+        "mediaType": OCI_INDEX,
+        "manifests": descriptors,
+    }))
+}
+
+/// Entries of the archive for the tagged manifests: `oci-layout`,
+/// `index.json`, then the manifests and blobs they reference, walking into
+/// indexes. Untagged manifests no tag reaches are left out.
+pub async fn plan_layout(
+    storage: &dyn Storage,
+    tags: &[LayoutTag],
+    manifests: &[LayoutManifest],
+) -> Result<Vec<LayoutEntry>, LayoutError> {
+    let index = serde_json::to_vec(&layout_index(tags, manifests)?).map_err(anyhow::Error::from)?;
+    let by_digest: HashMap<&str, &LayoutManifest> = manifests.iter().map(|m| (m.digest.as_str(), m)).collect();
+
+    // Manifests by path, so each is written once however many tags reach it
+    let mut manifest_entries: BTreeMap<String, LayoutEntry> = BTreeMap::new();
+    let mut blob_digests: BTreeSet<String> = BTreeSet::new();
+    let mut pending: VecDeque<&str> = tags.iter().map(|tag| tag.digest.as_str()).collect();
+    while let Some(digest) = pending.pop_front() {
+        let path = blob_path(digest)?;
+        if manifest_entries.contains_key(&path) {
+            continue;
+        }
+        let manifest = by_digest
+            .get(digest)
+            .ok_or_else(|| LayoutError::ManifestMissing(digest.to_string()))?;
+
+        let value: serde_json::Value = serde_json::from_str(&manifest.content).unwrap_or_default();
+        if let Some(children) = value.get("manifests").and_then(|m| m.as_array()) {
+            pending.extend(children.iter().filter_map(|child| child.get("digest").and_then(|d| d.as_str())));
+        }
+        blob_digests.extend(manifest_blob_descriptors(&manifest.content).into_iter().map(|(digest, _)| digest));
+
+        manifest_entries.insert(path.clone(), LayoutEntry {
+            path,
+            size: manifest.content.len() as u64,
+            source: EntrySource::Inline(Bytes::from(manifest.content.clone())),
+        });
+    }
+
+    let mut entries = vec![
+        inline_entry("oci-layout", Bytes::from_static(OCI_LAYOUT_FILE.as_bytes())),
+        inline_entry("index.json", Bytes::from(index)),
+    ];
+    let mut blob_entries: BTreeMap<String, LayoutEntry> = BTreeMap::new();
+    for digest in blob_digests {
+        let path = blob_path(&digest)?;
+        if manifest_entries.contains_key(&path) {
+            continue;
+        }
+        let key = format!("blobs/{}", digest);
+        let metadata = storage
+            .get_blob_metadata(&key)
+            .await?
+            .ok_or_else(|| LayoutError::BlobMissing(digest.clone()))?;
+        blob_entries.insert(path.clone(), LayoutEntry { path, size: metadata.size, source: EntrySource::Stored(key) });
+    }
+    entries.extend(manifest_entries.into_values());
+    entries.extend(blob_entries.into_values());
+    Ok(entries)
+}
+
+fn inline_entry(path: &str, content: Bytes) -> LayoutEntry {
+    LayoutEntry { path: path.to_string(), size: content.len() as u64, source: EntrySource::Inline(content) }
+}
+
+/// Length of the archive `layout_stream` produces for `entries`
+pub fn layout_len(entries: &[LayoutEntry]) -> u64 {
+    entries.iter().map(|entry| tar::entry_len(&entry.path, entry.size)).sum::<u64>() + tar::END_OF_ARCHIVE.len() as u64
+}
+
+/// The tarball of `entries`, reading stored blobs one at a time as the
+/// stream is polled
+pub fn layout_stream(
+    storage: Arc<dyn Storage>,
+    entries: Vec<LayoutEntry>,
+) -> impl Stream<Item = io::Result<Bytes>> + Send + 'static {
+    stream::iter(entries)
+        .flat_map(move |entry| entry_stream(storage.clone(), entry))
+        .chain(stream::once(async { Ok(Bytes::from_static(&tar::END_OF_ARCHIVE)) }))
+}
+
+fn entry_stream(storage: Arc<dyn Storage>, entry: LayoutEntry) -> BoxStream<'static, io::Result<Bytes>> {
+    let header = Bytes::from(tar::file_header(&entry.path, entry.size));
+    let padding = Bytes::from(vec![0u8; tar::padding(entry.size)]);
+    let size = entry.size;
+
+    let content = match entry.source {
+        EntrySource::Inline(content) => stream::once(async move { Ok(content) }).boxed(),
+        EntrySource::Stored(key) => stream::once(async move {
+            match storage.get_blob_streaming(&key).await {
+                // Blobs are content-addressed, so the size planned is the size
+                // read; `take` keeps a changed blob from corrupting the archive
+                Ok(Some(reader)) => ReaderStream::new(reader.take(size)).boxed(),
+                Ok(None) => stream::once(async move {
+                    Err(io::Error::new(io::ErrorKind::NotFound, format!("{} disappeared during export", key)))
+                })
+                .boxed(),
+                Err(e) => stream::once(async move { Err(io::Error::new(io::ErrorKind::Other, e.to_string())) }).boxed(),
+            }
+        })
+        .flatten()
+        .boxed(),
+    };
+
+    stream::once(async move { Ok(header) })
+        .chain(content)
+        .chain(stream::once(async move { Ok(padding) }))
+        .boxed()
+}
@@ -3,20 +3,29 @@ use utoipa::Modify;
 use utoipa::openapi::security::{SecurityScheme, Http, HttpAuthScheme};
 
 use crate::handlers::{
+    analytics,
+    audit,
     auth,
     docker_registry_v2,
     organizations,
     repositories,
+    tag_expiry,
 };
 use crate::models::{
-    user::UserResponse,
+    user::{UserResponse, UpdateProfileRequest},
+    session::SessionResponse,
     organizations::{
         Organization, CreateOrganizationRequest, UpdateOrganizationRequest,
-        AddMemberRequest, UpdateMemberRequest, OrganizationMember,
+        AddMemberRequest, UpdateMemberRequest, OrganizationMember, RenameOrganizationRequest,
+        OrganizationAction, OrganizationPermissions, MemberRoleResponse,
     },
     repository::{Repository as RepositoryModel, CreateRepositoryRequest, RepositoryDetailsResponse},
+    audit::AuditLogEntry,
+    analytics::{AnalyticsBucket, AnalyticsGranularity, OrganizationAnalytics, RepositoryActivity},
+    tag_expiry::{TagExpiryRule, CreateTagExpiryRuleRequest, UpdateTagExpiryRuleRequest},
 };
-use crate::handlers::docker_registry_v2::{ApiVersionResponse, CatalogResponse, TagListResponse, BlobUploadResponse, ErrorResponse, RegistryError};
+use crate::handlers::docker_registry_v2::{ApiVersionResponse, CatalogResponse, TagListResponse, BlobUploadResponse, ErrorResponse, RegistryError, BulkTagDeleteRequest, BulkTagDeleteResponse, RepositoryDeleteResponse, LayerChange, ManifestDiff, ManifestDiffResponse, ImageConfigDetails, ImageConfigResponse, ManifestBatchRequest, ManifestBatchError, ManifestBatchEntry, ManifestBatchResponse, PopularRepository, PopularResponse};
+use crate::nonce::NonceAction;
 
 /// Security addon to add Bearer Auth to OpenAPI
 pub struct SecurityAddon;
@@ -50,13 +59,22 @@ impl Modify for SecurityAddon {
         auth::register,
         auth::login,
         auth::me, 
+        auth::update_me,
+        auth::upload_avatar,
+        auth::get_avatar,
+        auth::my_permissions,
+        auth::list_sessions,
+        auth::revoke_session,
         auth::refresh,
+        auth::introspect,
+        auth::issue_request_nonce,
         auth::change_password,
         auth::forgot_password,
         auth::verify_otp_and_reset,
         auth::get_user_api_keys,
         auth::create_api_key,
         auth::delete_api_key,     
+        auth::rotate_api_key,
 
         // Organization endpoints
         organizations::create_organization,
@@ -64,10 +82,14 @@ impl Modify for SecurityAddon {
         organizations::list_user_organizations,
         organizations::update_organization,
         organizations::delete_organization,
+        organizations::rename_organization,
         organizations::get_organization_members,
         organizations::add_organization_member,
+        organizations::get_member_role,
         organizations::update_member_role,
         organizations::remove_organization_member,
+        audit::export_audit_log,
+        analytics::get_organization_analytics,
 
         // Repository endpoints
         repositories::create_repository,
@@ -76,9 +98,14 @@ impl Modify for SecurityAddon {
         repositories::list_public_repositories,
         repositories::get_repository,
         repositories::delete_repository,
+        tag_expiry::list_tag_expiry_rules,
+        tag_expiry::create_tag_expiry_rule,
+        tag_expiry::update_tag_expiry_rule,
+        tag_expiry::delete_tag_expiry_rule,
 
         // Docker Registry V2 API endpoints
         docker_registry_v2::get_catalog,
+        docker_registry_v2::get_popular,
         docker_registry_v2::get_manifest,
         docker_registry_v2::head_manifest,
         docker_registry_v2::put_manifest,
@@ -91,14 +118,28 @@ impl Modify for SecurityAddon {
         docker_registry_v2::get_upload_status,
         docker_registry_v2::cancel_blob_upload,
         docker_registry_v2::list_tags,
+        docker_registry_v2::bulk_delete_tags,
+        docker_registry_v2::delete_repository,
+        docker_registry_v2::diff_manifests_handler,
+        docker_registry_v2::get_manifest_config,
+        docker_registry_v2::get_manifest_batch,
+        docker_registry_v2::export_repository,
     ),
     components(
         schemas(
             // User schemas
             UserResponse,
+            UpdateProfileRequest,
             auth::RegisterRequest,
             auth::LoginRequest,
             auth::RefreshRequest,
+            auth::IntrospectRequest,
+            auth::IntrospectResponse,
+            auth::NonceRequest,
+            auth::NonceResponse,
+            NonceAction,
+            auth::PermissionsResponse,
+            SessionResponse,
             auth::AuthResponse,
             auth::ChangePasswordRequest,
             auth::ForgotPasswordRequest,
@@ -107,15 +148,25 @@ impl Modify for SecurityAddon {
             auth::CreateApiKeyRequest,
             auth::CreateApiKeyResponse,
             auth::DeleteApiKeyResponse,
+            auth::RotateApiKeyResponse,
             auth::ApiKeyErrorResponse, 
 
             // Organization schemas
             Organization,
             CreateOrganizationRequest,
             UpdateOrganizationRequest,
+            RenameOrganizationRequest,
             AddMemberRequest,
             UpdateMemberRequest,
             OrganizationMember,
+            OrganizationAction,
+            OrganizationPermissions,
+            MemberRoleResponse,
+            AuditLogEntry,
+            OrganizationAnalytics,
+            AnalyticsBucket,
+            AnalyticsGranularity,
+            RepositoryActivity,
 
             // Repository schemas
             RepositoryModel,
@@ -129,11 +180,28 @@ impl Modify for SecurityAddon {
             repositories::RepositoryDetailsResponse,
             repositories::RepositoryStats,
             repositories::ListRepositoriesQuery,
+            TagExpiryRule,
+            CreateTagExpiryRuleRequest,
+            UpdateTagExpiryRuleRequest,
             
             // Docker Registry V2 API schemas
             ApiVersionResponse,
             CatalogResponse,
+            PopularRepository,
+            PopularResponse,
             TagListResponse,
+            BulkTagDeleteRequest,
+            BulkTagDeleteResponse,
+            RepositoryDeleteResponse,
+            LayerChange,
+            ManifestDiff,
+            ManifestDiffResponse,
+            ImageConfigDetails,
+            ImageConfigResponse,
+            ManifestBatchRequest,
+            ManifestBatchError,
+            ManifestBatchEntry,
+            ManifestBatchResponse,
             BlobUploadResponse,
             ErrorResponse,
             RegistryError,
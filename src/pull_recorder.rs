@@ -0,0 +1,200 @@
+// Background counting of manifest pulls
+//
+// Pulls feed the startup cache warmup, `/v2/_popular` and organization
+// analytics. Handlers only queue a pull; one task takes what has queued up,
+// folds repeated pulls together and writes each tenant's share in a few
+// statements, so a burst of pulls does not become a burst of writes. The
+// queue is bounded: when it is full, pulls are left uncounted rather than
+// holding up the pulls themselves.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+
+use crate::models::analytics::ActivityAction;
+use crate::models::digest::Digest;
+use crate::tenant::TenantContext;
+
+/// Pulls waiting to be written before further ones are dropped
+pub const PULL_QUEUE_CAPACITY: usize = 10_000;
+/// Most pulls written in one batch
+const BATCH_SIZE: usize = 1_000;
+/// Pause between batches, so pulls arriving meanwhile share the next one
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One manifest pull
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestPull {
+    /// Tenant whose tables hold the repository
+    pub tenant: Option<TenantContext>,
+    /// Organization named in the pull; `None` for the default organization
+    pub organization: Option<String>,
+    pub repository: String,
+    pub digest: Digest,
+    /// User ID of the puller, or `org_<id>`; `None` when anonymous
+    pub puller: Option<String>,
+}
+
+/// The pulls of one tenant, folded for writing
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TenantPulls {
+    /// Pulls per manifest, by organization, repository and digest
+    pub manifests: HashMap<(Option<String>, String, Digest), i64>,
+    /// Pulls by organization, repository and puller
+    pub activity: HashMap<(Option<String>, String, Option<String>), i64>,
+}
+
+/// Group `pulls` by tenant and fold repeated pulls of a manifest together
+pub fn fold_pulls(pulls: Vec<ManifestPull>) -> Vec<(Option<TenantContext>, TenantPulls)> {
+    let mut tenants: Vec<(Option<TenantContext>, TenantPulls)> = Vec::new();
+    for pull in pulls {
+        let index = match tenants.iter().position(|(tenant, _)| *tenant == pull.tenant) {
+            Some(index) => index,
+            None => {
+                tenants.push((pull.tenant.clone(), TenantPulls::default()));
+                tenants.len() - 1
+            }
+        };
+        let folded = &mut tenants[index].1;
+        *folded
+            .manifests
+            .entry((pull.organization.clone(), pull.repository.clone(), pull.digest))
+            .or_insert(0) += 1;
+        *folded.activity.entry((pull.organization, pull.repository, pull.puller)).or_insert(0) += 1;
+    }
+    tenants
+}
+
+/// Queues pulls for the background writer
+#[derive(Clone)]
+pub struct PullRecorder {
+    sender: mpsc::Sender<ManifestPull>,
+}
+
+impl PullRecorder {
+    /// Recorder writing to `pool` from a background task
+    pub fn start(pool: PgPool) -> Self {
+        let (recorder, receiver) = Self::new(PULL_QUEUE_CAPACITY);
+        tokio::spawn(write_pulls(pool, receiver));
+        recorder
+    }
+
+    /// Recorder and the receiving end of its queue, which holds `capacity` pulls
+    pub fn new(capacity: usize) -> (Self, mpsc::Receiver<ManifestPull>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        (Self { sender }, receiver)
+    }
+
+    /// Queue `pull`; returns false when it was dropped because the queue is full
+    pub fn record(&self, pull: ManifestPull) -> bool {
+        match self.sender.try_send(pull) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                tracing::debug!("Pull queue full, pull not counted");
+                false
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+async fn write_pulls(pool: PgPool, mut receiver: mpsc::Receiver<ManifestPull>) {
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    while receiver.recv_many(&mut batch, BATCH_SIZE).await > 0 {
+        for (tenant, pulls) in fold_pulls(std::mem::take(&mut batch)) {
+            let written = match tenant {
+                Some(tenant) => tenant.scope(write_tenant_pulls(&pool, pulls)).await,
+                None => write_tenant_pulls(&pool, pulls).await,
+            };
+            if let Err(e) = written {
+                tracing::debug!("Failed to count manifest pulls: {}", e);
+            }
+        }
+        tokio::time::sleep(FLUSH_INTERVAL).await;
+    }
+}
+
+/// Add `pulls` to the manifest pull counts, the daily repository totals and
+/// the daily activity counts. Names without an organization belong to the
+/// default one.
+async fn write_tenant_pulls(pool: &PgPool, pulls: TenantPulls) -> Result<(), sqlx::Error> {
+    let mut organizations = Vec::with_capacity(pulls.manifests.len());
+    let mut repositories = Vec::with_capacity(pulls.manifests.len());
+    let mut digests = Vec::with_capacity(pulls.manifests.len());
+    let mut counts = Vec::with_capacity(pulls.manifests.len());
+    for ((organization, repository, digest), count) in pulls.manifests {
+        organizations.push(organization);
+        repositories.push(repository);
+        digests.push(digest);
+        counts.push(count);
+    }
+
+    // A repository may be named with and without the default organization,
+    // so counts are summed per row before they are added
+    sqlx::query(
+        "UPDATE manifests m SET pull_count = m.pull_count + c.pulls
+         FROM (
+             SELECT m2.id, SUM(p.pulls)::BIGINT AS pulls
+             FROM UNNEST($1::TEXT[], $2::TEXT[], $3::TEXT[], $4::BIGINT[]) AS p(organization, repository, digest, pulls)
+             JOIN repositories r ON r.name = p.repository
+             JOIN organizations o ON o.id = r.organization_id
+                 AND (o.name = p.organization OR (p.organization IS NULL AND o.id = 1))
+             JOIN manifests m2 ON m2.repository_id = r.id AND m2.digest = p.digest
+             GROUP BY m2.id
+         ) c
+         WHERE m.id = c.id",
+    )
+    .bind(&organizations)
+    .bind(&repositories)
+    .bind(&digests)
+    .bind(&counts)
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO repository_daily_pulls (repository_id, day, pulls)
+         SELECT r.id, CURRENT_DATE, SUM(p.pulls)::BIGINT
+         FROM UNNEST($1::TEXT[], $2::TEXT[], $3::BIGINT[]) AS p(organization, repository, pulls)
+         JOIN repositories r ON r.name = p.repository
+         JOIN organizations o ON o.id = r.organization_id
+             AND (o.name = p.organization OR (p.organization IS NULL AND o.id = 1))
+         GROUP BY r.id
+         ON CONFLICT (repository_id, day) DO UPDATE SET pulls = repository_daily_pulls.pulls + EXCLUDED.pulls",
+    )
+    .bind(&organizations)
+    .bind(&repositories)
+    .bind(&counts)
+    .execute(pool)
+    .await?;
+
+    let mut organizations = Vec::with_capacity(pulls.activity.len());
+    let mut repositories = Vec::with_capacity(pulls.activity.len());
+    let mut pullers = Vec::with_capacity(pulls.activity.len());
+    let mut counts = Vec::with_capacity(pulls.activity.len());
+    for ((organization, repository, puller), count) in pulls.activity {
+        organizations.push(organization);
+        repositories.push(repository);
+        pullers.push(puller);
+        counts.push(count);
+    }
+    sqlx::query(
+        "INSERT INTO registry_activity_daily (organization_id, repository_id, day, action, actor, count)
+         SELECT o.id, r.id, CURRENT_DATE, $5, p.actor, SUM(p.pulls)::BIGINT
+         FROM UNNEST($1::TEXT[], $2::TEXT[], $3::TEXT[], $4::BIGINT[]) AS p(organization, repository, actor, pulls)
+         JOIN repositories r ON r.name = p.repository
+         JOIN organizations o ON o.id = r.organization_id
+             AND (o.name = p.organization OR (p.organization IS NULL AND o.id = 1))
+         GROUP BY o.id, r.id, p.actor
+         ON CONFLICT (repository_id, day, action, (COALESCE(actor, '')))
+         DO UPDATE SET count = registry_activity_daily.count + EXCLUDED.count",
+    )
+    .bind(&organizations)
+    .bind(&repositories)
+    .bind(&pullers)
+    .bind(&counts)
+    .bind(ActivityAction::Pull.as_str())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+// Global read-only mode
+//
+// Set with READ_ONLY_MODE at startup or toggled through PUT /admin/read-only,
+// for disaster recovery and migrations. While it is on every mutating request
+// is refused with 503, pulls and other reads keep working, and background
+// writers (cleanup tasks, tag expiry, pull counting) skip their work. Reads
+// also skip the bookkeeping writes of authentication, such as when a session
+// or API key was last used.
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRef, Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::error::AppError;
+use crate::AppState;
+
+/// Path of the admin endpoint that toggles the mode, which stays writable
+pub const READ_ONLY_ADMIN_PATH: &str = "/admin/read-only";
+
+/// Base delay of the `Retry-After` sent with refused writes
+pub const READ_ONLY_RETRY_AFTER_SECONDS: u64 = 60;
+
+/// Requests that use POST without changing anything, or that must keep
+/// working so readers can sign in; while paused, sign-ins record no session
+/// and refreshes do not extend one
+const READ_ONLY_EXEMPT_POSTS: &[&str] = &[
+    "/api/v1/auth/login",
+    "/api/v1/auth/refresh",
+    "/api/v1/auth/introspect",
+];
+
+/// Whether the registry is read-only; shared by all clones
+#[derive(Debug, Clone, Default)]
+pub struct ReadOnlyMode(Arc<AtomicBool>);
+
+impl ReadOnlyMode {
+    pub fn new(enabled: bool) -> Self {
+        Self(Arc::new(AtomicBool::new(enabled)))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn set(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::SeqCst);
+    }
+}
+
+impl FromRef<AppState> for ReadOnlyMode {
+    fn from_ref(state: &AppState) -> Self {
+        state.read_only.clone()
+    }
+}
+
+/// Whether a `method` request to `path` may change state, and so is refused
+/// in read-only mode
+pub fn is_write_request(method: &Method, path: &str) -> bool {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) || path == READ_ONLY_ADMIN_PATH {
+        return false;
+    }
+    // Fetching several manifests at once is a pull
+    let batch_read = path.starts_with("/v2/_batch/");
+    !(*method == Method::POST && (batch_read || READ_ONLY_EXEMPT_POSTS.contains(&path)))
+}
+
+tokio::task_local! {
+    static WRITES_PAUSED: ();
+}
+
+/// Run `fut` as a request served in read-only mode
+pub async fn without_writes<F: Future>(fut: F) -> F::Output {
+    WRITES_PAUSED.scope((), fut).await
+}
+
+/// Whether the current request is served in read-only mode, so must not
+/// write even in passing
+pub fn writes_paused() -> bool {
+    WRITES_PAUSED.try_with(|_| ()).is_ok()
+}
+
+/// Error answered to writes while read-only mode is on
+pub fn read_only_error() -> AppError {
+    AppError::Unavailable {
+        code: "READ_ONLY",
+        message: "Registry is in read-only mode".to_string(),
+        retry_after_seconds: READ_ONLY_RETRY_AFTER_SECONDS,
+    }
+}
+
+/// Refuse writes with 503 while read-only mode is on
+pub async fn read_only_middleware(State(mode): State<ReadOnlyMode>, request: Request, next: Next) -> Response {
+    if !mode.is_enabled() {
+        return next.run(request).await;
+    }
+    if !is_write_request(request.method(), request.uri().path()) {
+        return without_writes(next.run(request)).await;
+    }
+
+    let path = request.uri().path();
+    tracing::debug!("Refusing {} {} in read-only mode", request.method(), path);
+    if path == "/v2" || path.starts_with("/v2/") {
+        return read_only_error().into_registry_response();
+    }
+    read_only_error().into_response()
+}
@@ -0,0 +1,18 @@
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+
+use crate::handlers::admin;
+use crate::AppState;
+
+/// Operator endpoints; they require the `X-Admin-Token` header
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/migrations", get(admin::migrations))
+        .route("/admin/storage-usage", get(admin::storage_usage))
+        .route("/admin/scan-results", post(admin::record_scan_result))
+        .route("/admin/organizations/:name/seats", put(admin::set_seat_limit))
+        .route("/admin/organizations/:name/image-size-limit", put(admin::set_image_size_limit))
+        .route("/admin/read-only", get(admin::get_read_only).put(admin::set_read_only))
+}
@@ -10,11 +10,21 @@ pub fn auth_router() -> Router<AppState> {
         .route("/register", post(auth::register))
         .route("/login", post(auth::login))
         .route("/logout", post(auth::logout))
-        .route("/me", get(auth::me))
+        .route("/me", get(auth::me).put(auth::update_me))
+        .route("/me/avatar", put(auth::upload_avatar))
+        .route("/users/:id/avatar", get(auth::get_avatar))
+        .route("/me/permissions", get(auth::my_permissions))
+        .route("/me/sessions", get(auth::list_sessions))
+        .route("/me/sessions/:id", delete(auth::revoke_session))
         .route("/api-keys", get(auth::get_user_api_keys))
         .route("/api-keys", post(auth::create_api_key))
         .route("/api-keys/:id", delete(auth::delete_api_key))
+        .route("/api-keys/:id/rotate", post(auth::rotate_api_key))
+        // API keys are the registry's personal access tokens
+        .route("/tokens/:id/rotate", post(auth::rotate_api_key))
         .route("/refresh", post(auth::refresh))
+        .route("/introspect", post(auth::introspect))
+        .route("/nonce", post(auth::issue_request_nonce))
         .route("/change-password", put(auth::change_password))
         .route("/forgot-password", post(auth::forgot_password))
         .route("/verify-otp", post(auth::verify_otp_and_reset))
@@ -0,0 +1,11 @@
+use axum::{routing::get, Router};
+
+use crate::handlers::profiling;
+use crate::AppState;
+
+/// Profiling endpoints; they answer 404 unless ENABLE_PROFILING is set
+pub fn debug_router() -> Router<AppState> {
+    Router::new()
+        .route("/debug/pprof/profile", get(profiling::cpu_profile))
+        .route("/debug/pprof/heap", get(profiling::heap_stats))
+}
@@ -25,6 +25,9 @@ pub fn docker_registry_v2_router() -> Router<AppState> {
         
         // Repository catalog
         .route("/v2/_catalog", get(docker_registry_v2::get_catalog))
+
+        // Most pulled public repositories
+        .route("/v2/_popular", get(docker_registry_v2::get_popular))
         
         // Use more specific patterns for Docker registry endpoints
         // These patterns should handle both simple names and namespaced names like org/repo
@@ -32,6 +35,14 @@ pub fn docker_registry_v2_router() -> Router<AppState> {
         // Tag listing endpoints - handles simple names and namespaced names
         .route("/v2/:name/tags/list", get(docker_registry_v2::list_tags))
         .route("/v2/:org/:name/tags/list", get(docker_registry_v2::list_tags_namespaced))
+
+        // Repository deletion - removes tags and manifests, queues blob cleanup
+        .route("/v2/:name", delete(docker_registry_v2::delete_repository))
+        .route("/v2/:org/:name", delete(docker_registry_v2::delete_repository_namespaced))
+
+        // Bulk tag deletion - exact names or a glob pattern
+        .route("/v2/:name/tags/delete", post(docker_registry_v2::bulk_delete_tags))
+        .route("/v2/:org/:name/tags/delete", post(docker_registry_v2::bulk_delete_tags_namespaced))
         
         // Manifest operations - simple names
         .route("/v2/:name/manifests/:reference", 
@@ -49,6 +60,23 @@ pub fn docker_registry_v2_router() -> Router<AppState> {
                 .delete(docker_registry_v2::delete_manifest_namespaced)
         )
         
+        // Manifest comparison - layers added and removed between two references
+        .route("/v2/:name/manifests/:from/diff/:to", get(docker_registry_v2::diff_manifests_handler))
+        .route("/v2/:org/:name/manifests/:from/diff/:to", get(docker_registry_v2::diff_manifests_namespaced))
+
+        // Several manifests in one request, under a reserved prefix so no
+        // repository or tag name is shadowed
+        .route("/v2/_batch/:name/manifests", post(docker_registry_v2::get_manifest_batch))
+        .route("/v2/_batch/:org/:name/manifests", post(docker_registry_v2::get_manifest_batch_namespaced))
+
+        // Whole repository as an OCI image layout tarball
+        .route("/v2/_export/:name", get(docker_registry_v2::export_repository))
+        .route("/v2/_export/:org/:name", get(docker_registry_v2::export_repository_namespaced))
+
+        // Decoded image config of a manifest
+        .route("/v2/:name/manifests/:reference/config", get(docker_registry_v2::get_manifest_config))
+        .route("/v2/:org/:name/manifests/:reference/config", get(docker_registry_v2::get_manifest_config_namespaced))
+        
         // Blob operations for simple names
         .route("/v2/:name/blobs/:digest", 
             get(docker_registry_v2::get_blob)
@@ -8,12 +8,15 @@ use axum::{
 };
 use serde_json::json;
 
+use crate::handlers::health::{dependency_health, readiness};
 use crate::AppState;
 
 pub fn health_router() -> Router<AppState> {
     Router::new()
         .route("/health", get(check_health))
+        .route("/health/ready", get(readiness))
         .route("/health/cache", get(cache_stats))
+        .route("/health/dependencies", get(dependency_health))
 }
 
 async fn check_health() -> impl IntoResponse {
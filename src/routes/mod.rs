@@ -1,6 +1,8 @@
 // Routes module
+pub mod admin;
 pub mod api;
 pub mod auth;
+pub mod debug;
 pub mod docker_registry_v2;
 pub mod health;
 pub mod organizations;
@@ -1,4 +1,4 @@
-use crate::handlers::organizations;
+use crate::handlers::{analytics, audit, organizations};
 use crate::AppState;
 use axum::{
     routing::{delete, get, post, put},
@@ -13,6 +13,8 @@ pub fn organization_router() -> Router<AppState> {
         .route("/:id", get(organizations::get_organization))
         .route("/:id", put(organizations::update_organization))
         .route("/:id", delete(organizations::delete_organization))
+        // The segment is the organization name here; axum requires one parameter name per position
+        .route("/:id/rename", post(organizations::rename_organization))
         // Member management
         .route(
             "/:id/members",
@@ -30,4 +32,13 @@ pub fn organization_router() -> Router<AppState> {
             "/:id/members/:member_id",
             delete(organizations::remove_organization_member),
         )
+        // The first segment is the organization name here
+        .route(
+            "/:id/members/:member_id/role",
+            get(organizations::get_member_role),
+        )
+        // Audit log export; the segment is the organization name
+        .route("/:id/audit/export", get(audit::export_audit_log))
+        // Pull and push analytics; the segment is the organization name
+        .route("/:id/analytics", get(analytics::get_organization_analytics))
 }
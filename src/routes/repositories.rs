@@ -1,5 +1,5 @@
 use axum::{
-    routing::{get, post, delete},
+    routing::{get, post, put, delete},
     Router,
 };
 
@@ -12,6 +12,12 @@ use crate::{
         delete_repository,
         get_repository,
     },
+    handlers::tag_expiry::{
+        list_tag_expiry_rules,
+        create_tag_expiry_rule,
+        update_tag_expiry_rule,
+        delete_tag_expiry_rule,
+    },
     AppState,
 };
 
@@ -23,4 +29,6 @@ pub fn repository_router() -> Router<AppState> {
         .route("/repositories/:namespace", get(list_repositories_by_namespace))  // List filtered by namespace
         .route("/:namespace/repositories/:repo_name", get(get_repository))  // Get repository details
         .route("/:namespace/:repo_name", delete(delete_repository))
+        .route("/:namespace/:repo_name/tag-expiry-rules", get(list_tag_expiry_rules).post(create_tag_expiry_rule))
+        .route("/:namespace/:repo_name/tag-expiry-rules/:rule_id", put(update_tag_expiry_rule).delete(delete_tag_expiry_rule))
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+// Tokio runtime construction from server settings
+use crate::config::settings::ServerSettings;
+
+/// Build the multi-threaded runtime the server runs on, applying
+/// TOKIO_WORKER_THREADS / TOKIO_MAX_BLOCKING_THREADS when set.
+pub fn build_runtime(server: &ServerSettings) -> std::io::Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+
+    if let Some(worker_threads) = server.worker_threads {
+        builder.worker_threads(worker_threads);
+    }
+
+    if let Some(max_blocking_threads) = server.max_blocking_threads {
+        builder.max_blocking_threads(max_blocking_threads);
+    }
+
+    builder.build()
+}
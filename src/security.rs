@@ -0,0 +1,35 @@
+// Transport security headers
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::config::settings::ServerSettings;
+use crate::AppState;
+
+/// `Strict-Transport-Security` value, when HSTS is enabled
+pub fn hsts_header_value(settings: &ServerSettings) -> Option<HeaderValue> {
+    if !settings.enable_hsts {
+        return None;
+    }
+
+    HeaderValue::from_str(&format!("max-age={}; includeSubDomains", settings.hsts_max_age_secs)).ok()
+}
+
+/// Add `Strict-Transport-Security` to every response. Only enable it when the
+/// server is reached over TLS (directly or through a TLS-terminating proxy).
+pub async fn hsts_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+
+    if let Some(value) = hsts_header_value(&state.config.server) {
+        response.headers_mut().insert(header::STRICT_TRANSPORT_SECURITY, value);
+    }
+
+    response
+}
@@ -0,0 +1,133 @@
+// Request-scoped Server-Timing breakdown, enabled with ENABLE_SERVER_TIMING
+use std::cell::RefCell;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::AppState;
+
+pub const SERVER_TIMING: HeaderName = HeaderName::from_static("server-timing");
+
+tokio::task_local! {
+    static TIMINGS: RefCell<ServerTimings>;
+}
+
+/// Time spent in each backend while handling one request. Database calls
+/// count towards `db` through `db::timed` or a `QueryTimer`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ServerTimings {
+    pub db: Duration,
+    pub cache: Duration,
+    pub storage: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimingMetric {
+    Db,
+    Cache,
+    Storage,
+}
+
+impl TimingMetric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimingMetric::Db => "db",
+            TimingMetric::Cache => "cache",
+            TimingMetric::Storage => "storage",
+        }
+    }
+}
+
+impl ServerTimings {
+    pub fn add(&mut self, metric: TimingMetric, elapsed: Duration) {
+        match metric {
+            TimingMetric::Db => self.db += elapsed,
+            TimingMetric::Cache => self.cache += elapsed,
+            TimingMetric::Storage => self.storage += elapsed,
+        }
+    }
+
+    /// Header value with one entry per backend plus the request total,
+    /// durations in milliseconds, e.g. `db;dur=1.250, cache;dur=0.030, ...`
+    pub fn header_value(&self, total: Duration) -> String {
+        [
+            (TimingMetric::Db.name(), self.db),
+            (TimingMetric::Cache.name(), self.cache),
+            (TimingMetric::Storage.name(), self.storage),
+            ("total", total),
+        ]
+        .iter()
+        .map(|(name, duration)| format!("{};dur={:.3}", name, duration.as_secs_f64() * 1000.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// Add `elapsed` to the current request's timings. A no-op outside a request
+/// being timed (background tasks, or when Server-Timing is disabled).
+pub fn record(metric: TimingMetric, elapsed: Duration) {
+    let _ = TIMINGS.try_with(|timings| timings.borrow_mut().add(metric, elapsed));
+}
+
+/// Records the time until it is dropped against `metric`
+pub struct TimingGuard {
+    metric: TimingMetric,
+    start: Instant,
+}
+
+impl TimingGuard {
+    pub fn start(metric: TimingMetric) -> Self {
+        Self {
+            metric,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for TimingGuard {
+    fn drop(&mut self) {
+        record(self.metric, self.start.elapsed());
+    }
+}
+
+/// Run `fut` with a fresh timing scope and return its output with the timings
+/// recorded while it ran.
+pub async fn collect<F: Future>(fut: F) -> (F::Output, ServerTimings) {
+    TIMINGS
+        .scope(RefCell::new(ServerTimings::default()), async move {
+            let output = fut.await;
+            let timings = TIMINGS.with(|timings| *timings.borrow());
+            (output, timings)
+        })
+        .await
+}
+
+/// Produce the response of `fut` with a `Server-Timing` header covering it
+pub async fn with_server_timing<F: Future<Output = Response>>(fut: F) -> Response {
+    let start = Instant::now();
+    let (mut response, timings) = collect(fut).await;
+
+    if let Ok(value) = HeaderValue::from_str(&timings.header_value(start.elapsed())) {
+        response.headers_mut().insert(SERVER_TIMING, value);
+    }
+    response
+}
+
+/// Adds a `Server-Timing` header to every response when enabled
+pub async fn server_timing_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.config.server.enable_server_timing {
+        return next.run(request).await;
+    }
+
+    with_server_timing(next.run(request)).await
+}
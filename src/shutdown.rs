@@ -0,0 +1,78 @@
+// Graceful shutdown with connection draining
+//
+// When a shutdown signal arrives the readiness endpoint starts answering 503
+// straight away, but the server keeps serving for SHUTDOWN_DRAIN_DELAY_SECONDS
+// so load balancers can deregister the instance before connections stop.
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::FromRef;
+
+use crate::AppState;
+
+/// Whether the instance should receive new traffic; shared by all clones
+#[derive(Debug, Clone)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Stop advertising the instance; there is no way back
+    pub fn set_draining(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Self(Arc::new(AtomicBool::new(true)))
+    }
+}
+
+impl FromRef<AppState> for Readiness {
+    fn from_ref(state: &AppState) -> Self {
+        state.readiness.clone()
+    }
+}
+
+/// Resolves once `signal` has fired and `delay` has passed, marking the
+/// instance not ready in between. Pass it to `with_graceful_shutdown`.
+pub async fn drain<F: Future<Output = ()>>(signal: F, readiness: Readiness, delay: Duration) {
+    signal.await;
+    readiness.set_draining();
+    tracing::info!("Readiness set to draining, shutting down in {}s", delay.as_secs_f64());
+    tokio::time::sleep(delay).await;
+}
+
+/// Resolves on Ctrl+C or SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {
+            tracing::info!("🛑 Received Ctrl+C, starting graceful shutdown");
+        },
+        _ = terminate => {
+            tracing::info!("🛑 Received terminate signal, starting graceful shutdown");
+        },
+    }
+}
@@ -0,0 +1,75 @@
+// Serving while dependencies start
+//
+// The listener is bound before the database and other dependencies are
+// ready. Until the application is handed over, every request is answered
+// with a 503 naming what startup is still waiting on, so orchestrators see
+// a starting instance instead of refused connections.
+use std::sync::{Arc, Mutex, OnceLock};
+
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower::ServiceExt;
+
+use crate::error::AppError;
+
+/// Base delay of the `Retry-After` sent while starting
+pub const STARTUP_RETRY_AFTER_SECONDS: u64 = 5;
+
+/// Answers for the application until it is ready, then forwards to it
+#[derive(Clone)]
+pub struct StartupGate {
+    app: Arc<OnceLock<Router>>,
+    waiting_on: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl StartupGate {
+    /// Gate waiting on each of `dependencies`
+    pub fn new(dependencies: &[&'static str]) -> Self {
+        Self {
+            app: Arc::new(OnceLock::new()),
+            waiting_on: Arc::new(Mutex::new(dependencies.to_vec())),
+        }
+    }
+
+    /// Dependencies startup is still waiting on
+    pub fn waiting_on(&self) -> Vec<&'static str> {
+        self.waiting_on.lock().unwrap().clone()
+    }
+
+    pub fn dependency_ready(&self, dependency: &str) {
+        self.waiting_on.lock().unwrap().retain(|waiting| *waiting != dependency);
+    }
+
+    /// Start serving `app`; later calls are ignored
+    pub fn open(&self, app: Router) {
+        self.waiting_on.lock().unwrap().clear();
+        let _ = self.app.set(app);
+    }
+
+    /// Router to serve from the moment the listener is bound
+    pub fn router(&self) -> Router {
+        let gate = self.clone();
+        Router::new().fallback(move |request: Request| {
+            let gate = gate.clone();
+            async move { gate.handle(request).await }
+        })
+    }
+
+    async fn handle(&self, request: Request) -> Response {
+        match self.app.get() {
+            Some(app) => match app.clone().oneshot(request).await {
+                Ok(response) => response,
+                Err(never) => match never {},
+            },
+            None => AppError::Unavailable {
+                code: "STARTING",
+                message: format!("Server is starting, waiting on: {}", self.waiting_on().join(", ")),
+                retry_after_seconds: STARTUP_RETRY_AFTER_SECONDS,
+            }
+            .into_response(),
+        }
+    }
+}
@@ -100,7 +100,8 @@ impl Storage for FilesystemStorage {
             Ok(metadata) => Ok(Some(BlobMetadata {
                 size: metadata.len(),
                 digest: digest.to_string(),
-                created_at: chrono::DateTime::from(metadata.created()?),
+                // Not every filesystem records creation time
+                created_at: chrono::DateTime::from(metadata.created().or_else(|_| metadata.modified())?),
                 content_type: None,
             })),
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
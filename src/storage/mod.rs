@@ -48,6 +48,57 @@ pub trait Storage: Send + Sync + 'static {
 
     /// Perform a health check on the storage backend
     async fn health_check(&self) -> Result<()>;
+
+    /// Copy a blob to a new key
+    async fn copy_blob(&self, from: &str, to: &str) -> Result<()> {
+        match self.get_blob(from).await? {
+            Some(data) => self.put_blob(to, data).await,
+            None => Err(anyhow::anyhow!("Blob not found: {}", from)),
+        }
+    }
+
+    // Multipart uploads assemble an object from separately uploaded parts, so
+    // a long upload can be continued by any instance, even after a restart.
+    // Backends without native support emulate them with one object per part.
+
+    /// Start a multipart upload to `key`, returning its upload ID
+    async fn multipart_create(&self, key: &str) -> Result<String> {
+        let _ = key;
+        Ok(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Upload part `part_number` (from 1), returning its ETag
+    async fn multipart_upload_part(&self, key: &str, upload_id: &str, part_number: i32, data: Bytes) -> Result<String> {
+        self.put_blob(&emulated_part_key(key, upload_id, part_number), data).await?;
+        Ok(part_number.to_string())
+    }
+
+    /// Assemble the uploaded parts, in order, into `key`
+    async fn multipart_complete(&self, key: &str, upload_id: &str, parts: &[(i32, String)]) -> Result<()> {
+        let mut assembled = Vec::new();
+        for (part_number, _etag) in parts {
+            let part_key = emulated_part_key(key, upload_id, *part_number);
+            match self.get_blob(&part_key).await? {
+                Some(data) => assembled.extend_from_slice(&data),
+                None => return Err(anyhow::anyhow!("Missing part {} of upload {}", part_number, upload_id)),
+            }
+        }
+        self.put_blob(key, Bytes::from(assembled)).await?;
+        self.multipart_abort(key, upload_id, parts).await
+    }
+
+    /// Discard an unfinished multipart upload and its parts
+    async fn multipart_abort(&self, key: &str, upload_id: &str, parts: &[(i32, String)]) -> Result<()> {
+        for (part_number, _etag) in parts {
+            self.delete_blob(&emulated_part_key(key, upload_id, *part_number)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Key of a part of an emulated multipart upload
+fn emulated_part_key(key: &str, upload_id: &str, part_number: i32) -> String {
+    format!("{}.parts/{}/{:05}", key, upload_id, part_number)
 }
 
 /// Storage configuration trait that must be implemented by all storage providers
@@ -58,4 +109,8 @@ pub trait StorageConfig: Send + Sync + 'static {
 
 // Re-export storage implementations
 pub mod filesystem;
+pub mod router;
 pub mod s3;
+pub mod timed;
+pub mod upload_session;
+pub mod verify;
@@ -0,0 +1,304 @@
+// Routing of stored content to different backends
+//
+// Operators can keep manifests on fast storage and large layers on cheap
+// object storage. Writes go to the backend the first matching route names
+// (or the default backend); reads look in the default backend first and then
+// in the others, so content stays reachable when routes change.
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::io::AsyncRead;
+
+use super::filesystem::FilesystemStorage;
+use super::{BlobMetadata, Storage};
+use crate::config::settings::StorageSettings;
+
+/// Name of the backend configured by the `S3_*` settings
+pub const S3_BACKEND: &str = "s3";
+
+/// Name of the backend configured by `STORAGE_FILESYSTEM_PATH`
+pub const FILESYSTEM_BACKEND: &str = "filesystem";
+
+/// What is being stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    Manifest,
+    /// Layers and config blobs
+    Blob,
+}
+
+/// Description of content about to be stored, used to pick its backend
+#[derive(Debug, Clone, Copy)]
+pub struct StoredContent<'a> {
+    pub kind: ContentKind,
+    pub media_type: Option<&'a str>,
+    pub size: u64,
+}
+
+impl<'a> StoredContent<'a> {
+    pub fn manifest(media_type: &'a str, size: u64) -> Self {
+        Self { kind: ContentKind::Manifest, media_type: Some(media_type), size }
+    }
+
+    pub fn blob(size: u64) -> Self {
+        Self { kind: ContentKind::Blob, media_type: None, size }
+    }
+}
+
+/// Which content a route applies to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContentMatch {
+    Manifest,
+    Blob,
+    /// An exact media type, e.g. `application/vnd.oci.image.index.v1+json`
+    MediaType(String),
+}
+
+/// A routing rule, written `<content>[:<min size in bytes>]=<backend>` where
+/// content is `manifest`, `blob` or a media type. Examples:
+/// `manifest=filesystem`, `blob:104857600=s3`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageRoute {
+    pub content: ContentMatch,
+    /// Only content of at least this many bytes matches
+    pub min_size: Option<u64>,
+    pub backend: String,
+}
+
+impl StorageRoute {
+    pub fn matches(&self, content: &StoredContent) -> bool {
+        let content_matches = match &self.content {
+            ContentMatch::Manifest => content.kind == ContentKind::Manifest,
+            ContentMatch::Blob => content.kind == ContentKind::Blob,
+            ContentMatch::MediaType(media_type) => content.media_type == Some(media_type.as_str()),
+        };
+        content_matches && self.min_size.map_or(true, |min| content.size >= min)
+    }
+}
+
+impl std::str::FromStr for StorageRoute {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (selector, backend) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("Invalid storage route (expected <content>=<backend>): {}", s))?;
+        let backend = backend.trim();
+        if backend.is_empty() {
+            return Err(format!("Storage route has no backend: {}", s));
+        }
+
+        // Media types contain no ':', so a trailing `:<n>` is always a size
+        let (content, min_size) = match selector.rsplit_once(':') {
+            Some((content, size)) => {
+                let size = size
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid minimum size in storage route: {}", s))?;
+                (content.trim(), Some(size))
+            }
+            None => (selector.trim(), None),
+        };
+
+        let content = match content {
+            "manifest" => ContentMatch::Manifest,
+            "blob" => ContentMatch::Blob,
+            media_type if media_type.contains('/') => ContentMatch::MediaType(media_type.to_string()),
+            other => return Err(format!("Unknown content in storage route: {}", other)),
+        };
+
+        Ok(Self { content, min_size, backend: backend.to_string() })
+    }
+}
+
+/// Parse a comma-separated list of routes, as in `STORAGE_ROUTES`
+pub fn parse_routes(routes: &str) -> Result<Vec<StorageRoute>, String> {
+    routes
+        .split(',')
+        .map(str::trim)
+        .filter(|route| !route.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Named storage backends and the routes choosing between them
+pub struct StorageRouter {
+    /// The default backend comes first
+    backends: Vec<(String, Arc<dyn Storage>)>,
+    routes: Vec<StorageRoute>,
+}
+
+impl StorageRouter {
+    /// Fails if the default backend or a route names an unknown backend
+    pub fn new(
+        backends: Vec<(String, Arc<dyn Storage>)>,
+        default_backend: &str,
+        routes: Vec<StorageRoute>,
+    ) -> Result<Self> {
+        let mut backends = backends;
+        let default_index = backends
+            .iter()
+            .position(|(name, _)| name == default_backend)
+            .with_context(|| format!("Unknown default storage backend: {}", default_backend))?;
+        let default = backends.remove(default_index);
+        backends.insert(0, default);
+
+        for route in &routes {
+            if !backends.iter().any(|(name, _)| name == &route.backend) {
+                anyhow::bail!("Storage route refers to unknown backend: {}", route.backend);
+            }
+        }
+
+        Ok(Self { backends, routes })
+    }
+
+    /// Router over the S3 backend and, if configured, the filesystem backend
+    pub fn from_settings(settings: &StorageSettings, s3: Arc<dyn Storage>) -> Result<Self> {
+        let mut backends: Vec<(String, Arc<dyn Storage>)> = vec![(S3_BACKEND.to_string(), s3)];
+        if let Some(path) = &settings.filesystem_path {
+            backends.push((FILESYSTEM_BACKEND.to_string(), Arc::new(FilesystemStorage::new(path.into()))));
+        }
+
+        Self::new(backends, &settings.default_backend, settings.routes.clone())
+    }
+
+    pub fn default_backend(&self) -> &Arc<dyn Storage> {
+        &self.backends[0].1
+    }
+
+    pub fn backend(&self, name: &str) -> Option<&Arc<dyn Storage>> {
+        self.backends.iter().find(|(n, _)| n == name).map(|(_, backend)| backend)
+    }
+
+    /// Name of the backend `content` should be stored in
+    pub fn backend_name_for(&self, content: &StoredContent) -> &str {
+        self.routes
+            .iter()
+            .find(|route| route.matches(content))
+            .map(|route| route.backend.as_str())
+            .unwrap_or(self.backends[0].0.as_str())
+    }
+
+    /// Backend `content` should be stored in
+    pub fn select(&self, content: &StoredContent) -> &Arc<dyn Storage> {
+        let name = self.backend_name_for(content);
+        self.backend(name).unwrap_or_else(|| self.default_backend())
+    }
+
+    /// Move content written to the default backend (e.g. by a chunked
+    /// upload) to the backend its route selects, streaming it across so
+    /// large layers are never held in memory
+    pub async fn relocate(&self, key: &str, content: &StoredContent<'_>) -> Result<()> {
+        let name = self.backend_name_for(content);
+        if name == self.backends[0].0 {
+            return Ok(());
+        }
+
+        let reader = self
+            .default_backend()
+            .get_blob_streaming(key)
+            .await?
+            .with_context(|| format!("Blob not found: {}", key))?;
+        self.select(content).put_blob_streaming(key, content.size, reader).await?;
+        self.default_backend().delete_blob(key).await?;
+        tracing::info!(%key, backend = %name, "Moved blob to storage backend");
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for StorageRouter {
+    async fn put_blob(&self, digest: &str, data: Bytes) -> Result<()> {
+        self.default_backend().put_blob(digest, data).await
+    }
+
+    async fn put_blob_streaming(
+        &self,
+        digest: &str,
+        content_length: u64,
+        data: Box<dyn AsyncRead + Send + Unpin>,
+    ) -> Result<()> {
+        self.default_backend().put_blob_streaming(digest, content_length, data).await
+    }
+
+    async fn get_blob(&self, digest: &str) -> Result<Option<Bytes>> {
+        for (_, backend) in &self.backends {
+            if let Some(data) = backend.get_blob(digest).await? {
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn get_blob_streaming(
+        &self,
+        digest: &str,
+    ) -> Result<Option<Box<dyn AsyncRead + Send + Unpin>>> {
+        for (_, backend) in &self.backends {
+            if let Some(reader) = backend.get_blob_streaming(digest).await? {
+                return Ok(Some(reader));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn delete_blob(&self, digest: &str) -> Result<bool> {
+        let mut deleted = false;
+        for (_, backend) in &self.backends {
+            deleted |= backend.delete_blob(digest).await?;
+        }
+        Ok(deleted)
+    }
+
+    async fn blob_exists(&self, digest: &str) -> Result<bool> {
+        for (_, backend) in &self.backends {
+            if backend.blob_exists(digest).await? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    async fn get_blob_metadata(&self, digest: &str) -> Result<Option<BlobMetadata>> {
+        for (_, backend) in &self.backends {
+            if let Some(metadata) = backend.get_blob_metadata(digest).await? {
+                return Ok(Some(metadata));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        for (name, backend) in &self.backends {
+            backend
+                .health_check()
+                .await
+                .with_context(|| format!("Storage backend {} is unhealthy", name))?;
+        }
+        Ok(())
+    }
+
+    // Uploads are staged in the default backend
+
+    async fn copy_blob(&self, from: &str, to: &str) -> Result<()> {
+        self.default_backend().copy_blob(from, to).await
+    }
+
+    async fn multipart_create(&self, key: &str) -> Result<String> {
+        self.default_backend().multipart_create(key).await
+    }
+
+    async fn multipart_upload_part(&self, key: &str, upload_id: &str, part_number: i32, data: Bytes) -> Result<String> {
+        self.default_backend().multipart_upload_part(key, upload_id, part_number, data).await
+    }
+
+    async fn multipart_complete(&self, key: &str, upload_id: &str, parts: &[(i32, String)]) -> Result<()> {
+        self.default_backend().multipart_complete(key, upload_id, parts).await
+    }
+
+    async fn multipart_abort(&self, key: &str, upload_id: &str, parts: &[(i32, String)]) -> Result<()> {
+        self.default_backend().multipart_abort(key, upload_id, parts).await
+    }
+}
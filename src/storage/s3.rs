@@ -12,6 +12,23 @@ use tokio::io::AsyncRead;
 use tokio_util::io::ReaderStream;
 use tracing::{error, warn};
 
+/// Largest object a single CopyObject can copy
+pub const MAX_SINGLE_COPY_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+/// Bounds S3 puts on multipart uploads
+const MIN_PART_BYTES: u64 = 5 * 1024 * 1024;
+const MAX_PARTS: u64 = 10_000;
+
+/// Inclusive byte ranges of the parts a multipart copy of `size` bytes is
+/// split into: `part_size` each, grown as needed to stay within S3's part
+/// count and size limits
+pub fn copy_part_ranges(size: u64, part_size: u64) -> Vec<(u64, u64)> {
+    let part_size = part_size.max(MIN_PART_BYTES).max(size.div_ceil(MAX_PARTS));
+    (0..size)
+        .step_by(part_size as usize)
+        .map(|first| (first, (first + part_size).min(size) - 1))
+        .collect()
+}
+
 pub struct S3Storage {
     client: S3Client,
     bucket: String,
@@ -117,6 +134,33 @@ impl S3Storage {
         })
     }
 
+    /// Copy `size` bytes of `from` into the multipart upload of `to`,
+    /// server-side, one UploadPartCopy per range
+    async fn copy_parts(&self, from: &str, to: &str, upload_id: &str, size: u64) -> Result<Vec<(i32, String)>> {
+        let mut parts = Vec::new();
+        for (index, (first, last)) in copy_part_ranges(size, self.part_size).into_iter().enumerate() {
+            let part_number = index as i32 + 1;
+            let part = self
+                .client
+                .upload_part_copy()
+                .bucket(&self.bucket)
+                .key(to)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .copy_source(format!("{}/{}", self.bucket, from))
+                .copy_source_range(format!("bytes={}-{}", first, last))
+                .send()
+                .await
+                .context("Failed to copy part")?;
+            let etag = part
+                .copy_part_result()
+                .and_then(|result| result.e_tag())
+                .context("Copied part has no ETag")?;
+            parts.push((part_number, etag.to_string()));
+        }
+        Ok(parts)
+    }
+
     async fn handle_error<T>(&self, result: Result<T>, context: &str) -> Result<T> {
         match result {
             Ok(value) => Ok(value),
@@ -387,6 +431,105 @@ impl Storage for S3Storage {
             .await?;
         Ok(())
     }
+
+    async fn copy_blob(&self, from: &str, to: &str) -> Result<()> {
+        let size = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(from)
+            .send()
+            .await
+            .context("Failed to read the size of the object to copy")?
+            .content_length
+            .unwrap_or(0)
+            .max(0) as u64;
+
+        // Server-side copy; S3 limits single copies to 5 GiB
+        if size <= MAX_SINGLE_COPY_BYTES {
+            self.client
+                .copy_object()
+                .bucket(&self.bucket)
+                .copy_source(format!("{}/{}", self.bucket, from))
+                .key(to)
+                .send()
+                .await
+                .context("Failed to copy object")?;
+            return Ok(());
+        }
+
+        let upload_id = self.multipart_create(to).await?;
+        match self.copy_parts(from, to, &upload_id, size).await {
+            Ok(parts) => self.multipart_complete(to, &upload_id, &parts).await,
+            Err(e) => {
+                self.abort_multipart_upload(to, &upload_id).await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn multipart_create(&self, key: &str) -> Result<String> {
+        let multipart = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .context("Failed to initiate multipart upload")?;
+
+        multipart
+            .upload_id()
+            .map(str::to_string)
+            .context("Multipart upload has no upload ID")
+    }
+
+    async fn multipart_upload_part(&self, key: &str, upload_id: &str, part_number: i32, data: Bytes) -> Result<String> {
+        let part = self
+            .client
+            .upload_part()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(data))
+            .send()
+            .await
+            .context("Failed to upload part")?;
+
+        part.e_tag.context("Uploaded part has no ETag")
+    }
+
+    async fn multipart_complete(&self, key: &str, upload_id: &str, parts: &[(i32, String)]) -> Result<()> {
+        let completed_parts = parts
+            .iter()
+            .map(|(part_number, etag)| {
+                aws_sdk_s3::types::CompletedPart::builder()
+                    .e_tag(etag)
+                    .part_number(*part_number)
+                    .build()
+            })
+            .collect();
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                aws_sdk_s3::types::CompletedMultipartUpload::builder()
+                    .set_parts(Some(completed_parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .context("Failed to complete multipart upload")?;
+        Ok(())
+    }
+
+    async fn multipart_abort(&self, key: &str, upload_id: &str, _parts: &[(i32, String)]) -> Result<()> {
+        self.abort_multipart_upload(key, upload_id).await
+    }
 }
 
 impl StorageConfig for S3Config {
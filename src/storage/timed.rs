@@ -0,0 +1,95 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::sync::Arc;
+use tokio::io::AsyncRead;
+
+use super::{BlobMetadata, Storage};
+use crate::server_timing::{TimingGuard, TimingMetric};
+
+/// Storage decorator that records time spent in the backend for Server-Timing.
+/// Streaming reads only account for opening the stream.
+pub struct TimedStorage {
+    inner: Arc<dyn Storage>,
+}
+
+impl TimedStorage {
+    pub fn new(inner: Arc<dyn Storage>) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl Storage for TimedStorage {
+    async fn put_blob(&self, digest: &str, data: Bytes) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.put_blob(digest, data).await
+    }
+
+    async fn put_blob_streaming(
+        &self,
+        digest: &str,
+        content_length: u64,
+        data: Box<dyn AsyncRead + Send + Unpin>,
+    ) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.put_blob_streaming(digest, content_length, data).await
+    }
+
+    async fn get_blob(&self, digest: &str) -> Result<Option<Bytes>> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.get_blob(digest).await
+    }
+
+    async fn get_blob_streaming(
+        &self,
+        digest: &str,
+    ) -> Result<Option<Box<dyn AsyncRead + Send + Unpin>>> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.get_blob_streaming(digest).await
+    }
+
+    async fn delete_blob(&self, digest: &str) -> Result<bool> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.delete_blob(digest).await
+    }
+
+    async fn blob_exists(&self, digest: &str) -> Result<bool> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.blob_exists(digest).await
+    }
+
+    async fn get_blob_metadata(&self, digest: &str) -> Result<Option<BlobMetadata>> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.get_blob_metadata(digest).await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.inner.health_check().await
+    }
+
+    async fn copy_blob(&self, from: &str, to: &str) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.copy_blob(from, to).await
+    }
+
+    async fn multipart_create(&self, key: &str) -> Result<String> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.multipart_create(key).await
+    }
+
+    async fn multipart_upload_part(&self, key: &str, upload_id: &str, part_number: i32, data: Bytes) -> Result<String> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.multipart_upload_part(key, upload_id, part_number, data).await
+    }
+
+    async fn multipart_complete(&self, key: &str, upload_id: &str, parts: &[(i32, String)]) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.multipart_complete(key, upload_id, parts).await
+    }
+
+    async fn multipart_abort(&self, key: &str, upload_id: &str, parts: &[(i32, String)]) -> Result<()> {
+        let _timing = TimingGuard::start(TimingMetric::Storage);
+        self.inner.multipart_abort(key, upload_id, parts).await
+    }
+}
@@ -0,0 +1,162 @@
+// Resumable chunked blob uploads
+//
+// All state of an upload lives in storage and in an `UploadSession` that the
+// caller persists (in `blob_uploads`) after every step, so any instance can
+// continue an upload, including after a restart. Bytes are buffered in a
+// pending object until a full part is available, then written as a
+// multipart part; uploads that never fill a part skip multipart entirely.
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use super::Storage;
+
+/// Smallest multipart part S3 accepts (except for the last one)
+pub const MIN_PART_BYTES: u64 = 5 * 1024 * 1024;
+
+/// A part already written to the multipart upload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadedPart {
+    pub number: i32,
+    pub etag: String,
+    pub size: u64,
+}
+
+/// Persisted state of an in-progress chunked upload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub uuid: String,
+    pub repository: String,
+    /// Multipart upload ID, once the first part has been written
+    pub multipart_upload_id: Option<String>,
+    pub parts: Vec<UploadedPart>,
+    /// Bytes received but not yet written as a part
+    pub pending_bytes: u64,
+}
+
+impl UploadSession {
+    pub fn new(uuid: &str, repository: &str) -> Self {
+        Self {
+            uuid: uuid.to_string(),
+            repository: repository.to_string(),
+            multipart_upload_id: None,
+            parts: Vec::new(),
+            pending_bytes: 0,
+        }
+    }
+
+    /// Storage key the multipart upload assembles into
+    pub fn multipart_key(&self) -> String {
+        format!("uploads/{}/{}/data", self.repository, self.uuid)
+    }
+
+    /// Storage key holding bytes not yet written as a part
+    pub fn pending_key(&self) -> String {
+        format!("uploads/{}/{}/pending", self.repository, self.uuid)
+    }
+
+    pub fn committed_bytes(&self) -> u64 {
+        self.parts.iter().map(|part| part.size).sum()
+    }
+
+    /// Total bytes received, i.e. where the next chunk must start
+    pub fn offset(&self) -> u64 {
+        self.committed_bytes() + self.pending_bytes
+    }
+
+    fn part_list(&self) -> Vec<(i32, String)> {
+        self.parts.iter().map(|part| (part.number, part.etag.clone())).collect()
+    }
+
+    /// Append a chunk. Once at least `part_size` bytes are pending they are
+    /// written as the next multipart part.
+    pub async fn append(&mut self, storage: &dyn Storage, chunk: Bytes, part_size: u64) -> Result<()> {
+        let pending = self.read_pending(storage).await?;
+        let mut data = pending.to_vec();
+        data.extend_from_slice(&chunk);
+
+        if (data.len() as u64) < part_size {
+            let len = data.len() as u64;
+            storage.put_blob(&self.pending_key(), Bytes::from(data)).await?;
+            self.pending_bytes = len;
+            return Ok(());
+        }
+
+        // The pending object is left in place: until the caller persists the
+        // updated session, a retry of this chunk still finds it intact
+        self.write_part(storage, Bytes::from(data)).await?;
+        self.pending_bytes = 0;
+        Ok(())
+    }
+
+    /// Finish the upload, storing the complete blob at `target_key`.
+    /// Returns the blob's size.
+    pub async fn finish(&mut self, storage: &dyn Storage, target_key: &str) -> Result<u64> {
+        let pending = self.read_pending(storage).await?;
+        let size = self.offset();
+
+        if self.multipart_upload_id.is_none() {
+            // Never reached a full part: a plain write is enough
+            storage.put_blob(target_key, pending).await?;
+        } else {
+            if !pending.is_empty() {
+                self.write_part(storage, pending).await?;
+            }
+            let upload_id = self.multipart_upload_id.clone().unwrap_or_default();
+            let key = self.multipart_key();
+            storage.multipart_complete(&key, &upload_id, &self.part_list()).await?;
+            storage.copy_blob(&key, target_key).await?;
+            storage.delete_blob(&key).await?;
+        }
+
+        storage.delete_blob(&self.pending_key()).await?;
+        self.pending_bytes = 0;
+        Ok(size)
+    }
+
+    /// Discard everything uploaded so far
+    pub async fn abort(&self, storage: &dyn Storage) -> Result<()> {
+        if let Some(upload_id) = &self.multipart_upload_id {
+            storage.multipart_abort(&self.multipart_key(), upload_id, &self.part_list()).await?;
+        }
+        storage.delete_blob(&self.pending_key()).await?;
+        Ok(())
+    }
+
+    async fn read_pending(&self, storage: &dyn Storage) -> Result<Bytes> {
+        if self.pending_bytes == 0 {
+            return Ok(Bytes::new());
+        }
+
+        let pending = storage
+            .get_blob(&self.pending_key())
+            .await?
+            .context("Pending upload data is missing")?;
+        if pending.len() as u64 != self.pending_bytes {
+            anyhow::bail!(
+                "Pending upload data has {} bytes, expected {}",
+                pending.len(),
+                self.pending_bytes
+            );
+        }
+        Ok(pending)
+    }
+
+    async fn write_part(&mut self, storage: &dyn Storage, data: Bytes) -> Result<()> {
+        let key = self.multipart_key();
+        let upload_id = match &self.multipart_upload_id {
+            Some(upload_id) => upload_id.clone(),
+            None => {
+                let upload_id = storage.multipart_create(&key).await?;
+                self.multipart_upload_id = Some(upload_id.clone());
+                upload_id
+            }
+        };
+
+        let number = self.parts.len() as i32 + 1;
+        let size = data.len() as u64;
+        let etag = storage.multipart_upload_part(&key, &upload_id, number, data).await?;
+        self.parts.push(UploadedPart { number, etag, size });
+        Ok(())
+    }
+}
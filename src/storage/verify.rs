@@ -0,0 +1,95 @@
+use sha2::{Digest as _, Sha256, Sha512};
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::models::digest::Digest;
+
+/// Incremental hash in the algorithm of a digest
+enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    fn for_digest(digest: &Digest) -> Self {
+        match digest.algorithm() {
+            "sha512" => Hasher::Sha512(Sha512::new()),
+            _ => Hasher::Sha256(Sha256::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Sha512(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Hex of the hash of everything passed to `update`
+    fn finish(self) -> String {
+        match self {
+            Hasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Sha512(hasher) => format!("{:x}", hasher.finalize()),
+        }
+    }
+}
+
+/// Whether `data` hashes to `digest`
+pub fn content_matches(digest: &Digest, data: &[u8]) -> bool {
+    let mut hasher = Hasher::for_digest(digest);
+    hasher.update(data);
+    hasher.finish() == digest.hex()
+}
+
+/// Reader that hashes a blob as it is read from storage. The read that
+/// reaches the end fails with `InvalidData` when the content does not match
+/// `digest`, so a streamed response is aborted instead of completing with
+/// corrupt bytes.
+pub struct VerifyingReader<R> {
+    inner: R,
+    digest: Digest,
+    /// `None` once the end was reached and the hash compared
+    hasher: Option<Hasher>,
+}
+
+impl<R> VerifyingReader<R> {
+    pub fn new(inner: R, digest: Digest) -> Self {
+        let hasher = Some(Hasher::for_digest(&digest));
+        Self { inner, digest, hasher }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for VerifyingReader<R> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let start = buf.filled().len();
+        let had_room = buf.remaining() > 0;
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+
+        let read = &buf.filled()[start..];
+        if !read.is_empty() {
+            if let Some(hasher) = this.hasher.as_mut() {
+                hasher.update(read);
+            }
+        } else if had_room {
+            // Nothing read into a buffer with room means the end of the blob
+            if let Some(hasher) = this.hasher.take() {
+                let actual = hasher.finish();
+                if actual != this.digest.hex() {
+                    tracing::error!(
+                        digest = %this.digest,
+                        actual = %format!("{}:{}", this.digest.algorithm(), actual),
+                        "Stored blob does not match its digest; aborting the pull"
+                    );
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("blob {} failed digest verification", this.digest),
+                    )));
+                }
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
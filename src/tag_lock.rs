@@ -0,0 +1,162 @@
+// Locks around tag updates
+//
+// Pushes to the same tag from different instances race when both move the
+// tag pointer. Each update runs under a short-lived lock keyed by repository
+// and tag: `SET key token NX PX ttl` in Redis, released only by the holder
+// of the random token, and expiring on its own if the holder dies. Without
+// Redis the lock is held in process, which covers a single instance. When
+// the lock backend fails the update runs unlocked, so a Redis outage does
+// not stop pushes.
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+/// How long a tag lock lasts unless released first
+pub const TAG_LOCK_TTL: Duration = Duration::from_secs(10);
+
+/// How long an update waits for the lock; longer than the TTL, so a lock
+/// left behind by a crashed instance is taken over rather than timing out
+pub const TAG_LOCK_WAIT: Duration = Duration::from_secs(15);
+
+/// Pause between attempts to take a held lock
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Deletes the lock only if it still holds the caller's token
+pub const RELEASE_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+else
+    return 0
+end
+"#;
+
+/// Where locks are kept
+#[async_trait]
+pub trait LockBackend: Send + Sync {
+    /// Take `key` for `ttl` with `token`, unless someone else holds it
+    async fn try_acquire(&self, key: &str, token: &str, ttl: Duration) -> anyhow::Result<bool>;
+
+    /// Release `key` if `token` still holds it; false when the lock had
+    /// already expired
+    async fn release(&self, key: &str, token: &str) -> anyhow::Result<bool>;
+}
+
+/// Locks held in this process, with the same expiry as the Redis ones
+#[derive(Default)]
+pub struct LocalLocks {
+    held: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+#[async_trait]
+impl LockBackend for LocalLocks {
+    async fn try_acquire(&self, key: &str, token: &str, ttl: Duration) -> anyhow::Result<bool> {
+        let now = Instant::now();
+        let mut held = self.held.lock().unwrap();
+        held.retain(|_, (_, expires)| *expires > now);
+        if held.contains_key(key) {
+            return Ok(false);
+        }
+        held.insert(key.to_string(), (token.to_string(), now + ttl));
+        Ok(true)
+    }
+
+    async fn release(&self, key: &str, token: &str) -> anyhow::Result<bool> {
+        let mut held = self.held.lock().unwrap();
+        match held.get(key) {
+            Some((holder, expires)) if holder == token && *expires > Instant::now() => {
+                held.remove(key);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TagLockError {
+    #[error("timed out waiting for the lock on {0}")]
+    Timeout(String),
+}
+
+/// Lock key for `tag` of `repository`
+pub fn tag_lock_key(repository: &str, tag: &str) -> String {
+    format!("lock:tag:{}:{}", repository, tag)
+}
+
+/// Run `update` while holding `key`, waiting up to `wait` for it. If the
+/// backend cannot be reached, `update` runs without the lock.
+pub async fn with_lock<F: Future>(
+    backend: &dyn LockBackend,
+    key: &str,
+    ttl: Duration,
+    wait: Duration,
+    update: F,
+) -> Result<F::Output, TagLockError> {
+    let token = uuid::Uuid::new_v4().to_string();
+    let deadline = Instant::now() + wait;
+    loop {
+        match backend.try_acquire(key, &token, ttl).await {
+            Ok(true) => break,
+            Ok(false) if Instant::now() >= deadline => return Err(TagLockError::Timeout(key.to_string())),
+            Ok(false) => tokio::time::sleep(RETRY_INTERVAL).await,
+            Err(e) => {
+                tracing::warn!(key, "Lock backend failed, updating without the lock: {}", e);
+                return Ok(update.await);
+            }
+        }
+    }
+
+    let output = update.await;
+    match backend.release(key, &token).await {
+        Ok(true) => {}
+        Ok(false) => tracing::warn!(
+            key,
+            "Lock expired before the update finished; another update may have run at the same time"
+        ),
+        // Left to expire on its own
+        Err(e) => tracing::warn!(key, "Failed to release lock: {}", e),
+    }
+    Ok(output)
+}
+
+/// `with_lock` over every key of `keys`, taken in sorted order so that two
+/// updates sharing keys cannot each hold one the other waits for
+pub async fn with_locks<F>(
+    backend: &dyn LockBackend,
+    keys: &[String],
+    ttl: Duration,
+    wait: Duration,
+    update: F,
+) -> Result<F::Output, TagLockError>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    let mut keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    nest_locks(backend, &keys, ttl, wait, update).await
+}
+
+fn nest_locks<'a, F>(
+    backend: &'a dyn LockBackend,
+    keys: &'a [&'a str],
+    ttl: Duration,
+    wait: Duration,
+    update: F,
+) -> Pin<Box<dyn Future<Output = Result<F::Output, TagLockError>> + Send + 'a>>
+where
+    F: Future + Send + 'a,
+    F::Output: Send,
+{
+    Box::pin(async move {
+        match keys.split_first() {
+            None => Ok(update.await),
+            Some((key, rest)) => with_lock(backend, key, ttl, wait, nest_locks(backend, rest, ttl, wait, update)).await?,
+        }
+    })
+}
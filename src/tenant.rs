@@ -0,0 +1,415 @@
+// Schema-per-tenant isolation (TENANCY_MODE=schema)
+//
+// In schema mode every organization gets its own Postgres schema holding its
+// copy of the tenant tables: `repositories` and every table that references
+// it, directly or through another tenant table. The list is read from the
+// foreign keys of the migrated `public` schema, so tables added by later
+// migrations are picked up without touching this module.
+//
+// Work for an organization runs inside `TenantContext::scope`. In schema mode
+// the pool points each connection it hands out at the scope's schema first,
+// so unqualified table names resolve to the tenant's tables while shared
+// tables (users, organizations) still resolve to `public`. `tenant_middleware`
+// opens the scope for requests whose path names an organization; work that
+// spans organizations iterates over `tenants`.
+use std::future::Future;
+
+use anyhow::Result;
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgConnection, PgPool};
+
+use crate::AppState;
+
+/// Organization whose repositories have no organization in their name
+pub const DEFAULT_ORGANIZATION_ID: i64 = 1;
+
+/// How organizations' data is separated in the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TenancyMode {
+    /// All organizations share the tables in `public` (default)
+    Shared,
+    /// Each organization has its own schema
+    Schema,
+}
+
+impl std::str::FromStr for TenancyMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "shared" => Ok(TenancyMode::Shared),
+            "schema" => Ok(TenancyMode::Schema),
+            _ => Err(format!("Invalid tenancy mode: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for TenancyMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TenancyMode::Shared => write!(f, "shared"),
+            TenancyMode::Schema => write!(f, "schema"),
+        }
+    }
+}
+
+/// Database scope of the organization a request acts on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantContext {
+    /// Schema holding the tenant's tables; `None` in shared mode
+    schema: Option<String>,
+}
+
+tokio::task_local! {
+    static CURRENT: TenantContext;
+}
+
+impl TenantContext {
+    /// Context for an organization. Schemas are keyed by ID so renaming an
+    /// organization does not move its data.
+    pub fn for_organization(mode: TenancyMode, organization_id: i64) -> Self {
+        match mode {
+            TenancyMode::Shared => Self { schema: None },
+            TenancyMode::Schema => Self { schema: Some(Self::schema_name(organization_id)) },
+        }
+    }
+
+    pub fn schema_name(organization_id: i64) -> String {
+        format!("tenant_{}", organization_id)
+    }
+
+    pub fn schema(&self) -> Option<&str> {
+        self.schema.as_deref()
+    }
+
+    /// `search_path` for the tenant: its schema first, then `public`
+    pub fn search_path(&self) -> Option<String> {
+        self.schema.as_ref().map(|schema| format!("\"{}\", public", schema))
+    }
+
+    /// Run `fut` as work for this tenant: connections it takes from a pool
+    /// built with `scoped_pool_options` use the tenant's tables
+    pub async fn scope<F: Future>(self, fut: F) -> F::Output {
+        CURRENT.scope(self, fut).await
+    }
+
+    /// Create the tenant's schema and tables, modelled on those in `public`,
+    /// with foreign keys pointing at the tenant's own tables. Idempotent, so
+    /// it also adds tables created by migrations since the tenant was
+    /// provisioned; a no-op in shared mode. Run it in the transaction that
+    /// creates the organization so both succeed or fail together.
+    pub async fn provision(&self, conn: &mut PgConnection) -> Result<()> {
+        let (schema, search_path) = match (&self.schema, self.search_path()) {
+            (Some(schema), Some(search_path)) => (schema, search_path),
+            _ => return Ok(()),
+        };
+
+        sqlx::query(&format!("CREATE SCHEMA IF NOT EXISTS \"{}\"", schema))
+            .execute(&mut *conn)
+            .await?;
+        let tables = tenant_tables(&mut *conn).await?;
+        for table in &tables {
+            sqlx::query(&format!(
+                "CREATE TABLE IF NOT EXISTS \"{}\".\"{}\" (LIKE public.\"{}\" INCLUDING ALL)",
+                schema, table, table
+            ))
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        // LIKE copies no foreign keys. Their definitions name the referenced
+        // table unqualified, so recreating them under the tenant's search
+        // path points them at its own tables, or `public` for shared ones.
+        let foreign_keys = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT cl.relname::text, c.conname::text, pg_get_constraintdef(c.oid)
+             FROM pg_constraint c
+             JOIN pg_class cl ON cl.oid = c.conrelid
+             WHERE c.contype = 'f'
+               AND cl.relnamespace = 'public'::regnamespace
+               AND cl.relname = ANY($1)
+               AND NOT EXISTS (
+                   SELECT 1 FROM pg_constraint existing
+                   JOIN pg_class tcl ON tcl.oid = existing.conrelid
+                   WHERE existing.conname = c.conname
+                     AND tcl.relname = cl.relname
+                     AND tcl.relnamespace = $2::regnamespace
+               )",
+        )
+        .bind(&tables)
+        .bind(schema)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        if !foreign_keys.is_empty() {
+            let saved = sqlx::query_scalar::<_, String>("SELECT current_setting('search_path')")
+                .fetch_one(&mut *conn)
+                .await?;
+            sqlx::query("SELECT set_config('search_path', $1, true)")
+                .bind(&search_path)
+                .execute(&mut *conn)
+                .await?;
+            for (table, name, definition) in &foreign_keys {
+                sqlx::query(&format!(
+                    "ALTER TABLE \"{}\".\"{}\" ADD CONSTRAINT \"{}\" {}",
+                    schema, table, name, definition
+                ))
+                .execute(&mut *conn)
+                .await?;
+            }
+            sqlx::query("SELECT set_config('search_path', $1, true)")
+                .bind(saved)
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        tracing::info!(%schema, tables = tables.len(), "Provisioned tenant schema");
+        Ok(())
+    }
+
+    /// Remove the tenant's schema and everything in it
+    pub async fn drop_schema(&self, pool: &PgPool) -> Result<()> {
+        if let Some(schema) = &self.schema {
+            sqlx::query(&format!("DROP SCHEMA IF EXISTS \"{}\" CASCADE", schema))
+                .execute(pool)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Tenant of the work being done, if it runs in a tenant scope
+pub fn current() -> Option<TenantContext> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+/// `tokio::spawn` that keeps the caller's tenant scope, for background work
+/// a request queues on its tenant's tables
+pub fn spawn<F>(fut: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match current() {
+        Some(tenant) => tokio::spawn(tenant.scope(fut)),
+        None => tokio::spawn(fut),
+    }
+}
+
+/// Tables each tenant schema gets its own copy of: `repositories` and the
+/// tables in `public` that reference it, directly or transitively
+pub async fn tenant_tables(conn: &mut PgConnection) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>(
+        "WITH RECURSIVE tenant_tables(oid) AS (
+             SELECT 'public.repositories'::regclass::oid
+             UNION
+             SELECT c.conrelid
+             FROM pg_constraint c
+             JOIN tenant_tables t ON c.confrelid = t.oid
+             JOIN pg_class cl ON cl.oid = c.conrelid
+             WHERE c.contype = 'f' AND cl.relnamespace = 'public'::regnamespace
+         )
+         SELECT cl.relname::text
+         FROM tenant_tables t
+         JOIN pg_class cl ON cl.oid = t.oid
+         ORDER BY cl.relname",
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// Every tenant: one per organization in schema mode, or the shared tables
+pub async fn tenants(pool: &PgPool, mode: TenancyMode) -> Result<Vec<TenantContext>, sqlx::Error> {
+    match mode {
+        TenancyMode::Shared => Ok(vec![TenantContext::for_organization(mode, DEFAULT_ORGANIZATION_ID)]),
+        TenancyMode::Schema => {
+            let ids = sqlx::query_scalar::<_, i64>("SELECT id FROM organizations ORDER BY id")
+                .fetch_all(pool)
+                .await?;
+            Ok(ids.into_iter().map(|id| TenantContext::for_organization(mode, id)).collect())
+        }
+    }
+}
+
+/// Run `work` in the scope of each tenant in turn and gather the results,
+/// for listings and jobs that span organizations
+pub async fn across_tenants<T, E, F, Fut>(pool: &PgPool, mode: TenancyMode, mut work: F) -> Result<Vec<T>, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Vec<T>, E>>,
+    E: From<sqlx::Error>,
+{
+    let mut all = Vec::new();
+    for tenant in tenants(pool, mode).await? {
+        all.extend(tenant.scope(work()).await?);
+    }
+    Ok(all)
+}
+
+/// Provision every organization's schema, adding tables created by
+/// migrations since. Run at startup in schema mode.
+pub async fn sync_tenant_schemas(pool: &PgPool, mode: TenancyMode) -> Result<()> {
+    if mode == TenancyMode::Shared {
+        return Ok(());
+    }
+    for tenant in tenants(pool, mode).await? {
+        let mut tx = pool.begin().await?;
+        tenant.provision(&mut tx).await?;
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+/// Pool options that point each connection handed out at the schema of
+/// the current tenant scope, or reset it outside one. Unchanged in shared
+/// mode, where everything lives in `public`.
+pub fn scoped_pool_options(options: PgPoolOptions, mode: TenancyMode) -> PgPoolOptions {
+    if mode == TenancyMode::Shared {
+        return options;
+    }
+    // Both hooks run in the task acquiring the connection, so they see its scope
+    options
+        .after_connect(|conn, _meta| Box::pin(async move { apply_search_path(conn).await }))
+        .before_acquire(|conn, _meta| Box::pin(async move { apply_search_path(conn).await.map(|()| true) }))
+}
+
+async fn apply_search_path(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    let statement = match current().and_then(|tenant| tenant.search_path()) {
+        Some(search_path) => format!("SET search_path TO {}", search_path),
+        None => "RESET search_path".to_string(),
+    };
+    sqlx::query(&statement).execute(conn).await.map(|_| ())
+}
+
+/// Which organization a request path acts on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestTenant {
+    /// The organization named in the path
+    Organization(String),
+    /// A repository name without an organization, which belongs to the
+    /// default organization
+    DefaultOrganization,
+    /// A repository addressed by ID
+    Repository(i64),
+}
+
+/// Registry path segments that follow the repository name
+const REGISTRY_RESOURCES: &[&str] = &["manifests", "blobs", "tags"];
+
+/// Reserved registry path segments that the repository name follows
+const REPOSITORY_ROUTES: &[&str] = &["_batch", "_export"];
+
+/// Organization a request to `path` works on, if it works on a single one.
+/// Catalog-style routes spanning organizations return `None` and iterate
+/// over `tenants` themselves.
+pub fn request_tenant(path: &str) -> Option<RequestTenant> {
+    if let Some(rest) = path.strip_prefix("/v2/") {
+        if let Some((route, named)) = rest.split_once('/') {
+            if REPOSITORY_ROUTES.contains(&route) {
+                return request_tenant(&format!("/v2/{}", named));
+            }
+        }
+        let segments: Vec<&str> = rest.split('/').collect();
+        let first = segments[0];
+        if first.is_empty() || first.starts_with('_') {
+            return None;
+        }
+        if first == "id" {
+            return segments.get(1).and_then(|id| id.parse().ok()).map(RequestTenant::Repository);
+        }
+        return match segments.len() {
+            1 => Some(RequestTenant::DefaultOrganization),
+            _ if REGISTRY_RESOURCES.contains(&segments[1]) => Some(RequestTenant::DefaultOrganization),
+            _ => Some(RequestTenant::Organization(first.to_string())),
+        };
+    }
+
+    if let Some(rest) = path.strip_prefix("/api/v1/repos/") {
+        let mut segments = rest.split('/');
+        return match segments.next() {
+            Some("repositories") => match segments.next() {
+                None | Some("") | Some("public") => None,
+                Some(namespace) => Some(RequestTenant::Organization(namespace.to_string())),
+            },
+            Some("") | None => None,
+            Some(namespace) => Some(RequestTenant::Organization(namespace.to_string())),
+        };
+    }
+
+    if let Some(rest) = path.strip_prefix("/api/v1/organizations/") {
+        if let Some(name) = rest.strip_suffix("/analytics") {
+            if !name.is_empty() && !name.contains('/') {
+                return Some(RequestTenant::Organization(name.to_string()));
+            }
+        }
+    }
+
+    None
+}
+
+/// The tenant `target` refers to, if it exists
+pub async fn resolve(pool: &PgPool, mode: TenancyMode, target: &RequestTenant) -> Result<Option<TenantContext>, sqlx::Error> {
+    match target {
+        RequestTenant::DefaultOrganization => Ok(Some(TenantContext::for_organization(mode, DEFAULT_ORGANIZATION_ID))),
+        RequestTenant::Organization(name) => organization_tenant(pool, mode, name).await,
+        RequestTenant::Repository(repository_id) => {
+            // Repository IDs come from one sequence, so at most one tenant has it
+            for tenant in tenants(pool, mode).await? {
+                let found = tenant
+                    .clone()
+                    .scope(async {
+                        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM repositories WHERE id = $1)")
+                            .bind(repository_id)
+                            .fetch_one(pool)
+                            .await
+                    })
+                    .await?;
+                if found {
+                    return Ok(Some(tenant));
+                }
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Tenant of the organization called `name`, if there is one
+pub async fn organization_tenant(pool: &PgPool, mode: TenancyMode, name: &str) -> Result<Option<TenantContext>, sqlx::Error> {
+    let id = sqlx::query_scalar::<_, i64>("SELECT id FROM organizations WHERE name = $1")
+        .bind(name)
+        .fetch_optional(pool)
+        .await?;
+    Ok(id.map(|id| TenantContext::for_organization(mode, id)))
+}
+
+/// Run the request in the scope of the organization its path names. Paths
+/// naming no known organization run unscoped, where tenant tables are empty,
+/// so handlers answer them as they would an unknown repository.
+pub async fn tenant_middleware(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let mode = state.config.database.tenancy_mode;
+    if mode == TenancyMode::Shared {
+        return next.run(request).await;
+    }
+
+    let tenant = match request_tenant(request.uri().path()) {
+        Some(target) => match resolve(&state.db_pool, mode, &target).await {
+            Ok(tenant) => tenant,
+            Err(e) => {
+                tracing::error!("Failed to resolve the tenant of {}: {}", request.uri().path(), e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+        None => None,
+    };
+
+    match tenant {
+        Some(tenant) => tenant.scope(next.run(request)).await,
+        None => next.run(request).await,
+    }
+}
@@ -0,0 +1,73 @@
+// Validation of uploaded avatar images
+//
+// Avatars are stored and served as uploaded, so the declared Content-Type has
+// to match what the bytes are. Only common web image formats are accepted.
+
+/// Largest avatar accepted, in bytes
+pub const MAX_AVATAR_BYTES: usize = 1024 * 1024;
+
+/// Image types an avatar may have
+pub const AVATAR_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AvatarError {
+    #[error("Avatar image is empty")]
+    Empty,
+    #[error("Avatar image is {size} bytes; the limit is {max}")]
+    TooLarge { size: usize, max: usize },
+    #[error("Avatar must be a PNG, JPEG, GIF or WebP image, not {0}")]
+    UnsupportedType(String),
+    #[error("Avatar content is not a {0} image")]
+    ContentMismatch(&'static str),
+}
+
+/// Image type of `data` from its leading bytes, if it is one of `AVATAR_TYPES`
+pub fn sniff_image_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Check an upload against the size limit and that its `Content-Type` is an
+/// accepted image type matching its content; returns that type
+pub fn validate_avatar(content_type: Option<&str>, data: &[u8]) -> Result<&'static str, AvatarError> {
+    if data.is_empty() {
+        return Err(AvatarError::Empty);
+    }
+    if data.len() > MAX_AVATAR_BYTES {
+        return Err(AvatarError::TooLarge { size: data.len(), max: MAX_AVATAR_BYTES });
+    }
+
+    let declared = content_type
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    let declared = match AVATAR_TYPES.iter().find(|known| **known == declared) {
+        Some(known) => *known,
+        None if declared.is_empty() => return Err(AvatarError::UnsupportedType("no Content-Type".to_string())),
+        None => return Err(AvatarError::UnsupportedType(declared)),
+    };
+
+    match sniff_image_type(data) {
+        Some(actual) if actual == declared => Ok(declared),
+        _ => Err(AvatarError::ContentMismatch(declared)),
+    }
+}
+
+/// Storage key of a user's avatar
+pub fn user_avatar_key(user_id: i64) -> String {
+    format!("avatars/users/{}", user_id)
+}
+
+/// Path the avatar of a user is served from
+pub fn user_avatar_url(user_id: i64) -> String {
+    format!("/api/v1/auth/users/{}/avatar", user_id)
+}
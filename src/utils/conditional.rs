@@ -0,0 +1,151 @@
+// Evaluation of conditional request headers (RFC 9110, section 13)
+//
+// Handlers describe the current state of the resource (its entity tag and
+// last modification time) and get back whether to proceed, answer
+// 304 Not Modified or answer 412 Precondition Failed.
+use axum::{
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+
+/// Outcome of evaluating a request's preconditions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precondition {
+    /// No precondition prevents the request
+    Proceed,
+    /// The client's cached copy is current (GET/HEAD only)
+    NotModified,
+    /// A precondition failed; the request must not be applied
+    Failed,
+}
+
+/// Current state of the resource a request targets
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResourceState<'a> {
+    /// Entity tag, quoted as sent in `ETag` (e.g. `"sha256:abc"` or `W/"v2"`); `None` if the resource does not exist
+    pub etag: Option<&'a str>,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Evaluate `If-Match`, `If-Unmodified-Since`, `If-None-Match` and
+/// `If-Modified-Since` in the order RFC 9110 prescribes.
+pub fn evaluate(headers: &HeaderMap, method: &Method, resource: &ResourceState) -> Precondition {
+    let is_read = method == Method::GET || method == Method::HEAD;
+
+    // 1. If-Match, else 2. If-Unmodified-Since
+    if let Some(if_match) = header_str(headers, header::IF_MATCH) {
+        if !matches_any(if_match, resource.etag, true) {
+            return Precondition::Failed;
+        }
+    } else if let Some(since) = header_date(headers, header::IF_UNMODIFIED_SINCE) {
+        if let Some(modified) = resource.last_modified {
+            if modified.timestamp() > since.timestamp() {
+                return Precondition::Failed;
+            }
+        }
+    }
+
+    // 3. If-None-Match, else 4. If-Modified-Since (reads only)
+    if let Some(if_none_match) = header_str(headers, header::IF_NONE_MATCH) {
+        if matches_any(if_none_match, resource.etag, false) {
+            return if is_read { Precondition::NotModified } else { Precondition::Failed };
+        }
+    } else if is_read {
+        if let (Some(since), Some(modified)) = (header_date(headers, header::IF_MODIFIED_SINCE), resource.last_modified) {
+            if modified.timestamp() <= since.timestamp() {
+                return Precondition::NotModified;
+            }
+        }
+    }
+
+    Precondition::Proceed
+}
+
+/// Evaluate the preconditions and build the 304/412 response when the
+/// request must not proceed. `None` means the handler should carry on.
+pub fn check(headers: &HeaderMap, method: &Method, resource: &ResourceState) -> Option<Response> {
+    match evaluate(headers, method, resource) {
+        Precondition::Proceed => None,
+        Precondition::NotModified => Some(not_modified(resource)),
+        Precondition::Failed => Some(precondition_failed()),
+    }
+}
+
+/// 304 carrying the validators the client should store
+pub fn not_modified(resource: &ResourceState) -> Response {
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    if let Some(value) = resource.etag.and_then(|etag| HeaderValue::from_str(etag).ok()) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    if let Some(modified) = resource.last_modified {
+        if let Ok(value) = HeaderValue::from_str(&http_date(modified)) {
+            response.headers_mut().insert(header::LAST_MODIFIED, value);
+        }
+    }
+    response
+}
+
+/// 412 with the management API's error body
+pub fn precondition_failed() -> Response {
+    (
+        StatusCode::PRECONDITION_FAILED,
+        Json(serde_json::json!({
+            "error": "Precondition failed",
+            "code": "PRECONDITION_FAILED"
+        })),
+    )
+        .into_response()
+}
+
+/// Quote a value for use as a strong entity tag
+pub fn strong_etag(value: &str) -> String {
+    format!("\"{}\"", value)
+}
+
+/// Format a timestamp as an HTTP date (IMF-fixdate)
+pub fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim)
+}
+
+/// Invalid dates are ignored, as RFC 9110 requires
+fn header_date(headers: &HeaderMap, name: header::HeaderName) -> Option<DateTime<Utc>> {
+    header_str(headers, name)
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Whether an `If-Match`/`If-None-Match` list matches the current entity tag.
+/// `If-Match` uses strong comparison (weak tags never match), `If-None-Match` weak.
+fn matches_any(list: &str, current: Option<&str>, strong: bool) -> bool {
+    let current = match current {
+        Some(current) => current,
+        // Nothing matches a resource that does not exist, not even `*`
+        None => return false,
+    };
+
+    if list == "*" {
+        return true;
+    }
+
+    let (current_weak, current_opaque) = split_weak(current);
+    list.split(',').map(str::trim).any(|candidate| {
+        let (weak, opaque) = split_weak(candidate);
+        if strong && (weak || current_weak) {
+            return false;
+        }
+        opaque == current_opaque
+    })
+}
+
+fn split_weak(tag: &str) -> (bool, &str) {
+    match tag.strip_prefix("W/") {
+        Some(opaque) => (true, opaque),
+        None => (false, tag),
+    }
+}
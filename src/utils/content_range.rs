@@ -0,0 +1,79 @@
+// Parsing of the Content-Range sent with blob upload chunks
+//
+// Docker clients send `<start>-<end>`, inclusive byte offsets; the HTTP form
+// `bytes <start>-<end>/<total>` (or `bytes=`) is accepted too. Anything else
+// is rejected with a reason the client can act on.
+
+/// Inclusive byte range of a chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ContentRange {
+    /// Number of bytes the range covers
+    pub fn byte_count(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ContentRangeError {
+    #[error("Content-Range is empty")]
+    Empty,
+    #[error("Content-Range must have the form <start>-<end>")]
+    Malformed,
+    #[error("Content-Range is missing its {0} offset")]
+    MissingBound(&'static str),
+    #[error("Content-Range offset '{0}' is not a number")]
+    NotNumeric(String),
+    #[error("Content-Range ends at {end}, before its start at {start}")]
+    Reversed { start: u64, end: u64 },
+    #[error("Content-Range covers more bytes than can be stored")]
+    TooLarge,
+}
+
+/// Parse a Content-Range header value
+pub fn parse_content_range(value: &str) -> Result<ContentRange, ContentRangeError> {
+    let value = value.trim();
+    let range = match value.strip_prefix("bytes") {
+        Some(rest) => rest.trim_start_matches([' ', '=']),
+        None => value,
+    };
+    // The total in `/<total>` is not needed to place the chunk
+    let range = match range.split_once('/') {
+        Some((range, total)) if total == "*" || is_digits(total) => range,
+        Some(_) => return Err(ContentRangeError::Malformed),
+        None => range,
+    };
+    if range.is_empty() {
+        return Err(ContentRangeError::Empty);
+    }
+
+    let (start, end) = range.split_once('-').ok_or(ContentRangeError::Malformed)?;
+    let start = parse_offset(start.trim(), "start")?;
+    let end = parse_offset(end.trim(), "end")?;
+    if end < start {
+        return Err(ContentRangeError::Reversed { start, end });
+    }
+    if end - start == u64::MAX {
+        return Err(ContentRangeError::TooLarge);
+    }
+
+    Ok(ContentRange { start, end })
+}
+
+fn parse_offset(offset: &str, bound: &'static str) -> Result<u64, ContentRangeError> {
+    if offset.is_empty() {
+        return Err(ContentRangeError::MissingBound(bound));
+    }
+    if !is_digits(offset) {
+        return Err(ContentRangeError::NotNumeric(offset.to_string()));
+    }
+    offset.parse().map_err(|_| ContentRangeError::TooLarge)
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
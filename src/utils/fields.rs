@@ -0,0 +1,48 @@
+// Field selection for API responses
+//
+// Clients that need only part of a resource, such as mobile apps, ask for
+// `?fields=id,name` and get an object with just those top-level fields.
+// Without the parameter the whole resource is returned.
+use serde::{Deserialize, Serialize};
+
+/// `fields` query parameter, a comma separated list of field names
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FieldsQuery {
+    pub fields: Option<String>,
+}
+
+/// Fields asked for that the resource does not have
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Unknown fields: {}", .0.join(", "))]
+pub struct UnknownFields(pub Vec<String>);
+
+/// `value` serialized with only the fields `query` selects. Unknown fields
+/// are left out, or rejected when `strict`. Values that do not serialize to
+/// an object are returned whole.
+pub fn project<T: Serialize>(
+    value: &T,
+    query: &FieldsQuery,
+    strict: bool,
+) -> Result<serde_json::Value, UnknownFields> {
+    let value = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+    let requested: Vec<&str> = match query.fields.as_deref() {
+        Some(fields) => fields.split(',').map(str::trim).filter(|f| !f.is_empty()).collect(),
+        None => return Ok(value),
+    };
+    let mut object = match value {
+        serde_json::Value::Object(object) if !requested.is_empty() => object,
+        value => return Ok(value),
+    };
+
+    let unknown: Vec<String> = requested
+        .iter()
+        .filter(|field| !object.contains_key(**field))
+        .map(|field| field.to_string())
+        .collect();
+    if strict && !unknown.is_empty() {
+        return Err(UnknownFields(unknown));
+    }
+
+    object.retain(|key, _| requested.contains(&key.as_str()));
+    Ok(serde_json::Value::Object(object))
+}
@@ -0,0 +1,8 @@
+// Utils module
+pub mod avatar;
+pub mod conditional;
+pub mod content_range;
+pub mod pagination;
+pub mod tar;
+pub mod timestamp;
+pub mod fields;
@@ -0,0 +1,92 @@
+// OCI-style pagination for list endpoints
+//
+// Clients ask for at most `n` items following the item named `last`; items
+// are ordered by name. When more remain, the response carries a
+// `Link: <url?n=..&last=..>; rel="next"` header for the following page.
+use axum::http::{header, HeaderMap, HeaderValue};
+use serde::Deserialize;
+
+/// `n` and `last` query parameters
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageQuery {
+    /// Largest number of items to return
+    pub n: Option<u32>,
+    /// Return only items ordered after this one
+    pub last: Option<String>,
+}
+
+impl PageQuery {
+    pub fn is_paginated(&self) -> bool {
+        self.n.is_some() || self.last.is_some()
+    }
+
+    /// `LIMIT` for loading a page: one row past `n`, which tells whether
+    /// another page follows. `None` binds as `LIMIT NULL`, every row.
+    pub fn fetch_limit(&self) -> Option<i64> {
+        self.n.map(|n| i64::from(n) + 1)
+    }
+}
+
+/// One page of a list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Key of the last item returned, when more items follow
+    pub next_last: Option<String>,
+    pub n: Option<u32>,
+}
+
+impl<T> Page<T> {
+    /// `Link` header for the next page of `base`, which may already carry a query
+    pub fn link(&self, base: &str) -> Option<HeaderValue> {
+        let (n, last) = (self.n?, self.next_last.as_deref()?);
+        HeaderValue::from_str(&next_link(base, n, last)).ok()
+    }
+
+    /// Add the `Link` header, if any, to `headers`
+    pub fn add_link(&self, base: &str, headers: &mut HeaderMap) {
+        if let Some(link) = self.link(base) {
+            headers.insert(header::LINK, link);
+        }
+    }
+}
+
+/// Select the page `query` asks for from `items`, ordered by `key`, which
+/// must be unique. Without `n` or `last` every item is returned in its
+/// original order.
+///
+/// Lists read from the database should load only the page: rows whose key
+/// sorts after `last` under `COLLATE "C"` (the byte order used here),
+/// ordered by key and limited to `fetch_limit()`.
+pub fn paginate<T, F>(items: Vec<T>, key: F, query: &PageQuery) -> Page<T>
+where
+    F: Fn(&T) -> String,
+{
+    if !query.is_paginated() {
+        return Page { items, next_last: None, n: None };
+    }
+
+    let mut keyed: Vec<(String, T)> = items.into_iter().map(|item| (key(&item), item)).collect();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    if let Some(last) = query.last.as_deref() {
+        keyed.retain(|(key, _)| key.as_str() > last);
+    }
+
+    let more = query.n.map_or(false, |n| keyed.len() > n as usize);
+    if let Some(n) = query.n {
+        keyed.truncate(n as usize);
+    }
+    let next_last = match keyed.last() {
+        Some((key, _)) if more => Some(key.clone()),
+        _ => None,
+    };
+
+    Page { items: keyed.into_iter().map(|(_, item)| item).collect(), next_last, n: query.n }
+}
+
+/// `<base?n=..&last=..>; rel="next"`
+pub fn next_link(base: &str, n: u32, last: &str) -> String {
+    let separator = if base.contains('?') { '&' } else { '?' };
+    let last: String = url::form_urlencoded::byte_serialize(last.as_bytes()).collect();
+    format!("<{}{}n={}&last={}>; rel=\"next\"", base, separator, n, last)
+}
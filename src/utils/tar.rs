@@ -0,0 +1,96 @@
+// Minimal ustar writer for streaming archives: only regular files, with a PAX
+// extended header for names and sizes that do not fit the ustar fields.
+
+/// Size of a tar header and of the blocks file content is padded to
+pub const BLOCK_SIZE: usize = 512;
+
+/// Two zero blocks that end an archive
+pub const END_OF_ARCHIVE: [u8; 2 * BLOCK_SIZE] = [0; 2 * BLOCK_SIZE];
+
+/// Longest name the ustar `name` field holds
+const MAX_NAME_LEN: usize = 100;
+
+/// Largest size the 11 octal digits of the ustar `size` field hold
+const MAX_USTAR_SIZE: u64 = 0o77777777777;
+
+/// Header(s) of a regular file entry at `path` holding `size` bytes. The
+/// content must follow, then `padding(size)` zero bytes.
+pub fn file_header(path: &str, size: u64) -> Vec<u8> {
+    let mut records = String::new();
+    if path.len() > MAX_NAME_LEN {
+        records.push_str(&pax_record("path", path));
+    }
+    if size > MAX_USTAR_SIZE {
+        records.push_str(&pax_record("size", &size.to_string()));
+    }
+
+    let mut out = Vec::with_capacity(BLOCK_SIZE);
+    if !records.is_empty() {
+        out.extend_from_slice(&ustar_header("././@PaxHeader", records.len() as u64, b'x'));
+        out.extend_from_slice(records.as_bytes());
+        out.resize(out.len() + padding(records.len() as u64), 0);
+    }
+    let name = truncate_name(path);
+    out.extend_from_slice(&ustar_header(name, size.min(MAX_USTAR_SIZE), b'0'));
+    out
+}
+
+/// Zero bytes that pad `size` bytes of content to a whole block
+pub fn padding(size: u64) -> usize {
+    let remainder = (size % BLOCK_SIZE as u64) as usize;
+    if remainder == 0 { 0 } else { BLOCK_SIZE - remainder }
+}
+
+/// Bytes an entry of `size` bytes at `path` takes up, headers and padding included
+pub fn entry_len(path: &str, size: u64) -> u64 {
+    file_header(path, size).len() as u64 + size + padding(size) as u64
+}
+
+/// A PAX record: `<length> <key>=<value>\n`, where the length counts itself
+fn pax_record(key: &str, value: &str) -> String {
+    let body = format!(" {}={}\n", key, value);
+    let mut len = body.len();
+    loop {
+        let total = len.to_string().len() + body.len();
+        if total == len {
+            return format!("{}{}", len, body);
+        }
+        len = total;
+    }
+}
+
+/// The last `MAX_NAME_LEN` bytes of `path`, for readers that ignore PAX headers
+fn truncate_name(path: &str) -> &str {
+    let mut start = path.len().saturating_sub(MAX_NAME_LEN);
+    while !path.is_char_boundary(start) {
+        start += 1;
+    }
+    &path[start..]
+}
+
+fn ustar_header(name: &str, size: u64, typeflag: u8) -> [u8; BLOCK_SIZE] {
+    let mut header = [0u8; BLOCK_SIZE];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], 0);
+    header[156] = typeflag;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is taken with its own field filled with spaces
+    header[148..156].fill(b' ');
+    let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+    write_octal(&mut header[148..155], checksum as u64);
+    header
+}
+
+/// Zero-padded octal digits followed by a NUL, filling `field`
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
@@ -0,0 +1,47 @@
+// Serialization of timestamps in API responses
+//
+// Every timestamp is written as RFC 3339 in UTC with millisecond precision and
+// a `Z` offset, e.g. `2025-09-24T10:15:30.123Z`, the form JavaScript's
+// `Date.toISOString` produces. Fields opt in with
+// `#[serde(with = "crate::utils::timestamp")]`, or `timestamp::option` for
+// optional ones. Any RFC 3339 timestamp is accepted when deserializing.
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// `timestamp` in the format every response uses
+pub fn format(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// An RFC 3339 timestamp with any offset, converted to UTC
+pub fn parse(value: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    DateTime::parse_from_rfc3339(value).map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+pub fn serialize<S: Serializer>(timestamp: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(timestamp))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse(&value).map_err(serde::de::Error::custom)
+}
+
+/// The same format for `Option<DateTime<Utc>>`; `None` is `null`
+pub mod option {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(timestamp: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match timestamp {
+            Some(timestamp) => serializer.serialize_some(&super::format(timestamp)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| super::parse(&value).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
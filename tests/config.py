@@ -108,6 +108,7 @@ def get_environment_vars() -> Dict[str, str]:
         "S3_REGION": TEST_CONFIG["minio"]["region"],
         "JWT_SECRET": TEST_CONFIG["auth"]["jwt_secret"],
         "API_PREFIX": "/api/v1",
+        "ALLOW_SELF_REGISTRATION": "true",
         "RUST_LOG": "debug",
         "RUST_BACKTRACE": "1"
     }
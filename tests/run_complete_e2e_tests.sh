@@ -194,7 +194,7 @@ run_compatibility_tests() {
     export DATABASE_URL="postgresql://aerugo:1@localhost:5432/aerugo_dev"
     export LISTEN_ADDRESS="0.0.0.0:8080"
     export LOG_LEVEL="warn"
-    export JWT_SECRET="test-secret-key-compatibility"
+    export JWT_SECRET="test-secret-key-for-compatibility-testing"
     export S3_ENDPOINT="http://localhost:9000"
     export S3_BUCKET="aerugo"
     export S3_ACCESS_KEY="minioadmin"
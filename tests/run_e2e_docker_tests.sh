@@ -87,7 +87,7 @@ start_aerugo_server() {
     export DATABASE_URL="postgresql://aerugo:1@localhost:5432/aerugo_dev"
     export LISTEN_ADDRESS="0.0.0.0:${REGISTRY_PORT}"
     export LOG_LEVEL="debug"
-    export JWT_SECRET="test-secret-key-for-e2e-testing"
+    export JWT_SECRET="test-secret-key-for-e2e-docker-testing"
     export S3_ENDPOINT="http://localhost:9000"
     export S3_BUCKET="aerugo"
     export S3_ACCESS_KEY="minioadmin"
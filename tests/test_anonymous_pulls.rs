@@ -0,0 +1,95 @@
+// Tests for anonymous pulls of public repositories by simple and namespaced
+// name; they need a live Postgres (DATABASE_URL)
+
+mod utils;
+
+use aerugo::handlers::docker_registry_v2::{authorize_blob_pull, authorize_pull};
+use axum::http::StatusCode;
+use utils::migrated_pool;
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_anonymous_simple_name_pull_of_public_repository() -> anyhow::Result<()> {
+    let pool = migrated_pool().await?;
+    // Simple names belong to the default organization
+    sqlx::query("INSERT INTO organizations (id, name, display_name) VALUES (1, 'library', 'library') ON CONFLICT DO NOTHING")
+        .execute(&pool)
+        .await?;
+    let public = format!("public-{}", uuid::Uuid::new_v4().simple());
+    let private = format!("private-{}", uuid::Uuid::new_v4().simple());
+    sqlx::query("INSERT INTO repositories (organization_id, name, is_public) VALUES (1, $1, TRUE), (1, $2, FALSE)")
+        .bind(&public)
+        .bind(&private)
+        .execute(&pool)
+        .await?;
+
+    assert!(authorize_pull(&pool, None, &public, None).await.is_ok());
+
+    // Private and unknown repositories both challenge for credentials
+    let response = authorize_pull(&pool, None, &private, None).await.unwrap_err();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = authorize_pull(&pool, None, "no-such-repository", None).await.unwrap_err();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    sqlx::query("DELETE FROM repositories WHERE organization_id = 1 AND name IN ($1, $2)")
+        .bind(&public)
+        .bind(&private)
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_anonymous_namespaced_pull_looks_in_the_named_organization() -> anyhow::Result<()> {
+    let pool = migrated_pool().await?;
+    let org = format!("anonymous-{}", uuid::Uuid::new_v4().simple());
+    let org_id = sqlx::query_scalar::<_, i64>("INSERT INTO organizations (name, display_name) VALUES ($1, $1) RETURNING id")
+        .bind(&org)
+        .fetch_one(&pool)
+        .await?;
+    sqlx::query("INSERT INTO repositories (organization_id, name, is_public) VALUES ($1, 'app', TRUE)")
+        .bind(org_id)
+        .execute(&pool)
+        .await?;
+
+    assert!(authorize_pull(&pool, Some(&org), "app", None).await.is_ok());
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1").bind(org_id).execute(&pool).await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_unqualified_blob_pull_of_private_repository() -> anyhow::Result<()> {
+    let pool = migrated_pool().await?;
+    // Unqualified names of authenticated callers resolve to their namespace
+    let username = format!("blobs-{}", uuid::Uuid::new_v4().simple());
+    let user_id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO users (username, email, password_hash) VALUES ($1, $1 || '@example.com', 'x') RETURNING id"
+    )
+    .bind(&username)
+    .fetch_one(&pool)
+    .await?;
+    let org_id = sqlx::query_scalar::<_, i64>("INSERT INTO organizations (name, display_name) VALUES ($1, $1) RETURNING id")
+        .bind(&username)
+        .fetch_one(&pool)
+        .await?;
+    sqlx::query("INSERT INTO repositories (organization_id, name, is_public, created_by) VALUES ($1, 'app', FALSE, $2)")
+        .bind(org_id)
+        .bind(user_id)
+        .execute(&pool)
+        .await?;
+
+    // Anonymous callers are challenged, whichever way they name the repository
+    let response = authorize_blob_pull(&pool, "app", None).await.unwrap_err();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = authorize_blob_pull(&pool, &format!("{}/app", username), None).await.unwrap_err();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    assert!(authorize_blob_pull(&pool, "app", Some(&user_id.to_string())).await.is_ok());
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1").bind(org_id).execute(&pool).await?;
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await?;
+    Ok(())
+}
@@ -0,0 +1,123 @@
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::with_captured_log;
+    use aerugo::error::{error_response, jittered_retry_after, AppError, DEFAULT_RETRY_AFTER_JITTER_PERCENT};
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    #[test]
+    fn test_conflict_maps_to_409() {
+        let err = AppError::Conflict("Organization with name 'acme' already exists".to_string());
+        assert_eq!(err.status_code(), StatusCode::CONFLICT);
+        assert_eq!(err.code(), "CONFLICT");
+
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn test_error_response_keeps_typed_status() {
+        let err: anyhow::Error = AppError::Conflict("Organization with name 'acme' already exists".to_string()).into();
+        let (status, body) = error_response(&err, StatusCode::BAD_REQUEST);
+
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body.0["code"], "CONFLICT");
+        assert_eq!(body.0["error"], "Organization with name 'acme' already exists");
+    }
+
+    #[test]
+    fn test_error_response_uses_fallback_for_untyped_errors() {
+        let err = anyhow::anyhow!("Insufficient permissions to update organization");
+        let (status, body) = error_response(&err, StatusCode::BAD_REQUEST);
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.0["error"], "Insufficient permissions to update organization");
+        assert!(body.0.get("code").is_none());
+    }
+
+    async fn retry_parts(response: axum::response::Response) -> (StatusCode, String, serde_json::Value) {
+        let status = response.status();
+        let retry_after = response.headers()["Retry-After"].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, retry_after, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn unavailable(retry_after_seconds: u64) -> AppError {
+        AppError::Unavailable { code: "STARTING", message: "Server is starting".to_string(), retry_after_seconds }
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_body_matches_retry_after_header() {
+        let (status, retry_after, body) = retry_parts(unavailable(30).into_response()).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!((24..=36).contains(&retry_after.parse::<u64>().unwrap()), "{}", retry_after);
+        assert_eq!(body["error"]["code"], "STARTING");
+        assert_eq!(body["error"]["message"], "Server is starting");
+        assert_eq!(body["error"]["retry_after_seconds"].to_string(), retry_after);
+    }
+
+    #[tokio::test]
+    async fn test_registry_body_matches_retry_after_header() {
+        let (status, retry_after, body) = retry_parts(unavailable(5).into_registry_response()).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!((4..=6).contains(&retry_after.parse::<u64>().unwrap()), "{}", retry_after);
+        assert_eq!(body["errors"][0]["code"], "UNAVAILABLE");
+        assert_eq!(body["errors"][0]["detail"]["retry_after_seconds"].to_string(), retry_after);
+    }
+
+    #[test]
+    fn test_non_backoff_errors_have_no_retry_after() {
+        let response = AppError::Conflict("taken".to_string()).into_response();
+        assert!(response.headers().get("Retry-After").is_none());
+    }
+
+    #[test]
+    fn test_retry_after_varies_within_jitter_band() {
+        let values: std::collections::HashSet<u64> =
+            (0..200).map(|_| jittered_retry_after(100, DEFAULT_RETRY_AFTER_JITTER_PERCENT)).collect();
+
+        assert!(values.iter().all(|v| (80..=120).contains(v)), "{:?}", values);
+        assert!(values.len() > 1, "Retry-After never varied: {:?}", values);
+    }
+
+    #[test]
+    fn test_retry_after_without_jitter_or_below_a_second() {
+        assert!((0..50).all(|_| jittered_retry_after(30, 0) == 30));
+        assert!((0..50).all(|_| jittered_retry_after(1, 100) >= 1));
+    }
+
+    const LEAKY_DETAIL: &str = "relation \"secret_billing_table\" does not exist: SELECT * FROM secret_billing_table";
+
+    #[test]
+    fn test_database_error_hides_detail_from_client_but_logs_it() {
+        let err = AppError::Database(sqlx::Error::Protocol(LEAKY_DETAIL.to_string()));
+        let ((status, body), log) = with_captured_log(|| err.response_parts());
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body.0["error"], "Internal server error");
+        let client_body = body.0.to_string();
+        assert!(!client_body.contains("secret_billing_table"));
+        assert!(!client_body.contains("SELECT"));
+
+        // The log carries the full detail and the id the client was given
+        let correlation_id = body.0["correlation_id"].as_str().unwrap();
+        assert!(log.contains("secret_billing_table"));
+        assert!(log.contains("SELECT * FROM"));
+        assert!(log.contains(correlation_id));
+    }
+
+    #[test]
+    fn test_untyped_sqlx_error_is_sanitized() {
+        let err: anyhow::Error = sqlx::Error::Protocol(LEAKY_DETAIL.to_string()).into();
+        let ((status, body), log) = with_captured_log(|| error_response(&err, StatusCode::BAD_REQUEST));
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body.0["error"], "Internal server error");
+        assert!(!body.0.to_string().contains("secret_billing_table"));
+        assert!(log.contains("secret_billing_table"));
+    }
+}
@@ -1080,6 +1080,133 @@ class AuthTests(BaseTestCase):
         
         self.logger.info("✅ Invalid email format test passed")
     
+    def test_api_key_rotation(self):
+        """Test that rotating an API key revokes the old secret and keeps its metadata"""
+        self.logger.info("Testing API key rotation")
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=8))
+        register_response = self.make_request("POST", "/auth/register", {
+            "username": f"rotate_key_{session_id}",
+            "email": f"rotate_key_{session_id}@example.com",
+            "password": "rotatepassword123"
+        })
+        self.assert_response(register_response, 201, "User registration failed")
+        token = register_response.json()["token"]
+        
+        create_response = self.make_request("POST", "/auth/api-keys", {"name": "ci-pipeline"}, token=token)
+        self.assert_response(create_response, 201, "API key creation failed")
+        created = create_response.json()
+        old_key = created["api_key"]
+        
+        response = self.make_request("GET", "/auth/api-keys", headers={"X-API-Key": old_key})
+        self.assert_response(response, 200, "API key should work before rotation")
+        
+        rotate_response = self.make_request("POST", f"/auth/tokens/{created['id']}/rotate", token=token)
+        self.assert_response(rotate_response, 200, "API key rotation failed")
+        rotated = rotate_response.json()
+        assert rotated["id"] == created["id"]
+        assert rotated["name"] == "ci-pipeline"
+        assert rotated["expires_at"] == created["expires_at"]
+        assert rotated["api_key"] != old_key
+        
+        # The old secret stops working immediately, the new one works
+        response = self.make_request("GET", "/auth/api-keys", headers={"X-API-Key": old_key})
+        self.assert_response(response, 401, "Old API key should be rejected after rotation")
+        
+        response = self.make_request("GET", "/auth/api-keys", headers={"X-API-Key": rotated["api_key"]})
+        self.assert_response(response, 200, "New API key should work after rotation")
+        
+        # Rotating someone else's or an unknown key is not found
+        response = self.make_request("POST", "/auth/tokens/999999999/rotate", token=token)
+        self.assert_response(response, 404, "Unknown API key should not be rotated")
+        
+        self.logger.info("✅ API key rotation test passed")
+
+    def test_email_uniqueness_ignores_case(self):
+        """Test that emails differing only in case are one account"""
+        self.logger.info("Testing case-insensitive email uniqueness")
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=8))
+        email = f'Mixed.Case_{session_id}@Example.com'
+        password = "mixedcase123"
+        
+        response = self.make_request("POST", "/auth/register", {
+            "username": f"mixed_case_{session_id}", "email": email, "password": password
+        })
+        self.assert_response(response, 201, "User registration failed")
+        
+        # The same address in another case is already taken
+        response = self.make_request("POST", "/auth/register", {
+            "username": f"mixed_case_other_{session_id}", "email": email.lower(), "password": password
+        })
+        self.assert_response(response, 409, "Registering the same email in another case should conflict")
+        
+        # Login works whatever case the address is typed in
+        for variant in (email, email.lower(), email.upper()):
+            response = self.make_request("POST", "/auth/login", {"email": variant, "password": password})
+            self.assert_response(response, 200, f"Login with {variant} failed")
+        
+        self.logger.info("✅ Case-insensitive email uniqueness test passed")
+
+    def test_session_listing_and_revocation(self):
+        """Test listing active sessions and revoking one of them"""
+        self.logger.info("Testing session listing and revocation")
+        
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=8))
+        credentials = {"email": f"sessions_{session_id}@example.com", "password": "sessionspassword123"}
+        response = self.make_request("POST", "/auth/register", {"username": f"sessions_{session_id}", **credentials})
+        self.assert_response(response, 201, "User registration failed")
+        laptop_token = response.json()["token"]
+        
+        response = self.make_request("POST", "/auth/login", credentials, headers={"User-Agent": "docker/24.0.7"})
+        self.assert_response(response, 200, "Login failed")
+        cli_token = response.json()["token"]
+        
+        response = self.make_request("POST", "/auth/api-keys", {"name": "ci-pipeline"}, token=laptop_token)
+        self.assert_response(response, 201, "API key creation failed")
+        api_key = response.json()["api_key"]
+        
+        response = self.make_request("GET", "/auth/me/sessions", token=laptop_token)
+        self.assert_response(response, 200, "Listing sessions failed")
+        sessions = response.json()
+        assert len(sessions) == 3, f"Expected two sessions and one API key, got {sessions}"
+        assert sum(1 for s in sessions if s["current"]) == 1
+        cli_session = next(s for s in sessions if s["label"] == "docker/24.0.7")
+        assert cli_session["kind"] == "session"
+        assert not cli_session["current"]
+        assert cli_session["created_at"] and cli_session["last_used_at"]
+        key_session = next(s for s in sessions if s["kind"] == "api_key")
+        assert key_session["label"] == "ci-pipeline"
+        
+        # Revoking the CLI session logs it out and leaves the others alone
+        response = self.make_request("DELETE", f"/auth/me/sessions/{cli_session['id']}", token=laptop_token)
+        self.assert_response(response, 204, "Revoking session failed")
+        response = self.make_request("GET", "/auth/me", token=cli_token)
+        self.assert_response(response, 401, "Revoked session token should be rejected")
+        response = self.make_request("POST", "/auth/refresh", {"token": cli_token})
+        self.assert_response(response, 401, "Revoked session should not be refreshable")
+        response = self.make_request("GET", "/auth/me", token=laptop_token)
+        self.assert_response(response, 200, "Other sessions should keep working")
+        
+        # Revoking the API key stops it working
+        response = self.make_request("DELETE", f"/auth/me/sessions/{key_session['id']}", token=laptop_token)
+        self.assert_response(response, 204, "Revoking API key failed")
+        response = self.make_request("GET", "/auth/me", headers={"X-API-Key": api_key})
+        self.assert_response(response, 401, "Revoked API key should be rejected")
+        
+        response = self.make_request("GET", "/auth/me/sessions", token=laptop_token)
+        self.assert_response(response, 200, "Listing sessions failed")
+        assert cli_session["id"] not in [s["id"] for s in response.json()]
+        assert len(response.json()) == 1
+        
+        # Unknown or already revoked sessions are not found
+        response = self.make_request("DELETE", f"/auth/me/sessions/{cli_session['id']}", token=laptop_token)
+        self.assert_response(response, 404, "Revoking twice should be not found")
+        response = self.make_request("DELETE", "/auth/me/sessions/not-a-session", token=laptop_token)
+        self.assert_response(response, 404, "Malformed session id should be not found")
+        
+        self.logger.info("✅ Session listing and revocation test passed")
+
     def run_all_tests(self):
         """Run all authentication tests"""
         self.logger.info("=== Running Auth Tests ===")
@@ -1106,6 +1233,7 @@ class AuthTests(BaseTestCase):
         self.test_refresh_invalid_token()
         self.test_registration_special_characters()
         self.test_login_case_sensitivity()
+        self.test_email_uniqueness_ignores_case()
         self.test_registration_max_length()
         self.test_rapid_consecutive_registrations()
         
@@ -1122,4 +1250,8 @@ class AuthTests(BaseTestCase):
         self.test_forgot_password_short_password()
         self.test_forgot_password_invalid_email_format()
         
+        # API key tests
+        self.test_api_key_rotation()
+        self.test_session_listing_and_revocation()
+        
         self.logger.info("✅ All auth tests passed")
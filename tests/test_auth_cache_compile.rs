@@ -62,10 +62,12 @@ mod tests {
             blob_metadata_ttl: Duration::from_secs(600),
             repository_ttl: Duration::from_secs(60),
             tag_ttl: Duration::from_secs(120),
+            catalog_ttl: Duration::from_secs(30),
             auth_token_ttl: Duration::from_secs(900),
             permission_ttl: Duration::from_secs(300),
             session_ttl: Duration::from_secs(1800),
             max_memory_entries: 10000,
+            max_entry_bytes: 1024 * 1024,
             enable_redis: false,
             enable_memory: true,
         };
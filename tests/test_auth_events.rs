@@ -0,0 +1,70 @@
+// Tests for the structured authentication event log
+
+mod utils;
+
+use aerugo::auth_events::{self, AuthEvent, AuthEventDestination, AuthEventKind, AUTH_EVENT_TARGET};
+use aerugo::correlation;
+use utils::CapturedLog;
+
+#[tokio::test]
+async fn test_failed_login_logged_with_reason() {
+    let captured = CapturedLog::default();
+    let _guard = tracing::subscriber::set_default(captured.subscriber());
+
+    correlation::scope(
+        "corr-failed-login".to_string(),
+        auth_events::scope("203.0.113.9".to_string(), async {
+            AuthEvent::new(AuthEventKind::LoginFailure).user(42).reason("invalid_password").log();
+        }),
+    )
+    .await;
+
+    let log = captured.contents();
+    assert_eq!(log.lines().count(), 1, "expected a single line, got: {}", log);
+    assert!(log.contains("WARN"));
+    assert!(log.contains(AUTH_EVENT_TARGET));
+    assert!(log.contains("event=\"login_failure\""), "{}", log);
+    assert!(log.contains("reason=\"invalid_password\""), "{}", log);
+    assert!(log.contains("user_id=42"), "{}", log);
+    assert!(log.contains("ip=\"203.0.113.9\""), "{}", log);
+    assert!(log.contains("correlation_id=corr-failed-login"), "{}", log);
+}
+
+#[tokio::test]
+async fn test_event_picks_up_request_user() {
+    let event = auth_events::scope("198.51.100.4".to_string(), async {
+        auth_events::note_user(7);
+        AuthEvent::new(AuthEventKind::TokenRevoked).reason("logout")
+    })
+    .await;
+    assert_eq!(event.user_id, Some(7));
+    assert_eq!(event.ip.as_deref(), Some("198.51.100.4"));
+    assert!(!event.correlation_id.is_empty());
+
+    // Outside a request there is no IP or user to report
+    let event = AuthEvent::new(AuthEventKind::LoginSuccess);
+    assert_eq!(event.user_id, None);
+    assert_eq!(event.ip, None);
+}
+
+#[test]
+fn test_successful_events_logged_as_info() {
+    let captured = CapturedLog::default();
+    tracing::subscriber::with_default(captured.subscriber(), || {
+        AuthEvent::new(AuthEventKind::TokenIssued).user(3).reason("refresh").log();
+    });
+
+    let log = captured.contents();
+    assert!(log.contains("INFO"));
+    assert!(log.contains("event=\"token_issued\""), "{}", log);
+    assert!(log.contains("ip=\"unknown\""), "{}", log);
+}
+
+#[test]
+fn test_destination_parsing() {
+    assert_eq!("log".parse::<AuthEventDestination>(), Ok(AuthEventDestination::Log));
+    assert_eq!("Database".parse::<AuthEventDestination>(), Ok(AuthEventDestination::Database));
+    assert_eq!("db".parse::<AuthEventDestination>(), Ok(AuthEventDestination::Database));
+    assert!("syslog".parse::<AuthEventDestination>().is_err());
+    assert_eq!(AuthEventDestination::default(), AuthEventDestination::Log);
+}
@@ -0,0 +1,65 @@
+// Tests for creating repositories on first push (AUTO_CREATE_REPOS); the
+// permission tests need a live Postgres (DATABASE_URL)
+
+mod utils;
+
+use aerugo::handlers::docker_registry_v2::{auto_create_allowed, authorize_auto_create};
+use axum::http::StatusCode;
+use utils::migrated_pool;
+
+#[test]
+fn test_first_push_creates_repository_by_default() {
+    assert!(auto_create_allowed(true, "acme/new-service").is_ok());
+}
+
+#[tokio::test]
+async fn test_first_push_refused_when_disabled() {
+    let response = auto_create_allowed(false, "acme/new-service").unwrap_err();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["errors"][0]["code"], "NAME_UNKNOWN");
+    assert!(body["errors"][0]["message"].as_str().unwrap().contains("acme/new-service"));
+}
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_first_push_creates_repository_only_for_members() -> anyhow::Result<()> {
+    let pool = migrated_pool().await?;
+    let org = format!("auto-create-{}", uuid::Uuid::new_v4().simple());
+    let org_id = sqlx::query_scalar::<_, i64>("INSERT INTO organizations (name, display_name) VALUES ($1, $1) RETURNING id")
+        .bind(&org)
+        .fetch_one(&pool)
+        .await?;
+    let mut users = Vec::new();
+    for suffix in ["member", "outsider"] {
+        let user_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO users (username, email, password_hash) VALUES ($1, $1 || '@example.com', 'x') RETURNING id"
+        )
+        .bind(format!("{}-{}", org, suffix))
+        .fetch_one(&pool)
+        .await?;
+        users.push(user_id);
+    }
+    let (member, outsider) = (users[0], users[1]);
+    sqlx::query("INSERT INTO organization_members (organization_id, user_id, role) VALUES ($1, $2, 'member')")
+        .bind(org_id)
+        .bind(member)
+        .execute(&pool)
+        .await?;
+
+    assert!(authorize_auto_create(&pool, org_id, Some(member), false).await.is_ok());
+    // Plain members may not when creation is reserved to admins
+    let response = authorize_auto_create(&pool, org_id, Some(member), true).await.unwrap_err();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = authorize_auto_create(&pool, org_id, Some(outsider), false).await.unwrap_err();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = authorize_auto_create(&pool, org_id, None, false).await.unwrap_err();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1").bind(org_id).execute(&pool).await?;
+    sqlx::query("DELETE FROM users WHERE id = ANY($1)").bind(&users).execute(&pool).await?;
+    Ok(())
+}
@@ -0,0 +1,98 @@
+// Tests that blobs are served as stored whatever encoding the client asks for
+
+use aerugo::compression::compress;
+use aerugo::handlers::docker_registry_v2::blob_response;
+use aerugo::storage::filesystem::FilesystemStorage;
+use aerugo::storage::Storage;
+use anyhow::Result;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{header, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use bytes::Bytes;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+const THRESHOLD: u64 = 1024;
+
+/// `layer contents\n` gzipped, as layers usually are
+const LAYER: &[u8] = &[
+    0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0x49, 0xac, 0x4c, 0x2d, 0x52,
+    0x48, 0xce, 0xcf, 0x2b, 0x49, 0xcd, 0x2b, 0x29, 0xe6, 0x02, 0x00, 0x9a, 0x08, 0x42, 0xbf, 0x0f,
+    0x00, 0x00, 0x00,
+];
+
+fn sha256_digest(data: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(data))
+}
+
+async fn get_blob(State(storage): State<Arc<FilesystemStorage>>) -> Response {
+    let digest = sha256_digest(LAYER);
+    blob_response(storage.as_ref(), &format!("blobs/{}", digest), &digest, THRESHOLD, false)
+        .await
+        .unwrap()
+        .unwrap_or_else(|| StatusCode::NOT_FOUND.into_response())
+}
+
+/// An API route behind compression next to a registry blob route, as in `create_app`
+async fn test_app(name: &str) -> Result<Router> {
+    let root = std::env::temp_dir().join(format!("aerugo-blob-encoding-{}-{}", name, uuid::Uuid::new_v4()));
+    let storage = Arc::new(FilesystemStorage::new(root));
+    storage.put_blob(&format!("blobs/{}", sha256_digest(LAYER)), Bytes::from_static(LAYER)).await?;
+
+    let api = compress(Router::new().route("/api/v1/info", get(|| async { "aerugo ".repeat(200) })), true);
+    Ok(api
+        .merge(Router::new().route("/v2/library/app/blobs/layer", get(get_blob)))
+        .with_state(storage))
+}
+
+fn gzip_request(uri: &str) -> Request<Body> {
+    Request::get(uri).header(header::ACCEPT_ENCODING, "gzip").body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_gzip_requested_blob_is_served_raw() -> Result<()> {
+    let app = test_app("raw").await?;
+
+    let response = app.oneshot(gzip_request("/v2/library/app/blobs/layer")).await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], LAYER.len().to_string().as_str());
+    let digest = response.headers()["Docker-Content-Digest"].to_str()?.to_string();
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    assert_eq!(&body[..], LAYER);
+    assert_eq!(sha256_digest(&body), digest);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unsupported_encoding_on_blob_is_not_an_error() -> Result<()> {
+    let app = test_app("unsupported").await?;
+    let request = Request::get("/v2/library/app/blobs/layer")
+        .header(header::ACCEPT_ENCODING, "br;q=1.0, zstd;q=0.5")
+        .body(Body::empty())?;
+
+    let response = app.oneshot(request).await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    assert_eq!(&body[..], LAYER);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_responses_are_still_compressed() -> Result<()> {
+    let app = test_app("api").await?;
+
+    let response = app.oneshot(gzip_request("/api/v1/info")).await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+    Ok(())
+}
@@ -0,0 +1,59 @@
+// Tests for the grace period protecting freshly uploaded blobs from cleanup
+
+mod utils;
+
+use aerugo::handlers::docker_registry_v2::{blob_gc_eligible, collect_orphan_blob};
+use aerugo::models::digest::Digest;
+use aerugo::storage::Storage;
+use anyhow::Result;
+use bytes::Bytes;
+use chrono::Utc;
+use std::time::Duration;
+use utils::test_storage;
+
+const GRACE: Duration = Duration::from_secs(3600);
+
+#[tokio::test]
+async fn test_orphan_blob_survives_grace_window_then_is_collected() -> Result<()> {
+    let storage = test_storage("orphan");
+    let orphan = Digest::sha256(b"layer");
+    storage.put_blob(&orphan.blob_key(), Bytes::from_static(b"layer")).await?;
+
+    // Just uploaded, its manifest not pushed yet
+    assert!(!collect_orphan_blob(&storage, &orphan, GRACE, Utc::now()).await?);
+    assert!(storage.blob_exists(&orphan.blob_key()).await?);
+
+    let after_grace = Utc::now() + chrono::Duration::seconds(GRACE.as_secs() as i64 + 1);
+    assert!(collect_orphan_blob(&storage, &orphan, GRACE, after_grace).await?);
+    assert!(!storage.blob_exists(&orphan.blob_key()).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_zero_grace_collects_immediately() -> Result<()> {
+    let storage = test_storage("zero");
+    let orphan = Digest::sha256(b"layer");
+    storage.put_blob(&orphan.blob_key(), Bytes::from_static(b"layer")).await?;
+
+    assert!(collect_orphan_blob(&storage, &orphan, Duration::ZERO, Utc::now()).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_blob_is_not_collected() -> Result<()> {
+    let storage = test_storage("missing");
+    let missing = Digest::sha256(b"never stored");
+    assert!(!collect_orphan_blob(&storage, &missing, GRACE, Utc::now()).await?);
+    Ok(())
+}
+
+#[test]
+fn test_eligibility_boundary() {
+    let now = Utc::now();
+    let grace = chrono::Duration::seconds(GRACE.as_secs() as i64);
+
+    assert!(!blob_gc_eligible(now - grace + chrono::Duration::seconds(1), now, GRACE));
+    assert!(blob_gc_eligible(now - grace, now, GRACE));
+    // A clock skewed timestamp in the future is never eligible
+    assert!(!blob_gc_eligible(now + chrono::Duration::seconds(10), now, GRACE));
+}
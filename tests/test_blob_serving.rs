@@ -0,0 +1,57 @@
+// Tests for the buffered vs streamed blob serving threshold
+
+mod utils;
+
+use aerugo::handlers::docker_registry_v2::blob_response;
+use aerugo::storage::Storage;
+use anyhow::Result;
+use axum::body::HttpBody;
+use bytes::Bytes;
+use utils::test_storage;
+
+const THRESHOLD: u64 = 1024;
+
+#[tokio::test]
+async fn test_small_blob_served_buffered() -> Result<()> {
+    let storage = test_storage("small");
+    let digest = "sha256:small";
+    let key = format!("blobs/{}", digest);
+    storage.put_blob(&key, Bytes::from(vec![7u8; 100])).await?;
+
+    let response = blob_response(&storage, &key, digest, THRESHOLD, false).await?.expect("blob exists");
+
+    assert_eq!(response.headers()["Content-Length"], "100");
+    assert_eq!(response.headers()["Docker-Content-Digest"], digest);
+    // A buffered body knows its exact size
+    assert_eq!(response.body().size_hint().exact(), Some(100));
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    assert_eq!(body.len(), 100);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_large_blob_streamed() -> Result<()> {
+    let storage = test_storage("large");
+    let digest = "sha256:large";
+    let key = format!("blobs/{}", digest);
+    storage.put_blob(&key, Bytes::from(vec![7u8; 4096])).await?;
+
+    let response = blob_response(&storage, &key, digest, THRESHOLD, false).await?.expect("blob exists");
+
+    assert_eq!(response.headers()["Content-Length"], "4096");
+    assert_eq!(response.headers()["Docker-Content-Digest"], digest);
+    // A streamed body does not know its size up front
+    assert_eq!(response.body().size_hint().exact(), None);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    assert_eq!(body.len(), 4096);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_blob_returns_none() -> Result<()> {
+    let storage = test_storage("missing");
+    assert!(blob_response(&storage, "blobs/sha256:missing", "sha256:missing", THRESHOLD, false).await?.is_none());
+    Ok(())
+}
@@ -0,0 +1,88 @@
+// Tests for VERIFY_BLOB_ON_READ catching blobs corrupted in storage
+
+mod utils;
+
+use aerugo::handlers::docker_registry_v2::blob_response;
+use aerugo::models::digest::Digest;
+use aerugo::storage::filesystem::FilesystemStorage;
+use aerugo::storage::verify::content_matches;
+use aerugo::storage::Storage;
+use anyhow::Result;
+use axum::http::StatusCode;
+use bytes::Bytes;
+use utils::test_storage;
+
+const THRESHOLD: u64 = 1024;
+
+/// Store `original` under its digest, then overwrite it with `original`
+/// with one bit flipped, as bit-rot in the backend would
+async fn store_corrupted(storage: &FilesystemStorage, original: &[u8]) -> Result<Digest> {
+    let digest = Digest::sha256(original);
+    let mut corrupted = original.to_vec();
+    corrupted[original.len() / 2] ^= 0x01;
+    storage.put_blob(&digest.blob_key(), Bytes::from(corrupted)).await?;
+    Ok(digest)
+}
+
+#[test]
+fn test_content_matches() {
+    let digest = Digest::sha256(b"layer contents");
+    assert!(content_matches(&digest, b"layer contents"));
+    assert!(!content_matches(&digest, b"layer c0ntents"));
+}
+
+#[tokio::test]
+async fn test_corrupted_buffered_blob_refused() -> Result<()> {
+    let storage = test_storage("buffered");
+    let digest = store_corrupted(&storage, &[7u8; 100]).await?;
+
+    let response = blob_response(&storage, &digest.blob_key(), digest.as_str(), THRESHOLD, true)
+        .await?
+        .expect("blob exists");
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_corrupted_streamed_blob_aborted() -> Result<()> {
+    let storage = test_storage("streamed");
+    let digest = store_corrupted(&storage, &[7u8; 4096]).await?;
+
+    let response = blob_response(&storage, &digest.blob_key(), digest.as_str(), THRESHOLD, true)
+        .await?
+        .expect("blob exists");
+    // Headers go out before the content is read, so the body is what fails
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(axum::body::to_bytes(response.into_body(), usize::MAX).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_intact_blobs_served() -> Result<()> {
+    let storage = test_storage("intact");
+    for size in [100, 4096] {
+        let data = vec![7u8; size];
+        let digest = Digest::sha256(&data);
+        storage.put_blob(&digest.blob_key(), Bytes::from(data)).await?;
+
+        let response = blob_response(&storage, &digest.blob_key(), digest.as_str(), THRESHOLD, true)
+            .await?
+            .expect("blob exists");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(body.len(), size);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_corruption_ignored_when_disabled() -> Result<()> {
+    let storage = test_storage("disabled");
+    let digest = store_corrupted(&storage, &[7u8; 100]).await?;
+
+    let response = blob_response(&storage, &digest.blob_key(), digest.as_str(), THRESHOLD, false)
+        .await?
+        .expect("blob exists");
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(())
+}
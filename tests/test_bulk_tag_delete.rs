@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::handlers::docker_registry_v2::{select_tags_to_delete, BulkTagDeleteRequest};
+
+    fn existing_tags() -> Vec<String> {
+        ["latest", "v1.0.0", "ci-101", "ci-102", "ci-103"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect()
+    }
+
+    fn by_tags(tags: &[&str]) -> BulkTagDeleteRequest {
+        BulkTagDeleteRequest {
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+            pattern: None,
+        }
+    }
+
+    fn by_pattern(pattern: &str) -> BulkTagDeleteRequest {
+        BulkTagDeleteRequest {
+            tags: None,
+            pattern: Some(pattern.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_exact_list_deletion() {
+        let selected = select_tags_to_delete(&by_tags(&["ci-101", "latest", "missing"]), &existing_tags()).unwrap();
+        // Unknown names are ignored rather than failing the whole request
+        assert_eq!(selected, vec!["latest".to_string(), "ci-101".to_string()]);
+    }
+
+    #[test]
+    fn test_pattern_deletion() {
+        let selected = select_tags_to_delete(&by_pattern("ci-*"), &existing_tags()).unwrap();
+        assert_eq!(selected, vec!["ci-101", "ci-102", "ci-103"]);
+    }
+
+    #[test]
+    fn test_no_match_deletes_zero() {
+        assert!(select_tags_to_delete(&by_pattern("pr-*"), &existing_tags()).unwrap().is_empty());
+        assert!(select_tags_to_delete(&by_tags(&["nope"]), &existing_tags()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_requires_exactly_one_selector() {
+        let neither = BulkTagDeleteRequest { tags: None, pattern: None };
+        assert!(select_tags_to_delete(&neither, &existing_tags()).is_err());
+
+        let both = BulkTagDeleteRequest {
+            tags: Some(vec!["latest".to_string()]),
+            pattern: Some("ci-*".to_string()),
+        };
+        assert!(select_tags_to_delete(&both, &existing_tags()).is_err());
+
+        assert!(select_tags_to_delete(&by_pattern(""), &existing_tags()).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+// Tests for the maximum size of cached manifests
+
+use aerugo::cache::{CacheConfig, RegistryCache};
+use anyhow::Result;
+use bytes::Bytes;
+
+const MAX_ENTRY_BYTES: usize = 64;
+
+async fn memory_cache() -> Result<RegistryCache> {
+    RegistryCache::new(CacheConfig {
+        max_entry_bytes: MAX_ENTRY_BYTES,
+        enable_redis: false,
+        ..CacheConfig::default()
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_small_manifest_is_cached() -> Result<()> {
+    let cache = memory_cache().await?;
+    let manifest = Bytes::from_static(b"{\"schemaVersion\":2}");
+
+    assert!(cache.cache_manifest("library/app:small", manifest.clone()).await?);
+    assert_eq!(cache.get_manifest("library/app:small").await, Some(manifest));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_oversized_manifest_is_not_cached() -> Result<()> {
+    let cache = memory_cache().await?;
+    let manifest = Bytes::from(vec![b' '; MAX_ENTRY_BYTES + 1]);
+
+    assert!(!cache.cache_manifest("library/app:index", manifest).await?);
+    assert_eq!(cache.get_manifest("library/app:index").await, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_manifest_at_limit_is_cached() -> Result<()> {
+    let cache = memory_cache().await?;
+    let manifest = Bytes::from(vec![b' '; MAX_ENTRY_BYTES]);
+
+    assert!(cache.cache_manifest("library/app:edge", manifest).await?);
+    assert!(cache.get_manifest("library/app:edge").await.is_some());
+    Ok(())
+}
@@ -0,0 +1,54 @@
+// Tests for per-type cache TTLs
+
+use aerugo::cache::{CacheConfig, CacheKeyType};
+use aerugo::config::settings::CacheSettings;
+use std::time::Duration;
+
+fn settings(manifest: Option<u64>, authz: Option<u64>, tag: Option<u64>) -> CacheSettings {
+    CacheSettings {
+        redis_url: "redis://localhost:6379".to_string(),
+        pool_size: 10,
+        ttl_seconds: 3600,
+        manifest_ttl_seconds: manifest,
+        authz_ttl_seconds: authz,
+        tag_ttl_seconds: tag,
+        catalog_ttl_seconds: 30,
+        max_entry_bytes: 1024 * 1024,
+        warmup: false,
+        warmup_count: 100,
+    }
+}
+
+#[test]
+fn test_each_type_uses_its_configured_ttl() {
+    let settings = settings(Some(86400), Some(30), Some(120));
+
+    assert_eq!(settings.ttl(CacheKeyType::Manifest), Duration::from_secs(86400));
+    assert_eq!(settings.ttl(CacheKeyType::Authz), Duration::from_secs(30));
+    assert_eq!(settings.ttl(CacheKeyType::Tag), Duration::from_secs(120));
+}
+
+#[test]
+fn test_unconfigured_types_fall_back_to_global_ttl() {
+    let settings = settings(None, Some(30), None);
+
+    assert_eq!(settings.ttl(CacheKeyType::Manifest), Duration::from_secs(3600));
+    assert_eq!(settings.ttl(CacheKeyType::Tag), Duration::from_secs(3600));
+    assert_eq!(settings.ttl(CacheKeyType::Session), Duration::from_secs(3600));
+    assert_eq!(settings.ttl(CacheKeyType::Authz), Duration::from_secs(30));
+}
+
+#[test]
+fn test_cache_config_selects_ttl_by_key_type() {
+    let config = CacheConfig {
+        manifest_ttl: Duration::from_secs(86400),
+        permission_ttl: Duration::from_secs(30),
+        tag_ttl: Duration::from_secs(120),
+        ..CacheConfig::default()
+    };
+
+    assert_eq!(config.ttl(CacheKeyType::Manifest), Duration::from_secs(86400));
+    assert_eq!(config.ttl(CacheKeyType::Authz), Duration::from_secs(30));
+    assert_eq!(config.ttl(CacheKeyType::Tag), Duration::from_secs(120));
+    assert_eq!(config.ttl(CacheKeyType::Repository), CacheConfig::default().repository_ttl);
+}
@@ -0,0 +1,64 @@
+// Tests for pre-loading popular manifests into the cache at startup
+
+use aerugo::cache::{manifest_cache_key, repository_pull_names, CacheConfig, RegistryCache};
+use aerugo::cache_warmup::{warm_manifests, WarmupManifest};
+use aerugo::storage::filesystem::FilesystemStorage;
+use aerugo::storage::Storage;
+use aerugo::tenant::DEFAULT_ORGANIZATION_ID;
+use anyhow::Result;
+use bytes::Bytes;
+
+fn manifest(repository: &str, digest: &str, tags: &[&str]) -> WarmupManifest {
+    WarmupManifest {
+        repositories: vec![repository.to_string()],
+        digest: digest.to_string(),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_cache_keys_cover_digest_and_tags() {
+    let manifest = manifest("library/app", "sha256:aaa", &["1.0", "latest"]);
+
+    assert_eq!(
+        manifest.cache_keys(),
+        vec!["manifest:library/app:sha256:aaa", "manifest:library/app:1.0", "manifest:library/app:latest"]
+    );
+}
+
+#[test]
+fn test_default_organization_keys_match_simple_name_pulls() {
+    let names = repository_pull_names("library", "app", DEFAULT_ORGANIZATION_ID);
+    assert_eq!(names, vec!["library/app", "app"]);
+    assert_eq!(repository_pull_names("acme", "app", DEFAULT_ORGANIZATION_ID + 1), vec!["acme/app"]);
+
+    let manifest = WarmupManifest { repositories: names, digest: "sha256:aaa".to_string(), tags: vec!["latest".to_string()] };
+    let keys = manifest.cache_keys();
+    // The key a GET of `app:latest` looks up
+    assert!(keys.contains(&manifest_cache_key("app", "latest")), "{:?}", keys);
+    assert!(keys.contains(&manifest_cache_key("library/app", "latest")), "{:?}", keys);
+    assert_eq!(keys.len(), 4);
+}
+
+#[tokio::test]
+async fn test_warmup_populates_cache() -> Result<()> {
+    let root = std::env::temp_dir().join(format!("aerugo-cache-warmup-{}", uuid::Uuid::new_v4()));
+    let storage = FilesystemStorage::new(root);
+    let popular = Bytes::from_static(b"{\"schemaVersion\":2,\"layers\":[]}");
+    storage.put_blob("blobs/sha256:popular", popular.clone()).await?;
+
+    let cache = RegistryCache::new(CacheConfig { enable_redis: false, ..CacheConfig::default() }).await?;
+    let manifests = vec![
+        manifest("library/app", "sha256:popular", &["latest"]),
+        // Not in storage, so skipped
+        manifest("library/gone", "sha256:missing", &["latest"]),
+    ];
+
+    let cached = warm_manifests(&cache, &storage, &manifests).await;
+
+    assert_eq!(cached, 2);
+    assert_eq!(cache.get_manifest("manifest:library/app:sha256:popular").await, Some(popular.clone()));
+    assert_eq!(cache.get_manifest("manifest:library/app:latest").await, Some(popular));
+    assert_eq!(cache.get_manifest("manifest:library/gone:latest").await, None);
+    Ok(())
+}
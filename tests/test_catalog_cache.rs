@@ -0,0 +1,72 @@
+// Tests for caching /v2/_catalog pages per caller
+
+use aerugo::cache::{catalog_cache_key, CacheConfig, CatalogPage, RegistryCache};
+use anyhow::Result;
+use std::time::Duration;
+
+async fn memory_cache() -> Result<RegistryCache> {
+    RegistryCache::new(CacheConfig { enable_redis: false, ..CacheConfig::default() }).await
+}
+
+fn page(repositories: &[&str]) -> CatalogPage {
+    CatalogPage {
+        repositories: repositories.iter().map(|r| r.to_string()).collect(),
+        next_last: None,
+    }
+}
+
+#[tokio::test]
+async fn test_repeated_identical_request_hits_cache() -> Result<()> {
+    let cache = memory_cache().await?;
+    let key = catalog_cache_key("42", Some(2), Some("acme/api"));
+    assert_eq!(cache.get_catalog_page(&key).await, None);
+
+    let first = CatalogPage {
+        repositories: vec!["acme/app".to_string(), "acme/db".to_string()],
+        next_last: Some("acme/db".to_string()),
+    };
+    cache.cache_catalog_page(&key, &first).await?;
+
+    assert_eq!(cache.get_catalog_page(&catalog_cache_key("42", Some(2), Some("acme/api"))).await, Some(first));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pages_are_not_shared_between_callers_or_pages() -> Result<()> {
+    let cache = memory_cache().await?;
+    cache.cache_catalog_page(&catalog_cache_key("42", None, None), &page(&["acme/private"])).await?;
+
+    assert_eq!(cache.get_catalog_page(&catalog_cache_key("7", None, None)).await, None);
+    assert_eq!(cache.get_catalog_page(&catalog_cache_key("org_42", None, None)).await, None);
+    assert_eq!(cache.get_catalog_page(&catalog_cache_key("42", Some(10), None)).await, None);
+    assert_eq!(cache.get_catalog_page(&catalog_cache_key("42", None, Some("acme/a"))).await, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_repository_creation_invalidates_cached_pages() -> Result<()> {
+    let cache = memory_cache().await?;
+    let key = catalog_cache_key("42", None, None);
+    cache.cache_catalog_page(&key, &page(&["acme/app"])).await?;
+
+    // What repository creation does once committed
+    cache.invalidate_repositories().await?;
+
+    assert_eq!(cache.get_catalog_page(&key).await, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_zero_ttl_disables_catalog_caching() -> Result<()> {
+    let cache = RegistryCache::new(CacheConfig {
+        catalog_ttl: Duration::ZERO,
+        enable_redis: false,
+        ..CacheConfig::default()
+    })
+    .await?;
+    let key = catalog_cache_key("42", None, None);
+    cache.cache_catalog_page(&key, &page(&["acme/app"])).await?;
+
+    assert_eq!(cache.get_catalog_page(&key).await, None);
+    Ok(())
+}
@@ -0,0 +1,44 @@
+// Tests for who may list /v2/_catalog under each CATALOG_VISIBILITY tier
+#[cfg(test)]
+mod tests {
+    use aerugo::config::settings::CatalogVisibility;
+    use aerugo::handlers::docker_registry_v2::{catalog_access, CatalogCaller};
+    use axum::http::StatusCode;
+
+    fn user() -> CatalogCaller {
+        CatalogCaller::User("42".to_string())
+    }
+
+    #[test]
+    fn test_public_tier_lets_anyone_list() {
+        let visibility = CatalogVisibility::Public;
+        assert_eq!(catalog_access(visibility, &CatalogCaller::Anonymous), Ok(()));
+        assert_eq!(catalog_access(visibility, &user()), Ok(()));
+        assert_eq!(catalog_access(visibility, &CatalogCaller::Admin), Ok(()));
+    }
+
+    #[test]
+    fn test_authenticated_tier_challenges_anonymous_callers() {
+        let visibility = CatalogVisibility::Authenticated;
+        assert_eq!(catalog_access(visibility, &CatalogCaller::Anonymous), Err(StatusCode::UNAUTHORIZED));
+        assert_eq!(catalog_access(visibility, &user()), Ok(()));
+        assert_eq!(catalog_access(visibility, &CatalogCaller::User("org_7".to_string())), Ok(()));
+        assert_eq!(catalog_access(visibility, &CatalogCaller::Admin), Ok(()));
+    }
+
+    #[test]
+    fn test_admin_tier_denies_other_users() {
+        let visibility = CatalogVisibility::Admin;
+        assert_eq!(catalog_access(visibility, &CatalogCaller::Anonymous), Err(StatusCode::UNAUTHORIZED));
+        assert_eq!(catalog_access(visibility, &user()), Err(StatusCode::FORBIDDEN));
+        assert_eq!(catalog_access(visibility, &CatalogCaller::Admin), Ok(()));
+    }
+
+    #[test]
+    fn test_visibility_parses_case_insensitively() {
+        assert_eq!("public".parse(), Ok(CatalogVisibility::Public));
+        assert_eq!("Authenticated".parse(), Ok(CatalogVisibility::Authenticated));
+        assert_eq!("ADMIN".parse(), Ok(CatalogVisibility::Admin));
+        assert!("everyone".parse::<CatalogVisibility>().is_err());
+    }
+}
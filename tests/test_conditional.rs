@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::utils::conditional::{check, evaluate, http_date, Precondition, ResourceState};
+    use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+    use chrono::{Duration, TimeZone, Utc};
+
+    const ETAG: &str = "\"sha256:abc\"";
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn resource() -> ResourceState<'static> {
+        ResourceState {
+            etag: Some(ETAG),
+            last_modified: Some(Utc.with_ymd_and_hms(2025, 9, 24, 12, 0, 0).unwrap()),
+        }
+    }
+
+    fn modified() -> chrono::DateTime<Utc> {
+        resource().last_modified.unwrap()
+    }
+
+    #[test]
+    fn test_no_conditions_proceed() {
+        assert_eq!(evaluate(&HeaderMap::new(), &Method::GET, &resource()), Precondition::Proceed);
+        assert_eq!(evaluate(&HeaderMap::new(), &Method::PUT, &resource()), Precondition::Proceed);
+    }
+
+    #[test]
+    fn test_if_match() {
+        let cases = [
+            (ETAG, Precondition::Proceed),
+            ("\"other\", \"sha256:abc\"", Precondition::Proceed),
+            ("*", Precondition::Proceed),
+            ("\"other\"", Precondition::Failed),
+            // Strong comparison: weak tags never satisfy If-Match
+            ("W/\"sha256:abc\"", Precondition::Failed),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(evaluate(&headers(&[("if-match", value)]), &Method::PUT, &resource()), expected, "{}", value);
+        }
+
+        // `*` does not match a resource that does not exist
+        let missing = ResourceState::default();
+        assert_eq!(evaluate(&headers(&[("if-match", "*")]), &Method::PUT, &missing), Precondition::Failed);
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let cases = [
+            (Method::GET, ETAG, Precondition::NotModified),
+            (Method::HEAD, "W/\"sha256:abc\"", Precondition::NotModified),
+            (Method::GET, "*", Precondition::NotModified),
+            (Method::GET, "\"other\"", Precondition::Proceed),
+            // On writes a match means the client's assumption is stale
+            (Method::PUT, ETAG, Precondition::Failed),
+            (Method::PUT, "\"other\"", Precondition::Proceed),
+        ];
+        for (method, value, expected) in cases {
+            assert_eq!(evaluate(&headers(&[("if-none-match", value)]), &method, &resource()), expected, "{} {}", method, value);
+        }
+
+        // Create-only semantics: `If-None-Match: *` lets a PUT create a missing resource
+        let missing = ResourceState::default();
+        assert_eq!(evaluate(&headers(&[("if-none-match", "*")]), &Method::PUT, &missing), Precondition::Proceed);
+    }
+
+    #[test]
+    fn test_if_unmodified_since() {
+        let before = http_date(modified() - Duration::hours(1));
+        let same = http_date(modified());
+        let after = http_date(modified() + Duration::hours(1));
+
+        assert_eq!(evaluate(&headers(&[("if-unmodified-since", &before)]), &Method::PUT, &resource()), Precondition::Failed);
+        assert_eq!(evaluate(&headers(&[("if-unmodified-since", &same)]), &Method::PUT, &resource()), Precondition::Proceed);
+        assert_eq!(evaluate(&headers(&[("if-unmodified-since", &after)]), &Method::PUT, &resource()), Precondition::Proceed);
+
+        // Invalid dates are ignored
+        assert_eq!(evaluate(&headers(&[("if-unmodified-since", "yesterday")]), &Method::PUT, &resource()), Precondition::Proceed);
+    }
+
+    #[test]
+    fn test_if_modified_since() {
+        let before = http_date(modified() - Duration::hours(1));
+        let after = http_date(modified() + Duration::hours(1));
+
+        assert_eq!(evaluate(&headers(&[("if-modified-since", &after)]), &Method::GET, &resource()), Precondition::NotModified);
+        assert_eq!(evaluate(&headers(&[("if-modified-since", &before)]), &Method::GET, &resource()), Precondition::Proceed);
+        // Only applies to reads
+        assert_eq!(evaluate(&headers(&[("if-modified-since", &after)]), &Method::PUT, &resource()), Precondition::Proceed);
+    }
+
+    #[test]
+    fn test_header_precedence() {
+        let before = http_date(modified() - Duration::hours(1));
+        let after = http_date(modified() + Duration::hours(1));
+
+        // If-Match takes precedence over If-Unmodified-Since
+        let h = headers(&[("if-match", ETAG), ("if-unmodified-since", &before)]);
+        assert_eq!(evaluate(&h, &Method::PUT, &resource()), Precondition::Proceed);
+
+        // If-None-Match takes precedence over If-Modified-Since
+        let h = headers(&[("if-none-match", "\"other\""), ("if-modified-since", &after)]);
+        assert_eq!(evaluate(&h, &Method::GET, &resource()), Precondition::Proceed);
+
+        // A failed If-Match wins over a matching If-None-Match
+        let h = headers(&[("if-match", "\"other\""), ("if-none-match", ETAG)]);
+        assert_eq!(evaluate(&h, &Method::GET, &resource()), Precondition::Failed);
+    }
+
+    #[test]
+    fn test_check_builds_responses() {
+        assert!(check(&HeaderMap::new(), &Method::GET, &resource()).is_none());
+
+        let not_modified = check(&headers(&[("if-none-match", ETAG)]), &Method::GET, &resource()).unwrap();
+        assert_eq!(not_modified.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(not_modified.headers()["etag"], ETAG);
+        assert_eq!(not_modified.headers()["last-modified"], "Wed, 24 Sep 2025 12:00:00 GMT");
+
+        let failed = check(&headers(&[("if-match", "\"other\"")]), &Method::PUT, &resource()).unwrap();
+        assert_eq!(failed.status(), StatusCode::PRECONDITION_FAILED);
+    }
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::handlers::docker_registry_v2::{set_content_digest, without_body, DOCKER_CONTENT_DIGEST};
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+
+    const DIGEST: &str = "sha256:4f53cda18c2baa0c0354bb5f9a3ecbe5ed12ab4d8e11ba873c2f11161202b945";
+
+    #[test]
+    fn test_sets_digest_header() {
+        let mut headers = HeaderMap::new();
+        set_content_digest(&mut headers, DIGEST);
+        assert_eq!(headers[DOCKER_CONTENT_DIGEST], DIGEST);
+    }
+
+    #[test]
+    fn test_replaces_existing_digest() {
+        let mut headers = HeaderMap::new();
+        set_content_digest(&mut headers, "sha256:old");
+        set_content_digest(&mut headers, DIGEST);
+        assert_eq!(headers.get_all(DOCKER_CONTENT_DIGEST).iter().count(), 1);
+        assert_eq!(headers[DOCKER_CONTENT_DIGEST], DIGEST);
+    }
+
+    #[test]
+    fn test_invalid_digest_is_skipped() {
+        let mut headers = HeaderMap::new();
+        set_content_digest(&mut headers, "sha256:bad\nvalue");
+        assert!(headers.get(DOCKER_CONTENT_DIGEST).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_head_response_keeps_digest_and_drops_body() {
+        let mut headers = HeaderMap::new();
+        set_content_digest(&mut headers, DIGEST);
+        headers.insert("Content-Length", "7".parse().unwrap());
+        let get = (StatusCode::OK, headers, "content").into_response();
+
+        let head = without_body(get);
+
+        assert_eq!(head.status(), StatusCode::OK);
+        assert_eq!(head.headers()[DOCKER_CONTENT_DIGEST], DIGEST);
+        assert_eq!(head.headers()["Content-Length"], "7");
+        let body = axum::body::to_bytes(head.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+// Tests for parsing the Content-Range of blob upload chunks
+#[cfg(test)]
+mod tests {
+    use aerugo::utils::content_range::{parse_content_range, ContentRange, ContentRangeError};
+
+    #[test]
+    fn test_valid_ranges_parse() {
+        let range = parse_content_range("0-1023").unwrap();
+        assert_eq!(range, ContentRange { start: 0, end: 1023 });
+        assert_eq!(range.byte_count(), 1024);
+
+        assert_eq!(parse_content_range("bytes=1024-2047").unwrap(), ContentRange { start: 1024, end: 2047 });
+        assert_eq!(parse_content_range("bytes 1024-2047/4096").unwrap(), ContentRange { start: 1024, end: 2047 });
+        assert_eq!(parse_content_range("5-5/*").unwrap().byte_count(), 1);
+    }
+
+    #[test]
+    fn test_empty_or_missing_ranges_rejected() {
+        assert_eq!(parse_content_range(""), Err(ContentRangeError::Empty));
+        assert_eq!(parse_content_range("bytes="), Err(ContentRangeError::Empty));
+        assert_eq!(parse_content_range("1024"), Err(ContentRangeError::Malformed));
+        assert_eq!(parse_content_range("-1023"), Err(ContentRangeError::MissingBound("start")));
+        assert_eq!(parse_content_range("0-"), Err(ContentRangeError::MissingBound("end")));
+    }
+
+    #[test]
+    fn test_non_numeric_ranges_rejected() {
+        assert_eq!(parse_content_range("a-b"), Err(ContentRangeError::NotNumeric("a".to_string())));
+        assert_eq!(parse_content_range("0-1k"), Err(ContentRangeError::NotNumeric("1k".to_string())));
+        assert_eq!(parse_content_range("+1-5"), Err(ContentRangeError::NotNumeric("+1".to_string())));
+        assert_eq!(parse_content_range("0-10-20"), Err(ContentRangeError::NotNumeric("10-20".to_string())));
+        assert_eq!(parse_content_range("0-10/abc"), Err(ContentRangeError::Malformed));
+    }
+
+    #[test]
+    fn test_reversed_range_rejected() {
+        assert_eq!(parse_content_range("2048-1024"), Err(ContentRangeError::Reversed { start: 2048, end: 1024 }));
+    }
+
+    #[test]
+    fn test_oversized_ranges_rejected_without_overflow() {
+        assert_eq!(parse_content_range("0-18446744073709551615"), Err(ContentRangeError::TooLarge));
+        assert_eq!(parse_content_range("0-99999999999999999999999"), Err(ContentRangeError::TooLarge));
+        assert_eq!(parse_content_range("1-18446744073709551615").unwrap().byte_count(), u64::MAX);
+    }
+}
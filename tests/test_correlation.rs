@@ -0,0 +1,143 @@
+// Tests for correlation ID and trace context propagation
+
+use aerugo::correlation::{self, correlation_middleware, CorrelationConfig, CorrelationId, TraceParent, TRACEPARENT};
+use axum::body::Body;
+use axum::http::{HeaderName, HeaderValue, Request, StatusCode};
+use axum::routing::get;
+use axum::{Extension, Router};
+use tower::ServiceExt;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const CLIENT_TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+/// Echoes the correlation ID the handler runs with
+fn test_app(config: CorrelationConfig) -> Router {
+    Router::new()
+        .route("/", get(|| async { correlation::current().unwrap_or_default() }))
+        .layer(axum::middleware::from_fn_with_state(config, correlation_middleware))
+}
+
+async fn body_string(response: axum::response::Response) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_custom_header_name_is_read_and_written() {
+    let app = test_app(CorrelationConfig::new(HeaderName::from_static("x-request-id")));
+    let request = Request::get("/")
+        .header("x-request-id", "req-42")
+        .header("x-correlation-id", "ignored")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.headers()["x-request-id"], "req-42");
+    assert!(response.headers().get("x-correlation-id").is_none());
+    assert_eq!(body_string(response).await, "req-42");
+}
+
+#[tokio::test]
+async fn test_default_header_is_generated_when_missing() {
+    let app = test_app(CorrelationConfig::default());
+
+    let response = app.oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+
+    let id = response.headers()["x-correlation-id"].to_str().unwrap().to_string();
+    assert!(!id.is_empty());
+    assert_eq!(body_string(response).await, id);
+}
+
+#[tokio::test]
+async fn test_traceparent_is_propagated() {
+    let app = test_app(CorrelationConfig::default());
+    let request = Request::get("/").header(TRACEPARENT, CLIENT_TRACEPARENT).body(Body::empty()).unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+
+    // The trace ID becomes the correlation ID, and the response continues the trace
+    assert_eq!(response.headers()["x-correlation-id"], TRACE_ID);
+    let traceparent = TraceParent::parse(response.headers()[TRACEPARENT].to_str().unwrap()).unwrap();
+    assert_eq!(traceparent.trace_id, TRACE_ID);
+    assert_ne!(traceparent.parent_id, "00f067aa0ba902b7");
+    assert_eq!(traceparent.flags, "01");
+    assert_eq!(body_string(response).await, TRACE_ID);
+}
+
+#[tokio::test]
+async fn test_traceparent_started_without_one() {
+    let app = test_app(CorrelationConfig::default());
+
+    let response = app.oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+
+    assert!(TraceParent::parse(response.headers()[TRACEPARENT].to_str().unwrap()).is_some());
+}
+
+#[test]
+fn test_invalid_traceparents_rejected() {
+    assert!(TraceParent::parse(CLIENT_TRACEPARENT).is_some());
+    assert!(TraceParent::parse("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none());
+    assert!(TraceParent::parse("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+    assert!(TraceParent::parse("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01").is_none());
+    assert!(TraceParent::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7").is_none());
+}
+
+#[tokio::test]
+async fn test_malformed_correlation_ids_are_replaced() {
+    let too_long = "a".repeat(129);
+    let malformed: [&[u8]; 5] = [
+        b"id\xff\xfe",
+        b"<script>alert(1)</script>",
+        b"two words",
+        b"\"quoted\"",
+        too_long.as_bytes(),
+    ];
+
+    for value in malformed {
+        let app = test_app(CorrelationConfig::default());
+        let request = Request::get("/")
+            .header("x-correlation-id", axum::http::HeaderValue::from_bytes(value).unwrap())
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        let id = response.headers()["x-correlation-id"].to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&id).is_ok(), "{:?} should be replaced, got {}", value, id);
+        assert_eq!(body_string(response).await, id);
+    }
+}
+
+#[test]
+fn test_well_formed_correlation_ids_are_kept() {
+    for id in ["req-42", "01HZX3K2M4N5P6Q7R8S9T0V1W2", "a9f/QmX+z0k=", "svc.api:1234_5"] {
+        let value = axum::http::HeaderValue::from_static(id);
+        assert_eq!(correlation::accept_client_id(&value).as_deref(), Some(id));
+    }
+}
+
+#[tokio::test]
+async fn test_weird_correlation_ids_still_complete() {
+    let weird: [&[u8]; 4] = [b"id\twith\ttabs", b"\xc3\xa9t\xc3\xa9", b"   ", b"line\\nbreak"];
+
+    for value in weird {
+        // Handlers can read the ID from the request extensions too
+        let app = Router::new()
+            .route("/", get(|Extension(CorrelationId(id)): Extension<CorrelationId>| async move { id }))
+            .layer(axum::middleware::from_fn_with_state(CorrelationConfig::default(), correlation_middleware));
+        let request = Request::get("/")
+            .header("x-correlation-id", HeaderValue::from_bytes(value).unwrap())
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK, "{:?}", value);
+        let id = response.headers()["x-correlation-id"].to_str().unwrap().to_string();
+        assert!(!id.is_empty());
+        assert!(HeaderValue::from_str(&id).is_ok());
+        assert!(TraceParent::parse(response.headers()[TRACEPARENT].to_str().unwrap()).is_some());
+        assert_eq!(body_string(response).await, id);
+    }
+}
@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::config::settings::DatabaseSettings;
+    use aerugo::db::pool_options;
+use aerugo::tenant::TenancyMode;
+    use secrecy::Secret;
+    use std::time::Duration;
+
+    fn database_settings() -> DatabaseSettings {
+        DatabaseSettings {
+            host: "localhost".to_string(),
+            port: 5432,
+            username: "aerugo".to_string(),
+            password: Secret::new("secret".to_string()),
+            database_name: "aerugo_test".to_string(),
+            require_ssl: false,
+            min_connections: 2,
+            max_connections: 8,
+            idle_timeout_secs: 45,
+            max_lifetime_secs: 600,
+            health_check_interval_secs: 30,
+            slow_query_threshold_ms: 500,
+            tenancy_mode: TenancyMode::Shared,
+        }
+    }
+
+    #[test]
+    fn test_connections_are_validated_before_use() {
+        // A stale connection fails the ping and is replaced instead of failing the query
+        assert!(pool_options(&database_settings()).get_test_before_acquire());
+    }
+
+    #[test]
+    fn test_pool_recycling_uses_settings() {
+        let options = pool_options(&database_settings());
+
+        assert_eq!(options.get_idle_timeout(), Some(Duration::from_secs(45)));
+        assert_eq!(options.get_max_lifetime(), Some(Duration::from_secs(600)));
+        assert_eq!(options.get_min_connections(), 2);
+        assert_eq!(options.get_max_connections(), 8);
+    }
+}
@@ -0,0 +1,29 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::config::settings::DeletionMode;
+    use aerugo::handlers::docker_registry_v2::{deletion_response, DELETION_ID};
+    use axum::http::StatusCode;
+
+    #[test]
+    fn test_async_deletion_is_accepted_with_tracking_id() {
+        let response = deletion_response(DeletionMode::Async, "3f1c2a9e-0000-4000-8000-000000000001");
+
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(response.headers()[DELETION_ID], "3f1c2a9e-0000-4000-8000-000000000001");
+    }
+
+    #[test]
+    fn test_sync_deletion_has_no_content() {
+        let response = deletion_response(DeletionMode::Sync, "3f1c2a9e-0000-4000-8000-000000000002");
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(response.headers().get(DELETION_ID).is_none());
+    }
+
+    #[test]
+    fn test_deletion_mode_parsing() {
+        assert_eq!("async".parse::<DeletionMode>(), Ok(DeletionMode::Async));
+        assert_eq!("SYNC".parse::<DeletionMode>(), Ok(DeletionMode::Sync));
+        assert!("later".parse::<DeletionMode>().is_err());
+    }
+}
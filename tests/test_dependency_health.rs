@@ -0,0 +1,62 @@
+// Tests for the aggregated dependency health report
+
+use aerugo::handlers::health::{probe, DependencyReport, DependencyStatus, HealthReport, OverallStatus};
+use aerugo::storage::filesystem::FilesystemStorage;
+use aerugo::storage::Storage;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+
+async fn up(name: &'static str, critical: bool) -> DependencyReport {
+    probe(name, critical, async { Ok(()) }).await
+}
+
+async fn down(name: &'static str, critical: bool) -> DependencyReport {
+    probe(name, critical, async { Err(anyhow::anyhow!("Connection refused")) }).await
+}
+
+#[tokio::test]
+async fn test_all_dependencies_up_is_healthy() {
+    let storage = FilesystemStorage::new(std::env::temp_dir().join(format!("aerugo-health-{}", uuid::Uuid::new_v4())));
+    let storage_report = probe("storage", true, storage.health_check()).await;
+    assert_eq!(storage_report.status, DependencyStatus::Up);
+
+    let report = HealthReport::new(vec![up("database", true).await, up("redis", false).await, storage_report]);
+
+    assert_eq!(report.status, OverallStatus::Healthy);
+    assert_eq!(report.into_response().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_non_critical_dependency_down_is_degraded() {
+    let (database, redis, storage) = tokio::join!(up("database", true), down("redis", false), up("storage", true));
+    let report = HealthReport::new(vec![database, redis, storage]);
+
+    assert_eq!(report.status, OverallStatus::Degraded);
+    let redis = report.dependencies.iter().find(|d| d.name == "redis").unwrap();
+    assert_eq!(redis.status, DependencyStatus::Down);
+    assert_eq!(redis.error.as_deref(), Some("Connection refused"));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["status"], "degraded");
+    assert_eq!(json["dependencies"][1]["status"], "down");
+    assert!(json["dependencies"][0].get("error").is_none());
+    assert_eq!(report.into_response().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_critical_dependency_down_is_down() {
+    let report = HealthReport::new(vec![
+        down("database", true).await,
+        up("redis", false).await,
+        up("storage", true).await,
+    ]);
+
+    assert_eq!(report.status, OverallStatus::Down);
+    assert_eq!(report.into_response().status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn test_disabled_dependency_does_not_degrade() {
+    let report = HealthReport::new(vec![up("database", true).await, DependencyReport::disabled("redis", false)]);
+    assert_eq!(report.status, OverallStatus::Healthy);
+}
@@ -0,0 +1,56 @@
+// Tests for content digest parsing and validation
+
+use aerugo::models::digest::{Digest, DigestError};
+
+const EMPTY_SHA256: &str = "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+#[test]
+fn test_valid_digests_parse() {
+    let digest: Digest = EMPTY_SHA256.parse().unwrap();
+    assert_eq!(digest.algorithm(), "sha256");
+    assert_eq!(digest.hex().len(), 64);
+    assert_eq!(digest.to_string(), EMPTY_SHA256);
+    assert_eq!(digest.blob_key(), format!("blobs/{}", EMPTY_SHA256));
+
+    let sha512 = format!("sha512:{}", "ab".repeat(64));
+    let digest = Digest::parse(&sha512).unwrap();
+    assert_eq!(digest.algorithm(), "sha512");
+    assert_eq!(digest.as_str(), sha512);
+}
+
+#[test]
+fn test_sha256_of_content() {
+    assert_eq!(Digest::sha256(b"").as_str(), EMPTY_SHA256);
+}
+
+#[test]
+fn test_invalid_digests_rejected() {
+    assert_eq!(
+        Digest::parse("e3b0c44298fc1c149afbf4c8996fb924"),
+        Err(DigestError::MissingAlgorithm("e3b0c44298fc1c149afbf4c8996fb924".to_string()))
+    );
+    assert_eq!(
+        Digest::parse("md5:d41d8cd98f00b204e9800998ecf8427e"),
+        Err(DigestError::UnsupportedAlgorithm("md5".to_string()))
+    );
+    assert_eq!(
+        Digest::parse("sha256:abc123"),
+        Err(DigestError::InvalidLength { algorithm: "sha256", expected: 64, actual: 6 })
+    );
+    assert_eq!(
+        Digest::parse(&EMPTY_SHA256.to_uppercase().replacen("SHA256", "sha256", 1)),
+        Err(DigestError::InvalidHex('E'))
+    );
+    assert_eq!(Digest::parse(&format!("sha256:{}", "g".repeat(64))), Err(DigestError::InvalidHex('g')));
+    assert_eq!(Digest::parse("sha256:unknown"), Err(DigestError::InvalidHex('u')));
+}
+
+#[test]
+fn test_serde_round_trip() {
+    let digest = Digest::parse(EMPTY_SHA256).unwrap();
+    let json = serde_json::to_string(&digest).unwrap();
+    assert_eq!(json, format!("\"{}\"", EMPTY_SHA256));
+    assert_eq!(serde_json::from_str::<Digest>(&json).unwrap(), digest);
+
+    assert!(serde_json::from_str::<Digest>("\"sha256:not-a-digest\"").is_err());
+}
@@ -0,0 +1,15 @@
+// Tests for case-insensitive email addresses
+
+use aerugo::models::user::normalize_email;
+
+#[test]
+fn test_casings_normalize_to_one_address() {
+    assert_eq!(normalize_email("User@X.com"), "user@x.com");
+    assert_eq!(normalize_email("user@x.com"), "user@x.com");
+    assert_eq!(normalize_email(" USER@X.COM "), "user@x.com");
+}
+
+#[test]
+fn test_distinct_addresses_stay_distinct() {
+    assert_ne!(normalize_email("user1@x.com"), normalize_email("user2@x.com"));
+}
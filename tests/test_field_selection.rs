@@ -0,0 +1,42 @@
+// Tests for selecting response fields with `?fields=`
+
+use aerugo::utils::fields::{project, FieldsQuery, UnknownFields};
+use serde_json::json;
+
+fn fields(fields: &str) -> FieldsQuery {
+    FieldsQuery { fields: Some(fields.to_string()) }
+}
+
+fn organization() -> serde_json::Value {
+    json!({
+        "id": 7,
+        "name": "acme",
+        "display_name": "Acme Corp",
+        "description": null,
+        "created_at": "2025-01-01T00:00:00Z"
+    })
+}
+
+#[test]
+fn test_subset_projection() {
+    let projected = project(&organization(), &fields("id, name,description"), false).unwrap();
+
+    assert_eq!(projected, json!({ "id": 7, "name": "acme", "description": null }));
+}
+
+#[test]
+fn test_without_fields_everything_is_returned() {
+    assert_eq!(project(&organization(), &FieldsQuery::default(), true).unwrap(), organization());
+    assert_eq!(project(&organization(), &fields(" , "), true).unwrap(), organization());
+}
+
+#[test]
+fn test_unknown_fields_ignored_unless_strict() {
+    let query = fields("id,email,owner");
+
+    assert_eq!(project(&organization(), &query, false).unwrap(), json!({ "id": 7 }));
+
+    let err = project(&organization(), &query, true).unwrap_err();
+    assert_eq!(err, UnknownFields(vec!["email".to_string(), "owner".to_string()]));
+    assert_eq!(err.to_string(), "Unknown fields: email, owner");
+}
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::config::settings::ServerSettings;
+    use aerugo::security::hsts_header_value;
+
+    fn server_settings(enable_hsts: bool) -> ServerSettings {
+        ServerSettings {
+            bind_address: "127.0.0.1:3000".to_string(),
+            port: 3000,
+            api_prefix: "/api/v1".to_string(),
+            log_level: "info".to_string(),
+            worker_threads: None,
+            max_blocking_threads: None,
+            enable_server_timing: false,
+            enable_hsts,
+            hsts_max_age_secs: 31_536_000,
+            enable_profiling: false,
+            enable_compression: false,
+            correlation_header: "x-correlation-id".to_string(),
+            shutdown_drain_delay_secs: 0,
+            strict_field_selection: false,
+            retry_after_jitter_percent: 0,
+            trusted_proxies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_hsts_only_when_enabled() {
+        assert_eq!(
+            hsts_header_value(&server_settings(true)).unwrap(),
+            "max-age=31536000; includeSubDomains"
+        );
+        assert!(hsts_header_value(&server_settings(false)).is_none());
+    }
+}
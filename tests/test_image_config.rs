@@ -0,0 +1,82 @@
+// Tests for decoding image config blobs for the manifest config endpoint
+
+use aerugo::handlers::docker_registry_v2::{parse_image_config, ImageConfigDetails};
+
+#[test]
+fn test_sample_config_blob_is_decoded() {
+    let blob = serde_json::json!({
+        "architecture": "amd64",
+        "os": "linux",
+        "created": "2025-09-01T12:00:00.123456789Z",
+        "config": {
+            "User": "app",
+            "Env": ["PATH=/usr/local/bin:/usr/bin", "APP_ENV=production"],
+            "Entrypoint": ["/docker-entrypoint.sh"],
+            "Cmd": ["serve", "--port", "8080"],
+            "WorkingDir": "/srv/app",
+            "Labels": {
+                "org.opencontainers.image.source": "https://example.com/app",
+                "maintainer": "team@example.com"
+            }
+        },
+        "rootfs": { "type": "layers", "diff_ids": ["sha256:aaaa"] },
+        "history": [{ "created_by": "COPY . /srv/app" }]
+    });
+
+    let config = parse_image_config(&serde_json::to_vec(&blob).unwrap()).unwrap();
+
+    assert_eq!(config.architecture.as_deref(), Some("amd64"));
+    assert_eq!(config.os.as_deref(), Some("linux"));
+    assert_eq!(config.created.as_deref(), Some("2025-09-01T12:00:00.123456789Z"));
+    assert_eq!(config.entrypoint, vec!["/docker-entrypoint.sh"]);
+    assert_eq!(config.cmd, vec!["serve", "--port", "8080"]);
+    assert_eq!(config.env, vec!["PATH=/usr/local/bin:/usr/bin", "APP_ENV=production"]);
+    assert_eq!(config.working_dir.as_deref(), Some("/srv/app"));
+    assert_eq!(config.user.as_deref(), Some("app"));
+    assert_eq!(config.labels.get("maintainer").map(String::as_str), Some("team@example.com"));
+    assert_eq!(config.labels.len(), 2);
+}
+
+#[test]
+fn test_config_without_container_section_has_empty_fields() {
+    let blob = br#"{"architecture":"arm64","os":"linux","config":{"Env":null,"WorkingDir":""}}"#;
+
+    assert_eq!(
+        parse_image_config(blob),
+        Some(ImageConfigDetails {
+            architecture: Some("arm64".to_string()),
+            os: Some("linux".to_string()),
+            created: None,
+            labels: Default::default(),
+            entrypoint: vec![],
+            cmd: vec![],
+            env: vec![],
+            working_dir: None,
+            user: None,
+        })
+    );
+}
+
+#[test]
+fn test_non_config_blobs_are_rejected() {
+    assert_eq!(parse_image_config(b"not json"), None);
+    assert_eq!(parse_image_config(b"[1, 2, 3]"), None);
+    assert_eq!(parse_image_config(br#"{"config":{"Entrypoint":"not-a-list"}}"#), None);
+}
+
+#[test]
+fn test_response_flattens_config_fields() {
+    let blob = br#"{"architecture":"amd64","os":"linux","config":{"Entrypoint":["/bin/sh"]}}"#;
+    let config = parse_image_config(blob).unwrap();
+
+    let json = serde_json::to_value(aerugo::handlers::docker_registry_v2::ImageConfigResponse {
+        manifest: "sha256:manifest".to_string(),
+        config_digest: "sha256:config".to_string(),
+        config,
+    })
+    .unwrap();
+
+    assert_eq!(json["config_digest"], "sha256:config");
+    assert_eq!(json["architecture"], "amd64");
+    assert_eq!(json["entrypoint"], serde_json::json!(["/bin/sh"]));
+}
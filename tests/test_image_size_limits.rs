@@ -0,0 +1,97 @@
+// Tests for the total image size limit enforced on manifest push
+
+mod utils;
+
+use std::sync::Arc;
+
+use aerugo::handlers::docker_registry_v2::{image_layers_size, ManifestConfigError};
+use aerugo::models::digest::Digest;
+use aerugo::models::manifest_size::{check_image_size, image_size_limit};
+use aerugo::storage::Storage;
+use anyhow::Result;
+use bytes::Bytes;
+use serde_json::json;
+use utils::shared_test_storage;
+
+/// Store `size` bytes as a blob and return its digest
+async fn seed_layer(storage: &Arc<dyn Storage>, byte: u8, size: usize) -> Result<String> {
+    let content = vec![byte; size];
+    let digest = Digest::sha256(&content);
+    storage.put_blob(&digest.blob_key(), Bytes::from(content)).await?;
+    Ok(digest.as_str().to_string())
+}
+
+fn image_manifest(layers: &[(&str, u64)]) -> serde_json::Value {
+    json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.manifest.v1+json",
+        "config": { "mediaType": "application/vnd.oci.image.config.v1+json", "digest": "sha256:0", "size": 2 },
+        "layers": layers
+            .iter()
+            .map(|(digest, size)| json!({
+                "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip",
+                "digest": digest,
+                "size": size
+            }))
+            .collect::<Vec<_>>()
+    })
+}
+
+#[tokio::test]
+async fn test_image_under_limit_accepted() -> Result<()> {
+    let storage = shared_test_storage("under");
+    let first = seed_layer(&storage, 1, 3000).await?;
+    let second = seed_layer(&storage, 2, 1000).await?;
+    let manifest = image_manifest(&[(&first, 3000), (&second, 1000)]);
+
+    let total = image_layers_size(storage.as_ref(), &manifest).await?;
+    assert_eq!(total, 4000);
+    assert!(check_image_size(total, Some(4000)).is_ok());
+    assert!(check_image_size(total, None).is_ok());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_image_over_limit_refused() -> Result<()> {
+    let storage = shared_test_storage("over");
+    let first = seed_layer(&storage, 1, 3000).await?;
+    let second = seed_layer(&storage, 2, 1000).await?;
+    // Descriptors understating their size do not get an image under the limit
+    let manifest = image_manifest(&[(&first, 10), (&second, 10)]);
+
+    let total = image_layers_size(storage.as_ref(), &manifest).await?;
+    assert_eq!(total, 4000);
+    let message = check_image_size(total, Some(3999)).unwrap_err();
+    assert!(message.contains("4000"), "{}", message);
+    assert!(message.contains("3999"), "{}", message);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unstored_layers_refused() -> Result<()> {
+    let storage = shared_test_storage("unstored");
+    let stored = seed_layer(&storage, 1, 1000).await?;
+    let missing = Digest::sha256(b"uploaded later").as_str().to_string();
+    // A declared size cannot stand in for a blob that is not there yet
+    let manifest = image_manifest(&[(&stored, 1000), (&missing, 1)]);
+    let error = image_layers_size(storage.as_ref(), &manifest).await.unwrap_err();
+    assert_eq!(error, ManifestConfigError::BlobUnknown(missing.clone()));
+
+    // Foreign layers are served from their URLs and count as declared
+    let mut manifest = image_manifest(&[(&stored, 1000), (&missing, 2048)]);
+    manifest["layers"][1]["urls"] = json!(["https://example.com/layer.tar.gz"]);
+    assert_eq!(image_layers_size(storage.as_ref(), &manifest).await?, 3048);
+
+    // Indexes reference manifests, not layers
+    let index = json!({ "schemaVersion": 2, "manifests": [{ "digest": missing, "size": 2048 }] });
+    assert_eq!(image_layers_size(storage.as_ref(), &index).await?, 0);
+    Ok(())
+}
+
+#[test]
+fn test_organization_limit_takes_precedence() {
+    assert_eq!(image_size_limit(None, None), None);
+    assert_eq!(image_size_limit(None, Some(1000)), Some(1000));
+    assert_eq!(image_size_limit(Some(5000), Some(1000)), Some(5000));
+    assert_eq!(image_size_limit(Some(500), None), Some(500));
+}
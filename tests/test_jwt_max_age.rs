@@ -0,0 +1,46 @@
+// Tests for rejecting JWTs older than JWT_MAX_AGE_SECONDS
+
+use aerugo::auth::{token_within_max_age, verify_token};
+use aerugo::handlers::auth::Claims;
+use jsonwebtoken::{encode, EncodingKey, Header};
+
+const SECRET: &[u8] = b"max-age-test-secret";
+const HOUR: i64 = 3600;
+
+fn token_issued(seconds_ago: Option<i64>) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = Claims {
+        sub: "42".to_string(),
+        exp: (now + 24 * HOUR) as usize,
+        iat: seconds_ago.map(|ago| (now - ago) as usize),
+        sid: None,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+}
+
+fn accepted(token: &str, max_age: Option<u64>) -> bool {
+    verify_token(token, SECRET, max_age).is_ok()
+}
+
+#[test]
+fn test_token_within_max_age_accepted() {
+    assert!(accepted(&token_issued(Some(60)), Some(HOUR as u64)));
+}
+
+#[test]
+fn test_old_but_unexpired_token_rejected() {
+    let token = token_issued(Some(2 * HOUR));
+
+    assert!(!accepted(&token, Some(HOUR as u64)));
+    // Without a limit only exp matters
+    assert!(accepted(&token, None));
+}
+
+#[test]
+fn test_token_without_iat_rejected_only_when_limited() {
+    assert!(!accepted(&token_issued(None), Some(HOUR as u64)));
+    assert!(accepted(&token_issued(None), None));
+
+    assert!(!token_within_max_age(None, Some(HOUR as u64), 0));
+    assert!(token_within_max_age(None, None, 0));
+}
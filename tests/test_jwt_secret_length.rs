@@ -0,0 +1,58 @@
+// Tests for the minimum JWT secret length checked at startup
+#[cfg(test)]
+mod tests {
+    use aerugo::auth_events::AuthEventDestination;
+    use aerugo::config::settings::{validate_jwt_secret, AuthSettings, DEFAULT_JWT_MIN_SECRET_BYTES};
+    use jsonwebtoken::Algorithm;
+    use secrecy::Secret;
+    use validator::Validate;
+
+    fn auth_settings(secret: &str, min_bytes: usize) -> AuthSettings {
+        AuthSettings {
+            jwt_secret: Secret::new(secret.to_string()),
+            jwt_min_secret_bytes: min_bytes,
+            jwt_expiration_seconds: 3600,
+            refresh_token_expiration_seconds: 604800,
+            jwt_max_age_seconds: None,
+            introspection_secret: None,
+            allow_self_registration: false,
+            reserved_usernames: Vec::new(),
+            require_request_nonce: false,
+            auth_event_destination: AuthEventDestination::Log,
+            admin_token: None,
+            login_challenge_threshold: 5,
+            login_challenge_window_secs: 900,
+            captcha_verify_url: None,
+            captcha_secret: None,
+        }
+    }
+
+    #[test]
+    fn test_short_secret_rejected() {
+        let err = validate_jwt_secret(Algorithm::HS256, "your-super-secret-key", 32).unwrap_err();
+        assert_eq!(err.code, "jwt_secret_too_short");
+        let message = err.message.unwrap();
+        assert!(message.contains("21 bytes"), "{}", message);
+        assert!(message.contains("at least 32"), "{}", message);
+
+        assert!(auth_settings("short", DEFAULT_JWT_MIN_SECRET_BYTES).validate().is_err());
+    }
+
+    #[test]
+    fn test_sufficient_secret_accepted() {
+        let secret = "0123456789abcdef0123456789abcdef";
+        assert_eq!(validate_jwt_secret(Algorithm::HS256, secret, 32), Ok(()));
+        assert!(auth_settings(secret, DEFAULT_JWT_MIN_SECRET_BYTES).validate().is_ok());
+    }
+
+    #[test]
+    fn test_asymmetric_algorithms_skip_the_check() {
+        assert_eq!(validate_jwt_secret(Algorithm::RS256, "", 32), Ok(()));
+        assert_eq!(validate_jwt_secret(Algorithm::ES256, "short", 32), Ok(()));
+    }
+
+    #[test]
+    fn test_zero_minimum_disables_the_check() {
+        assert!(auth_settings("short", 0).validate().is_ok());
+    }
+}
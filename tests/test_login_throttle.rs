@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::login_throttle::{client_ip, ChallengeVerifier, LoginGate, LoginThrottle};
+    use async_trait::async_trait;
+    use axum::http::{HeaderMap, HeaderValue};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const IP: &str = "203.0.113.7";
+
+    /// Accepts only "valid-token", standing in for a CAPTCHA service
+    struct StubVerifier;
+
+    #[async_trait]
+    impl ChallengeVerifier for StubVerifier {
+        async fn verify(&self, token: &str, _client_ip: &str) -> bool {
+            token == "valid-token"
+        }
+    }
+
+    fn throttle(threshold: u32) -> LoginThrottle {
+        LoginThrottle::new(threshold, Duration::from_secs(900), Some(Arc::new(StubVerifier)))
+    }
+
+    fn fail(throttle: &LoginThrottle, times: u32) {
+        for _ in 0..times {
+            throttle.record_failure(IP);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_challenge_below_threshold() {
+        let throttle = throttle(3);
+        fail(&throttle, 2);
+        assert_eq!(throttle.check(IP, None).await, LoginGate::Allowed);
+    }
+
+    #[tokio::test]
+    async fn test_challenge_required_at_threshold() {
+        let throttle = throttle(3);
+        fail(&throttle, 3);
+
+        assert_eq!(throttle.check(IP, None).await, LoginGate::ChallengeRequired);
+        assert_eq!(throttle.check(IP, Some("")).await, LoginGate::ChallengeRequired);
+        // Other addresses are unaffected
+        assert_eq!(throttle.check("198.51.100.1", None).await, LoginGate::Allowed);
+    }
+
+    #[tokio::test]
+    async fn test_valid_challenge_allows_login() {
+        let throttle = throttle(3);
+        fail(&throttle, 3);
+
+        assert_eq!(throttle.check(IP, Some("valid-token")).await, LoginGate::Allowed);
+
+        // Logging in successfully clears the failures
+        throttle.record_success(IP);
+        assert_eq!(throttle.failure_count(IP), 0);
+        assert_eq!(throttle.check(IP, None).await, LoginGate::Allowed);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_challenge_rejected() {
+        let throttle = throttle(3);
+        fail(&throttle, 3);
+        assert_eq!(throttle.check(IP, Some("forged")).await, LoginGate::ChallengeFailed);
+    }
+
+    #[tokio::test]
+    async fn test_failures_expire_after_window() {
+        let throttle = LoginThrottle::new(1, Duration::from_millis(20), Some(Arc::new(StubVerifier)));
+        fail(&throttle, 1);
+        assert_eq!(throttle.check(IP, None).await, LoginGate::ChallengeRequired);
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(throttle.check(IP, None).await, LoginGate::Allowed);
+    }
+
+    #[tokio::test]
+    async fn test_no_challenge_without_verifier() {
+        let throttle = LoginThrottle::new(1, Duration::from_secs(900), None);
+        fail(&throttle, 10);
+        assert_eq!(throttle.check(IP, None).await, LoginGate::Allowed);
+    }
+
+    #[test]
+    fn test_forwarded_for_ignored_from_untrusted_peer() {
+        let peer = Some("198.51.100.20:5000".parse().unwrap());
+        assert_eq!(client_ip(&HeaderMap::new(), peer, &[]), "198.51.100.20");
+
+        // A client cannot pick its own address by sending the header directly
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", HeaderValue::from_static("203.0.113.7"));
+        assert_eq!(client_ip(&headers, peer, &[]), "198.51.100.20");
+        assert_eq!(client_ip(&headers, peer, &["10.0.0.1".parse().unwrap()]), "198.51.100.20");
+        assert_eq!(client_ip(&headers, None, &[]), "unknown");
+    }
+
+    #[test]
+    fn test_forwarded_for_read_behind_trusted_proxy() {
+        let proxies: Vec<std::net::IpAddr> = vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()];
+        let peer = Some("10.0.0.1:5000".parse().unwrap());
+
+        // The right-most untrusted entry is the client; earlier ones are client-supplied
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", HeaderValue::from_static("192.0.2.66, 203.0.113.7, 10.0.0.2"));
+        assert_eq!(client_ip(&headers, peer, &proxies), IP);
+
+        // Without a usable entry the proxy itself is reported
+        assert_eq!(client_ip(&HeaderMap::new(), peer, &proxies), "10.0.0.1");
+        headers.insert("X-Forwarded-For", HeaderValue::from_static("not-an-ip"));
+        assert_eq!(client_ip(&headers, peer, &proxies), "10.0.0.1");
+    }
+}
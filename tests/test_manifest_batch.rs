@@ -0,0 +1,72 @@
+// Tests for fetching several manifests in one request
+#[cfg(test)]
+mod tests {
+    use aerugo::handlers::docker_registry_v2::{
+        manifest_batch_entry, validate_manifest_batch, ManifestBatchError, MAX_MANIFEST_BATCH,
+    };
+
+    const OCI_MANIFEST: &str = r#"{"schemaVersion":2,"mediaType":"application/vnd.oci.image.manifest.v1+json","config":{},"layers":[]}"#;
+    const INDEX: &str = r#"{"schemaVersion":2,"manifests":[]}"#;
+
+    fn unknown(reference: &str) -> ManifestBatchError {
+        ManifestBatchError {
+            status: 404,
+            code: "MANIFEST_UNKNOWN".to_string(),
+            message: format!("manifest unknown: {}", reference),
+        }
+    }
+
+    fn references(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("v{}", i)).collect()
+    }
+
+    #[test]
+    fn test_batch_size_is_bounded() {
+        assert!(validate_manifest_batch(&references(1)).is_ok());
+        assert!(validate_manifest_batch(&references(MAX_MANIFEST_BATCH)).is_ok());
+        assert!(validate_manifest_batch(&[]).is_err());
+        assert!(validate_manifest_batch(&references(MAX_MANIFEST_BATCH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_mixed_batch_keeps_order_with_per_entry_errors() {
+        let resolved = vec![
+            ("latest", Ok(("sha256:aaa".to_string(), OCI_MANIFEST.to_string()))),
+            ("missing", Err(unknown("missing"))),
+            ("sha256:bbb", Ok(("sha256:bbb".to_string(), INDEX.to_string()))),
+        ];
+        let entries: Vec<_> = resolved
+            .into_iter()
+            .map(|(reference, result)| manifest_batch_entry(reference.to_string(), result))
+            .collect();
+
+        let references: Vec<_> = entries.iter().map(|e| e.reference.as_str()).collect();
+        assert_eq!(references, vec!["latest", "missing", "sha256:bbb"]);
+
+        assert_eq!(entries[0].digest.as_deref(), Some("sha256:aaa"));
+        assert_eq!(entries[0].media_type.as_deref(), Some("application/vnd.oci.image.manifest.v1+json"));
+        assert_eq!(entries[0].manifest.as_deref(), Some(OCI_MANIFEST));
+        assert!(entries[0].error.is_none());
+
+        assert_eq!(entries[1].error, Some(unknown("missing")));
+        assert!(entries[1].digest.is_none() && entries[1].manifest.is_none());
+
+        assert_eq!(entries[2].media_type.as_deref(), Some("application/vnd.oci.image.index.v1+json"));
+    }
+
+    #[test]
+    fn test_entry_serialization_omits_absent_fields() {
+        let found = serde_json::to_value(manifest_batch_entry(
+            "latest".to_string(),
+            Ok(("sha256:aaa".to_string(), OCI_MANIFEST.to_string())),
+        ))
+        .unwrap();
+        assert!(found.get("error").is_none());
+        assert_eq!(found["manifest"], OCI_MANIFEST);
+
+        let missing = serde_json::to_value(manifest_batch_entry("missing".to_string(), Err(unknown("missing")))).unwrap();
+        assert!(missing.get("manifest").is_none());
+        assert_eq!(missing["error"]["code"], "MANIFEST_UNKNOWN");
+        assert_eq!(missing["error"]["status"], 404);
+    }
+}
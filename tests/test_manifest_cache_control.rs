@@ -0,0 +1,30 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::handlers::docker_registry_v2::manifest_cache_control;
+
+    const DIGEST: &str = "sha256:4f53cda18c2baa0c0354bb5f9a3ecbe5ed12ab4d8e11ba873c2f11161202b945";
+
+    #[test]
+    fn test_digest_reference_is_immutable() {
+        assert_eq!(manifest_cache_control(DIGEST, 0), "public, max-age=31536000, immutable");
+        // The tag max-age setting never applies to digests
+        assert_eq!(manifest_cache_control(DIGEST, 60), "public, max-age=31536000, immutable");
+    }
+
+    #[test]
+    fn test_tag_reference_is_revalidated_by_default() {
+        assert_eq!(manifest_cache_control("latest", 0), "no-cache");
+        assert_eq!(manifest_cache_control("v1.2.3", 0), "no-cache");
+    }
+
+    #[test]
+    fn test_tag_reference_uses_configured_max_age() {
+        assert_eq!(manifest_cache_control("latest", 60), "public, max-age=60");
+    }
+
+    #[test]
+    fn test_malformed_digest_is_treated_as_tag() {
+        assert_eq!(manifest_cache_control("sha256:", 0), "no-cache");
+        assert_eq!(manifest_cache_control("sha256:not-hex", 0), "no-cache");
+    }
+}
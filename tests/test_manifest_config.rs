@@ -0,0 +1,166 @@
+// Tests for config and layer blob verification on manifest push and tagging
+
+mod utils;
+
+use aerugo::handlers::docker_registry_v2::{
+    image_layers_size, verify_manifest_blobs, verify_manifest_config, ImagePlatform, ManifestConfigError,
+};
+use aerugo::models::digest::Digest;
+use aerugo::storage::filesystem::FilesystemStorage;
+use aerugo::storage::Storage;
+use anyhow::Result;
+use bytes::Bytes;
+use utils::test_storage;
+
+/// Digest standing for the blob named `name`
+fn digest(name: &str) -> String {
+    Digest::sha256(name.as_bytes()).as_str().to_string()
+}
+
+/// Store `content` under the digest of `name`
+async fn put(storage: &FilesystemStorage, name: &str, content: &'static [u8]) -> Result<()> {
+    storage.put_blob(&format!("blobs/{}", digest(name)), Bytes::from_static(content)).await?;
+    Ok(())
+}
+
+fn manifest_with_config(config_digest: &str) -> String {
+    serde_json::json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.docker.distribution.manifest.v2+json",
+        "config": {
+            "mediaType": "application/vnd.docker.container.image.v1+json",
+            "size": 0,
+            "digest": config_digest
+        },
+        "layers": []
+    })
+    .to_string()
+}
+
+#[tokio::test]
+async fn test_missing_config_blob_is_rejected() -> Result<()> {
+    let storage = test_storage("missing");
+    let manifest = manifest_with_config(&digest("missing"));
+
+    let result = verify_manifest_config(&storage, &manifest).await;
+
+    assert_eq!(result, Err(ManifestConfigError::BlobUnknown(digest("missing"))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_valid_config_records_platform() -> Result<()> {
+    let storage = test_storage("valid");
+    let config = serde_json::json!({
+        "architecture": "arm64",
+        "os": "linux",
+        "rootfs": { "type": "layers", "diff_ids": [] }
+    });
+    storage
+        .put_blob(&format!("blobs/{}", digest("config")), Bytes::from(serde_json::to_vec(&config)?))
+        .await?;
+
+    let platform = verify_manifest_config(&storage, &manifest_with_config(&digest("config"))).await;
+
+    assert_eq!(
+        platform,
+        Ok(Some(ImagePlatform {
+            architecture: "arm64".to_string(),
+            os: "linux".to_string(),
+        }))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_manifest_list_has_no_config() -> Result<()> {
+    let storage = test_storage("index");
+    let index = serde_json::json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.index.v1+json",
+        "manifests": []
+    })
+    .to_string();
+
+    assert_eq!(verify_manifest_config(&storage, &index).await, Ok(None));
+    Ok(())
+}
+
+fn manifest_with_layers(config_digest: &str, layers: serde_json::Value) -> String {
+    let mut manifest: serde_json::Value = serde_json::from_str(&manifest_with_config(config_digest)).unwrap();
+    manifest["layers"] = layers;
+    manifest.to_string()
+}
+
+#[tokio::test]
+async fn test_rollback_to_manifest_with_collected_layer_is_rejected() -> Result<()> {
+    let storage = test_storage("rollback");
+    put(&storage, "config", b"{}").await?;
+    put(&storage, "base", b"base").await?;
+    put(&storage, "app", b"app").await?;
+    let manifest = manifest_with_layers(
+        &digest("config"),
+        serde_json::json!([{ "digest": digest("base") }, { "digest": digest("app") }]),
+    );
+    assert_eq!(verify_manifest_blobs(&storage, &manifest).await, Ok(()));
+
+    // Garbage collection removed a layer after the tag moved on
+    storage.delete_blob(&format!("blobs/{}", digest("app"))).await?;
+
+    assert_eq!(
+        verify_manifest_blobs(&storage, &manifest).await,
+        Err(ManifestConfigError::BlobUnknown(digest("app")))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_collected_config_blob_is_reported() -> Result<()> {
+    let storage = test_storage("collected-config");
+    let manifest = manifest_with_layers(&digest("config"), serde_json::json!([]));
+
+    assert_eq!(
+        verify_manifest_blobs(&storage, &manifest).await,
+        Err(ManifestConfigError::BlobUnknown(digest("config")))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_foreign_layers_are_not_checked() -> Result<()> {
+    let storage = test_storage("foreign");
+    put(&storage, "config", b"{}").await?;
+    let manifest = manifest_with_layers(
+        &digest("config"),
+        serde_json::json!([{ "digest": digest("windows-base"), "urls": ["https://example.com/layer"] }]),
+    );
+
+    assert_eq!(verify_manifest_blobs(&storage, &manifest).await, Ok(()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_descriptor_digests_cannot_leave_the_storage_root() -> Result<()> {
+    let storage = test_storage("escape");
+    put(&storage, "config", b"{}").await?;
+    // Joined onto the storage root, this would name a path outside it
+    let escape = "sha256:../../../../etc/passwd";
+
+    let manifest = manifest_with_config(escape);
+    assert!(matches!(
+        verify_manifest_config(&storage, &manifest).await,
+        Err(ManifestConfigError::InvalidDigest(_))
+    ));
+
+    let manifest = manifest_with_layers(&digest("config"), serde_json::json!([{ "digest": escape, "size": 2 }]));
+    assert!(matches!(
+        verify_manifest_blobs(&storage, &manifest).await,
+        Err(ManifestConfigError::InvalidDigest(_))
+    ));
+    let manifest: serde_json::Value = serde_json::from_str(&manifest)?;
+    assert!(matches!(
+        image_layers_size(&storage, &manifest).await,
+        Err(ManifestConfigError::InvalidDigest(_))
+    ));
+    Ok(())
+}
@@ -0,0 +1,88 @@
+// Tests for comparing two image manifests
+
+use aerugo::handlers::docker_registry_v2::{diff_manifests, LayerChange};
+use serde_json::json;
+
+fn image_manifest(config: &str, layers: &[(&str, u64)]) -> String {
+    let layers: Vec<_> = layers
+        .iter()
+        .map(|(digest, size)| {
+            json!({
+                "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip",
+                "size": size,
+                "digest": digest
+            })
+        })
+        .collect();
+
+    json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.manifest.v1+json",
+        "config": {
+            "mediaType": "application/vnd.oci.image.config.v1+json",
+            "size": 100,
+            "digest": config
+        },
+        "layers": layers
+    })
+    .to_string()
+}
+
+fn layer(digest: &str, size: u64) -> LayerChange {
+    LayerChange { digest: digest.to_string(), size }
+}
+
+#[test]
+fn test_diff_of_manifests_sharing_base_layers() {
+    let from = image_manifest("sha256:config-a", &[("sha256:base", 1000), ("sha256:deps", 500), ("sha256:app-v1", 200)]);
+    let to = image_manifest("sha256:config-b", &[("sha256:base", 1000), ("sha256:deps", 500), ("sha256:app-v2", 350), ("sha256:assets", 50)]);
+
+    let diff = diff_manifests(&from, &to).unwrap();
+
+    assert_eq!(diff.layers_added, vec![layer("sha256:app-v2", 350), layer("sha256:assets", 50)]);
+    assert_eq!(diff.layers_removed, vec![layer("sha256:app-v1", 200)]);
+    assert_eq!(diff.layers_shared, 2);
+    assert!(diff.config_changed);
+    assert_eq!(diff.from_config.as_deref(), Some("sha256:config-a"));
+    assert_eq!(diff.to_config.as_deref(), Some("sha256:config-b"));
+    assert_eq!(diff.size_delta, 200);
+}
+
+#[test]
+fn test_diff_of_identical_manifests_is_empty() {
+    let manifest = image_manifest("sha256:config", &[("sha256:base", 1000), ("sha256:app", 200)]);
+
+    let diff = diff_manifests(&manifest, &manifest).unwrap();
+
+    assert!(diff.layers_added.is_empty());
+    assert!(diff.layers_removed.is_empty());
+    assert_eq!(diff.layers_shared, 2);
+    assert!(!diff.config_changed);
+    assert_eq!(diff.size_delta, 0);
+}
+
+#[test]
+fn test_diff_to_smaller_image_has_negative_delta() {
+    let from = image_manifest("sha256:config", &[("sha256:base", 1000), ("sha256:debug-tools", 800)]);
+    let to = image_manifest("sha256:config", &[("sha256:base", 1000)]);
+
+    let diff = diff_manifests(&from, &to).unwrap();
+
+    assert_eq!(diff.layers_removed, vec![layer("sha256:debug-tools", 800)]);
+    assert!(!diff.config_changed);
+    assert_eq!(diff.size_delta, -800);
+}
+
+#[test]
+fn test_index_cannot_be_compared() {
+    let index = json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.index.v1+json",
+        "manifests": [{ "digest": "sha256:amd64", "size": 500 }]
+    })
+    .to_string();
+    let manifest = image_manifest("sha256:config", &[("sha256:base", 1000)]);
+
+    assert!(diff_manifests(&index, &manifest).is_none());
+    assert!(diff_manifests(&manifest, "not json").is_none());
+}
@@ -0,0 +1,44 @@
+// Tests for refusing to overwrite stored manifests with different content
+
+mod utils;
+
+use aerugo::handlers::docker_registry_v2::stored_manifest_matches;
+use aerugo::models::digest::Digest;
+use aerugo::storage::Storage;
+use anyhow::Result;
+use bytes::Bytes;
+use utils::test_storage;
+
+const MANIFEST: &str = r#"{"schemaVersion":2,"layers":[]}"#;
+
+#[tokio::test]
+async fn test_first_push_matches() -> Result<()> {
+    let storage = test_storage("first");
+    let digest = Digest::sha256(MANIFEST.as_bytes());
+
+    assert!(stored_manifest_matches(&storage, &digest, MANIFEST.as_bytes()).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_identical_re_push_matches() -> Result<()> {
+    let storage = test_storage("re-push");
+    let digest = Digest::sha256(MANIFEST.as_bytes());
+    storage.put_blob(&digest.blob_key(), Bytes::from_static(MANIFEST.as_bytes())).await?;
+
+    assert!(stored_manifest_matches(&storage, &digest, MANIFEST.as_bytes()).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_different_stored_content_does_not_match() -> Result<()> {
+    let storage = test_storage("mismatch");
+    let digest = Digest::sha256(MANIFEST.as_bytes());
+    // Simulate corruption: other bytes stored under the manifest's digest
+    storage
+        .put_blob(&digest.blob_key(), Bytes::from_static(br#"{"schemaVersion":2,"layers":[{}]}"#))
+        .await?;
+
+    assert!(!stored_manifest_matches(&storage, &digest, MANIFEST.as_bytes()).await?);
+    Ok(())
+}
@@ -0,0 +1,79 @@
+// Tests for manifest layer list validation on push
+
+use aerugo::handlers::docker_registry_v2::{validate_manifest_layers, ManifestLayersError};
+use serde_json::json;
+
+fn image_manifest(layer_digests: &[&str]) -> String {
+    let layers: Vec<_> = layer_digests
+        .iter()
+        .map(|digest| {
+            json!({
+                "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip",
+                "size": 1024,
+                "digest": digest
+            })
+        })
+        .collect();
+
+    json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.manifest.v1+json",
+        "config": {
+            "mediaType": "application/vnd.oci.image.config.v1+json",
+            "size": 2,
+            "digest": "sha256:config"
+        },
+        "layers": layers
+    })
+    .to_string()
+}
+
+#[test]
+fn test_duplicate_layer_rejected() {
+    let manifest = image_manifest(&["sha256:aaa", "sha256:bbb", "sha256:aaa"]);
+
+    assert_eq!(
+        validate_manifest_layers(&manifest, false),
+        Err(ManifestLayersError::DuplicateLayer("sha256:aaa".to_string()))
+    );
+}
+
+#[test]
+fn test_single_layer_accepted() {
+    let manifest = image_manifest(&["sha256:aaa"]);
+
+    assert_eq!(validate_manifest_layers(&manifest, false), Ok(()));
+    assert_eq!(validate_manifest_layers(&manifest, true), Ok(()));
+}
+
+#[test]
+fn test_empty_layers_rejected_only_when_configured() {
+    let manifest = image_manifest(&[]);
+
+    assert_eq!(validate_manifest_layers(&manifest, false), Ok(()));
+    assert_eq!(validate_manifest_layers(&manifest, true), Err(ManifestLayersError::NoLayers));
+}
+
+#[test]
+fn test_layer_without_digest_rejected() {
+    let manifest = json!({
+        "schemaVersion": 2,
+        "config": { "digest": "sha256:config" },
+        "layers": [{ "digest": "sha256:aaa" }, { "size": 10 }]
+    })
+    .to_string();
+
+    assert_eq!(validate_manifest_layers(&manifest, false), Err(ManifestLayersError::MissingDigest(1)));
+}
+
+#[test]
+fn test_index_is_not_checked() {
+    let index = json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.index.v1+json",
+        "manifests": []
+    })
+    .to_string();
+
+    assert_eq!(validate_manifest_layers(&index, true), Ok(()));
+}
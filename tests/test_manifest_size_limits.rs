@@ -0,0 +1,100 @@
+// Tests for the per artifact type manifest size limits enforced on push
+#[cfg(test)]
+mod tests {
+    use aerugo::models::manifest_size::{artifact_type, ManifestSizeLimits, DEFAULT_MANIFEST_MAX_BYTES};
+    use serde_json::json;
+
+    const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+    const NOTARY_SIGNATURE: &str = "application/vnd.cncf.notary.signature";
+
+    /// A signature manifest as notation pushes it, padded to `size` bytes
+    /// with an annotation
+    fn signature_manifest(size: usize) -> String {
+        let manifest = |padding: &str| {
+            json!({
+                "schemaVersion": 2,
+                "mediaType": OCI_MANIFEST,
+                "artifactType": NOTARY_SIGNATURE,
+                "config": { "mediaType": "application/vnd.oci.empty.v1+json", "digest": "sha256:44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a", "size": 2 },
+                "layers": [],
+                "annotations": { "padding": padding }
+            })
+            .to_string()
+        };
+        let base = manifest("").len();
+        manifest(&"x".repeat(size - base))
+    }
+
+    #[test]
+    fn test_oversized_signature_manifest_rejected() {
+        let limits = ManifestSizeLimits::default();
+        let body = signature_manifest(100 * 1024);
+        let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        let artifact = artifact_type(&value, OCI_MANIFEST);
+        assert_eq!(artifact, NOTARY_SIGNATURE);
+        let error = limits.check(&artifact, body.len()).unwrap_err();
+        assert!(error.contains(NOTARY_SIGNATURE), "{}", error);
+
+        // A normal sized signature is fine
+        assert!(limits.check(&artifact, signature_manifest(2048).len()).is_ok());
+    }
+
+    #[test]
+    fn test_normal_image_manifest_accepted() {
+        let limits = ManifestSizeLimits::default();
+        let manifest = json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_MANIFEST,
+            "config": { "mediaType": "application/vnd.oci.image.config.v1+json", "digest": "sha256:aaa", "size": 1469 },
+            "layers": [{ "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "digest": "sha256:bbb", "size": 3370706 }]
+        });
+        let body = manifest.to_string();
+
+        let artifact = artifact_type(&manifest, OCI_MANIFEST);
+        assert_eq!(artifact, OCI_MANIFEST);
+        assert!(limits.check(&artifact, body.len()).is_ok());
+        // Image manifests may be far larger than signatures
+        assert!(limits.check(&artifact, 1024 * 1024).is_ok());
+        assert!(limits.check(&artifact, 4 * 1024 * 1024 + 1).is_err());
+    }
+
+    #[test]
+    fn test_artifact_type_from_config_media_type() {
+        let helm_chart = json!({
+            "schemaVersion": 2,
+            "config": { "mediaType": "application/vnd.cncf.helm.config.v1+json" },
+            "layers": []
+        });
+        assert_eq!(artifact_type(&helm_chart, OCI_MANIFEST), "application/vnd.cncf.helm.config.v1+json");
+
+        let index = json!({ "schemaVersion": 2, "manifests": [] });
+        assert_eq!(
+            artifact_type(&index, "application/vnd.oci.image.index.v1+json"),
+            "application/vnd.oci.image.index.v1+json"
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let limits = ManifestSizeLimits::default()
+            .with_overrides("application/vnd.cncf.notary.signature=1024, default=2048, Application/Vnd.Acme.Model=10")
+            .unwrap();
+        assert_eq!(limits.limit_for(NOTARY_SIGNATURE), 1024);
+        assert_eq!(limits.limit_for("application/vnd.acme.model"), 10);
+        assert_eq!(limits.limit_for("application/vnd.unknown+json"), 2048);
+        // Built-in limits not overridden are kept
+        assert_eq!(limits.limit_for("application/vnd.oci.image.index.v1+json"), 1024 * 1024);
+
+        assert_eq!(ManifestSizeLimits::default().limit_for("application/x-unknown"), DEFAULT_MANIFEST_MAX_BYTES);
+        assert!(ManifestSizeLimits::default().with_overrides("").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        assert!(ManifestSizeLimits::default().with_overrides("application/vnd.acme").is_err());
+        assert!(ManifestSizeLimits::default().with_overrides("application/vnd.acme=big").is_err());
+        assert!(ManifestSizeLimits::default().with_overrides("application/vnd.acme=0").is_err());
+        assert!(ManifestSizeLimits::default().with_overrides("signature=1024").is_err());
+    }
+}
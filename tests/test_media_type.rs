@@ -0,0 +1,118 @@
+// Tests for media type parsing and manifest content negotiation
+
+use aerugo::handlers::docker_registry_v2::{manifest_media_type, manifest_push_media_type};
+use aerugo::models::media_type::{MediaType, MediaTypeError};
+use serde_json::json;
+
+const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+const OCI_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+const DOCKER_MANIFEST: &str = "application/vnd.docker.distribution.manifest.v2+json";
+
+#[test]
+fn test_known_media_types_parse() {
+    assert_eq!(OCI_MANIFEST.parse(), Ok(MediaType::OciManifest));
+    assert_eq!(OCI_INDEX.parse(), Ok(MediaType::OciIndex));
+    assert_eq!(DOCKER_MANIFEST.parse(), Ok(MediaType::DockerManifest));
+    assert_eq!(
+        "application/vnd.docker.distribution.manifest.list.v2+json".parse(),
+        Ok(MediaType::DockerManifestList)
+    );
+    assert_eq!(
+        "application/vnd.oci.image.layer.v1.tar+gzip".parse(),
+        Ok(MediaType::OciLayerGzip)
+    );
+}
+
+#[test]
+fn test_parameters_and_case_are_ignored() {
+    assert_eq!(
+        "Application/VND.OCI.Image.Manifest.v1+JSON; charset=utf-8".parse(),
+        Ok(MediaType::OciManifest)
+    );
+    assert_eq!(" application/json ".parse(), Ok(MediaType::Other("application/json".to_string())));
+}
+
+#[test]
+fn test_unknown_media_types_round_trip() {
+    let artifact: MediaType = "application/vnd.cncf.helm.config.v1+json".parse().unwrap();
+    assert_eq!(artifact, MediaType::Other("application/vnd.cncf.helm.config.v1+json".to_string()));
+    assert_eq!(artifact.to_string(), "application/vnd.cncf.helm.config.v1+json");
+    assert!(!artifact.is_manifest());
+    assert!(!artifact.is_index());
+    assert!(!artifact.is_blob());
+}
+
+#[test]
+fn test_display_round_trips_known_types() {
+    for media_type in [OCI_MANIFEST, OCI_INDEX, DOCKER_MANIFEST] {
+        assert_eq!(media_type.parse::<MediaType>().unwrap().to_string(), media_type);
+    }
+}
+
+#[test]
+fn test_malformed_media_types_rejected() {
+    assert_eq!("".parse::<MediaType>(), Err(MediaTypeError::Empty));
+    assert_eq!("; charset=utf-8".parse::<MediaType>(), Err(MediaTypeError::Empty));
+    for value in ["application", "application/", "/json", "application/json/extra", "appli cation/json"] {
+        assert_eq!(
+            value.parse::<MediaType>(),
+            Err(MediaTypeError::Malformed(value.to_string())),
+            "{}",
+            value
+        );
+    }
+}
+
+#[test]
+fn test_manifest_and_index_helpers() {
+    assert!(MediaType::DockerManifest.is_manifest());
+    assert!(MediaType::OciManifest.is_manifest());
+    assert!(!MediaType::OciIndex.is_manifest());
+
+    assert!(MediaType::OciIndex.is_index());
+    assert!(MediaType::DockerManifestList.is_index());
+    assert!(!MediaType::OciManifest.is_index());
+
+    assert!(MediaType::OciConfig.is_blob());
+    assert!(MediaType::DockerLayer.is_blob());
+    assert!(!MediaType::OciManifest.is_blob());
+}
+
+#[test]
+fn test_accept_header_matching() {
+    let index = MediaType::OciIndex;
+    assert!(index.accepted_by(OCI_INDEX));
+    assert!(index.accepted_by(&format!("{}, {}", DOCKER_MANIFEST, OCI_INDEX)));
+    assert!(index.accepted_by("*/*"));
+    assert!(index.accepted_by("application/*"));
+    assert!(!index.accepted_by(&format!("{}, {}", DOCKER_MANIFEST, OCI_MANIFEST)));
+    assert!(!index.accepted_by(&format!("{};q=0", OCI_INDEX)));
+    assert!(index.accepted_by(&format!("{}; q=0.5", OCI_INDEX)));
+}
+
+#[test]
+fn test_stored_manifest_media_type() {
+    assert_eq!(manifest_media_type(&json!({ "mediaType": OCI_MANIFEST })), MediaType::OciManifest);
+    assert_eq!(manifest_media_type(&json!({ "schemaVersion": 2, "manifests": [] })), MediaType::OciIndex);
+    assert_eq!(manifest_media_type(&json!({ "schemaVersion": 2, "layers": [] })), MediaType::DockerManifest);
+}
+
+#[test]
+fn test_push_media_type_selection() {
+    let oci_body = json!({ "schemaVersion": 2, "mediaType": OCI_MANIFEST }).to_string();
+    let bare_body = json!({ "schemaVersion": 2 }).to_string();
+
+    assert_eq!(manifest_push_media_type(Some(OCI_MANIFEST), &oci_body), Ok(MediaType::OciManifest));
+    assert_eq!(manifest_push_media_type(None, &oci_body), Ok(MediaType::OciManifest));
+    assert_eq!(manifest_push_media_type(Some(OCI_INDEX), &bare_body), Ok(MediaType::OciIndex));
+    assert_eq!(manifest_push_media_type(None, &bare_body), Ok(MediaType::DockerManifest));
+}
+
+#[test]
+fn test_push_media_type_rejections() {
+    let oci_body = json!({ "schemaVersion": 2, "mediaType": OCI_MANIFEST }).to_string();
+
+    assert!(manifest_push_media_type(Some(DOCKER_MANIFEST), &oci_body).is_err());
+    assert!(manifest_push_media_type(Some("not a type"), &oci_body).is_err());
+    assert!(manifest_push_media_type(Some("application/vnd.oci.image.config.v1+json"), "{}").is_err());
+}
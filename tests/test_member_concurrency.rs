@@ -0,0 +1,36 @@
+// Tests for optimistic concurrency on organization member updates
+#[cfg(test)]
+mod tests {
+    use aerugo::error::AppError;
+    use aerugo::models::organizations::{check_member_version, OrganizationRole, UpdateMemberRequest};
+    use axum::http::StatusCode;
+
+    #[test]
+    fn test_stale_role_update_rejected() {
+        // Two admins loaded the member at version 3; the first update moved it to 4
+        let error = check_member_version(Some(3), 4).unwrap_err();
+        assert!(matches!(error, AppError::PreconditionFailed(_)));
+        assert_eq!(error.status_code(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(error.code(), "PRECONDITION_FAILED");
+    }
+
+    #[test]
+    fn test_fresh_role_update_succeeds() {
+        assert!(check_member_version(Some(4), 4).is_ok());
+    }
+
+    #[test]
+    fn test_unversioned_update_applies_unconditionally() {
+        assert!(check_member_version(None, 7).is_ok());
+    }
+
+    #[test]
+    fn test_version_is_optional_in_request() {
+        let request: UpdateMemberRequest = serde_json::from_str(r#"{"role": "Admin"}"#).unwrap();
+        assert_eq!(request.role, OrganizationRole::Admin);
+        assert_eq!(request.version, None);
+
+        let request: UpdateMemberRequest = serde_json::from_str(r#"{"role": "Member", "version": 2}"#).unwrap();
+        assert_eq!(request.version, Some(2));
+    }
+}
@@ -0,0 +1,51 @@
+// Tests for looking up a user's effective role in an organization
+#[cfg(test)]
+mod tests {
+    use aerugo::error::AppError;
+    use aerugo::models::organizations::{
+        resolve_member_role, MemberRoleResponse, OrganizationAction, OrganizationRole,
+    };
+    use axum::http::StatusCode;
+
+    #[test]
+    fn test_existing_member_role_returned() {
+        let role = resolve_member_role(Some(&OrganizationRole::Owner), Some(OrganizationRole::Member)).unwrap();
+        assert_eq!(role, OrganizationRole::Member);
+
+        let role = resolve_member_role(Some(&OrganizationRole::Admin), Some(OrganizationRole::Owner)).unwrap();
+        assert_eq!(role, OrganizationRole::Owner);
+    }
+
+    #[test]
+    fn test_non_member_not_found() {
+        let error = resolve_member_role(Some(&OrganizationRole::Admin), None).unwrap_err();
+        assert!(matches!(error, AppError::NotFound(_)));
+        assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_unauthorized_caller_forbidden() {
+        // Plain members and outsiders cannot look roles up, even of non-members
+        for caller in [Some(&OrganizationRole::Member), None] {
+            for member in [Some(OrganizationRole::Admin), None] {
+                let error = resolve_member_role(caller, member).unwrap_err();
+                assert!(matches!(error, AppError::Forbidden(_)));
+                assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+            }
+        }
+        assert!(!OrganizationRole::Member.allows(OrganizationAction::ViewMemberRoles));
+    }
+
+    #[test]
+    fn test_response_shape() {
+        let response = MemberRoleResponse {
+            organization: "acme".to_string(),
+            user_id: 42,
+            role: OrganizationRole::Admin,
+        };
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            serde_json::json!({ "organization": "acme", "user_id": 42, "role": "Admin" })
+        );
+    }
+}
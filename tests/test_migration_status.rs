@@ -0,0 +1,42 @@
+// Tests for the migration status report; needs a live Postgres (DATABASE_URL)
+
+mod utils;
+
+use aerugo::db::{migration_status, MIGRATOR};
+use anyhow::Result;
+use utils::migrated_pool;
+
+#[tokio::test]
+async fn test_applied_migrations_are_listed() -> Result<()> {
+    let pool = migrated_pool().await?;
+
+    let status = migration_status(&pool, &MIGRATOR).await?;
+
+    let embedded: Vec<i64> = MIGRATOR.iter().map(|m| m.version).collect();
+    for version in &embedded {
+        assert!(
+            status.applied.iter().any(|a| a.version == *version && a.success),
+            "migration {} not listed as applied",
+            version
+        );
+    }
+    assert!(status.pending.is_empty());
+    assert!(status.up_to_date);
+    assert_eq!(status.schema_version, embedded.iter().max().copied());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_report_serializes_versions_and_descriptions() -> Result<()> {
+    let pool = migrated_pool().await?;
+
+    let json = serde_json::to_value(migration_status(&pool, &MIGRATOR).await?)?;
+
+    assert!(json["schema_version"].is_i64());
+    assert_eq!(json["up_to_date"], true);
+    let first = &json["applied"][0];
+    assert!(first["version"].is_i64());
+    assert!(first["description"].is_string());
+    assert!(first["installed_on"].is_string());
+    Ok(())
+}
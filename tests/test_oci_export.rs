@@ -0,0 +1,173 @@
+// Tests for exporting a repository as an OCI image layout tarball
+
+mod utils;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use aerugo::models::digest::Digest;
+use aerugo::oci_layout::{
+    layout_len, layout_stream, plan_layout, LayoutError, LayoutManifest, LayoutTag, OCI_LAYOUT_FILE,
+    REF_NAME_ANNOTATION,
+};
+use aerugo::storage::Storage;
+use aerugo::utils::tar;
+use anyhow::Result;
+use bytes::Bytes;
+use futures::TryStreamExt;
+use serde_json::json;
+use utils::shared_test_storage;
+
+const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+
+/// Store `content` as a blob and return its digest
+async fn seed_blob(storage: &Arc<dyn Storage>, content: &[u8]) -> Result<String> {
+    let digest = Digest::sha256(content);
+    storage.put_blob(&digest.blob_key(), Bytes::copy_from_slice(content)).await?;
+    Ok(digest.as_str().to_string())
+}
+
+fn image_manifest(config: &str, config_size: usize, layer: &str, layer_size: usize) -> LayoutManifest {
+    let content = json!({
+        "schemaVersion": 2,
+        "mediaType": OCI_MANIFEST,
+        "config": { "mediaType": "application/vnd.oci.image.config.v1+json", "digest": config, "size": config_size },
+        "layers": [{ "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "digest": layer, "size": layer_size }]
+    })
+    .to_string();
+    LayoutManifest { digest: Digest::sha256(content.as_bytes()).as_str().to_string(), media_type: OCI_MANIFEST.to_string(), content }
+}
+
+/// Files of a tarball by path, read as `tar -x` would
+fn read_tar(archive: &[u8]) -> HashMap<String, Vec<u8>> {
+    let mut files = HashMap::new();
+    let mut offset = 0;
+    while offset + tar::BLOCK_SIZE <= archive.len() {
+        let header = &archive[offset..offset + tar::BLOCK_SIZE];
+        if header.iter().all(|b| *b == 0) {
+            break;
+        }
+        let name_end = header[..100].iter().position(|b| *b == 0).unwrap_or(100);
+        let name = String::from_utf8(header[..name_end].to_vec()).unwrap();
+        let size = usize::from_str_radix(std::str::from_utf8(&header[124..135]).unwrap(), 8).unwrap();
+        assert_eq!(&header[257..262], b"ustar");
+
+        let start = offset + tar::BLOCK_SIZE;
+        files.insert(name, archive[start..start + size].to_vec());
+        offset = start + size + tar::padding(size as u64);
+    }
+    files
+}
+
+#[tokio::test]
+async fn test_export_contains_index_and_blobs() -> Result<()> {
+    let storage = shared_test_storage("seeded");
+    let config = br#"{"architecture":"amd64","os":"linux"}"#;
+    let layer = vec![42u8; 1500];
+    let config_digest = seed_blob(&storage, config).await?;
+    let layer_digest = seed_blob(&storage, &layer).await?;
+    let manifest = image_manifest(&config_digest, config.len(), &layer_digest, layer.len());
+    let tags = vec![
+        LayoutTag { name: "latest".to_string(), digest: manifest.digest.clone() },
+        LayoutTag { name: "v1".to_string(), digest: manifest.digest.clone() },
+    ];
+
+    let entries = plan_layout(storage.as_ref(), &tags, std::slice::from_ref(&manifest)).await?;
+    let expected_len = layout_len(&entries);
+    let chunks: Vec<Bytes> = layout_stream(storage.clone(), entries).try_collect().await?;
+    let archive = chunks.concat();
+    assert_eq!(archive.len() as u64, expected_len);
+    assert_eq!(archive.len() % tar::BLOCK_SIZE, 0);
+
+    let files = read_tar(&archive);
+    assert_eq!(files.len(), 5);
+    assert_eq!(files["oci-layout"], OCI_LAYOUT_FILE.as_bytes());
+
+    let index: serde_json::Value = serde_json::from_slice(&files["index.json"])?;
+    assert_eq!(index["schemaVersion"], 2);
+    let descriptors = index["manifests"].as_array().unwrap();
+    assert_eq!(descriptors.len(), 2);
+    assert_eq!(descriptors[0]["digest"], manifest.digest);
+    assert_eq!(descriptors[0]["mediaType"], OCI_MANIFEST);
+    assert_eq!(descriptors[0]["size"], manifest.content.len());
+    assert_eq!(descriptors[0]["annotations"][REF_NAME_ANNOTATION], "latest");
+    assert_eq!(descriptors[1]["annotations"][REF_NAME_ANNOTATION], "v1");
+
+    let blob = |digest: &str| files[&format!("blobs/sha256/{}", digest.trim_start_matches("sha256:"))].clone();
+    assert_eq!(blob(&manifest.digest), manifest.content.as_bytes());
+    assert_eq!(blob(&config_digest), config);
+    assert_eq!(blob(&layer_digest), layer);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_follows_index_children() -> Result<()> {
+    let storage = shared_test_storage("index");
+    let config = br#"{"architecture":"arm64","os":"linux"}"#;
+    let layer = b"layer";
+    let config_digest = seed_blob(&storage, config).await?;
+    let layer_digest = seed_blob(&storage, layer).await?;
+    let child = image_manifest(&config_digest, config.len(), &layer_digest, layer.len());
+    let index_content = json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.index.v1+json",
+        "manifests": [{ "mediaType": OCI_MANIFEST, "digest": child.digest, "size": child.content.len() }]
+    })
+    .to_string();
+    let index = LayoutManifest {
+        digest: Digest::sha256(index_content.as_bytes()).as_str().to_string(),
+        media_type: "application/vnd.oci.image.index.v1+json".to_string(),
+        content: index_content,
+    };
+    let tags = vec![LayoutTag { name: "multiarch".to_string(), digest: index.digest.clone() }];
+
+    let entries = plan_layout(storage.as_ref(), &tags, &[index.clone(), child.clone()]).await?;
+    let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+    assert_eq!(paths.len(), 6);
+    for digest in [&index.digest, &child.digest, &config_digest, &layer_digest] {
+        assert!(paths.contains(&format!("blobs/sha256/{}", &digest[7..]).as_str()), "{} missing", digest);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_blob_fails_before_streaming() -> Result<()> {
+    let storage = shared_test_storage("missing");
+    let config_digest = seed_blob(&storage, b"{}").await?;
+    let absent = Digest::sha256(b"never uploaded").as_str().to_string();
+    let manifest = image_manifest(&config_digest, 2, &absent, 14);
+    let tags = vec![LayoutTag { name: "latest".to_string(), digest: manifest.digest.clone() }];
+
+    let error = plan_layout(storage.as_ref(), &tags, &[manifest]).await.unwrap_err();
+    assert!(matches!(error, LayoutError::BlobMissing(ref digest) if *digest == absent), "{}", error);
+
+    let dangling = vec![LayoutTag { name: "gone".to_string(), digest: absent.clone() }];
+    let error = plan_layout(storage.as_ref(), &dangling, &[]).await.unwrap_err();
+    assert!(matches!(error, LayoutError::ManifestMissing(_)));
+    Ok(())
+}
+
+#[test]
+fn test_tar_headers() {
+    let header = tar::file_header("index.json", 1234);
+    assert_eq!(header.len(), tar::BLOCK_SIZE);
+    assert_eq!(&header[124..136], b"00000002322\0");
+
+    // The checksum is the byte sum with the checksum field read as spaces
+    let stored = u32::from_str_radix(std::str::from_utf8(&header[148..154]).unwrap(), 8).unwrap();
+    let mut blank = header.clone();
+    blank[148..156].fill(b' ');
+    assert_eq!(stored, blank.iter().map(|b| *b as u32).sum::<u32>());
+
+    // sha512 blob paths are too long for ustar and get a PAX header
+    let path = format!("blobs/sha512/{}", "a".repeat(128));
+    let header = tar::file_header(&path, 10);
+    assert_eq!(header.len(), 3 * tar::BLOCK_SIZE);
+    assert_eq!(header[156], b'x');
+    let records = std::str::from_utf8(&header[tar::BLOCK_SIZE..2 * tar::BLOCK_SIZE]).unwrap();
+    assert!(records.starts_with(&format!("151 path={}\n", path)));
+
+    assert_eq!(tar::padding(0), 0);
+    assert_eq!(tar::padding(1), 511);
+    assert_eq!(tar::entry_len("oci-layout", 30), 1024);
+}
@@ -0,0 +1,130 @@
+// Tests for bucketing organization pull and push analytics
+#[cfg(test)]
+mod tests {
+    use aerugo::models::analytics::{
+        summarize_activity, ActivityAction, ActivityCount, AnalyticsBucket, AnalyticsGranularity, AnalyticsQuery,
+        AnalyticsRange, MAX_ANALYTICS_DAYS,
+    };
+    use aerugo::models::organizations::{OrganizationAction, OrganizationRole};
+    use chrono::{Duration, NaiveDate};
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 9, d).unwrap()
+    }
+
+    fn pull(d: u32, repository: &str, actor: Option<&str>, count: i64) -> ActivityCount {
+        ActivityCount {
+            day: day(d),
+            repository: repository.to_string(),
+            action: ActivityAction::Pull,
+            actor: actor.map(str::to_string),
+            count,
+        }
+    }
+
+    fn push(d: u32, repository: &str, count: i64) -> ActivityCount {
+        ActivityCount {
+            day: day(d),
+            repository: repository.to_string(),
+            action: ActivityAction::Push,
+            actor: Some("7".to_string()),
+            count,
+        }
+    }
+
+    /// Two weeks of activity: 2025-09-01 and 2025-09-08 are Mondays
+    fn seeded() -> Vec<ActivityCount> {
+        vec![
+            pull(1, "api", Some("7"), 5),
+            pull(1, "api", Some("8"), 2),
+            pull(1, "web", None, 4),
+            push(1, "api", 1),
+            pull(3, "web", Some("7"), 1),
+            push(5, "web", 2),
+            pull(9, "api", Some("9"), 3),
+            pull(9, "cli", Some("org_2"), 10),
+            push(14, "cli", 1),
+            // Outside the requested range
+            pull(15, "api", Some("10"), 100),
+        ]
+    }
+
+    fn range(from: u32, to: u32, granularity: AnalyticsGranularity) -> AnalyticsRange {
+        AnalyticsRange { from: day(from), to: day(to), granularity }
+    }
+
+    fn bucket(start: u32, pulls: i64, pushes: i64, unique_pullers: i64) -> AnalyticsBucket {
+        AnalyticsBucket { start: day(start), pulls, pushes, unique_pullers }
+    }
+
+    #[test]
+    fn test_daily_buckets() {
+        let analytics = summarize_activity("acme".to_string(), range(1, 14, AnalyticsGranularity::Day), &seeded());
+
+        assert_eq!(analytics.buckets.len(), 14);
+        assert_eq!(analytics.buckets[0], bucket(1, 11, 1, 2));
+        // Days without activity are present with zeros
+        assert_eq!(analytics.buckets[1], bucket(2, 0, 0, 0));
+        assert_eq!(analytics.buckets[2], bucket(3, 1, 0, 1));
+        assert_eq!(analytics.buckets[8], bucket(9, 13, 0, 2));
+        assert_eq!(analytics.buckets[13], bucket(14, 0, 1, 0));
+    }
+
+    #[test]
+    fn test_weekly_buckets_start_on_monday() {
+        let analytics = summarize_activity("acme".to_string(), range(1, 14, AnalyticsGranularity::Week), &seeded());
+
+        assert_eq!(analytics.buckets, vec![bucket(1, 12, 3, 2), bucket(8, 13, 1, 2)]);
+    }
+
+    #[test]
+    fn test_week_containing_from_is_labelled_by_its_monday() {
+        // 2025-09-03 is a Wednesday; pulls on the Monday before are outside the range
+        let analytics = summarize_activity("acme".to_string(), range(3, 9, AnalyticsGranularity::Week), &seeded());
+
+        assert_eq!(analytics.buckets, vec![bucket(1, 1, 2, 1), bucket(8, 13, 0, 2)]);
+    }
+
+    #[test]
+    fn test_top_repositories_and_unique_pullers() {
+        let analytics = summarize_activity("acme".to_string(), range(1, 14, AnalyticsGranularity::Day), &seeded());
+
+        let top: Vec<_> = analytics.top_repositories.iter().map(|r| (r.name.as_str(), r.pulls, r.pushes)).collect();
+        assert_eq!(top, vec![("api", 10, 1), ("cli", 10, 1), ("web", 5, 2)]);
+        // Anonymous pulls count towards totals but not towards unique pullers
+        assert_eq!(analytics.unique_pullers, 4);
+    }
+
+    #[test]
+    fn test_range_defaults_and_limits() {
+        let today = day(30);
+        let resolved = AnalyticsQuery::default().resolve(today).unwrap();
+        assert_eq!(resolved, range(1, 30, AnalyticsGranularity::Day));
+
+        let backwards = AnalyticsQuery { from: Some(day(10)), to: Some(day(9)), granularity: None };
+        assert!(backwards.resolve(today).is_err());
+
+        let too_long = AnalyticsQuery {
+            from: Some(today - Duration::days(MAX_ANALYTICS_DAYS)),
+            to: Some(today),
+            granularity: None,
+        };
+        assert!(too_long.resolve(today).is_err());
+    }
+
+    #[test]
+    fn test_granularity_parsing() {
+        let query: AnalyticsQuery =
+            serde_json::from_str(r#"{"from": "2025-09-01", "granularity": "week"}"#).unwrap();
+        assert_eq!(query.from, Some(day(1)));
+        assert_eq!(query.granularity, Some(AnalyticsGranularity::Week));
+        assert!(serde_json::from_str::<AnalyticsQuery>(r#"{"granularity": "month"}"#).is_err());
+    }
+
+    #[test]
+    fn test_only_admins_view_analytics() {
+        assert!(OrganizationRole::Owner.allows(OrganizationAction::ViewAnalytics));
+        assert!(OrganizationRole::Admin.allows(OrganizationAction::ViewAnalytics));
+        assert!(!OrganizationRole::Member.allows(OrganizationAction::ViewAnalytics));
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::models::organizations::{OrganizationAction, OrganizationRole};
+
+    #[test]
+    fn test_owner_gets_every_action() {
+        assert_eq!(OrganizationRole::Owner.allowed_actions(), OrganizationAction::ALL.to_vec());
+    }
+
+    #[test]
+    fn test_admin_cannot_touch_owners_or_delete() {
+        let actions = OrganizationRole::Admin.allowed_actions();
+
+        assert!(actions.contains(&OrganizationAction::UpdateOrganization));
+        assert!(actions.contains(&OrganizationAction::AddMembers));
+        assert!(actions.contains(&OrganizationAction::RemoveMembers));
+        assert!(actions.contains(&OrganizationAction::AssignAdminRole));
+        assert!(!actions.contains(&OrganizationAction::DeleteOrganization));
+        assert!(!actions.contains(&OrganizationAction::RenameOrganization));
+        assert!(!actions.contains(&OrganizationAction::RemoveOwners));
+        assert!(!actions.contains(&OrganizationAction::AssignOwnerRole));
+    }
+
+    #[test]
+    fn test_member_gets_reduced_set() {
+        assert_eq!(OrganizationRole::Member.allowed_actions(), vec![OrganizationAction::ViewOrganization]);
+    }
+
+    #[test]
+    fn test_repository_creation_roles() {
+        assert!(OrganizationRole::Member.can_create_repository(false));
+        assert!(!OrganizationRole::Member.can_create_repository(true));
+        assert!(OrganizationRole::Admin.can_create_repository(true));
+        assert!(OrganizationRole::Owner.can_create_repository(true));
+    }
+
+    #[test]
+    fn test_actions_serialize_as_snake_case() {
+        let json = serde_json::to_string(&OrganizationRole::Member.allowed_actions()).unwrap();
+        assert_eq!(json, r#"["view_organization"]"#);
+        assert_eq!(
+            serde_json::to_string(&OrganizationAction::AssignOwnerRole).unwrap(),
+            r#""assign_owner_role""#
+        );
+    }
+}
@@ -0,0 +1,72 @@
+// Tests for the checks SANITIZE_ORG_PROFILES applies to organization profiles
+#[cfg(test)]
+mod tests {
+    use aerugo::models::organizations::{
+        check_profile_url, strip_control_characters, CreateOrganizationRequest, UpdateOrganizationRequest,
+    };
+
+    fn create_request(website_url: Option<&str>, description: Option<&str>) -> CreateOrganizationRequest {
+        CreateOrganizationRequest {
+            name: "acme".to_string(),
+            display_name: "Acme".to_string(),
+            description: description.map(str::to_string),
+            website_url: website_url.map(str::to_string),
+            avatar_url: None,
+        }
+    }
+
+    #[test]
+    fn test_javascript_url_rejected() {
+        let mut request = create_request(Some("javascript:alert(document.cookie)"), None);
+        let error = request.sanitize().unwrap_err();
+        assert!(error.starts_with("website_url"), "{}", error);
+    }
+
+    #[test]
+    fn test_https_url_accepted() {
+        let mut request = create_request(Some("https://acme.example/about"), None);
+        assert_eq!(request.sanitize(), Ok(()));
+        assert_eq!(request.website_url.as_deref(), Some("https://acme.example/about"));
+    }
+
+    #[test]
+    fn test_only_http_and_https_with_a_host() {
+        assert_eq!(check_profile_url("http://acme.example"), Ok(()));
+        assert_eq!(check_profile_url("HTTPS://ACME.EXAMPLE"), Ok(()));
+        assert!(check_profile_url("JavaScript:alert(1)").is_err());
+        assert!(check_profile_url("data:text/html,<script>alert(1)</script>").is_err());
+        assert!(check_profile_url("ftp://acme.example").is_err());
+        assert!(check_profile_url("//acme.example").is_err());
+        assert!(check_profile_url("acme.example").is_err());
+    }
+
+    #[test]
+    fn test_avatar_url_checked_on_update() {
+        let mut request = UpdateOrganizationRequest {
+            display_name: None,
+            description: None,
+            website_url: None,
+            avatar_url: Some("data:image/svg+xml,<svg onload=alert(1)>".to_string()),
+            block_vulnerable_pulls: None,
+            default_repo_public: None,
+        };
+        let error = request.sanitize().unwrap_err();
+        assert!(error.starts_with("avatar_url"), "{}", error);
+    }
+
+    #[test]
+    fn test_description_control_characters_stripped() {
+        assert_eq!(strip_control_characters("Acme\u{0}\u{1b}[31m Corp\u{7f}"), "Acme[31m Corp");
+        assert_eq!(strip_control_characters("Line one\nLine two\r\n\tIndented"), "Line one\nLine two\r\n\tIndented");
+
+        let mut request = create_request(None, Some("We build\u{8} things"));
+        assert_eq!(request.sanitize(), Ok(()));
+        assert_eq!(request.description.as_deref(), Some("We build things"));
+    }
+
+    #[test]
+    fn test_empty_url_clears_rather_than_fails() {
+        let mut request = create_request(Some(""), None);
+        assert_eq!(request.sanitize(), Ok(()));
+    }
+}
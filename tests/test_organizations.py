@@ -8,13 +8,18 @@ sys.path.insert(0, os.path.dirname(os.path.abspath(__file__)))
 
 try:
     from base_test import BaseTestCase, test_data_manager
-    from config import TEST_USERS, TestUser
+    from config import TEST_USERS, TestUser, SERVER_URL
 except ImportError:
     from .base_test import BaseTestCase, test_data_manager
-    from .config import TEST_USERS, TestUser
+    from .config import TEST_USERS, TestUser, SERVER_URL
 
 import random
 import string
+import json
+import time
+from urllib.parse import quote
+from datetime import datetime, timedelta, timezone
+import requests
 
 
 class OrganizationTests(BaseTestCase):
@@ -492,6 +497,271 @@ class OrganizationTests(BaseTestCase):
         
         self.logger.info("✅ Permissions test passed")
     
+    def test_duplicate_organization_name(self):
+        """Test that a duplicate organization name returns 409"""
+        self.logger.info("Testing duplicate organization name")
+        
+        owner = self.create_dynamic_owner()
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        org_data = {
+            "name": f"duporg_{session_id}",
+            "display_name": f"Duplicate Org {session_id}",
+        }
+        response = self.make_request("POST", "/organizations", data=org_data, token=owner.token)
+        self.assert_response(response, 201)
+        
+        duplicate = self.make_request("POST", "/organizations", data=org_data, token=owner.token)
+        self.assert_response(duplicate, 409, "Duplicate organization name should conflict")
+        assert duplicate.json()["code"] == "CONFLICT"
+        
+        self.logger.info("✅ Duplicate organization name test passed")
+
+    def create_org_for_rename(self, owner, prefix):
+        """Create an organization to be renamed"""
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        org_data = {
+            "name": f"{prefix}_{session_id}",
+            "display_name": f"Rename Org {session_id}",
+            "description": "Rename test org"
+        }
+        response = self.make_request("POST", "/organizations", data=org_data, token=owner.token)
+        self.assert_response(response, 201)
+        return response.json()["organization"]
+
+    def test_rename_organization(self):
+        """Test renaming an organization"""
+        self.logger.info("Testing rename organization")
+        
+        owner = self.create_dynamic_owner()
+        org = self.create_org_for_rename(owner, "renameorg")
+        new_name = f"{org['name']}_new"
+        
+        response = self.make_request("POST", f"/organizations/{org['name']}/rename",
+                                     data={"new_name": new_name}, token=owner.token)
+        self.assert_response(response, 200, "Failed to rename organization")
+        
+        data = response.json()
+        assert data["organization"]["name"] == new_name
+        assert data["previous_name"] == org["name"]
+        
+        get_response = self.make_request("GET", f"/organizations/{org['id']}", token=owner.token)
+        self.assert_response(get_response, 200)
+        assert get_response.json()["organization"]["name"] == new_name
+        
+        self.logger.info("✅ Rename organization test passed")
+
+    def test_renamed_organization_redirect(self):
+        """Test that the old organization name redirects registry requests"""
+        self.logger.info("Testing renamed organization redirect")
+        
+        owner = self.create_dynamic_owner()
+        org = self.create_org_for_rename(owner, "redirorg")
+        new_name = f"{org['name']}_new"
+        
+        response = self.make_request("POST", f"/organizations/{org['name']}/rename",
+                                     data={"new_name": new_name}, token=owner.token)
+        self.assert_response(response, 200)
+        
+        old_url = f"{SERVER_URL}/v2/{org['name']}/app/tags/list?n=10"
+        redirect = requests.get(old_url, headers={"Authorization": f"Bearer {owner.token}"},
+                                allow_redirects=False, timeout=30)
+        assert redirect.status_code == 301, f"Expected 301, got {redirect.status_code}"
+        assert redirect.headers["Location"] == f"/v2/{new_name}/app/tags/list?n=10"
+        
+        # The old name can not be claimed by a new organization during the grace period
+        other = self.create_dynamic_owner()
+        create_response = self.make_request("POST", "/organizations", data={
+            "name": org["name"],
+            "display_name": "Squatter",
+        }, token=other.token)
+        assert create_response.status_code != 201, "Old name should stay reserved"
+        
+        self.logger.info("✅ Renamed organization redirect test passed")
+
+    def test_rename_organization_conflict(self):
+        """Test renaming to a taken or reserved name"""
+        self.logger.info("Testing rename organization conflict")
+        
+        owner = self.create_dynamic_owner()
+        org = self.create_org_for_rename(owner, "conflictorg")
+        taken = self.create_org_for_rename(owner, "takenorg")
+        
+        response = self.make_request("POST", f"/organizations/{org['name']}/rename",
+                                     data={"new_name": taken["name"]}, token=owner.token)
+        self.assert_response(response, 409, "Renaming to a taken name should conflict")
+        
+        reserved_response = self.make_request("POST", f"/organizations/{org['name']}/rename",
+                                              data={"new_name": "_catalog"}, token=owner.token)
+        self.assert_response(reserved_response, 409, "Renaming to a reserved name should conflict")
+        
+        self.logger.info("✅ Rename organization conflict test passed")
+    
+    def test_membership_expiry(self):
+        """Test that expired memberships no longer grant access"""
+        self.logger.info("Testing membership expiry")
+
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        contractor = self.create_dynamic_member()
+        long_term = self.create_dynamic_member()
+
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        org_data = {
+            "name": f"expiryorg_{session_id}",
+            "display_name": f"Expiry Org {session_id}",
+            "description": "Org for membership expiry"
+        }
+        create_response = self.make_request("POST", "/organizations", data=org_data, token=owner.token)
+        self.assert_response(create_response, 201)
+        org_id = create_response.json()["organization"]["id"]
+        self.current_org_id = org_id
+
+        now = datetime.now(timezone.utc)
+        soon = (now + timedelta(seconds=3)).isoformat()
+        later = (now + timedelta(days=30)).isoformat()
+
+        # Expiry in the past is rejected
+        past = (now - timedelta(days=1)).isoformat()
+        past_response = self.make_request("POST", f"/organizations/{org_id}/members",
+                                          data={"email": contractor.email, "role": "Member", "expires_at": past},
+                                          token=owner.token)
+        self.assert_response(past_response, 400, "Past expiry should be rejected")
+
+        add_response = self.make_request("POST", f"/organizations/{org_id}/members",
+                                         data={"email": contractor.email, "role": "Member", "expires_at": soon},
+                                         token=owner.token)
+        self.assert_response(add_response, 201, "Failed to add time-boxed member")
+        assert add_response.json()["member"]["expires_at"] is not None
+
+        add_response = self.make_request("POST", f"/organizations/{org_id}/members",
+                                         data={"email": long_term.email, "role": "Member", "expires_at": later},
+                                         token=owner.token)
+        self.assert_response(add_response, 201, "Failed to add member with future expiry")
+
+        # Both memberships grant access before expiry
+        for user in (contractor, long_term):
+            response = self.make_request("GET", f"/organizations/{org_id}/members", token=user.token)
+            self.assert_response(response, 200, "Unexpired member should access members list")
+
+        time.sleep(4)
+
+        # The expired membership is treated as a non-member; the future expiry still works
+        expired_response = self.make_request("GET", f"/organizations/{org_id}/members", token=contractor.token)
+        self.assert_response(expired_response, 400, "Expired member should not access members")
+        active_response = self.make_request("GET", f"/organizations/{org_id}/members", token=long_term.token)
+        self.assert_response(active_response, 200, "Member with future expiry should keep access")
+
+        emails = [m["email"] for m in active_response.json()["members"]]
+        assert contractor.email not in emails, "Expired member should not be listed"
+
+        self.logger.info("✅ Membership expiry test passed")
+
+    def test_audit_log_export(self):
+        """Test JSON lines export of the audit log with a date range"""
+        self.logger.info("Testing audit log export")
+
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        member = self.create_dynamic_member()
+
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        org_name = f"auditorg_{session_id}"
+        create_response = self.make_request("POST", "/organizations",
+                                            data={"name": org_name, "display_name": f"Audit Org {session_id}"},
+                                            token=owner.token)
+        self.assert_response(create_response, 201)
+        org_id = create_response.json()["organization"]["id"]
+        self.current_org_id = org_id
+
+        before = datetime.now(timezone.utc) - timedelta(seconds=5)
+        add_response = self.make_request("POST", f"/organizations/{org_id}/members",
+                                         data={"email": member.email, "role": "Member"},
+                                         token=owner.token)
+        self.assert_response(add_response, 201)
+        update_response = self.make_request("PUT", f"/organizations/{org_id}",
+                                            data={"description": "Audited"}, token=owner.token)
+        self.assert_response(update_response, 200)
+        after = datetime.now(timezone.utc) + timedelta(seconds=5)
+
+        # Every line is a standalone JSON object
+        export_response = self.make_request("GET", f"/organizations/{org_name}/audit/export", token=owner.token)
+        self.assert_response(export_response, 200, "Owner should export the audit log")
+        assert export_response.headers["Content-Type"].startswith("application/x-ndjson")
+        assert export_response.text.endswith("\n"), "Export should be newline terminated"
+        entries = [json.loads(line) for line in export_response.text.splitlines()]
+        actions = [entry["action"] for entry in entries]
+        assert actions == ["member.added", "organization.updated"], f"Unexpected actions: {actions}"
+        assert all(entry["organization_id"] == org_id for entry in entries)
+
+        # The date range covering the actions includes them, one after them excludes them
+        in_range = self.make_request(
+            "GET",
+            f"/organizations/{org_name}/audit/export?from={quote(before.isoformat())}&to={quote(after.isoformat())}",
+            token=owner.token)
+        self.assert_response(in_range, 200)
+        assert len(in_range.text.splitlines()) == 2
+
+        out_of_range = self.make_request(
+            "GET", f"/organizations/{org_name}/audit/export?from={quote(after.isoformat())}", token=owner.token)
+        self.assert_response(out_of_range, 200)
+        assert out_of_range.text == "", "No entries should fall after the actions"
+
+        inverted = self.make_request(
+            "GET",
+            f"/organizations/{org_name}/audit/export?from={quote(after.isoformat())}&to={quote(before.isoformat())}",
+            token=owner.token)
+        self.assert_response(inverted, 400, "Inverted range should be rejected")
+
+        # Plain members cannot export
+        member_response = self.make_request("GET", f"/organizations/{org_name}/audit/export", token=member.token)
+        self.assert_response(member_response, 403, "Members should not export the audit log")
+
+        self.logger.info("✅ Audit log export test passed")
+
+    def test_my_permissions(self):
+        """Test that owners and members get their own action sets"""
+        self.logger.info("Testing effective permissions")
+
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        member = self.create_dynamic_member()
+
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        org_name = f"permsorg_{session_id}"
+        create_response = self.make_request("POST", "/organizations", data={
+            "name": org_name,
+            "display_name": f"Perms Org {session_id}",
+            "description": "Org for effective permissions"
+        }, token=owner.token)
+        self.assert_response(create_response, 201)
+        org_id = create_response.json()["organization"]["id"]
+        self.current_org_id = org_id
+
+        add_response = self.make_request("POST", f"/organizations/{org_id}/members",
+                                         data={"email": member.email, "role": "Member"}, token=owner.token)
+        self.assert_response(add_response, 201, "Failed to add member")
+
+        def permissions_for(user):
+            response = self.make_request("GET", "/auth/me/permissions", token=user.token)
+            self.assert_response(response, 200, "Failed to get permissions")
+            entries = [o for o in response.json()["organizations"] if o["organization"] == org_name]
+            assert len(entries) == 1, f"Expected one entry for {org_name}"
+            return entries[0]
+
+        owner_entry = permissions_for(owner)
+        assert owner_entry["role"] == "owner"
+        assert "delete_organization" in owner_entry["actions"]
+        assert "assign_owner_role" in owner_entry["actions"]
+
+        member_entry = permissions_for(member)
+        assert member_entry["role"] == "member"
+        assert member_entry["actions"] == ["view_organization"], f"Unexpected member actions: {member_entry['actions']}"
+
+        unauthenticated = self.make_request("GET", "/auth/me/permissions")
+        self.assert_response(unauthenticated, 401, "Permissions require authentication")
+
+        self.logger.info("✅ Effective permissions test passed")
+
     def run_all_tests(self):
         """Run all organization tests"""
         self.logger.info("=== Running Organization Tests ===")
@@ -507,5 +777,12 @@ class OrganizationTests(BaseTestCase):
         self.test_update_member_role()
         self.test_remove_organization_member()
         self.test_organization_permissions()
+        self.test_duplicate_organization_name()
+        self.test_rename_organization()
+        self.test_renamed_organization_redirect()
+        self.test_rename_organization_conflict()
+        self.test_membership_expiry()
+        self.test_audit_log_export()
+        self.test_my_permissions()
         
         self.logger.info("✅ All organization tests passed")
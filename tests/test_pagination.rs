@@ -0,0 +1,80 @@
+// Tests for OCI-style n/last pagination and its Link header
+
+use aerugo::utils::pagination::{next_link, paginate, PageQuery};
+use axum::http::{header, HeaderMap};
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn query(n: Option<u32>, last: Option<&str>) -> PageQuery {
+    PageQuery { n, last: last.map(str::to_string) }
+}
+
+#[test]
+fn test_link_header_format() {
+    assert_eq!(next_link("/v2/_catalog", 2, "org/b"), "</v2/_catalog?n=2&last=org%2Fb>; rel=\"next\"");
+    assert_eq!(
+        next_link("/api/v1/repos/repositories?namespace=org", 10, "org/app"),
+        "</api/v1/repos/repositories?namespace=org&n=10&last=org%2Fapp>; rel=\"next\""
+    );
+}
+
+#[test]
+fn test_following_links_visits_every_item_once() {
+    let all = names(&["c", "a", "e", "b", "d"]);
+
+    let first = paginate(all.clone(), |name| name.clone(), &query(Some(2), None));
+    assert_eq!(first.items, names(&["a", "b"]));
+    assert_eq!(first.next_last.as_deref(), Some("b"));
+
+    let second = paginate(all.clone(), |name| name.clone(), &query(Some(2), Some("b")));
+    assert_eq!(second.items, names(&["c", "d"]));
+
+    let third = paginate(all, |name| name.clone(), &query(Some(2), Some("d")));
+    assert_eq!(third.items, names(&["e"]));
+    assert_eq!(third.next_last, None);
+    assert_eq!(third.link("/v2/_catalog"), None);
+}
+
+#[test]
+fn test_link_added_only_when_more_items_follow() {
+    let page = paginate(names(&["v1", "v2", "v3"]), |tag| tag.clone(), &query(Some(2), None));
+    let mut headers = HeaderMap::new();
+    page.add_link("/v2/org/app/tags/list", &mut headers);
+    assert_eq!(headers[header::LINK], "</v2/org/app/tags/list?n=2&last=v2>; rel=\"next\"");
+
+    // Exactly n items left: no next page
+    let page = paginate(names(&["v1", "v2"]), |tag| tag.clone(), &query(Some(2), None));
+    let mut headers = HeaderMap::new();
+    page.add_link("/v2/org/app/tags/list", &mut headers);
+    assert!(headers.get(header::LINK).is_none());
+}
+
+#[test]
+fn test_unpaginated_request_keeps_order() {
+    let page = paginate(names(&["newest", "older", "oldest"]), |tag| tag.clone(), &PageQuery::default());
+
+    assert_eq!(page.items, names(&["newest", "older", "oldest"]));
+    assert_eq!(page.next_last, None);
+}
+
+#[test]
+fn test_last_without_n_returns_the_rest() {
+    let page = paginate(names(&["a", "b", "c"]), |name| name.clone(), &query(None, Some("a")));
+
+    assert_eq!(page.items, names(&["b", "c"]));
+    assert_eq!(page.link("/v2/_catalog"), None);
+}
+
+#[test]
+fn test_fetched_rows_past_n_mark_a_next_page() {
+    // A query loads one row past n; that row only says another page follows
+    assert_eq!(query(Some(2), None).fetch_limit(), Some(3));
+    assert_eq!(query(None, Some("a")).fetch_limit(), None);
+
+    let fetched = names(&["b", "c", "d"]);
+    let page = paginate(fetched, |name| name.clone(), &query(Some(2), Some("a")));
+    assert_eq!(page.items, names(&["b", "c"]));
+    assert_eq!(page.next_last.as_deref(), Some("c"));
+}
@@ -0,0 +1,97 @@
+// Tests for ranking repositories on /v2/_popular; the query test needs a live Postgres (DATABASE_URL)
+
+mod utils;
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::migrated_pool;
+    use aerugo::handlers::docker_registry_v2::{popular_repositories, rank_popular, PopularRepository, PullTotal};
+    use aerugo::tenant::TenancyMode;
+    use anyhow::Result;
+    use sqlx::PgPool;
+
+    fn total(name: &str, is_public: bool, pulls: i64) -> PullTotal {
+        PullTotal { name: name.to_string(), is_public, pulls }
+    }
+
+    fn names(ranked: &[PopularRepository]) -> Vec<&str> {
+        ranked.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_ordered_by_pull_count() {
+        let ranked = rank_popular(
+            vec![total("acme/web", true, 12), total("acme/api", true, 250), total("tools/cli", true, 40)],
+            10,
+        );
+        assert_eq!(names(&ranked), vec!["acme/api", "tools/cli", "acme/web"]);
+        assert_eq!(ranked[0].pulls, 250);
+    }
+
+    #[test]
+    fn test_private_repositories_excluded() {
+        let ranked = rank_popular(
+            vec![total("acme/secret", false, 1000), total("acme/web", true, 5), total("acme/internal", false, 7)],
+            10,
+        );
+        assert_eq!(names(&ranked), vec!["acme/web"]);
+    }
+
+    #[test]
+    fn test_limit_and_ties() {
+        let ranked = rank_popular(
+            vec![total("b/app", true, 3), total("a/app", true, 3), total("c/app", true, 9), total("d/app", true, 1)],
+            3,
+        );
+        // Equal counts are ordered by name so the ranking is stable
+        assert_eq!(names(&ranked), vec!["c/app", "a/app", "b/app"]);
+    }
+
+    #[test]
+    fn test_unpulled_repositories_left_out() {
+        assert!(rank_popular(vec![total("acme/new", true, 0)], 10).is_empty());
+    }
+
+    async fn add_repository(pool: &PgPool, org_id: i64, name: &str, is_public: bool, pulls: i64) -> Result<()> {
+        let id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO repositories (organization_id, name, is_public) VALUES ($1, $2, $3) RETURNING id"
+        )
+        .bind(org_id)
+        .bind(name)
+        .bind(is_public)
+        .fetch_one(pool)
+        .await?;
+        sqlx::query("INSERT INTO repository_daily_pulls (repository_id, day, pulls) VALUES ($1, CURRENT_DATE, $2)")
+            .bind(id)
+            .bind(pulls)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a live Postgres (DATABASE_URL)"]
+    async fn test_query_ranks_and_limits_public_repositories() -> Result<()> {
+        let pool = migrated_pool().await?;
+        let org = format!("popular-{}", uuid::Uuid::new_v4().simple());
+        let org_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO organizations (name, display_name) VALUES ($1, $1) RETURNING id"
+        )
+        .bind(&org)
+        .fetch_one(&pool)
+        .await?;
+
+        // Counts far above anything else in the database, so these lead
+        add_repository(&pool, org_id, "web", true, 1_000_000_000_000).await?;
+        add_repository(&pool, org_id, "api", true, 3_000_000_000_000).await?;
+        add_repository(&pool, org_id, "cli", true, 2_000_000_000_000).await?;
+        add_repository(&pool, org_id, "secret", false, 9_000_000_000_000).await?;
+
+        let ranked = popular_repositories(&pool, TenancyMode::Shared, 7, 2).await?;
+        let expected = vec![format!("{}/api", org), format!("{}/cli", org)];
+        assert_eq!(ranked.iter().map(|r| r.name.clone()).collect::<Vec<_>>(), expected);
+
+        sqlx::query("DELETE FROM organizations WHERE id = $1").bind(org_id).execute(&pool).await?;
+        Ok(())
+    }
+}
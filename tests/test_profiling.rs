@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::handlers::profiling::{parse_proc_status, HeapStats};
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cpu_profile_is_not_empty() {
+        use aerugo::handlers::profiling::capture_cpu_profile;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        // Keep a thread busy so the profiler has samples to record
+        let running = Arc::new(AtomicBool::new(true));
+        let busy = {
+            let running = running.clone();
+            std::thread::spawn(move || {
+                let mut x: u64 = 0;
+                while running.load(Ordering::Relaxed) {
+                    x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+                }
+                x
+            })
+        };
+
+        let profile = capture_cpu_profile(1).unwrap();
+        running.store(false, Ordering::Relaxed);
+        busy.join().unwrap();
+
+        assert!(!profile.is_empty());
+    }
+
+    #[test]
+    fn test_parse_proc_status() {
+        let status = "Name:\taerugo\nVmPeak:\t  300000 kB\nVmSize:\t  250000 kB\nVmHWM:\t   12000 kB\nVmRSS:\t   10000 kB\nVmData:\t   80000 kB\nThreads:\t8\n";
+
+        assert_eq!(
+            parse_proc_status(status),
+            HeapStats {
+                resident_bytes: 10000 * 1024,
+                peak_resident_bytes: 12000 * 1024,
+                virtual_bytes: 250000 * 1024,
+                data_bytes: 80000 * 1024,
+            }
+        );
+        assert_eq!(parse_proc_status(""), HeapStats::default());
+    }
+}
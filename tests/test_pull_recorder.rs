@@ -0,0 +1,48 @@
+// Tests for queueing and folding manifest pulls before they are counted
+
+use aerugo::models::digest::Digest;
+use aerugo::pull_recorder::{fold_pulls, ManifestPull, PullRecorder};
+use aerugo::tenant::{TenancyMode, TenantContext};
+
+fn pull(tenant: Option<i64>, repository: &str, content: &[u8], puller: Option<&str>) -> ManifestPull {
+    ManifestPull {
+        tenant: tenant.map(|id| TenantContext::for_organization(TenancyMode::Schema, id)),
+        organization: Some("acme".to_string()),
+        repository: repository.to_string(),
+        digest: Digest::sha256(content),
+        puller: puller.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_repeated_pulls_are_folded_per_tenant() {
+    let folded = fold_pulls(vec![
+        pull(Some(2), "app", b"v1", Some("7")),
+        pull(Some(2), "app", b"v1", None),
+        pull(Some(2), "app", b"v2", Some("7")),
+        pull(Some(3), "app", b"v1", Some("8")),
+    ]);
+
+    assert_eq!(folded.len(), 2);
+    let (tenant, pulls) = &folded[0];
+    assert_eq!(tenant.as_ref().and_then(|t| t.schema()), Some("tenant_2"));
+    assert_eq!(pulls.manifests.len(), 2);
+    assert_eq!(pulls.manifests[&(Some("acme".to_string()), "app".to_string(), Digest::sha256(b"v1"))], 2);
+    // Activity is counted per puller, whatever the manifest
+    assert_eq!(pulls.activity.len(), 2);
+    assert_eq!(pulls.activity[&(Some("acme".to_string()), "app".to_string(), Some("7".to_string()))], 2);
+    assert_eq!(pulls.activity[&(Some("acme".to_string()), "app".to_string(), None)], 1);
+    assert_eq!(folded[1].1.activity[&(Some("acme".to_string()), "app".to_string(), Some("8".to_string()))], 1);
+}
+
+#[tokio::test]
+async fn test_full_queue_drops_pulls_instead_of_waiting() {
+    let (recorder, mut receiver) = PullRecorder::new(2);
+
+    assert!(recorder.record(pull(None, "app", b"v1", None)));
+    assert!(recorder.record(pull(None, "app", b"v1", None)));
+    assert!(!recorder.record(pull(None, "app", b"v1", None)));
+
+    receiver.recv().await;
+    assert!(recorder.record(pull(None, "app", b"v1", None)));
+}
@@ -0,0 +1,215 @@
+// Tests for READ_ONLY_MODE refusing writes while reads keep working; the
+// authentication bookkeeping tests need a live Postgres (DATABASE_URL)
+
+mod utils;
+
+use aerugo::auth::{create_session, extend_session, hash_api_key, session_active, start_session, verify_api_key};
+use aerugo::read_only::{is_write_request, read_only_middleware, without_writes, writes_paused, ReadOnlyMode};
+use anyhow::Result;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::routing::{get, post};
+use axum::Router;
+use tower::ServiceExt;
+use utils::migrated_pool;
+
+/// Registry and API routes behind the read-only middleware, as in `create_app`
+fn test_app(mode: ReadOnlyMode) -> Router {
+    Router::new()
+        .route(
+            "/v2/acme/app/manifests/latest",
+            get(|| async { "manifest" }).put(|| async { StatusCode::CREATED }).delete(|| async { StatusCode::ACCEPTED }),
+        )
+        .route("/v2/_batch/acme/app/manifests", post(|| async { "manifests" }))
+        .route("/v2/acme/app/blobs/uploads/", post(|| async { StatusCode::ACCEPTED }))
+        .route("/api/v1/repos/acme", get(|| async { "repositories" }).post(|| async { StatusCode::CREATED }))
+        .route("/api/v1/auth/login", post(|| async { "token" }))
+        .layer(axum::middleware::from_fn_with_state(mode, read_only_middleware))
+}
+
+async fn status(app: &Router, method: Method, uri: &str) -> Result<StatusCode> {
+    let request = Request::builder().method(method).uri(uri).body(Body::empty())?;
+    Ok(app.clone().oneshot(request).await?.status())
+}
+
+#[tokio::test]
+async fn test_writes_blocked_in_read_only_mode() -> Result<()> {
+    let app = test_app(ReadOnlyMode::new(true));
+
+    assert_eq!(status(&app, Method::PUT, "/v2/acme/app/manifests/latest").await?, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(status(&app, Method::DELETE, "/v2/acme/app/manifests/latest").await?, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(status(&app, Method::POST, "/v2/acme/app/blobs/uploads/").await?, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(status(&app, Method::POST, "/api/v1/repos/acme").await?, StatusCode::SERVICE_UNAVAILABLE);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reads_succeed_in_read_only_mode() -> Result<()> {
+    let app = test_app(ReadOnlyMode::new(true));
+
+    assert_eq!(status(&app, Method::GET, "/v2/acme/app/manifests/latest").await?, StatusCode::OK);
+    assert_eq!(status(&app, Method::HEAD, "/v2/acme/app/manifests/latest").await?, StatusCode::OK);
+    assert_eq!(status(&app, Method::POST, "/v2/_batch/acme/app/manifests").await?, StatusCode::OK);
+    assert_eq!(status(&app, Method::GET, "/api/v1/repos/acme").await?, StatusCode::OK);
+    assert_eq!(status(&app, Method::POST, "/api/v1/auth/login").await?, StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_error_bodies() -> Result<()> {
+    let app = test_app(ReadOnlyMode::new(true));
+
+    // Both formats tell clients when to retry, in the header and the body
+    let request = Request::put("/v2/acme/app/manifests/latest").body(Body::empty())?;
+    let response = app.clone().oneshot(request).await?;
+    let retry_after: u64 = response.headers()["retry-after"].to_str()?.parse()?;
+    assert!((48..=72).contains(&retry_after), "{}", retry_after);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["errors"][0]["code"], "UNAVAILABLE");
+    assert_eq!(json["errors"][0]["detail"]["retry_after_seconds"], retry_after);
+
+    let request = Request::post("/api/v1/repos/acme").body(Body::empty())?;
+    let response = app.oneshot(request).await?;
+    let retry_after: u64 = response.headers()["retry-after"].to_str()?.parse()?;
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let json: serde_json::Value = serde_json::from_slice(&body)?;
+    assert_eq!(json["error"]["code"], "READ_ONLY");
+    assert_eq!(json["error"]["retry_after_seconds"], retry_after);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_toggling_at_runtime() -> Result<()> {
+    let mode = ReadOnlyMode::new(false);
+    let app = test_app(mode.clone());
+    assert_eq!(status(&app, Method::PUT, "/v2/acme/app/manifests/latest").await?, StatusCode::CREATED);
+
+    mode.set(true);
+    assert_eq!(status(&app, Method::PUT, "/v2/acme/app/manifests/latest").await?, StatusCode::SERVICE_UNAVAILABLE);
+
+    mode.set(false);
+    assert_eq!(status(&app, Method::PUT, "/v2/acme/app/manifests/latest").await?, StatusCode::CREATED);
+    Ok(())
+}
+
+#[test]
+fn test_write_classification() {
+    assert!(!is_write_request(&Method::OPTIONS, "/api/v1/repos/acme"));
+    assert!(is_write_request(&Method::PATCH, "/v2/acme/app/blobs/uploads/1234"));
+    assert!(is_write_request(&Method::POST, "/api/v1/auth/register"));
+    // The toggle itself stays reachable so read-only mode can be switched off
+    assert!(!is_write_request(&Method::PUT, "/admin/read-only"));
+    assert!(is_write_request(&Method::PUT, "/admin/organizations/acme/seats"));
+}
+
+#[tokio::test]
+async fn test_reads_in_read_only_mode_do_not_write() -> Result<()> {
+    let app = |mode: ReadOnlyMode| {
+        Router::new()
+            .route("/v2/acme/app/tags/list", get(|| async { if writes_paused() { "paused" } else { "writing" } }))
+            .layer(axum::middleware::from_fn_with_state(mode, read_only_middleware))
+    };
+    let body = |app: Router| async move {
+        let response = app.oneshot(Request::get("/v2/acme/app/tags/list").body(Body::empty())?).await?;
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        Ok::<_, anyhow::Error>(String::from_utf8(bytes.to_vec())?)
+    };
+
+    assert_eq!(body(app(ReadOnlyMode::new(true))).await?, "paused");
+    assert_eq!(body(app(ReadOnlyMode::new(false))).await?, "writing");
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_authenticating_in_read_only_mode_leaves_last_use_alone() -> Result<()> {
+    let pool = migrated_pool().await?;
+
+    let name = format!("read-only-{}", uuid::Uuid::new_v4().simple());
+    let user_id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO users (username, email, password_hash) VALUES ($1, $1 || '@example.com', 'x') RETURNING id"
+    )
+    .bind(&name)
+    .fetch_one(&pool)
+    .await?;
+    let sid = create_session(&pool, user_id, None).await?;
+    let key = format!("key-{}", name);
+    sqlx::query("INSERT INTO api_keys (user_id, name, key_hash) VALUES ($1, 'ci', $2)")
+        .bind(user_id)
+        .bind(hash_api_key(&key))
+        .execute(&pool)
+        .await?;
+    sqlx::query("UPDATE user_sessions SET last_used_at = NOW() - INTERVAL '1 hour' WHERE id = $1")
+        .bind(sid)
+        .execute(&pool)
+        .await?;
+
+    without_writes(async {
+        assert!(session_active(&pool, sid, user_id).await?);
+        assert_eq!(verify_api_key(&key, &pool, None).await, Ok(user_id));
+        Ok::<_, anyhow::Error>(())
+    })
+    .await?;
+
+    let session_touched = sqlx::query_scalar::<_, bool>(
+        "SELECT last_used_at > NOW() - INTERVAL '1 minute' FROM user_sessions WHERE id = $1"
+    )
+    .bind(sid)
+    .fetch_one(&pool)
+    .await?;
+    let key_used = sqlx::query_scalar::<_, bool>("SELECT last_used_at IS NOT NULL FROM api_keys WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await?;
+    assert!(!session_touched);
+    assert!(!key_used);
+
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_signing_in_and_refreshing_in_read_only_mode_write_no_session() -> Result<()> {
+    let pool = migrated_pool().await?;
+
+    let name = format!("read-only-{}", uuid::Uuid::new_v4().simple());
+    let user_id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO users (username, email, password_hash) VALUES ($1, $1 || '@example.com', 'x') RETURNING id"
+    )
+    .bind(&name)
+    .fetch_one(&pool)
+    .await?;
+    let sid = create_session(&pool, user_id, None).await?;
+    let expires_at = || async {
+        sqlx::query_scalar::<_, chrono::DateTime<chrono::Utc>>("SELECT expires_at FROM user_sessions WHERE id = $1")
+            .bind(sid)
+            .fetch_one(&pool)
+            .await
+    };
+    let before = expires_at().await?;
+
+    without_writes(async {
+        // Login: a token without a session row
+        assert_eq!(start_session(&pool, user_id, Some("docker/24.0")).await?, None);
+        // Refresh: the session is checked but not extended
+        assert!(extend_session(&pool, sid, user_id).await?);
+        Ok::<_, anyhow::Error>(())
+    })
+    .await?;
+
+    let sessions = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM user_sessions WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(sessions, 1);
+    assert_eq!(expires_at().await?, before);
+
+    // A revoked session still cannot be refreshed
+    sqlx::query("UPDATE user_sessions SET revoked_at = NOW() WHERE id = $1").bind(sid).execute(&pool).await?;
+    assert!(!without_writes(extend_session(&pool, sid, user_id)).await?);
+
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await?;
+    Ok(())
+}
@@ -8,13 +8,16 @@ sys.path.insert(0, os.path.dirname(os.path.abspath(__file__)))
 
 try:
     from base_test import BaseTestCase, test_data_manager
-    from config import TEST_USERS, TestUser
+    from config import TEST_USERS, TestUser, SERVER_URL, API_BASE
 except ImportError:
     from .base_test import BaseTestCase, test_data_manager
-    from .config import TEST_USERS, TestUser
+    from .config import TEST_USERS, TestUser, SERVER_URL, API_BASE
 
+import hashlib
+import json
 import random
 import string
+import requests
 
 
 class RepositoryTests(BaseTestCase):
@@ -384,6 +387,325 @@ class RepositoryTests(BaseTestCase):
         
     # Non-owner try delete (handler currently lacks auth/permission check)
 
+    def push_test_image(self, org_name, repo_name, token):
+        """Push a config blob and a manifest referencing it, returning the manifest digest"""
+        auth = {"Authorization": f"Bearer {token}"}
+        base = f"{SERVER_URL}/v2/{org_name}/{repo_name}"
+
+        config = json.dumps({"architecture": "amd64", "os": "linux",
+                             "rootfs": {"type": "layers", "diff_ids": []}}).encode()
+        config_digest = "sha256:" + hashlib.sha256(config).hexdigest()
+
+        start = requests.post(f"{base}/blobs/uploads/", headers=auth)
+        self.assert_response(start, 202, "Failed to start config blob upload")
+        upload_url = f"{SERVER_URL}{start.headers['Location']}"
+        chunk = requests.patch(upload_url, data=config, headers=auth)
+        self.assert_response(chunk, 202, "Failed to upload config blob")
+        complete = requests.put(f"{upload_url}?digest={config_digest}", headers=auth)
+        self.assert_response(complete, 201, "Failed to complete config blob upload")
+
+        manifest = json.dumps({
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.docker.distribution.manifest.v2+json",
+            "config": {
+                "mediaType": "application/vnd.docker.container.image.v1+json",
+                "size": len(config),
+                "digest": config_digest
+            },
+            "layers": []
+        })
+        push = requests.put(f"{base}/manifests/latest", data=manifest, headers={
+            **auth, "Content-Type": "application/vnd.docker.distribution.manifest.v2+json"})
+        self.assert_response(push, 201, "Failed to push manifest")
+        return config_digest
+
+    def test_anonymous_and_authenticated_pull(self):
+        """Test that anonymous and authenticated callers share the same pull routes"""
+        self.logger.info("Testing anonymous vs authenticated pull")
+
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        self.create_dynamic_org(owner)
+        org_name = self.current_org["name"]
+
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        repos = {}
+        for visibility, is_public in (("public", True), ("private", False)):
+            repo_data = {
+                "name": f"pull{visibility}_{session_id}",
+                "description": f"{visibility} pull test repo",
+                "is_public": is_public
+            }
+            response = self.make_request("POST", f"/repos/{org_name}", data=repo_data, token=owner.token)
+            self.assert_response(response, 201, f"Failed to create {visibility} repo")
+            config_digest = self.push_test_image(org_name, repo_data["name"], owner.token)
+            repos[visibility] = (repo_data["name"], config_digest)
+
+        auth = {"Authorization": f"Bearer {owner.token}"}
+        for visibility, (repo_name, config_digest) in repos.items():
+            manifest_url = f"{SERVER_URL}/v2/{org_name}/{repo_name}/manifests/latest"
+            blob_url = f"{SERVER_URL}/v2/{org_name}/{repo_name}/blobs/{config_digest}"
+
+            # Authenticated callers resolve their identity and can pull either repo
+            self.assert_response(requests.get(manifest_url, headers=auth), 200,
+                                 f"Owner should pull {visibility} manifest")
+            self.assert_response(requests.get(blob_url, headers=auth), 200,
+                                 f"Owner should pull {visibility} blob")
+
+            # Anonymous callers can pull public content and are challenged otherwise
+            expected = 200 if visibility == "public" else 401
+            anonymous = requests.get(manifest_url)
+            self.assert_response(anonymous, expected, f"Anonymous {visibility} manifest pull")
+            self.assert_response(requests.get(blob_url), expected, f"Anonymous {visibility} blob pull")
+            if expected == 401:
+                assert "WWW-Authenticate" in anonymous.headers, "Anonymous pull should be challenged"
+
+        self.logger.info("✅ Anonymous vs authenticated pull test passed")
+
+    def test_manifest_deletion(self):
+        """Test deleting a tag and a manifest under the default (async) deletion mode"""
+        self.logger.info("Testing manifest deletion")
+
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        self.create_dynamic_org(owner)
+        org_name = self.current_org["name"]
+
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        repo_name = f"delmanifest_{session_id}"
+        response = self.make_request("POST", f"/repos/{org_name}",
+                                     data={"name": repo_name, "description": "Manifest deletion test"},
+                                     token=owner.token)
+        self.assert_response(response, 201, "Failed to create repository")
+        self.push_test_image(org_name, repo_name, owner.token)
+
+        auth = {"Authorization": f"Bearer {owner.token}"}
+        base = f"{SERVER_URL}/v2/{org_name}/{repo_name}"
+        digest = requests.head(f"{base}/manifests/latest", headers=auth).headers["Docker-Content-Digest"]
+
+        self.assert_response(requests.delete(f"{base}/manifests/{digest}"), 401, "Anonymous manifest deletion")
+
+        # Cleanup runs in the background, so the delete is only accepted
+        deletion = requests.delete(f"{base}/manifests/{digest}", headers=auth)
+        self.assert_response(deletion, 202, "Manifest delete should be accepted")
+        assert deletion.headers.get("X-Deletion-ID"), "Async delete should return a tracking id"
+
+        self.assert_response(requests.get(f"{base}/manifests/{digest}", headers=auth), 404, "Manifest should be gone")
+        self.assert_response(requests.get(f"{base}/manifests/latest", headers=auth), 404, "Tag should go with its manifest")
+        self.assert_response(requests.delete(f"{base}/manifests/{digest}", headers=auth), 404, "Second delete")
+
+        self.logger.info("✅ Manifest deletion test passed")
+
+    def test_registry_repository_deletion(self):
+        """Test that deleting a repository removes its tags and manifests and reports counts"""
+        self.logger.info("Testing registry repository deletion")
+
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        self.create_dynamic_org(owner)
+        org_name = self.current_org["name"]
+
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        repo_name = f"deleteme_{session_id}"
+        response = self.make_request("POST", f"/repos/{org_name}",
+                                     data={"name": repo_name, "description": "Repository deletion test"},
+                                     token=owner.token)
+        self.assert_response(response, 201, "Failed to create repository")
+        self.push_test_image(org_name, repo_name, owner.token)
+
+        auth = {"Authorization": f"Bearer {owner.token}"}
+        base = f"{SERVER_URL}/v2/{org_name}/{repo_name}"
+
+        # Point a second tag at the same manifest
+        manifest = requests.get(f"{base}/manifests/latest", headers=auth)
+        self.assert_response(manifest, 200, "Failed to fetch pushed manifest")
+        retag = requests.put(f"{base}/manifests/v1", data=manifest.content, headers={
+            **auth, "Content-Type": manifest.headers["Content-Type"]})
+        self.assert_response(retag, 201, "Failed to push second tag")
+
+        # Anonymous callers cannot delete
+        self.assert_response(requests.delete(base), 401, "Anonymous repository deletion")
+
+        deletion = requests.delete(base, headers=auth)
+        self.assert_response(deletion, 200, "Owner should delete the repository")
+        summary = deletion.json()
+        assert summary["name"] == f"{org_name}/{repo_name}"
+        assert summary["tags_deleted"] == 2, f"Unexpected tag count: {summary}"
+        assert summary["manifests_deleted"] == 1, f"Unexpected manifest count: {summary}"
+        assert summary["blobs_queued_for_cleanup"] == 1, f"Unexpected blob count: {summary}"
+
+        # Nothing of the repository is left
+        repo_response = self.make_request("GET", f"/repos/{org_name}/repositories/{repo_name}", token=owner.token)
+        self.assert_response(repo_response, 404, "Repository metadata should be gone")
+        self.assert_response(requests.get(f"{base}/manifests/latest", headers=auth), 404, "Manifest should be gone")
+        self.assert_response(requests.delete(base, headers=auth), 404, "Repository should be gone")
+
+        self.logger.info("✅ Registry repository deletion test passed")
+
+    def test_content_digest_headers(self):
+        """Test that manifest and blob responses carry the correct Docker-Content-Digest"""
+        self.logger.info("Testing Docker-Content-Digest headers")
+
+        owner = self.create_dynamic_owner()
+        self.current_owner = owner
+        self.create_dynamic_org(owner)
+        org_name = self.current_org["name"]
+
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        repo_name = f"digest_{session_id}"
+        response = self.make_request("POST", f"/repos/{org_name}",
+                                     data={"name": repo_name, "is_public": False}, token=owner.token)
+        self.assert_response(response, 201, "Failed to create repository")
+        config_digest = self.push_test_image(org_name, repo_name, owner.token)
+
+        auth = {"Authorization": f"Bearer {owner.token}"}
+        base = f"{SERVER_URL}/v2/{org_name}/{repo_name}"
+
+        # Manifest GET, HEAD and PUT all report the digest of the manifest bytes
+        manifest = requests.get(f"{base}/manifests/latest", headers=auth)
+        self.assert_response(manifest, 200, "Failed to fetch manifest")
+        manifest_digest = "sha256:" + hashlib.sha256(manifest.content).hexdigest()
+        assert manifest.headers.get("Docker-Content-Digest") == manifest_digest
+
+        head = requests.head(f"{base}/manifests/latest", headers=auth)
+        self.assert_response(head, 200, "Failed to HEAD manifest")
+        assert head.headers.get("Docker-Content-Digest") == manifest_digest
+
+        put = requests.put(f"{base}/manifests/v1", data=manifest.content, headers={
+            **auth, "Content-Type": manifest.headers["Content-Type"]})
+        self.assert_response(put, 201, "Failed to push manifest")
+        assert put.headers.get("Docker-Content-Digest") == manifest_digest
+
+        # Blob GET and HEAD report the requested digest
+        blob = requests.get(f"{base}/blobs/{config_digest}", headers=auth)
+        self.assert_response(blob, 200, "Failed to fetch config blob")
+        assert blob.headers.get("Docker-Content-Digest") == config_digest
+        assert "sha256:" + hashlib.sha256(blob.content).hexdigest() == config_digest
+
+        blob_head = requests.head(f"{base}/blobs/{config_digest}", headers=auth)
+        self.assert_response(blob_head, 200, "Failed to HEAD config blob")
+        assert blob_head.headers.get("Docker-Content-Digest") == config_digest
+        assert blob_head.headers.get("Content-Length") == str(len(blob.content))
+
+        self.logger.info("✅ Docker-Content-Digest headers test passed")
+
+    def test_duplicate_display_names_allowed_by_default(self):
+        """Test that display names may repeat when ENFORCE_UNIQUE_DISPLAY_NAMES is off"""
+        self.logger.info("Testing duplicate display names with the policy disabled")
+
+        owner = self.create_dynamic_owner()
+        self.create_dynamic_org(owner)
+        org_name = self.current_org["name"]
+
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        for suffix in ("a", "b"):
+            repo_data = {
+                "name": f"displayrepo_{session_id}_{suffix}",
+                "display_name": "Shared Display Name",
+                "is_public": False
+            }
+            response = self.make_request("POST", f"/repos/{org_name}", data=repo_data, token=owner.token)
+            self.assert_response(response, 201, f"Repository {repo_data['name']} should be created")
+            assert response.json()["display_name"] == "Shared Display Name"
+
+        self.logger.info("✅ Duplicate display names test passed")
+
+    def test_repository_creation_requires_membership(self):
+        """Test that members may create repositories and non-members are denied"""
+        self.logger.info("Testing organization membership check on repository creation")
+
+        owner = self.create_dynamic_owner()
+        member = self.create_dynamic_member()
+        outsider = self.create_dynamic_member()
+        org = self.create_dynamic_org(owner)
+
+        add_response = self.make_request("POST", f"/organizations/{org['id']}/members", data={
+            "email": member.email,
+            "role": "Member"
+        }, token=owner.token)
+        self.assert_response(add_response, 201, "Failed to add member")
+
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        member_repo = {"name": f"memberrepo_{session_id}", "is_public": False}
+        response = self.make_request("POST", f"/repos/{org['name']}", data=member_repo, token=member.token)
+        self.assert_response(response, 201, "Member should be able to create a repository")
+        assert response.json()["created_by"] == member.user_id
+
+        outsider_repo = {"name": f"outsiderrepo_{session_id}", "is_public": False}
+        response = self.make_request("POST", f"/repos/{org['name']}", data=outsider_repo, token=outsider.token)
+        self.assert_response(response, 403, "Non-member should not be able to create a repository")
+
+        self.logger.info("✅ Repository creation membership test passed")
+
+    def follow_pages(self, url, token, items_key):
+        """Fetch every page of a list endpoint two items at a time by following its Link header"""
+        pages = []
+        next_url = f"{url}{'&' if '?' in url else '?'}n=2"
+        while next_url:
+            response = self.make_request("GET", next_url, token=token)
+            self.assert_response(response, 200, f"Failed to list {next_url}")
+            data = response.json()
+            pages.append(data[items_key] if items_key else data)
+
+            link = response.headers.get("Link")
+            if link is None:
+                break
+            assert link.startswith("</") and link.endswith('>; rel="next"'), f"Unexpected Link header: {link}"
+            assert "n=2" in link and "last=" in link, f"Link header lacks n/last: {link}"
+            next_url = f"{SERVER_URL}{link[1:link.index('>')]}"
+            assert len(pages) < 10, "Pagination did not terminate"
+        return pages
+
+    def test_link_header_pagination(self):
+        """Test that list endpoints page with n/last and link the next page"""
+        self.logger.info("Testing Link header pagination")
+
+        owner = self.create_dynamic_owner()
+        org = self.create_dynamic_org(owner)
+        org_name = org["name"]
+        for _ in range(2):
+            member = self.create_dynamic_member()
+            response = self.make_request("POST", f"/organizations/{org['id']}/members", data={
+                "email": member.email,
+                "role": "Member"
+            }, token=owner.token)
+            self.assert_response(response, 201, "Failed to add member")
+
+        session_id = ''.join(random.choices(string.ascii_lowercase + string.digits, k=6))
+        repo_names = [f"pagerepo_{session_id}_{suffix}" for suffix in ("a", "b", "c")]
+        for repo_name in repo_names:
+            response = self.make_request("POST", f"/repos/{org_name}",
+                                         data={"name": repo_name, "is_public": False}, token=owner.token)
+            self.assert_response(response, 201, f"Failed to create {repo_name}")
+
+        # Tag the same manifest three times
+        self.push_test_image(org_name, repo_names[0], owner.token)
+        auth = {"Authorization": f"Bearer {owner.token}"}
+        manifest_url = f"{SERVER_URL}/v2/{org_name}/{repo_names[0]}/manifests"
+        manifest = requests.get(f"{manifest_url}/latest", headers=auth)
+        self.assert_response(manifest, 200, "Failed to fetch pushed manifest")
+        for tag in ("v1", "v2"):
+            push = requests.put(f"{manifest_url}/{tag}", data=manifest.content, headers={
+                **auth, "Content-Type": manifest.headers["Content-Type"]})
+            self.assert_response(push, 201, f"Failed to tag {tag}")
+
+        catalog = self.follow_pages(f"{SERVER_URL}/v2/_catalog", owner.token, "repositories")
+        assert catalog == [[f"{org_name}/{name}" for name in repo_names[:2]], [f"{org_name}/{repo_names[2]}"]], catalog
+
+        tags = self.follow_pages(f"{SERVER_URL}/v2/{org_name}/{repo_names[0]}/tags/list", owner.token, "tags")
+        assert tags == [["latest", "v1"], ["v2"]], tags
+
+        repos = self.follow_pages(f"{API_BASE}/repos/repositories?namespace={org_name}", owner.token, None)
+        assert [[repo["name"] for repo in page] for page in repos] == [repo_names[:2], repo_names[2:]], repos
+
+        members = self.follow_pages(f"{API_BASE}/organizations/{org['id']}/members", owner.token, "members")
+        usernames = [[member["username"] for member in page] for page in members]
+        assert [len(page) for page in usernames] == [2, 1], usernames
+        flat = [name for page in usernames for name in page]
+        assert flat == sorted(flat), f"Members should be ordered by username: {flat}"
+
+        self.logger.info("✅ Link header pagination test passed")
+
     def run_all_tests(self):
         """Run all repository tests"""
         self.logger.info("=== Running repository Tests ===")
@@ -393,6 +715,13 @@ class RepositoryTests(BaseTestCase):
         self.test_list_repositories()
         self.test_get_repository()
         self.test_delete_repository()
+        self.test_anonymous_and_authenticated_pull()
+        self.test_registry_repository_deletion()
+        self.test_manifest_deletion()
+        self.test_content_digest_headers()
+        self.test_duplicate_display_names_allowed_by_default()
+        self.test_repository_creation_requires_membership()
+        self.test_link_header_pagination()
         # self.test_set_repository_permissions()
         # self.test_repository_permissions()
         
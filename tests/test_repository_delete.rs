@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::handlers::docker_registry_v2::{manifest_blob_digests, manifest_referenced_digests};
+
+    #[test]
+    fn test_image_manifest_references_config_and_layers() {
+        let manifest = r#"{
+            "schemaVersion": 2,
+            "config": {"mediaType": "application/vnd.oci.image.config.v1+json", "size": 10, "digest": "sha256:cfg"},
+            "layers": [
+                {"mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "size": 20, "digest": "sha256:layer2"},
+                {"mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "size": 30, "digest": "sha256:layer1"},
+                {"mediaType": "application/vnd.oci.image.layer.v1.tar+gzip", "size": 30, "digest": "sha256:layer1"}
+            ]
+        }"#;
+
+        // Shared layers are only queued once
+        assert_eq!(
+            manifest_blob_digests(manifest),
+            vec!["sha256:cfg", "sha256:layer1", "sha256:layer2"]
+        );
+    }
+
+    #[test]
+    fn test_index_and_invalid_content_reference_no_blobs() {
+        let index = r#"{
+            "schemaVersion": 2,
+            "manifests": [{"mediaType": "application/vnd.oci.image.manifest.v1+json", "size": 1, "digest": "sha256:child"}]
+        }"#;
+
+        assert!(manifest_blob_digests(index).is_empty());
+        assert!(manifest_blob_digests("not json").is_empty());
+    }
+
+    #[test]
+    fn test_index_references_its_child_manifests() {
+        let index = r#"{
+            "schemaVersion": 2,
+            "manifests": [
+                {"mediaType": "application/vnd.oci.image.manifest.v1+json", "size": 1, "digest": "sha256:arm"},
+                {"mediaType": "application/vnd.oci.image.manifest.v1+json", "size": 1, "digest": "sha256:amd"}
+            ]
+        }"#;
+        assert_eq!(manifest_referenced_digests(index), vec!["sha256:amd", "sha256:arm"]);
+
+        let manifest = r#"{"config": {"digest": "sha256:cfg"}, "layers": [{"digest": "sha256:layer"}]}"#;
+        assert_eq!(manifest_referenced_digests(manifest), vec!["sha256:cfg", "sha256:layer"]);
+        assert!(manifest_referenced_digests("not json").is_empty());
+    }
+}
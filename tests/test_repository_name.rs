@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::models::repository::{check_repository_depth, RepositoryName, RepositoryNameError};
+
+    #[test]
+    fn test_valid_names() {
+        for name in [
+            "ubuntu",
+            "library/ubuntu",
+            "my-org/my-app",
+            "org/team/app.v2",
+            "a__b",
+            "a---b",
+            "app_2",
+            "0x",
+        ] {
+            let parsed = RepositoryName::parse(name).unwrap_or_else(|e| panic!("{} rejected: {}", name, e));
+            assert_eq!(parsed.as_str(), name);
+        }
+    }
+
+    #[test]
+    fn test_uppercase_rejected_with_suggestion() {
+        assert_eq!(
+            RepositoryName::parse("MyOrg/App"),
+            Err(RepositoryNameError::Uppercase { suggestion: "myorg/app".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_confusable_characters_rejected() {
+        // Cyrillic 'а', fullwidth solidus and a non-breaking hyphen look like valid characters
+        assert_eq!(RepositoryName::parse("lib\u{0430}ry"), Err(RepositoryNameError::NonAscii('\u{0430}')));
+        assert_eq!(RepositoryName::parse("org\u{ff0f}app"), Err(RepositoryNameError::NonAscii('\u{ff0f}')));
+        assert_eq!(RepositoryName::parse("my\u{2011}app"), Err(RepositoryNameError::NonAscii('\u{2011}')));
+        // Uppercase look-alikes from other scripts are not folded into ASCII
+        assert_eq!(RepositoryName::parse("\u{0391}pp"), Err(RepositoryNameError::NonAscii('\u{0391}')));
+    }
+
+    #[test]
+    fn test_invalid_structure_rejected() {
+        assert_eq!(RepositoryName::parse(""), Err(RepositoryNameError::Empty));
+        assert_eq!(RepositoryName::parse("my app"), Err(RepositoryNameError::InvalidCharacter(' ')));
+        assert_eq!(RepositoryName::parse("app:latest"), Err(RepositoryNameError::InvalidCharacter(':')));
+        for name in ["-app", "app-", "a..b", "a___b", "a._b", "org//app", "/app", "app/"] {
+            assert!(
+                matches!(RepositoryName::parse(name), Err(RepositoryNameError::InvalidComponent(_))),
+                "{} should be rejected",
+                name
+            );
+        }
+        assert_eq!(RepositoryName::parse(&"a".repeat(256)), Err(RepositoryNameError::TooLong));
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let at_limit = RepositoryName::parse("acme/team/app").unwrap();
+        assert_eq!(at_limit.check_depth(3), Ok(()));
+
+        let over_limit = RepositoryName::parse("acme/team/sub/app").unwrap();
+        assert_eq!(
+            over_limit.check_depth(3),
+            Err(RepositoryNameError::TooDeep { segments: 4, max: 3 })
+        );
+        assert_eq!(check_repository_depth("acme/team/sub/app", 4), Ok(()));
+        assert_eq!(check_repository_depth("app", 1), Ok(()));
+    }
+
+    #[test]
+    fn test_namespace_split_and_serde() {
+        let name: RepositoryName = "acme/web".parse().unwrap();
+        assert_eq!(name.split_namespace(), Some(("acme", "web")));
+        assert_eq!(RepositoryName::parse("web").unwrap().split_namespace(), None);
+
+        assert_eq!(serde_json::to_string(&name).unwrap(), "\"acme/web\"");
+        assert!(serde_json::from_str::<RepositoryName>("\"Acme/web\"").is_err());
+    }
+}
@@ -0,0 +1,76 @@
+// Tests for one-time nonces on organization deletion and ownership transfer
+
+use aerugo::error::AppError;
+use aerugo::nonce::{check_nonce, issue_nonce, nonce_key, LocalNonces, NonceAction, NonceStore, NONCE_HEADER};
+use anyhow::Result;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use std::time::Duration;
+
+fn with_nonce(nonce: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(NONCE_HEADER, HeaderValue::from_str(nonce).unwrap());
+    headers
+}
+
+fn status(error: &anyhow::Error) -> StatusCode {
+    error.downcast_ref::<AppError>().expect("typed error").status_code()
+}
+
+#[tokio::test]
+async fn test_nonce_accepted_once() -> Result<()> {
+    let store = LocalNonces::default();
+    let nonce = issue_nonce(&store, 7, NonceAction::DeleteOrganization).await?;
+    let headers = with_nonce(&nonce);
+
+    check_nonce(&store, &headers, 7, NonceAction::DeleteOrganization, true).await?;
+
+    // Replaying the same request is refused
+    let error = check_nonce(&store, &headers, 7, NonceAction::DeleteOrganization, true).await.unwrap_err();
+    assert_eq!(status(&error), StatusCode::CONFLICT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nonce_bound_to_user_and_action() -> Result<()> {
+    let store = LocalNonces::default();
+    let nonce = issue_nonce(&store, 7, NonceAction::TransferOwnership).await?;
+    let headers = with_nonce(&nonce);
+
+    let error = check_nonce(&store, &headers, 8, NonceAction::TransferOwnership, false).await.unwrap_err();
+    assert_eq!(status(&error), StatusCode::CONFLICT);
+    let error = check_nonce(&store, &headers, 7, NonceAction::DeleteOrganization, false).await.unwrap_err();
+    assert_eq!(status(&error), StatusCode::CONFLICT);
+
+    // Failed attempts elsewhere do not spend it
+    check_nonce(&store, &headers, 7, NonceAction::TransferOwnership, false).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unknown_and_expired_nonces_rejected() -> Result<()> {
+    let store = LocalNonces::default();
+    let error = check_nonce(&store, &with_nonce("made-up"), 7, NonceAction::DeleteOrganization, false)
+        .await
+        .unwrap_err();
+    assert_eq!(status(&error), StatusCode::CONFLICT);
+
+    store.store(&nonce_key(7, NonceAction::DeleteOrganization, "short-lived"), Duration::from_millis(10)).await?;
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    let error = check_nonce(&store, &with_nonce("short-lived"), 7, NonceAction::DeleteOrganization, false)
+        .await
+        .unwrap_err();
+    assert_eq!(status(&error), StatusCode::CONFLICT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_nonce_only_refused_when_required() -> Result<()> {
+    let store = LocalNonces::default();
+    check_nonce(&store, &HeaderMap::new(), 7, NonceAction::DeleteOrganization, false).await?;
+
+    let error = check_nonce(&store, &HeaderMap::new(), 7, NonceAction::DeleteOrganization, true)
+        .await
+        .unwrap_err();
+    assert_eq!(status(&error), StatusCode::BAD_REQUEST);
+    Ok(())
+}
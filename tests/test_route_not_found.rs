@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::error::route_not_found;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/api/v1/health", get(|| async { "ok" }))
+            .fallback(route_not_found)
+    }
+
+    async fn get_json(path: &str) -> (StatusCode, serde_json::Value) {
+        let response = app()
+            .oneshot(Request::builder().uri(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_api_route_returns_structured_error() {
+        let path = format!("/api/v1/{}", uuid::Uuid::new_v4());
+        let (status, body) = get_json(&path).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "NOT_FOUND");
+        assert_eq!(body["error"]["message"], "Route not found");
+        assert!(!body["error"]["correlation_id"].as_str().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_registry_route_keeps_oci_format() {
+        let (status, body) = get_json("/v2/some/unknown/endpoint").await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.get("error").is_none());
+        assert_eq!(body["errors"][0]["code"], "UNSUPPORTED");
+        assert_eq!(body["errors"][0]["message"], "Route not found");
+    }
+}
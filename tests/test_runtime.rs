@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::config::settings::ServerSettings;
+    use aerugo::runtime::build_runtime;
+
+    fn server_settings(worker_threads: Option<usize>, max_blocking_threads: Option<usize>) -> ServerSettings {
+        ServerSettings {
+            bind_address: "127.0.0.1:3000".to_string(),
+            port: 3000,
+            api_prefix: "/api/v1".to_string(),
+            log_level: "info".to_string(),
+            worker_threads,
+            max_blocking_threads,
+            enable_server_timing: false,
+            enable_hsts: false,
+            hsts_max_age_secs: 31_536_000,
+            enable_profiling: false,
+            enable_compression: false,
+            correlation_header: "x-correlation-id".to_string(),
+            shutdown_drain_delay_secs: 0,
+            strict_field_selection: false,
+            retry_after_jitter_percent: 0,
+            trusted_proxies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_runtime_uses_configured_worker_threads() {
+        let runtime = build_runtime(&server_settings(Some(3), Some(16))).unwrap();
+        assert_eq!(runtime.metrics().num_workers(), 3);
+    }
+
+    #[test]
+    fn test_runtime_defaults_without_configuration() {
+        let runtime = build_runtime(&server_settings(None, None)).unwrap();
+        assert!(runtime.metrics().num_workers() >= 1);
+
+        // The runtime is usable for async work
+        assert_eq!(runtime.block_on(async { 1 + 1 }), 2);
+    }
+}
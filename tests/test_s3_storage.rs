@@ -3,7 +3,7 @@
 // Complementary to test_s3_storage_python.py which tests the HTTP API endpoints
 
 use aerugo::storage::{
-    s3::{S3AuthMethod, S3Config, S3Storage},
+    s3::{copy_part_ranges, S3AuthMethod, S3Config, S3Storage, MAX_SINGLE_COPY_BYTES},
     Storage,
 };
 use bytes::Bytes;
@@ -113,6 +113,25 @@ async fn test_error_handling() {
     assert!(result.is_err(), "Expected error with invalid credentials");
 }
 
+#[test]
+fn test_copy_part_ranges_cover_the_object() {
+    const MIB: u64 = 1024 * 1024;
+
+    let ranges = copy_part_ranges(12 * MIB, 5 * MIB);
+    assert_eq!(ranges, vec![(0, 5 * MIB - 1), (5 * MIB, 10 * MIB - 1), (10 * MIB, 12 * MIB - 1)]);
+
+    // Parts smaller than S3 accepts are raised to 5 MiB
+    assert_eq!(copy_part_ranges(6 * MIB, MIB).len(), 2);
+
+    // Objects too large for a single copy stay within 10,000 parts
+    let size = 2 * MAX_SINGLE_COPY_BYTES * 1024;
+    let ranges = copy_part_ranges(size, 10 * MIB);
+    assert!(ranges.len() <= 10_000, "{} parts", ranges.len());
+    assert_eq!(ranges.first().map(|r| r.0), Some(0));
+    assert_eq!(ranges.last().map(|r| r.1), Some(size - 1));
+    assert!(ranges.windows(2).all(|pair| pair[0].1 + 1 == pair[1].0));
+}
+
 fn setup_test_config() -> S3Config {
     // Replace these values with your test environment values
     S3Config {
@@ -0,0 +1,49 @@
+// Tests for organization seat limits
+
+use aerugo::error::{error_response, AppError};
+use aerugo::handlers::admin::{OrganizationSeats, StorageUsageReport};
+use aerugo::models::organizations::seats_remaining;
+use axum::http::StatusCode;
+
+#[test]
+fn test_adding_up_to_the_limit_is_allowed() {
+    // Two of three seats taken: the third member may join
+    assert_eq!(seats_remaining(Some(3), 2), Some(1));
+}
+
+#[test]
+fn test_adding_one_over_the_limit_is_refused() {
+    assert_eq!(seats_remaining(Some(3), 3), Some(0));
+    // A limit lowered below the member count leaves no seats, not negative ones
+    assert_eq!(seats_remaining(Some(3), 5), Some(0));
+
+    let err: anyhow::Error = AppError::SeatLimitExceeded { max_members: 3 }.into();
+    let (status, body) = error_response(&err, StatusCode::BAD_REQUEST);
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body.0["code"], "SEAT_LIMIT_EXCEEDED");
+    assert_eq!(body.0["error"], "Organization has used all 3 of its seats");
+}
+
+#[test]
+fn test_no_limit_is_unlimited() {
+    assert_eq!(seats_remaining(None, 10_000), None);
+}
+
+#[test]
+fn test_usage_report_lists_seats() {
+    let mut report = StorageUsageReport::compute(Vec::new());
+    report.add_seats(
+        vec![
+            OrganizationSeats { organization: "globex".to_string(), members: 4, max_members: Some(10) },
+            OrganizationSeats { organization: "acme".to_string(), members: 2, max_members: None },
+        ],
+        Some(5),
+    );
+
+    let names: Vec<&str> = report.organizations.iter().map(|o| o.organization.as_str()).collect();
+    assert_eq!(names, vec!["acme", "globex"]);
+    let acme = &report.organizations[0];
+    assert_eq!((acme.members, acme.max_members, acme.seats_remaining), (2, Some(5), Some(3)));
+    let globex = &report.organizations[1];
+    assert_eq!((globex.members, globex.max_members, globex.seats_remaining), (4, Some(10), Some(6)));
+}
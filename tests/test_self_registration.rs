@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::auth::{is_admin_request, registration_allowed, ADMIN_TOKEN_HEADER};
+    use aerugo::auth_events::AuthEventDestination;
+    use aerugo::config::settings::AuthSettings;
+    use axum::http::{HeaderMap, HeaderValue};
+    use secrecy::Secret;
+
+    fn auth_settings(allow_self_registration: bool, admin_token: Option<&str>) -> AuthSettings {
+        AuthSettings {
+            jwt_secret: Secret::new("registration-test-secret".to_string()),
+            jwt_min_secret_bytes: 0,
+            jwt_expiration_seconds: 3600,
+            refresh_token_expiration_seconds: 604800,
+            jwt_max_age_seconds: None,
+            introspection_secret: None,
+            allow_self_registration,
+            reserved_usernames: Vec::new(),
+            require_request_nonce: false,
+            auth_event_destination: AuthEventDestination::Log,
+            admin_token: admin_token.map(|t| Secret::new(t.to_string())),
+            login_challenge_threshold: 5,
+            login_challenge_window_secs: 900,
+            captcha_verify_url: None,
+            captcha_secret: None,
+        }
+    }
+
+    fn with_admin_token(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ADMIN_TOKEN_HEADER, HeaderValue::from_str(token).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_registration_open_when_enabled() {
+        let settings = auth_settings(true, None);
+        assert!(registration_allowed(&HeaderMap::new(), &settings));
+    }
+
+    #[test]
+    fn test_registration_blocked_when_disabled() {
+        let settings = auth_settings(false, Some("s3cret"));
+        assert!(!registration_allowed(&HeaderMap::new(), &settings));
+        assert!(!registration_allowed(&with_admin_token("wrong"), &settings));
+    }
+
+    #[test]
+    fn test_admin_token_bypasses_disabled_registration() {
+        let settings = auth_settings(false, Some("s3cret"));
+        assert!(registration_allowed(&with_admin_token("s3cret"), &settings));
+    }
+
+    #[test]
+    fn test_no_admin_access_without_configured_token() {
+        let settings = auth_settings(false, None);
+        assert!(!is_admin_request(&with_admin_token(""), &settings));
+        assert!(!registration_allowed(&with_admin_token("anything"), &settings));
+    }
+}
@@ -0,0 +1,76 @@
+// Tests for the Server-Timing header breakdown
+
+use aerugo::db::{self, Timed};
+use aerugo::server_timing::{collect, with_server_timing, TimingGuard, TimingMetric, SERVER_TIMING};
+use aerugo::storage::filesystem::FilesystemStorage;
+use aerugo::storage::timed::TimedStorage;
+use aerugo::storage::Storage;
+use axum::{body::Body, http::Request, routing::get, Router};
+use bytes::Bytes;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+async fn handler() -> &'static str {
+    let storage = TimedStorage::new(Arc::new(FilesystemStorage::new(
+        std::env::temp_dir().join(format!("aerugo-server-timing-{}", uuid::Uuid::new_v4())),
+    )));
+    storage.put_blob("blobs/sha256:timing", Bytes::from_static(b"data")).await.unwrap();
+    storage.get_blob("blobs/sha256:timing").await.unwrap();
+
+    {
+        let _timing = TimingGuard::start(TimingMetric::Db);
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+    "ok"
+}
+
+#[tokio::test]
+async fn test_server_timing_header_has_expected_metrics() {
+    let app = Router::new()
+        .route("/", get(handler))
+        .layer(axum::middleware::from_fn(|request: axum::extract::Request, next: axum::middleware::Next| {
+            with_server_timing(next.run(request))
+        }));
+
+    let response = app
+        .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    let header = response.headers()[SERVER_TIMING].to_str().unwrap().to_string();
+    let metrics: Vec<&str> = header
+        .split(", ")
+        .map(|entry| entry.split(";dur=").next().unwrap())
+        .collect();
+    assert_eq!(metrics, vec!["db", "cache", "storage", "total"]);
+
+    let duration = |name: &str| -> f64 {
+        header
+            .split(", ")
+            .find_map(|entry| entry.strip_prefix(&format!("{};dur=", name)))
+            .unwrap()
+            .parse()
+            .unwrap()
+    };
+    assert!(duration("db") >= 5.0);
+    assert!(duration("storage") > 0.0);
+    assert_eq!(duration("cache"), 0.0);
+    assert!(duration("total") >= duration("db"));
+}
+
+#[tokio::test]
+async fn test_recording_outside_a_request_is_ignored() {
+    // No timing scope: must not panic
+    let _timing = TimingGuard::start(TimingMetric::Cache);
+}
+
+#[tokio::test]
+async fn test_timed_queries_count_towards_db() {
+    let ((), timings) = collect(async {
+        tokio::time::sleep(std::time::Duration::from_millis(5)).timed("first_query").await;
+        db::timed("second_query", tokio::time::sleep(std::time::Duration::from_millis(5))).await;
+    })
+    .await;
+
+    assert!(timings.db >= std::time::Duration::from_millis(10), "{:?}", timings);
+}
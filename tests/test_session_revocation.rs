@@ -0,0 +1,97 @@
+// Tests for rejecting tokens of revoked sessions; needs a live Postgres (DATABASE_URL)
+
+mod utils;
+
+use aerugo::auth::{bearer_user_id, create_session, extract_user_id};
+use aerugo::handlers::auth::{introspect_session, Claims};
+use anyhow::Result;
+use axum::http::{header::AUTHORIZATION, HeaderMap, HeaderValue, StatusCode};
+use axum_extra::headers::{authorization::Bearer, Authorization};
+use axum_extra::TypedHeader;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use sqlx::PgPool;
+use utils::{auth_settings, migrated_pool};
+
+const SECRET: &str = "session-revocation-test-secret";
+
+async fn create_user(pool: &PgPool) -> Result<i64> {
+    let name = format!("sessions-{}", uuid::Uuid::new_v4().simple());
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO users (username, email, password_hash) VALUES ($1, $1 || '@example.com', 'x') RETURNING id"
+    )
+    .bind(&name)
+    .fetch_one(pool)
+    .await?;
+    Ok(id)
+}
+
+fn session_token(user_id: i64, sid: i64) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let claims = Claims {
+        sub: user_id.to_string(),
+        exp: (now + 3600) as usize,
+        iat: Some(now as usize),
+        sid: Some(sid),
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET.as_bytes())).unwrap()
+}
+
+fn bearer(token: &str) -> Option<TypedHeader<Authorization<Bearer>>> {
+    Some(TypedHeader(Authorization::bearer(token).unwrap()))
+}
+
+fn bearer_headers(token: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+    headers
+}
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_revoked_session_token_is_rejected_everywhere() -> Result<()> {
+    let pool = migrated_pool().await?;
+    let user_id = create_user(&pool).await?;
+    let sid = create_session(&pool, user_id, None).await?;
+    let token = session_token(user_id, sid);
+
+    assert_eq!(extract_user_id(bearer(&token), &auth_settings(SECRET), &pool).await, Ok(user_id));
+    assert!(introspect_session(&token, &auth_settings(SECRET), &pool).await.unwrap().active);
+    // Repository deletion reads the token from the raw headers
+    assert_eq!(bearer_user_id(&bearer_headers(&token), &auth_settings(SECRET), &pool).await, Ok(user_id));
+
+    sqlx::query("UPDATE user_sessions SET revoked_at = NOW() WHERE id = $1").bind(sid).execute(&pool).await?;
+
+    assert_eq!(extract_user_id(bearer(&token), &auth_settings(SECRET), &pool).await, Err(StatusCode::UNAUTHORIZED));
+    assert!(!introspect_session(&token, &auth_settings(SECRET), &pool).await.unwrap().active);
+    assert_eq!(bearer_user_id(&bearer_headers(&token), &auth_settings(SECRET), &pool).await, Err(StatusCode::UNAUTHORIZED));
+
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_recent_session_use_is_not_written_again() -> Result<()> {
+    let pool = migrated_pool().await?;
+    let user_id = create_user(&pool).await?;
+    let sid = create_session(&pool, user_id, None).await?;
+    let token = session_token(user_id, sid);
+
+    let last_used = || async {
+        sqlx::query_scalar::<_, chrono::DateTime<chrono::Utc>>("SELECT last_used_at FROM user_sessions WHERE id = $1")
+            .bind(sid)
+            .fetch_one(&pool)
+            .await
+    };
+    let created = last_used().await?;
+    extract_user_id(bearer(&token), &auth_settings(SECRET), &pool).await.unwrap();
+    assert_eq!(last_used().await?, created);
+
+    // A stale session is brought up to date
+    sqlx::query("UPDATE user_sessions SET last_used_at = NOW() - INTERVAL '1 hour' WHERE id = $1").bind(sid).execute(&pool).await?;
+    extract_user_id(bearer(&token), &auth_settings(SECRET), &pool).await.unwrap();
+    assert!(last_used().await? >= created);
+
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await?;
+    Ok(())
+}
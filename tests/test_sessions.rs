@@ -0,0 +1,29 @@
+// Tests for session ids and labels shown in the session list
+
+use aerugo::models::session::{session_label, SessionId, MAX_USER_AGENT_LEN};
+
+#[test]
+fn test_session_ids_round_trip() {
+    for id in [SessionId::Session(12), SessionId::ApiKey(5)] {
+        assert_eq!(id.to_string().parse::<SessionId>(), Ok(id));
+    }
+    assert_eq!(SessionId::Session(12).to_string(), "session-12");
+    assert_eq!(SessionId::ApiKey(5).to_string(), "api-key-5");
+}
+
+#[test]
+fn test_malformed_session_ids_rejected() {
+    for id in ["12", "session-", "session-abc", "api-key-", "token-5", ""] {
+        assert!(id.parse::<SessionId>().is_err(), "{} should not parse", id);
+    }
+}
+
+#[test]
+fn test_session_label_from_user_agent() {
+    assert_eq!(session_label(Some(" docker/24.0.7 ")), Some("docker/24.0.7".to_string()));
+    assert_eq!(session_label(Some("  ")), None);
+    assert_eq!(session_label(None), None);
+
+    let long = "a".repeat(MAX_USER_AGENT_LEN + 10);
+    assert_eq!(session_label(Some(&long)).unwrap().len(), MAX_USER_AGENT_LEN);
+}
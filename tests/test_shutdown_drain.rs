@@ -0,0 +1,57 @@
+// Tests for draining connections on shutdown
+
+use aerugo::handlers::health::readiness;
+use aerugo::shutdown::{drain, Readiness};
+use anyhow::Result;
+use axum::{routing::get, Router};
+use std::future::IntoFuture;
+use std::time::Duration;
+
+const DRAIN_DELAY: Duration = Duration::from_millis(500);
+
+#[tokio::test]
+async fn test_readiness_fails_before_server_stops() -> Result<()> {
+    let readiness_state = Readiness::default();
+    let app = Router::new()
+        .route("/health/ready", get(readiness))
+        .with_state(readiness_state.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/health/ready", listener.local_addr()?);
+    let (signal_tx, signal_rx) = tokio::sync::oneshot::channel::<()>();
+    let shutdown = drain(
+        async {
+            signal_rx.await.ok();
+        },
+        readiness_state,
+        DRAIN_DELAY,
+    );
+    let server = tokio::spawn(axum::serve(listener, app).with_graceful_shutdown(shutdown).into_future());
+
+    assert_eq!(reqwest::get(&url).await?.status(), 200);
+
+    // Readiness fails at once, while the server still answers
+    signal_tx.send(()).unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let response = reqwest::Client::new().get(&url).send().await?;
+    assert_eq!(response.status(), 503);
+    let body: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+    assert_eq!(body["status"], "draining");
+    assert!(!server.is_finished());
+
+    // Once the delay has passed the server stops accepting
+    tokio::time::timeout(DRAIN_DELAY * 4, server).await???;
+    assert!(reqwest::Client::new().get(&url).send().await.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_draining_is_shared_between_clones() {
+    let readiness = Readiness::default();
+    let clone = readiness.clone();
+    assert!(clone.is_ready());
+
+    readiness.set_draining();
+
+    assert!(!clone.is_ready());
+}
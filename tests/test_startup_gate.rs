@@ -0,0 +1,39 @@
+// Tests for answering requests while startup is still in progress
+
+use aerugo::startup::StartupGate;
+use anyhow::Result;
+use axum::{routing::get, Router};
+use std::future::IntoFuture;
+
+#[tokio::test]
+async fn test_starting_phase_returns_structured_503_until_ready() -> Result<()> {
+    let gate = StartupGate::new(&["database", "storage"]);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let base = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(axum::serve(listener, gate.router()).into_future());
+    let client = reqwest::Client::new();
+
+    // Every path is answered while starting, naming what is awaited
+    for path in ["/health", "/v2/", "/api/v1/repos/repositories"] {
+        let response = client.get(format!("{}{}", base, path)).send().await?;
+        assert_eq!(response.status(), 503);
+        let retry_after: u64 = response.headers()["retry-after"].to_str()?.parse()?;
+        assert!((4..=6).contains(&retry_after), "{}", retry_after);
+        let body: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        assert_eq!(body["error"]["code"], "STARTING");
+        assert_eq!(body["error"]["message"], "Server is starting, waiting on: database, storage");
+        assert_eq!(body["error"]["retry_after_seconds"], retry_after);
+    }
+
+    gate.dependency_ready("database");
+    let response = client.get(format!("{}/health", base)).send().await?;
+    let body: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+    assert_eq!(body["error"]["message"], "Server is starting, waiting on: storage");
+
+    // Once open, requests reach the application
+    gate.open(Router::new().route("/health", get(|| async { "ok" })));
+    let response = client.get(format!("{}/health", base)).send().await?;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await?, "ok");
+    Ok(())
+}
@@ -0,0 +1,87 @@
+// Tests for S3 endpoint, region and addressing style consistency checks
+
+#[cfg(test)]
+mod tests {
+    use aerugo::config::settings::{S3Endpoint, S3Provider, StorageConsistencyError, StorageSettings};
+    use secrecy::Secret;
+
+    fn storage_settings(endpoint: &str, region: &str, use_path_style: bool) -> StorageSettings {
+        StorageSettings {
+            endpoint: endpoint.to_string(),
+            region: region.to_string(),
+            bucket: "aerugo".to_string(),
+            access_key_id: Secret::new("access".to_string()),
+            secret_access_key: Secret::new("secret".to_string()),
+            use_path_style,
+            filesystem_path: None,
+            default_backend: "s3".to_string(),
+            routes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_valid_aws_config() {
+        let settings = storage_settings("https://s3.eu-west-1.amazonaws.com", "eu-west-1", false);
+        assert_eq!(settings.validate_consistency(), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_valid_minio_config() {
+        let settings = storage_settings("http://localhost:9000", "us-east-1", true);
+        assert_eq!(settings.validate_consistency(), Ok(Vec::new()));
+
+        let settings = storage_settings("http://minio:9000", "", true);
+        let warnings = settings.validate_consistency().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("S3_REGION"));
+    }
+
+    #[test]
+    fn test_inconsistent_configs_rejected() {
+        assert_eq!(
+            storage_settings("https://s3.eu-west-1.amazonaws.com", "", false).validate_consistency(),
+            Err(StorageConsistencyError::MissingRegion("https://s3.eu-west-1.amazonaws.com".to_string()))
+        );
+        assert_eq!(
+            storage_settings("https://s3.eu-west-1.amazonaws.com", "us-east-1", false).validate_consistency(),
+            Err(StorageConsistencyError::RegionMismatch {
+                region: "us-east-1".to_string(),
+                endpoint: "https://s3.eu-west-1.amazonaws.com".to_string(),
+                endpoint_region: "eu-west-1".to_string(),
+            })
+        );
+        assert_eq!(
+            storage_settings("http://localhost:9000", "us-east-1", false).validate_consistency(),
+            Err(StorageConsistencyError::VirtualHostUnsupported("http://localhost:9000".to_string()))
+        );
+        assert_eq!(
+            storage_settings("http://10.0.0.5:9000", "us-east-1", false).validate_consistency(),
+            Err(StorageConsistencyError::VirtualHostUnsupported("http://10.0.0.5:9000".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_path_style_on_aws_warns() {
+        let settings = storage_settings("https://s3.amazonaws.com", "us-east-1", true);
+        let warnings = settings.validate_consistency().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("path-style"));
+    }
+
+    #[test]
+    fn test_endpoint_parsing() {
+        let aws = S3Endpoint::parse("https://s3-ap-south-1.amazonaws.com").unwrap();
+        assert_eq!(aws.provider(), S3Provider::Aws);
+        assert_eq!(aws.aws_region().as_deref(), Some("ap-south-1"));
+
+        let dualstack = S3Endpoint::parse("https://s3.dualstack.us-east-2.amazonaws.com").unwrap();
+        assert_eq!(dualstack.aws_region().as_deref(), Some("us-east-2"));
+
+        let minio = S3Endpoint::parse("https://minio.example.com").unwrap();
+        assert_eq!(minio.provider(), S3Provider::Compatible);
+        assert_eq!(minio.aws_region(), None);
+        assert!(minio.supports_virtual_host());
+
+        assert!(S3Endpoint::parse("not a url").is_err());
+    }
+}
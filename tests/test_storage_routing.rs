@@ -0,0 +1,122 @@
+// Tests for routing stored content to different backends
+
+mod utils;
+
+use aerugo::storage::router::{parse_routes, ContentMatch, StorageRoute, StorageRouter, StoredContent};
+use aerugo::storage::Storage;
+use anyhow::Result;
+use bytes::Bytes;
+use std::sync::Arc;
+use utils::shared_test_storage;
+
+const MANIFEST_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+
+/// Manifests on "fast", everything else on "bulk"
+fn test_router(fast: &Arc<dyn Storage>, bulk: &Arc<dyn Storage>) -> StorageRouter {
+    StorageRouter::new(
+        vec![("fast".to_string(), fast.clone()), ("bulk".to_string(), bulk.clone())],
+        "bulk",
+        parse_routes("manifest=fast").unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_parse_routes() {
+    let routes = parse_routes(&format!("manifest=filesystem, blob:1048576=s3, {}=s3", MANIFEST_TYPE)).unwrap();
+
+    assert_eq!(
+        routes,
+        vec![
+            StorageRoute { content: ContentMatch::Manifest, min_size: None, backend: "filesystem".to_string() },
+            StorageRoute { content: ContentMatch::Blob, min_size: Some(1048576), backend: "s3".to_string() },
+            StorageRoute {
+                content: ContentMatch::MediaType(MANIFEST_TYPE.to_string()),
+                min_size: None,
+                backend: "s3".to_string(),
+            },
+        ]
+    );
+    assert!(parse_routes("").unwrap().is_empty());
+    assert!(parse_routes("manifest").is_err());
+    assert!(parse_routes("layer=s3").is_err());
+    assert!(parse_routes("blob:big=s3").is_err());
+}
+
+#[test]
+fn test_unknown_backend_rejected() {
+    let storage = shared_test_storage("unknown");
+    let backends = vec![("s3".to_string(), storage)];
+
+    assert!(StorageRouter::new(backends.clone(), "filesystem", Vec::new()).is_err());
+    assert!(StorageRouter::new(backends, "s3", parse_routes("manifest=filesystem").unwrap()).is_err());
+}
+
+#[test]
+fn test_routes_match_in_order_by_type_and_size() {
+    let fast = shared_test_storage("order-fast");
+    let bulk = shared_test_storage("order-bulk");
+    let router = StorageRouter::new(
+        vec![("fast".to_string(), fast), ("bulk".to_string(), bulk)],
+        "fast",
+        parse_routes("blob:1000=bulk,manifest=fast").unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(router.backend_name_for(&StoredContent::blob(999)), "fast");
+    assert_eq!(router.backend_name_for(&StoredContent::blob(1000)), "bulk");
+    assert_eq!(router.backend_name_for(&StoredContent::manifest(MANIFEST_TYPE, 5000)), "fast");
+}
+
+#[tokio::test]
+async fn test_manifest_and_layer_land_in_routed_backends() -> Result<()> {
+    let fast = shared_test_storage("fast");
+    let bulk = shared_test_storage("bulk");
+    let router = test_router(&fast, &bulk);
+
+    // A manifest is written straight to the backend its route selects
+    let manifest = Bytes::from_static(b"{\"schemaVersion\":2}");
+    router
+        .select(&StoredContent::manifest(MANIFEST_TYPE, manifest.len() as u64))
+        .put_blob("blobs/sha256:manifest", manifest.clone())
+        .await?;
+
+    // A layer is staged in the default backend, as chunked uploads are, and stays there
+    let layer = Bytes::from(vec![9u8; 4096]);
+    router.put_blob("blobs/sha256:layer", layer.clone()).await?;
+    router.relocate("blobs/sha256:layer", &StoredContent::blob(layer.len() as u64)).await?;
+
+    assert!(fast.blob_exists("blobs/sha256:manifest").await?);
+    assert!(!bulk.blob_exists("blobs/sha256:manifest").await?);
+    assert!(bulk.blob_exists("blobs/sha256:layer").await?);
+    assert!(!fast.blob_exists("blobs/sha256:layer").await?);
+
+    // Reads find content in whichever backend holds it
+    assert_eq!(router.get_blob("blobs/sha256:manifest").await?, Some(manifest));
+    assert_eq!(router.get_blob("blobs/sha256:layer").await?, Some(layer));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_relocate_moves_blob_out_of_default_backend() -> Result<()> {
+    let fast = shared_test_storage("move-fast");
+    let bulk = shared_test_storage("move-bulk");
+    let router = StorageRouter::new(
+        vec![("fast".to_string(), fast.clone()), ("bulk".to_string(), bulk.clone())],
+        "fast",
+        parse_routes("blob:1024=bulk").unwrap(),
+    )
+    .unwrap();
+
+    router.put_blob("blobs/sha256:big", Bytes::from(vec![1u8; 2048])).await?;
+    router.relocate("blobs/sha256:big", &StoredContent::blob(2048)).await?;
+
+    assert!(bulk.blob_exists("blobs/sha256:big").await?);
+    assert!(!fast.blob_exists("blobs/sha256:big").await?);
+    assert_eq!(bulk.get_blob("blobs/sha256:big").await?, Some(Bytes::from(vec![1u8; 2048])));
+
+    // Deleting through the router removes the blob wherever it is
+    assert!(router.delete_blob("blobs/sha256:big").await?);
+    assert!(!router.blob_exists("blobs/sha256:big").await?);
+    Ok(())
+}
@@ -0,0 +1,80 @@
+// Tests for the per-organization and per-repository storage usage breakdown
+
+#[cfg(test)]
+mod tests {
+    use aerugo::handlers::admin::{BlobReference, StorageUsageReport};
+    use aerugo::handlers::docker_registry_v2::manifest_blob_descriptors;
+
+    fn reference(organization: &str, repository: &str, digest: &str, size: u64) -> BlobReference {
+        BlobReference {
+            organization: organization.to_string(),
+            repository: repository.to_string(),
+            digest: digest.to_string(),
+            size,
+        }
+    }
+
+    #[test]
+    fn test_shared_blob_is_split_between_repositories() {
+        let report = StorageUsageReport::compute(vec![
+            // Both repositories are built on the same base layer
+            reference("acme", "api", "sha256:base", 1001),
+            reference("acme", "api", "sha256:api-config", 100),
+            reference("acme", "web", "sha256:base", 1001),
+            reference("acme", "web", "sha256:web-config", 50),
+            // A manifest listing the same layer twice does not count it twice
+            reference("acme", "web", "sha256:base", 1001),
+        ]);
+
+        assert_eq!(report.total_bytes, 1001 + 100 + 50);
+        let acme = &report.organizations[0];
+        assert_eq!(acme.organization, "acme");
+        assert_eq!(acme.attributed_bytes, report.total_bytes);
+        assert_eq!(acme.logical_bytes, 2 * 1001 + 100 + 50);
+
+        let api = &acme.repositories[0];
+        let web = &acme.repositories[1];
+        assert_eq!((api.repository.as_str(), web.repository.as_str()), ("api", "web"));
+        assert_eq!(api.blob_count, 2);
+        assert_eq!(api.logical_bytes, 1101);
+        assert_eq!(web.logical_bytes, 1051);
+        // 1001 bytes split as 501 + 500
+        assert_eq!(api.attributed_bytes, 501 + 100);
+        assert_eq!(web.attributed_bytes, 500 + 50);
+        assert_eq!(api.attributed_bytes + web.attributed_bytes, report.total_bytes);
+    }
+
+    #[test]
+    fn test_breakdown_sums_across_organizations() {
+        let report = StorageUsageReport::compute(vec![
+            reference("acme", "app", "sha256:shared", 300),
+            reference("globex", "app", "sha256:shared", 300),
+            reference("globex", "tools", "sha256:shared", 300),
+            reference("globex", "tools", "sha256:tools", 7),
+        ]);
+
+        assert_eq!(report.total_bytes, 307);
+        let attributed: Vec<u64> = report.organizations.iter().map(|o| o.attributed_bytes).collect();
+        assert_eq!(attributed, vec![100, 207]);
+        assert_eq!(attributed.iter().sum::<u64>(), report.total_bytes);
+    }
+
+    #[test]
+    fn test_manifest_descriptors_carry_sizes() {
+        let manifest = r#"{
+            "schemaVersion": 2,
+            "config": {"digest": "sha256:config", "size": 1469},
+            "layers": [{"digest": "sha256:layer", "size": 3370706}, {"digest": "sha256:nosize"}]
+        }"#;
+
+        assert_eq!(
+            manifest_blob_descriptors(manifest),
+            vec![
+                ("sha256:config".to_string(), 1469),
+                ("sha256:layer".to_string(), 3370706),
+                ("sha256:nosize".to_string(), 0),
+            ]
+        );
+        assert!(manifest_blob_descriptors("not json").is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::models::tag_expiry::{expired_tag_names, tag_matches_pattern, TagExpiryRule};
+    use chrono::{Duration, Utc};
+
+    fn rule(pattern: &str, ttl_days: i32) -> TagExpiryRule {
+        TagExpiryRule {
+            id: 1,
+            repository_id: 1,
+            pattern: pattern.to_string(),
+            ttl_days,
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_glob_patterns() {
+        assert!(tag_matches_pattern("ci-*", "ci-1234"));
+        assert!(tag_matches_pattern("ci-*", "ci-"));
+        assert!(tag_matches_pattern("*-rc?", "v1.2-rc1"));
+        assert!(tag_matches_pattern("pr-*-build-*", "pr-12-build-7"));
+        assert!(tag_matches_pattern("latest", "latest"));
+
+        assert!(!tag_matches_pattern("ci-*", "release-1"));
+        assert!(!tag_matches_pattern("ci-*", "xci-1"));
+        assert!(!tag_matches_pattern("*-rc?", "v1.2-rc10"));
+        assert!(!tag_matches_pattern("latest", "latest-1"));
+    }
+
+    #[test]
+    fn test_expired_matching_tag_is_removed() {
+        let now = Utc::now();
+        let rules = vec![rule("ci-*", 7)];
+        let tags = vec![
+            ("ci-old".to_string(), now - Duration::days(8)),
+            ("ci-fresh".to_string(), now - Duration::days(1)),
+            ("release-old".to_string(), now - Duration::days(30)),
+        ];
+
+        // Only the old CI tag expires; the fresh one and the non-matching one survive
+        assert_eq!(expired_tag_names(&rules, &tags, now), vec!["ci-old".to_string()]);
+    }
+
+    #[test]
+    fn test_any_rule_can_expire_a_tag() {
+        let now = Utc::now();
+        let rules = vec![rule("ci-*", 30), rule("ci-tmp-*", 1)];
+        let tags = vec![
+            ("ci-tmp-1".to_string(), now - Duration::days(2)),
+            ("ci-main".to_string(), now - Duration::days(2)),
+        ];
+
+        assert_eq!(expired_tag_names(&rules, &tags, now), vec!["ci-tmp-1".to_string()]);
+    }
+
+    #[test]
+    fn test_no_rules_expire_nothing() {
+        let now = Utc::now();
+        let tags = vec![("ci-old".to_string(), now - Duration::days(365))];
+
+        assert!(expired_tag_names(&[], &tags, now).is_empty());
+    }
+}
@@ -0,0 +1,56 @@
+// Tests for streaming a repository's tags as newline-delimited JSON
+
+use aerugo::handlers::docker_registry_v2::{ndjson_tag_response, NDJSON_MEDIA_TYPE};
+use axum::http::{header, StatusCode};
+
+fn tags(tags: &[&str]) -> Vec<Result<String, std::io::Error>> {
+    tags.iter().map(|tag| Ok(tag.to_string())).collect()
+}
+
+#[tokio::test]
+async fn test_each_tag_on_its_own_line() {
+    let response = ndjson_tag_response(futures::stream::iter(tags(&["1.0", "1.1", "latest"])));
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], NDJSON_MEDIA_TYPE);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.ends_with('\n'));
+    let lines: Vec<serde_json::Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(
+        lines,
+        vec![
+            serde_json::json!({ "tag": "1.0" }),
+            serde_json::json!({ "tag": "1.1" }),
+            serde_json::json!({ "tag": "latest" }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_tags_are_written_as_they_arrive() {
+    let (sender, receiver) = tokio::sync::mpsc::channel(1);
+    let response = ndjson_tag_response(tokio_stream::wrappers::ReceiverStream::new(receiver));
+    let mut body = response.into_body().into_data_stream();
+
+    // The first line is readable before the second tag exists
+    sender.send(Ok::<_, std::io::Error>("1.0".to_string())).await.unwrap();
+    let first = futures::StreamExt::next(&mut body).await.unwrap().unwrap();
+    assert_eq!(&first[..], b"{\"tag\":\"1.0\"}\n");
+
+    sender.send(Ok("1.1".to_string())).await.unwrap();
+    drop(sender);
+    let second = futures::StreamExt::next(&mut body).await.unwrap().unwrap();
+    assert_eq!(&second[..], b"{\"tag\":\"1.1\"}\n");
+    assert!(futures::StreamExt::next(&mut body).await.is_none());
+}
+
+#[tokio::test]
+async fn test_error_ends_the_stream() {
+    let mut items = tags(&["1.0"]);
+    items.push(Err(std::io::Error::new(std::io::ErrorKind::Other, "connection reset")));
+    let response = ndjson_tag_response(futures::stream::iter(items));
+
+    assert!(axum::body::to_bytes(response.into_body(), usize::MAX).await.is_err());
+}
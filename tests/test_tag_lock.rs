@@ -0,0 +1,128 @@
+// Tests for the lock held around tag updates
+#[cfg(test)]
+mod tests {
+    use aerugo::tag_lock::{tag_lock_key, with_lock, with_locks, LocalLocks, LockBackend, TagLockError};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    const TTL: Duration = Duration::from_secs(5);
+    const WAIT: Duration = Duration::from_secs(5);
+
+    #[tokio::test]
+    async fn concurrent_updates_to_one_tag_run_one_after_the_other() {
+        let locks = Arc::new(LocalLocks::default());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let key = tag_lock_key("team/app", "latest");
+
+        let first = {
+            let (locks, events, key) = (locks.clone(), events.clone(), key.clone());
+            tokio::spawn(async move {
+                with_lock(locks.as_ref(), &key, TTL, WAIT, async {
+                    events.lock().unwrap().push("first start");
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    events.lock().unwrap().push("first end");
+                })
+                .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let second = {
+            let (locks, events, key) = (locks.clone(), events.clone(), key.clone());
+            tokio::spawn(async move {
+                with_lock(locks.as_ref(), &key, TTL, WAIT, async {
+                    events.lock().unwrap().push("second start");
+                    events.lock().unwrap().push("second end");
+                })
+                .await
+            })
+        };
+
+        first.await.unwrap().unwrap();
+        second.await.unwrap().unwrap();
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["first start", "first end", "second start", "second end"]
+        );
+    }
+
+    #[tokio::test]
+    async fn different_tags_do_not_wait_on_each_other() {
+        let locks = LocalLocks::default();
+        assert!(locks.try_acquire(&tag_lock_key("team/app", "v1"), "a", TTL).await.unwrap());
+        assert!(locks.try_acquire(&tag_lock_key("team/app", "v2"), "b", TTL).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn waiting_past_the_deadline_times_out() {
+        let locks = LocalLocks::default();
+        let key = tag_lock_key("team/app", "latest");
+        assert!(locks.try_acquire(&key, "holder", TTL).await.unwrap());
+
+        let result = with_lock(&locks, &key, TTL, Duration::from_millis(100), async {}).await;
+        assert!(matches!(result, Err(TagLockError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn several_tags_are_held_together_for_one_update() {
+        let locks = LocalLocks::default();
+        let (v1, v2) = (tag_lock_key("team/app", "v1"), tag_lock_key("team/app", "v2"));
+        let keys = vec![v2.clone(), v1.clone(), v2.clone()];
+
+        let held = with_locks(&locks, &keys, TTL, WAIT, async {
+            !locks.try_acquire(&v1, "push", TTL).await.unwrap() && !locks.try_acquire(&v2, "push", TTL).await.unwrap()
+        })
+        .await
+        .unwrap();
+        assert!(held);
+        assert!(locks.try_acquire(&v1, "push", TTL).await.unwrap());
+        assert!(locks.try_acquire(&v2, "push", TTL).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn one_held_tag_times_out_the_whole_update() {
+        let locks = LocalLocks::default();
+        let (v1, v2) = (tag_lock_key("team/app", "v1"), tag_lock_key("team/app", "v2"));
+        assert!(locks.try_acquire(&v2, "push", TTL).await.unwrap());
+
+        let result = with_locks(&locks, &[v1.clone(), v2], TTL, Duration::from_millis(100), async {}).await;
+        assert!(matches!(result, Err(TagLockError::Timeout(_))));
+        // The lock taken before the timeout is given back
+        assert!(locks.try_acquire(&v1, "push", TTL).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn expired_lock_is_taken_over_and_old_holder_cannot_release_it() {
+        let locks = LocalLocks::default();
+        let key = tag_lock_key("team/app", "latest");
+        let ttl = Duration::from_millis(50);
+
+        assert!(locks.try_acquire(&key, "crashed", ttl).await.unwrap());
+        assert!(!locks.try_acquire(&key, "next", TTL).await.unwrap());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(locks.try_acquire(&key, "next", TTL).await.unwrap());
+        assert!(!locks.release(&key, "crashed").await.unwrap());
+        assert!(locks.release(&key, "next").await.unwrap());
+    }
+
+    /// A lock store that is down, as Redis is during an outage
+    struct Unreachable;
+
+    #[async_trait::async_trait]
+    impl LockBackend for Unreachable {
+        async fn try_acquire(&self, _key: &str, _token: &str, _ttl: Duration) -> anyhow::Result<bool> {
+            anyhow::bail!("connection refused")
+        }
+
+        async fn release(&self, _key: &str, _token: &str) -> anyhow::Result<bool> {
+            anyhow::bail!("connection refused")
+        }
+    }
+
+    #[tokio::test]
+    async fn update_runs_unlocked_when_the_backend_is_down() {
+        let key = tag_lock_key("team/app", "latest");
+        let result = with_lock(&Unreachable, &key, TTL, WAIT, async { "stored" }).await;
+        assert_eq!(result.unwrap(), "stored");
+    }
+}
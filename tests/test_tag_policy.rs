@@ -0,0 +1,26 @@
+// Tests for the semantic-version tag policy
+
+use aerugo::models::tag_policy::{is_semver_tag, tag_allowed};
+
+#[test]
+fn test_semver_tags() {
+    for tag in ["1.2.3", "v1.2.3", "0.0.0", "10.20.30", "1.0.0-rc.1", "1.0.0-alpha-beta", "1.0.0-0.3.7", "1.0.0+build.5"] {
+        assert!(is_semver_tag(tag), "{} should be a semantic version", tag);
+    }
+}
+
+#[test]
+fn test_non_semver_tags() {
+    for tag in ["latest", "main", "1.2", "1.2.3.4", "01.2.3", "1.2.3-", "1.2.3-01", "1.2.3-rc..1", "v", "1.2.x", "sha-abc123", ""] {
+        assert!(!is_semver_tag(tag), "{} should not be a semantic version", tag);
+    }
+}
+
+#[test]
+fn test_policy_accepts_semver_and_latest_only_when_required() {
+    assert!(tag_allowed(true, "v2.1.0"));
+    assert!(tag_allowed(true, "latest"));
+    assert!(!tag_allowed(true, "nightly"));
+
+    assert!(tag_allowed(false, "nightly"));
+}
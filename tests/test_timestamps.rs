@@ -0,0 +1,92 @@
+// Tests for the timestamp format shared by all API responses
+#[cfg(test)]
+mod tests {
+    use aerugo::models::organizations::{Organization, OrganizationMember};
+    use aerugo::models::user::UserResponse;
+    use aerugo::utils::timestamp;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    /// 2025-09-24 10:15:30.123456789 UTC
+    fn created() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 9, 24, 10, 15, 30).unwrap() + chrono::Duration::nanoseconds(123_456_789)
+    }
+
+    fn updated() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 9, 25, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_format_is_rfc3339_utc_with_milliseconds() {
+        assert_eq!(timestamp::format(&created()), "2025-09-24T10:15:30.123Z");
+        // Whole seconds still carry the fraction, so every value has one shape
+        assert_eq!(timestamp::format(&updated()), "2025-09-25T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_parse_accepts_any_offset() {
+        let parsed = timestamp::parse("2025-09-24T12:15:30.123+02:00").unwrap();
+        assert_eq!(timestamp::format(&parsed), "2025-09-24T10:15:30.123Z");
+        assert!(timestamp::parse("2025-09-24 10:15:30").is_err());
+    }
+
+    #[test]
+    fn test_organization_timestamps() {
+        let organization = Organization {
+            id: 1,
+            name: "acme".to_string(),
+            display_name: "Acme".to_string(),
+            description: None,
+            website_url: None,
+            avatar_url: None,
+            created_at: created(),
+            updated_at: updated(),
+            block_vulnerable_pulls: None,
+            default_repo_public: None,
+        };
+
+        let json = serde_json::to_value(&organization).unwrap();
+        assert_eq!(json["created_at"], "2025-09-24T10:15:30.123Z");
+        assert_eq!(json["updated_at"], "2025-09-25T00:00:00.000Z");
+
+        let parsed: Organization = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.updated_at, updated());
+    }
+
+    #[test]
+    fn test_user_timestamps() {
+        let user = UserResponse {
+            id: 7,
+            username: "ada".to_string(),
+            email: "ada@example.com".to_string(),
+            display_name: None,
+            bio: None,
+            avatar_url: None,
+            created_at: created(),
+        };
+
+        let json = serde_json::to_value(&user).unwrap();
+        assert_eq!(json["created_at"], "2025-09-24T10:15:30.123Z");
+    }
+
+    #[test]
+    fn test_optional_member_timestamps() {
+        let member: OrganizationMember = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "organization_id": 1,
+            "user_id": 7,
+            "role": "member",
+            "joined_at": "2025-09-24T10:15:30.123Z",
+            "expires_at": "2025-10-01T00:00:00+00:00",
+            "version": 1,
+            "username": "ada",
+            "email": "ada@example.com"
+        }))
+        .unwrap();
+        assert_eq!(member.invited_at, None);
+
+        let json = serde_json::to_value(&member).unwrap();
+        assert_eq!(json["joined_at"], "2025-09-24T10:15:30.123Z");
+        assert_eq!(json["expires_at"], "2025-10-01T00:00:00.000Z");
+        assert!(json["invited_at"].is_null());
+    }
+}
@@ -0,0 +1,72 @@
+// Tests for unique repository display names; needs a live Postgres (DATABASE_URL)
+
+mod utils;
+
+use aerugo::handlers::repositories::{display_name_taken, duplicate_display_names, UNIQUE_DISPLAY_NAME_INDEX};
+use aerugo::tenant::TenancyMode;
+use anyhow::Result;
+use sqlx::PgPool;
+use utils::migrated_pool;
+
+async fn create_organization(pool: &PgPool) -> Result<i64> {
+    let name = format!("display-names-{}", uuid::Uuid::new_v4().simple());
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO organizations (name, display_name) VALUES ($1, $1) RETURNING id"
+    )
+    .bind(&name)
+    .fetch_one(pool)
+    .await?;
+    Ok(id)
+}
+
+async fn create_repository(pool: &PgPool, org_id: i64, name: &str, display_name: &str, claimed: bool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO repositories (organization_id, name, display_name, display_name_claimed) VALUES ($1, $2, $3, $4)"
+    )
+    .bind(org_id)
+    .bind(name)
+    .bind(display_name)
+    .bind(claimed)
+    .execute(pool)
+    .await
+    .map(|_| ())
+}
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_taken_name_ignores_case_and_whitespace() -> Result<()> {
+    let pool = migrated_pool().await?;
+    let org_id = create_organization(&pool).await?;
+    create_repository(&pool, org_id, "web", "Web Frontend", false).await?;
+
+    assert!(display_name_taken(&pool, org_id, "Web Frontend").await?);
+    assert!(display_name_taken(&pool, org_id, "  web frontend ").await?);
+    assert!(!display_name_taken(&pool, org_id, "Web Backend").await?);
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1").bind(org_id).execute(&pool).await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_claimed_names_collide_and_unclaimed_are_allowed() -> Result<()> {
+    let pool = migrated_pool().await?;
+    let org_id = create_organization(&pool).await?;
+
+    // Without enforcement duplicates are allowed
+    create_repository(&pool, org_id, "billing", "Billing API", false).await?;
+    create_repository(&pool, org_id, "billing-v2", "billing api", false).await?;
+
+    // With enforcement the index rejects a second claim of the same name
+    create_repository(&pool, org_id, "web", "Web Frontend", true).await?;
+    let error = create_repository(&pool, org_id, "web-2", " WEB FRONTEND", true).await.unwrap_err();
+    let constraint = error.as_database_error().and_then(|e| e.constraint().map(str::to_string));
+    assert_eq!(constraint.as_deref(), Some(UNIQUE_DISPLAY_NAME_INDEX));
+
+    let duplicates = duplicate_display_names(&pool, TenancyMode::Shared).await?;
+    assert!(duplicates.contains(&(org_id, "billing api".to_string(), 2)), "{:?}", duplicates);
+    assert!(!duplicates.iter().any(|(id, name, _)| *id == org_id && name == "web frontend"));
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1").bind(org_id).execute(&pool).await?;
+    Ok(())
+}
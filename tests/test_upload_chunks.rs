@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use aerugo::handlers::docker_registry_v2::{validate_upload_chunk, ChunkValidationError};
+    use aerugo::utils::content_range::ContentRangeError;
+    use axum::http::StatusCode;
+
+    const MIN: u64 = 1024;
+    const MAX: u64 = 10 * 1024;
+
+    #[test]
+    fn test_contiguous_chunk_accepted() {
+        assert_eq!(validate_upload_chunk(0, Some("0-2047"), 2048, MIN, MAX), Ok(()));
+        assert_eq!(validate_upload_chunk(2048, Some("2048-4095"), 2048, MIN, MAX), Ok(()));
+    }
+
+    #[test]
+    fn test_out_of_order_chunk_rejected() {
+        let err = validate_upload_chunk(2048, Some("4096-6143"), 2048, MIN, MAX).unwrap_err();
+        assert_eq!(err, ChunkValidationError::NonContiguous { expected_offset: 2048 });
+        assert_eq!(err.status_code(), StatusCode::RANGE_NOT_SATISFIABLE);
+    }
+
+    #[test]
+    fn test_undersized_non_final_chunk_rejected() {
+        let err = validate_upload_chunk(0, Some("0-99"), 100, MIN, MAX).unwrap_err();
+        assert_eq!(err, ChunkValidationError::ChunkTooSmall { min: MIN });
+        assert_eq!(err.status_code(), StatusCode::RANGE_NOT_SATISFIABLE);
+    }
+
+    #[test]
+    fn test_oversized_chunk_rejected() {
+        let err = validate_upload_chunk(0, None, MAX + 1, MIN, MAX).unwrap_err();
+        assert_eq!(err, ChunkValidationError::ChunkTooLarge { max: MAX });
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_streamed_upload_without_range_skips_minimum() {
+        assert_eq!(validate_upload_chunk(0, None, 10, MIN, MAX), Ok(()));
+    }
+
+    #[test]
+    fn test_contiguous_sequence_advances_offset() {
+        let mut offset = 0;
+        for (range, len) in [("0-1023", 1024), ("bytes=1024-3071", 2048), ("3072-4095", 1024)] {
+            assert_eq!(validate_upload_chunk(offset, Some(range), len, MIN, MAX), Ok(()));
+            offset += len;
+        }
+        assert_eq!(offset, 4096);
+    }
+
+    #[test]
+    fn test_gapped_and_overlapping_ranges_rejected() {
+        // Starts past the offset, leaving a gap
+        let err = validate_upload_chunk(2048, Some("3072-4095"), 1024, MIN, MAX).unwrap_err();
+        assert_eq!(err, ChunkValidationError::NonContiguous { expected_offset: 2048 });
+
+        // Resends bytes already received
+        let err = validate_upload_chunk(2048, Some("1024-3071"), 2048, MIN, MAX).unwrap_err();
+        assert_eq!(err, ChunkValidationError::NonContiguous { expected_offset: 2048 });
+        assert_eq!(err.status_code(), StatusCode::RANGE_NOT_SATISFIABLE);
+    }
+
+    #[test]
+    fn test_range_not_matching_body_rejected() {
+        let err = validate_upload_chunk(0, Some("0-2047"), 1024, MIN, MAX).unwrap_err();
+        assert_eq!(err, ChunkValidationError::InvalidRange);
+    }
+
+    #[test]
+    fn test_malformed_range_is_bad_request() {
+        let err = validate_upload_chunk(0, Some("1024-0"), 1024, MIN, MAX).unwrap_err();
+        assert_eq!(err, ChunkValidationError::MalformedRange(ContentRangeError::Reversed { start: 1024, end: 0 }));
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+
+        let err = validate_upload_chunk(0, Some(""), 1024, MIN, MAX).unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+    }
+}
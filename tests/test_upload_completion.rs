@@ -0,0 +1,123 @@
+// Tests for completing chunked uploads while a PATCH for the same upload is
+// in flight; they need a live Postgres (DATABASE_URL)
+
+mod utils;
+
+use aerugo::database::queries::create_blob_upload;
+use aerugo::handlers::docker_registry_v2::{append_upload_chunk, finish_blob_upload, UploadChunk};
+use aerugo::models::digest::Digest;
+use aerugo::storage::router::StorageRouter;
+use aerugo::storage::Storage;
+use axum::http::StatusCode;
+use bytes::Bytes;
+use utils::{migrated_pool, shared_test_storage};
+
+const MIN: u64 = 1024;
+const MAX: u64 = 10 * 1024;
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_concurrent_patch_and_final_put_do_not_interleave() -> anyhow::Result<()> {
+    let pool = migrated_pool().await?;
+    let org = format!("upload-race-{}", uuid::Uuid::new_v4().simple());
+    let org_id = sqlx::query_scalar::<_, i64>("INSERT INTO organizations (name, display_name) VALUES ($1, $1) RETURNING id")
+        .bind(&org)
+        .fetch_one(&pool)
+        .await?;
+    let repository_id = sqlx::query_scalar::<_, i64>("INSERT INTO repositories (organization_id, name) VALUES ($1, 'app') RETURNING id")
+        .bind(org_id)
+        .fetch_one(&pool)
+        .await?;
+    let name = format!("{}/app", org);
+    let router = StorageRouter::new(vec![("filesystem".to_string(), shared_test_storage("upload-race"))], "filesystem", Vec::new())?;
+
+    for _ in 0..10 {
+        let uuid = uuid::Uuid::new_v4().to_string();
+        create_blob_upload(&pool, &uuid, repository_id, None).await?;
+
+        // Both claim the start of the upload
+        let patch_body = Bytes::from(vec![1u8; 2048]);
+        let put_body = Bytes::from(vec![2u8; 100]);
+        let put_digest = Digest::sha256(&put_body);
+        let (patched, put) = tokio::join!(
+            append_upload_chunk(
+                &pool,
+                &router,
+                &name,
+                &uuid,
+                UploadChunk { content_range: Some("0-2047"), body: patch_body.clone() },
+                MIN,
+                MAX,
+            ),
+            finish_blob_upload(
+                &pool,
+                &router,
+                &name,
+                &uuid,
+                &put_digest,
+                UploadChunk { content_range: Some("0-99"), body: put_body.clone() },
+                MAX,
+            ),
+        );
+
+        match (patched.status(), put.status()) {
+            // The completion went first; the PATCH found the upload finished
+            (StatusCode::NOT_FOUND, StatusCode::CREATED) => {
+                assert_eq!(router.get_blob(&put_digest.blob_key()).await?, Some(put_body));
+            }
+            // The PATCH went first; the final chunk no longer starts at the offset
+            (StatusCode::ACCEPTED, StatusCode::RANGE_NOT_SATISFIABLE) => {
+                let mut blob = patch_body.to_vec();
+                blob.extend_from_slice(&put_body);
+                let digest = Digest::sha256(&blob);
+                let completed = finish_blob_upload(
+                    &pool,
+                    &router,
+                    &name,
+                    &uuid,
+                    &digest,
+                    UploadChunk { content_range: Some("2048-2147"), body: put_body },
+                    MAX,
+                )
+                .await;
+                assert_eq!(completed.status(), StatusCode::CREATED);
+                assert_eq!(router.get_blob(&digest.blob_key()).await?, Some(Bytes::from(blob)));
+            }
+            statuses => panic!("PATCH and PUT interleaved: {:?}", statuses),
+        }
+    }
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1").bind(org_id).execute(&pool).await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs a live Postgres (DATABASE_URL)"]
+async fn test_final_chunk_range_is_validated() -> anyhow::Result<()> {
+    let pool = migrated_pool().await?;
+    let org = format!("upload-final-{}", uuid::Uuid::new_v4().simple());
+    let org_id = sqlx::query_scalar::<_, i64>("INSERT INTO organizations (name, display_name) VALUES ($1, $1) RETURNING id")
+        .bind(&org)
+        .fetch_one(&pool)
+        .await?;
+    let repository_id = sqlx::query_scalar::<_, i64>("INSERT INTO repositories (organization_id, name) VALUES ($1, 'app') RETURNING id")
+        .bind(org_id)
+        .fetch_one(&pool)
+        .await?;
+    let name = format!("{}/app", org);
+    let router = StorageRouter::new(vec![("filesystem".to_string(), shared_test_storage("upload-final"))], "filesystem", Vec::new())?;
+    let uuid = uuid::Uuid::new_v4().to_string();
+    create_blob_upload(&pool, &uuid, repository_id, None).await?;
+
+    let body = Bytes::from(vec![3u8; 100]);
+    let digest = Digest::sha256(&body);
+    let finish = |content_range| finish_blob_upload(&pool, &router, &name, &uuid, &digest, UploadChunk { content_range, body: body.clone() }, MAX);
+
+    // A range that does not match the body is refused, and the upload stays open
+    assert_eq!(finish(Some("0-49")).await.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    // The final chunk may be smaller than the minimum
+    assert_eq!(finish(Some("0-99")).await.status(), StatusCode::CREATED);
+
+    sqlx::query("DELETE FROM organizations WHERE id = $1").bind(org_id).execute(&pool).await?;
+    Ok(())
+}
@@ -0,0 +1,82 @@
+// Tests for resuming chunked blob uploads from their persisted session
+
+use aerugo::storage::filesystem::FilesystemStorage;
+use aerugo::storage::upload_session::UploadSession;
+use aerugo::storage::Storage;
+use anyhow::Result;
+use bytes::Bytes;
+use std::path::PathBuf;
+
+const PART_SIZE: u64 = 1024;
+
+fn test_root(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("aerugo-upload-resume-{}-{}", name, uuid::Uuid::new_v4()))
+}
+
+#[tokio::test]
+async fn test_upload_resumes_after_restart() -> Result<()> {
+    let root = test_root("restart");
+    let blob: Vec<u8> = (0..5000u32).map(|i| (i % 251) as u8).collect();
+
+    // First instance receives the first two chunks, the second of which
+    // fills a part and starts the multipart upload
+    let persisted = {
+        let storage = FilesystemStorage::new(root.clone());
+        let mut session = UploadSession::new("upload-1", "library/app");
+        session.append(&storage, Bytes::copy_from_slice(&blob[..600]), PART_SIZE).await?;
+        assert_eq!(session.offset(), 600);
+        assert!(session.multipart_upload_id.is_none());
+
+        session.append(&storage, Bytes::copy_from_slice(&blob[600..1800]), PART_SIZE).await?;
+        assert_eq!(session.offset(), 1800);
+        assert!(session.multipart_upload_id.is_some());
+
+        session.append(&storage, Bytes::copy_from_slice(&blob[1800..2000]), PART_SIZE).await?;
+        serde_json::to_string(&session)?
+    };
+
+    // After a restart a new instance picks up the session where it stopped
+    let storage = FilesystemStorage::new(root);
+    let mut session: UploadSession = serde_json::from_str(&persisted)?;
+    assert_eq!(session.offset(), 2000);
+
+    session.append(&storage, Bytes::copy_from_slice(&blob[2000..4500]), PART_SIZE).await?;
+    session.append(&storage, Bytes::copy_from_slice(&blob[4500..]), PART_SIZE).await?;
+
+    let size = session.finish(&storage, "blobs/sha256:resumed").await?;
+
+    assert_eq!(size, blob.len() as u64);
+    assert_eq!(storage.get_blob("blobs/sha256:resumed").await?, Some(Bytes::from(blob)));
+    assert!(!storage.blob_exists(&session.pending_key()).await?);
+    assert!(!storage.blob_exists(&session.multipart_key()).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_small_upload_skips_multipart() -> Result<()> {
+    let storage = FilesystemStorage::new(test_root("small"));
+    let mut session = UploadSession::new("upload-2", "library/app");
+
+    session.append(&storage, Bytes::from_static(b"hello "), PART_SIZE).await?;
+    session.append(&storage, Bytes::from_static(b"world"), PART_SIZE).await?;
+    let size = session.finish(&storage, "blobs/sha256:small").await?;
+
+    assert_eq!(size, 11);
+    assert!(session.multipart_upload_id.is_none());
+    assert_eq!(storage.get_blob("blobs/sha256:small").await?, Some(Bytes::from_static(b"hello world")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aborted_upload_leaves_nothing_behind() -> Result<()> {
+    let storage = FilesystemStorage::new(test_root("abort"));
+    let mut session = UploadSession::new("upload-3", "library/app");
+
+    session.append(&storage, Bytes::from(vec![1u8; 1500]), PART_SIZE).await?;
+    session.append(&storage, Bytes::from(vec![2u8; 100]), PART_SIZE).await?;
+    session.abort(&storage).await?;
+
+    assert!(!storage.blob_exists(&session.pending_key()).await?);
+    assert!(!storage.blob_exists(&session.multipart_key()).await?);
+    Ok(())
+}
@@ -0,0 +1,92 @@
+// Tests for self-service profile updates and avatar uploads
+#[cfg(test)]
+mod tests {
+    use aerugo::models::user::UpdateProfileRequest;
+    use aerugo::utils::avatar::{
+        sniff_image_type, user_avatar_key, user_avatar_url, validate_avatar, AvatarError, MAX_AVATAR_BYTES,
+    };
+    use validator::Validate;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const JPEG: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F'];
+    const WEBP: &[u8] = b"RIFF\x24\0\0\0WEBPVP8 ";
+
+    fn profile(display_name: Option<&str>, bio: Option<&str>) -> UpdateProfileRequest {
+        UpdateProfileRequest {
+            display_name: display_name.map(str::to_string),
+            bio: bio.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_profile_update_validation() {
+        assert!(profile(Some("Ada Lovelace"), Some("Writes compilers")).validate().is_ok());
+        // Leaving every field out is a valid no-op update
+        assert!(profile(None, None).validate().is_ok());
+
+        assert!(profile(Some(""), None).validate().is_err());
+        assert!(profile(Some(&"a".repeat(101)), None).validate().is_err());
+        assert!(profile(None, Some(&"a".repeat(501))).validate().is_err());
+    }
+
+    #[test]
+    fn test_profile_update_fields_are_optional_in_json() {
+        let request: UpdateProfileRequest = serde_json::from_str(r#"{"bio": "Hi"}"#).unwrap();
+        assert_eq!(request.display_name, None);
+        assert_eq!(request.bio.as_deref(), Some("Hi"));
+    }
+
+    #[test]
+    fn test_image_types_sniffed_from_content() {
+        assert_eq!(sniff_image_type(PNG), Some("image/png"));
+        assert_eq!(sniff_image_type(JPEG), Some("image/jpeg"));
+        assert_eq!(sniff_image_type(b"GIF89a\x01\0"), Some("image/gif"));
+        assert_eq!(sniff_image_type(WEBP), Some("image/webp"));
+        assert_eq!(sniff_image_type(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), None);
+    }
+
+    #[test]
+    fn test_avatar_upload_accepted() {
+        assert_eq!(validate_avatar(Some("image/png"), PNG), Ok("image/png"));
+        assert_eq!(validate_avatar(Some("IMAGE/JPEG; charset=binary"), JPEG), Ok("image/jpeg"));
+        assert_eq!(validate_avatar(Some("image/webp"), WEBP), Ok("image/webp"));
+    }
+
+    #[test]
+    fn test_avatar_upload_rejected() {
+        assert_eq!(validate_avatar(Some("image/png"), b""), Err(AvatarError::Empty));
+        assert_eq!(
+            validate_avatar(Some("image/svg+xml"), b"<svg/>"),
+            Err(AvatarError::UnsupportedType("image/svg+xml".to_string()))
+        );
+        assert_eq!(
+            validate_avatar(None, PNG),
+            Err(AvatarError::UnsupportedType("no Content-Type".to_string()))
+        );
+        // An HTML page labelled as a PNG is refused
+        assert_eq!(
+            validate_avatar(Some("image/png"), b"<html><script></script></html>"),
+            Err(AvatarError::ContentMismatch("image/png"))
+        );
+        assert_eq!(validate_avatar(Some("image/jpeg"), PNG), Err(AvatarError::ContentMismatch("image/jpeg")));
+    }
+
+    #[test]
+    fn test_avatar_size_limit() {
+        let mut large = PNG.to_vec();
+        large.resize(MAX_AVATAR_BYTES, 0);
+        assert_eq!(validate_avatar(Some("image/png"), &large), Ok("image/png"));
+
+        large.push(0);
+        assert_eq!(
+            validate_avatar(Some("image/png"), &large),
+            Err(AvatarError::TooLarge { size: MAX_AVATAR_BYTES + 1, max: MAX_AVATAR_BYTES })
+        );
+    }
+
+    #[test]
+    fn test_avatar_locations() {
+        assert_eq!(user_avatar_key(42), "avatars/users/42");
+        assert_eq!(user_avatar_url(42), "/api/v1/auth/users/42/avatar");
+    }
+}
@@ -0,0 +1,60 @@
+// Tests for username format and reserved name checks at registration
+
+use aerugo::models::user::{normalize_username, validate_username, DEFAULT_RESERVED_USERNAMES};
+
+fn reserved() -> Vec<String> {
+    DEFAULT_RESERVED_USERNAMES.iter().map(|name| name.to_string()).collect()
+}
+
+fn error_code(username: &str) -> Option<String> {
+    validate_username(&normalize_username(username), &reserved())
+        .err()
+        .map(|error| error.code.to_string())
+}
+
+#[test]
+fn test_valid_usernames() {
+    for username in ["ada", "grace-hopper", "build_bot_2", "x9z"] {
+        assert_eq!(error_code(username), None, "{} should be valid", username);
+    }
+    assert_eq!(error_code(&"a".repeat(39)), None);
+}
+
+#[test]
+fn test_reserved_usernames() {
+    for username in ["admin", "root", "support", "Admin", " ROOT "] {
+        assert_eq!(error_code(username).as_deref(), Some("reserved"), "{} should be reserved", username);
+    }
+    // The list is configurable; an empty one reserves nothing
+    assert!(validate_username("admin", &[]).is_ok());
+    assert!(validate_username("ops", &["ops".to_string()]).is_err());
+}
+
+#[test]
+fn test_invalid_characters() {
+    for username in ["ada lovelace", "ada.lovelace", "ada@example", "-ada", "ada_", "ádá", "ada/ops"] {
+        assert_eq!(
+            error_code(username).as_deref(),
+            Some("invalid_characters"),
+            "{} should be rejected",
+            username
+        );
+    }
+}
+
+#[test]
+fn test_length_limits() {
+    assert_eq!(error_code("ab").as_deref(), Some("length"));
+    assert_eq!(error_code("").as_deref(), Some("length"));
+    assert_eq!(error_code(&"a".repeat(40)).as_deref(), Some("length"));
+
+    let error = validate_username("ab", &reserved()).unwrap_err();
+    assert!(error.message.unwrap().contains("3-39"));
+}
+
+#[test]
+fn test_usernames_normalize_for_uniqueness() {
+    assert_eq!(normalize_username(" Ada "), "ada");
+    assert_eq!(normalize_username("ADA"), normalize_username("ada"));
+    assert_ne!(normalize_username("ada1"), normalize_username("ada2"));
+}